@@ -14,9 +14,13 @@ use axum::{
     },
     response::IntoResponse,
 };
+use chrono::{DateTime, Utc};
 use headers::authorization::{Basic, Bearer, Credentials as _};
 use http::{Request, StatusCode};
-use mas_data_model::{Client, JwksOrJwksUri};
+use mas_data_model::{
+    Client, ClientSecretHashError, ClientSecretValue, JwksOrJwksUri, client_secret_matches,
+    client_secret_matches_hash,
+};
 use mas_http::RequestBuilderExt;
 use mas_iana::oauth::OAuthClientAuthenticationMethod;
 use mas_jose::{jwk::PublicJsonWebKeySet, jwt::Jwt};
@@ -117,6 +121,7 @@ impl Credentials {
         &self,
         http_client: &reqwest::Client,
         encrypter: &Encrypter,
+        now: DateTime<Utc>,
         method: &OAuthClientAuthenticationMethod,
         client: &Client,
     ) -> Result<(), CredentialsVerificationError> {
@@ -131,18 +136,42 @@ impl Credentials {
                 Credentials::ClientSecretBasic { client_secret, .. },
                 OAuthClientAuthenticationMethod::ClientSecretBasic,
             ) => {
-                // Decrypt the client_secret
-                let encrypted_client_secret = client
-                    .encrypted_client_secret
-                    .as_ref()
-                    .ok_or(CredentialsVerificationError::InvalidClientConfig)?;
+                // During a rotation, the client may have two active secrets
+                let mut secrets = client.active_secrets(now).peekable();
+                if secrets.peek().is_none() {
+                    return Err(CredentialsVerificationError::InvalidClientConfig);
+                }
 
-                let decrypted_client_secret = encrypter
-                    .decrypt_string(encrypted_client_secret)
-                    .map_err(|_e| CredentialsVerificationError::DecryptionError)?;
+                let mut matched = false;
+                for secret in secrets {
+                    matched = match &secret.value {
+                        ClientSecretValue::Encrypted { encrypted } => {
+                            let decrypted_client_secret = encrypter
+                                .decrypt_string(encrypted)
+                                .map_err(|_e| CredentialsVerificationError::DecryptionError)?;
+
+                            client_secret_matches(
+                                &client.client_id,
+                                client_secret,
+                                &decrypted_client_secret,
+                            )
+                        }
+
+                        ClientSecretValue::Hashed { version, hash } => client_secret_matches_hash(
+                            &client.client_id,
+                            client_secret,
+                            *version,
+                            hash,
+                        )
+                        .map_err(CredentialsVerificationError::HashVerificationFailed)?,
+                    };
+
+                    if matched {
+                        break;
+                    }
+                }
 
-                // Check if the client_secret matches
-                if client_secret.as_bytes() != decrypted_client_secret {
+                if !matched {
                     return Err(CredentialsVerificationError::ClientSecretMismatch);
                 }
             }
@@ -169,18 +198,34 @@ impl Credentials {
                 Credentials::ClientAssertionJwtBearer { jwt, .. },
                 OAuthClientAuthenticationMethod::ClientSecretJwt,
             ) => {
-                // Decrypt the client_secret
-                let encrypted_client_secret = client
-                    .encrypted_client_secret
-                    .as_ref()
-                    .ok_or(CredentialsVerificationError::InvalidClientConfig)?;
+                // Hashed secrets can't be used as a shared key, so only look at the
+                // encrypted ones
+                let mut encrypted_secrets = client
+                    .active_secrets(now)
+                    .filter_map(|secret| secret.encrypted_secret())
+                    .peekable();
+                if encrypted_secrets.peek().is_none() {
+                    return Err(CredentialsVerificationError::InvalidClientConfig);
+                }
 
-                let decrypted_client_secret = encrypter
-                    .decrypt_string(encrypted_client_secret)
-                    .map_err(|_e| CredentialsVerificationError::DecryptionError)?;
+                let mut verified = false;
+                for encrypted_client_secret in encrypted_secrets {
+                    let decrypted_client_secret = encrypter
+                        .decrypt_string(encrypted_client_secret)
+                        .map_err(|_e| CredentialsVerificationError::DecryptionError)?;
+
+                    if jwt
+                        .verify_with_shared_secret(decrypted_client_secret)
+                        .is_ok()
+                    {
+                        verified = true;
+                        break;
+                    }
+                }
 
-                jwt.verify_with_shared_secret(decrypted_client_secret)
-                    .map_err(|_| CredentialsVerificationError::InvalidAssertionSignature)?;
+                if !verified {
+                    return Err(CredentialsVerificationError::InvalidAssertionSignature);
+                }
             }
 
             (_, _) => {
@@ -222,6 +267,9 @@ pub enum CredentialsVerificationError {
     #[error("client secret did not match")]
     ClientSecretMismatch,
 
+    #[error("failed to verify hashed client secret")]
+    HashVerificationFailed(#[source] ClientSecretHashError),
+
     #[error("authentication method mismatch")]
     AuthenticationMethodMismatch,
 
@@ -238,7 +286,10 @@ impl CredentialsVerificationError {
     pub fn is_internal(&self) -> bool {
         matches!(
             self,
-            Self::DecryptionError | Self::InvalidClientConfig | Self::JwksFetchFailed(_)
+            Self::DecryptionError
+                | Self::InvalidClientConfig
+                | Self::HashVerificationFailed(_)
+                | Self::JwksFetchFailed(_)
         )
     }
 }
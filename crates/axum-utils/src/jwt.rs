@@ -13,9 +13,18 @@ use mime::Mime;
 pub struct JwtResponse<T>(pub Jwt<'static, T>);
 
 impl<T> IntoResponse for JwtResponse<T> {
+    fn into_response(self) -> Response {
+        EncryptedJwtResponse(self.0.into_string()).into_response()
+    }
+}
+
+/// A response containing an encrypted JWT, in its compact serialization
+pub struct EncryptedJwtResponse(pub String);
+
+impl IntoResponse for EncryptedJwtResponse {
     fn into_response(self) -> Response {
         let application_jwt: Mime = "application/jwt".parse().unwrap();
         let content_type = ContentType::from(application_jwt);
-        (TypedHeader(content_type), self.0.into_string()).into_response()
+        (TypedHeader(content_type), self.0).into_response()
     }
 }
@@ -6,16 +6,19 @@
 
 use std::{convert::Infallible, net::IpAddr, sync::Arc};
 
+use arc_swap::ArcSwap;
 use axum::extract::{FromRef, FromRequestParts};
 use ipnetwork::IpNetwork;
 use mas_context::LogContext;
 use mas_data_model::{AppVersion, BoxClock, BoxRng, SiteConfig, SystemClock};
 use mas_handlers::{
-    ActivityTracker, BoundActivityTracker, CookieManager, ErrorWrapper, GraphQLSchema, Limiter,
-    MetadataCache, RequesterFingerprint, passwords::PasswordManager,
+    ActivityTracker, BoundActivityTracker, ClientCertificateCache, CookieManager, ErrorWrapper,
+    GraphQLSchema, IntrospectionCache, Limiter, MetadataCache, RequesterFingerprint,
+    passwords::PasswordManager,
 };
 use mas_i18n::Translator;
 use mas_keystore::{Encrypter, Keystore};
+use mas_ldap::Directory;
 use mas_matrix::HomeserverConnection;
 use mas_policy::{Policy, PolicyFactory};
 use mas_router::UrlBuilder;
@@ -33,7 +36,7 @@ use crate::{VERSION, telemetry::METER};
 pub struct AppState {
     pub repository_factory: PgRepositoryFactory,
     pub templates: Templates,
-    pub key_store: Keystore,
+    pub key_store: Arc<ArcSwap<Keystore>>,
     pub cookie_manager: CookieManager,
     pub encrypter: Encrypter,
     pub url_builder: UrlBuilder,
@@ -42,11 +45,14 @@ pub struct AppState {
     pub graphql_schema: GraphQLSchema,
     pub http_client: reqwest::Client,
     pub password_manager: PasswordManager,
+    pub directory: Option<Arc<dyn Directory>>,
     pub metadata_cache: MetadataCache,
+    pub certificate_cache: ClientCertificateCache,
     pub site_config: SiteConfig,
     pub activity_tracker: ActivityTracker,
     pub trusted_proxies: Vec<IpNetwork>,
     pub limiter: Limiter,
+    pub introspection_cache: IntrospectionCache,
 }
 
 impl AppState {
@@ -114,6 +120,15 @@ impl AppState {
                 .instrument(tracing::info_span!("metadata_cache.background_warmup")),
         );
     }
+
+    /// Regularly evict the client certificates of removed upstream providers
+    /// in the background
+    pub fn init_certificate_cache(&self) {
+        let _handle = self.certificate_cache.run_eviction(
+            self.repository_factory.clone().boxed(),
+            std::time::Duration::from_mins(15),
+        );
+    }
 }
 
 // XXX(quenting): we only use this for the healthcheck endpoint, checking the db
@@ -150,7 +165,7 @@ impl FromRef<AppState> for Arc<Translator> {
 
 impl FromRef<AppState> for Keystore {
     fn from_ref(input: &AppState) -> Self {
-        input.key_store.clone()
+        Keystore::clone(&input.key_store.load())
     }
 }
 
@@ -178,6 +193,12 @@ impl FromRef<AppState> for PasswordManager {
     }
 }
 
+impl FromRef<AppState> for Option<Arc<dyn Directory>> {
+    fn from_ref(input: &AppState) -> Self {
+        input.directory.clone()
+    }
+}
+
 impl FromRef<AppState> for CookieManager {
     fn from_ref(input: &AppState) -> Self {
         input.cookie_manager.clone()
@@ -190,6 +211,12 @@ impl FromRef<AppState> for MetadataCache {
     }
 }
 
+impl FromRef<AppState> for ClientCertificateCache {
+    fn from_ref(input: &AppState) -> Self {
+        input.certificate_cache.clone()
+    }
+}
+
 impl FromRef<AppState> for SiteConfig {
     fn from_ref(input: &AppState) -> Self {
         input.site_config.clone()
@@ -202,6 +229,12 @@ impl FromRef<AppState> for Limiter {
     }
 }
 
+impl FromRef<AppState> for IntrospectionCache {
+    fn from_ref(input: &AppState) -> Self {
+        input.introspection_cache.clone()
+    }
+}
+
 impl FromRef<AppState> for Arc<PolicyFactory> {
     fn from_ref(input: &AppState) -> Self {
         input.policy_factory.clone()
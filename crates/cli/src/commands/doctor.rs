@@ -16,10 +16,17 @@ use clap::Parser;
 use figment::Figment;
 use hyper::StatusCode;
 use mas_config::{ConfigurationSection, RootConfig};
+use mas_data_model::UpstreamOAuthProviderHealthCheck;
+use mas_handlers::MetadataCache;
 use mas_http::RequestBuilderExt;
+use mas_storage::{RepositoryAccess, upstream_oauth2::UpstreamOAuthProviderRepository};
+use mas_storage_pg::PgRepository;
+use sqlx::Acquire;
 use tracing::{error, info, info_span, warn};
 use url::{Host, Url};
 
+use crate::util::database_connection_from_config;
+
 /// Base URL for the human-readable documentation
 const DOCS_BASE: &str = "https://element-hq.github.io/matrix-authentication-service";
 
@@ -45,7 +52,7 @@ impl Options {
 See {DOCS_BASE}/setup/homeserver.html",
         )?;
         let secret = config.matrix.secret().await?;
-        let hs_api = config.matrix.endpoint;
+        let hs_api = config.matrix.endpoint.clone();
 
         if !issuer.starts_with("https://") {
             warn!(
@@ -405,6 +412,72 @@ Error details: {e}"#
             ),
         }
 
+        check_upstream_oauth_providers(&config, &http_client).await;
+
         Ok(ExitCode::SUCCESS)
     }
 }
+
+/// Check that the upstream OAuth 2.0 providers stored in the database are
+/// reachable
+async fn check_upstream_oauth_providers(config: &RootConfig, http_client: &reqwest::Client) {
+    let providers = async {
+        let mut conn = database_connection_from_config(&config.database).await?;
+        let txn = conn.begin().await?;
+        let mut repo = PgRepository::from_conn(txn);
+        let providers = repo.upstream_oauth_provider().all_enabled().await?;
+        anyhow::Ok(providers)
+    }
+    .await;
+
+    let providers = match providers {
+        Ok(providers) => providers,
+        Err(e) => {
+            warn!(
+                r"⚠️ Can't load the upstream OAuth 2.0 providers from the database, skipping their checks.
+
+Error details: {e:#}"
+            );
+            return;
+        }
+    };
+
+    let metadata_cache = MetadataCache::new();
+    for provider in providers {
+        let name = provider
+            .human_name
+            .clone()
+            .or_else(|| provider.issuer.clone())
+            .unwrap_or_else(|| provider.id.to_string());
+
+        let checks = mas_handlers::check_upstream_oauth_provider_health(
+            http_client,
+            &metadata_cache,
+            &provider,
+        )
+        .await;
+
+        if checks.is_healthy() {
+            info!(r#"✅ The upstream provider "{name}" is reachable."#);
+            continue;
+        }
+
+        for (check, outcome) in [
+            ("discovery", &checks.discovery),
+            ("JWKS", &checks.jwks),
+            ("token endpoint", &checks.token_endpoint),
+        ] {
+            if let UpstreamOAuthProviderHealthCheck::Failed { error } = outcome {
+                error!(
+                    r#"❌ The {check} check of the upstream provider "{name}" ({id}) failed.
+Users won't be able to login with this provider.
+
+See {DOCS_BASE}/setup/sso.html
+
+Error details: {error}"#,
+                    id = provider.id,
+                );
+            }
+        }
+    }
+}
@@ -7,6 +7,7 @@
 use std::{collections::BTreeMap, process::ExitCode};
 
 use anyhow::Context;
+use camino::Utf8PathBuf;
 use chrono::Duration;
 use clap::{ArgAction, CommandFactory, Parser};
 use console::{Alignment, Style, Term, pad_str, style};
@@ -14,24 +15,29 @@ use dialoguer::{Confirm, FuzzySelect, Input, Password, theme::ColorfulTheme};
 use figment::Figment;
 use mas_config::{
     ConfigurationSection, ConfigurationSectionExt, DatabaseConfig, MatrixConfig, PasswordsConfig,
+    SecretsConfig,
+};
+use mas_data_model::{
+    ClientSecret, Clock, Device, SystemClock, TokenType, Ulid, UpstreamOAuthProvider, User,
 };
-use mas_data_model::{Clock, Device, SystemClock, TokenType, Ulid, UpstreamOAuthProvider, User};
 use mas_email::Address;
 use mas_matrix::HomeserverConnection;
 use mas_storage::{
     Pagination, RepositoryAccess,
     compat::{CompatAccessTokenRepository, CompatSessionFilter, CompatSessionRepository},
-    oauth2::OAuth2SessionFilter,
+    oauth2::{OAuth2ClientRepository, OAuth2SessionFilter},
     queue::{
         DeactivateUserJob, ProvisionUserJob, QueueJobRepositoryExt as _, ReactivateUserJob,
-        SyncDevicesJob,
+        RotateSigningKeysJob, SyncDevicesJob,
     },
+    upstream_oauth2::UpstreamOAuthLinkFilter,
     user::{
-        BrowserSessionFilter, UserEmailRepository, UserFilter, UserPasswordRepository,
-        UserRepository,
+        BrowserSessionFilter, ImportedPassword, ImportedUpstreamLink, UserEmailFilter,
+        UserEmailRepository, UserFilter, UserImportRecord, UserPasswordRepository, UserRepository,
     },
 };
 use mas_storage_pg::{DatabaseError, PgRepository};
+use mas_tasks::import_user;
 use rand::{
     RngCore, SeedableRng,
     distributions::{Alphanumeric, DistString as _},
@@ -40,9 +46,12 @@ use sqlx::{Acquire, types::Uuid};
 use tracing::{error, info, info_span, warn};
 use zeroize::Zeroizing;
 
-use crate::util::{
-    database_connection_from_config, homeserver_connection_from_config,
-    password_manager_from_config,
+use crate::{
+    user_file::{UserFileFormat, UserWriter, read_users},
+    util::{
+        database_connection_from_config, homeserver_connection_from_config,
+        password_manager_from_config,
+    },
 };
 
 const USER_ATTRIBUTES_HEADING: &str = "User attributes";
@@ -216,6 +225,83 @@ enum Subcommand {
         #[clap(long)]
         ignore_password_complexity: bool,
     },
+
+    /// Import users from a JSON Lines or CSV file
+    ///
+    /// Users which already exist, or which conflict with existing email
+    /// addresses or upstream links, are skipped. Password hashes must have
+    /// been produced by one of the configured password schemes.
+    ImportUsers {
+        /// Path to the file to import
+        path: Utf8PathBuf,
+
+        /// Format of the file. If not set, it is guessed from the file
+        /// extension
+        #[arg(long, value_enum)]
+        format: Option<UserFileFormat>,
+
+        /// Do a dry run
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Export all users which are not deactivated to a JSON Lines or CSV file
+    ///
+    /// The output can be imported back with `import-users`, for example to
+    /// migrate users to another instance.
+    ExportUsers {
+        /// Path to the file to write. Defaults to the standard output
+        #[arg(short, long)]
+        output: Option<Utf8PathBuf>,
+
+        /// Format of the file. If not set, it is guessed from the file
+        /// extension, and defaults to JSON Lines
+        #[arg(long, value_enum)]
+        format: Option<UserFileFormat>,
+    },
+
+    /// Rotate the signing keys managed by the service
+    ///
+    /// This schedules a job which publishes new signing keys, even if the
+    /// current ones are not due for rotation yet. The new keys are only used
+    /// once they have been published for the configured publication period.
+    RotateSigningKeys,
+
+    /// Re-encrypt the secrets stored in the database with the active
+    /// encryption key
+    ///
+    /// This should be run after a new key was added at the top of
+    /// `secrets.encryption_keys`, so that older keys can eventually be removed
+    /// from the configuration.
+    ReencryptSecrets {
+        /// Do a dry run, only counting the values which would be re-encrypted
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Rotate the secret of a confidential OAuth 2.0 client
+    ///
+    /// The current secret keeps being accepted during the overlap period, so
+    /// that deployed instances of the client can be updated one by one.
+    RotateClientSecret {
+        /// ID of the client
+        client_id: Ulid,
+
+        /// Hash the new secret instead of encrypting it. This can't be used
+        /// with clients authenticating with `client_secret_jwt`.
+        #[arg(long)]
+        hash: bool,
+
+        /// Time in seconds during which the current secret is still accepted.
+        /// Set to 0 to stop accepting it immediately.
+        #[arg(long, default_value_t = 86400)]
+        overlap: u32,
+
+        /// Time in seconds after which the new secret expires.
+        /// If not provided, the secret never expires.
+        #[arg(long)]
+        expires_in: Option<u32>,
+    },
 }
 
 impl Options {
@@ -928,6 +1014,314 @@ impl Options {
 
                 Ok(ExitCode::SUCCESS)
             }
+
+            SC::ImportUsers {
+                path,
+                format,
+                dry_run,
+            } => {
+                let _span = info_span!("cli.manage.import_users", %path).entered();
+                let format = format
+                    .or_else(|| UserFileFormat::from_path(&path))
+                    .context("Could not guess the file format, use the --format option")?;
+
+                let file =
+                    std::fs::File::open(&path).with_context(|| format!("Failed to open {path}"))?;
+                let users = read_users(std::io::BufReader::new(file), format)?;
+                let total = users.len();
+                info!("Importing {total} users");
+
+                let database_config = DatabaseConfig::extract_or_default(figment)
+                    .map_err(anyhow::Error::from_boxed)?;
+                let passwords_config = PasswordsConfig::extract_or_default(figment)
+                    .map_err(anyhow::Error::from_boxed)?;
+                let matrix_config =
+                    MatrixConfig::extract(figment).map_err(anyhow::Error::from_boxed)?;
+
+                let password_manager = password_manager_from_config(&passwords_config).await?;
+                let http_client = mas_http::reqwest_client();
+                let homeserver =
+                    homeserver_connection_from_config(&matrix_config, http_client).await?;
+                let mut conn = database_connection_from_config(&database_config).await?;
+                let txn = conn.begin().await?;
+                let mut repo = PgRepository::from_conn(txn);
+
+                let mut imported = 0;
+                let mut skipped = 0;
+                for (index, user) in users.into_iter().enumerate() {
+                    if index > 0 && index % 100 == 0 {
+                        info!("Processed {index}/{total} users");
+                    }
+
+                    let username = user.username.clone();
+                    if let Some(password) = &user.password
+                        && !password_manager.has_scheme(password.version)
+                    {
+                        warn!(
+                            user.username = username,
+                            "Skipping user: unknown password scheme version {}", password.version
+                        );
+                        skipped += 1;
+                        continue;
+                    }
+
+                    match import_user(&mut repo, &mut rng, &clock, &homeserver, user).await {
+                        Ok(user) => {
+                            info!(%user.id, %user.username, "Imported user");
+                            imported += 1;
+                        }
+                        Err(e) if e.is_invalid_record() => {
+                            warn!(user.username = username, "Skipping user: {e}");
+                            skipped += 1;
+                        }
+                        Err(e) => return Err(e.into()),
+                    }
+                }
+
+                if dry_run {
+                    info!(imported, skipped, "Dry run, not importing users");
+                    repo.into_inner().rollback().await?;
+                } else {
+                    repo.into_inner().commit().await?;
+                    info!(imported, skipped, "Users imported");
+                }
+
+                Ok(ExitCode::SUCCESS)
+            }
+
+            SC::ExportUsers { output, format } => {
+                let _span = info_span!("cli.manage.export_users").entered();
+                let format = format
+                    .or_else(|| output.as_deref().and_then(UserFileFormat::from_path))
+                    .unwrap_or_default();
+
+                let database_config = DatabaseConfig::extract_or_default(figment)
+                    .map_err(anyhow::Error::from_boxed)?;
+                let mut conn = database_connection_from_config(&database_config).await?;
+                let txn = conn.begin().await?;
+                let mut repo = PgRepository::from_conn(txn);
+
+                let writer: Box<dyn std::io::Write> = if let Some(output) = &output {
+                    let file = std::fs::File::create(output)
+                        .with_context(|| format!("Failed to create {output}"))?;
+                    Box::new(std::io::BufWriter::new(file))
+                } else {
+                    Box::new(std::io::stdout().lock())
+                };
+                let mut writer = UserWriter::new(writer, format)?;
+
+                // Export both active and locked users, but not deactivated ones
+                let filter = UserFilter::new();
+                let active = repo.user().count(UserFilter::new().active_only()).await?;
+                let locked = repo.user().count(UserFilter::new().locked_only()).await?;
+                let total = active + locked;
+                info!("Exporting {total} users");
+
+                let mut cursor = Pagination::first(1000);
+                let mut exported = 0;
+                loop {
+                    let page = repo.user().list(filter, cursor).await?;
+                    for edge in page.edges {
+                        let user = edge.node;
+                        cursor = cursor.after(edge.cursor);
+
+                        if user.deactivated_at.is_some() {
+                            continue;
+                        }
+
+                        // Users have a handful of emails and links at most, so we
+                        // don't bother paginating those
+                        let emails = repo
+                            .user_email()
+                            .list(
+                                UserEmailFilter::new().for_user(&user),
+                                Pagination::first(100),
+                            )
+                            .await?
+                            .edges
+                            .into_iter()
+                            .map(|edge| edge.node.email)
+                            .collect();
+
+                        let upstream_links = repo
+                            .upstream_oauth_link()
+                            .list(
+                                UpstreamOAuthLinkFilter::new().for_user(&user),
+                                Pagination::first(100),
+                            )
+                            .await?
+                            .edges
+                            .into_iter()
+                            .map(|edge| ImportedUpstreamLink {
+                                provider_id: edge.node.provider_id,
+                                subject: edge.node.subject,
+                                human_account_name: edge.node.human_account_name,
+                            })
+                            .collect();
+
+                        let password = repo.user_password().active(&user).await?.map(|password| {
+                            ImportedPassword {
+                                version: password.version,
+                                hash: password.hashed_password,
+                            }
+                        });
+
+                        writer.write(&UserImportRecord {
+                            username: user.username,
+                            emails,
+                            password,
+                            admin: user.can_request_admin,
+                            locked: user.locked_at.is_some(),
+                            upstream_links,
+                            // The display name is stored on the homeserver
+                            display_name: None,
+                        })?;
+
+                        exported += 1;
+                        if exported % 1000 == 0 {
+                            info!("Exported {exported}/{total} users");
+                        }
+                    }
+
+                    if !page.has_next_page {
+                        break;
+                    }
+                }
+
+                writer.finish()?;
+                info!(exported, "Users exported");
+
+                Ok(ExitCode::SUCCESS)
+            }
+
+            SC::RotateSigningKeys => {
+                let _span = info_span!("cli.manage.rotate_signing_keys").entered();
+                let secrets_config =
+                    SecretsConfig::extract(figment).map_err(anyhow::Error::from_boxed)?;
+                if secrets_config.key_rotation.is_none() {
+                    error!("Signing key rotation is not enabled in the configuration");
+                    return Ok(ExitCode::FAILURE);
+                }
+
+                let database_config = DatabaseConfig::extract_or_default(figment)
+                    .map_err(anyhow::Error::from_boxed)?;
+                let mut conn = database_connection_from_config(&database_config).await?;
+                let txn = conn.begin().await?;
+                let mut repo = PgRepository::from_conn(txn);
+
+                info!("Scheduling signing key rotation");
+                repo.queue_job()
+                    .schedule_job(&mut rng, &clock, RotateSigningKeysJob::forced())
+                    .await?;
+
+                repo.into_inner().commit().await?;
+
+                Ok(ExitCode::SUCCESS)
+            }
+
+            SC::ReencryptSecrets { dry_run } => {
+                let _span = info_span!("cli.manage.reencrypt_secrets").entered();
+                let secrets_config =
+                    SecretsConfig::extract(figment).map_err(anyhow::Error::from_boxed)?;
+                let encrypter = secrets_config.encrypter().await?;
+                let Some(active_key_id) = encrypter.active_key_id() else {
+                    error!("No encryption key is configured in `secrets.encryption_keys`");
+                    return Ok(ExitCode::FAILURE);
+                };
+                info!(active_key_id, "Re-encrypting secrets with the active key");
+
+                let database_config = DatabaseConfig::extract_or_default(figment)
+                    .map_err(anyhow::Error::from_boxed)?;
+                let mut conn = database_connection_from_config(&database_config).await?;
+
+                let mut total = 0;
+                for column in crate::reencrypt::ENCRYPTED_COLUMNS {
+                    total += column.reencrypt(&mut conn, &encrypter, dry_run).await?;
+                }
+
+                if dry_run {
+                    info!(total, "Dry run, not re-encrypting anything");
+                } else {
+                    info!(total, "Re-encrypted all secrets");
+                }
+
+                Ok(ExitCode::SUCCESS)
+            }
+
+            SC::RotateClientSecret {
+                client_id,
+                hash,
+                overlap,
+                expires_in,
+            } => {
+                let _span =
+                    info_span!("cli.manage.rotate_client_secret", client.id = %client_id).entered();
+
+                let database_config = DatabaseConfig::extract_or_default(figment)
+                    .map_err(anyhow::Error::from_boxed)?;
+                let mut conn = database_connection_from_config(&database_config).await?;
+                let txn = conn.begin().await?;
+                let mut repo = PgRepository::from_conn(txn);
+
+                let client = repo
+                    .oauth2_client()
+                    .lookup(client_id)
+                    .await?
+                    .context("Client not found")?;
+
+                // Static clients get their secret from the configuration
+                let static_clients = repo.oauth2_client().all_static().await?;
+                if static_clients.iter().any(|c| c.id == client.id) {
+                    error!("This client is defined in the configuration, change its secret there");
+                    return Ok(ExitCode::FAILURE);
+                }
+
+                if !client.uses_client_secret() {
+                    error!("This client does not authenticate with a client secret");
+                    return Ok(ExitCode::FAILURE);
+                }
+
+                if hash && client.needs_encrypted_secret() {
+                    error!("The secret of a client using `client_secret_jwt` can't be hashed");
+                    return Ok(ExitCode::FAILURE);
+                }
+
+                let now = clock.now();
+                let expires_at = expires_in.map(|seconds| now + Duration::seconds(seconds.into()));
+                let client_secret = Alphanumeric.sample_string(&mut rng, 32);
+
+                let new_secret = if hash {
+                    ClientSecret::hash(&client.client_id, &client_secret, expires_at)
+                } else {
+                    let secrets_config =
+                        SecretsConfig::extract(figment).map_err(anyhow::Error::from_boxed)?;
+                    let encrypter = secrets_config.encrypter().await?;
+                    let encrypted_secret = encrypter.encrypt_to_string(client_secret.as_bytes())?;
+                    ClientSecret::encrypted(encrypted_secret, expires_at)
+                };
+
+                let previous_secret =
+                    client.secret_kept_on_rotation(now, Duration::seconds(overlap.into()));
+                let previous_secret_expires_at = previous_secret
+                    .as_ref()
+                    .and_then(|secret| secret.expires_at);
+
+                let client = repo
+                    .oauth2_client()
+                    .set_secrets(client, Some(new_secret), previous_secret)
+                    .await?;
+
+                repo.into_inner().commit().await?;
+
+                info!(
+                    %client.id,
+                    ?expires_at,
+                    ?previous_secret_expires_at,
+                    "Rotated client secret: {client_secret}"
+                );
+
+                Ok(ExitCode::SUCCESS)
+            }
         }
     }
 }
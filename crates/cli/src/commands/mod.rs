@@ -18,6 +18,7 @@ mod database;
 mod debug;
 mod doctor;
 mod manage;
+mod queue;
 mod server;
 mod syn2mas;
 mod templates;
@@ -40,6 +41,9 @@ enum Subcommand {
     /// Manage the instance
     Manage(self::manage::Options),
 
+    /// Inspect and manage the background job queue
+    Queue(self::queue::Options),
+
     /// Templates-related commands
     Templates(self::templates::Options),
 
@@ -78,6 +82,7 @@ impl Options {
             Some(S::Server(c)) => Box::pin(c.run(figment)).await,
             Some(S::Worker(c)) => Box::pin(c.run(figment)).await,
             Some(S::Manage(c)) => Box::pin(c.run(figment)).await,
+            Some(S::Queue(c)) => Box::pin(c.run(figment)).await,
             Some(S::Templates(c)) => Box::pin(c.run(figment)).await,
             Some(S::Debug(c)) => Box::pin(c.run(figment)).await,
             Some(S::Doctor(c)) => Box::pin(c.run(figment)).await,
@@ -0,0 +1,266 @@
+// Copyright 2026 Element Creations Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+// Please see LICENSE files in the repository root for full details.
+
+use std::process::ExitCode;
+
+use anyhow::Context;
+use chrono::Duration;
+use clap::Parser;
+use figment::Figment;
+use mas_config::{ConfigurationSectionExt, DatabaseConfig};
+use mas_data_model::{SystemClock, Ulid};
+use mas_storage::{
+    Pagination, RepositoryAccess,
+    queue::{JobFilter, JobStatus},
+};
+use mas_storage_pg::PgRepository;
+use rand::SeedableRng;
+use sqlx::Acquire;
+use tracing::{error, info, info_span, warn};
+
+use crate::util::database_connection_from_config;
+
+#[derive(Parser, Debug)]
+pub(super) struct Options {
+    #[command(subcommand)]
+    subcommand: Subcommand,
+}
+
+#[derive(Parser, Debug)]
+enum Subcommand {
+    /// List jobs in the queue, most recent first
+    List {
+        /// Only list jobs placed on this queue
+        #[arg(long)]
+        queue: Option<String>,
+
+        /// Only list jobs with this status. One of `available`, `running`,
+        /// `completed`, `failed`, `scheduled`, `lost` or `cancelled`
+        #[arg(long)]
+        status: Option<JobStatus>,
+
+        /// Maximum number of jobs to list
+        #[arg(long, default_value_t = 100)]
+        limit: usize,
+    },
+
+    /// Show the details of a job, including its payload and error
+    Show {
+        /// The ID of the job
+        id: Ulid,
+    },
+
+    /// Retry a failed job as soon as possible
+    Retry {
+        /// The ID of the failed job
+        id: Ulid,
+    },
+
+    /// Cancel a job which was not picked up by a worker yet
+    Cancel {
+        /// The ID of the job
+        id: Ulid,
+    },
+
+    /// Pause a queue, so that workers stop picking up jobs from it
+    Pause {
+        /// The name of the queue, for example `provision-user`
+        queue: String,
+    },
+
+    /// Resume a paused queue
+    Resume {
+        /// The name of the queue
+        queue: String,
+    },
+
+    /// List the paused queues
+    Paused,
+}
+
+impl Options {
+    pub async fn run(self, figment: &Figment) -> anyhow::Result<ExitCode> {
+        use Subcommand as SC;
+        let clock = SystemClock::default();
+        // XXX: we should disallow SeedableRng::from_entropy
+        let mut rng = rand_chacha::ChaChaRng::from_entropy();
+
+        let database_config =
+            DatabaseConfig::extract_or_default(figment).map_err(anyhow::Error::from_boxed)?;
+        let mut conn = database_connection_from_config(&database_config).await?;
+        let txn = conn.begin().await?;
+        let mut repo = PgRepository::from_conn(txn);
+
+        match self.subcommand {
+            SC::List {
+                queue,
+                status,
+                limit,
+            } => {
+                let _span = info_span!("cli.queue.list").entered();
+
+                let filter = JobFilter::new();
+                let filter = match &queue {
+                    Some(queue) => filter.for_queue(queue),
+                    None => filter,
+                };
+                let filter = match status {
+                    Some(status) => filter.with_status(status),
+                    None => filter,
+                };
+
+                let total = repo.queue_job().count(filter).await?;
+                let page = repo
+                    .queue_job()
+                    .list(filter, Pagination::last(limit))
+                    .await?;
+
+                info!("Showing {} out of {total} jobs", page.edges.len());
+                for edge in page.edges.into_iter().rev() {
+                    let job = edge.node;
+                    info!(
+                        job.id = %job.id,
+                        job.queue_name = %job.queue_name,
+                        job.status = %job.status,
+                        job.attempt = %job.attempt,
+                        job.created_at = %job.created_at,
+                        job.failed_reason = job.failed_reason.as_deref(),
+                    );
+                }
+
+                Ok(ExitCode::SUCCESS)
+            }
+
+            SC::Show { id } => {
+                let _span = info_span!("cli.queue.show", job.id = %id).entered();
+
+                let job = repo
+                    .queue_job()
+                    .lookup(id)
+                    .await?
+                    .context("Job not found")?;
+
+                info!(
+                    job.id = %job.id,
+                    job.queue_name = %job.queue_name,
+                    job.status = %job.status,
+                    job.attempt = %job.attempt,
+                    job.created_at = %job.created_at,
+                    job.scheduled_at = job.scheduled_at.map(tracing::field::display),
+                    job.started_at = job.started_at.map(tracing::field::display),
+                    job.started_by = job.started_by.map(tracing::field::display),
+                    job.completed_at = job.completed_at.map(tracing::field::display),
+                    job.failed_at = job.failed_at.map(tracing::field::display),
+                    job.cancelled_at = job.cancelled_at.map(tracing::field::display),
+                    job.next_attempt_id = job.next_attempt_id.map(tracing::field::display),
+                    job.schedule_name = job.schedule_name.as_deref(),
+                );
+
+                if let Some(reason) = &job.failed_reason {
+                    warn!("Job failed: {reason}");
+                }
+
+                let payload = serde_json::to_string_pretty(&job.payload)?;
+                info!("Payload:\n{payload}");
+
+                Ok(ExitCode::SUCCESS)
+            }
+
+            SC::Retry { id } => {
+                let _span = info_span!("cli.queue.retry", job.id = %id).entered();
+
+                let job = repo
+                    .queue_job()
+                    .lookup(id)
+                    .await?
+                    .context("Job not found")?;
+
+                if job.status != JobStatus::Failed {
+                    error!(job.status = %job.status, "Only failed jobs can be retried");
+                    return Ok(ExitCode::from(1));
+                }
+
+                if let Some(next_attempt_id) = job.next_attempt_id {
+                    error!(%next_attempt_id, "Job was already retried");
+                    return Ok(ExitCode::from(1));
+                }
+
+                repo.queue_job()
+                    .retry(&mut rng, &clock, id, Duration::zero())
+                    .await?;
+
+                repo.into_inner().commit().await?;
+                info!(job.id = %id, job.queue_name = %job.queue_name, "Job retried");
+
+                Ok(ExitCode::SUCCESS)
+            }
+
+            SC::Cancel { id } => {
+                let _span = info_span!("cli.queue.cancel", job.id = %id).entered();
+
+                let job = repo
+                    .queue_job()
+                    .lookup(id)
+                    .await?
+                    .context("Job not found")?;
+
+                if !job.status.is_cancellable() {
+                    error!(job.status = %job.status, "Job was already picked up by a worker");
+                    return Ok(ExitCode::from(1));
+                }
+
+                repo.queue_job().cancel(&clock, id).await?;
+
+                repo.into_inner().commit().await?;
+                info!(job.id = %id, job.queue_name = %job.queue_name, "Job cancelled");
+
+                Ok(ExitCode::SUCCESS)
+            }
+
+            SC::Pause { queue } => {
+                let _span = info_span!("cli.queue.pause", queue.name = %queue).entered();
+
+                if !repo.queue_worker().pause_queue(&clock, &queue).await? {
+                    warn!("Queue is already paused");
+                    return Ok(ExitCode::SUCCESS);
+                }
+
+                repo.into_inner().commit().await?;
+                info!("Queue paused");
+
+                Ok(ExitCode::SUCCESS)
+            }
+
+            SC::Resume { queue } => {
+                let _span = info_span!("cli.queue.resume", queue.name = %queue).entered();
+
+                if !repo.queue_worker().resume_queue(&queue).await? {
+                    warn!("Queue is not paused");
+                    return Ok(ExitCode::SUCCESS);
+                }
+
+                repo.into_inner().commit().await?;
+                info!("Queue resumed");
+
+                Ok(ExitCode::SUCCESS)
+            }
+
+            SC::Paused => {
+                let _span = info_span!("cli.queue.paused").entered();
+
+                let queues = repo.queue_worker().list_paused_queues().await?;
+                if queues.is_empty() {
+                    info!("No queue is paused");
+                }
+
+                for queue in queues {
+                    info!(queue.name = %queue.queue_name, queue.paused_at = %queue.paused_at);
+                }
+
+                Ok(ExitCode::SUCCESS)
+            }
+        }
+    }
+}
@@ -7,6 +7,7 @@
 use std::{process::ExitCode, sync::Arc, time::Duration};
 
 use anyhow::Context;
+use arc_swap::ArcSwap;
 use clap::Parser;
 use figment::Figment;
 use itertools::Itertools;
@@ -15,7 +16,10 @@ use mas_config::{
 };
 use mas_context::LogContext;
 use mas_data_model::SystemClock;
-use mas_handlers::{ActivityTracker, CookieManager, Limiter, MetadataCache};
+use mas_handlers::{
+    ActivityTracker, ClientCertificateCache, CookieManager, IntrospectionCache, Limiter,
+    MetadataCache, UpstreamClaimsSyncer,
+};
 use mas_listener::server::Server;
 use mas_router::UrlBuilder;
 use mas_storage_pg::PgRepositoryFactory;
@@ -25,10 +29,10 @@ use crate::{
     app_state::AppState,
     lifecycle::LifecycleManager,
     util::{
-        database_pool_from_config, homeserver_connection_from_config,
-        load_policy_factory_dynamic_data_continuously, mailer_from_config,
-        password_manager_from_config, policy_factory_from_config, site_config_from_config,
-        templates_from_config, test_mailer_in_background,
+        database_pool_from_config, directory_from_config, homeserver_connection_from_config,
+        load_policy_factory_dynamic_data_continuously, load_signing_keys_continuously,
+        mailer_from_config, password_manager_from_config, policy_factory_from_config,
+        site_config_from_config, templates_from_config, test_mailer_in_background,
     },
 };
 
@@ -114,12 +118,27 @@ impl Options {
         }
 
         // Initialize the key store
-        let key_store = config
+        let config_key_store = config
             .secrets
             .key_store()
             .await
             .context("could not import keys from config")?;
 
+        // Add the signing keys managed by the service if the automatic rotation is
+        // enabled
+        let key_store = Arc::new(ArcSwap::from_pointee(config_key_store.clone()));
+        if config.secrets.key_rotation.is_some() {
+            load_signing_keys_continuously(
+                &key_store,
+                config_key_store.clone(),
+                encrypter.clone(),
+                PgRepositoryFactory::new(pool.clone()).boxed(),
+                shutdown.soft_shutdown_token(),
+                shutdown.task_tracker(),
+            )
+            .await?;
+        }
+
         let cookie_manager = CookieManager::derive_from(
             config.http.public_base.clone(),
             &config.secrets.encryption().await?,
@@ -154,6 +173,7 @@ impl Options {
             &config.passwords,
             &config.account,
             &config.captcha,
+            config.secrets.key_rotation.as_ref(),
         )?;
 
         // Load and compile the templates
@@ -174,6 +194,12 @@ impl Options {
         let homeserver_connection =
             homeserver_connection_from_config(&config.matrix, http_client.clone()).await?;
 
+        // The upstream OIDC metadata cache
+        let metadata_cache = MetadataCache::new();
+
+        // The HTTP clients presenting the client certificates of upstream providers
+        let certificate_cache = ClientCertificateCache::new();
+
         if !self.no_worker {
             let mailer = mailer_from_config(&config.email, &templates)?;
             test_mailer_in_background(&mailer, Duration::from_secs(30));
@@ -186,6 +212,14 @@ impl Options {
                 homeserver_connection.clone(),
                 url_builder.clone(),
                 &site_config,
+                &encrypter,
+                Arc::new(UpstreamClaimsSyncer::new(
+                    http_client.clone(),
+                    metadata_cache.clone(),
+                    certificate_cache.clone(),
+                    config_key_store,
+                    encrypter.clone(),
+                )),
                 shutdown.soft_shutdown_token(),
                 shutdown.task_tracker(),
             )
@@ -195,9 +229,7 @@ impl Options {
         let listeners_config = config.http.listeners.clone();
 
         let password_manager = password_manager_from_config(&config.passwords).await?;
-
-        // The upstream OIDC metadata cache
-        let metadata_cache = MetadataCache::new();
+        let directory = directory_from_config(&config.passwords).await?;
 
         // Initialize the activity tracker
         // Activity is flushed every minute
@@ -218,6 +250,9 @@ impl Options {
         let limiter = Limiter::new(&config.rate_limiting)
             .context("rate-limiting configuration is not valid")?;
 
+        let introspection_cache =
+            IntrospectionCache::new(config.experimental.introspection_cache_ttl);
+
         // Explicitly the config to properly zeroize secret keys
         drop(config);
 
@@ -231,6 +266,7 @@ impl Options {
             password_manager.clone(),
             url_builder.clone(),
             limiter.clone(),
+            introspection_cache.clone(),
         );
 
         let state = {
@@ -246,14 +282,18 @@ impl Options {
                 graphql_schema,
                 http_client,
                 password_manager,
+                directory,
                 metadata_cache,
+                certificate_cache,
                 site_config,
                 activity_tracker,
                 trusted_proxies,
                 limiter,
+                introspection_cache,
             };
             s.init_metrics();
             s.init_metadata_cache();
+            s.init_certificate_cache();
             s
         };
 
@@ -86,6 +86,7 @@ impl Options {
                     &password_config,
                     &account_config,
                     &captcha_config,
+                    None,
                 )?;
                 let templates = templates_from_config(
                     &template_config,
@@ -4,12 +4,14 @@
 // SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
 // Please see LICENSE files in the repository root for full details.
 
-use std::{process::ExitCode, time::Duration};
+use std::{process::ExitCode, sync::Arc, time::Duration};
 
+use anyhow::Context;
 use clap::Parser;
 use figment::Figment;
 use mas_config::{AppConfig, ConfigurationSection};
 use mas_data_model::SystemClock;
+use mas_handlers::{ClientCertificateCache, MetadataCache, UpstreamClaimsSyncer};
 use mas_router::UrlBuilder;
 use mas_storage_pg::PgRepositoryFactory;
 use tracing::{info, info_span};
@@ -49,6 +51,7 @@ impl Options {
             &config.passwords,
             &config.account,
             &config.captcha,
+            config.secrets.key_rotation.as_ref(),
         )?;
 
         // Load and compile the templates
@@ -67,7 +70,27 @@ impl Options {
         test_mailer_in_background(&mailer, Duration::from_secs(30));
 
         let http_client = mas_http::reqwest_client();
-        let conn = homeserver_connection_from_config(&config.matrix, http_client).await?;
+        let conn = homeserver_connection_from_config(&config.matrix, http_client.clone()).await?;
+
+        // Used to refresh the claims of users linked to upstream providers
+        let key_store = config
+            .secrets
+            .key_store()
+            .await
+            .context("could not import keys from config")?;
+        let encrypter = config.secrets.encrypter().await?;
+        let certificate_cache = ClientCertificateCache::new();
+        let _eviction = certificate_cache.run_eviction(
+            PgRepositoryFactory::new(pool.clone()).boxed(),
+            Duration::from_mins(15),
+        );
+        let upstream_oauth_claims_sync = Arc::new(UpstreamClaimsSyncer::new(
+            http_client,
+            MetadataCache::new(),
+            certificate_cache,
+            key_store,
+            encrypter.clone(),
+        ));
 
         drop(config);
 
@@ -79,6 +102,8 @@ impl Options {
             conn,
             url_builder,
             &site_config,
+            &encrypter,
+            upstream_oauth_claims_sync,
             shutdown.soft_shutdown_token(),
             shutdown.task_tracker(),
         )
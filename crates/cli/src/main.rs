@@ -22,9 +22,11 @@ use tracing_subscriber::{
 mod app_state;
 mod commands;
 mod lifecycle;
+mod reencrypt;
 mod server;
 mod sync;
 mod telemetry;
+mod user_file;
 mod util;
 
 /// The application version, as reported by `git describe` at build time
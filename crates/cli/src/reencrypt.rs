@@ -0,0 +1,150 @@
+// Copyright 2026 Element Creations Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+// Please see LICENSE files in the repository root for full details.
+
+//! Re-encryption of the secrets stored in the database, as used by the
+//! `manage reencrypt-secrets` command.
+
+use anyhow::Context;
+use mas_keystore::Encrypter;
+use sqlx::{Acquire, PgConnection, types::Uuid};
+use tracing::info;
+
+/// How many rows are re-encrypted in a single transaction
+const BATCH_SIZE: i64 = 1000;
+
+/// A column holding values encrypted with the [`Encrypter`]
+pub struct EncryptedColumn {
+    table: &'static str,
+    id_column: &'static str,
+    column: &'static str,
+}
+
+/// All the columns holding values encrypted with the [`Encrypter`]
+pub const ENCRYPTED_COLUMNS: &[EncryptedColumn] = &[
+    EncryptedColumn {
+        table: "oauth2_clients",
+        id_column: "oauth2_client_id",
+        column: "encrypted_client_secret",
+    },
+    EncryptedColumn {
+        table: "oauth2_clients",
+        id_column: "oauth2_client_id",
+        column: "previous_encrypted_client_secret",
+    },
+    EncryptedColumn {
+        table: "upstream_oauth_providers",
+        id_column: "upstream_oauth_provider_id",
+        column: "encrypted_client_secret",
+    },
+    EncryptedColumn {
+        table: "upstream_oauth_providers",
+        id_column: "upstream_oauth_provider_id",
+        column: "encrypted_client_keys",
+    },
+    EncryptedColumn {
+        table: "upstream_oauth_links",
+        id_column: "upstream_oauth_link_id",
+        column: "encrypted_refresh_token",
+    },
+    EncryptedColumn {
+        table: "upstream_oauth_links",
+        id_column: "upstream_oauth_link_id",
+        column: "encrypted_access_token",
+    },
+    EncryptedColumn {
+        table: "signing_keys",
+        id_column: "signing_key_id",
+        column: "encrypted_key",
+    },
+];
+
+impl EncryptedColumn {
+    /// Re-encrypt all the values of this column which were not encrypted with
+    /// the active key, in batches of [`BATCH_SIZE`] rows.
+    ///
+    /// Returns the number of values which were re-encrypted, or which would be
+    /// re-encrypted if `dry_run` is set.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a value could not be decrypted, or if a database
+    /// query failed.
+    pub async fn reencrypt(
+        &self,
+        conn: &mut PgConnection,
+        encrypter: &Encrypter,
+        dry_run: bool,
+    ) -> anyhow::Result<u64> {
+        let Self {
+            table,
+            id_column,
+            column,
+        } = self;
+
+        let total: i64 = sqlx::query_scalar(&format!(
+            "SELECT COUNT(*) FROM {table} WHERE {column} IS NOT NULL"
+        ))
+        .fetch_one(&mut *conn)
+        .await?;
+        info!("Checking {total} values in {table}.{column}");
+
+        // Lock the rows until the batch is committed, so that a value changed
+        // concurrently isn't overwritten with the re-encrypted old one
+        let select = format!(
+            "SELECT {id_column}, {column} FROM {table}
+             WHERE {column} IS NOT NULL AND {id_column} > $1
+             ORDER BY {id_column} ASC
+             LIMIT $2
+             FOR UPDATE"
+        );
+        let update = format!("UPDATE {table} SET {column} = $2 WHERE {id_column} = $1");
+
+        let mut last_id = Uuid::nil();
+        let mut checked = 0;
+        let mut reencrypted = 0;
+        loop {
+            let mut txn = conn.begin().await?;
+
+            let rows: Vec<(Uuid, String)> = sqlx::query_as(&select)
+                .bind(last_id)
+                .bind(BATCH_SIZE)
+                .fetch_all(&mut *txn)
+                .await?;
+
+            let Some((id, _)) = rows.last() else {
+                break;
+            };
+            last_id = *id;
+            checked += rows.len();
+
+            for (id, value) in rows {
+                if !encrypter.needs_reencryption(&value) {
+                    continue;
+                }
+
+                let value = encrypter
+                    .reencrypt_string(&value)
+                    .with_context(|| format!("Could not decrypt {table}.{column} for row {id}"))?;
+
+                if !dry_run {
+                    sqlx::query(&update)
+                        .bind(id)
+                        .bind(value)
+                        .execute(&mut *txn)
+                        .await?;
+                }
+
+                reencrypted += 1;
+            }
+
+            txn.commit().await?;
+            info!("Checked {checked}/{total} values in {table}.{column}");
+        }
+
+        info!(reencrypted, "Done with {table}.{column}");
+
+        Ok(reencrypted)
+    }
+}
@@ -10,6 +10,7 @@ use std::collections::{BTreeMap, BTreeSet};
 
 use mas_config::{ClientsConfig, UpstreamOAuth2Config};
 use mas_data_model::Clock;
+use mas_jose::constraints::Constrainable;
 use mas_keystore::Encrypter;
 use mas_storage::{
     Pagination, RepositoryAccess,
@@ -81,6 +82,20 @@ fn map_claims_imports(
         account_name: mas_data_model::UpstreamOAuthProviderSubjectPreference {
             template: config.account_name.template.clone(),
         },
+        can_request_admin: mas_data_model::UpstreamOAuthProviderSubjectPreference {
+            template: config.can_request_admin.template.clone(),
+        },
+        account_status: mas_data_model::UpstreamOAuthProviderAccountStatusPreference {
+            template: config.account_status.template.clone(),
+            allow_deactivation: config.account_status.allow_deactivation,
+        },
+        attributes: mas_data_model::UpstreamOAuthProviderSubjectPreference {
+            template: config.attributes.template.clone(),
+        },
+        resync: mas_data_model::UpstreamOAuthProviderResyncPreference {
+            on_login: config.resync.on_login,
+            in_background: config.resync.in_background,
+        },
     }
 }
 
@@ -209,7 +224,98 @@ pub async fn config_sync(
                 continue;
             }
 
-            let encrypted_client_secret = if let Some(client_secret) = provider.client_secret {
+            let saml = if let Some(saml) = &provider.saml {
+                let sp_certificate = saml.sp_certificate().await?;
+                let sp_private_key = saml.sp_private_key().await?;
+
+                // Check that the certificates and the key can be loaded, so that
+                // configuration errors are caught early
+                for certificate in &saml.idp_certificates {
+                    mas_saml::Certificate::parse(certificate)?;
+                }
+                if let Some(certificate) = &sp_certificate {
+                    mas_saml::Certificate::parse(certificate)?;
+                }
+                if let Some(key) = &sp_private_key {
+                    mas_saml::private_key_from_pem(key)?;
+                }
+
+                let sso_binding = match saml.sso_binding {
+                    mas_config::UpstreamOAuth2SamlBinding::Redirect => {
+                        mas_data_model::UpstreamOAuthProviderSamlBinding::Redirect
+                    }
+                    mas_config::UpstreamOAuth2SamlBinding::Post => {
+                        mas_data_model::UpstreamOAuthProviderSamlBinding::Post
+                    }
+                };
+
+                let config = mas_data_model::UpstreamOAuthProviderSamlConfig {
+                    idp_entity_id: saml.idp_entity_id.clone(),
+                    sso_url: saml.sso_url.clone(),
+                    sso_binding,
+                    idp_certificates: saml.idp_certificates.clone(),
+                    sp_certificate,
+                    sign_authn_requests: saml.sign_authn_requests,
+                    want_assertions_signed: saml.want_assertions_signed,
+                    want_assertions_encrypted: saml.want_assertions_encrypted,
+                    allow_cbc_encryption: saml.allow_cbc_encryption,
+                    name_id_format: saml.name_id_format.clone(),
+                };
+
+                Some((config, sp_private_key))
+            } else {
+                None
+            };
+
+            // The private keys and the client certificate are stored together as an
+            // encrypted JSON document, with the keys in PEM format
+            let private_keys = provider.private_keys().await?;
+            let client_certificate = if let Some(client_certificate) = &provider.client_certificate
+            {
+                let certificate = client_certificate.certificate().await?;
+                let private_key = client_certificate.private_key().await?;
+
+                // Check that the certificate and the key can be loaded, so that
+                // configuration errors are caught early
+                mas_http::ClientCertificate::from_pem(&certificate, &private_key)?;
+
+                Some(serde_json::json!({
+                    "certificate": certificate,
+                    "private_key": private_key,
+                }))
+            } else {
+                None
+            };
+
+            let encrypted_client_keys = if private_keys.is_empty() && client_certificate.is_none() {
+                None
+            } else {
+                let keys = private_keys
+                    .iter()
+                    .map(|key| {
+                        let pem = key.params().to_pem(pem_rfc7468::LineEnding::LF)?;
+                        Ok(serde_json::json!({
+                            "kid": key.kid(),
+                            "key": pem.as_str(),
+                        }))
+                    })
+                    .collect::<anyhow::Result<Vec<_>>>()?;
+
+                let encoded = serde_json::to_vec(&serde_json::json!({
+                    "keys": keys,
+                    "certificate": client_certificate,
+                }))?;
+                Some(encrypter.encrypt_to_string(&encoded)?)
+            };
+
+            let encrypted_client_secret = if let Some((_, sp_private_key)) = &saml {
+                // The private key of the SAML service provider is stored like a
+                // client secret
+                sp_private_key
+                    .as_deref()
+                    .map(|key| encrypter.encrypt_to_string(key.as_bytes()))
+                    .transpose()?
+            } else if let Some(client_secret) = provider.client_secret {
                 Some(encrypter.encrypt_to_string(client_secret.value().await?.as_bytes())?)
             } else if let Some(mut siwa) = provider.sign_in_with_apple.clone() {
                 // if private key file is defined and not private key (raw), we populate the
@@ -229,6 +335,8 @@ pub async fn config_sync(
             };
 
             let discovery_mode = match provider.discovery_mode {
+                // SAML providers don't use OAuth 2.0 discovery
+                _ if saml.is_some() => mas_data_model::UpstreamOAuthProviderDiscoveryMode::Disabled,
                 mas_config::UpstreamOAuth2DiscoveryMode::Oidc => {
                     mas_data_model::UpstreamOAuthProviderDiscoveryMode::Oidc
                 }
@@ -256,6 +364,12 @@ pub async fn config_sync(
                 mas_config::UpstreamOAuth2TokenAuthMethod::PrivateKeyJwt => {
                     mas_data_model::UpstreamOAuthProviderTokenAuthMethod::PrivateKeyJwt
                 }
+                mas_config::UpstreamOAuth2TokenAuthMethod::TlsClientAuth => {
+                    mas_data_model::UpstreamOAuthProviderTokenAuthMethod::TlsClientAuth
+                }
+                mas_config::UpstreamOAuth2TokenAuthMethod::SelfSignedTlsClientAuth => {
+                    mas_data_model::UpstreamOAuthProviderTokenAuthMethod::SelfSignedTlsClientAuth
+                }
                 mas_config::UpstreamOAuth2TokenAuthMethod::SignInWithApple => {
                     mas_data_model::UpstreamOAuthProviderTokenAuthMethod::SignInWithApple
                 }
@@ -272,7 +386,7 @@ pub async fn config_sync(
                     }
                 });
 
-            if discovery_mode.is_disabled() {
+            if discovery_mode.is_disabled() && saml.is_none() {
                 if provider.authorization_endpoint.is_none() {
                     error!(provider.id = %provider.id, "Provider has discovery disabled but no authorization endpoint set");
                 }
@@ -341,6 +455,11 @@ pub async fn config_sync(
                         forward_login_hint: provider.forward_login_hint,
                         ui_order,
                         on_backchannel_logout,
+                        saml: saml.map(|(config, _)| config),
+                        email_domains: provider.email_domains,
+                        store_tokens: provider.store_tokens,
+                        allow_initiated_login: provider.allow_initiated_login,
+                        encrypted_client_keys,
                     },
                 )
                 .await?;
@@ -0,0 +1,300 @@
+// Copyright 2026 Element Creations Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+// Please see LICENSE files in the repository root for full details.
+
+//! Reading and writing user files, as used by the `manage import-users` and
+//! `manage export-users` commands.
+//!
+//! Two formats are supported:
+//!
+//!  - JSON Lines, with one [`UserImportRecord`] per line
+//!  - CSV, with the `username`, `emails`, `password_version`,
+//!    `password_hash`, `admin`, `locked`, `upstream_links` and `display_name`
+//!    columns.
+//!    Multiple emails and upstream links are separated by `;`, and upstream
+//!    links are written as `provider_id:subject`
+
+use std::io::{BufRead, Write};
+
+use anyhow::Context;
+use camino::Utf8Path;
+use clap::ValueEnum;
+use mas_storage::user::{ImportedPassword, ImportedUpstreamLink, UserImportRecord};
+
+const CSV_HEADERS: [&str; 8] = [
+    "username",
+    "emails",
+    "password_version",
+    "password_hash",
+    "admin",
+    "locked",
+    "upstream_links",
+    "display_name",
+];
+
+/// The format of a user file
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum UserFileFormat {
+    /// One JSON object per line
+    #[default]
+    Jsonl,
+
+    /// Comma-separated values, with a header row
+    Csv,
+}
+
+impl UserFileFormat {
+    /// Guess the format from the file extension
+    pub fn from_path(path: &Utf8Path) -> Option<Self> {
+        match path.extension()? {
+            "jsonl" | "ndjson" => Some(Self::Jsonl),
+            "csv" => Some(Self::Csv),
+            _ => None,
+        }
+    }
+}
+
+/// Read all the users from the given reader
+pub fn read_users(
+    reader: impl BufRead,
+    format: UserFileFormat,
+) -> anyhow::Result<Vec<UserImportRecord>> {
+    match format {
+        UserFileFormat::Jsonl => read_jsonl(reader),
+        UserFileFormat::Csv => read_csv(reader),
+    }
+}
+
+fn read_jsonl(reader: impl BufRead) -> anyhow::Result<Vec<UserImportRecord>> {
+    let mut users = Vec::new();
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let user = serde_json::from_str(&line)
+            .with_context(|| format!("Invalid user on line {}", index + 1))?;
+        users.push(user);
+    }
+
+    Ok(users)
+}
+
+/// Split a list of values separated by `;`, ignoring empty values
+fn split_list(value: &str) -> impl Iterator<Item = &str> {
+    value.split(';').map(str::trim).filter(|v| !v.is_empty())
+}
+
+fn parse_bool(value: &str) -> anyhow::Result<bool> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "" | "false" | "no" | "0" => Ok(false),
+        "true" | "yes" | "1" => Ok(true),
+        _ => anyhow::bail!("Invalid boolean value {value:?}"),
+    }
+}
+
+fn read_csv(reader: impl BufRead) -> anyhow::Result<Vec<UserImportRecord>> {
+    let mut reader = csv::Reader::from_reader(reader);
+    let headers = reader.headers()?.clone();
+
+    // Find the index of each known column. Only `username` is required.
+    let column = |name: &str| headers.iter().position(|h| h.trim() == name);
+    let username_column = column("username").context("Missing `username` column")?;
+    let emails_column = column("emails");
+    let password_version_column = column("password_version");
+    let password_hash_column = column("password_hash");
+    let admin_column = column("admin");
+    let locked_column = column("locked");
+    let upstream_links_column = column("upstream_links");
+    let display_name_column = column("display_name");
+
+    let mut users = Vec::new();
+    for (index, row) in reader.records().enumerate() {
+        let row = row?;
+        // The header is on the first line
+        let line = index + 2;
+        let get = |column: Option<usize>| column.and_then(|c| row.get(c)).unwrap_or_default();
+
+        let username = get(Some(username_column)).trim().to_owned();
+        let emails = split_list(get(emails_column))
+            .map(ToOwned::to_owned)
+            .collect();
+
+        let password = match (
+            get(password_version_column).trim(),
+            get(password_hash_column).trim(),
+        ) {
+            ("", "") => None,
+            (version, hash) if !version.is_empty() && !hash.is_empty() => Some(ImportedPassword {
+                version: version
+                    .parse()
+                    .with_context(|| format!("Invalid password version on line {line}"))?,
+                hash: hash.to_owned(),
+            }),
+            _ => anyhow::bail!(
+                "Both `password_version` and `password_hash` must be set on line {line}"
+            ),
+        };
+
+        let admin = parse_bool(get(admin_column))
+            .with_context(|| format!("Invalid admin flag on line {line}"))?;
+
+        let locked = parse_bool(get(locked_column))
+            .with_context(|| format!("Invalid locked flag on line {line}"))?;
+
+        let upstream_links = split_list(get(upstream_links_column))
+            .map(|link| {
+                let (provider_id, subject) = link
+                    .split_once(':')
+                    .context("Upstream links must be in the `provider_id:subject` format")?;
+                Ok(ImportedUpstreamLink {
+                    provider_id: provider_id
+                        .parse()
+                        .context("Invalid upstream provider ID")?,
+                    subject: subject.to_owned(),
+                    human_account_name: None,
+                })
+            })
+            .collect::<anyhow::Result<_>>()
+            .with_context(|| format!("Invalid upstream link on line {line}"))?;
+
+        let display_name = Some(get(display_name_column).trim())
+            .filter(|d| !d.is_empty())
+            .map(ToOwned::to_owned);
+
+        users.push(UserImportRecord {
+            username,
+            emails,
+            password,
+            admin,
+            locked,
+            upstream_links,
+            display_name,
+        });
+    }
+
+    Ok(users)
+}
+
+/// Writes users to a file, in the given format
+pub enum UserWriter<W: Write> {
+    Jsonl(W),
+    Csv(Box<csv::Writer<W>>),
+}
+
+impl<W: Write> UserWriter<W> {
+    pub fn new(writer: W, format: UserFileFormat) -> anyhow::Result<Self> {
+        match format {
+            UserFileFormat::Jsonl => Ok(Self::Jsonl(writer)),
+            UserFileFormat::Csv => {
+                let mut writer = csv::Writer::from_writer(writer);
+                writer.write_record(CSV_HEADERS)?;
+                Ok(Self::Csv(Box::new(writer)))
+            }
+        }
+    }
+
+    pub fn write(&mut self, user: &UserImportRecord) -> anyhow::Result<()> {
+        match self {
+            Self::Jsonl(writer) => {
+                serde_json::to_writer(&mut *writer, user)?;
+                writer.write_all(b"\n")?;
+            }
+            Self::Csv(writer) => {
+                let upstream_links: Vec<String> = user
+                    .upstream_links
+                    .iter()
+                    .map(|link| format!("{}:{}", link.provider_id, link.subject))
+                    .collect();
+
+                writer.write_record([
+                    user.username.as_str(),
+                    &user.emails.join(";"),
+                    &user
+                        .password
+                        .as_ref()
+                        .map(|p| p.version.to_string())
+                        .unwrap_or_default(),
+                    user.password.as_ref().map_or("", |p| p.hash.as_str()),
+                    if user.admin { "true" } else { "false" },
+                    if user.locked { "true" } else { "false" },
+                    &upstream_links.join(";"),
+                    user.display_name.as_deref().unwrap_or_default(),
+                ])?;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn finish(self) -> anyhow::Result<()> {
+        match self {
+            Self::Jsonl(mut writer) => writer.flush()?,
+            Self::Csv(mut writer) => writer.flush()?,
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roundtrip() {
+        let users = vec![
+            UserImportRecord {
+                username: "alice".to_owned(),
+                emails: vec!["alice@example.com".to_owned(), "a@example.com".to_owned()],
+                password: Some(ImportedPassword {
+                    version: 1,
+                    hash: "$2b$12$abcdef".to_owned(),
+                }),
+                admin: true,
+                locked: false,
+                upstream_links: vec![ImportedUpstreamLink {
+                    provider_id: "01FSHN9AG0MZAA6S4AF7CTV32E".parse().unwrap(),
+                    subject: "alice:subject".to_owned(),
+                    human_account_name: None,
+                }],
+                display_name: Some("Alice, of Wonderland".to_owned()),
+            },
+            UserImportRecord {
+                username: "bob".to_owned(),
+                emails: Vec::new(),
+                password: None,
+                admin: false,
+                locked: true,
+                upstream_links: Vec::new(),
+                display_name: None,
+            },
+        ];
+
+        for format in [UserFileFormat::Jsonl, UserFileFormat::Csv] {
+            let mut buffer = Vec::new();
+            let mut writer = UserWriter::new(&mut buffer, format).unwrap();
+            for user in &users {
+                writer.write(user).unwrap();
+            }
+            writer.finish().unwrap();
+
+            assert_eq!(read_users(&buffer[..], format).unwrap(), users);
+        }
+    }
+
+    #[test]
+    fn test_csv_partial_columns() {
+        let input = "username,admin\nalice,yes\nbob,\n";
+        let users = read_users(input.as_bytes(), UserFileFormat::Csv).unwrap();
+        assert_eq!(users.len(), 2);
+        assert_eq!(users[0].username, "alice");
+        assert!(users[0].admin);
+        assert!(!users[1].admin);
+
+        let input = "username,password_hash\nalice,$2b$12$abcdef\n";
+        assert!(read_users(input.as_bytes(), UserFileFormat::Csv).is_err());
+    }
+}
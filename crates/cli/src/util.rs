@@ -7,15 +7,23 @@
 use std::{sync::Arc, time::Duration};
 
 use anyhow::Context;
+use arc_swap::ArcSwap;
 use mas_config::{
     AccountConfig, BrandingConfig, CaptchaConfig, DatabaseConfig, EmailConfig, EmailSmtpMode,
-    EmailTransportKind, ExperimentalConfig, HomeserverKind, MatrixConfig, PasswordsConfig,
-    PolicyConfig, TemplatesConfig,
+    EmailTransportKind, ExperimentalConfig, HomeserverKind, KeyRotationConfig, MatrixConfig,
+    PasswordsConfig, PolicyConfig, TemplatesConfig,
 };
 use mas_context::LogContext;
-use mas_data_model::{SessionExpirationConfig, SessionLimitConfig, SiteConfig};
+use mas_data_model::{
+    CompatTokenAnomalyDetectionConfig, JwtAccessTokensConfig, SessionExpirationConfig,
+    SessionLimitConfig, SigningKeyRotationConfig, SigningKeyType, SiteConfig,
+};
 use mas_email::{MailTransport, Mailer};
 use mas_handlers::passwords::PasswordManager;
+use mas_iana::jose::JsonWebKeyUse;
+use mas_jose::jwk::{JsonWebKey, JsonWebKeySet};
+use mas_keystore::{Encrypter, Keystore, PrivateKey};
+use mas_ldap::{AttributeMapping, BindMethod, Directory, LdapDirectory};
 use mas_matrix::{HomeserverConnection, ReadOnlyHomeserverConnection};
 use mas_matrix_synapse::{LegacySynapseConnection, SynapseConnection};
 use mas_policy::PolicyFactory;
@@ -32,7 +40,8 @@ use tracing::{Instrument, log::LevelFilter};
 pub async fn password_manager_from_config(
     config: &PasswordsConfig,
 ) -> Result<PasswordManager, anyhow::Error> {
-    if !config.enabled() {
+    // With the LDAP backend, no password hashes are stored locally
+    if !config.enabled() || config.ldap_enabled() {
         return Ok(PasswordManager::disabled());
     }
 
@@ -58,6 +67,47 @@ pub async fn password_manager_from_config(
     PasswordManager::new(config.minimum_complexity(), schemes)
 }
 
+pub async fn directory_from_config(
+    config: &PasswordsConfig,
+) -> Result<Option<Arc<dyn Directory>>, anyhow::Error> {
+    if !config.ldap_enabled() {
+        return Ok(None);
+    }
+
+    let ldap = config
+        .ldap
+        .as_ref()
+        .context("The LDAP password backend requires the `passwords.ldap` section")?;
+
+    let bind_method = match (&ldap.user_dn_template, &ldap.search_base) {
+        (Some(dn_template), None) => BindMethod::Direct {
+            dn_template: dn_template.clone(),
+        },
+        (None, Some(base_dn)) => BindMethod::SearchAndBind {
+            base_dn: base_dn.clone(),
+            filter: ldap.search_filter.clone(),
+            bind_dn: ldap.bind_dn.clone(),
+            bind_password: ldap
+                .bind_password()
+                .await
+                .context("Failed to load the LDAP bind password")?,
+        },
+        _ => anyhow::bail!("Exactly one of `user_dn_template` or `search_base` must be set"),
+    };
+
+    let attributes = AttributeMapping {
+        localpart: ldap.attributes.localpart.clone(),
+        display_name: ldap.attributes.display_name.clone(),
+        email: ldap.attributes.email.clone(),
+    };
+
+    let directory = LdapDirectory::new(ldap.url.as_str(), bind_method, attributes)
+        .with_starttls(ldap.starttls)
+        .with_timeout(ldap.timeout);
+
+    Ok(Some(Arc::new(directory)))
+}
+
 pub fn mailer_from_config(
     config: &EmailConfig,
     templates: &Templates,
@@ -147,6 +197,8 @@ pub async fn policy_factory_from_config(
         authorization_grant: config.authorization_grant_entrypoint.clone(),
         compat_login: config.compat_login_entrypoint.clone(),
         email: config.email_entrypoint.clone(),
+        upstream_oauth_link: config.upstream_oauth_link_entrypoint.clone(),
+        compat_token_anomaly: config.compat_token_anomaly_entrypoint.clone(),
     };
 
     let session_limit_config =
@@ -201,6 +253,7 @@ pub fn site_config_from_config(
     password_config: &PasswordsConfig,
     account_config: &AccountConfig,
     captcha_config: &CaptchaConfig,
+    key_rotation_config: Option<&KeyRotationConfig>,
 ) -> Result<SiteConfig, anyhow::Error> {
     let captcha = captcha_config_from_config(captcha_config)?;
     let session_expiration = experimental_config
@@ -220,15 +273,23 @@ pub fn site_config_from_config(
         tos_uri: branding_config.tos_uri.clone(),
         imprint: branding_config.imprint.clone(),
         password_login_enabled: password_config.enabled(),
+        ldap_adopt_existing_users: password_config.ldap_enabled()
+            && password_config
+                .ldap
+                .as_ref()
+                .is_some_and(|ldap| ldap.adopt_existing_users),
         password_registration_enabled: password_config.enabled()
+            && !password_config.ldap_enabled()
             && account_config.password_registration_enabled,
         password_registration_email_required: account_config.password_registration_email_required,
         registration_token_required: account_config.registration_token_required,
         email_change_allowed: account_config.email_change_allowed,
         displayname_change_allowed: account_config.displayname_change_allowed,
         password_change_allowed: password_config.enabled()
+            && !password_config.ldap_enabled()
             && account_config.password_change_allowed,
         account_recovery_allowed: password_config.enabled()
+            && !password_config.ldap_enabled()
             && account_config.password_recovery_enabled,
         account_deactivation_allowed: account_config.account_deactivation_allowed,
         captcha,
@@ -243,6 +304,38 @@ pub fn site_config_from_config(
                 soft_limit: c.soft_limit,
                 hard_limit: c.hard_limit,
             }),
+        signing_key_rotation: key_rotation_config.map(|c| SigningKeyRotationConfig {
+            key_types: c
+                .key_types
+                .iter()
+                .map(|key_type| match key_type {
+                    mas_config::SigningKeyType::Rsa => SigningKeyType::Rsa,
+                    mas_config::SigningKeyType::EcP256 => SigningKeyType::EcP256,
+                    mas_config::SigningKeyType::EcP384 => SigningKeyType::EcP384,
+                    mas_config::SigningKeyType::EcK256 => SigningKeyType::EcK256,
+                    mas_config::SigningKeyType::Ed25519 => SigningKeyType::Ed25519,
+                })
+                .collect(),
+            rotation_interval: c.rotation_interval,
+            publication_period: c.publication_period,
+            retention_period: c.retention_period,
+        }),
+        jwt_access_tokens: experimental_config.jwt_access_tokens.as_ref().map(|c| {
+            JwtAccessTokensConfig {
+                clients: c.clients.clone(),
+                scopes: c.scopes.clone(),
+                audience: c.audience.clone(),
+            }
+        }),
+        revoke_session_on_refresh_token_replay: experimental_config
+            .revoke_session_on_refresh_token_replay,
+        compat_token_anomaly_detection: experimental_config
+            .compat_token_anomaly_detection
+            .as_ref()
+            .map(|c| CompatTokenAnomalyDetectionConfig {
+                ipv4_prefix_length: c.ipv4_prefix_length,
+                ipv6_prefix_length: c.ipv6_prefix_length,
+            }),
     })
 }
 
@@ -484,6 +577,98 @@ pub async fn load_policy_factory_dynamic_data(
     Ok(())
 }
 
+/// Load the signing keys managed by the service from the database on top of
+/// the keys from the configuration, and reload them periodically in the
+/// background
+pub async fn load_signing_keys_continuously(
+    key_store: &Arc<ArcSwap<Keystore>>,
+    config_key_store: Keystore,
+    encrypter: Encrypter,
+    repository_factory: BoxRepositoryFactory,
+    cancellation_token: CancellationToken,
+    task_tracker: &TaskTracker,
+) -> Result<(), anyhow::Error> {
+    let key_store = key_store.clone();
+
+    let keys = load_signing_keys(&config_key_store, &encrypter, &*repository_factory).await?;
+    key_store.store(Arc::new(keys));
+
+    task_tracker.spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_mins(1));
+
+        loop {
+            tokio::select! {
+                () = cancellation_token.cancelled() => {
+                    return;
+                }
+                _ = interval.tick() => {}
+            }
+
+            // Keep the previous keys on failure, they will be reloaded on the next tick
+            match load_signing_keys(&config_key_store, &encrypter, &*repository_factory).await {
+                Ok(keys) => key_store.store(Arc::new(keys)),
+                Err(err) => {
+                    tracing::error!(
+                        error = ?err,
+                        "Failed to load the signing keys from the database"
+                    );
+                }
+            }
+        }
+    });
+
+    Ok(())
+}
+
+/// Build a [`Keystore`] out of the keys from the configuration and the signing
+/// keys stored in the database.
+///
+/// Active keys from the database are preferred for signing over the ones from
+/// the configuration, while pending and retired keys are only published for
+/// verification.
+#[tracing::instrument(name = "keystore.load_signing_keys", skip_all)]
+pub async fn load_signing_keys(
+    config_key_store: &Keystore,
+    encrypter: &Encrypter,
+    repository_factory: &(dyn RepositoryFactory + Send + Sync),
+) -> Result<Keystore, anyhow::Error> {
+    let mut repo = repository_factory
+        .create()
+        .await
+        .context("Failed to acquire database connection")?;
+
+    let signing_keys = repo.signing_key().all().await?;
+
+    let mut keys = Vec::new();
+    let mut verification_keys = Vec::new();
+    // Iterate from the most recent key, so that the latest active key of each type
+    // is preferred
+    for signing_key in signing_keys.into_iter().rev() {
+        let der = encrypter
+            .decrypt_string(&signing_key.encrypted_key)
+            .with_context(|| format!("Failed to decrypt signing key {}", signing_key.id))?;
+        let private_key = PrivateKey::load_der(&der)
+            .with_context(|| format!("Failed to load signing key {}", signing_key.id))?;
+        let jwk = JsonWebKey::new(private_key)
+            .with_kid(signing_key.kid.clone())
+            .with_use(JsonWebKeyUse::Sig);
+
+        if signing_key.is_active() {
+            keys.push(jwk);
+        } else {
+            verification_keys.push(jwk);
+        }
+    }
+
+    keys.extend(config_key_store.iter().cloned());
+    verification_keys.extend(config_key_store.verification_keys().iter().cloned());
+
+    Ok(Keystore::with_verification_keys(
+        JsonWebKeySet::new(keys),
+        JsonWebKeySet::new(verification_keys),
+    ))
+}
+
 /// Create a clonable, type-erased [`HomeserverConnection`] from the
 /// configuration
 pub async fn homeserver_connection_from_config(
@@ -10,6 +10,7 @@ use chrono::Duration;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use serde_with::serde_as;
+use ulid::Ulid;
 
 use crate::ConfigurationSection;
 
@@ -47,6 +48,58 @@ pub struct InactiveSessionExpirationConfig {
     pub expire_user_sessions: bool,
 }
 
+/// Configuration options for the JWT access tokens feature
+#[derive(Clone, Debug, Default, Deserialize, JsonSchema, Serialize)]
+pub struct JwtAccessTokensConfig {
+    /// The IDs of the clients which get signed JWT access tokens
+    #[schemars(with = "Vec<String>")]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub clients: Vec<Ulid>,
+
+    /// Sessions requesting any of those scopes get signed JWT access tokens
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub scopes: Vec<String>,
+
+    /// The audience (`aud` claim) of the JWT access tokens. Defaults to the
+    /// client ID
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub audience: Option<String>,
+}
+
+fn default_ipv4_prefix_length() -> u8 {
+    16
+}
+
+fn default_ipv6_prefix_length() -> u8 {
+    48
+}
+
+/// Configuration options for the compatibility access token anomaly detection
+/// feature
+#[derive(Clone, Debug, Deserialize, JsonSchema, Serialize)]
+pub struct CompatTokenAnomalyDetectionConfig {
+    /// Length of the prefix an IPv4 address must share with the one the
+    /// session was last active from. Defaults to 16.
+    #[schemars(range(min = 0, max = 32))]
+    #[serde(default = "default_ipv4_prefix_length")]
+    pub ipv4_prefix_length: u8,
+
+    /// Length of the prefix an IPv6 address must share with the one the
+    /// session was last active from. Defaults to 48.
+    #[schemars(range(min = 0, max = 128))]
+    #[serde(default = "default_ipv6_prefix_length")]
+    pub ipv6_prefix_length: u8,
+}
+
+impl Default for CompatTokenAnomalyDetectionConfig {
+    fn default() -> Self {
+        Self {
+            ipv4_prefix_length: default_ipv4_prefix_length(),
+            ipv6_prefix_length: default_ipv6_prefix_length(),
+        }
+    }
+}
+
 /// Configuration sections for experimental options
 ///
 /// Do not change these options unless you know what you are doing.
@@ -90,6 +143,53 @@ pub struct ExperimentalConfig {
     /// Disabled by default.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub session_limit: Option<SessionLimitConfig>,
+
+    /// Experimental feature to issue signed JWT access tokens, as per RFC
+    /// 9068, to some clients or for some scopes. Those can be validated
+    /// offline by resource servers, but remain revocable for the ones which
+    /// introspect them.
+    ///
+    /// Disabled by default.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub jwt_access_tokens: Option<JwtAccessTokensConfig>,
+
+    /// Experimental feature to finish the whole session when a refresh token
+    /// is replayed, revoking all its access and refresh tokens. Replays are
+    /// always logged and counted, regardless of this setting.
+    ///
+    /// Disabled by default.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub revoke_session_on_refresh_token_replay: bool,
+
+    /// Experimental feature to cache positive introspection results of access
+    /// tokens in memory for the given number of seconds. Tokens revoked
+    /// through the OAuth 2.0 revocation endpoint, token refreshes and the
+    /// compatibility logout endpoints of this instance are invalidated right
+    /// away. Other revocations, like the ones done through the admin API, the
+    /// user interface, background jobs or other instances, may only be seen by
+    /// resource servers once the cached result expires.
+    ///
+    /// Disabled by default.
+    #[schemars(with = "Option<u64>", range(min = 1, max = 300))]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[serde_as(as = "Option<serde_with::DurationSeconds<i64>>")]
+    pub introspection_cache_ttl: Option<Duration>,
+
+    /// Experimental feature to detect compatibility access tokens being used
+    /// from a different network or client than the one their session was
+    /// last active from. Anomalies are recorded and visible in the admin API,
+    /// and the `compat_token_anomaly` policy decides whether the token gets
+    /// rejected.
+    ///
+    /// The homeserver must give the IP address and user agent of the client
+    /// using the token in the `X-MAS-Client-IP` and `X-MAS-Client-User-Agent`
+    /// headers when introspecting it. While this is enabled, introspection
+    /// requests for compatibility access tokens without the `X-MAS-Client-IP`
+    /// header are refused, so only enable it once the homeserver sends them.
+    ///
+    /// Disabled by default.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub compat_token_anomaly_detection: Option<CompatTokenAnomalyDetectionConfig>,
 }
 
 impl Default for ExperimentalConfig {
@@ -100,6 +200,10 @@ impl Default for ExperimentalConfig {
             inactive_session_expiration: None,
             plan_management_iframe_uri: None,
             session_limit: None,
+            jwt_access_tokens: None,
+            revoke_session_on_refresh_token_replay: false,
+            introspection_cache_ttl: None,
+            compat_token_anomaly_detection: None,
         }
     }
 }
@@ -111,6 +215,10 @@ impl ExperimentalConfig {
             && self.inactive_session_expiration.is_none()
             && self.plan_management_iframe_uri.is_none()
             && self.session_limit.is_none()
+            && self.jwt_access_tokens.is_none()
+            && !self.revoke_session_on_refresh_token_replay
+            && self.introspection_cache_ttl.is_none()
+            && self.compat_token_anomaly_detection.is_none()
     }
 }
 
@@ -34,30 +34,38 @@ pub use self::{
     clients::{ClientAuthMethodConfig, ClientConfig, ClientsConfig},
     database::{DatabaseConfig, PgSslMode},
     email::{EmailConfig, EmailSmtpMode, EmailTransportKind},
-    experimental::ExperimentalConfig,
+    experimental::{CompatTokenAnomalyDetectionConfig, ExperimentalConfig, JwtAccessTokensConfig},
     http::{
         BindConfig as HttpBindConfig, HttpConfig, ListenerConfig as HttpListenerConfig,
         Resource as HttpResource, TlsConfig as HttpTlsConfig, UnixOrTcp,
     },
     matrix::{HomeserverKind, MatrixConfig},
     passwords::{
-        Algorithm as PasswordAlgorithm, HashingScheme as PasswordHashingScheme, PasswordsConfig,
+        Algorithm as PasswordAlgorithm, HashingScheme as PasswordHashingScheme,
+        LdapAttributesConfig, LdapConfig, PasswordBackend, PasswordsConfig,
     },
     policy::PolicyConfig,
     rate_limiting::RateLimitingConfig,
-    secrets::SecretsConfig,
+    secrets::{
+        EncryptionKeyConfig, KeyConfig, KeyRotationConfig, Pkcs11Config, Pkcs11KeyConfig,
+        SecretsConfig, SigningKeyType,
+    },
     telemetry::{
         MetricsConfig, MetricsExporterKind, Propagator, TelemetryConfig, TracingConfig,
         TracingExporterKind,
     },
     templates::TemplatesConfig,
     upstream_oauth2::{
-        ClaimsImports as UpstreamOAuth2ClaimsImports, DiscoveryMode as UpstreamOAuth2DiscoveryMode,
+        ClaimsImports as UpstreamOAuth2ClaimsImports,
+        ClientCertificate as UpstreamOAuth2ClientCertificate,
+        DiscoveryMode as UpstreamOAuth2DiscoveryMode,
         EmailImportPreference as UpstreamOAuth2EmailImportPreference,
         ImportAction as UpstreamOAuth2ImportAction,
         OnBackchannelLogout as UpstreamOAuth2OnBackchannelLogout,
         OnConflict as UpstreamOAuth2OnConflict, PkceMethod as UpstreamOAuth2PkceMethod,
         Provider as UpstreamOAuth2Provider, ResponseMode as UpstreamOAuth2ResponseMode,
+        ResyncPreference as UpstreamOAuth2ResyncPreference,
+        SamlBinding as UpstreamOAuth2SamlBinding, SamlProvider as UpstreamOAuth2SamlProvider,
         TokenAuthMethod as UpstreamOAuth2TokenAuthMethod, UpstreamOAuth2Config,
     },
 };
@@ -4,12 +4,14 @@
 // SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
 // Please see LICENSE files in the repository root for full details.
 
-use std::cmp::Reverse;
+use std::{cmp::Reverse, time::Duration};
 
 use anyhow::bail;
 use camino::Utf8PathBuf;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
+use url::Url;
 
 use crate::ConfigurationSection;
 
@@ -57,6 +59,15 @@ pub struct PasswordsConfig {
     /// - 4: any more than that
     #[serde(default = "default_minimum_complexity")]
     minimum_complexity: u8,
+
+    /// Where passwords are checked. Defaults to `local`, which checks them
+    /// against the hashes stored in the database.
+    #[serde(default, skip_serializing_if = "PasswordBackend::is_default")]
+    pub backend: PasswordBackend,
+
+    /// Settings of the LDAP backend, required if `backend` is `ldap`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ldap: Option<LdapConfig>,
 }
 
 impl Default for PasswordsConfig {
@@ -65,6 +76,8 @@ impl Default for PasswordsConfig {
             enabled: default_enabled(),
             schemes: default_schemes(),
             minimum_complexity: default_minimum_complexity(),
+            backend: PasswordBackend::default(),
+            ldap: None,
         }
     }
 }
@@ -88,6 +101,19 @@ impl ConfigurationSection for PasswordsConfig {
             return Ok(());
         }
 
+        if self.backend == PasswordBackend::Ldap {
+            let Some(ldap) = &self.ldap else {
+                return Err(annotate(figment::Error::from(
+                    "The `ldap` section is required when the backend is `ldap`".to_owned(),
+                ))
+                .into());
+            };
+
+            return ldap
+                .validate()
+                .map_err(|e| annotate(figment::Error::from(e.to_owned())).into());
+        }
+
         if self.schemes.is_empty() {
             return Err(annotate(figment::Error::from(
                 "Requires at least one password scheme in the config".to_owned(),
@@ -115,6 +141,13 @@ impl PasswordsConfig {
         self.enabled
     }
 
+    /// Whether passwords are checked against an LDAP directory instead of
+    /// local hashes
+    #[must_use]
+    pub fn ldap_enabled(&self) -> bool {
+        self.enabled && self.backend == PasswordBackend::Ldap
+    }
+
     /// Minimum complexity of passwords, from 0 to 4, according to the zxcvbn
     /// scorer.
     #[must_use]
@@ -175,6 +208,180 @@ const fn is_default_false(value: &bool) -> bool {
     !*value
 }
 
+/// Where passwords are checked
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum PasswordBackend {
+    /// Check passwords against the hashes stored in the database
+    #[default]
+    Local,
+
+    /// Check passwords against an LDAP directory, like `OpenLDAP` or Active
+    /// Directory. Users are provisioned on their first login.
+    Ldap,
+}
+
+impl PasswordBackend {
+    #[allow(clippy::trivially_copy_pass_by_ref)]
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+fn default_ldap_timeout() -> Duration {
+    Duration::from_secs(10)
+}
+
+fn default_ldap_search_filter() -> String {
+    "(uid={username})".to_owned()
+}
+
+fn default_ldap_localpart_attribute() -> String {
+    "uid".to_owned()
+}
+
+/// Which attributes of the directory entries are mapped to the user profile
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct LdapAttributesConfig {
+    /// The attribute holding the localpart of the user. Defaults to `uid`;
+    /// Active Directory deployments usually want `sAMAccountName`.
+    #[serde(default = "default_ldap_localpart_attribute")]
+    pub localpart: String,
+
+    /// The attribute holding the display name of the user, like `cn` or
+    /// `displayName`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+
+    /// The attribute holding the email address of the user, like `mail`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+}
+
+impl Default for LdapAttributesConfig {
+    fn default() -> Self {
+        Self {
+            localpart: default_ldap_localpart_attribute(),
+            display_name: None,
+            email: None,
+        }
+    }
+}
+
+/// Settings of the LDAP password backend
+///
+/// The entry of a user is found either by building its DN from
+/// `user_dn_template`, or by searching for it under `search_base`. In both
+/// cases, the password is checked by binding as the user.
+#[serde_as]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct LdapConfig {
+    /// The URL of the server, with the `ldap` or `ldaps` scheme
+    pub url: Url,
+
+    /// Whether to upgrade `ldap` connections to TLS with `StartTLS`
+    #[serde(default, skip_serializing_if = "is_default_false")]
+    pub starttls: bool,
+
+    /// The timeout of connections and operations, in seconds
+    #[schemars(with = "u64")]
+    #[serde(default = "default_ldap_timeout")]
+    #[serde_as(as = "serde_with::DurationSeconds<u64>")]
+    pub timeout: Duration,
+
+    /// The template of the DN of users, for a direct bind. `{username}` is
+    /// replaced by the username typed by the user.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_dn_template: Option<String>,
+
+    /// The DN under which to search for users, for a search and bind
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub search_base: Option<String>,
+
+    /// The filter used to search for users. `{username}` is replaced by the
+    /// username typed by the user. Defaults to `(uid={username})`.
+    #[serde(default = "default_ldap_search_filter")]
+    pub search_filter: String,
+
+    /// The DN to bind as to search for users. Searches are anonymous if not
+    /// set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bind_dn: Option<String>,
+
+    /// The password of `bind_dn`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bind_password: Option<String>,
+
+    /// Same as `bind_password`, but read from a file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schemars(with = "Option<String>")]
+    pub bind_password_file: Option<Utf8PathBuf>,
+
+    /// Which attributes are mapped to the user profile
+    #[serde(default)]
+    pub attributes: LdapAttributesConfig,
+
+    /// Whether a directory login can sign into an existing user with the same
+    /// username, which was not provisioned from the directory. The user is
+    /// then linked to the directory entry.
+    ///
+    /// This lets anyone controlling a directory entry take over the local user
+    /// with the same username, so it should only be enabled when migrating
+    /// users which are known to match the directory. Defaults to `false`.
+    #[serde(default, skip_serializing_if = "is_default_false")]
+    pub adopt_existing_users: bool,
+}
+
+impl LdapConfig {
+    fn validate(&self) -> Result<(), &'static str> {
+        if !matches!(self.url.scheme(), "ldap" | "ldaps") {
+            return Err("The LDAP URL must use the `ldap` or `ldaps` scheme");
+        }
+
+        if self.starttls && self.url.scheme() == "ldaps" {
+            return Err("`starttls` can't be used with an `ldaps` URL");
+        }
+
+        match (&self.user_dn_template, &self.search_base) {
+            (Some(_), Some(_)) => {
+                return Err("Only one of `user_dn_template` and `search_base` can be set");
+            }
+            (None, None) => {
+                return Err("One of `user_dn_template` or `search_base` must be set");
+            }
+            (Some(template), None) if !template.contains("{username}") => {
+                return Err("`user_dn_template` must contain the `{username}` placeholder");
+            }
+            (None, Some(_)) if !self.search_filter.contains("{username}") => {
+                return Err("`search_filter` must contain the `{username}` placeholder");
+            }
+            _ => {}
+        }
+
+        if self.bind_password.is_some() && self.bind_password_file.is_some() {
+            return Err("Cannot specify both `bind_password` and `bind_password_file`");
+        }
+
+        Ok(())
+    }
+
+    /// Get the password of the search account
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the password file could not be read
+    pub async fn bind_password(&self) -> anyhow::Result<Option<String>> {
+        Ok(match (&self.bind_password, &self.bind_password_file) {
+            (Some(password), None) => Some(password.clone()),
+            (None, Some(path)) => Some(tokio::fs::read_to_string(path).await?),
+            (Some(_), Some(_)) => {
+                bail!("Cannot specify both `bind_password` and `bind_password_file`")
+            }
+            (None, None) => None,
+        })
+    }
+}
+
 /// Parameters for a password hashing scheme
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct HashingScheme {
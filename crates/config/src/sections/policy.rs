@@ -78,6 +78,22 @@ fn is_default_email_entrypoint(value: &String) -> bool {
     *value == default_email_entrypoint()
 }
 
+fn default_upstream_oauth_link_entrypoint() -> String {
+    "upstream_oauth_link/violation".to_owned()
+}
+
+fn is_default_upstream_oauth_link_entrypoint(value: &String) -> bool {
+    *value == default_upstream_oauth_link_entrypoint()
+}
+
+fn default_compat_token_anomaly_entrypoint() -> String {
+    "compat_token_anomaly/violation".to_owned()
+}
+
+fn is_default_compat_token_anomaly_entrypoint(value: &String) -> bool {
+    *value == default_compat_token_anomaly_entrypoint()
+}
+
 fn default_data() -> serde_json::Value {
     serde_json::json!({})
 }
@@ -140,6 +156,25 @@ pub struct PolicyConfig {
     )]
     pub email_entrypoint: String,
 
+    /// Entrypoint to use when linking or unlinking an upstream provider.
+    ///
+    /// Optional: if the policy doesn't have it, linking is always allowed.
+    #[serde(
+        default = "default_upstream_oauth_link_entrypoint",
+        skip_serializing_if = "is_default_upstream_oauth_link_entrypoint"
+    )]
+    pub upstream_oauth_link_entrypoint: String,
+
+    /// Entrypoint to use when a compatibility access token is used from an
+    /// unusual network or client.
+    ///
+    /// Optional: if the policy doesn't have it, tokens are never rejected.
+    #[serde(
+        default = "default_compat_token_anomaly_entrypoint",
+        skip_serializing_if = "is_default_compat_token_anomaly_entrypoint"
+    )]
+    pub compat_token_anomaly_entrypoint: String,
+
     /// Arbitrary data to pass to the policy
     #[serde(default = "default_data", skip_serializing_if = "is_default_data")]
     pub data: serde_json::Value,
@@ -155,6 +190,8 @@ impl Default for PolicyConfig {
             compat_login_entrypoint: default_compat_login_entrypoint(),
             password_entrypoint: default_password_entrypoint(),
             email_entrypoint: default_email_entrypoint(),
+            upstream_oauth_link_entrypoint: default_upstream_oauth_link_entrypoint(),
+            compat_token_anomaly_entrypoint: default_compat_token_anomaly_entrypoint(),
             data: default_data(),
         }
     }
@@ -169,6 +206,8 @@ impl PolicyConfig {
             && is_default_authorization_grant_entrypoint(&self.authorization_grant_entrypoint)
             && is_default_password_entrypoint(&self.password_entrypoint)
             && is_default_email_entrypoint(&self.email_entrypoint)
+            && is_default_upstream_oauth_link_entrypoint(&self.upstream_oauth_link_entrypoint)
+            && is_default_compat_token_anomaly_entrypoint(&self.compat_token_anomaly_entrypoint)
             && is_default_data(&self.data)
     }
 }
@@ -8,9 +8,10 @@ use std::borrow::Cow;
 
 use anyhow::{Context, bail};
 use camino::Utf8PathBuf;
+use chrono::Duration;
 use futures_util::future::{try_join, try_join_all};
 use mas_jose::jwk::{JsonWebKey, JsonWebKeySet, Thumbprint};
-use mas_keystore::{Encrypter, Keystore, PrivateKey};
+use mas_keystore::{Encrypter, Keystore, Pkcs11Token, PrivateKey};
 use rand::{Rng, SeedableRng, distributions::Standard, prelude::Distribution as _};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
@@ -166,7 +167,9 @@ impl KeyConfig {
     /// Returns the JSON Web Key derived from this key config.
     ///
     /// Password and/or key are read from file if they’re given as path.
-    async fn json_web_key(&self) -> anyhow::Result<JsonWebKey<mas_keystore::PrivateKey>> {
+    pub(crate) async fn json_web_key(
+        &self,
+    ) -> anyhow::Result<JsonWebKey<mas_keystore::PrivateKey>> {
         let (key, password) = try_join(self.key(), self.password()).await?;
 
         let private_key = match password {
@@ -257,6 +260,276 @@ async fn key_configs_from_path(path: &Utf8PathBuf) -> anyhow::Result<Vec<KeyConf
     Ok(result)
 }
 
+/// Encryption key fields as serialized in JSON.
+#[serde_as]
+#[derive(JsonSchema, Serialize, Deserialize, Debug, Clone)]
+struct EncryptionKeyRaw {
+    /// File containing the encryption key.
+    #[schemars(with = "Option<String>")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    key_file: Option<Utf8PathBuf>,
+
+    /// Encryption key, as 64 hex characters.
+    #[schemars(
+        with = "Option<String>",
+        regex(pattern = r"[0-9a-fA-F]{64}"),
+        example = &"0000111122223333444455556666777788889999aaaabbbbccccddddeeeeffff"
+    )]
+    #[serde_as(as = "Option<serde_with::hex::Hex>")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    key: Option<[u8; 32]>,
+}
+
+impl TryFrom<EncryptionKeyRaw> for Encryption {
+    type Error = anyhow::Error;
+
+    fn try_from(value: EncryptionKeyRaw) -> Result<Encryption, Self::Error> {
+        match (value.key, value.key_file) {
+            (None, None) => bail!("Missing `key` or `key_file`"),
+            (None, Some(path)) => Ok(Encryption::File(path)),
+            (Some(key), None) => Ok(Encryption::Value(key)),
+            (Some(_), Some(_)) => bail!("Cannot specify both `key` and `key_file`"),
+        }
+    }
+}
+
+impl From<Encryption> for EncryptionKeyRaw {
+    fn from(value: Encryption) -> Self {
+        match value {
+            Encryption::File(path) => EncryptionKeyRaw {
+                key_file: Some(path),
+                key: None,
+            },
+            Encryption::Value(key) => EncryptionKeyRaw {
+                key_file: None,
+                key: Some(key),
+            },
+        }
+    }
+}
+
+/// A versioned key used to encrypt data stored in the database
+#[serde_as]
+#[derive(JsonSchema, Serialize, Deserialize, Clone, Debug)]
+pub struct EncryptionKeyConfig {
+    /// Identifier of the key, stored alongside the data it encrypted. It must
+    /// not contain `:`, and must stay stable as long as data encrypted with
+    /// this key is stored.
+    pub id: String,
+
+    #[schemars(with = "EncryptionKeyRaw")]
+    #[serde_as(as = "serde_with::TryFromInto<EncryptionKeyRaw>")]
+    #[serde(flatten)]
+    key: Encryption,
+}
+
+impl EncryptionKeyConfig {
+    /// Returns the encryption key.
+    ///
+    /// If `key_file` was given, the key is read from that file.
+    async fn key(&self) -> anyhow::Result<[u8; 32]> {
+        match self.key {
+            Encryption::Value(key) => Ok(key),
+            Encryption::File(ref path) => {
+                let mut bytes = [0; 32];
+                let content = tokio::fs::read(path).await?;
+                hex::decode_to_slice(content, &mut bytes).context(
+                    "Content of `key_file` must contain hex characters encoding exactly 32 bytes",
+                )?;
+                Ok(bytes)
+            }
+        }
+    }
+}
+
+/// PIN fields as serialized in JSON.
+#[derive(JsonSchema, Serialize, Deserialize, Clone, Debug)]
+struct PinRaw {
+    #[schemars(with = "Option<String>")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pin_file: Option<Utf8PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pin: Option<String>,
+}
+
+impl TryFrom<PinRaw> for Password {
+    type Error = anyhow::Error;
+
+    fn try_from(value: PinRaw) -> Result<Self, Self::Error> {
+        match (value.pin, value.pin_file) {
+            (None, None) => bail!("Missing `pin` or `pin_file`"),
+            (None, Some(path)) => Ok(Password::File(path)),
+            (Some(pin), None) => Ok(Password::Value(pin)),
+            (Some(_), Some(_)) => bail!("Cannot specify both `pin` and `pin_file`"),
+        }
+    }
+}
+
+impl From<Password> for PinRaw {
+    fn from(value: Password) -> Self {
+        match value {
+            Password::File(path) => PinRaw {
+                pin_file: Some(path),
+                pin: None,
+            },
+            Password::Value(pin) => PinRaw {
+                pin_file: None,
+                pin: Some(pin),
+            },
+        }
+    }
+}
+
+/// A signing key held in a PKCS#11 token
+#[derive(JsonSchema, Serialize, Deserialize, Clone, Debug)]
+pub struct Pkcs11KeyConfig {
+    /// The key ID `kid` of the key as used by JWKs.
+    ///
+    /// If not given, `kid` will be the key’s RFC 7638 JWK Thumbprint.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    kid: Option<String>,
+
+    /// Label of the key in the token. Both the private and the public key
+    /// objects must have this label.
+    label: String,
+}
+
+/// Signing keys held in a PKCS#11 token, like a hardware security module
+#[serde_as]
+#[derive(JsonSchema, Serialize, Deserialize, Clone, Debug)]
+pub struct Pkcs11Config {
+    /// Path to the PKCS#11 module to load
+    #[schemars(with = "String")]
+    module: Utf8PathBuf,
+
+    /// Label of the token holding the keys
+    token_label: String,
+
+    #[schemars(with = "PinRaw")]
+    #[serde_as(as = "serde_with::TryFromInto<PinRaw>")]
+    #[serde(flatten)]
+    pin: Password,
+
+    /// Keys to load from the token. Only RSA keys and ECDSA keys on the P-256
+    /// and P-384 curves are supported.
+    keys: Vec<Pkcs11KeyConfig>,
+}
+
+impl Pkcs11Config {
+    /// Returns the user PIN of the token.
+    ///
+    /// If `pin_file` was given, the PIN is read from that file.
+    async fn pin(&self) -> anyhow::Result<String> {
+        Ok(match &self.pin {
+            Password::File(path) => tokio::fs::read_to_string(path).await?.trim_end().to_owned(),
+            Password::Value(pin) => pin.clone(),
+        })
+    }
+
+    /// Returns the JSON Web Keys for the keys held in the token.
+    async fn json_web_keys(&self) -> anyhow::Result<Vec<JsonWebKey<PrivateKey>>> {
+        let pin = self.pin().await?;
+        let config = self.clone();
+
+        // Talking to the PKCS#11 module is blocking
+        task::spawn_blocking(move || {
+            let token = Pkcs11Token::open(&config.module, &config.token_label, &pin).with_context(
+                || format!("Failed to open PKCS#11 token {:?}", config.token_label),
+            )?;
+
+            config
+                .keys
+                .iter()
+                .map(|key| {
+                    let private_key =
+                        PrivateKey::Pkcs11(token.load_key(&key.label).with_context(|| {
+                            format!("Failed to load PKCS#11 key {:?}", key.label)
+                        })?);
+
+                    let kid = match key.kid.clone() {
+                        Some(kid) => kid,
+                        None => private_key.thumbprint_sha256_base64(),
+                    };
+
+                    Ok(JsonWebKey::new(private_key)
+                        .with_kid(kid)
+                        .with_use(mas_iana::jose::JsonWebKeyUse::Sig))
+                })
+                .collect()
+        })
+        .await
+        .context("could not join blocking task")?
+    }
+}
+
+/// Type of signing key generated by the automatic key rotation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SigningKeyType {
+    /// 2048-bit RSA key
+    Rsa,
+
+    /// ECDSA key on the P-256 curve
+    EcP256,
+
+    /// ECDSA key on the P-384 curve
+    EcP384,
+
+    /// ECDSA key on the secp256k1 curve
+    EcK256,
+
+    /// `EdDSA` key on the Ed25519 curve
+    Ed25519,
+}
+
+fn default_key_types() -> Vec<SigningKeyType> {
+    vec![SigningKeyType::Rsa]
+}
+
+fn default_rotation_interval() -> Duration {
+    Duration::days(30)
+}
+
+fn default_publication_period() -> Duration {
+    Duration::days(1)
+}
+
+fn default_retention_period() -> Duration {
+    Duration::days(1)
+}
+
+/// Automatic rotation of signing keys stored in the database
+#[serde_as]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct KeyRotationConfig {
+    /// Types of keys to generate and rotate. Defaults to RSA keys only.
+    #[serde(default = "default_key_types")]
+    pub key_types: Vec<SigningKeyType>,
+
+    /// How long a key is used for signing before being replaced, in seconds.
+    /// Defaults to 30 days.
+    #[schemars(with = "u64", range(min = 3600))]
+    #[serde(default = "default_rotation_interval")]
+    #[serde_as(as = "serde_with::DurationSeconds<i64>")]
+    pub rotation_interval: Duration,
+
+    /// How long a new key is published before being used for signing, in
+    /// seconds. This should be longer than the time relying parties cache the
+    /// JWKS. Defaults to 1 day.
+    #[schemars(with = "u64")]
+    #[serde(default = "default_publication_period")]
+    #[serde_as(as = "serde_with::DurationSeconds<i64>")]
+    pub publication_period: Duration,
+
+    /// How long a retired key stays published after it stopped being used for
+    /// signing, in seconds. This should be longer than the lifetime of the
+    /// tokens it signed. Defaults to 1 day.
+    #[schemars(with = "u64")]
+    #[serde(default = "default_retention_period")]
+    #[serde_as(as = "serde_with::DurationSeconds<i64>")]
+    pub retention_period: Duration,
+}
+
 /// Application secrets
 #[serde_as]
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
@@ -267,6 +540,15 @@ pub struct SecretsConfig {
     #[serde(flatten)]
     encryption: Encryption,
 
+    /// Versioned keys used to encrypt data stored in the database.
+    ///
+    /// The first key is used to encrypt new data, the other ones are only used
+    /// to decrypt data encrypted before it became active. Data encrypted
+    /// before versioned keys were set up is still decrypted with the main
+    /// encryption secret.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub encryption_keys: Vec<EncryptionKeyConfig>,
+
     /// List of private keys to use for signing and encrypting payloads.
     #[serde(skip_serializing_if = "Option::is_none")]
     keys: Option<Vec<KeyConfig>>,
@@ -275,6 +557,18 @@ pub struct SecretsConfig {
     #[schemars(with = "Option<String>")]
     #[serde(skip_serializing_if = "Option::is_none")]
     keys_dir: Option<Utf8PathBuf>,
+
+    /// Signing keys held in a PKCS#11 token, like a hardware security module,
+    /// on top of the keys listed here.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pkcs11: Option<Pkcs11Config>,
+
+    /// Automatically generate and rotate signing keys, stored encrypted in the
+    /// database, on top of the keys listed here.
+    ///
+    /// Disabled by default
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key_rotation: Option<KeyRotationConfig>,
 }
 
 impl SecretsConfig {
@@ -286,7 +580,11 @@ impl SecretsConfig {
     #[tracing::instrument(name = "secrets.load", skip_all)]
     pub async fn key_store(&self) -> anyhow::Result<Keystore> {
         let key_configs = self.key_configs().await?;
-        let web_keys = try_join_all(key_configs.iter().map(KeyConfig::json_web_key)).await?;
+        let mut web_keys = try_join_all(key_configs.iter().map(KeyConfig::json_web_key)).await?;
+
+        if let Some(pkcs11) = &self.pkcs11 {
+            web_keys.extend(pkcs11.json_web_keys().await?);
+        }
 
         Ok(Keystore::new(JsonWebKeySet::new(web_keys)))
     }
@@ -297,7 +595,12 @@ impl SecretsConfig {
     ///
     /// Returns an error when the Encryptor can not be created.
     pub async fn encrypter(&self) -> anyhow::Result<Encrypter> {
-        Ok(Encrypter::new(&self.encryption().await?))
+        let mut keys = Vec::with_capacity(self.encryption_keys.len());
+        for key in &self.encryption_keys {
+            keys.push((key.id.clone(), key.key().await?));
+        }
+
+        Ok(Encrypter::new(&self.encryption().await?).with_keys(keys))
     }
 
     /// Returns the encryption secret.
@@ -339,6 +642,74 @@ impl SecretsConfig {
 
 impl ConfigurationSection for SecretsConfig {
     const PATH: Option<&'static str> = Some("secrets");
+
+    fn validate(
+        &self,
+        figment: &figment::Figment,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+        let annotate = |mut error: figment::Error, field: &str| {
+            error.metadata = figment.find_metadata(Self::PATH.unwrap()).cloned();
+            error.profile = Some(figment::Profile::Default);
+            error.path = vec![Self::PATH.unwrap().to_owned(), field.to_owned()];
+            error
+        };
+
+        let mut seen = std::collections::HashSet::new();
+        for key in &self.encryption_keys {
+            if key.id.is_empty() || key.id.contains(':') {
+                return Err(annotate(
+                    figment::Error::from(format!(
+                        "Invalid encryption key ID {:?}, it must be non-empty and not contain `:`",
+                        key.id
+                    )),
+                    "encryption_keys",
+                )
+                .into());
+            }
+
+            if !seen.insert(&key.id) {
+                return Err(annotate(
+                    figment::Error::from(format!("Duplicate encryption key ID {:?}", key.id)),
+                    "encryption_keys",
+                )
+                .into());
+            }
+        }
+
+        if let Some(pkcs11) = &self.pkcs11
+            && pkcs11.keys.is_empty()
+        {
+            return Err(annotate(
+                figment::Error::from("At least one key must be set in `keys`".to_owned()),
+                "pkcs11",
+            )
+            .into());
+        }
+
+        if let Some(key_rotation) = &self.key_rotation {
+            if key_rotation.key_types.is_empty() {
+                return Err(annotate(
+                    figment::Error::from(
+                        "At least one key type must be set in `key_types`".to_owned(),
+                    ),
+                    "key_rotation",
+                )
+                .into());
+            }
+
+            if key_rotation.publication_period >= key_rotation.rotation_interval {
+                return Err(annotate(
+                    figment::Error::from(
+                        "`publication_period` must be shorter than `rotation_interval`".to_owned(),
+                    ),
+                    "key_rotation",
+                )
+                .into());
+            }
+        }
+
+        Ok(())
+    }
 }
 
 impl SecretsConfig {
@@ -414,10 +785,35 @@ impl SecretsConfig {
             key: Key::Value(ec_k256_key.to_pem(pem_rfc7468::LineEnding::LF)?.to_string()),
         };
 
+        let span = tracing::info_span!("ed25519");
+        let key_rng = rand_chacha::ChaChaRng::from_rng(&mut rng)?;
+        let ed25519_key = task::spawn_blocking(move || {
+            let _entered = span.enter();
+            let ret = PrivateKey::generate_ed25519(key_rng);
+            info!("Done generating Ed25519 key");
+            ret
+        })
+        .await
+        .context("could not join blocking task")?;
+        let ed25519_key = KeyConfig {
+            kid: None,
+            password: None,
+            key: Key::Value(ed25519_key.to_pem(pem_rfc7468::LineEnding::LF)?.to_string()),
+        };
+
         Ok(Self {
             encryption: Encryption::Value(Standard.sample(&mut rng)),
-            keys: Some(vec![rsa_key, ec_p256_key, ec_p384_key, ec_k256_key]),
+            encryption_keys: Vec::new(),
+            keys: Some(vec![
+                rsa_key,
+                ec_p256_key,
+                ec_p384_key,
+                ec_k256_key,
+                ed25519_key,
+            ]),
             keys_dir: None,
+            pkcs11: None,
+            key_rotation: None,
         })
     }
 
@@ -458,8 +854,11 @@ impl SecretsConfig {
 
         Self {
             encryption: Encryption::Value([0xEA; 32]),
+            encryption_keys: Vec::new(),
             keys: Some(vec![rsa_key, ecdsa_key]),
             keys_dir: None,
+            pkcs11: None,
+            key_rotation: None,
         }
     }
 }
@@ -706,4 +1105,47 @@ mod tests {
         .await
         .unwrap();
     }
+
+    #[tokio::test]
+    async fn load_config_encryption_keys() {
+        task::spawn_blocking(|| {
+            Jail::expect_with(|jail| {
+                jail.create_file(
+                    "config.yaml",
+                    indoc::indoc! {r"
+                        secrets:
+                          encryption: 0000111122223333444455556666777788889999aaaabbbbccccddddeeeeffff
+                          encryption_keys:
+                            - id: new
+                              key_file: new_key
+                            - id: old
+                              key: '1111111111111111111111111111111111111111111111111111111111111111'
+                    "},
+                )?;
+                jail.create_file(
+                    "new_key",
+                    "2222222222222222222222222222222222222222222222222222222222222222",
+                )?;
+
+                let config = Figment::new()
+                    .merge(Yaml::file("config.yaml"))
+                    .extract_inner::<SecretsConfig>("secrets")?;
+
+                Handle::current().block_on(async move {
+                    let encrypter = config.encrypter().await.unwrap();
+                    assert_eq!(encrypter.active_key_id(), Some("new"));
+
+                    // Values encrypted with the old key can still be decrypted
+                    let old = Encrypter::new(&[0; 32]).with_keys([("old".to_owned(), [0x11; 32])]);
+                    let ciphertext = old.encrypt_to_string(b"hello").unwrap();
+                    assert_eq!(encrypter.decrypt_string(&ciphertext).unwrap(), b"hello");
+                    assert!(encrypter.needs_reencryption(&ciphertext));
+                });
+
+                Ok(())
+            });
+        })
+        .await
+        .unwrap();
+    }
 }
@@ -4,17 +4,20 @@
 // SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
 // Please see LICENSE files in the repository root for full details.
 
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 
 use camino::Utf8PathBuf;
+use futures_util::future::try_join_all;
 use mas_iana::jose::JsonWebSignatureAlg;
+use mas_jose::jwk::JsonWebKey;
+use mas_keystore::PrivateKey;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize, de::Error};
 use serde_with::{serde_as, skip_serializing_none};
 use ulid::Ulid;
 use url::Url;
 
-use crate::{ClientSecret, ClientSecretRaw, ConfigurationSection};
+use crate::{ClientSecret, ClientSecretRaw, ConfigurationSection, KeyConfig};
 
 /// Upstream OAuth 2.0 providers configuration
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, Default)]
@@ -37,6 +40,7 @@ impl ConfigurationSection for UpstreamOAuth2Config {
         &self,
         figment: &figment::Figment,
     ) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+        let mut email_domains = BTreeSet::new();
         for (index, provider) in self.providers.iter().enumerate() {
             let annotate = |mut error: figment::Error| {
                 error.metadata = figment
@@ -53,6 +57,7 @@ impl ConfigurationSection for UpstreamOAuth2Config {
 
             if !matches!(provider.discovery_mode, DiscoveryMode::Disabled)
                 && provider.issuer.is_none()
+                && provider.saml.is_none()
             {
                 return Err(annotate(figment::Error::custom(
                     "The `issuer` field is required when discovery is enabled",
@@ -63,6 +68,8 @@ impl ConfigurationSection for UpstreamOAuth2Config {
             match provider.token_endpoint_auth_method {
                 TokenAuthMethod::None
                 | TokenAuthMethod::PrivateKeyJwt
+                | TokenAuthMethod::TlsClientAuth
+                | TokenAuthMethod::SelfSignedTlsClientAuth
                 | TokenAuthMethod::SignInWithApple => {
                     if provider.client_secret.is_some() {
                         return Err(annotate(figment::Error::custom(
@@ -83,6 +90,8 @@ impl ConfigurationSection for UpstreamOAuth2Config {
                 TokenAuthMethod::None
                 | TokenAuthMethod::ClientSecretBasic
                 | TokenAuthMethod::ClientSecretPost
+                | TokenAuthMethod::TlsClientAuth
+                | TokenAuthMethod::SelfSignedTlsClientAuth
                 | TokenAuthMethod::SignInWithApple => {
                     if provider.token_endpoint_auth_signing_alg.is_some() {
                         return Err(annotate(figment::Error::custom(
@@ -118,6 +127,134 @@ impl ConfigurationSection for UpstreamOAuth2Config {
                 }
             }
 
+            if !provider.private_keys.is_empty()
+                && !matches!(
+                    provider.token_endpoint_auth_method,
+                    TokenAuthMethod::PrivateKeyJwt
+                )
+            {
+                return Err(annotate(figment::Error::custom(
+                    "Unexpected field `private_keys` for the selected authentication method",
+                ))
+                .into());
+            }
+
+            match (
+                provider.token_endpoint_auth_method,
+                &provider.client_certificate,
+            ) {
+                (
+                    TokenAuthMethod::TlsClientAuth | TokenAuthMethod::SelfSignedTlsClientAuth,
+                    None,
+                ) => {
+                    return Err(
+                        annotate(figment::Error::missing_field("client_certificate")).into(),
+                    );
+                }
+
+                (_, Some(client_certificate)) => {
+                    if client_certificate.certificate.is_some()
+                        == client_certificate.certificate_file.is_some()
+                    {
+                        return Err(annotate(figment::Error::custom(
+                            "Exactly one of `certificate` and `certificate_file` must be set",
+                        ))
+                        .with_path("client_certificate")
+                        .into());
+                    }
+
+                    if client_certificate.private_key.is_some()
+                        == client_certificate.private_key_file.is_some()
+                    {
+                        return Err(annotate(figment::Error::custom(
+                            "Exactly one of `private_key` and `private_key_file` must be set",
+                        ))
+                        .with_path("client_certificate")
+                        .into());
+                    }
+                }
+
+                (_, None) => {}
+            }
+
+            if let Some(saml) = &provider.saml {
+                if provider.client_certificate.is_some() {
+                    return Err(annotate(figment::Error::custom(
+                        "A client certificate can't be used with SAML providers",
+                    ))
+                    .with_path("client_certificate")
+                    .into());
+                }
+
+                if provider.claims_imports.resync.in_background {
+                    return Err(annotate(figment::Error::custom(
+                        "Claims can't be refreshed in the background for SAML providers",
+                    ))
+                    .with_path("claims_imports.resync.in_background")
+                    .into());
+                }
+
+                if provider.store_tokens {
+                    return Err(annotate(figment::Error::custom(
+                        "Tokens can't be stored for SAML providers",
+                    ))
+                    .with_path("store_tokens")
+                    .into());
+                }
+
+                if !matches!(provider.token_endpoint_auth_method, TokenAuthMethod::None) {
+                    return Err(annotate(figment::Error::custom(
+                        "The field `token_endpoint_auth_method` must be `none` for SAML providers",
+                    ))
+                    .into());
+                }
+
+                if saml.idp_certificates.is_empty() {
+                    return Err(annotate(figment::Error::custom(
+                        "At least one identity provider certificate must be set",
+                    ))
+                    .with_path("saml.idp_certificates")
+                    .into());
+                }
+
+                if saml.sp_certificate.is_some() && saml.sp_certificate_file.is_some() {
+                    return Err(annotate(figment::Error::custom(
+                        "Only one of `sp_certificate` and `sp_certificate_file` can be set",
+                    ))
+                    .with_path("saml")
+                    .into());
+                }
+
+                if saml.sp_private_key.is_some() && saml.sp_private_key_file.is_some() {
+                    return Err(annotate(figment::Error::custom(
+                        "Only one of `sp_private_key` and `sp_private_key_file` can be set",
+                    ))
+                    .with_path("saml")
+                    .into());
+                }
+
+                let has_certificate =
+                    saml.sp_certificate.is_some() || saml.sp_certificate_file.is_some();
+                let has_private_key =
+                    saml.sp_private_key.is_some() || saml.sp_private_key_file.is_some();
+                if has_certificate != has_private_key {
+                    return Err(annotate(figment::Error::custom(
+                        "The service provider certificate and private key must be set together",
+                    ))
+                    .with_path("saml")
+                    .into());
+                }
+
+                if (saml.sign_authn_requests || saml.want_assertions_encrypted) && !has_private_key
+                {
+                    return Err(annotate(figment::Error::custom(
+                        "A service provider certificate and private key are required to sign requests or decrypt assertions",
+                    ))
+                    .with_path("saml")
+                    .into());
+                }
+            }
+
             if provider.claims_imports.skip_confirmation {
                 if provider.claims_imports.localpart.action != ImportAction::Require {
                     return Err(annotate(figment::Error::custom(
@@ -138,6 +275,29 @@ impl ConfigurationSection for UpstreamOAuth2Config {
                 }
             }
 
+            for domain in &provider.email_domains {
+                let name = domain.strip_prefix("*.").unwrap_or(domain);
+                if name.is_empty()
+                    || name
+                        .chars()
+                        .any(|c| c.is_whitespace() || matches!(c, '@' | '*' | '/'))
+                {
+                    return Err(annotate(figment::Error::custom(format!(
+                        "Invalid email domain `{domain}`"
+                    )))
+                    .with_path("email_domains")
+                    .into());
+                }
+
+                if provider.enabled && !email_domains.insert(domain.to_ascii_lowercase()) {
+                    return Err(annotate(figment::Error::custom(format!(
+                        "The email domain `{domain}` is already handled by another provider"
+                    )))
+                    .with_path("email_domains")
+                    .into());
+                }
+            }
+
             if matches!(
                 provider.claims_imports.localpart.on_conflict,
                 OnConflict::Add | OnConflict::Replace | OnConflict::Set
@@ -193,6 +353,14 @@ pub enum TokenAuthMethod {
     /// signed by an asymmetric key
     PrivateKeyJwt,
 
+    /// `tls_client_auth`: mutual TLS, with a client certificate issued by a
+    /// certificate authority trusted by the provider
+    TlsClientAuth,
+
+    /// `self_signed_tls_client_auth`: mutual TLS, with a self-signed client
+    /// certificate registered with the provider
+    SelfSignedTlsClientAuth,
+
     /// `sign_in_with_apple`: a special method for Signin with Apple
     SignInWithApple,
 }
@@ -346,6 +514,93 @@ impl AccountNameImportPreference {
     }
 }
 
+/// What should be done for the admin flag of the user
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default, JsonSchema)]
+pub struct CanRequestAdminImportPreference {
+    /// The Jinja2 template deciding whether the user can request admin access.
+    /// The user is allowed to if it renders to `true`, and disallowed
+    /// otherwise.
+    ///
+    /// If not provided, the admin flag of the user is left untouched.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub template: Option<String>,
+}
+
+impl CanRequestAdminImportPreference {
+    const fn is_default(&self) -> bool {
+        self.template.is_none()
+    }
+}
+
+/// What should be done for the status of the user account
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default, JsonSchema)]
+pub struct AccountStatusImportPreference {
+    /// The Jinja2 template deciding the status of the account. It should render
+    /// to `active`, `locked` or `deactivated`. Any other value leaves the
+    /// account untouched.
+    ///
+    /// Deactivated accounts are never reactivated, and `active` only unlocks
+    /// accounts which were locked by this template.
+    ///
+    /// If not provided, the status of the account is left untouched.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub template: Option<String>,
+
+    /// Whether the template is allowed to deactivate accounts. Deactivation
+    /// can't be undone, so unless this is set, `deactivated` only locks the
+    /// account.
+    ///
+    /// Defaults to `false`.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub allow_deactivation: bool,
+}
+
+impl AccountStatusImportPreference {
+    const fn is_default(&self) -> bool {
+        self.template.is_none() && !self.allow_deactivation
+    }
+}
+
+/// What should be done for the user attributes passed to the policy engine
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default, JsonSchema)]
+pub struct AttributesImportPreference {
+    /// The Jinja2 template rendering the user attributes as a JSON object, for
+    /// example `{{ {"groups": user.groups} | tojson }}`.
+    ///
+    /// If not provided, no attributes are imported.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub template: Option<String>,
+}
+
+impl AttributesImportPreference {
+    const fn is_default(&self) -> bool {
+        self.template.is_none()
+    }
+}
+
+/// When to re-apply the claims imports to users who are already linked
+///
+/// Only the attributes with the `force` or `require` action are re-applied.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default, JsonSchema)]
+pub struct ResyncPreference {
+    /// Re-apply the claims imports every time the user logs in through this
+    /// provider
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub on_login: bool,
+
+    /// Periodically refresh the claims of linked users in the background. This
+    /// stores the refresh token obtained during the last login, and uses it to
+    /// fetch fresh claims from the provider.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub in_background: bool,
+}
+
+impl ResyncPreference {
+    const fn is_default(&self) -> bool {
+        !self.on_login && !self.in_background
+    }
+}
+
 /// How claims should be imported
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default, JsonSchema)]
 pub struct ClaimsImports {
@@ -381,6 +636,31 @@ pub struct ClaimsImports {
         skip_serializing_if = "AccountNameImportPreference::is_default"
     )]
     pub account_name: AccountNameImportPreference,
+
+    /// Decide whether the user can request admin access
+    #[serde(
+        default,
+        skip_serializing_if = "CanRequestAdminImportPreference::is_default"
+    )]
+    pub can_request_admin: CanRequestAdminImportPreference,
+
+    /// Lock or deactivate the user account
+    #[serde(
+        default,
+        skip_serializing_if = "AccountStatusImportPreference::is_default"
+    )]
+    pub account_status: AccountStatusImportPreference,
+
+    /// Import user attributes which are passed to the policy engine
+    #[serde(
+        default,
+        skip_serializing_if = "AttributesImportPreference::is_default"
+    )]
+    pub attributes: AttributesImportPreference,
+
+    /// Re-apply the claims imports to users who are already linked
+    #[serde(default, skip_serializing_if = "ResyncPreference::is_default")]
+    pub resync: ResyncPreference,
 }
 
 impl ClaimsImports {
@@ -391,6 +671,10 @@ impl ClaimsImports {
             && self.displayname.is_default()
             && self.email.is_default()
             && self.account_name.is_default()
+            && self.can_request_admin.is_default()
+            && self.account_status.is_default()
+            && self.attributes.is_default()
+            && self.resync.is_default()
     }
 }
 
@@ -478,6 +762,197 @@ pub struct SignInWithApple {
     pub key_id: String,
 }
 
+/// A TLS client certificate presented to the provider
+#[skip_serializing_none]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ClientCertificate {
+    /// The PEM-encoded certificate, optionally followed by the intermediate
+    /// certificates of its chain
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub certificate: Option<String>,
+
+    /// Path to the PEM-encoded certificate
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(with = "Option<String>")]
+    pub certificate_file: Option<Utf8PathBuf>,
+
+    /// The PEM-encoded private key of the certificate
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub private_key: Option<String>,
+
+    /// Path to the PEM-encoded private key of the certificate
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(with = "Option<String>")]
+    pub private_key_file: Option<Utf8PathBuf>,
+}
+
+impl ClientCertificate {
+    /// Returns the certificate chain.
+    ///
+    /// If `certificate_file` was given, the certificate is read from that
+    /// file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when the certificate is missing or could not be read
+    /// from file.
+    pub async fn certificate(&self) -> anyhow::Result<String> {
+        match (&self.certificate, &self.certificate_file) {
+            (Some(certificate), _) => Ok(certificate.clone()),
+            (None, Some(path)) => Ok(tokio::fs::read_to_string(path).await?),
+            (None, None) => anyhow::bail!("Missing `certificate` or `certificate_file`"),
+        }
+    }
+
+    /// Returns the private key of the certificate.
+    ///
+    /// If `private_key_file` was given, the key is read from that file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when the key is missing or could not be read from
+    /// file.
+    pub async fn private_key(&self) -> anyhow::Result<String> {
+        match (&self.private_key, &self.private_key_file) {
+            (Some(key), _) => Ok(key.clone()),
+            (None, Some(path)) => Ok(tokio::fs::read_to_string(path).await?),
+            (None, None) => anyhow::bail!("Missing `private_key` or `private_key_file`"),
+        }
+    }
+}
+
+/// The SAML binding used to send authentication requests to the identity
+/// provider
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum SamlBinding {
+    /// Redirect the user to the identity provider, with the request in the
+    /// query string
+    #[default]
+    Redirect,
+
+    /// Make the user's browser POST the request to the identity provider
+    Post,
+}
+
+impl SamlBinding {
+    #[allow(clippy::trivially_copy_pass_by_ref)]
+    const fn is_default(&self) -> bool {
+        matches!(self, SamlBinding::Redirect)
+    }
+}
+
+/// Settings for providers which are SAML 2.0 identity providers
+///
+/// The `client_id` of the provider is used as the entity ID of the service
+/// provider.
+#[skip_serializing_none]
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct SamlProvider {
+    /// The entity ID of the identity provider
+    pub idp_entity_id: String,
+
+    /// The URL of the single sign-on service of the identity provider
+    pub sso_url: Url,
+
+    /// The binding to use to send authentication requests to the single
+    /// sign-on service
+    ///
+    /// Defaults to `redirect`
+    #[serde(default, skip_serializing_if = "SamlBinding::is_default")]
+    pub sso_binding: SamlBinding,
+
+    /// The certificates used by the identity provider to sign responses and
+    /// assertions, either PEM-encoded or as found in its metadata
+    pub idp_certificates: Vec<String>,
+
+    /// The PEM-encoded certificate of the service provider, advertised in its
+    /// metadata
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sp_certificate: Option<String>,
+
+    /// Path to the PEM-encoded certificate of the service provider
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(with = "Option<String>")]
+    pub sp_certificate_file: Option<Utf8PathBuf>,
+
+    /// The PEM-encoded private key of the service provider, used to sign
+    /// authentication requests and to decrypt assertions
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sp_private_key: Option<String>,
+
+    /// Path to the PEM-encoded private key of the service provider
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(with = "Option<String>")]
+    pub sp_private_key_file: Option<Utf8PathBuf>,
+
+    /// Whether to sign the authentication requests
+    ///
+    /// Defaults to `false`.
+    #[serde(default)]
+    pub sign_authn_requests: bool,
+
+    /// Whether the assertions themselves must be signed, a signed response not
+    /// being enough
+    ///
+    /// Defaults to `true`.
+    #[serde(default = "default_true", skip_serializing_if = "is_default_true")]
+    pub want_assertions_signed: bool,
+
+    /// Whether the assertions must be encrypted
+    ///
+    /// Defaults to `false`.
+    #[serde(default)]
+    pub want_assertions_encrypted: bool,
+
+    /// Whether to accept assertions encrypted with AES-CBC. This is vulnerable
+    /// to padding oracle attacks, and should only be enabled for identity
+    /// providers which don't support AES-GCM
+    ///
+    /// Defaults to `false`.
+    #[serde(default)]
+    pub allow_cbc_encryption: bool,
+
+    /// The name identifier format to request, e.g.
+    /// `urn:oasis:names:tc:SAML:2.0:nameid-format:persistent`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name_id_format: Option<String>,
+}
+
+impl SamlProvider {
+    /// Returns the certificate of the service provider, if any.
+    ///
+    /// If `sp_certificate_file` was given, the certificate is read from that
+    /// file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when the certificate could not be read from file.
+    pub async fn sp_certificate(&self) -> anyhow::Result<Option<String>> {
+        Ok(match (&self.sp_certificate, &self.sp_certificate_file) {
+            (Some(certificate), _) => Some(certificate.clone()),
+            (None, Some(path)) => Some(tokio::fs::read_to_string(path).await?),
+            (None, None) => None,
+        })
+    }
+
+    /// Returns the private key of the service provider, if any.
+    ///
+    /// If `sp_private_key_file` was given, the key is read from that file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when the key could not be read from file.
+    pub async fn sp_private_key(&self) -> anyhow::Result<Option<String>> {
+        Ok(match (&self.sp_private_key, &self.sp_private_key_file) {
+            (Some(key), _) => Some(key.clone()),
+            (None, Some(path)) => Some(tokio::fs::read_to_string(path).await?),
+            (None, None) => None,
+        })
+    }
+}
+
 fn default_scope() -> String {
     "openid".to_owned()
 }
@@ -512,6 +987,7 @@ impl OnBackchannelLogout {
 /// Configuration for one upstream OAuth 2 provider.
 #[serde_as]
 #[skip_serializing_none]
+#[allow(clippy::struct_excessive_bools)]
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Provider {
     /// Whether this provider is enabled.
@@ -585,6 +1061,28 @@ pub struct Provider {
     /// The method to authenticate the client with the provider
     pub token_endpoint_auth_method: TokenAuthMethod,
 
+    /// The private keys used to sign the client assertions of the
+    /// `private_key_jwt` method
+    ///
+    /// The first key compatible with `token_endpoint_auth_signing_alg` is used
+    /// for signing, and the public part of all keys is published on the
+    /// `/upstream/jwks/{id}` endpoint. To rotate keys without downtime, add
+    /// the new key after the current one, wait for the provider to fetch the
+    /// updated key set, then remove the old key.
+    ///
+    /// If empty, the keys from the `secrets` section are used.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub private_keys: Vec<KeyConfig>,
+
+    /// The TLS client certificate to present when calling the provider's
+    /// token and userinfo endpoints
+    ///
+    /// Required by the `tls_client_auth` and `self_signed_tls_client_auth`
+    /// methods, and can be used with other methods for providers which
+    /// require mutual TLS.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_certificate: Option<ClientCertificate>,
+
     /// Additional parameters for the `sign_in_with_apple` method
     #[serde(skip_serializing_if = "Option::is_none")]
     pub sign_in_with_apple: Option<SignInWithApple>,
@@ -688,11 +1186,50 @@ pub struct Provider {
     #[serde(default)]
     pub forward_login_hint: bool,
 
+    /// The email domains handled by this provider.
+    ///
+    /// When a user enters an email address in one of those domains on the
+    /// login page, or when a client passes one as `login_hint`, they are sent
+    /// straight to this provider. A domain starting with `*.` matches any of
+    /// its subdomains.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub email_domains: Vec<String>,
+
+    /// Whether the access and refresh tokens obtained from the provider should
+    /// be stored, encrypted, so that they can be used to call the provider's
+    /// APIs on behalf of users.
+    ///
+    /// The stored tokens are refreshed in the background, and can be retrieved
+    /// through the admin API.
+    ///
+    /// Defaults to `false`.
+    #[serde(default)]
+    pub store_tokens: bool,
+
+    /// Whether logins with this provider can be started from outside of MAS,
+    /// by the provider itself or by a third party like a company portal.
+    ///
+    /// When enabled, the `/upstream/initiate/{id}` endpoint starts a login
+    /// with this provider, optionally followed by a redirection to a client
+    /// through its registered `initiate_login_uri`.
+    ///
+    /// Defaults to `false`.
+    #[serde(default)]
+    pub allow_initiated_login: bool,
+
     /// What to do when receiving an OIDC Backchannel logout request.
     ///
     /// Defaults to `do_nothing`.
     #[serde(default, skip_serializing_if = "OnBackchannelLogout::is_default")]
     pub on_backchannel_logout: OnBackchannelLogout,
+
+    /// Settings for SAML 2.0 identity providers
+    ///
+    /// If set, this provider is a SAML 2.0 identity provider instead of an
+    /// OAuth 2.0 one. The `token_endpoint_auth_method` must then be `none`,
+    /// and the `client_id` is used as the entity ID of the service provider.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub saml: Option<SamlProvider>,
 }
 
 impl Provider {
@@ -709,6 +1246,17 @@ impl Provider {
             None => None,
         })
     }
+
+    /// Returns the private keys used by the `private_key_jwt` method.
+    ///
+    /// Keys and passwords are read from file if they're given as path.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when a key could not be read or parsed.
+    pub async fn private_keys(&self) -> anyhow::Result<Vec<JsonWebKey<PrivateKey>>> {
+        try_join_all(self.private_keys.iter().map(KeyConfig::json_web_key)).await
+    }
 }
 
 #[cfg(test)]
@@ -769,6 +1317,13 @@ mod tests {
                                 use: "sig"
                                 e: "AQAB"
                                 n: "0hukqytPwrj1RbMYhYoepCi3CN5k7DwYkTe_Cmb7cP9_qv4ok78KdvFXt5AnQxCRwBD7-qTNkkfMWO2RxUMBdQD0ED6tsSb1n5dp0XY8dSWiBDCX8f6Hr-KolOpvMLZKRy01HdAWcM6RoL9ikbjYHUEW1C8IJnw3MzVHkpKFDL354aptdNLaAdTCBvKzU9WpXo10g-5ctzSlWWjQuecLMQ4G1mNdsR1LHhUENEnOvgT8cDkX0fJzLbEbyBYkdMgKggyVPEB1bg6evG4fTKawgnf0IDSPxIU-wdS9wdSP9ZCJJPLi5CEp-6t6rE_sb2dGcnzjCGlembC57VwpkUvyMw"
+
+                          - id: 01GFWR5KJ8G3Q2Y0V7R6TB4M9N
+                            client_id: upstream-oauth2
+                            token_endpoint_auth_method: tls_client_auth
+                            client_certificate:
+                              certificate_file: client.crt
+                              private_key_file: client.key
                     "#,
                 )?;
                 jail.create_file("secret", r"c1!3n753c237")?;
@@ -777,7 +1332,7 @@ mod tests {
                     .merge(Yaml::file("config.yaml"))
                     .extract_inner::<UpstreamOAuth2Config>("upstream_oauth2")?;
 
-                assert_eq!(config.providers.len(), 5);
+                assert_eq!(config.providers.len(), 6);
 
                 assert_eq!(
                     config.providers[1].id,
@@ -789,6 +1344,11 @@ mod tests {
                 assert!(matches!(config.providers[2].client_secret, Some(ClientSecret::Value(ref v)) if v == "c1!3n753c237"));
                 assert!(matches!(config.providers[3].client_secret, Some(ClientSecret::File(ref p)) if p == "secret"));
                 assert!(config.providers[4].client_secret.is_none());
+                assert!(config.providers[5].client_secret.is_none());
+                assert!(matches!(
+                    config.providers[5].client_certificate,
+                    Some(ClientCertificate { certificate_file: Some(ref p), .. }) if p == "client.crt"
+                ));
 
                 Handle::current().block_on(async move {
                     assert_eq!(config.providers[1].client_secret().await.unwrap().unwrap(), "c1!3n753c237");
@@ -0,0 +1,80 @@
+// Copyright 2026 Element Creations Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+// Please see LICENSE files in the repository root for full details.
+
+use std::net::IpAddr;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use ulid::Ulid;
+
+/// A kind of anomaly detected when a compatibility access token is used
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CompatSessionAnomalyKind {
+    /// The token was used from an IP address outside of the network range the
+    /// session was last active from
+    IpRange,
+
+    /// The token was used by a different client software, operating system or
+    /// kind of device than the one the session was last active from
+    UserAgent,
+}
+
+impl CompatSessionAnomalyKind {
+    /// Returns the string representation of the anomaly kind, as stored in the
+    /// database
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::IpRange => "ip_range",
+            Self::UserAgent => "user_agent",
+        }
+    }
+}
+
+impl std::fmt::Display for CompatSessionAnomalyKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Error returned when parsing an unknown [`CompatSessionAnomalyKind`]
+#[derive(Debug, Error)]
+#[error("Invalid compat session anomaly kind {0:?}")]
+pub struct InvalidCompatSessionAnomalyKindError(String);
+
+impl std::str::FromStr for CompatSessionAnomalyKind {
+    type Err = InvalidCompatSessionAnomalyKindError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ip_range" => Ok(Self::IpRange),
+            "user_agent" => Ok(Self::UserAgent),
+            s => Err(InvalidCompatSessionAnomalyKindError(s.to_owned())),
+        }
+    }
+}
+
+/// A record of a compatibility access token being used in an unusual way
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CompatSessionAnomaly {
+    pub id: Ulid,
+    pub compat_session_id: Ulid,
+    pub user_id: Ulid,
+    pub created_at: DateTime<Utc>,
+
+    /// What was unusual about this use of the token
+    pub kinds: Vec<CompatSessionAnomalyKind>,
+
+    /// The IP address the token was used from
+    pub ip_address: Option<IpAddr>,
+
+    /// The user agent the token was used with
+    pub user_agent: Option<String>,
+
+    /// Whether the use of the token was rejected, or only flagged
+    pub rejected: bool,
+}
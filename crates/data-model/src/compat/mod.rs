@@ -7,11 +7,15 @@
 use chrono::{DateTime, Utc};
 use ulid::Ulid;
 
+mod anomaly;
 mod device;
 mod session;
 mod sso_login;
 
 pub use self::{
+    anomaly::{
+        CompatSessionAnomaly, CompatSessionAnomalyKind, InvalidCompatSessionAnomalyKindError,
+    },
     device::{Device, ToScopeTokenError},
     session::{CompatSession, CompatSessionState},
     sso_login::{CompatSsoLogin, CompatSsoLoginState},
@@ -25,6 +29,7 @@ pub struct CompatAccessToken {
     pub token: String,
     pub created_at: DateTime<Utc>,
     pub expires_at: Option<DateTime<Utc>>,
+    pub first_used_at: Option<DateTime<Utc>>,
 }
 
 impl CompatAccessToken {
@@ -36,6 +41,12 @@ impl CompatAccessToken {
             true
         }
     }
+
+    /// Whether the access token was used at least once
+    #[must_use]
+    pub fn is_used(&self) -> bool {
+        self.first_used_at.is_some()
+    }
 }
 
 #[derive(Debug, Clone, Default, PartialEq, Eq)]
@@ -44,6 +55,7 @@ pub enum CompatRefreshTokenState {
     Valid,
     Consumed {
         consumed_at: DateTime<Utc>,
+        next_refresh_token_id: Option<Ulid>,
     },
 }
 
@@ -69,12 +81,31 @@ impl CompatRefreshTokenState {
     /// # Errors
     ///
     /// Returns an error if the refresh token is already consumed.
-    pub fn consume(self, consumed_at: DateTime<Utc>) -> Result<Self, InvalidTransitionError> {
+    pub fn consume(
+        self,
+        consumed_at: DateTime<Utc>,
+        replaced_by: &CompatRefreshToken,
+    ) -> Result<Self, InvalidTransitionError> {
         match self {
-            Self::Valid => Ok(Self::Consumed { consumed_at }),
+            Self::Valid => Ok(Self::Consumed {
+                consumed_at,
+                next_refresh_token_id: Some(replaced_by.id),
+            }),
             Self::Consumed { .. } => Err(InvalidTransitionError),
         }
     }
+
+    /// Returns the next refresh token ID, if any.
+    #[must_use]
+    pub fn next_refresh_token_id(&self) -> Option<Ulid> {
+        match self {
+            Self::Valid => None,
+            Self::Consumed {
+                next_refresh_token_id,
+                ..
+            } => *next_refresh_token_id,
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -101,8 +132,12 @@ impl CompatRefreshToken {
     /// # Errors
     ///
     /// Returns an error if the refresh token is already consumed.
-    pub fn consume(mut self, consumed_at: DateTime<Utc>) -> Result<Self, InvalidTransitionError> {
-        self.state = self.state.consume(consumed_at)?;
+    pub fn consume(
+        mut self,
+        consumed_at: DateTime<Utc>,
+        replaced_by: &Self,
+    ) -> Result<Self, InvalidTransitionError> {
+        self.state = self.state.consume(consumed_at, replaced_by)?;
         Ok(self)
     }
 }
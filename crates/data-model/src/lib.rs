@@ -13,6 +13,7 @@ pub(crate) mod compat;
 pub mod oauth2;
 pub mod personal;
 pub(crate) mod policy_data;
+pub(crate) mod signing_key;
 mod site_config;
 pub(crate) mod tokens;
 pub(crate) mod upstream_oauth2;
@@ -32,27 +33,36 @@ pub use self::{
     clock::{Clock, SystemClock},
     compat::{
         CompatAccessToken, CompatRefreshToken, CompatRefreshTokenState, CompatSession,
-        CompatSessionState, CompatSsoLogin, CompatSsoLoginState, Device, ToScopeTokenError,
+        CompatSessionAnomaly, CompatSessionAnomalyKind, CompatSessionState, CompatSsoLogin,
+        CompatSsoLoginState, Device, InvalidCompatSessionAnomalyKindError, ToScopeTokenError,
     },
     oauth2::{
-        AuthorizationCode, AuthorizationGrant, AuthorizationGrantStage, Client, DeviceCodeGrant,
-        DeviceCodeGrantState, InvalidRedirectUriError, JwksOrJwksUri, Pkce, Session, SessionState,
+        AuthorizationCode, AuthorizationGrant, AuthorizationGrantStage, Client, ClientSecret,
+        ClientSecretHashError, ClientSecretValue, DeviceCodeGrant, DeviceCodeGrantState,
+        InvalidRedirectUriError, JwksOrJwksUri, Pkce, Session, SessionState, client_secret_matches,
+        client_secret_matches_hash,
     },
     policy_data::PolicyData,
+    signing_key::{InvalidSigningKeyTypeError, SigningKey, SigningKeyState, SigningKeyType},
     site_config::{
-        CaptchaConfig, CaptchaService, SessionExpirationConfig, SessionLimitConfig, SiteConfig,
+        CaptchaConfig, CaptchaService, CompatTokenAnomalyDetectionConfig, JwtAccessTokensConfig,
+        SessionExpirationConfig, SessionLimitConfig, SigningKeyRotationConfig, SiteConfig,
     },
     tokens::{
         AccessToken, AccessTokenState, RefreshToken, RefreshTokenState, TokenFormatError, TokenType,
     },
     upstream_oauth2::{
         UpstreamOAuthAuthorizationSession, UpstreamOAuthAuthorizationSessionState,
-        UpstreamOAuthLink, UpstreamOAuthProvider, UpstreamOAuthProviderClaimsImports,
-        UpstreamOAuthProviderDiscoveryMode, UpstreamOAuthProviderImportAction,
+        UpstreamOAuthLink, UpstreamOAuthProvider, UpstreamOAuthProviderAccountStatusPreference,
+        UpstreamOAuthProviderClaimsImports, UpstreamOAuthProviderDiscoveryMode,
+        UpstreamOAuthProviderHealth, UpstreamOAuthProviderHealthCheck,
+        UpstreamOAuthProviderHealthChecks, UpstreamOAuthProviderImportAction,
         UpstreamOAuthProviderImportPreference, UpstreamOAuthProviderLocalpartPreference,
         UpstreamOAuthProviderOnBackchannelLogout, UpstreamOAuthProviderOnConflict,
         UpstreamOAuthProviderPkceMode, UpstreamOAuthProviderResponseMode,
-        UpstreamOAuthProviderSubjectPreference, UpstreamOAuthProviderTokenAuthMethod,
+        UpstreamOAuthProviderResyncPreference, UpstreamOAuthProviderSamlBinding,
+        UpstreamOAuthProviderSamlConfig, UpstreamOAuthProviderSubjectPreference,
+        UpstreamOAuthProviderTokenAuthMethod,
     },
     user_agent::{DeviceType, UserAgent},
     users::{
@@ -4,8 +4,13 @@
 // SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
 // Please see LICENSE files in the repository root for full details.
 
-use chrono::{DateTime, Utc};
-use mas_iana::{jose::JsonWebSignatureAlg, oauth::OAuthClientAuthenticationMethod};
+use base64ct::{Base64, Encoding};
+use chrono::{DateTime, Duration, Utc};
+use hmac::{Hmac, Mac};
+use mas_iana::{
+    jose::{JsonWebEncryptionAlg, JsonWebEncryptionEnc, JsonWebSignatureAlg},
+    oauth::OAuthClientAuthenticationMethod,
+};
 use mas_jose::jwk::PublicJsonWebKeySet;
 use oauth2_types::{
     oidc::ApplicationType,
@@ -14,6 +19,7 @@ use oauth2_types::{
 };
 use rand::RngCore;
 use serde::Serialize;
+use sha2::Sha256;
 use thiserror::Error;
 use ulid::Ulid;
 use url::Url;
@@ -28,6 +34,132 @@ pub enum JwksOrJwksUri {
     JwksUri(Url),
 }
 
+/// How a client secret is stored
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case", tag = "kind")]
+pub enum ClientSecretValue {
+    /// Encrypted with the service encryption key. It can be decrypted, which
+    /// is required to verify `client_secret_jwt` assertions.
+    Encrypted { encrypted: String },
+
+    /// Hashed with the given version of the client secret hashing scheme. It
+    /// can only be compared with a secret presented by the client.
+    Hashed { version: u16, hash: String },
+}
+
+/// Version of the scheme used to hash client secrets. Version 1 is an
+/// HMAC-SHA256 of the secret, keyed with the client ID.
+const CLIENT_SECRET_HASH_VERSION: u16 = 1;
+
+/// Computes the MAC of a client secret, keyed with the client ID
+fn client_secret_mac(client_id: &str, client_secret: &[u8]) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(client_id.as_bytes())
+        .expect("HMAC accepts keys of any size");
+    mac.update(client_secret);
+    mac
+}
+
+/// Check whether the secret presented by a client matches the expected one
+///
+/// The comparison is done on the MAC of both secrets, in constant time, so
+/// that it doesn't leak how much of the secret was right.
+#[must_use]
+pub fn client_secret_matches(client_id: &str, client_secret: &str, expected: &[u8]) -> bool {
+    let expected = client_secret_mac(client_id, expected)
+        .finalize()
+        .into_bytes();
+    client_secret_mac(client_id, client_secret.as_bytes())
+        .verify_slice(&expected)
+        .is_ok()
+}
+
+#[derive(Debug, Error)]
+pub enum ClientSecretHashError {
+    #[error("unknown client secret hashing scheme version {0}")]
+    UnknownVersion(u16),
+
+    #[error("malformed client secret hash")]
+    Malformed,
+}
+
+/// Check whether the secret presented by a client matches a hashed secret,
+/// in constant time
+///
+/// # Errors
+///
+/// Returns an error if the hash was made with an unknown version of the
+/// hashing scheme, or if it is malformed
+pub fn client_secret_matches_hash(
+    client_id: &str,
+    client_secret: &str,
+    version: u16,
+    hash: &str,
+) -> Result<bool, ClientSecretHashError> {
+    if version != CLIENT_SECRET_HASH_VERSION {
+        return Err(ClientSecretHashError::UnknownVersion(version));
+    }
+
+    let hash = Base64::decode_vec(hash).map_err(|_| ClientSecretHashError::Malformed)?;
+    Ok(client_secret_mac(client_id, client_secret.as_bytes())
+        .verify_slice(&hash)
+        .is_ok())
+}
+
+/// A secret a confidential client can authenticate with
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ClientSecret {
+    pub value: ClientSecretValue,
+
+    /// When this secret stops being accepted, if ever
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+impl ClientSecret {
+    /// Create a new encrypted client secret
+    #[must_use]
+    pub fn encrypted(encrypted: String, expires_at: Option<DateTime<Utc>>) -> Self {
+        Self {
+            value: ClientSecretValue::Encrypted { encrypted },
+            expires_at,
+        }
+    }
+
+    /// Create a new hashed client secret
+    ///
+    /// Client secrets are long random strings, not passwords chosen by
+    /// people, so they don't need a slow password hashing scheme. They are
+    /// hashed with an HMAC keyed with the client ID, so that the hash can't be
+    /// copied to another client.
+    #[must_use]
+    pub fn hash(client_id: &str, client_secret: &str, expires_at: Option<DateTime<Utc>>) -> Self {
+        let hash = client_secret_mac(client_id, client_secret.as_bytes())
+            .finalize()
+            .into_bytes();
+        Self {
+            value: ClientSecretValue::Hashed {
+                version: CLIENT_SECRET_HASH_VERSION,
+                hash: Base64::encode_string(&hash),
+            },
+            expires_at,
+        }
+    }
+
+    /// Whether this secret is still accepted at the given time
+    #[must_use]
+    pub fn is_active(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at.is_none_or(|expires_at| now < expires_at)
+    }
+
+    /// The encrypted secret, if it wasn't hashed
+    #[must_use]
+    pub fn encrypted_secret(&self) -> Option<&str> {
+        match &self.value {
+            ClientSecretValue::Encrypted { encrypted } => Some(encrypted),
+            ClientSecretValue::Hashed { .. } => None,
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 pub struct Client {
     pub id: Ulid,
@@ -38,7 +170,15 @@ pub struct Client {
     /// Hash of the client metadata
     pub metadata_digest: Option<String>,
 
-    pub encrypted_client_secret: Option<String>,
+    /// The current client secret, if any
+    #[serde(skip)]
+    pub client_secret: Option<ClientSecret>,
+
+    /// The secret this client had before its last rotation, still accepted
+    /// until it expires so that every deployed instance of the client can be
+    /// updated
+    #[serde(skip)]
+    pub previous_client_secret: Option<ClientSecret>,
 
     pub application_type: Option<ApplicationType>,
 
@@ -75,6 +215,21 @@ pub struct Client {
     /// JWS alg algorithm REQUIRED for signing `UserInfo` Responses.
     pub userinfo_signed_response_alg: Option<JsonWebSignatureAlg>,
 
+    /// JWE alg algorithm REQUIRED for encrypting the ID Token issued to this
+    /// Client
+    pub id_token_encrypted_response_alg: Option<JsonWebEncryptionAlg>,
+
+    /// JWE enc algorithm REQUIRED for encrypting the ID Token issued to this
+    /// Client. Always set if `id_token_encrypted_response_alg` is set.
+    pub id_token_encrypted_response_enc: Option<JsonWebEncryptionEnc>,
+
+    /// JWE alg algorithm REQUIRED for encrypting `UserInfo` Responses.
+    pub userinfo_encrypted_response_alg: Option<JsonWebEncryptionAlg>,
+
+    /// JWE enc algorithm REQUIRED for encrypting `UserInfo` Responses. Always
+    /// set if `userinfo_encrypted_response_alg` is set.
+    pub userinfo_encrypted_response_enc: Option<JsonWebEncryptionEnc>,
+
     /// Requested authentication method for the token endpoint
     pub token_endpoint_auth_method: Option<OAuthClientAuthenticationMethod>,
 
@@ -153,10 +308,10 @@ impl Client {
             software_version: None,
             sector_identifier_uri: None,
             subject_type: None,
-            id_token_encrypted_response_alg: None,
-            id_token_encrypted_response_enc: None,
-            userinfo_encrypted_response_alg: None,
-            userinfo_encrypted_response_enc: None,
+            id_token_encrypted_response_alg: self.id_token_encrypted_response_alg,
+            id_token_encrypted_response_enc: self.id_token_encrypted_response_enc,
+            userinfo_encrypted_response_alg: self.userinfo_encrypted_response_alg,
+            userinfo_encrypted_response_enc: self.userinfo_encrypted_response_enc,
             request_object_signing_alg: None,
             request_object_encryption_alg: None,
             request_object_encryption_enc: None,
@@ -173,6 +328,86 @@ impl Client {
         }
     }
 
+    /// The JWE `alg` and `enc` algorithms to use to encrypt the ID tokens
+    /// issued to this client, if it asked for them to be encrypted
+    #[must_use]
+    pub fn id_token_encrypted_response(
+        &self,
+    ) -> Option<(&JsonWebEncryptionAlg, &JsonWebEncryptionEnc)> {
+        self.id_token_encrypted_response_alg
+            .as_ref()
+            .zip(self.id_token_encrypted_response_enc.as_ref())
+    }
+
+    /// The JWE `alg` and `enc` algorithms to use to encrypt the user info
+    /// responses sent to this client, if it asked for them to be encrypted
+    #[must_use]
+    pub fn userinfo_encrypted_response(
+        &self,
+    ) -> Option<(&JsonWebEncryptionAlg, &JsonWebEncryptionEnc)> {
+        self.userinfo_encrypted_response_alg
+            .as_ref()
+            .zip(self.userinfo_encrypted_response_enc.as_ref())
+    }
+
+    /// The client secrets which are still accepted at the given time, current
+    /// one first
+    pub fn active_secrets(&self, now: DateTime<Utc>) -> impl Iterator<Item = &ClientSecret> {
+        self.client_secret
+            .iter()
+            .chain(self.previous_client_secret.iter())
+            .filter(move |secret| secret.is_active(now))
+    }
+
+    /// Whether this client authenticates with a client secret
+    #[must_use]
+    pub fn uses_client_secret(&self) -> bool {
+        matches!(
+            self.token_endpoint_auth_method,
+            Some(
+                OAuthClientAuthenticationMethod::ClientSecretBasic
+                    | OAuthClientAuthenticationMethod::ClientSecretPost
+                    | OAuthClientAuthenticationMethod::ClientSecretJwt
+            )
+        )
+    }
+
+    /// Whether the secret of this client must be recoverable, because it uses
+    /// it to sign `client_secret_jwt` assertions. Such secrets can't be hashed.
+    #[must_use]
+    pub fn needs_encrypted_secret(&self) -> bool {
+        self.token_endpoint_auth_method == Some(OAuthClientAuthenticationMethod::ClientSecretJwt)
+    }
+
+    /// The secret to keep accepting after rotating the current one, for at
+    /// most `overlap`. Returns `None` if there is no current secret, if it
+    /// already expired or if `overlap` is not positive.
+    #[must_use]
+    pub fn secret_kept_on_rotation(
+        &self,
+        now: DateTime<Utc>,
+        overlap: Duration,
+    ) -> Option<ClientSecret> {
+        if overlap <= Duration::zero() {
+            return None;
+        }
+
+        let secret = self.client_secret.as_ref()?;
+        if !secret.is_active(now) {
+            return None;
+        }
+
+        let expires_at = now + overlap;
+        let expires_at = secret
+            .expires_at
+            .map_or(expires_at, |current| current.min(expires_at));
+
+        Some(ClientSecret {
+            value: secret.value.clone(),
+            expires_at: Some(expires_at),
+        })
+    }
+
     #[doc(hidden)]
     pub fn samples(now: DateTime<Utc>, rng: &mut impl RngCore) -> Vec<Client> {
         vec![
@@ -181,7 +416,8 @@ impl Client {
                 id: Ulid::from_datetime_with_source(now.into(), rng),
                 client_id: "client1".to_owned(),
                 metadata_digest: None,
-                encrypted_client_secret: None,
+                client_secret: None,
+                previous_client_secret: None,
                 application_type: Some(ApplicationType::Web),
                 redirect_uris: vec![
                     Url::parse("https://client1.example.com/redirect").unwrap(),
@@ -200,6 +436,10 @@ impl Client {
                 token_endpoint_auth_signing_alg: None,
                 id_token_signed_response_alg: None,
                 userinfo_signed_response_alg: None,
+                id_token_encrypted_response_alg: None,
+                id_token_encrypted_response_enc: None,
+                userinfo_encrypted_response_alg: None,
+                userinfo_encrypted_response_enc: None,
                 jwks: None,
             },
             // Another client without any URIs set
@@ -207,7 +447,8 @@ impl Client {
                 id: Ulid::from_datetime_with_source(now.into(), rng),
                 client_id: "client2".to_owned(),
                 metadata_digest: None,
-                encrypted_client_secret: None,
+                client_secret: None,
+                previous_client_secret: None,
                 application_type: Some(ApplicationType::Native),
                 redirect_uris: vec![Url::parse("https://client2.example.com/redirect").unwrap()],
                 grant_types: vec![GrantType::AuthorizationCode, GrantType::RefreshToken],
@@ -221,6 +462,10 @@ impl Client {
                 token_endpoint_auth_signing_alg: None,
                 id_token_signed_response_alg: None,
                 userinfo_signed_response_alg: None,
+                id_token_encrypted_response_alg: None,
+                id_token_encrypted_response_enc: None,
+                userinfo_encrypted_response_alg: None,
+                userinfo_encrypted_response_enc: None,
                 jwks: None,
             },
         ]
@@ -248,6 +493,8 @@ fn uri_matches_one_of(uri: &Url, registered_uris: &[Url]) -> bool {
 
 #[cfg(test)]
 mod tests {
+    use rand::SeedableRng;
+    use rand_chacha::ChaChaRng;
     use url::Url;
 
     use super::*;
@@ -283,4 +530,79 @@ mod tests {
             registered_uris
         ));
     }
+
+    #[test]
+    fn test_client_secret_hash() {
+        let secret = ClientSecret::hash("client", "secret", None);
+        let ClientSecretValue::Hashed { version, hash } = &secret.value else {
+            panic!("secret should be hashed");
+        };
+
+        assert!(client_secret_matches_hash("client", "secret", *version, hash).unwrap());
+        assert!(!client_secret_matches_hash("client", "secreT", *version, hash).unwrap());
+        // The hash is bound to the client
+        assert!(!client_secret_matches_hash("other", "secret", *version, hash).unwrap());
+
+        assert!(matches!(
+            client_secret_matches_hash("client", "secret", 2, hash),
+            Err(ClientSecretHashError::UnknownVersion(2))
+        ));
+        assert!(matches!(
+            client_secret_matches_hash("client", "secret", *version, "$argon2id$"),
+            Err(ClientSecretHashError::Malformed)
+        ));
+
+        assert!(client_secret_matches("client", "secret", b"secret"));
+        assert!(!client_secret_matches("client", "secret", b"secreT"));
+    }
+
+    #[test]
+    fn test_secret_rotation() {
+        let now = DateTime::default();
+        let mut rng = ChaChaRng::seed_from_u64(42);
+        let mut client = Client::samples(now, &mut rng).remove(0);
+        assert!(
+            client
+                .secret_kept_on_rotation(now, Duration::days(1))
+                .is_none()
+        );
+
+        client.client_secret = Some(ClientSecret::encrypted("current".to_owned(), None));
+        client.previous_client_secret = Some(ClientSecret::hash(
+            &client.client_id,
+            "previous",
+            Some(now + Duration::hours(1)),
+        ));
+        assert_eq!(client.active_secrets(now).count(), 2);
+        assert_eq!(client.active_secrets(now + Duration::hours(2)).count(), 1);
+
+        // The current secret is kept for the overlap period
+        let kept = client
+            .secret_kept_on_rotation(now, Duration::days(1))
+            .unwrap();
+        assert_eq!(kept.encrypted_secret(), Some("current"));
+        assert_eq!(kept.expires_at, Some(now + Duration::days(1)));
+
+        // But not longer than it would have been accepted anyway
+        client.client_secret = Some(ClientSecret::encrypted(
+            "current".to_owned(),
+            Some(now + Duration::hours(2)),
+        ));
+        let kept = client
+            .secret_kept_on_rotation(now, Duration::days(1))
+            .unwrap();
+        assert_eq!(kept.expires_at, Some(now + Duration::hours(2)));
+
+        // Nothing is kept without an overlap, or once the secret expired
+        assert!(
+            client
+                .secret_kept_on_rotation(now, Duration::zero())
+                .is_none()
+        );
+        assert!(
+            client
+                .secret_kept_on_rotation(now + Duration::hours(3), Duration::days(1))
+                .is_none()
+        );
+    }
 }
@@ -11,7 +11,10 @@ mod session;
 
 pub use self::{
     authorization_grant::{AuthorizationCode, AuthorizationGrant, AuthorizationGrantStage, Pkce},
-    client::{Client, InvalidRedirectUriError, JwksOrJwksUri},
+    client::{
+        Client, ClientSecret, ClientSecretHashError, ClientSecretValue, InvalidRedirectUriError,
+        JwksOrJwksUri, client_secret_matches, client_secret_matches_hash,
+    },
     device_code_grant::{DeviceCodeGrant, DeviceCodeGrantState},
     session::{Session, SessionState},
 };
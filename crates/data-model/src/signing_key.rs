@@ -0,0 +1,227 @@
+// Copyright 2026 Element Creations Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+// Please see LICENSE files in the repository root for full details.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use ulid::Ulid;
+
+use crate::InvalidTransitionError;
+
+/// The type of a signing key managed by the service
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SigningKeyType {
+    /// A 2048-bit RSA key
+    Rsa,
+
+    /// An ECDSA key on the P-256 curve
+    EcP256,
+
+    /// An ECDSA key on the P-384 curve
+    EcP384,
+
+    /// An ECDSA key on the secp256k1 curve
+    EcK256,
+
+    /// An `EdDSA` key on the Ed25519 curve
+    Ed25519,
+}
+
+impl SigningKeyType {
+    /// Returns the string representation of the key type, as stored in the
+    /// database
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Rsa => "rsa",
+            Self::EcP256 => "ec_p256",
+            Self::EcP384 => "ec_p384",
+            Self::EcK256 => "ec_k256",
+            Self::Ed25519 => "ed25519",
+        }
+    }
+}
+
+impl std::fmt::Display for SigningKeyType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Error returned when parsing an unknown [`SigningKeyType`]
+#[derive(Debug, Error)]
+#[error("Invalid signing key type {0:?}")]
+pub struct InvalidSigningKeyTypeError(String);
+
+impl std::str::FromStr for SigningKeyType {
+    type Err = InvalidSigningKeyTypeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "rsa" => Ok(Self::Rsa),
+            "ec_p256" => Ok(Self::EcP256),
+            "ec_p384" => Ok(Self::EcP384),
+            "ec_k256" => Ok(Self::EcK256),
+            "ed25519" => Ok(Self::Ed25519),
+            s => Err(InvalidSigningKeyTypeError(s.to_owned())),
+        }
+    }
+}
+
+/// The lifecycle of a signing key managed by the service.
+///
+/// A key is first published in the JWKS without being used, then used for
+/// signing, and finally retired: it is not used for signing anymore, but stays
+/// published until the tokens it signed have expired.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub enum SigningKeyState {
+    #[default]
+    Pending,
+    Active {
+        activated_at: DateTime<Utc>,
+    },
+    Retired {
+        activated_at: Option<DateTime<Utc>>,
+        retired_at: DateTime<Utc>,
+    },
+}
+
+impl SigningKeyState {
+    /// Returns `true` if the signing key state is [`Pending`].
+    ///
+    /// [`Pending`]: SigningKeyState::Pending
+    #[must_use]
+    pub fn is_pending(&self) -> bool {
+        matches!(self, Self::Pending)
+    }
+
+    /// Returns `true` if the signing key state is [`Active`].
+    ///
+    /// [`Active`]: SigningKeyState::Active
+    #[must_use]
+    pub fn is_active(&self) -> bool {
+        matches!(self, Self::Active { .. })
+    }
+
+    /// Returns `true` if the signing key state is [`Retired`].
+    ///
+    /// [`Retired`]: SigningKeyState::Retired
+    #[must_use]
+    pub fn is_retired(&self) -> bool {
+        matches!(self, Self::Retired { .. })
+    }
+
+    /// Transitions the signing key state to [`Active`].
+    ///
+    /// # Parameters
+    ///
+    /// * `activated_at` - The time at which the key started being used
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the signing key state is not [`Pending`].
+    ///
+    /// [`Active`]: SigningKeyState::Active
+    /// [`Pending`]: SigningKeyState::Pending
+    pub fn activate(self, activated_at: DateTime<Utc>) -> Result<Self, InvalidTransitionError> {
+        match self {
+            Self::Pending => Ok(Self::Active { activated_at }),
+            Self::Active { .. } | Self::Retired { .. } => Err(InvalidTransitionError),
+        }
+    }
+
+    /// Transitions the signing key state to [`Retired`].
+    ///
+    /// # Parameters
+    ///
+    /// * `retired_at` - The time at which the key stopped being used
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the signing key state is already [`Retired`].
+    ///
+    /// [`Retired`]: SigningKeyState::Retired
+    pub fn retire(self, retired_at: DateTime<Utc>) -> Result<Self, InvalidTransitionError> {
+        match self {
+            Self::Pending => Ok(Self::Retired {
+                activated_at: None,
+                retired_at,
+            }),
+            Self::Active { activated_at } => Ok(Self::Retired {
+                activated_at: Some(activated_at),
+                retired_at,
+            }),
+            Self::Retired { .. } => Err(InvalidTransitionError),
+        }
+    }
+
+    #[must_use]
+    pub fn activated_at(&self) -> Option<DateTime<Utc>> {
+        match self {
+            Self::Pending => None,
+            Self::Active { activated_at } => Some(*activated_at),
+            Self::Retired { activated_at, .. } => *activated_at,
+        }
+    }
+
+    #[must_use]
+    pub fn retired_at(&self) -> Option<DateTime<Utc>> {
+        match self {
+            Self::Pending | Self::Active { .. } => None,
+            Self::Retired { retired_at, .. } => Some(*retired_at),
+        }
+    }
+}
+
+/// A signing key managed by the service, stored encrypted in the database
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SigningKey {
+    pub id: Ulid,
+    pub kid: String,
+    pub key_type: SigningKeyType,
+    pub state: SigningKeyState,
+    #[serde(skip)]
+    pub encrypted_key: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl std::ops::Deref for SigningKey {
+    type Target = SigningKeyState;
+
+    fn deref(&self) -> &Self::Target {
+        &self.state
+    }
+}
+
+impl SigningKey {
+    /// Marks the signing key as active.
+    ///
+    /// # Parameters
+    ///
+    /// * `activated_at` - The time at which the key started being used
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the key is not pending.
+    pub fn activate(mut self, activated_at: DateTime<Utc>) -> Result<Self, InvalidTransitionError> {
+        self.state = self.state.activate(activated_at)?;
+        Ok(self)
+    }
+
+    /// Marks the signing key as retired.
+    ///
+    /// # Parameters
+    ///
+    /// * `retired_at` - The time at which the key stopped being used
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the key is already retired.
+    pub fn retire(mut self, retired_at: DateTime<Utc>) -> Result<Self, InvalidTransitionError> {
+        self.state = self.state.retire(retired_at)?;
+        Ok(self)
+    }
+}
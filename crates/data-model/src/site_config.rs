@@ -7,9 +7,13 @@
 use std::num::NonZeroU64;
 
 use chrono::Duration;
+use oauth2_types::scope::Scope;
 use serde::Serialize;
+use ulid::Ulid;
 use url::Url;
 
+use crate::SigningKeyType;
+
 /// Which Captcha service is being used
 #[derive(Debug, Clone, Copy)]
 pub enum CaptchaService {
@@ -39,6 +43,57 @@ pub struct SessionExpirationConfig {
     pub compat_session_inactivity_ttl: Option<Duration>,
 }
 
+/// Automatic rotation of the signing keys managed by the service
+#[derive(Debug, Clone)]
+pub struct SigningKeyRotationConfig {
+    /// The types of keys to generate and rotate
+    pub key_types: Vec<SigningKeyType>,
+
+    /// How long a key is used for signing before being replaced
+    pub rotation_interval: Duration,
+
+    /// How long a new key is published before being used for signing
+    pub publication_period: Duration,
+
+    /// How long a retired key stays published after it stopped being used
+    pub retention_period: Duration,
+}
+
+/// Issuance of signed JWT access tokens, as per RFC 9068
+#[derive(Debug, Clone)]
+pub struct JwtAccessTokensConfig {
+    /// The IDs of the clients which get JWT access tokens
+    pub clients: Vec<Ulid>,
+
+    /// The scopes which, when requested, make sessions get JWT access tokens
+    pub scopes: Vec<String>,
+
+    /// The audience of the tokens. If not set, the client ID is used
+    pub audience: Option<String>,
+}
+
+impl JwtAccessTokensConfig {
+    /// Whether the access tokens of a session of the given client with the
+    /// given scope should be JWTs
+    #[must_use]
+    pub fn applies_to(&self, client_id: Ulid, scope: &Scope) -> bool {
+        self.clients.contains(&client_id) || self.scopes.iter().any(|s| scope.contains(s))
+    }
+}
+
+/// Detection of compatibility access tokens being used from an unusual
+/// network or client
+#[derive(Debug, Clone, Copy)]
+pub struct CompatTokenAnomalyDetectionConfig {
+    /// The length of the prefix of IPv4 addresses which must match the one
+    /// the session was last active from
+    pub ipv4_prefix_length: u8,
+
+    /// The length of the prefix of IPv6 addresses which must match the one
+    /// the session was last active from
+    pub ipv6_prefix_length: u8,
+}
+
 #[derive(Serialize, Debug, Clone)]
 pub struct SessionLimitConfig {
     pub soft_limit: NonZeroU64,
@@ -70,6 +125,11 @@ pub struct SiteConfig {
     /// Whether password login is enabled.
     pub password_login_enabled: bool,
 
+    /// Whether a login through the LDAP directory can sign into an existing
+    /// user with the same username, if that user is not linked to a directory
+    /// entry yet.
+    pub ldap_adopt_existing_users: bool,
+
     /// Whether password registration is enabled.
     pub password_registration_enabled: bool,
 
@@ -111,4 +171,17 @@ pub struct SiteConfig {
 
     /// Limits on the number of application sessions that each user can have
     pub session_limit: Option<SessionLimitConfig>,
+
+    /// Automatic rotation of the signing keys stored in the database
+    pub signing_key_rotation: Option<SigningKeyRotationConfig>,
+
+    /// Issuance of signed JWT access tokens instead of opaque ones
+    pub jwt_access_tokens: Option<JwtAccessTokensConfig>,
+
+    /// Whether to finish the whole session when a refresh token is replayed
+    pub revoke_session_on_refresh_token_replay: bool,
+
+    /// Detection of compatibility access tokens used from an unusual network
+    /// or client
+    pub compat_token_anomaly_detection: Option<CompatTokenAnomalyDetectionConfig>,
 }
@@ -0,0 +1,63 @@
+// Copyright 2026 Element Creations Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+// Please see LICENSE files in the repository root for full details.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use ulid::Ulid;
+
+/// The outcome of a single check made against an upstream provider
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum HealthCheck {
+    /// The check passed
+    Ok,
+
+    /// The check doesn't apply to this provider, for example because discovery
+    /// is disabled
+    Skipped,
+
+    /// The check failed
+    Failed {
+        /// A human-readable description of the failure
+        error: String,
+    },
+}
+
+impl HealthCheck {
+    /// Returns `true` if the check failed
+    #[must_use]
+    pub const fn is_failed(&self) -> bool {
+        matches!(self, Self::Failed { .. })
+    }
+}
+
+/// The outcome of all the checks made against an upstream provider
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct HealthChecks {
+    /// Whether the provider metadata could be discovered
+    pub discovery: HealthCheck,
+
+    /// Whether the JWKS of the provider could be fetched
+    pub jwks: HealthCheck,
+
+    /// Whether the token endpoint of the provider is reachable
+    pub token_endpoint: HealthCheck,
+}
+
+impl HealthChecks {
+    /// Returns `true` if none of the checks failed
+    #[must_use]
+    pub const fn is_healthy(&self) -> bool {
+        !self.discovery.is_failed() && !self.jwks.is_failed() && !self.token_endpoint.is_failed()
+    }
+}
+
+/// The last recorded health of an upstream provider
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct UpstreamOAuthProviderHealth {
+    pub provider_id: Ulid,
+    pub checked_at: DateTime<Utc>,
+    pub checks: HealthChecks,
+}
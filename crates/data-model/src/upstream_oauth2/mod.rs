@@ -4,13 +4,19 @@
 // SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
 // Please see LICENSE files in the repository root for full details.
 
+mod health;
 mod link;
 mod provider;
 mod session;
 
 pub use self::{
+    health::{
+        HealthCheck as UpstreamOAuthProviderHealthCheck,
+        HealthChecks as UpstreamOAuthProviderHealthChecks, UpstreamOAuthProviderHealth,
+    },
     link::UpstreamOAuthLink,
     provider::{
+        AccountStatusPreference as UpstreamOAuthProviderAccountStatusPreference,
         ClaimsImports as UpstreamOAuthProviderClaimsImports,
         DiscoveryMode as UpstreamOAuthProviderDiscoveryMode,
         ImportAction as UpstreamOAuthProviderImportAction,
@@ -19,6 +25,9 @@ pub use self::{
         OnBackchannelLogout as UpstreamOAuthProviderOnBackchannelLogout,
         OnConflict as UpstreamOAuthProviderOnConflict, PkceMode as UpstreamOAuthProviderPkceMode,
         ResponseMode as UpstreamOAuthProviderResponseMode,
+        ResyncPreference as UpstreamOAuthProviderResyncPreference,
+        SamlBinding as UpstreamOAuthProviderSamlBinding,
+        SamlConfig as UpstreamOAuthProviderSamlConfig,
         SubjectPreference as UpstreamOAuthProviderSubjectPreference,
         TokenAuthMethod as UpstreamOAuthProviderTokenAuthMethod, UpstreamOAuthProvider,
     },
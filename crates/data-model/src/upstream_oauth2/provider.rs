@@ -173,6 +173,8 @@ pub enum TokenAuthMethod {
     ClientSecretPost,
     ClientSecretJwt,
     PrivateKeyJwt,
+    TlsClientAuth,
+    SelfSignedTlsClientAuth,
     SignInWithApple,
 }
 
@@ -185,6 +187,8 @@ impl TokenAuthMethod {
             Self::ClientSecretPost => "client_secret_post",
             Self::ClientSecretJwt => "client_secret_jwt",
             Self::PrivateKeyJwt => "private_key_jwt",
+            Self::TlsClientAuth => "tls_client_auth",
+            Self::SelfSignedTlsClientAuth => "self_signed_tls_client_auth",
             Self::SignInWithApple => "sign_in_with_apple",
         }
     }
@@ -206,6 +210,8 @@ impl std::str::FromStr for TokenAuthMethod {
             "client_secret_basic" => Ok(Self::ClientSecretBasic),
             "client_secret_jwt" => Ok(Self::ClientSecretJwt),
             "private_key_jwt" => Ok(Self::PrivateKeyJwt),
+            "tls_client_auth" => Ok(Self::TlsClientAuth),
+            "self_signed_tls_client_auth" => Ok(Self::SelfSignedTlsClientAuth),
             "sign_in_with_apple" => Ok(Self::SignInWithApple),
             s => Err(InvalidUpstreamOAuth2TokenAuthMethod(s.to_owned())),
         }
@@ -258,6 +264,7 @@ impl std::str::FromStr for OnBackchannelLogout {
 #[error("Invalid upstream OAuth 2.0 'on backchannel logout': {0}")]
 pub struct InvalidUpstreamOAuth2OnBackchannelLogout(String);
 
+#[allow(clippy::struct_excessive_bools)]
 #[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 pub struct UpstreamOAuthProvider {
     pub id: Ulid,
@@ -285,6 +292,11 @@ pub struct UpstreamOAuthProvider {
     pub additional_authorization_parameters: Vec<(String, String)>,
     pub forward_login_hint: bool,
     pub on_backchannel_logout: OnBackchannelLogout,
+    pub saml: Option<SamlConfig>,
+    pub email_domains: Vec<String>,
+    pub store_tokens: bool,
+    pub allow_initiated_login: bool,
+    pub encrypted_client_keys: Option<String>,
 }
 
 impl PartialOrd for UpstreamOAuthProvider {
@@ -305,6 +317,85 @@ impl UpstreamOAuthProvider {
     pub const fn enabled(&self) -> bool {
         self.disabled_at.is_none()
     }
+
+    /// Returns `true` if the provider handles users with an email address in
+    /// the given domain
+    ///
+    /// Domains are compared case-insensitively, and a `*.` prefix matches any
+    /// subdomain of the domain which follows it.
+    #[must_use]
+    pub fn handles_email_domain(&self, domain: &str) -> bool {
+        let domain = domain.trim_end_matches('.').to_ascii_lowercase();
+        self.email_domains.iter().any(|pattern| {
+            let pattern = pattern.to_ascii_lowercase();
+            match pattern.strip_prefix("*.") {
+                Some(parent) => domain
+                    .strip_suffix(parent)
+                    .and_then(|rest| rest.strip_suffix('.'))
+                    .is_some_and(|subdomain| !subdomain.is_empty()),
+                None => pattern == domain,
+            }
+        })
+    }
+}
+
+/// The SAML binding used to send authentication requests to the identity
+/// provider
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum SamlBinding {
+    /// Redirect the user agent to the identity provider, with the request in
+    /// the query string
+    #[default]
+    Redirect,
+
+    /// Make the user agent POST the request to the identity provider
+    Post,
+}
+
+/// The settings of an upstream SAML 2.0 identity provider
+///
+/// The private key of the service provider is not part of this, as it is
+/// stored encrypted in the client secret of the provider.
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SamlConfig {
+    /// The entity ID of the identity provider
+    pub idp_entity_id: String,
+
+    /// The URL of the single sign-on service of the identity provider
+    pub sso_url: Url,
+
+    /// The binding to use to send authentication requests
+    #[serde(default)]
+    pub sso_binding: SamlBinding,
+
+    /// The PEM-encoded certificates trusted to sign responses and assertions
+    pub idp_certificates: Vec<String>,
+
+    /// The PEM-encoded certificate of the service provider
+    #[serde(default)]
+    pub sp_certificate: Option<String>,
+
+    /// Whether authentication requests should be signed
+    #[serde(default)]
+    pub sign_authn_requests: bool,
+
+    /// Whether assertions must be signed, a signed response not being enough
+    #[serde(default)]
+    pub want_assertions_signed: bool,
+
+    /// Whether assertions must be encrypted
+    #[serde(default)]
+    pub want_assertions_encrypted: bool,
+
+    /// Whether assertions encrypted with AES-CBC are accepted
+    #[serde(default)]
+    pub allow_cbc_encryption: bool,
+
+    /// The name identifier format to request
+    #[serde(default)]
+    pub name_id_format: Option<String>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
@@ -326,6 +417,50 @@ pub struct ClaimsImports {
 
     #[serde(default)]
     pub account_name: SubjectPreference,
+
+    /// Template rendering to `true` if the user is allowed to request admin
+    /// access
+    #[serde(default)]
+    pub can_request_admin: SubjectPreference,
+
+    /// Template rendering to `active`, `locked` or `deactivated`
+    #[serde(default)]
+    pub account_status: AccountStatusPreference,
+
+    /// Template rendering to a JSON object of user attributes passed to the
+    /// policy engine
+    #[serde(default)]
+    pub attributes: SubjectPreference,
+
+    #[serde(default)]
+    pub resync: ResyncPreference,
+}
+
+/// When the forced claims imports are re-applied to users who are already
+/// linked to the provider
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub struct ResyncPreference {
+    /// Re-apply the claims imports every time the user logs in through the
+    /// provider
+    #[serde(default)]
+    pub on_login: bool,
+
+    /// Periodically refresh the claims in the background, using the refresh
+    /// token obtained during the last login
+    #[serde(default)]
+    pub in_background: bool,
+}
+
+/// How the status of the account is imported from the upstream provider
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub struct AccountStatusPreference {
+    #[serde(default)]
+    pub template: Option<String>,
+
+    /// Whether the template is allowed to deactivate the account. Otherwise,
+    /// `deactivated` is treated like `locked`.
+    #[serde(default)]
+    pub allow_deactivation: bool,
 }
 
 // XXX: this should have another name
@@ -89,6 +89,7 @@ pub struct Authentication {
 pub enum AuthenticationMethod {
     Password { user_password_id: Ulid },
     UpstreamOAuth2 { upstream_oauth2_session_id: Ulid },
+    Ldap { dn: String },
     Unknown,
 }
 
@@ -22,6 +22,7 @@ use indexmap::IndexMap;
 use mas_axum_utils::InternalError;
 use mas_data_model::{AppVersion, BoxRng, SiteConfig};
 use mas_http::CorsLayerExt;
+use mas_keystore::{Encrypter, Keystore};
 use mas_matrix::HomeserverConnection;
 use mas_policy::PolicyFactory;
 use mas_router::{
@@ -37,10 +38,13 @@ mod model;
 mod params;
 mod response;
 mod schema;
+mod scim;
 mod v1;
 
 use self::call_context::CallContext;
-use crate::passwords::PasswordManager;
+use crate::{
+    ClientCertificateCache, IntrospectionCache, MetadataCache, passwords::PasswordManager,
+};
 
 fn finish(t: TransformOpenApi) -> TransformOpenApi {
     t.title("Matrix Authentication Service admin API")
@@ -64,6 +68,11 @@ fn finish(t: TransformOpenApi) -> TransformOpenApi {
             description: Some("Manage OAuth2 sessions".to_owned()),
             ..Tag::default()
         })
+        .tag(Tag {
+            name: "oauth2-client".to_owned(),
+            description: Some("Manage OAuth 2.0 clients".to_owned()),
+            ..Tag::default()
+        })
         .tag(Tag {
             name: "user".to_owned(),
             description: Some("Manage users".to_owned()),
@@ -97,6 +106,21 @@ fn finish(t: TransformOpenApi) -> TransformOpenApi {
             description: Some("Manage upstream OAuth 2.0 providers".to_owned()),
             ..Tag::default()
         })
+        .tag(Tag {
+            name: "queue".to_owned(),
+            description: Some("Inspect and manage the background job queue".to_owned()),
+            ..Tag::default()
+        })
+        .tag(Tag {
+            name: "signing-key".to_owned(),
+            description: Some("Manage the signing keys rotated by the service".to_owned()),
+            ..Tag::default()
+        })
+        .tag(Tag {
+            name: "stats".to_owned(),
+            description: Some("Usage statistics, computed once a day".to_owned()),
+            ..Tag::default()
+        })
         .security_scheme("oauth2", oauth_security_scheme(None))
         .security_scheme(
             "token",
@@ -166,6 +190,12 @@ where
     Arc<PolicyFactory>: FromRef<S>,
     SiteConfig: FromRef<S>,
     AppVersion: FromRef<S>,
+    reqwest::Client: FromRef<S>,
+    MetadataCache: FromRef<S>,
+    ClientCertificateCache: FromRef<S>,
+    IntrospectionCache: FromRef<S>,
+    Keystore: FromRef<S>,
+    Encrypter: FromRef<S>,
 {
     // We *always* want to explicitly set the possible responses, beacuse the
     // infered ones are not necessarily correct
@@ -216,6 +246,9 @@ where
             ApiDocCallback::route(),
             axum::routing::get(swagger_callback),
         )
+        // The SCIM provisioning API is not part of the OpenAPI spec, as it
+        // follows its own protocol
+        .nest("/scim/v2", self::scim::router())
         .layer(
             CorsLayer::new()
                 .allow_origin(Any)
@@ -14,8 +14,14 @@ use mas_data_model::{
         session::{PersonalSession as DataModelPersonalSession, PersonalSessionOwner},
     },
 };
+use mas_storage::queue::{
+    DeactivateUserJob, DeleteDeviceJob, ExpireInactiveCompatSessionsJob,
+    ExpireInactiveOAuthSessionsJob, ExpireInactiveUserSessionsJob, InsertableJob, JobDetails,
+    JobStatus, ProvisionDeviceJob, ProvisionUserJob, ReactivateUserJob, RotateSigningKeysJob,
+    SendAccountRecoveryEmailsJob, SendEmailAuthenticationCodeJob, SyncDevicesJob, VerifyEmailJob,
+};
 use schemars::JsonSchema;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 use ulid::Ulid;
 use url::Url;
@@ -294,6 +300,112 @@ impl CompatSession {
     }
 }
 
+/// What was unusual about the use of a compatibility access token
+#[derive(Serialize, JsonSchema, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum CompatSessionAnomalyKind {
+    /// The token was used from an IP address outside of the network range the
+    /// session was last active from
+    IpRange,
+
+    /// The token was used by a different client software, operating system or
+    /// kind of device than the one the session was last active from
+    UserAgent,
+}
+
+impl From<mas_data_model::CompatSessionAnomalyKind> for CompatSessionAnomalyKind {
+    fn from(kind: mas_data_model::CompatSessionAnomalyKind) -> Self {
+        match kind {
+            mas_data_model::CompatSessionAnomalyKind::IpRange => Self::IpRange,
+            mas_data_model::CompatSessionAnomalyKind::UserAgent => Self::UserAgent,
+        }
+    }
+}
+
+/// A compatibility access token used from an unusual network or client
+#[derive(Serialize, JsonSchema)]
+pub struct CompatSessionAnomaly {
+    #[serde(skip)]
+    pub id: Ulid,
+
+    /// The ID of the compatibility session the token belongs to
+    #[schemars(with = "super::schema::Ulid")]
+    pub compat_session_id: Ulid,
+
+    /// The ID of the user owning the compatibility session
+    #[schemars(with = "super::schema::Ulid")]
+    pub user_id: Ulid,
+
+    /// When the anomaly was detected
+    pub created_at: DateTime<Utc>,
+
+    /// What was unusual about this use of the token
+    pub kinds: Vec<CompatSessionAnomalyKind>,
+
+    /// The IP address the token was used from, if known
+    pub ip_address: Option<IpAddr>,
+
+    /// The user agent the token was used with, if known
+    pub user_agent: Option<String>,
+
+    /// Whether the use of the token was rejected, or only flagged
+    pub rejected: bool,
+}
+
+impl From<mas_data_model::CompatSessionAnomaly> for CompatSessionAnomaly {
+    fn from(anomaly: mas_data_model::CompatSessionAnomaly) -> Self {
+        Self {
+            id: anomaly.id,
+            compat_session_id: anomaly.compat_session_id,
+            user_id: anomaly.user_id,
+            created_at: anomaly.created_at,
+            kinds: anomaly.kinds.into_iter().map(Into::into).collect(),
+            ip_address: anomaly.ip_address,
+            user_agent: anomaly.user_agent,
+            rejected: anomaly.rejected,
+        }
+    }
+}
+
+impl Resource for CompatSessionAnomaly {
+    const KIND: &'static str = "compat-session-anomaly";
+    const PATH: &'static str = "/api/admin/v1/compat-session-anomalies";
+
+    fn id(&self) -> Ulid {
+        self.id
+    }
+}
+
+impl CompatSessionAnomaly {
+    pub fn samples() -> [Self; 2] {
+        [
+            Self {
+                id: Ulid::from_bytes([0x01; 16]),
+                compat_session_id: Ulid::from_bytes([0x02; 16]),
+                user_id: Ulid::from_bytes([0x03; 16]),
+                created_at: DateTime::default(),
+                kinds: vec![CompatSessionAnomalyKind::IpRange],
+                ip_address: Some([5, 6, 7, 8].into()),
+                user_agent: Some("Mozilla/5.0".to_owned()),
+                rejected: false,
+            },
+            Self {
+                id: Ulid::from_bytes([0x04; 16]),
+                compat_session_id: Ulid::from_bytes([0x02; 16]),
+                user_id: Ulid::from_bytes([0x03; 16]),
+                created_at: DateTime::default(),
+                kinds: vec![
+                    CompatSessionAnomalyKind::IpRange,
+                    CompatSessionAnomalyKind::UserAgent,
+                ],
+                ip_address: Some([9, 10, 11, 12].into()),
+                user_agent: Some("curl/8.0".to_owned()),
+                rejected: true,
+            },
+        ]
+    }
+}
+
 /// A OAuth 2.0 session
 #[derive(Serialize, JsonSchema)]
 pub struct OAuth2Session {
@@ -570,6 +682,117 @@ impl UpstreamOAuthLink {
     }
 }
 
+/// An access token issued by an upstream provider, stored on an upstream
+/// OAuth 2.0 link
+#[derive(Serialize, JsonSchema)]
+pub struct UpstreamOAuthAccessToken {
+    #[serde(skip)]
+    id: Ulid,
+
+    /// The access token, to use against the APIs of the upstream provider
+    access_token: String,
+
+    /// When the access token expires, if known
+    expires_at: Option<DateTime<Utc>>,
+}
+
+impl Resource for UpstreamOAuthAccessToken {
+    const KIND: &'static str = "upstream-oauth-access-token";
+    const PATH: &'static str = "/api/admin/v1/upstream-oauth-links";
+
+    fn id(&self) -> Ulid {
+        self.id
+    }
+
+    fn path(&self) -> String {
+        format!("{}/{}/access-token", Self::PATH, self.id())
+    }
+}
+
+impl UpstreamOAuthAccessToken {
+    /// Create a new access token resource for the given link
+    pub fn new(link_id: Ulid, access_token: String, expires_at: Option<DateTime<Utc>>) -> Self {
+        Self {
+            id: link_id,
+            access_token,
+            expires_at,
+        }
+    }
+
+    /// Samples of upstream access tokens
+    pub fn samples() -> [Self; 1] {
+        [Self {
+            id: Ulid::from_bytes([0x01; 16]),
+            access_token: "upstream-access-token".to_owned(),
+            expires_at: Some(DateTime::default()),
+        }]
+    }
+}
+
+/// A new secret generated for an OAuth 2.0 client
+#[derive(Serialize, JsonSchema)]
+pub struct OAuth2ClientSecret {
+    #[serde(skip)]
+    id: Ulid,
+
+    /// The new client secret. It is only shown once, and can't be retrieved
+    /// later.
+    client_secret: String,
+
+    /// Whether the secret is stored as a one-way hash instead of being
+    /// encrypted
+    hashed: bool,
+
+    /// When the new secret expires, if ever
+    expires_at: Option<DateTime<Utc>>,
+
+    /// Until when the previous secret is still accepted, if at all
+    previous_secret_expires_at: Option<DateTime<Utc>>,
+}
+
+impl Resource for OAuth2ClientSecret {
+    const KIND: &'static str = "oauth2-client-secret";
+    const PATH: &'static str = "/api/admin/v1/oauth2-clients";
+
+    fn id(&self) -> Ulid {
+        self.id
+    }
+
+    fn path(&self) -> String {
+        format!("{}/{}/rotate-secret", Self::PATH, self.id())
+    }
+}
+
+impl OAuth2ClientSecret {
+    /// Create a new client secret resource for the given client
+    pub fn new(
+        client_id: Ulid,
+        client_secret: String,
+        hashed: bool,
+        expires_at: Option<DateTime<Utc>>,
+        previous_secret_expires_at: Option<DateTime<Utc>>,
+    ) -> Self {
+        Self {
+            id: client_id,
+            client_secret,
+            hashed,
+            expires_at,
+            previous_secret_expires_at,
+        }
+    }
+
+    /// Samples of client secrets
+    pub fn samples() -> [Self; 1] {
+        [Self {
+            id: Ulid::from_bytes([0x01; 16]),
+            client_secret: "wbVmUKfBDvGfChpxaTHq3TzJ8gFqQbVw".to_owned(),
+            hashed: true,
+            expires_at: None,
+            previous_secret_expires_at: Some(DateTime::default()),
+        }]
+    }
+}
+
 /// The policy data
 #[derive(Serialize, JsonSchema)]
 pub struct PolicyData {
@@ -779,6 +1002,109 @@ impl UpstreamOAuthProvider {
     }
 }
 
+/// The outcome of a single health check of an upstream OAuth 2.0 provider
+#[derive(Serialize, JsonSchema)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum UpstreamOAuthProviderHealthCheck {
+    /// The check passed
+    Ok,
+
+    /// The check doesn't apply to this provider
+    Skipped,
+
+    /// The check failed
+    Failed {
+        /// A description of the failure
+        error: String,
+    },
+}
+
+impl From<mas_data_model::UpstreamOAuthProviderHealthCheck> for UpstreamOAuthProviderHealthCheck {
+    fn from(check: mas_data_model::UpstreamOAuthProviderHealthCheck) -> Self {
+        match check {
+            mas_data_model::UpstreamOAuthProviderHealthCheck::Ok => Self::Ok,
+            mas_data_model::UpstreamOAuthProviderHealthCheck::Skipped => Self::Skipped,
+            mas_data_model::UpstreamOAuthProviderHealthCheck::Failed { error } => {
+                Self::Failed { error }
+            }
+        }
+    }
+}
+
+/// The outcome of the last health check of an upstream OAuth 2.0 provider
+#[derive(Serialize, JsonSchema)]
+pub struct UpstreamOAuthProviderHealth {
+    #[serde(skip)]
+    id: Ulid,
+
+    /// Whether all the checks passed or were skipped
+    healthy: bool,
+
+    /// When the checks were made
+    checked_at: DateTime<Utc>,
+
+    /// Whether the provider metadata could be discovered
+    discovery: UpstreamOAuthProviderHealthCheck,
+
+    /// Whether the JWKS of the provider could be fetched
+    jwks: UpstreamOAuthProviderHealthCheck,
+
+    /// Whether the token endpoint of the provider is reachable
+    token_endpoint: UpstreamOAuthProviderHealthCheck,
+}
+
+impl From<mas_data_model::UpstreamOAuthProviderHealth> for UpstreamOAuthProviderHealth {
+    fn from(health: mas_data_model::UpstreamOAuthProviderHealth) -> Self {
+        Self {
+            id: health.provider_id,
+            healthy: health.checks.is_healthy(),
+            checked_at: health.checked_at,
+            discovery: health.checks.discovery.into(),
+            jwks: health.checks.jwks.into(),
+            token_endpoint: health.checks.token_endpoint.into(),
+        }
+    }
+}
+
+impl Resource for UpstreamOAuthProviderHealth {
+    const KIND: &'static str = "upstream-oauth-provider-health";
+    const PATH: &'static str = "/api/admin/v1/upstream-oauth-providers";
+
+    fn id(&self) -> Ulid {
+        self.id
+    }
+
+    fn path(&self) -> String {
+        format!("{}/{}/health", Self::PATH, self.id())
+    }
+}
+
+impl UpstreamOAuthProviderHealth {
+    /// Samples of upstream OAuth 2.0 provider health
+    pub fn samples() -> [Self; 2] {
+        [
+            Self {
+                id: Ulid::from_bytes([0x01; 16]),
+                healthy: true,
+                checked_at: DateTime::default(),
+                discovery: UpstreamOAuthProviderHealthCheck::Ok,
+                jwks: UpstreamOAuthProviderHealthCheck::Ok,
+                token_endpoint: UpstreamOAuthProviderHealthCheck::Ok,
+            },
+            Self {
+                id: Ulid::from_bytes([0x02; 16]),
+                healthy: false,
+                checked_at: DateTime::default(),
+                discovery: UpstreamOAuthProviderHealthCheck::Failed {
+                    error: "Request error: connection refused".to_owned(),
+                },
+                jwks: UpstreamOAuthProviderHealthCheck::Skipped,
+                token_endpoint: UpstreamOAuthProviderHealthCheck::Skipped,
+            },
+        ]
+    }
+}
+
 /// An error that shouldn't happen in practice, but suggests database
 /// inconsistency.
 #[derive(Debug, Error)]
@@ -954,3 +1280,231 @@ impl PersonalSession {
         self
     }
 }
+
+/// The status of a job in the job queue
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum QueueJobStatus {
+    /// The job is waiting to be picked up by a worker
+    Available,
+
+    /// The job is currently being processed by a worker
+    Running,
+
+    /// The job has been completed
+    Completed,
+
+    /// The job failed. It may have been retried as a new job.
+    Failed,
+
+    /// The job is scheduled to run at a later date
+    Scheduled,
+
+    /// The worker running the job was lost
+    Lost,
+
+    /// The job was cancelled before it ran
+    Cancelled,
+}
+
+impl std::fmt::Display for QueueJobStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        JobStatus::from(*self).fmt(f)
+    }
+}
+
+impl From<JobStatus> for QueueJobStatus {
+    fn from(status: JobStatus) -> Self {
+        match status {
+            JobStatus::Available => Self::Available,
+            JobStatus::Running => Self::Running,
+            JobStatus::Completed => Self::Completed,
+            JobStatus::Failed => Self::Failed,
+            JobStatus::Scheduled => Self::Scheduled,
+            JobStatus::Lost => Self::Lost,
+            JobStatus::Cancelled => Self::Cancelled,
+        }
+    }
+}
+
+impl From<QueueJobStatus> for JobStatus {
+    fn from(status: QueueJobStatus) -> Self {
+        match status {
+            QueueJobStatus::Available => Self::Available,
+            QueueJobStatus::Running => Self::Running,
+            QueueJobStatus::Completed => Self::Completed,
+            QueueJobStatus::Failed => Self::Failed,
+            QueueJobStatus::Scheduled => Self::Scheduled,
+            QueueJobStatus::Lost => Self::Lost,
+            QueueJobStatus::Cancelled => Self::Cancelled,
+        }
+    }
+}
+
+/// The queues which schedule jobs with a payload that only contains IDs and
+/// flags. Payloads of other queues are redacted, as they may contain secrets
+/// or personal data. Queues which schedule jobs without a payload don't need
+/// to be listed here, as an empty payload is always shown.
+const QUEUES_WITH_VISIBLE_PAYLOAD: &[&str] = &[
+    VerifyEmailJob::QUEUE_NAME,
+    SendEmailAuthenticationCodeJob::QUEUE_NAME,
+    ProvisionUserJob::QUEUE_NAME,
+    ProvisionDeviceJob::QUEUE_NAME,
+    DeleteDeviceJob::QUEUE_NAME,
+    SyncDevicesJob::QUEUE_NAME,
+    DeactivateUserJob::QUEUE_NAME,
+    ReactivateUserJob::QUEUE_NAME,
+    SendAccountRecoveryEmailsJob::QUEUE_NAME,
+    ExpireInactiveOAuthSessionsJob::QUEUE_NAME,
+    ExpireInactiveCompatSessionsJob::QUEUE_NAME,
+    ExpireInactiveUserSessionsJob::QUEUE_NAME,
+    RotateSigningKeysJob::QUEUE_NAME,
+];
+
+/// A job in the job queue
+#[derive(Serialize, JsonSchema)]
+pub struct QueueJob {
+    #[serde(skip)]
+    id: Ulid,
+
+    /// The name of the queue the job was placed on
+    queue_name: String,
+
+    /// The status of the job
+    status: QueueJobStatus,
+
+    /// The payload of the job. This is null if the payload was redacted,
+    /// because it may contain sensitive data.
+    payload: Option<serde_json::Value>,
+
+    /// Which attempt this job is, starting from 0
+    attempt: usize,
+
+    /// When the job was created
+    created_at: DateTime<Utc>,
+
+    /// When the job is scheduled to run. If null, the job was not scheduled
+    /// for later.
+    scheduled_at: Option<DateTime<Utc>>,
+
+    /// When the job was picked up by a worker
+    started_at: Option<DateTime<Utc>>,
+
+    /// When the job was completed
+    completed_at: Option<DateTime<Utc>>,
+
+    /// When the job failed
+    failed_at: Option<DateTime<Utc>>,
+
+    /// The error which made the job fail
+    failed_reason: Option<String>,
+
+    /// When the job was cancelled
+    cancelled_at: Option<DateTime<Utc>>,
+
+    /// The ID of the job which retried this one. If null, the job was not
+    /// retried.
+    #[schemars(with = "Option<super::schema::Ulid>")]
+    next_attempt_id: Option<Ulid>,
+
+    /// The name of the recurring schedule which scheduled this job
+    schedule_name: Option<String>,
+}
+
+impl From<JobDetails> for QueueJob {
+    fn from(job: JobDetails) -> Self {
+        let payload_is_empty = job
+            .payload
+            .as_object()
+            .is_some_and(serde_json::Map::is_empty);
+        let payload = (payload_is_empty
+            || QUEUES_WITH_VISIBLE_PAYLOAD.contains(&job.queue_name.as_str()))
+        .then_some(job.payload);
+
+        Self {
+            id: job.id,
+            queue_name: job.queue_name,
+            status: job.status.into(),
+            payload,
+            attempt: job.attempt,
+            created_at: job.created_at,
+            scheduled_at: job.scheduled_at,
+            started_at: job.started_at,
+            completed_at: job.completed_at,
+            failed_at: job.failed_at,
+            failed_reason: job.failed_reason,
+            cancelled_at: job.cancelled_at,
+            next_attempt_id: job.next_attempt_id,
+            schedule_name: job.schedule_name,
+        }
+    }
+}
+
+impl Resource for QueueJob {
+    const KIND: &'static str = "queue-job";
+    const PATH: &'static str = "/api/admin/v1/queue-jobs";
+
+    fn id(&self) -> Ulid {
+        self.id
+    }
+}
+
+impl QueueJob {
+    /// Samples of queue jobs
+    pub fn samples() -> [Self; 3] {
+        [
+            Self {
+                id: Ulid::from_bytes([0x01; 16]),
+                queue_name: "provision-user".to_owned(),
+                status: QueueJobStatus::Failed,
+                payload: Some(serde_json::json!({
+                    "user_id": "01040G2081040G2081040G2081",
+                })),
+                attempt: 0,
+                created_at: DateTime::default(),
+                scheduled_at: None,
+                started_at: Some(DateTime::default()),
+                completed_at: None,
+                failed_at: Some(DateTime::default()),
+                failed_reason: Some("Failed to provision user on the homeserver".to_owned()),
+                cancelled_at: None,
+                next_attempt_id: Some(Ulid::from_bytes([0x02; 16])),
+                schedule_name: None,
+            },
+            Self {
+                id: Ulid::from_bytes([0x02; 16]),
+                queue_name: "provision-user".to_owned(),
+                status: QueueJobStatus::Completed,
+                payload: Some(serde_json::json!({
+                    "user_id": "01040G2081040G2081040G2081",
+                })),
+                attempt: 1,
+                created_at: DateTime::default(),
+                scheduled_at: Some(DateTime::default()),
+                started_at: Some(DateTime::default()),
+                completed_at: Some(DateTime::default()),
+                failed_at: None,
+                failed_reason: None,
+                cancelled_at: None,
+                next_attempt_id: None,
+                schedule_name: None,
+            },
+            Self {
+                id: Ulid::from_bytes([0x03; 16]),
+                queue_name: "cleanup-queue-jobs".to_owned(),
+                status: QueueJobStatus::Scheduled,
+                payload: Some(serde_json::json!({})),
+                attempt: 0,
+                created_at: DateTime::default(),
+                scheduled_at: Some(DateTime::default() + chrono::Duration::hours(1)),
+                started_at: None,
+                completed_at: None,
+                failed_at: None,
+                failed_reason: None,
+                cancelled_at: None,
+                next_attempt_id: None,
+                schedule_name: Some("cleanup-queue-jobs".to_owned()),
+            },
+        ]
+    }
+}
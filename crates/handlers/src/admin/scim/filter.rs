@@ -0,0 +1,194 @@
+// Copyright 2026 Element Creations Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+// Please see LICENSE files in the repository root for full details.
+
+//! A minimal parser for SCIM filter expressions, as defined in [RFC 7644
+//! section 3.4.2.2](https://datatracker.ietf.org/doc/html/rfc7644#section-3.4.2.2)
+//!
+//! We only support the subset of the grammar which provisioning clients use in
+//! practice to look up an existing user before creating it, which is a single
+//! `eq` comparison on the username or on an email address.
+
+/// A parsed SCIM filter
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Filter {
+    /// `userName eq "alice"`
+    UserName(String),
+
+    /// `emails eq "alice@example.com"`, `emails.value eq "alice@example.com"`
+    /// or `emails[value eq "alice@example.com"]`
+    Email(String),
+}
+
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum FilterError {
+    #[error("Unsupported filter attribute {0:?}")]
+    UnsupportedAttribute(String),
+
+    #[error("Unsupported filter operator {0:?}, only \"eq\" is supported")]
+    UnsupportedOperator(String),
+
+    #[error("Filter value must be a quoted string")]
+    InvalidValue,
+
+    #[error("Invalid filter expression")]
+    Syntax,
+}
+
+/// Parse a JSON-style quoted string, returning the unescaped value and the rest
+/// of the input
+fn parse_quoted(input: &str) -> Result<(String, &str), FilterError> {
+    let input = input.trim_start();
+    if !input.starts_with('"') {
+        return Err(FilterError::InvalidValue);
+    }
+
+    // Find the closing quote, skipping escaped characters
+    let mut escaped = false;
+    for (index, c) in input.char_indices().skip(1) {
+        match c {
+            '\\' if !escaped => escaped = true,
+            '"' if !escaped => {
+                let (literal, rest) = input.split_at(index + 1);
+                let value: String =
+                    serde_json::from_str(literal).map_err(|_| FilterError::InvalidValue)?;
+                return Ok((value, rest));
+            }
+            _ => escaped = false,
+        }
+    }
+
+    Err(FilterError::InvalidValue)
+}
+
+/// Parse an `attribute eq "value"` comparison, returning the attribute name,
+/// the value and the rest of the input
+fn parse_comparison(input: &str) -> Result<(&str, String, &str), FilterError> {
+    let input = input.trim_start();
+    let (attribute, rest) = input
+        .split_once(char::is_whitespace)
+        .ok_or(FilterError::Syntax)?;
+    let rest = rest.trim_start();
+    let (operator, rest) = rest
+        .split_once(char::is_whitespace)
+        .ok_or(FilterError::Syntax)?;
+
+    if !operator.eq_ignore_ascii_case("eq") {
+        return Err(FilterError::UnsupportedOperator(operator.to_owned()));
+    }
+
+    let (value, rest) = parse_quoted(rest)?;
+    Ok((attribute, value, rest))
+}
+
+impl std::str::FromStr for Filter {
+    type Err = FilterError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let input = input.trim();
+
+        // Handle the value path form, `emails[value eq "..."]`
+        if let Some((attribute, inner)) = input.split_once('[') {
+            if !attribute.trim().eq_ignore_ascii_case("emails") {
+                return Err(FilterError::UnsupportedAttribute(
+                    attribute.trim().to_owned(),
+                ));
+            }
+
+            let inner = inner.strip_suffix(']').ok_or(FilterError::Syntax)?;
+            let (sub_attribute, value, rest) = parse_comparison(inner)?;
+            if !sub_attribute.eq_ignore_ascii_case("value") {
+                return Err(FilterError::UnsupportedAttribute(format!(
+                    "emails.{sub_attribute}"
+                )));
+            }
+
+            if !rest.trim().is_empty() {
+                return Err(FilterError::Syntax);
+            }
+
+            return Ok(Self::Email(value));
+        }
+
+        let (attribute, value, rest) = parse_comparison(input)?;
+        if !rest.trim().is_empty() {
+            return Err(FilterError::Syntax);
+        }
+
+        // Attribute names are case-insensitive, and may be prefixed by the
+        // schema URN
+        let attribute = attribute
+            .strip_prefix(super::model::USER_SCHEMA)
+            .and_then(|a| a.strip_prefix(':'))
+            .unwrap_or(attribute);
+
+        if attribute.eq_ignore_ascii_case("userName") {
+            Ok(Self::UserName(value))
+        } else if attribute.eq_ignore_ascii_case("emails")
+            || attribute.eq_ignore_ascii_case("emails.value")
+        {
+            Ok(Self::Email(value))
+        } else {
+            Err(FilterError::UnsupportedAttribute(attribute.to_owned()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_filter() {
+        assert_eq!(
+            r#"userName eq "alice""#.parse(),
+            Ok(Filter::UserName("alice".to_owned()))
+        );
+        assert_eq!(
+            r#"USERNAME EQ "alice""#.parse(),
+            Ok(Filter::UserName("alice".to_owned()))
+        );
+        assert_eq!(
+            r#"urn:ietf:params:scim:schemas:core:2.0:User:userName eq "alice""#.parse(),
+            Ok(Filter::UserName("alice".to_owned()))
+        );
+        assert_eq!(
+            r#"emails eq "alice@example.com""#.parse(),
+            Ok(Filter::Email("alice@example.com".to_owned()))
+        );
+        assert_eq!(
+            r#"emails.value eq "alice@example.com""#.parse(),
+            Ok(Filter::Email("alice@example.com".to_owned()))
+        );
+        assert_eq!(
+            r#"emails[value eq "alice@example.com"]"#.parse(),
+            Ok(Filter::Email("alice@example.com".to_owned()))
+        );
+        assert_eq!(
+            r#"userName eq "with \"quotes\"""#.parse(),
+            Ok(Filter::UserName(r#"with "quotes""#.to_owned()))
+        );
+    }
+
+    #[test]
+    fn test_parse_invalid_filter() {
+        assert_eq!(
+            r#"userName sw "al""#.parse::<Filter>(),
+            Err(FilterError::UnsupportedOperator("sw".to_owned()))
+        );
+        assert_eq!(
+            r#"title eq "boss""#.parse::<Filter>(),
+            Err(FilterError::UnsupportedAttribute("title".to_owned()))
+        );
+        assert_eq!(
+            "userName eq alice".parse::<Filter>(),
+            Err(FilterError::InvalidValue)
+        );
+        assert_eq!(
+            r#"userName eq "alice" and active eq true"#.parse::<Filter>(),
+            Err(FilterError::Syntax)
+        );
+        assert_eq!("userName".parse::<Filter>(), Err(FilterError::Syntax));
+    }
+}
@@ -0,0 +1,175 @@
+// Copyright 2026 Element Creations Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+// Please see LICENSE files in the repository root for full details.
+
+//! A SCIM 2.0 ([RFC 7643], [RFC 7644]) provisioning endpoint for users
+//!
+//! This is served alongside the admin API, and authenticated the same way, so
+//! that HR systems and identity management tools can create, update and
+//! deactivate users without custom glue around the admin API.
+//!
+//! [RFC 7643]: https://datatracker.ietf.org/doc/html/rfc7643
+//! [RFC 7644]: https://datatracker.ietf.org/doc/html/rfc7644
+
+use std::sync::Arc;
+
+use axum::{
+    Router,
+    extract::{FromRef, FromRequestParts},
+    http::HeaderValue,
+    response::{IntoResponse, Response},
+    routing::get,
+};
+use hyper::{StatusCode, header::CONTENT_TYPE};
+use mas_axum_utils::record_error;
+use mas_data_model::BoxRng;
+use mas_matrix::HomeserverConnection;
+use serde::Serialize;
+use ulid::Ulid;
+
+use self::model::{ERROR_SCHEMA, ErrorMessage, SERVICE_PROVIDER_CONFIG_SCHEMA};
+use super::call_context::CallContext;
+use crate::{IntrospectionCache, impl_from_error_for_route};
+
+mod filter;
+mod model;
+mod users;
+
+/// The maximum number of resources returned in a single list response
+const MAX_RESULTS: usize = 100;
+
+pub fn router<S>() -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+    Arc<dyn HomeserverConnection>: FromRef<S>,
+    IntrospectionCache: FromRef<S>,
+    BoxRng: FromRequestParts<S>,
+    CallContext: FromRequestParts<S>,
+{
+    Router::new()
+        .route("/ServiceProviderConfig", get(service_provider_config))
+        .route("/Users", get(self::users::list).post(self::users::create))
+        .route(
+            "/Users/{id}",
+            get(self::users::get)
+                .put(self::users::replace)
+                .patch(self::users::patch)
+                .delete(self::users::delete),
+        )
+}
+
+/// A response serialized as JSON, with the SCIM media type
+pub struct Scim<T>(pub T);
+
+impl<T: Serialize> IntoResponse for Scim<T> {
+    fn into_response(self) -> Response {
+        match serde_json::to_vec(&self.0) {
+            Ok(body) => (
+                [(
+                    CONTENT_TYPE,
+                    HeaderValue::from_static("application/scim+json"),
+                )],
+                body,
+            )
+                .into_response(),
+            Err(e) => RouteError::Internal(Box::new(e)).into_response(),
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum RouteError {
+    #[error(transparent)]
+    Internal(Box<dyn std::error::Error + Send + Sync + 'static>),
+
+    #[error(transparent)]
+    Homeserver(anyhow::Error),
+
+    #[error("User ID {0} not found")]
+    NotFound(Ulid),
+
+    #[error(transparent)]
+    InvalidFilter(#[from] self::filter::FilterError),
+
+    #[error("Invalid request: {0}")]
+    InvalidSyntax(String),
+
+    #[error("Unsupported path {0:?}")]
+    InvalidPath(String),
+
+    #[error("Invalid value for {0:?}")]
+    InvalidValue(&'static str),
+
+    #[error("Username is not valid")]
+    UsernameNotValid,
+
+    #[error("User already exists")]
+    UserAlreadyExists,
+
+    #[error("Username is reserved by the homeserver")]
+    UsernameReserved,
+
+    #[error("Renaming users is not supported")]
+    UsernameImmutable,
+
+    #[error("Email {0:?} is not valid")]
+    EmailNotValid(String),
+
+    #[error("User email {0:?} already in use")]
+    EmailAlreadyInUse(String),
+
+    #[error("The resource has been modified since it was last fetched")]
+    PreconditionFailed,
+}
+
+impl_from_error_for_route!(mas_storage::RepositoryError);
+
+impl IntoResponse for RouteError {
+    fn into_response(self) -> Response {
+        let sentry_event_id = record_error!(self, Self::Internal(_) | Self::Homeserver(_));
+        let (status, scim_type) = match &self {
+            Self::Internal(_) | Self::Homeserver(_) => (StatusCode::INTERNAL_SERVER_ERROR, None),
+            Self::NotFound(_) => (StatusCode::NOT_FOUND, None),
+            Self::InvalidFilter(_) => (StatusCode::BAD_REQUEST, Some("invalidFilter")),
+            Self::InvalidSyntax(_) => (StatusCode::BAD_REQUEST, Some("invalidSyntax")),
+            Self::InvalidPath(_) => (StatusCode::BAD_REQUEST, Some("invalidPath")),
+            Self::InvalidValue(_) | Self::UsernameNotValid | Self::EmailNotValid(_) => {
+                (StatusCode::BAD_REQUEST, Some("invalidValue"))
+            }
+            Self::UsernameImmutable => (StatusCode::BAD_REQUEST, Some("mutability")),
+            Self::UserAlreadyExists | Self::UsernameReserved | Self::EmailAlreadyInUse(_) => {
+                (StatusCode::CONFLICT, Some("uniqueness"))
+            }
+            Self::PreconditionFailed => (StatusCode::PRECONDITION_FAILED, None),
+        };
+
+        let message = ErrorMessage {
+            schemas: [ERROR_SCHEMA],
+            status: status.as_u16().to_string(),
+            scim_type,
+            detail: self.to_string(),
+        };
+
+        (status, sentry_event_id, Scim(message)).into_response()
+    }
+}
+
+#[tracing::instrument(name = "handler.admin.scim.service_provider_config", skip_all)]
+async fn service_provider_config(_ctx: CallContext) -> Scim<serde_json::Value> {
+    Scim(serde_json::json!({
+        "schemas": [SERVICE_PROVIDER_CONFIG_SCHEMA],
+        "patch": { "supported": true },
+        "bulk": { "supported": false, "maxOperations": 0, "maxPayloadSize": 0 },
+        "filter": { "supported": true, "maxResults": MAX_RESULTS },
+        "changePassword": { "supported": false },
+        "sort": { "supported": false },
+        "etag": { "supported": true },
+        "authenticationSchemes": [{
+            "type": "oauthbearertoken",
+            "name": "OAuth Bearer Token",
+            "description": "An access token or personal access token with the urn:mas:admin scope",
+            "primary": true,
+        }],
+    }))
+}
@@ -0,0 +1,264 @@
+// Copyright 2026 Element Creations Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+// Please see LICENSE files in the repository root for full details.
+
+use chrono::{DateTime, Utc};
+use mas_data_model::{User, UserEmail};
+use serde::{Deserialize, Deserializer, Serialize};
+use sha2::{Digest, Sha256};
+use ulid::Ulid;
+
+/// The URN of the core user schema
+pub const USER_SCHEMA: &str = "urn:ietf:params:scim:schemas:core:2.0:User";
+
+/// The URN of the list response message
+pub const LIST_RESPONSE_SCHEMA: &str = "urn:ietf:params:scim:api:messages:2.0:ListResponse";
+
+/// The URN of the patch operation message
+pub const PATCH_OP_SCHEMA: &str = "urn:ietf:params:scim:api:messages:2.0:PatchOp";
+
+/// The URN of the error message
+pub const ERROR_SCHEMA: &str = "urn:ietf:params:scim:api:messages:2.0:Error";
+
+/// The URN of the service provider configuration schema
+pub const SERVICE_PROVIDER_CONFIG_SCHEMA: &str =
+    "urn:ietf:params:scim:schemas:core:2.0:ServiceProviderConfig";
+
+/// An email address attached to a SCIM user
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Email {
+    /// The email address
+    pub value: String,
+
+    /// Whether this is the primary email address
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub primary: bool,
+}
+
+/// Metadata about a SCIM resource
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Meta {
+    resource_type: &'static str,
+    created: DateTime<Utc>,
+    location: String,
+    version: String,
+}
+
+/// A user, as represented in SCIM
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ScimUser {
+    schemas: [&'static str; 1],
+    id: Ulid,
+    user_name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    display_name: Option<String>,
+    active: bool,
+    emails: Vec<Email>,
+    meta: Meta,
+}
+
+/// Compute the strong entity tag of a user, from the attributes we expose
+pub fn etag(user: &User, emails: &[UserEmail], display_name: Option<&str>) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(user.id.to_bytes());
+    hasher.update(user.username.as_bytes());
+    hasher.update([u8::from(user.locked_at.is_some())]);
+    hasher.update([u8::from(user.deactivated_at.is_some())]);
+
+    // Prefix the display name with a marker, so that an unset display name and
+    // an empty one give different tags
+    match display_name {
+        Some(display_name) => {
+            hasher.update([1]);
+            hasher.update(display_name.as_bytes());
+        }
+        None => hasher.update([0]),
+    }
+
+    let mut emails: Vec<&str> = emails.iter().map(|e| e.email.as_str()).collect();
+    emails.sort_unstable();
+    for email in emails {
+        hasher.update([0]);
+        hasher.update(email.as_bytes());
+    }
+
+    let digest = hasher.finalize();
+    format!("\"{}\"", hex::encode(&digest[..16]))
+}
+
+impl ScimUser {
+    pub fn new(user: User, emails: Vec<UserEmail>, display_name: Option<String>) -> Self {
+        let version = etag(&user, &emails, display_name.as_deref());
+        let active = user.is_valid();
+        // We don't have a notion of primary email address, so we never set the
+        // `primary` flag
+        let emails = emails
+            .into_iter()
+            .map(|email| Email {
+                value: email.email,
+                primary: false,
+            })
+            .collect();
+
+        Self {
+            schemas: [USER_SCHEMA],
+            id: user.id,
+            user_name: user.username,
+            display_name,
+            active,
+            emails,
+            meta: Meta {
+                resource_type: "User",
+                created: user.created_at,
+                location: format!("/scim/v2/Users/{}", user.id),
+                version,
+            },
+        }
+    }
+
+    pub fn location(&self) -> &str {
+        &self.meta.location
+    }
+
+    pub fn version(&self) -> &str {
+        &self.meta.version
+    }
+}
+
+/// A paginated list of resources
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ListResponse<T> {
+    schemas: [&'static str; 1],
+    total_results: usize,
+    start_index: usize,
+    items_per_page: usize,
+    #[serde(rename = "Resources")]
+    resources: Vec<T>,
+}
+
+impl<T> ListResponse<T> {
+    pub fn new(resources: Vec<T>, total_results: usize, start_index: usize) -> Self {
+        Self {
+            schemas: [LIST_RESPONSE_SCHEMA],
+            total_results,
+            start_index,
+            items_per_page: resources.len(),
+            resources,
+        }
+    }
+}
+
+/// Deserialize a boolean which may be sent as a string.
+///
+/// Some provisioning clients (notably Microsoft Entra ID) send `"True"` and
+/// `"False"` instead of JSON booleans.
+fn lenient_bool<'de, D>(deserializer: D) -> Result<Option<bool>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum BoolOrString {
+        Bool(bool),
+        String(String),
+    }
+
+    match Option::<BoolOrString>::deserialize(deserializer)? {
+        None => Ok(None),
+        Some(BoolOrString::Bool(b)) => Ok(Some(b)),
+        Some(BoolOrString::String(s)) if s.eq_ignore_ascii_case("true") => Ok(Some(true)),
+        Some(BoolOrString::String(s)) if s.eq_ignore_ascii_case("false") => Ok(Some(false)),
+        Some(BoolOrString::String(s)) => Err(serde::de::Error::invalid_value(
+            serde::de::Unexpected::Str(&s),
+            &"a boolean",
+        )),
+    }
+}
+
+/// The body of a `POST /Users` or `PUT /Users/{id}` request
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct UserRequest {
+    pub user_name: String,
+
+    #[serde(default, deserialize_with = "lenient_bool")]
+    pub active: Option<bool>,
+
+    #[serde(default)]
+    pub emails: Vec<Email>,
+
+    #[serde(default)]
+    pub display_name: Option<String>,
+}
+
+/// A single operation in a `PATCH` request
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct PatchOperation {
+    pub op: PatchOp,
+
+    #[serde(default)]
+    pub path: Option<String>,
+
+    #[serde(default)]
+    pub value: Option<serde_json::Value>,
+}
+
+/// The kind of a patch operation
+///
+/// The RFC says those are case-insensitive, and some clients do send them
+/// capitalized
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PatchOp {
+    #[serde(rename = "add", alias = "Add", alias = "ADD")]
+    Add,
+
+    #[serde(rename = "replace", alias = "Replace", alias = "REPLACE")]
+    Replace,
+
+    #[serde(rename = "remove", alias = "Remove", alias = "REMOVE")]
+    Remove,
+}
+
+/// The body of a `PATCH /Users/{id}` request
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct PatchRequest {
+    pub schemas: Vec<String>,
+
+    #[serde(rename = "Operations")]
+    pub operations: Vec<PatchOperation>,
+}
+
+/// The attributes which can be set through a path-less `replace` or `add`
+/// operation
+#[derive(Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct PatchValue {
+    #[serde(default)]
+    pub user_name: Option<String>,
+
+    #[serde(default, deserialize_with = "lenient_bool")]
+    pub active: Option<bool>,
+
+    #[serde(default)]
+    pub emails: Option<Vec<Email>>,
+
+    #[serde(default)]
+    pub display_name: Option<String>,
+}
+
+/// A SCIM error message
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ErrorMessage {
+    pub schemas: [&'static str; 1],
+    pub status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scim_type: Option<&'static str>,
+    pub detail: String,
+}
@@ -0,0 +1,905 @@
+// Copyright 2026 Element Creations Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+// Please see LICENSE files in the repository root for full details.
+
+use std::{str::FromStr as _, sync::Arc};
+
+use axum::{
+    Json,
+    extract::{Path, State},
+    response::IntoResponse,
+};
+use axum_extra::extract::Query;
+use hyper::{
+    HeaderMap, StatusCode,
+    header::{ETAG, IF_MATCH, LOCATION},
+};
+use mas_data_model::{BoxClock, BoxRng, User, UserEmail};
+use mas_matrix::HomeserverConnection;
+use mas_storage::{
+    BoxRepository, Pagination,
+    queue::{DeactivateUserJob, ProvisionUserJob, QueueJobRepositoryExt as _},
+    user::{UserEmailFilter, UserFilter},
+};
+use serde::Deserialize;
+use tracing::{debug, info};
+use ulid::Ulid;
+
+use super::{
+    MAX_RESULTS, RouteError, Scim,
+    filter::Filter,
+    model::{Email, ListResponse, PATCH_OP_SCHEMA, PatchOp, PatchRequest, PatchValue, ScimUser},
+};
+use crate::{
+    IntrospectionCache,
+    admin::{call_context::CallContext, v1::username_valid},
+};
+
+/// Query parameters of the `GET /Users` endpoint
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ListParams {
+    filter: Option<String>,
+    start_index: Option<usize>,
+    count: Option<usize>,
+}
+
+/// The state of a user we want to reach, after applying a `PUT` or `PATCH`
+/// request
+#[derive(Debug)]
+struct Target {
+    active: bool,
+    emails: Vec<String>,
+    display_name: Option<String>,
+
+    /// Whether to provision the user on the homeserver even if nothing
+    /// relevant changed
+    provision: bool,
+}
+
+impl Target {
+    fn from_current(user: &User, emails: &[UserEmail]) -> Self {
+        Self {
+            active: user.is_valid(),
+            emails: emails.iter().map(|e| e.email.clone()).collect(),
+            display_name: None,
+            provision: false,
+        }
+    }
+
+    fn add_emails(&mut self, emails: Vec<Email>) {
+        for email in emails {
+            if !self
+                .emails
+                .iter()
+                .any(|e| e.eq_ignore_ascii_case(&email.value))
+            {
+                self.emails.push(email.value);
+            }
+        }
+    }
+
+    fn set_emails(&mut self, emails: Vec<Email>) {
+        self.emails.clear();
+        self.add_emails(emails);
+    }
+}
+
+/// Parse a boolean value in a patch operation, accepting strings like some
+/// clients send
+fn parse_bool(value: Option<&serde_json::Value>) -> Option<bool> {
+    match value? {
+        serde_json::Value::Bool(b) => Some(*b),
+        serde_json::Value::String(s) if s.eq_ignore_ascii_case("true") => Some(true),
+        serde_json::Value::String(s) if s.eq_ignore_ascii_case("false") => Some(false),
+        _ => None,
+    }
+}
+
+/// Check the `If-Match` header against the current version of the resource
+///
+/// `If-Match` uses the strong comparison function, so weak entity tags never
+/// match, even if their opaque part is the same.
+fn check_precondition(headers: &HeaderMap, current: &str) -> Result<(), RouteError> {
+    let Some(if_match) = headers.get(IF_MATCH) else {
+        return Ok(());
+    };
+
+    let if_match = if_match
+        .to_str()
+        .map_err(|_| RouteError::PreconditionFailed)?;
+    let matches = if_match
+        .split(',')
+        .map(str::trim)
+        .filter(|tag| !tag.starts_with("W/"))
+        .any(|tag| tag == "*" || tag == current);
+
+    if matches {
+        Ok(())
+    } else {
+        Err(RouteError::PreconditionFailed)
+    }
+}
+
+/// Respond with a single user, with its `ETag` and `Location` headers set
+fn user_response(status: StatusCode, user: ScimUser) -> impl IntoResponse {
+    let location = user.location().to_owned();
+    let etag = user.version().to_owned();
+    (status, [(ETAG, etag), (LOCATION, location)], Scim(user))
+}
+
+async fn load_user(
+    repo: &mut BoxRepository,
+    id: Ulid,
+) -> Result<(User, Vec<UserEmail>), RouteError> {
+    let user = repo
+        .user()
+        .lookup(id)
+        .await?
+        .ok_or(RouteError::NotFound(id))?;
+    let emails = repo.user_email().all(&user).await?;
+    Ok((user, emails))
+}
+
+/// Get the display name of a user from the homeserver, which is where it is
+/// stored
+///
+/// Users which were not provisioned yet don't have one, so errors are treated
+/// as an unset display name.
+async fn fetch_display_name(homeserver: &dyn HomeserverConnection, user: &User) -> Option<String> {
+    match homeserver.query_user(&user.username).await {
+        Ok(matrix_user) => matrix_user.displayname,
+        Err(e) => {
+            debug!(
+                %user.id,
+                error = &*e as &dyn std::error::Error,
+                "Could not query the user on the homeserver"
+            );
+            None
+        }
+    }
+}
+
+/// Reactivate a user on the homeserver, once the transaction which
+/// reactivated it on our side was committed
+async fn reactivate_on_homeserver(
+    homeserver: &dyn HomeserverConnection,
+    user: &User,
+    reactivated: bool,
+) -> Result<(), RouteError> {
+    if reactivated {
+        homeserver
+            .reactivate_user(&user.username)
+            .await
+            .map_err(RouteError::Homeserver)?;
+    }
+
+    Ok(())
+}
+
+/// Apply the target state to a user, returning the updated user, its email
+/// addresses, and whether it was reactivated and must also be reactivated on
+/// the homeserver once the changes are saved
+async fn apply(
+    repo: &mut BoxRepository,
+    rng: &mut BoxRng,
+    clock: &BoxClock,
+    mut user: User,
+    current_emails: Vec<UserEmail>,
+    target: Target,
+) -> Result<(User, Vec<UserEmail>, bool), RouteError> {
+    // Validate all the new email addresses before changing anything
+    for email in &target.emails {
+        if lettre::Address::from_str(email).is_err() {
+            return Err(RouteError::EmailNotValid(email.clone()));
+        }
+    }
+
+    let mut emails_changed = false;
+    for email in current_emails {
+        if target
+            .emails
+            .iter()
+            .any(|e| e.eq_ignore_ascii_case(&email.email))
+        {
+            continue;
+        }
+
+        info!(%user.id, user_email.id = %email.id, "Removing email address through SCIM");
+        repo.user_email().remove(email).await?;
+        emails_changed = true;
+    }
+
+    for email in target.emails {
+        if repo.user_email().find(&user, &email).await?.is_some() {
+            continue;
+        }
+
+        let count = repo
+            .user_email()
+            .count(UserEmailFilter::new().for_email(&email))
+            .await?;
+        if count > 0 {
+            return Err(RouteError::EmailAlreadyInUse(email));
+        }
+
+        let user_email = repo.user_email().add(rng, clock, &user, email).await?;
+        info!(%user.id, user_email.id = %user_email.id, "Added email address through SCIM");
+        emails_changed = true;
+    }
+
+    let mut reactivated = false;
+    if target.active && !user.is_valid() {
+        if user.deactivated_at.is_some() {
+            user = repo.user().reactivate(user).await?;
+            reactivated = true;
+            info!(%user.id, "Reactivated user through SCIM");
+        }
+
+        user = repo.user().unlock(user).await?;
+        info!(%user.id, "Unlocked user through SCIM");
+    } else if !target.active && user.is_valid() {
+        user = repo.user().lock(clock, user).await?;
+        info!(%user.id, "Locked user through SCIM");
+    }
+
+    if target.provision || emails_changed || target.display_name.is_some() {
+        let mut job = ProvisionUserJob::new(&user);
+        if let Some(display_name) = target.display_name {
+            job = job.set_display_name(display_name);
+        }
+        repo.queue_job().schedule_job(rng, clock, job).await?;
+    }
+
+    let emails = repo.user_email().all(&user).await?;
+    Ok((user, emails, reactivated))
+}
+
+#[tracing::instrument(name = "handler.admin.scim.users.list", skip_all)]
+pub async fn list(
+    CallContext { mut repo, .. }: CallContext,
+    State(homeserver): State<Arc<dyn HomeserverConnection>>,
+    Query(params): Query<ListParams>,
+) -> Result<impl IntoResponse, RouteError> {
+    let start_index = params.start_index.unwrap_or(1).max(1);
+    let count = params.count.unwrap_or(MAX_RESULTS).min(MAX_RESULTS);
+    let filter = params
+        .filter
+        .as_deref()
+        .map(str::parse::<Filter>)
+        .transpose()?;
+
+    let (users, total) = match filter {
+        Some(Filter::UserName(username)) => {
+            let users: Vec<User> = repo
+                .user()
+                .find_by_username(&username)
+                .await?
+                .into_iter()
+                .collect();
+            let total = users.len();
+            let users = users
+                .into_iter()
+                .skip(start_index - 1)
+                .take(count)
+                .collect();
+            (users, total)
+        }
+
+        Some(Filter::Email(email)) => {
+            let page = repo
+                .user_email()
+                .list(
+                    UserEmailFilter::new().for_email(&email),
+                    Pagination::first(MAX_RESULTS),
+                )
+                .await?;
+
+            let mut users = Vec::with_capacity(page.edges.len());
+            for edge in page.edges {
+                let user_id = edge.node.user_id;
+                if users.iter().any(|u: &User| u.id == user_id) {
+                    continue;
+                }
+
+                if let Some(user) = repo.user().lookup(user_id).await? {
+                    users.push(user);
+                }
+            }
+
+            let total = users.len();
+            let users = users
+                .into_iter()
+                .skip(start_index - 1)
+                .take(count)
+                .collect();
+            (users, total)
+        }
+
+        None => {
+            let total = repo.user().count(UserFilter::new()).await?;
+
+            // SCIM uses index-based pagination, when we use cursors. Walk
+            // through the users to find the cursor corresponding to the start
+            // index
+            let mut to_skip = start_index - 1;
+            let mut cursor = None;
+            while to_skip > 0 {
+                let mut pagination = Pagination::first(to_skip.min(1000));
+                if let Some(cursor) = cursor {
+                    pagination = pagination.after(cursor);
+                }
+
+                let page = repo.user().list(UserFilter::new(), pagination).await?;
+                to_skip -= page.edges.len();
+                cursor = page.edges.last().map(|edge| edge.cursor);
+                if !page.has_next_page {
+                    break;
+                }
+            }
+
+            let users = if to_skip > 0 {
+                // We went past the end of the list
+                Vec::new()
+            } else {
+                let mut pagination = Pagination::first(count);
+                if let Some(cursor) = cursor {
+                    pagination = pagination.after(cursor);
+                }
+
+                repo.user()
+                    .list(UserFilter::new(), pagination)
+                    .await?
+                    .edges
+                    .into_iter()
+                    .map(|edge| edge.node)
+                    .collect()
+            };
+
+            (users, total)
+        }
+    };
+
+    let mut resources = Vec::with_capacity(users.len());
+    for user in users {
+        let emails = repo.user_email().all(&user).await?;
+        let display_name = fetch_display_name(&*homeserver, &user).await;
+        resources.push(ScimUser::new(user, emails, display_name));
+    }
+
+    Ok(Scim(ListResponse::new(resources, total, start_index)))
+}
+
+#[tracing::instrument(name = "handler.admin.scim.users.get", skip_all)]
+pub async fn get(
+    CallContext { mut repo, .. }: CallContext,
+    State(homeserver): State<Arc<dyn HomeserverConnection>>,
+    Path(id): Path<Ulid>,
+) -> Result<impl IntoResponse, RouteError> {
+    let (user, emails) = load_user(&mut repo, id).await?;
+    let display_name = fetch_display_name(&*homeserver, &user).await;
+    Ok(user_response(
+        StatusCode::OK,
+        ScimUser::new(user, emails, display_name),
+    ))
+}
+
+#[tracing::instrument(name = "handler.admin.scim.users.create", skip_all)]
+pub async fn create(
+    CallContext {
+        mut repo, clock, ..
+    }: CallContext,
+    mut rng: BoxRng,
+    State(homeserver): State<Arc<dyn HomeserverConnection>>,
+    Json(params): Json<super::model::UserRequest>,
+) -> Result<impl IntoResponse, RouteError> {
+    if !username_valid(&params.user_name) {
+        return Err(RouteError::UsernameNotValid);
+    }
+
+    if repo.user().exists(&params.user_name).await? {
+        return Err(RouteError::UserAlreadyExists);
+    }
+
+    let available = homeserver
+        .is_localpart_available(&params.user_name)
+        .await
+        .map_err(RouteError::Homeserver)?;
+    if !available {
+        return Err(RouteError::UsernameReserved);
+    }
+
+    let user = repo.user().add(&mut rng, &clock, params.user_name).await?;
+    info!(%user.id, "Created user through SCIM");
+
+    let mut target = Target::from_current(&user, &[]);
+    target.set_emails(params.emails);
+    target.active = params.active.unwrap_or(true);
+    target.display_name = params.display_name;
+    target.provision = true;
+
+    // The display name is set on the homeserver by the provisioning job
+    let display_name = target.display_name.clone();
+    let (user, emails, _) = apply(&mut repo, &mut rng, &clock, user, Vec::new(), target).await?;
+
+    repo.save().await?;
+
+    Ok(user_response(
+        StatusCode::CREATED,
+        ScimUser::new(user, emails, display_name),
+    ))
+}
+
+#[tracing::instrument(name = "handler.admin.scim.users.replace", skip_all)]
+pub async fn replace(
+    CallContext {
+        mut repo, clock, ..
+    }: CallContext,
+    mut rng: BoxRng,
+    State(homeserver): State<Arc<dyn HomeserverConnection>>,
+    State(introspection_cache): State<IntrospectionCache>,
+    Path(id): Path<Ulid>,
+    headers: HeaderMap,
+    Json(params): Json<super::model::UserRequest>,
+) -> Result<impl IntoResponse, RouteError> {
+    let (user, emails) = load_user(&mut repo, id).await?;
+    let current_display_name = fetch_display_name(&*homeserver, &user).await;
+    check_precondition(
+        &headers,
+        &super::model::etag(&user, &emails, current_display_name.as_deref()),
+    )?;
+
+    if params.user_name != user.username {
+        return Err(RouteError::UsernameImmutable);
+    }
+
+    let mut target = Target::from_current(&user, &emails);
+    target.set_emails(params.emails);
+    // Unlike on creation, an absent `active` keeps the current state, so that
+    // replacing the attributes of a locked user doesn't unlock them
+    if let Some(active) = params.active {
+        target.active = active;
+    }
+    target.display_name = params.display_name;
+
+    // The display name is set on the homeserver by the provisioning job
+    let display_name = target.display_name.clone().or(current_display_name);
+    let (user, emails, reactivated) =
+        apply(&mut repo, &mut rng, &clock, user, emails, target).await?;
+
+    repo.save().await?;
+
+    if !user.is_valid() {
+        // The user might just have been locked, and can't use their tokens anymore
+        introspection_cache.invalidate_user(user.id).await;
+    }
+
+    reactivate_on_homeserver(&*homeserver, &user, reactivated).await?;
+
+    Ok(user_response(
+        StatusCode::OK,
+        ScimUser::new(user, emails, display_name),
+    ))
+}
+
+#[tracing::instrument(name = "handler.admin.scim.users.patch", skip_all)]
+pub async fn patch(
+    CallContext {
+        mut repo, clock, ..
+    }: CallContext,
+    mut rng: BoxRng,
+    State(homeserver): State<Arc<dyn HomeserverConnection>>,
+    State(introspection_cache): State<IntrospectionCache>,
+    Path(id): Path<Ulid>,
+    headers: HeaderMap,
+    Json(params): Json<PatchRequest>,
+) -> Result<impl IntoResponse, RouteError> {
+    if !params.schemas.iter().any(|s| s == PATCH_OP_SCHEMA) {
+        return Err(RouteError::InvalidSyntax(format!(
+            "expected the {PATCH_OP_SCHEMA} schema"
+        )));
+    }
+
+    let (user, emails) = load_user(&mut repo, id).await?;
+    let current_display_name = fetch_display_name(&*homeserver, &user).await;
+    check_precondition(
+        &headers,
+        &super::model::etag(&user, &emails, current_display_name.as_deref()),
+    )?;
+
+    let mut target = Target::from_current(&user, &emails);
+
+    for operation in params.operations {
+        let path = operation.path.as_deref().map(str::trim);
+        match (operation.op, path) {
+            // Path-less operations carry a partial resource as value
+            (PatchOp::Add | PatchOp::Replace, None) => {
+                let value: PatchValue = operation
+                    .value
+                    .map(serde_json::from_value)
+                    .transpose()
+                    .map_err(|e| RouteError::InvalidSyntax(e.to_string()))?
+                    .unwrap_or_default();
+
+                if let Some(user_name) = value.user_name
+                    && user_name != user.username
+                {
+                    return Err(RouteError::UsernameImmutable);
+                }
+
+                if let Some(active) = value.active {
+                    target.active = active;
+                }
+
+                if let Some(emails) = value.emails {
+                    if operation.op == PatchOp::Add {
+                        target.add_emails(emails);
+                    } else {
+                        target.set_emails(emails);
+                    }
+                }
+
+                if let Some(display_name) = value.display_name {
+                    target.display_name = Some(display_name);
+                }
+            }
+
+            (PatchOp::Add | PatchOp::Replace, Some(path))
+                if path.eq_ignore_ascii_case("active") =>
+            {
+                target.active = parse_bool(operation.value.as_ref())
+                    .ok_or(RouteError::InvalidValue("active"))?;
+            }
+
+            (PatchOp::Add | PatchOp::Replace, Some(path))
+                if path.eq_ignore_ascii_case("displayName") =>
+            {
+                let Some(serde_json::Value::String(display_name)) = operation.value else {
+                    return Err(RouteError::InvalidValue("displayName"));
+                };
+                target.display_name = Some(display_name);
+            }
+
+            (PatchOp::Add | PatchOp::Replace, Some(path))
+                if path.eq_ignore_ascii_case("userName") =>
+            {
+                if operation.value.as_ref().and_then(serde_json::Value::as_str)
+                    != Some(user.username.as_str())
+                {
+                    return Err(RouteError::UsernameImmutable);
+                }
+            }
+
+            (op @ (PatchOp::Add | PatchOp::Replace), Some(path))
+                if path.eq_ignore_ascii_case("emails") =>
+            {
+                let emails: Vec<Email> = operation
+                    .value
+                    .map(serde_json::from_value)
+                    .transpose()
+                    .map_err(|_| RouteError::InvalidValue("emails"))?
+                    .unwrap_or_default();
+
+                if op == PatchOp::Add {
+                    target.add_emails(emails);
+                } else {
+                    target.set_emails(emails);
+                }
+            }
+
+            (PatchOp::Remove, Some(path)) if path.eq_ignore_ascii_case("emails") => {
+                target.emails.clear();
+            }
+
+            (PatchOp::Remove, Some(path)) => {
+                // The only other thing we can remove is a single email address,
+                // addressed with a value filter
+                let Ok(Filter::Email(email)) = path.parse() else {
+                    return Err(RouteError::InvalidPath(path.to_owned()));
+                };
+                target.emails.retain(|e| !e.eq_ignore_ascii_case(&email));
+            }
+
+            (_, Some(path)) => return Err(RouteError::InvalidPath(path.to_owned())),
+
+            (PatchOp::Remove, None) => {
+                return Err(RouteError::InvalidPath(String::new()));
+            }
+        }
+    }
+
+    // The display name is set on the homeserver by the provisioning job
+    let display_name = target.display_name.clone().or(current_display_name);
+    let (user, emails, reactivated) =
+        apply(&mut repo, &mut rng, &clock, user, emails, target).await?;
+
+    repo.save().await?;
+
+    if !user.is_valid() {
+        // The user might just have been locked, and can't use their tokens anymore
+        introspection_cache.invalidate_user(user.id).await;
+    }
+
+    reactivate_on_homeserver(&*homeserver, &user, reactivated).await?;
+
+    Ok(user_response(
+        StatusCode::OK,
+        ScimUser::new(user, emails, display_name),
+    ))
+}
+
+#[tracing::instrument(name = "handler.admin.scim.users.delete", skip_all)]
+pub async fn delete(
+    CallContext {
+        mut repo, clock, ..
+    }: CallContext,
+    mut rng: BoxRng,
+    State(homeserver): State<Arc<dyn HomeserverConnection>>,
+    State(introspection_cache): State<IntrospectionCache>,
+    Path(id): Path<Ulid>,
+    headers: HeaderMap,
+) -> Result<StatusCode, RouteError> {
+    let (user, emails) = load_user(&mut repo, id).await?;
+    let current_display_name = fetch_display_name(&*homeserver, &user).await;
+    check_precondition(
+        &headers,
+        &super::model::etag(&user, &emails, current_display_name.as_deref()),
+    )?;
+
+    // We never delete users, we deactivate them, like the admin API does
+    let user = repo.user().deactivate(&clock, user).await?;
+
+    info!(%user.id, "Scheduling deactivation of user through SCIM");
+    repo.queue_job()
+        .schedule_job(&mut rng, &clock, DeactivateUserJob::new(&user, true))
+        .await?;
+
+    repo.save().await?;
+
+    introspection_cache.invalidate_user(user.id).await;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[cfg(test)]
+mod tests {
+    use hyper::{Request, StatusCode};
+    use mas_storage::{RepositoryAccess, user::UserRepository};
+    use sqlx::PgPool;
+
+    use crate::test_utils::{RequestBuilderExt, ResponseExt, TestState, setup};
+
+    #[sqlx::test(migrator = "mas_storage_pg::MIGRATOR")]
+    async fn test_create_and_get_user(pool: PgPool) {
+        setup();
+        let mut state = TestState::from_pool(pool).await.unwrap();
+        let token = state.token_with_scope("urn:mas:admin").await;
+
+        let request = Request::post("/scim/v2/Users")
+            .bearer(&token)
+            .json(serde_json::json!({
+                "schemas": ["urn:ietf:params:scim:schemas:core:2.0:User"],
+                "userName": "alice",
+                "emails": [{ "value": "alice@example.com", "primary": true }],
+                "displayName": "Alice",
+            }));
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::CREATED);
+        assert_eq!(
+            response.headers().get("content-type").unwrap(),
+            "application/scim+json"
+        );
+        let etag = response.headers().get("etag").unwrap().clone();
+        let body: serde_json::Value = response.json();
+        assert_eq!(body["userName"], "alice");
+        assert_eq!(body["active"], true);
+        assert_eq!(body["emails"][0]["value"], "alice@example.com");
+        assert_eq!(body["meta"]["version"], etag.to_str().unwrap());
+        let id = body["id"].as_str().unwrap().to_owned();
+
+        // Creating it again should fail with a uniqueness error
+        let request = Request::post("/scim/v2/Users")
+            .bearer(&token)
+            .json(serde_json::json!({ "userName": "alice" }));
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::CONFLICT);
+        let body: serde_json::Value = response.json();
+        assert_eq!(body["scimType"], "uniqueness");
+
+        // Fetch it back, once it was provisioned with its display name
+        state.run_jobs_in_queue().await;
+        let request = Request::get(format!("/scim/v2/Users/{id}"))
+            .bearer(&token)
+            .empty();
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::OK);
+        assert_eq!(response.headers().get("etag").unwrap(), &etag);
+        let body: serde_json::Value = response.json();
+        assert_eq!(body["displayName"], "Alice");
+
+        // Filter by username and by email
+        for filter in [
+            r#"userName eq "alice""#,
+            r#"emails[value eq "alice@example.com"]"#,
+        ] {
+            let request = Request::get(format!(
+                "/scim/v2/Users?filter={}",
+                url::form_urlencoded::byte_serialize(filter.as_bytes()).collect::<String>()
+            ))
+            .bearer(&token)
+            .empty();
+            let response = state.request(request).await;
+            response.assert_status(StatusCode::OK);
+            let body: serde_json::Value = response.json();
+            assert_eq!(body["totalResults"], 1);
+            assert_eq!(body["Resources"][0]["id"], id);
+        }
+
+        let request = Request::get("/scim/v2/Users?filter=userName%20eq%20%22bob%22")
+            .bearer(&token)
+            .empty();
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::OK);
+        let body: serde_json::Value = response.json();
+        assert_eq!(body["totalResults"], 0);
+    }
+
+    #[sqlx::test(migrator = "mas_storage_pg::MIGRATOR")]
+    async fn test_patch_active(pool: PgPool) {
+        setup();
+        let mut state = TestState::from_pool(pool).await.unwrap();
+        let token = state.token_with_scope("urn:mas:admin").await;
+
+        let mut repo = state.repository().await.unwrap();
+        let user = repo
+            .user()
+            .add(&mut state.rng(), &state.clock, "alice".to_owned())
+            .await
+            .unwrap();
+        repo.save().await.unwrap();
+
+        // Entra ID sends booleans as strings
+        let request = Request::patch(format!("/scim/v2/Users/{}", user.id))
+            .bearer(&token)
+            .json(serde_json::json!({
+                "schemas": ["urn:ietf:params:scim:api:messages:2.0:PatchOp"],
+                "Operations": [{ "op": "Replace", "path": "active", "value": "False" }],
+            }));
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::OK);
+        let etag = response.headers().get("etag").unwrap().clone();
+        let body: serde_json::Value = response.json();
+        assert_eq!(body["active"], false);
+
+        let mut repo = state.repository().await.unwrap();
+        let user = repo.user().lookup(user.id).await.unwrap().unwrap();
+        repo.save().await.unwrap();
+        assert!(user.locked_at.is_some());
+
+        // Replacing the user without the `active` attribute keeps them locked
+        let request = Request::put(format!("/scim/v2/Users/{}", user.id))
+            .bearer(&token)
+            .json(serde_json::json!({
+                "schemas": ["urn:ietf:params:scim:schemas:core:2.0:User"],
+                "userName": "alice",
+            }));
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::OK);
+        let body: serde_json::Value = response.json();
+        assert_eq!(body["active"], false);
+
+        // Using a stale ETag should fail
+        let request = Request::patch(format!("/scim/v2/Users/{}", user.id))
+            .bearer(&token)
+            .header("if-match", r#""stale""#)
+            .json(serde_json::json!({
+                "schemas": ["urn:ietf:params:scim:api:messages:2.0:PatchOp"],
+                "Operations": [{ "op": "replace", "value": { "active": true } }],
+            }));
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::PRECONDITION_FAILED);
+
+        // A weak version of the current one should also fail, as If-Match uses
+        // the strong comparison
+        let request = Request::patch(format!("/scim/v2/Users/{}", user.id))
+            .bearer(&token)
+            .header("if-match", format!("W/{}", etag.to_str().unwrap()))
+            .json(serde_json::json!({
+                "schemas": ["urn:ietf:params:scim:api:messages:2.0:PatchOp"],
+                "Operations": [{ "op": "replace", "value": { "active": true } }],
+            }));
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::PRECONDITION_FAILED);
+
+        // But the current one should work
+        let request = Request::patch(format!("/scim/v2/Users/{}", user.id))
+            .bearer(&token)
+            .header("if-match", etag)
+            .json(serde_json::json!({
+                "schemas": ["urn:ietf:params:scim:api:messages:2.0:PatchOp"],
+                "Operations": [
+                    { "op": "replace", "value": { "active": true } },
+                    { "op": "add", "path": "emails", "value": [{ "value": "alice@example.com" }] },
+                ],
+            }));
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::OK);
+        let body: serde_json::Value = response.json();
+        assert_eq!(body["active"], true);
+        assert_eq!(body["emails"][0]["value"], "alice@example.com");
+
+        // Remove the email through a value filter
+        let request = Request::patch(format!("/scim/v2/Users/{}", user.id))
+            .bearer(&token)
+            .json(serde_json::json!({
+                "schemas": ["urn:ietf:params:scim:api:messages:2.0:PatchOp"],
+                "Operations": [
+                    { "op": "remove", "path": "emails[value eq \"alice@example.com\"]" },
+                ],
+            }));
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::OK);
+        let etag = response.headers().get("etag").unwrap().clone();
+        let body: serde_json::Value = response.json();
+        assert_eq!(body["emails"], serde_json::json!([]));
+
+        // Changing the display name changes the ETag
+        let request = Request::patch(format!("/scim/v2/Users/{}", user.id))
+            .bearer(&token)
+            .header("if-match", etag.clone())
+            .json(serde_json::json!({
+                "schemas": ["urn:ietf:params:scim:api:messages:2.0:PatchOp"],
+                "Operations": [{ "op": "replace", "path": "displayName", "value": "Alice" }],
+            }));
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::OK);
+        assert_ne!(response.headers().get("etag").unwrap(), &etag);
+        let body: serde_json::Value = response.json();
+        assert_eq!(body["displayName"], "Alice");
+
+        // Renaming is not supported
+        let request = Request::patch(format!("/scim/v2/Users/{}", user.id))
+            .bearer(&token)
+            .json(serde_json::json!({
+                "schemas": ["urn:ietf:params:scim:api:messages:2.0:PatchOp"],
+                "Operations": [{ "op": "replace", "path": "userName", "value": "bob" }],
+            }));
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::BAD_REQUEST);
+        let body: serde_json::Value = response.json();
+        assert_eq!(body["scimType"], "mutability");
+    }
+
+    #[sqlx::test(migrator = "mas_storage_pg::MIGRATOR")]
+    async fn test_delete_user(pool: PgPool) {
+        setup();
+        let mut state = TestState::from_pool(pool).await.unwrap();
+        let token = state.token_with_scope("urn:mas:admin").await;
+
+        let mut repo = state.repository().await.unwrap();
+        let user = repo
+            .user()
+            .add(&mut state.rng(), &state.clock, "alice".to_owned())
+            .await
+            .unwrap();
+        repo.save().await.unwrap();
+
+        let request = Request::delete(format!("/scim/v2/Users/{}", user.id))
+            .bearer(&token)
+            .empty();
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::NO_CONTENT);
+
+        let mut repo = state.repository().await.unwrap();
+        let user = repo.user().lookup(user.id).await.unwrap().unwrap();
+        repo.save().await.unwrap();
+        assert!(user.deactivated_at.is_some());
+
+        let request = Request::get("/scim/v2/Users/01040G2081040G2081040G2081")
+            .bearer(&token)
+            .empty();
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::NOT_FOUND);
+    }
+}
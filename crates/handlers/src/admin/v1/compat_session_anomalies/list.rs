@@ -0,0 +1,403 @@
+// Copyright 2026 Element Creations Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+// Please see LICENSE files in the repository root for full details.
+
+use aide::{OperationIo, transform::TransformOperation};
+use axum::{Json, response::IntoResponse};
+use axum_extra::extract::{Query, QueryRejection};
+use axum_macros::FromRequestParts;
+use hyper::StatusCode;
+use mas_axum_utils::record_error;
+use mas_storage::{Page, compat::CompatSessionAnomalyFilter};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use ulid::Ulid;
+
+use crate::{
+    admin::{
+        call_context::CallContext,
+        model::{CompatSessionAnomaly, Resource},
+        params::{IncludeCount, Pagination},
+        response::{ErrorResponse, PaginatedResponse},
+    },
+    impl_from_error_for_route,
+};
+
+#[derive(Deserialize, JsonSchema, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+enum CompatSessionAnomalyStatus {
+    Flagged,
+    Rejected,
+}
+
+impl std::fmt::Display for CompatSessionAnomalyStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Flagged => write!(f, "flagged"),
+            Self::Rejected => write!(f, "rejected"),
+        }
+    }
+}
+
+#[derive(FromRequestParts, Deserialize, JsonSchema, OperationIo)]
+#[serde(rename = "CompatSessionAnomalyFilter")]
+#[aide(input_with = "Query<FilterParams>")]
+#[from_request(via(Query), rejection(RouteError))]
+pub struct FilterParams {
+    /// Retrieve the items for the given user
+    #[serde(rename = "filter[user]")]
+    #[schemars(with = "Option<crate::admin::schema::Ulid>")]
+    user: Option<Ulid>,
+
+    /// Retrieve the items for the given compatibility session
+    #[serde(rename = "filter[compat-session]")]
+    #[schemars(with = "Option<crate::admin::schema::Ulid>")]
+    compat_session: Option<Ulid>,
+
+    /// Retrieve the items with the given status
+    ///
+    /// Defaults to retrieve all anomalies.
+    ///
+    /// * `flagged`: Only retrieve anomalies for which the token was still
+    ///   accepted
+    ///
+    /// * `rejected`: Only retrieve anomalies for which the token was rejected
+    #[serde(rename = "filter[status]")]
+    status: Option<CompatSessionAnomalyStatus>,
+}
+
+impl std::fmt::Display for FilterParams {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut sep = '?';
+
+        if let Some(user) = self.user {
+            write!(f, "{sep}filter[user]={user}")?;
+            sep = '&';
+        }
+
+        if let Some(compat_session) = self.compat_session {
+            write!(f, "{sep}filter[compat-session]={compat_session}")?;
+            sep = '&';
+        }
+
+        if let Some(status) = self.status {
+            write!(f, "{sep}filter[status]={status}")?;
+            sep = '&';
+        }
+
+        let _ = sep;
+        Ok(())
+    }
+}
+
+#[derive(Debug, thiserror::Error, OperationIo)]
+#[aide(output_with = "Json<ErrorResponse>")]
+pub enum RouteError {
+    #[error(transparent)]
+    Internal(Box<dyn std::error::Error + Send + Sync + 'static>),
+
+    #[error("User ID {0} not found")]
+    UserNotFound(Ulid),
+
+    #[error("Compatibility session ID {0} not found")]
+    CompatSessionNotFound(Ulid),
+
+    #[error("Invalid filter parameters")]
+    InvalidFilter(#[from] QueryRejection),
+}
+
+impl_from_error_for_route!(mas_storage::RepositoryError);
+
+impl IntoResponse for RouteError {
+    fn into_response(self) -> axum::response::Response {
+        let error = ErrorResponse::from_error(&self);
+        let sentry_event_id = record_error!(self, RouteError::Internal(_));
+        let status = match &self {
+            Self::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            Self::UserNotFound(_) | Self::CompatSessionNotFound(_) => StatusCode::NOT_FOUND,
+            Self::InvalidFilter(_) => StatusCode::BAD_REQUEST,
+        };
+
+        (status, sentry_event_id, Json(error)).into_response()
+    }
+}
+
+pub fn doc(operation: TransformOperation) -> TransformOperation {
+    operation
+        .id("listCompatSessionAnomalies")
+        .summary("List compatibility session anomalies")
+        .description("Retrieve a list of the times a compatibility access token was used from an unusual network or client, oldest first.
+Use the `filter[status]` parameter to only retrieve the anomalies for which the token was rejected or only flagged.")
+        .tag("compat-session")
+        .response_with::<200, Json<PaginatedResponse<CompatSessionAnomaly>>, _>(|t| {
+            let anomalies = CompatSessionAnomaly::samples();
+            let pagination = mas_storage::Pagination::first(anomalies.len());
+            let page = Page {
+                edges: anomalies
+                    .into_iter()
+                    .map(|node| mas_storage::pagination::Edge {
+                        cursor: node.id(),
+                        node,
+                    })
+                    .collect(),
+                has_next_page: true,
+                has_previous_page: false,
+            };
+
+            t.description("Paginated response of compatibility session anomalies")
+                .example(PaginatedResponse::for_page(
+                    page,
+                    pagination,
+                    Some(42),
+                    CompatSessionAnomaly::PATH,
+                ))
+        })
+        .response_with::<404, RouteError, _>(|t| {
+            let response = ErrorResponse::from_error(&RouteError::UserNotFound(Ulid::nil()));
+            t.description("User was not found").example(response)
+        })
+}
+
+#[tracing::instrument(name = "handler.admin.v1.compat_session_anomalies.list", skip_all)]
+pub async fn handler(
+    CallContext { mut repo, .. }: CallContext,
+    Pagination(pagination, include_count): Pagination,
+    params: FilterParams,
+) -> Result<Json<PaginatedResponse<CompatSessionAnomaly>>, RouteError> {
+    let base = format!("{path}{params}", path = CompatSessionAnomaly::PATH);
+    let base = include_count.add_to_base(&base);
+    let filter = CompatSessionAnomalyFilter::default();
+
+    // Load the user from the filter
+    let user = if let Some(user_id) = params.user {
+        let user = repo
+            .user()
+            .lookup(user_id)
+            .await?
+            .ok_or(RouteError::UserNotFound(user_id))?;
+
+        Some(user)
+    } else {
+        None
+    };
+
+    let filter = match &user {
+        Some(user) => filter.for_user(user),
+        None => filter,
+    };
+
+    let compat_session = if let Some(compat_session_id) = params.compat_session {
+        let compat_session = repo
+            .compat_session()
+            .lookup(compat_session_id)
+            .await?
+            .ok_or(RouteError::CompatSessionNotFound(compat_session_id))?;
+
+        Some(compat_session)
+    } else {
+        None
+    };
+
+    let filter = match &compat_session {
+        Some(compat_session) => filter.for_compat_session(compat_session),
+        None => filter,
+    };
+
+    let filter = match params.status {
+        Some(CompatSessionAnomalyStatus::Flagged) => filter.flagged_only(),
+        Some(CompatSessionAnomalyStatus::Rejected) => filter.rejected_only(),
+        None => filter,
+    };
+
+    let response = match include_count {
+        IncludeCount::True => {
+            let page = repo
+                .compat_session_anomaly()
+                .list(filter, pagination)
+                .await?
+                .map(CompatSessionAnomaly::from);
+            let count = repo.compat_session_anomaly().count(filter).await?;
+            PaginatedResponse::for_page(page, pagination, Some(count), &base)
+        }
+        IncludeCount::False => {
+            let page = repo
+                .compat_session_anomaly()
+                .list(filter, pagination)
+                .await?
+                .map(CompatSessionAnomaly::from);
+            PaginatedResponse::for_page(page, pagination, None, &base)
+        }
+        IncludeCount::Only => {
+            let count = repo.compat_session_anomaly().count(filter).await?;
+            PaginatedResponse::for_count_only(count, &base)
+        }
+    };
+
+    Ok(Json(response))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+    use hyper::{Request, StatusCode};
+    use mas_data_model::{CompatSessionAnomalyKind, Device};
+    use sqlx::PgPool;
+
+    use crate::test_utils::{RequestBuilderExt, ResponseExt, TestState, setup};
+
+    #[sqlx::test(migrator = "mas_storage_pg::MIGRATOR")]
+    async fn test_compat_session_anomaly_list(pool: PgPool) {
+        setup();
+        let mut state = TestState::from_pool(pool).await.unwrap();
+        let token = state.token_with_scope("urn:mas:admin").await;
+        let mut rng = state.rng();
+
+        // Provision two users with a compat session each, one flagged anomaly on
+        // alice's session and one rejected anomaly on bob's
+        let mut repo = state.repository().await.unwrap();
+        let alice = repo
+            .user()
+            .add(&mut rng, &state.clock, "alice".to_owned())
+            .await
+            .unwrap();
+        let bob = repo
+            .user()
+            .add(&mut rng, &state.clock, "bob".to_owned())
+            .await
+            .unwrap();
+
+        let device = Device::generate(&mut rng);
+        let alice_session = repo
+            .compat_session()
+            .add(&mut rng, &state.clock, &alice, device, None, false, None)
+            .await
+            .unwrap();
+        let device = Device::generate(&mut rng);
+        let bob_session = repo
+            .compat_session()
+            .add(&mut rng, &state.clock, &bob, device, None, false, None)
+            .await
+            .unwrap();
+
+        state.clock.advance(Duration::minutes(1));
+        repo.compat_session_anomaly()
+            .add(
+                &mut rng,
+                &state.clock,
+                &alice_session,
+                vec![CompatSessionAnomalyKind::IpRange],
+                Some([198, 51, 100, 1].into()),
+                None,
+                false,
+            )
+            .await
+            .unwrap();
+
+        state.clock.advance(Duration::minutes(1));
+        repo.compat_session_anomaly()
+            .add(
+                &mut rng,
+                &state.clock,
+                &bob_session,
+                vec![
+                    CompatSessionAnomalyKind::IpRange,
+                    CompatSessionAnomalyKind::UserAgent,
+                ],
+                Some([203, 0, 113, 7].into()),
+                Some("curl/8.0".to_owned()),
+                true,
+            )
+            .await
+            .unwrap();
+        repo.save().await.unwrap();
+
+        let request = Request::get("/api/admin/v1/compat-session-anomalies")
+            .bearer(&token)
+            .empty();
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::OK);
+        let body: serde_json::Value = response.json();
+        assert_eq!(body["meta"]["count"], 2);
+        assert_eq!(body["data"][0]["type"], "compat-session-anomaly");
+        assert_eq!(
+            body["data"][0]["attributes"]["compat_session_id"],
+            alice_session.id.to_string()
+        );
+        assert_eq!(
+            body["data"][0]["attributes"]["kinds"],
+            serde_json::json!(["ip_range"])
+        );
+        assert_eq!(body["data"][0]["attributes"]["ip_address"], "198.51.100.1");
+        assert_eq!(body["data"][0]["attributes"]["rejected"], false);
+        assert_eq!(
+            body["data"][1]["attributes"]["kinds"],
+            serde_json::json!(["ip_range", "user_agent"])
+        );
+        assert_eq!(body["data"][1]["attributes"]["user_agent"], "curl/8.0");
+        assert_eq!(body["data"][1]["attributes"]["rejected"], true);
+
+        // Filter by user
+        let request = Request::get(format!(
+            "/api/admin/v1/compat-session-anomalies?filter[user]={}",
+            alice.id
+        ))
+        .bearer(&token)
+        .empty();
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::OK);
+        let body: serde_json::Value = response.json();
+        assert_eq!(body["meta"]["count"], 1);
+        assert_eq!(
+            body["data"][0]["attributes"]["user_id"],
+            alice.id.to_string()
+        );
+
+        // Filter by compat session
+        let request = Request::get(format!(
+            "/api/admin/v1/compat-session-anomalies?filter[compat-session]={}",
+            bob_session.id
+        ))
+        .bearer(&token)
+        .empty();
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::OK);
+        let body: serde_json::Value = response.json();
+        assert_eq!(body["meta"]["count"], 1);
+        assert_eq!(
+            body["data"][0]["attributes"]["compat_session_id"],
+            bob_session.id.to_string()
+        );
+
+        // Filter by status
+        let request =
+            Request::get("/api/admin/v1/compat-session-anomalies?filter[status]=rejected")
+                .bearer(&token)
+                .empty();
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::OK);
+        let body: serde_json::Value = response.json();
+        assert_eq!(body["meta"]["count"], 1);
+        assert_eq!(body["data"][0]["attributes"]["rejected"], true);
+
+        let request = Request::get(
+            "/api/admin/v1/compat-session-anomalies?filter[status]=flagged&count=only",
+        )
+        .bearer(&token)
+        .empty();
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::OK);
+        let body: serde_json::Value = response.json();
+        assert_eq!(body["meta"]["count"], 1);
+
+        // Unknown user
+        let request = Request::get(format!(
+            "/api/admin/v1/compat-session-anomalies?filter[user]={}",
+            ulid::Ulid::nil()
+        ))
+        .bearer(&token)
+        .empty();
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::NOT_FOUND);
+    }
+}
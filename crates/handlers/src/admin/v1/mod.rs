@@ -12,17 +12,26 @@ use aide::axum::{
 };
 use axum::extract::{FromRef, FromRequestParts};
 use mas_data_model::{AppVersion, BoxRng, SiteConfig};
+use mas_keystore::{Encrypter, Keystore};
 use mas_matrix::HomeserverConnection;
 use mas_policy::PolicyFactory;
 
 use super::call_context::CallContext;
-use crate::passwords::PasswordManager;
+use crate::{
+    ClientCertificateCache, IntrospectionCache, MetadataCache, passwords::PasswordManager,
+};
 
+mod compat_session_anomalies;
 mod compat_sessions;
+mod oauth2_clients;
 mod oauth2_sessions;
 mod personal_sessions;
 mod policy_data;
+mod queue_jobs;
+mod queues;
+mod signing_keys;
 mod site_config;
+mod stats;
 mod upstream_oauth_links;
 mod upstream_oauth_providers;
 mod user_emails;
@@ -31,6 +40,8 @@ mod user_sessions;
 mod users;
 mod version;
 
+pub(super) use self::users::username_valid;
+
 pub fn router<S>() -> ApiRouter<S>
 where
     S: Clone + Send + Sync + 'static,
@@ -39,6 +50,12 @@ where
     SiteConfig: FromRef<S>,
     AppVersion: FromRef<S>,
     Arc<PolicyFactory>: FromRef<S>,
+    reqwest::Client: FromRef<S>,
+    MetadataCache: FromRef<S>,
+    ClientCertificateCache: FromRef<S>,
+    IntrospectionCache: FromRef<S>,
+    Keystore: FromRef<S>,
+    Encrypter: FromRef<S>,
     BoxRng: FromRequestParts<S>,
     CallContext: FromRequestParts<S>,
 {
@@ -66,6 +83,20 @@ where
                 self::compat_sessions::finish_doc,
             ),
         )
+        .api_route(
+            "/compat-session-anomalies",
+            get_with(
+                self::compat_session_anomalies::list,
+                self::compat_session_anomalies::list_doc,
+            ),
+        )
+        .api_route(
+            "/oauth2-clients/{id}/rotate-secret",
+            post_with(
+                self::oauth2_clients::rotate_secret,
+                self::oauth2_clients::rotate_secret_doc,
+            ),
+        )
         .api_route(
             "/oauth2-sessions",
             get_with(self::oauth2_sessions::list, self::oauth2_sessions::list_doc),
@@ -133,6 +164,10 @@ where
             get_with(self::users::list, self::users::list_doc)
                 .post_with(self::users::add, self::users::add_doc),
         )
+        .api_route(
+            "/users/import",
+            post_with(self::users::import, self::users::import_doc),
+        )
         .api_route(
             "/users/{id}",
             get_with(self::users::get, self::users::get_doc),
@@ -149,6 +184,17 @@ where
             "/users/{id}/set-admin",
             post_with(self::users::set_admin, self::users::set_admin_doc),
         )
+        .api_route(
+            "/users/{id}/set-display-name",
+            post_with(
+                self::users::set_display_name,
+                self::users::set_display_name_doc,
+            ),
+        )
+        .api_route(
+            "/users/{id}/rename",
+            post_with(self::users::rename, self::users::rename_doc),
+        )
         .api_route(
             "/users/{id}/deactivate",
             post_with(self::users::deactivate, self::users::deactivate_doc),
@@ -245,6 +291,13 @@ where
                 self::upstream_oauth_links::delete_doc,
             ),
         )
+        .api_route(
+            "/upstream-oauth-links/{id}/access-token",
+            post_with(
+                self::upstream_oauth_links::access_token,
+                self::upstream_oauth_links::access_token_doc,
+            ),
+        )
         .api_route(
             "/upstream-oauth-providers",
             get_with(
@@ -259,4 +312,62 @@ where
                 self::upstream_oauth_providers::get_doc,
             ),
         )
+        .api_route(
+            "/upstream-oauth-providers/{id}/health",
+            get_with(
+                self::upstream_oauth_providers::health,
+                self::upstream_oauth_providers::health_doc,
+            ),
+        )
+        .api_route(
+            "/queue-jobs",
+            get_with(self::queue_jobs::list, self::queue_jobs::list_doc),
+        )
+        .api_route(
+            "/queue-jobs/{id}",
+            get_with(self::queue_jobs::get, self::queue_jobs::get_doc),
+        )
+        .api_route(
+            "/queue-jobs/{id}/retry",
+            post_with(self::queue_jobs::retry, self::queue_jobs::retry_doc),
+        )
+        .api_route(
+            "/queue-jobs/{id}/cancel",
+            post_with(self::queue_jobs::cancel, self::queue_jobs::cancel_doc),
+        )
+        .api_route(
+            "/queues/paused",
+            get_with(self::queues::paused, self::queues::paused_doc),
+        )
+        .api_route(
+            "/queues/{name}/pause",
+            post_with(self::queues::pause, self::queues::pause_doc),
+        )
+        .api_route(
+            "/queues/{name}/resume",
+            post_with(self::queues::resume, self::queues::resume_doc),
+        )
+        .api_route(
+            "/signing-keys",
+            get_with(self::signing_keys::list, self::signing_keys::list_doc),
+        )
+        .api_route(
+            "/signing-keys/rotate",
+            post_with(self::signing_keys::rotate, self::signing_keys::rotate_doc),
+        )
+        .api_route(
+            "/stats/users",
+            get_with(self::stats::users, self::stats::users_doc),
+        )
+        .api_route(
+            "/stats/sessions",
+            get_with(self::stats::sessions, self::stats::sessions_doc),
+        )
+        .api_route(
+            "/stats/upstream-logins",
+            get_with(
+                self::stats::upstream_logins,
+                self::stats::upstream_logins_doc,
+            ),
+        )
 }
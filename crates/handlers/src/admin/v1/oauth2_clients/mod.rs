@@ -0,0 +1,8 @@
+// Copyright 2026 Element Creations Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+// Please see LICENSE files in the repository root for full details.
+
+mod rotate_secret;
+
+pub use self::rotate_secret::{doc as rotate_secret_doc, handler as rotate_secret};
@@ -0,0 +1,359 @@
+// Copyright 2026 Element Creations Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+// Please see LICENSE files in the repository root for full details.
+
+use aide::{NoApi, OperationIo, transform::TransformOperation};
+use axum::{Json, extract::State, response::IntoResponse};
+use chrono::Duration;
+use hyper::StatusCode;
+use mas_axum_utils::record_error;
+use mas_data_model::{BoxRng, ClientSecret};
+use mas_keystore::Encrypter;
+use rand::distributions::{Alphanumeric, DistString};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use tracing::info;
+use ulid::Ulid;
+
+use crate::{
+    admin::{
+        call_context::CallContext,
+        model::OAuth2ClientSecret,
+        params::UlidPathParam,
+        response::{ErrorResponse, SingleResponse},
+    },
+    impl_from_error_for_route,
+};
+
+/// How long the current secret is still accepted by default, in seconds
+const DEFAULT_OVERLAP: u32 = 24 * 60 * 60;
+
+#[derive(Debug, thiserror::Error, OperationIo)]
+#[aide(output_with = "Json<ErrorResponse>")]
+pub enum RouteError {
+    #[error(transparent)]
+    Internal(Box<dyn std::error::Error + Send + Sync + 'static>),
+
+    #[error("OAuth 2.0 client ID {0} not found")]
+    NotFound(Ulid),
+
+    #[error("OAuth 2.0 client ID {0} is defined in the configuration")]
+    StaticClient(Ulid),
+
+    #[error("OAuth 2.0 client ID {0} does not authenticate with a client secret")]
+    NoClientSecret(Ulid),
+
+    #[error("The secret of OAuth 2.0 client ID {0} can't be hashed, as it uses client_secret_jwt")]
+    HashingNotAllowed(Ulid),
+}
+
+impl_from_error_for_route!(mas_storage::RepositoryError);
+impl_from_error_for_route!(mas_keystore::aead::Error);
+
+impl IntoResponse for RouteError {
+    fn into_response(self) -> axum::response::Response {
+        let error = ErrorResponse::from_error(&self);
+        let sentry_event_id = record_error!(self, Self::Internal(_));
+        let status = match self {
+            Self::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            Self::NotFound(_) => StatusCode::NOT_FOUND,
+            Self::StaticClient(_) | Self::NoClientSecret(_) => StatusCode::CONFLICT,
+            Self::HashingNotAllowed(_) => StatusCode::UNPROCESSABLE_ENTITY,
+        };
+        (status, sentry_event_id, Json(error)).into_response()
+    }
+}
+
+/// # JSON payload for the `POST /api/admin/v1/oauth2-clients/{id}/rotate-secret` endpoint
+#[derive(Default, Deserialize, JsonSchema)]
+#[schemars(rename = "RotateOAuth2ClientSecretRequest")]
+pub struct Request {
+    /// Store a one-way hash of the new secret instead of encrypting it. This
+    /// can't be used with clients authenticating with `client_secret_jwt`.
+    #[serde(default)]
+    hash: bool,
+
+    /// Time in seconds during which the current secret is still accepted.
+    /// Defaults to one day. Set to 0 to stop accepting it immediately.
+    overlap: Option<u32>,
+
+    /// Time in seconds after which the new secret expires.
+    /// If not set, the secret never expires.
+    expires_in: Option<u32>,
+}
+
+pub fn doc(operation: TransformOperation) -> TransformOperation {
+    operation
+        .id("rotateOAuth2ClientSecret")
+        .summary("Rotate the secret of an OAuth 2.0 client")
+        .description(
+            r"Generate a new secret for a confidential client registered in the database, and return it once.
+The current secret keeps being accepted during the overlap period, so that deployed instances of the client can be updated one by one.
+Clients defined in the configuration get their secret from there, and can't be rotated through this endpoint.",
+        )
+        .tag("oauth2-client")
+        .response_with::<201, Json<SingleResponse<OAuth2ClientSecret>>, _>(|t| {
+            let [sample] = OAuth2ClientSecret::samples();
+            let response = SingleResponse::new_canonical(sample);
+            t.description("A new secret was generated").example(response)
+        })
+        .response_with::<404, RouteError, _>(|t| {
+            let response = ErrorResponse::from_error(&RouteError::NotFound(Ulid::nil()));
+            t.description("OAuth 2.0 client was not found")
+                .example(response)
+        })
+        .response_with::<409, RouteError, _>(|t| {
+            let response = ErrorResponse::from_error(&RouteError::StaticClient(Ulid::nil()));
+            t.description(
+                "OAuth 2.0 client is defined in the configuration, or doesn't use a client secret",
+            )
+            .example(response)
+        })
+        .response_with::<422, RouteError, _>(|t| {
+            let response = ErrorResponse::from_error(&RouteError::HashingNotAllowed(Ulid::nil()));
+            t.description("The secret of this client can't be hashed")
+                .example(response)
+        })
+}
+
+#[tracing::instrument(name = "handler.admin.v1.oauth2_clients.rotate_secret", skip_all)]
+pub async fn handler(
+    CallContext {
+        mut repo, clock, ..
+    }: CallContext,
+    NoApi(mut rng): NoApi<BoxRng>,
+    NoApi(State(encrypter)): NoApi<State<Encrypter>>,
+    id: UlidPathParam,
+    params: Option<Json<Request>>,
+) -> Result<(StatusCode, Json<SingleResponse<OAuth2ClientSecret>>), RouteError> {
+    let Json(params) = params.unwrap_or_default();
+
+    let client = repo
+        .oauth2_client()
+        .lookup(*id)
+        .await?
+        .ok_or(RouteError::NotFound(*id))?;
+
+    // Static clients get their secret from the configuration, which would
+    // overwrite the rotated one on the next sync
+    let static_clients = repo.oauth2_client().all_static().await?;
+    if static_clients.iter().any(|c| c.id == client.id) {
+        return Err(RouteError::StaticClient(client.id));
+    }
+
+    if !client.uses_client_secret() {
+        return Err(RouteError::NoClientSecret(client.id));
+    }
+
+    if params.hash && client.needs_encrypted_secret() {
+        return Err(RouteError::HashingNotAllowed(client.id));
+    }
+
+    let now = clock.now();
+    let expires_at = params
+        .expires_in
+        .map(|seconds| now + Duration::seconds(seconds.into()));
+    let client_secret = Alphanumeric.sample_string(&mut rng, 32);
+
+    let new_secret = if params.hash {
+        ClientSecret::hash(&client.client_id, &client_secret, expires_at)
+    } else {
+        let encrypted_secret = encrypter.encrypt_to_string(client_secret.as_bytes())?;
+        ClientSecret::encrypted(encrypted_secret, expires_at)
+    };
+
+    let overlap = params.overlap.unwrap_or(DEFAULT_OVERLAP);
+    let previous_secret = client.secret_kept_on_rotation(now, Duration::seconds(overlap.into()));
+    let previous_secret_expires_at = previous_secret
+        .as_ref()
+        .and_then(|secret| secret.expires_at);
+
+    let client = repo
+        .oauth2_client()
+        .set_secrets(client, Some(new_secret), previous_secret)
+        .await?;
+
+    repo.save().await?;
+
+    info!(%client.id, "Client secret rotated by admin");
+
+    Ok((
+        StatusCode::CREATED,
+        Json(SingleResponse::new_canonical(OAuth2ClientSecret::new(
+            client.id,
+            client_secret,
+            params.hash,
+            expires_at,
+            previous_secret_expires_at,
+        ))),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+    use hyper::{Request, StatusCode};
+    use mas_router::SimpleRoute;
+    use oauth2_types::registration::ClientRegistrationResponse;
+    use serde_json::{Value, json};
+    use sqlx::PgPool;
+
+    use crate::test_utils::{RequestBuilderExt, ResponseExt, TestState, setup};
+
+    /// Register a confidential client with the given metadata, returning its ID
+    /// and secret
+    async fn register_client(state: &TestState, metadata: Value) -> (String, String) {
+        let request = Request::post(mas_router::OAuth2RegistrationEndpoint::PATH).json(metadata);
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::CREATED);
+        let response: ClientRegistrationResponse = response.json();
+        (response.client_id, response.client_secret.unwrap())
+    }
+
+    /// Check whether the given credentials are accepted by the introspection
+    /// endpoint
+    async fn credentials_accepted(state: &TestState, client_id: &str, secret: &str) -> bool {
+        let request = Request::post(mas_router::OAuth2Introspection::PATH)
+            .basic_auth(client_id, secret)
+            .form(json!({ "token": "not-a-token" }));
+        let response = state.request(request).await;
+        response.status() == StatusCode::OK
+    }
+
+    #[sqlx::test(migrator = "mas_storage_pg::MIGRATOR")]
+    async fn test_rotate_secret(pool: PgPool) {
+        setup();
+        let mut state = TestState::from_pool(pool).await.unwrap();
+        let token = state.token_with_scope("urn:mas:admin").await;
+
+        let (client_id, old_secret) = register_client(
+            &state,
+            json!({
+                "client_uri": "https://example.com/",
+                "grant_types": [],
+                "token_endpoint_auth_method": "client_secret_basic",
+            }),
+        )
+        .await;
+        assert!(credentials_accepted(&state, &client_id, &old_secret).await);
+
+        let request = Request::post(format!(
+            "/api/admin/v1/oauth2-clients/{client_id}/rotate-secret"
+        ))
+        .bearer(&token)
+        .json(json!({
+            "hash": true,
+            "overlap": 3600,
+        }));
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::CREATED);
+        let body: Value = response.json();
+        assert_eq!(body["data"]["type"], "oauth2-client-secret");
+        assert_eq!(body["data"]["attributes"]["hashed"], true);
+        assert_eq!(body["data"]["attributes"]["expires_at"], Value::Null);
+        assert_eq!(
+            body["data"]["attributes"]["previous_secret_expires_at"],
+            "2022-01-16T15:40:00Z"
+        );
+        let new_secret = body["data"]["attributes"]["client_secret"]
+            .as_str()
+            .unwrap()
+            .to_owned();
+
+        // Both secrets are accepted during the overlap
+        assert!(credentials_accepted(&state, &client_id, &old_secret).await);
+        assert!(credentials_accepted(&state, &client_id, &new_secret).await);
+        assert!(!credentials_accepted(&state, &client_id, "wrong").await);
+
+        // Only the new one is accepted after it
+        state.clock.advance(Duration::hours(2));
+        assert!(!credentials_accepted(&state, &client_id, &old_secret).await);
+        assert!(credentials_accepted(&state, &client_id, &new_secret).await);
+
+        // The admin token expired in the meantime
+        let token = state.token_with_scope("urn:mas:admin").await;
+
+        // Rotating without overlap drops the current secret immediately
+        let request = Request::post(format!(
+            "/api/admin/v1/oauth2-clients/{client_id}/rotate-secret"
+        ))
+        .bearer(&token)
+        .json(json!({
+            "overlap": 0,
+        }));
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::CREATED);
+        let body: Value = response.json();
+        assert_eq!(body["data"]["attributes"]["hashed"], false);
+        assert_eq!(
+            body["data"]["attributes"]["previous_secret_expires_at"],
+            Value::Null
+        );
+        let newest_secret = body["data"]["attributes"]["client_secret"]
+            .as_str()
+            .unwrap()
+            .to_owned();
+
+        assert!(!credentials_accepted(&state, &client_id, &new_secret).await);
+        assert!(credentials_accepted(&state, &client_id, &newest_secret).await);
+    }
+
+    #[sqlx::test(migrator = "mas_storage_pg::MIGRATOR")]
+    async fn test_rotate_secret_errors(pool: PgPool) {
+        setup();
+        let mut state = TestState::from_pool(pool).await.unwrap();
+        let token = state.token_with_scope("urn:mas:admin").await;
+
+        // Unknown client
+        let request = Request::post(format!(
+            "/api/admin/v1/oauth2-clients/{}/rotate-secret",
+            ulid::Ulid::nil()
+        ))
+        .bearer(&token)
+        .empty();
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::NOT_FOUND);
+
+        // Secrets used for client_secret_jwt can't be hashed
+        let (client_id, _) = register_client(
+            &state,
+            json!({
+                "client_uri": "https://example.com/",
+                "grant_types": [],
+                "token_endpoint_auth_method": "client_secret_jwt",
+                "token_endpoint_auth_signing_alg": "HS256",
+            }),
+        )
+        .await;
+        let request = Request::post(format!(
+            "/api/admin/v1/oauth2-clients/{client_id}/rotate-secret"
+        ))
+        .bearer(&token)
+        .json(json!({
+            "hash": true,
+        }));
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::UNPROCESSABLE_ENTITY);
+
+        // Public clients don't have a secret to rotate
+        let request = Request::post(mas_router::OAuth2RegistrationEndpoint::PATH).json(json!({
+            "client_uri": "https://example.com/",
+            "redirect_uris": ["https://example.com/"],
+            "response_types": ["code"],
+            "grant_types": ["authorization_code"],
+            "token_endpoint_auth_method": "none",
+        }));
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::CREATED);
+        let response: ClientRegistrationResponse = response.json();
+        let request = Request::post(format!(
+            "/api/admin/v1/oauth2-clients/{}/rotate-secret",
+            response.client_id
+        ))
+        .bearer(&token)
+        .empty();
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::CONFLICT);
+    }
+}
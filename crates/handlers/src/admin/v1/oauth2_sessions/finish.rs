@@ -4,7 +4,7 @@
 // Please see LICENSE files in the repository root for full details.
 
 use aide::{NoApi, OperationIo, transform::TransformOperation};
-use axum::{Json, response::IntoResponse};
+use axum::{Json, extract::State, response::IntoResponse};
 use hyper::StatusCode;
 use mas_axum_utils::record_error;
 use mas_data_model::BoxRng;
@@ -12,6 +12,7 @@ use mas_storage::queue::{QueueJobRepositoryExt as _, SyncDevicesJob};
 use ulid::Ulid;
 
 use crate::{
+    IntrospectionCache,
     admin::{
         call_context::CallContext,
         model::{OAuth2Session, Resource},
@@ -85,6 +86,7 @@ pub async fn handler(
         mut repo, clock, ..
     }: CallContext,
     NoApi(mut rng): NoApi<BoxRng>,
+    NoApi(State(introspection_cache)): NoApi<State<IntrospectionCache>>,
     id: UlidPathParam,
 ) -> Result<Json<SingleResponse<OAuth2Session>>, RouteError> {
     let id = *id;
@@ -111,6 +113,8 @@ pub async fn handler(
 
     repo.save().await?;
 
+    introspection_cache.invalidate_session(session.id).await;
+
     Ok(Json(SingleResponse::new(
         OAuth2Session::from(session),
         format!("/api/admin/v1/oauth2-sessions/{id}/finish"),
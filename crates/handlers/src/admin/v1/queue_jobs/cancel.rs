@@ -0,0 +1,181 @@
+// Copyright 2026 Element Creations Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+// Please see LICENSE files in the repository root for full details.
+
+use aide::{OperationIo, transform::TransformOperation};
+use axum::{Json, response::IntoResponse};
+use hyper::StatusCode;
+use mas_axum_utils::record_error;
+use tracing::info;
+use ulid::Ulid;
+
+use crate::{
+    admin::{
+        call_context::CallContext,
+        model::{QueueJob, Resource},
+        params::UlidPathParam,
+        response::{ErrorResponse, SingleResponse},
+    },
+    impl_from_error_for_route,
+};
+
+#[derive(Debug, thiserror::Error, OperationIo)]
+#[aide(output_with = "Json<ErrorResponse>")]
+pub enum RouteError {
+    #[error(transparent)]
+    Internal(Box<dyn std::error::Error + Send + Sync + 'static>),
+
+    #[error("Job ID {0} not found")]
+    NotFound(Ulid),
+
+    #[error("Job ID {0} was already picked up by a worker")]
+    NotCancellable(Ulid),
+}
+
+impl_from_error_for_route!(mas_storage::RepositoryError);
+
+impl IntoResponse for RouteError {
+    fn into_response(self) -> axum::response::Response {
+        let error = ErrorResponse::from_error(&self);
+        let sentry_event_id = record_error!(self, Self::Internal(_));
+        let status = match self {
+            Self::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            Self::NotFound(_) => StatusCode::NOT_FOUND,
+            Self::NotCancellable(_) => StatusCode::CONFLICT,
+        };
+        (status, sentry_event_id, Json(error)).into_response()
+    }
+}
+
+pub fn doc(operation: TransformOperation) -> TransformOperation {
+    operation
+        .id("cancelQueueJob")
+        .summary("Cancel a job")
+        .description("Only jobs which were not picked up by a worker yet can be cancelled.")
+        .tag("queue")
+        .response_with::<200, Json<SingleResponse<QueueJob>>, _>(|t| {
+            let [_, _, sample] = QueueJob::samples();
+            let id = sample.id();
+            let response =
+                SingleResponse::new(sample, format!("/api/admin/v1/queue-jobs/{id}/cancel"));
+            t.description("The job was cancelled").example(response)
+        })
+        .response_with::<404, RouteError, _>(|t| {
+            let response = ErrorResponse::from_error(&RouteError::NotFound(Ulid::nil()));
+            t.description("Job was not found").example(response)
+        })
+        .response_with::<409, RouteError, _>(|t| {
+            let response = ErrorResponse::from_error(&RouteError::NotCancellable(Ulid::nil()));
+            t.description("Job was already picked up by a worker")
+                .example(response)
+        })
+}
+
+#[tracing::instrument(name = "handler.admin.v1.queue_jobs.cancel", skip_all)]
+pub async fn handler(
+    CallContext {
+        mut repo, clock, ..
+    }: CallContext,
+    id: UlidPathParam,
+) -> Result<Json<SingleResponse<QueueJob>>, RouteError> {
+    let id = *id;
+    let job = repo
+        .queue_job()
+        .lookup(id)
+        .await?
+        .ok_or(RouteError::NotFound(id))?;
+
+    if !job.status.is_cancellable() {
+        return Err(RouteError::NotCancellable(id));
+    }
+
+    repo.queue_job().cancel(&clock, id).await?;
+
+    let job = repo
+        .queue_job()
+        .lookup(id)
+        .await?
+        .ok_or(RouteError::NotFound(id))?;
+
+    repo.save().await?;
+
+    info!(job.id = %id, job.queue_name = %job.queue_name, "Job cancelled by admin");
+
+    Ok(Json(SingleResponse::new(
+        QueueJob::from(job),
+        format!("/api/admin/v1/queue-jobs/{id}/cancel"),
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use hyper::{Request, StatusCode};
+    use mas_data_model::Clock as _;
+    use sqlx::PgPool;
+
+    use crate::test_utils::{RequestBuilderExt, ResponseExt, TestState, setup};
+
+    #[sqlx::test(migrator = "mas_storage_pg::MIGRATOR")]
+    async fn test_cancel_job(pool: PgPool) {
+        setup();
+        let mut state = TestState::from_pool(pool).await.unwrap();
+        let token = state.token_with_scope("urn:mas:admin").await;
+
+        let mut repo = state.repository().await.unwrap();
+        repo.queue_job()
+            .schedule_later(
+                &mut state.rng(),
+                &state.clock,
+                "test-queue",
+                serde_json::json!({}),
+                serde_json::json!({}),
+                state.clock.now() + chrono::Duration::hours(1),
+                None,
+            )
+            .await
+            .unwrap();
+        repo.save().await.unwrap();
+
+        // Find the job ID through the list endpoint
+        let request = Request::get("/api/admin/v1/queue-jobs?filter[queue]=test-queue")
+            .bearer(&token)
+            .empty();
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::OK);
+        let body: serde_json::Value = response.json();
+        let id = body["data"][0]["id"].as_str().unwrap().to_owned();
+
+        let request = Request::post(format!("/api/admin/v1/queue-jobs/{id}/cancel"))
+            .bearer(&token)
+            .empty();
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::OK);
+        let body: serde_json::Value = response.json();
+        assert_eq!(body["data"]["attributes"]["status"], "cancelled");
+        assert_eq!(
+            body["data"]["attributes"]["cancelled_at"],
+            serde_json::json!(state.clock.now())
+        );
+
+        // It can't be cancelled twice
+        let request = Request::post(format!("/api/admin/v1/queue-jobs/{id}/cancel"))
+            .bearer(&token)
+            .empty();
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::CONFLICT);
+    }
+
+    #[sqlx::test(migrator = "mas_storage_pg::MIGRATOR")]
+    async fn test_cancel_unknown_job(pool: PgPool) {
+        setup();
+        let mut state = TestState::from_pool(pool).await.unwrap();
+        let token = state.token_with_scope("urn:mas:admin").await;
+
+        let request = Request::post("/api/admin/v1/queue-jobs/01040G2081040G2081040G2081/cancel")
+            .bearer(&token)
+            .empty();
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,290 @@
+// Copyright 2026 Element Creations Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+// Please see LICENSE files in the repository root for full details.
+
+use aide::{OperationIo, transform::TransformOperation};
+use axum::{Json, response::IntoResponse};
+use axum_extra::extract::{Query, QueryRejection};
+use axum_macros::FromRequestParts;
+use hyper::StatusCode;
+use mas_axum_utils::record_error;
+use mas_storage::{Page, queue::JobFilter};
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+use crate::{
+    admin::{
+        call_context::CallContext,
+        model::{QueueJob, QueueJobStatus, Resource},
+        params::{IncludeCount, Pagination},
+        response::{ErrorResponse, PaginatedResponse},
+    },
+    impl_from_error_for_route,
+};
+
+#[derive(FromRequestParts, Deserialize, JsonSchema, OperationIo)]
+#[serde(rename = "QueueJobFilter")]
+#[aide(input_with = "Query<FilterParams>")]
+#[from_request(via(Query), rejection(RouteError))]
+pub struct FilterParams {
+    /// Retrieve the jobs placed on the given queue
+    #[serde(rename = "filter[queue]")]
+    queue: Option<String>,
+
+    /// Retrieve the jobs with the given status
+    #[serde(rename = "filter[status]")]
+    status: Option<QueueJobStatus>,
+}
+
+impl std::fmt::Display for FilterParams {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut sep = '?';
+
+        if let Some(queue) = &self.queue {
+            write!(f, "{sep}filter[queue]={queue}")?;
+            sep = '&';
+        }
+
+        if let Some(status) = self.status {
+            write!(f, "{sep}filter[status]={status}")?;
+            sep = '&';
+        }
+
+        let _ = sep;
+        Ok(())
+    }
+}
+
+#[derive(Debug, thiserror::Error, OperationIo)]
+#[aide(output_with = "Json<ErrorResponse>")]
+pub enum RouteError {
+    #[error(transparent)]
+    Internal(Box<dyn std::error::Error + Send + Sync + 'static>),
+
+    #[error("Invalid filter parameters")]
+    InvalidFilter(#[from] QueryRejection),
+}
+
+impl_from_error_for_route!(mas_storage::RepositoryError);
+
+impl IntoResponse for RouteError {
+    fn into_response(self) -> axum::response::Response {
+        let error = ErrorResponse::from_error(&self);
+        let sentry_event_id = record_error!(self, Self::Internal(_));
+        let status = match self {
+            Self::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            Self::InvalidFilter(_) => StatusCode::BAD_REQUEST,
+        };
+        (status, sentry_event_id, Json(error)).into_response()
+    }
+}
+
+pub fn doc(operation: TransformOperation) -> TransformOperation {
+    operation
+        .id("listQueueJobs")
+        .summary("List jobs in the job queue")
+        .description("Jobs are kept around for a while after they completed or failed, before being cleaned up.")
+        .tag("queue")
+        .response_with::<200, Json<PaginatedResponse<QueueJob>>, _>(|t| {
+            let jobs = QueueJob::samples();
+            let pagination = mas_storage::Pagination::first(jobs.len());
+            let page = Page {
+                edges: jobs
+                    .into_iter()
+                    .map(|node| mas_storage::pagination::Edge {
+                        cursor: node.id(),
+                        node,
+                    })
+                    .collect(),
+                has_next_page: true,
+                has_previous_page: false,
+            };
+
+            t.description("Paginated response of queue jobs")
+                .example(PaginatedResponse::for_page(
+                    page,
+                    pagination,
+                    Some(42),
+                    QueueJob::PATH,
+                ))
+        })
+}
+
+#[tracing::instrument(name = "handler.admin.v1.queue_jobs.list", skip_all)]
+pub async fn handler(
+    CallContext { mut repo, .. }: CallContext,
+    Pagination(pagination, include_count): Pagination,
+    params: FilterParams,
+) -> Result<Json<PaginatedResponse<QueueJob>>, RouteError> {
+    let base = format!("{path}{params}", path = QueueJob::PATH);
+    let base = include_count.add_to_base(&base);
+    let filter = JobFilter::new();
+
+    let filter = match &params.queue {
+        Some(queue) => filter.for_queue(queue),
+        None => filter,
+    };
+
+    let filter = match params.status {
+        Some(status) => filter.with_status(status.into()),
+        None => filter,
+    };
+
+    let response = match include_count {
+        IncludeCount::True => {
+            let page = repo
+                .queue_job()
+                .list(filter, pagination)
+                .await?
+                .map(QueueJob::from);
+            let count = repo.queue_job().count(filter).await?;
+            PaginatedResponse::for_page(page, pagination, Some(count), &base)
+        }
+        IncludeCount::False => {
+            let page = repo
+                .queue_job()
+                .list(filter, pagination)
+                .await?
+                .map(QueueJob::from);
+            PaginatedResponse::for_page(page, pagination, None, &base)
+        }
+        IncludeCount::Only => {
+            let count = repo.queue_job().count(filter).await?;
+            PaginatedResponse::for_count_only(count, &base)
+        }
+    };
+
+    Ok(Json(response))
+}
+
+#[cfg(test)]
+mod tests {
+    use hyper::{Request, StatusCode};
+    use mas_data_model::Clock as _;
+    use mas_storage::queue::{ProvisionUserJob, QueueJobRepositoryExt as _};
+    use sqlx::PgPool;
+
+    use crate::test_utils::{RequestBuilderExt, ResponseExt, TestState, setup};
+
+    #[sqlx::test(migrator = "mas_storage_pg::MIGRATOR")]
+    async fn test_list_jobs(pool: PgPool) {
+        setup();
+        let mut state = TestState::from_pool(pool).await.unwrap();
+        let token = state.token_with_scope("urn:mas:admin").await;
+
+        // Schedule a job on two different queues, one of them for later
+        let mut repo = state.repository().await.unwrap();
+        repo.queue_job()
+            .schedule(
+                &mut state.rng(),
+                &state.clock,
+                "first-queue",
+                serde_json::json!({ "hello": "world" }),
+                serde_json::json!({}),
+            )
+            .await
+            .unwrap();
+        repo.queue_job()
+            .schedule_later(
+                &mut state.rng(),
+                &state.clock,
+                "second-queue",
+                serde_json::json!({}),
+                serde_json::json!({}),
+                state.clock.now() + chrono::Duration::hours(1),
+                None,
+            )
+            .await
+            .unwrap();
+        repo.save().await.unwrap();
+
+        let request = Request::get("/api/admin/v1/queue-jobs?filter[queue]=first-queue")
+            .bearer(&token)
+            .empty();
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::OK);
+        let body: serde_json::Value = response.json();
+        assert_eq!(body["meta"]["count"], 1);
+        assert_eq!(body["data"][0]["type"], "queue-job");
+        assert_eq!(body["data"][0]["attributes"]["queue_name"], "first-queue");
+        assert_eq!(body["data"][0]["attributes"]["status"], "available");
+        // The queue is unknown, so its payload is redacted
+        assert_eq!(
+            body["data"][0]["attributes"]["payload"],
+            serde_json::Value::Null
+        );
+
+        let request = Request::get("/api/admin/v1/queue-jobs?filter[status]=scheduled")
+            .bearer(&token)
+            .empty();
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::OK);
+        let body: serde_json::Value = response.json();
+        assert_eq!(body["meta"]["count"], 1);
+        assert_eq!(body["data"][0]["attributes"]["queue_name"], "second-queue");
+
+        let request = Request::get("/api/admin/v1/queue-jobs?filter[status]=invalid")
+            .bearer(&token)
+            .empty();
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::BAD_REQUEST);
+    }
+
+    #[sqlx::test(migrator = "mas_storage_pg::MIGRATOR")]
+    async fn test_list_jobs_redacts_payloads(pool: PgPool) {
+        setup();
+        let mut state = TestState::from_pool(pool).await.unwrap();
+        let token = state.token_with_scope("urn:mas:admin").await;
+
+        let mut repo = state.repository().await.unwrap();
+        let user = repo
+            .user()
+            .add(&mut state.rng(), &state.clock, "alice".to_owned())
+            .await
+            .unwrap();
+        repo.queue_job()
+            .schedule_job(&mut state.rng(), &state.clock, ProvisionUserJob::new(&user))
+            .await
+            .unwrap();
+        repo.save().await.unwrap();
+
+        // Schedule an import with a password hash
+        let request = Request::post("/api/admin/v1/users/import")
+            .bearer(&token)
+            .json(serde_json::json!({
+                "users": [{
+                    "username": "bob",
+                    "password": { "version": 1, "hash": "super-secret-hash" },
+                }],
+            }));
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::ACCEPTED);
+
+        let request = Request::get("/api/admin/v1/queue-jobs?filter[queue]=import-users")
+            .bearer(&token)
+            .empty();
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::OK);
+        let body: serde_json::Value = response.json();
+        assert_eq!(body["meta"]["count"], 1);
+        assert_eq!(
+            body["data"][0]["attributes"]["payload"],
+            serde_json::Value::Null
+        );
+        assert!(!body.to_string().contains("super-secret-hash"));
+
+        // Payloads which only contain IDs are still shown
+        let request = Request::get("/api/admin/v1/queue-jobs?filter[queue]=provision-user")
+            .bearer(&token)
+            .empty();
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::OK);
+        let body: serde_json::Value = response.json();
+        assert_eq!(body["meta"]["count"], 1);
+        assert_eq!(
+            body["data"][0]["attributes"]["payload"]["user_id"],
+            user.id.to_string()
+        );
+    }
+}
@@ -0,0 +1,16 @@
+// Copyright 2026 Element Creations Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+// Please see LICENSE files in the repository root for full details.
+
+mod cancel;
+mod get;
+mod list;
+mod retry;
+
+pub use self::{
+    cancel::{doc as cancel_doc, handler as cancel},
+    get::{doc as get_doc, handler as get},
+    list::{doc as list_doc, handler as list},
+    retry::{doc as retry_doc, handler as retry},
+};
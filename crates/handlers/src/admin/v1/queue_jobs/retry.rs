@@ -0,0 +1,224 @@
+// Copyright 2026 Element Creations Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+// Please see LICENSE files in the repository root for full details.
+
+use aide::{NoApi, OperationIo, transform::TransformOperation};
+use axum::{Json, response::IntoResponse};
+use chrono::Duration;
+use hyper::StatusCode;
+use mas_axum_utils::record_error;
+use mas_data_model::BoxRng;
+use mas_storage::queue::JobStatus;
+use tracing::info;
+use ulid::Ulid;
+
+use crate::{
+    admin::{
+        call_context::CallContext,
+        model::{QueueJob, Resource},
+        params::UlidPathParam,
+        response::{ErrorResponse, SingleResponse},
+    },
+    impl_from_error_for_route,
+};
+
+#[derive(Debug, thiserror::Error, OperationIo)]
+#[aide(output_with = "Json<ErrorResponse>")]
+pub enum RouteError {
+    #[error(transparent)]
+    Internal(Box<dyn std::error::Error + Send + Sync + 'static>),
+
+    #[error("Job ID {0} not found")]
+    NotFound(Ulid),
+
+    #[error("Job ID {0} has not failed")]
+    NotFailed(Ulid),
+
+    #[error("Job ID {0} was already retried")]
+    AlreadyRetried(Ulid),
+}
+
+impl_from_error_for_route!(mas_storage::RepositoryError);
+
+impl IntoResponse for RouteError {
+    fn into_response(self) -> axum::response::Response {
+        let error = ErrorResponse::from_error(&self);
+        let sentry_event_id = record_error!(self, Self::Internal(_));
+        let status = match self {
+            Self::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            Self::NotFound(_) => StatusCode::NOT_FOUND,
+            Self::NotFailed(_) | Self::AlreadyRetried(_) => StatusCode::CONFLICT,
+        };
+        (status, sentry_event_id, Json(error)).into_response()
+    }
+}
+
+pub fn doc(operation: TransformOperation) -> TransformOperation {
+    operation
+        .id("retryQueueJob")
+        .summary("Retry a failed job")
+        .description(
+            r"This schedules a new attempt of the job to run as soon as possible, and returns the new job.
+Only failed jobs which were not retried yet can be retried.",
+        )
+        .tag("queue")
+        .response_with::<200, Json<SingleResponse<QueueJob>>, _>(|t| {
+            let [_, sample, ..] = QueueJob::samples();
+            let response = SingleResponse::new_canonical(sample);
+            t.description("The job was retried").example(response)
+        })
+        .response_with::<404, RouteError, _>(|t| {
+            let response = ErrorResponse::from_error(&RouteError::NotFound(Ulid::nil()));
+            t.description("Job was not found").example(response)
+        })
+        .response_with::<409, RouteError, _>(|t| {
+            let response = ErrorResponse::from_error(&RouteError::NotFailed(Ulid::nil()));
+            t.description("Job has not failed, or was already retried")
+                .example(response)
+        })
+}
+
+#[tracing::instrument(name = "handler.admin.v1.queue_jobs.retry", skip_all)]
+pub async fn handler(
+    CallContext {
+        mut repo, clock, ..
+    }: CallContext,
+    NoApi(mut rng): NoApi<BoxRng>,
+    id: UlidPathParam,
+) -> Result<Json<SingleResponse<QueueJob>>, RouteError> {
+    let id = *id;
+    let job = repo
+        .queue_job()
+        .lookup(id)
+        .await?
+        .ok_or(RouteError::NotFound(id))?;
+
+    if job.status != JobStatus::Failed {
+        return Err(RouteError::NotFailed(id));
+    }
+
+    if job.next_attempt_id.is_some() {
+        return Err(RouteError::AlreadyRetried(id));
+    }
+
+    repo.queue_job()
+        .retry(&mut rng, &clock, id, Duration::zero())
+        .await?;
+
+    // Load back the job to find out the ID of the new attempt
+    let next_attempt_id = repo
+        .queue_job()
+        .lookup(id)
+        .await?
+        .and_then(|job| job.next_attempt_id)
+        .ok_or(RouteError::NotFound(id))?;
+
+    let new_job = repo
+        .queue_job()
+        .lookup(next_attempt_id)
+        .await?
+        .ok_or(RouteError::NotFound(next_attempt_id))?;
+
+    repo.save().await?;
+
+    info!(
+        job.id = %id,
+        job.queue_name = %new_job.queue_name,
+        job.next_attempt_id = %new_job.id,
+        "Job retried by admin"
+    );
+
+    let new_job = QueueJob::from(new_job);
+    let path = new_job.path();
+    Ok(Json(SingleResponse::new(new_job, path)))
+}
+
+#[cfg(test)]
+mod tests {
+    use hyper::{Request, StatusCode};
+    use sqlx::PgPool;
+
+    use crate::test_utils::{RequestBuilderExt, ResponseExt, TestState, setup};
+
+    #[sqlx::test(migrator = "mas_storage_pg::MIGRATOR")]
+    async fn test_retry_job(pool: PgPool) {
+        setup();
+        let mut state = TestState::from_pool(pool).await.unwrap();
+        let token = state.token_with_scope("urn:mas:admin").await;
+
+        // Schedule a job, and make it fail
+        let mut repo = state.repository().await.unwrap();
+        repo.queue_job()
+            .schedule(
+                &mut state.rng(),
+                &state.clock,
+                "test-queue",
+                serde_json::json!({}),
+                serde_json::json!({}),
+            )
+            .await
+            .unwrap();
+        let worker = repo
+            .queue_worker()
+            .register(&mut state.rng(), &state.clock)
+            .await
+            .unwrap();
+        let job = repo
+            .queue_job()
+            .reserve(&state.clock, &worker, &["test-queue"], 1)
+            .await
+            .unwrap()
+            .pop()
+            .unwrap();
+
+        // It can't be retried while it is running
+        repo.save().await.unwrap();
+        let request = Request::post(format!("/api/admin/v1/queue-jobs/{}/retry", job.id))
+            .bearer(&token)
+            .empty();
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::CONFLICT);
+
+        let mut repo = state.repository().await.unwrap();
+        repo.queue_job()
+            .mark_as_failed(&state.clock, job.id, "Something went wrong")
+            .await
+            .unwrap();
+        repo.save().await.unwrap();
+
+        let request = Request::post(format!("/api/admin/v1/queue-jobs/{}/retry", job.id))
+            .bearer(&token)
+            .empty();
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::OK);
+        let body: serde_json::Value = response.json();
+        assert_ne!(body["data"]["id"], job.id.to_string());
+        assert_eq!(body["data"]["attributes"]["queue_name"], "test-queue");
+        assert_eq!(body["data"]["attributes"]["status"], "scheduled");
+        assert_eq!(body["data"]["attributes"]["attempt"], 1);
+
+        // The original job now points to the new attempt, and keeps its error
+        let request = Request::get(format!("/api/admin/v1/queue-jobs/{}", job.id))
+            .bearer(&token)
+            .empty();
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::OK);
+        let original: serde_json::Value = response.json();
+        assert_eq!(
+            original["data"]["attributes"]["next_attempt_id"],
+            body["data"]["id"]
+        );
+        assert_eq!(
+            original["data"]["attributes"]["failed_reason"],
+            "Something went wrong"
+        );
+
+        // It can't be retried twice
+        let request = Request::post(format!("/api/admin/v1/queue-jobs/{}/retry", job.id))
+            .bearer(&token)
+            .empty();
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::CONFLICT);
+    }
+}
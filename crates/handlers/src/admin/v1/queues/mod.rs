@@ -0,0 +1,23 @@
+// Copyright 2026 Element Creations Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+// Please see LICENSE files in the repository root for full details.
+
+mod pause;
+mod paused;
+mod resume;
+
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+pub use self::{
+    pause::{doc as pause_doc, handler as pause},
+    paused::{doc as paused_doc, handler as paused},
+    resume::{doc as resume_doc, handler as resume},
+};
+
+#[derive(Deserialize, JsonSchema)]
+pub struct QueueNamePathParam {
+    /// The name of the queue, for example `provision-user`
+    name: String,
+}
@@ -0,0 +1,163 @@
+// Copyright 2026 Element Creations Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+// Please see LICENSE files in the repository root for full details.
+
+use aide::{OperationIo, transform::TransformOperation};
+use axum::{Json, extract::Path, response::IntoResponse};
+use hyper::StatusCode;
+use mas_axum_utils::record_error;
+use tracing::info;
+
+use super::QueueNamePathParam;
+use crate::{
+    admin::{call_context::CallContext, response::ErrorResponse},
+    impl_from_error_for_route,
+};
+
+#[derive(Debug, thiserror::Error, OperationIo)]
+#[aide(output_with = "Json<ErrorResponse>")]
+pub enum RouteError {
+    #[error(transparent)]
+    Internal(Box<dyn std::error::Error + Send + Sync + 'static>),
+
+    #[error("Queue {0:?} is already paused")]
+    AlreadyPaused(String),
+}
+
+impl_from_error_for_route!(mas_storage::RepositoryError);
+
+impl IntoResponse for RouteError {
+    fn into_response(self) -> axum::response::Response {
+        let error = ErrorResponse::from_error(&self);
+        let sentry_event_id = record_error!(self, Self::Internal(_));
+        let status = match self {
+            Self::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            Self::AlreadyPaused(_) => StatusCode::CONFLICT,
+        };
+        (status, sentry_event_id, Json(error)).into_response()
+    }
+}
+
+pub fn doc(operation: TransformOperation) -> TransformOperation {
+    operation
+        .id("pauseQueue")
+        .summary("Pause a queue")
+        .description(
+            r"Workers stop picking up new jobs from a paused queue. Jobs which are already running are not interrupted, and new jobs can still be scheduled on the queue.
+The queue name is not checked, which means a queue can be paused before any job was ever scheduled on it.",
+        )
+        .tag("queue")
+        .response_with::<204, (), _>(|t| t.description("The queue was paused"))
+        .response_with::<409, RouteError, _>(|t| {
+            let response =
+                ErrorResponse::from_error(&RouteError::AlreadyPaused("provision-user".to_owned()));
+            t.description("The queue is already paused")
+                .example(response)
+        })
+}
+
+#[tracing::instrument(name = "handler.admin.v1.queues.pause", skip_all)]
+pub async fn handler(
+    CallContext {
+        mut repo, clock, ..
+    }: CallContext,
+    Path(QueueNamePathParam { name }): Path<QueueNamePathParam>,
+) -> Result<StatusCode, RouteError> {
+    if !repo.queue_worker().pause_queue(&clock, &name).await? {
+        return Err(RouteError::AlreadyPaused(name));
+    }
+
+    repo.save().await?;
+
+    info!(queue.name = %name, "Queue paused by admin");
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[cfg(test)]
+mod tests {
+    use hyper::{Request, StatusCode};
+    use sqlx::PgPool;
+
+    use crate::test_utils::{RequestBuilderExt, ResponseExt, TestState, setup};
+
+    #[sqlx::test(migrator = "mas_storage_pg::MIGRATOR")]
+    async fn test_pause_and_resume(pool: PgPool) {
+        setup();
+        let mut state = TestState::from_pool(pool).await.unwrap();
+        let token = state.token_with_scope("urn:mas:admin").await;
+
+        // Schedule a job on a queue, and pause it
+        let mut repo = state.repository().await.unwrap();
+        repo.queue_job()
+            .schedule(
+                &mut state.rng(),
+                &state.clock,
+                "test-queue",
+                serde_json::json!({}),
+                serde_json::json!({}),
+            )
+            .await
+            .unwrap();
+        repo.save().await.unwrap();
+
+        let request = Request::post("/api/admin/v1/queues/test-queue/pause")
+            .bearer(&token)
+            .empty();
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::NO_CONTENT);
+
+        // Pausing it twice is a conflict
+        let request = Request::post("/api/admin/v1/queues/test-queue/pause")
+            .bearer(&token)
+            .empty();
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::CONFLICT);
+
+        let request = Request::get("/api/admin/v1/queues/paused")
+            .bearer(&token)
+            .empty();
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::OK);
+        let body: serde_json::Value = response.json();
+        assert_eq!(body["queues"][0]["name"], "test-queue");
+
+        // Workers don't pick up jobs from the paused queue
+        let mut repo = state.repository().await.unwrap();
+        let worker = repo
+            .queue_worker()
+            .register(&mut state.rng(), &state.clock)
+            .await
+            .unwrap();
+        let jobs = repo
+            .queue_job()
+            .reserve(&state.clock, &worker, &["test-queue"], 10)
+            .await
+            .unwrap();
+        assert!(jobs.is_empty());
+        repo.save().await.unwrap();
+
+        let request = Request::post("/api/admin/v1/queues/test-queue/resume")
+            .bearer(&token)
+            .empty();
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::NO_CONTENT);
+
+        // Resuming a queue which is not paused is not found
+        let request = Request::post("/api/admin/v1/queues/test-queue/resume")
+            .bearer(&token)
+            .empty();
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::NOT_FOUND);
+
+        let mut repo = state.repository().await.unwrap();
+        let jobs = repo
+            .queue_job()
+            .reserve(&state.clock, &worker, &["test-queue"], 10)
+            .await
+            .unwrap();
+        assert_eq!(jobs.len(), 1);
+        repo.save().await.unwrap();
+    }
+}
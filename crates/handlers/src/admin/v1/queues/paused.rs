@@ -0,0 +1,88 @@
+// Copyright 2026 Element Creations Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+// Please see LICENSE files in the repository root for full details.
+
+use aide::{OperationIo, transform::TransformOperation};
+use axum::{Json, response::IntoResponse};
+use chrono::{DateTime, Utc};
+use hyper::StatusCode;
+use mas_axum_utils::record_error;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::{
+    admin::{call_context::CallContext, response::ErrorResponse},
+    impl_from_error_for_route,
+};
+
+#[derive(Debug, thiserror::Error, OperationIo)]
+#[aide(output_with = "Json<ErrorResponse>")]
+pub enum RouteError {
+    #[error(transparent)]
+    Internal(Box<dyn std::error::Error + Send + Sync + 'static>),
+}
+
+impl_from_error_for_route!(mas_storage::RepositoryError);
+
+impl IntoResponse for RouteError {
+    fn into_response(self) -> axum::response::Response {
+        let error = ErrorResponse::from_error(&self);
+        let sentry_event_id = record_error!(self, Self::Internal(_));
+        let status = match self {
+            Self::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        (status, sentry_event_id, Json(error)).into_response()
+    }
+}
+
+/// A queue paused by an administrator
+#[derive(Serialize, JsonSchema)]
+pub struct PausedQueue {
+    /// The name of the queue
+    name: String,
+
+    /// When the queue was paused
+    paused_at: DateTime<Utc>,
+}
+
+/// The list of paused queues
+#[derive(Serialize, JsonSchema)]
+pub struct PausedQueues {
+    /// The queues which are currently paused
+    queues: Vec<PausedQueue>,
+}
+
+pub fn doc(operation: TransformOperation) -> TransformOperation {
+    operation
+        .id("listPausedQueues")
+        .summary("List the paused queues")
+        .tag("queue")
+        .response_with::<200, Json<PausedQueues>, _>(|t| {
+            t.description("The list of paused queues")
+                .example(PausedQueues {
+                    queues: vec![PausedQueue {
+                        name: "provision-user".to_owned(),
+                        paused_at: DateTime::default(),
+                    }],
+                })
+        })
+}
+
+#[tracing::instrument(name = "handler.admin.v1.queues.paused", skip_all)]
+pub async fn handler(
+    CallContext { mut repo, .. }: CallContext,
+) -> Result<Json<PausedQueues>, RouteError> {
+    let queues = repo
+        .queue_worker()
+        .list_paused_queues()
+        .await?
+        .into_iter()
+        .map(|queue| PausedQueue {
+            name: queue.queue_name,
+            paused_at: queue.paused_at,
+        })
+        .collect();
+
+    Ok(Json(PausedQueues { queues }))
+}
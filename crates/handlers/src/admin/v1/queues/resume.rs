@@ -0,0 +1,69 @@
+// Copyright 2026 Element Creations Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+// Please see LICENSE files in the repository root for full details.
+
+use aide::{OperationIo, transform::TransformOperation};
+use axum::{Json, extract::Path, response::IntoResponse};
+use hyper::StatusCode;
+use mas_axum_utils::record_error;
+use tracing::info;
+
+use super::QueueNamePathParam;
+use crate::{
+    admin::{call_context::CallContext, response::ErrorResponse},
+    impl_from_error_for_route,
+};
+
+#[derive(Debug, thiserror::Error, OperationIo)]
+#[aide(output_with = "Json<ErrorResponse>")]
+pub enum RouteError {
+    #[error(transparent)]
+    Internal(Box<dyn std::error::Error + Send + Sync + 'static>),
+
+    #[error("Queue {0:?} is not paused")]
+    NotPaused(String),
+}
+
+impl_from_error_for_route!(mas_storage::RepositoryError);
+
+impl IntoResponse for RouteError {
+    fn into_response(self) -> axum::response::Response {
+        let error = ErrorResponse::from_error(&self);
+        let sentry_event_id = record_error!(self, Self::Internal(_));
+        let status = match self {
+            Self::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            Self::NotPaused(_) => StatusCode::NOT_FOUND,
+        };
+        (status, sentry_event_id, Json(error)).into_response()
+    }
+}
+
+pub fn doc(operation: TransformOperation) -> TransformOperation {
+    operation
+        .id("resumeQueue")
+        .summary("Resume a paused queue")
+        .tag("queue")
+        .response_with::<204, (), _>(|t| t.description("The queue was resumed"))
+        .response_with::<404, RouteError, _>(|t| {
+            let response =
+                ErrorResponse::from_error(&RouteError::NotPaused("provision-user".to_owned()));
+            t.description("The queue is not paused").example(response)
+        })
+}
+
+#[tracing::instrument(name = "handler.admin.v1.queues.resume", skip_all)]
+pub async fn handler(
+    CallContext { mut repo, .. }: CallContext,
+    Path(QueueNamePathParam { name }): Path<QueueNamePathParam>,
+) -> Result<StatusCode, RouteError> {
+    if !repo.queue_worker().resume_queue(&name).await? {
+        return Err(RouteError::NotPaused(name));
+    }
+
+    repo.save().await?;
+
+    info!(queue.name = %name, "Queue resumed by admin");
+
+    Ok(StatusCode::NO_CONTENT)
+}
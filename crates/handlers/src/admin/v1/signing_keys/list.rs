@@ -0,0 +1,146 @@
+// Copyright 2026 Element Creations Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+// Please see LICENSE files in the repository root for full details.
+
+use aide::{OperationIo, transform::TransformOperation};
+use axum::{Json, response::IntoResponse};
+use chrono::{DateTime, Utc};
+use hyper::StatusCode;
+use mas_axum_utils::record_error;
+use schemars::JsonSchema;
+use serde::Serialize;
+use ulid::Ulid;
+
+use crate::{
+    admin::{call_context::CallContext, response::ErrorResponse},
+    impl_from_error_for_route,
+};
+
+#[derive(Debug, thiserror::Error, OperationIo)]
+#[aide(output_with = "Json<ErrorResponse>")]
+pub enum RouteError {
+    #[error(transparent)]
+    Internal(Box<dyn std::error::Error + Send + Sync + 'static>),
+}
+
+impl_from_error_for_route!(mas_storage::RepositoryError);
+
+impl IntoResponse for RouteError {
+    fn into_response(self) -> axum::response::Response {
+        let error = ErrorResponse::from_error(&self);
+        let sentry_event_id = record_error!(self, Self::Internal(_));
+        let status = match self {
+            Self::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        (status, sentry_event_id, Json(error)).into_response()
+    }
+}
+
+/// The state of a signing key
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SigningKeyState {
+    /// The key is published, but not used for signing yet
+    Pending,
+
+    /// The key is used for signing
+    Active,
+
+    /// The key is not used for signing anymore, but is still published
+    Retired,
+}
+
+/// A signing key managed by the service
+#[derive(Serialize, JsonSchema)]
+pub struct SigningKey {
+    /// The ID of the key
+    #[schemars(with = "crate::admin::schema::Ulid")]
+    id: Ulid,
+
+    /// The key ID, as published in the JWKS
+    kid: String,
+
+    /// The type of key
+    key_type: String,
+
+    /// The state of the key
+    state: SigningKeyState,
+
+    /// When the key was created and published
+    created_at: DateTime<Utc>,
+
+    /// When the key started being used for signing
+    activated_at: Option<DateTime<Utc>>,
+
+    /// When the key stopped being used for signing
+    retired_at: Option<DateTime<Utc>>,
+}
+
+impl From<mas_data_model::SigningKey> for SigningKey {
+    fn from(key: mas_data_model::SigningKey) -> Self {
+        let state = if key.is_retired() {
+            SigningKeyState::Retired
+        } else if key.is_active() {
+            SigningKeyState::Active
+        } else {
+            SigningKeyState::Pending
+        };
+
+        Self {
+            id: key.id,
+            key_type: key.key_type.to_string(),
+            state,
+            created_at: key.created_at,
+            activated_at: key.activated_at(),
+            retired_at: key.retired_at(),
+            kid: key.kid,
+        }
+    }
+}
+
+/// The list of signing keys managed by the service
+#[derive(Serialize, JsonSchema)]
+pub struct SigningKeys {
+    /// The signing keys, from the oldest to the most recent
+    keys: Vec<SigningKey>,
+}
+
+pub fn doc(operation: TransformOperation) -> TransformOperation {
+    operation
+        .id("listSigningKeys")
+        .summary("List the signing keys managed by the service")
+        .description(
+            "This only lists the keys generated by the automatic key rotation, not the ones from the configuration file.",
+        )
+        .tag("signing-key")
+        .response_with::<200, Json<SigningKeys>, _>(|t| {
+            t.description("The list of signing keys")
+                .example(SigningKeys {
+                    keys: vec![SigningKey {
+                        id: Ulid::from_bytes([0x01; 16]),
+                        kid: "xmgGCzGtQFmhEOP0YAqBt-oZyVauSVMXcf4kwcgGZLc".to_owned(),
+                        key_type: "rsa".to_owned(),
+                        state: SigningKeyState::Active,
+                        created_at: DateTime::default(),
+                        activated_at: Some(DateTime::default()),
+                        retired_at: None,
+                    }],
+                })
+        })
+}
+
+#[tracing::instrument(name = "handler.admin.v1.signing_keys.list", skip_all)]
+pub async fn handler(
+    CallContext { mut repo, .. }: CallContext,
+) -> Result<Json<SigningKeys>, RouteError> {
+    let keys = repo
+        .signing_key()
+        .all()
+        .await?
+        .into_iter()
+        .map(SigningKey::from)
+        .collect();
+
+    Ok(Json(SigningKeys { keys }))
+}
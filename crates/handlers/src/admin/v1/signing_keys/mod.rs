@@ -0,0 +1,12 @@
+// Copyright 2026 Element Creations Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+// Please see LICENSE files in the repository root for full details.
+
+mod list;
+mod rotate;
+
+pub use self::{
+    list::{doc as list_doc, handler as list},
+    rotate::{doc as rotate_doc, handler as rotate},
+};
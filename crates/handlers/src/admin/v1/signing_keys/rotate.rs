@@ -0,0 +1,142 @@
+// Copyright 2026 Element Creations Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+// Please see LICENSE files in the repository root for full details.
+
+use aide::{NoApi, OperationIo, transform::TransformOperation};
+use axum::{Json, extract::State, response::IntoResponse};
+use hyper::StatusCode;
+use mas_axum_utils::record_error;
+use mas_data_model::{BoxRng, SiteConfig};
+use mas_storage::queue::{QueueJobRepositoryExt as _, RotateSigningKeysJob};
+use tracing::info;
+
+use crate::{
+    admin::{call_context::CallContext, response::ErrorResponse},
+    impl_from_error_for_route,
+};
+
+#[derive(Debug, thiserror::Error, OperationIo)]
+#[aide(output_with = "Json<ErrorResponse>")]
+pub enum RouteError {
+    #[error(transparent)]
+    Internal(Box<dyn std::error::Error + Send + Sync + 'static>),
+
+    #[error("Signing key rotation is not enabled")]
+    NotEnabled,
+}
+
+impl_from_error_for_route!(mas_storage::RepositoryError);
+
+impl IntoResponse for RouteError {
+    fn into_response(self) -> axum::response::Response {
+        let error = ErrorResponse::from_error(&self);
+        let sentry_event_id = record_error!(self, Self::Internal(_));
+        let status = match self {
+            Self::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            Self::NotEnabled => StatusCode::BAD_REQUEST,
+        };
+        (status, sentry_event_id, Json(error)).into_response()
+    }
+}
+
+pub fn doc(operation: TransformOperation) -> TransformOperation {
+    operation
+        .id("rotateSigningKeys")
+        .summary("Rotate the signing keys managed by the service")
+        .description(
+            r"Schedule a job to publish new signing keys, even if the current ones are not due for rotation yet.
+The new keys are only used for signing once they have been published for the configured publication period, after which the current keys are retired.",
+        )
+        .tag("signing-key")
+        .response_with::<202, (), _>(|t| t.description("The rotation was scheduled"))
+        .response_with::<400, RouteError, _>(|t| {
+            let response = ErrorResponse::from_error(&RouteError::NotEnabled);
+            t.description("Signing key rotation is not enabled")
+                .example(response)
+        })
+}
+
+#[tracing::instrument(name = "handler.admin.v1.signing_keys.rotate", skip_all)]
+pub async fn handler(
+    CallContext {
+        mut repo, clock, ..
+    }: CallContext,
+    NoApi(mut rng): NoApi<BoxRng>,
+    State(site_config): State<SiteConfig>,
+) -> Result<StatusCode, RouteError> {
+    if site_config.signing_key_rotation.is_none() {
+        return Err(RouteError::NotEnabled);
+    }
+
+    repo.queue_job()
+        .schedule_job(&mut rng, &clock, RotateSigningKeysJob::forced())
+        .await?;
+
+    repo.save().await?;
+
+    info!("Signing key rotation scheduled by admin");
+
+    Ok(StatusCode::ACCEPTED)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+    use hyper::{Request, StatusCode};
+    use mas_data_model::{SigningKeyRotationConfig, SigningKeyType};
+    use sqlx::PgPool;
+
+    use crate::test_utils::{RequestBuilderExt, ResponseExt, TestState, setup, test_site_config};
+
+    #[sqlx::test(migrator = "mas_storage_pg::MIGRATOR")]
+    async fn test_rotate_not_enabled(pool: PgPool) {
+        setup();
+        let mut state = TestState::from_pool(pool).await.unwrap();
+        let token = state.token_with_scope("urn:mas:admin").await;
+
+        let request = Request::post("/api/admin/v1/signing-keys/rotate")
+            .bearer(&token)
+            .empty();
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::BAD_REQUEST);
+    }
+
+    #[sqlx::test(migrator = "mas_storage_pg::MIGRATOR")]
+    async fn test_rotate(pool: PgPool) {
+        setup();
+        let site_config = mas_data_model::SiteConfig {
+            signing_key_rotation: Some(SigningKeyRotationConfig {
+                key_types: vec![SigningKeyType::EcP256],
+                rotation_interval: Duration::days(30),
+                publication_period: Duration::days(1),
+                retention_period: Duration::days(1),
+            }),
+            ..test_site_config()
+        };
+        let mut state = TestState::from_pool_with_site_config(pool, site_config)
+            .await
+            .unwrap();
+        let token = state.token_with_scope("urn:mas:admin").await;
+
+        let request = Request::post("/api/admin/v1/signing-keys/rotate")
+            .bearer(&token)
+            .empty();
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::ACCEPTED);
+
+        // Run the job, which should publish a new pending key
+        state.run_jobs_in_queue().await;
+
+        let request = Request::get("/api/admin/v1/signing-keys")
+            .bearer(&token)
+            .empty();
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::OK);
+        let body: serde_json::Value = response.json();
+        let keys = body["keys"].as_array().unwrap();
+        assert_eq!(keys.len(), 1);
+        assert_eq!(keys[0]["key_type"], "ec_p256");
+        assert_eq!(keys[0]["state"], "pending");
+    }
+}
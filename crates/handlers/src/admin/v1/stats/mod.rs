@@ -0,0 +1,114 @@
+// Copyright 2026 Element Creations Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+// Please see LICENSE files in the repository root for full details.
+
+mod sessions;
+mod upstream_logins;
+mod users;
+
+use aide::OperationIo;
+use axum::{Json, extract::FromRequestParts, response::IntoResponse};
+use axum_extra::extract::{Query, QueryRejection};
+use chrono::{DateTime, Days, NaiveDate, Utc};
+use hyper::StatusCode;
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+pub use self::{
+    sessions::{doc as sessions_doc, handler as sessions},
+    upstream_logins::{doc as upstream_logins_doc, handler as upstream_logins},
+    users::{doc as users_doc, handler as users},
+};
+use crate::admin::response::ErrorResponse;
+
+/// The maximum number of days which can be retrieved at once
+const MAX_DAYS: u64 = 366;
+
+/// The number of days retrieved if no `since` parameter is given
+const DEFAULT_DAYS: u64 = 30;
+
+#[derive(Deserialize, JsonSchema, Clone, Copy)]
+#[serde(rename = "StatsRange")]
+struct StatsRangeParams {
+    /// The first day to retrieve statistics for, inclusive.
+    ///
+    /// Defaults to retrieving 30 days of statistics, up to `until`.
+    since: Option<NaiveDate>,
+
+    /// The last day to retrieve statistics for, inclusive.
+    ///
+    /// Defaults to yesterday, which is the last day statistics are computed
+    /// for.
+    until: Option<NaiveDate>,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("Invalid range parameters")]
+pub struct StatsRangeRejection(#[from] QueryRejection);
+
+impl IntoResponse for StatsRangeRejection {
+    fn into_response(self) -> axum::response::Response {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::from_error(&self)),
+        )
+            .into_response()
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum InvalidStatsRange {
+    #[error("The `since` parameter must not be after the `until` parameter")]
+    Inverted,
+
+    #[error("Cannot retrieve more than {MAX_DAYS} days of statistics at once")]
+    TooLarge,
+}
+
+/// A range of days to retrieve statistics for, as given in the query
+/// parameters
+#[derive(OperationIo, Debug, Clone, Copy)]
+#[aide(input_with = "Query<StatsRangeParams>")]
+pub struct StatsRange {
+    since: Option<NaiveDate>,
+    until: Option<NaiveDate>,
+}
+
+impl StatsRange {
+    /// Resolve the range to an inclusive pair of days, filling in the
+    /// defaults relative to the given time
+    pub fn resolve(self, now: DateTime<Utc>) -> Result<(NaiveDate, NaiveDate), InvalidStatsRange> {
+        let until = self
+            .until
+            .unwrap_or_else(|| now.date_naive() - Days::new(1));
+        let since = self
+            .since
+            .unwrap_or_else(|| until - Days::new(DEFAULT_DAYS - 1));
+
+        if since > until {
+            return Err(InvalidStatsRange::Inverted);
+        }
+
+        if since + Days::new(MAX_DAYS) <= until {
+            return Err(InvalidStatsRange::TooLarge);
+        }
+
+        Ok((since, until))
+    }
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for StatsRange {
+    type Rejection = StatsRangeRejection;
+
+    async fn from_request_parts(
+        parts: &mut axum::http::request::Parts,
+        state: &S,
+    ) -> Result<Self, Self::Rejection> {
+        let Query(params) = Query::<StatsRangeParams>::from_request_parts(parts, state).await?;
+        Ok(Self {
+            since: params.since,
+            until: params.until,
+        })
+    }
+}
@@ -0,0 +1,221 @@
+// Copyright 2026 Element Creations Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+// Please see LICENSE files in the repository root for full details.
+
+use std::collections::BTreeMap;
+
+use aide::{OperationIo, transform::TransformOperation};
+use axum::{Json, response::IntoResponse};
+use chrono::NaiveDate;
+use hyper::StatusCode;
+use mas_axum_utils::record_error;
+use mas_storage::stats::StatsMetric;
+use schemars::JsonSchema;
+use serde::Serialize;
+use ulid::Ulid;
+
+use super::{InvalidStatsRange, StatsRange};
+use crate::{
+    admin::{call_context::CallContext, response::ErrorResponse},
+    impl_from_error_for_route,
+};
+
+#[derive(Debug, thiserror::Error, OperationIo)]
+#[aide(output_with = "Json<ErrorResponse>")]
+pub enum RouteError {
+    #[error(transparent)]
+    Internal(Box<dyn std::error::Error + Send + Sync + 'static>),
+
+    #[error(transparent)]
+    InvalidRange(#[from] InvalidStatsRange),
+}
+
+impl_from_error_for_route!(mas_storage::RepositoryError);
+
+impl IntoResponse for RouteError {
+    fn into_response(self) -> axum::response::Response {
+        let error = ErrorResponse::from_error(&self);
+        let sentry_event_id = record_error!(self, Self::Internal(_));
+        let status = match self {
+            Self::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            Self::InvalidRange(_) => StatusCode::BAD_REQUEST,
+        };
+        (status, sentry_event_id, Json(error)).into_response()
+    }
+}
+
+/// Statistics about active sessions at the end of a single day
+#[derive(Serialize, JsonSchema)]
+pub struct SessionStatsDay {
+    /// The day, in UTC
+    day: NaiveDate,
+
+    /// The number of active browser sessions
+    browser: usize,
+
+    /// The number of active compatibility sessions
+    compat: usize,
+
+    /// The number of active OAuth 2.0 sessions
+    oauth2: usize,
+
+    /// The number of active OAuth 2.0 sessions, keyed by the ID of the client.
+    /// Clients without active sessions are omitted.
+    oauth2_by_client: BTreeMap<String, usize>,
+}
+
+impl SessionStatsDay {
+    fn new(day: NaiveDate) -> Self {
+        Self {
+            day,
+            browser: 0,
+            compat: 0,
+            oauth2: 0,
+            oauth2_by_client: BTreeMap::new(),
+        }
+    }
+}
+
+/// Daily statistics about active sessions
+#[derive(Serialize, JsonSchema)]
+pub struct SessionStats {
+    /// The statistics for each day statistics were computed for, oldest first
+    days: Vec<SessionStatsDay>,
+}
+
+pub fn doc(operation: TransformOperation) -> TransformOperation {
+    operation
+        .id("getSessionStats")
+        .summary("Get daily statistics about active sessions")
+        .description(
+            "Statistics are computed once a day, shortly after midnight UTC, and reflect the sessions which were active at that time. Days missed by the daily computation, for example during an outage, are filled in later without session counts.",
+        )
+        .tag("stats")
+        .response_with::<200, Json<SessionStats>, _>(|t| {
+            let day = NaiveDate::default();
+            t.description("Daily statistics about active sessions")
+                .example(SessionStats {
+                    days: vec![SessionStatsDay {
+                        day,
+                        browser: 30,
+                        compat: 12,
+                        oauth2: 25,
+                        oauth2_by_client: BTreeMap::from([
+                            (Ulid::from_bytes([0x01; 16]).to_string(), 20),
+                            (Ulid::from_bytes([0x02; 16]).to_string(), 5),
+                        ]),
+                    }],
+                })
+        })
+        .response_with::<400, RouteError, _>(|t| {
+            let response = ErrorResponse::from_error(&RouteError::InvalidRange(
+                InvalidStatsRange::Inverted,
+            ));
+            t.description("The range of days is invalid").example(response)
+        })
+}
+
+#[tracing::instrument(name = "handler.admin.v1.stats.sessions", skip_all)]
+pub async fn handler(
+    CallContext {
+        mut repo, clock, ..
+    }: CallContext,
+    range: StatsRange,
+) -> Result<Json<SessionStats>, RouteError> {
+    let (since, until) = range.resolve(clock.now())?;
+
+    let mut days = BTreeMap::new();
+    for metric in [
+        StatsMetric::ActiveBrowserSessions,
+        StatsMetric::ActiveCompatSessions,
+        StatsMetric::ActiveOAuth2Sessions,
+    ] {
+        for stat in repo.stats().list(metric, since, until).await? {
+            let entry = days
+                .entry(stat.day)
+                .or_insert_with(|| SessionStatsDay::new(stat.day));
+            match (metric, stat.dimension) {
+                (StatsMetric::ActiveBrowserSessions, _) => entry.browser = stat.value,
+                (StatsMetric::ActiveCompatSessions, _) => entry.compat = stat.value,
+                (_, None) => entry.oauth2 = stat.value,
+                (_, Some(client_id)) => {
+                    entry
+                        .oauth2_by_client
+                        .insert(client_id.to_string(), stat.value);
+                }
+            }
+        }
+    }
+
+    Ok(Json(SessionStats {
+        days: days.into_values().collect(),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+    use hyper::{Request, StatusCode};
+    use mas_storage::stats::{DailyStat, StatsMetric};
+    use sqlx::PgPool;
+    use ulid::Ulid;
+
+    use crate::test_utils::{RequestBuilderExt, ResponseExt, TestState, setup};
+
+    #[sqlx::test(migrator = "mas_storage_pg::MIGRATOR")]
+    async fn test_session_stats(pool: PgPool) {
+        setup();
+        let mut state = TestState::from_pool(pool).await.unwrap();
+        let token = state.token_with_scope("urn:mas:admin").await;
+
+        let day = NaiveDate::from_ymd_opt(2026, 10, 1).unwrap();
+        let client_id = Ulid::from_bytes([0x01; 16]);
+        let stat = |metric, dimension, value| DailyStat {
+            day,
+            metric,
+            dimension,
+            value,
+        };
+
+        let mut repo = state.repository().await.unwrap();
+        repo.stats()
+            .replace_day(
+                &state.clock,
+                day,
+                &[
+                    stat(StatsMetric::ActiveBrowserSessions, None, 3),
+                    stat(StatsMetric::ActiveCompatSessions, None, 2),
+                    stat(StatsMetric::ActiveOAuth2Sessions, None, 1),
+                    stat(StatsMetric::ActiveOAuth2Sessions, Some(client_id), 1),
+                ],
+            )
+            .await
+            .unwrap();
+        repo.save().await.unwrap();
+
+        let request =
+            Request::get("/api/admin/v1/stats/sessions?since=2026-10-01&until=2026-10-01")
+                .bearer(&token)
+                .empty();
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::OK);
+        let body: serde_json::Value = response.json();
+        assert_eq!(
+            body,
+            serde_json::json!({
+                "days": [
+                    {
+                        "day": "2026-10-01",
+                        "browser": 3,
+                        "compat": 2,
+                        "oauth2": 1,
+                        "oauth2_by_client": {
+                            "01040G2081040G2081040G2081": 1,
+                        },
+                    },
+                ],
+            })
+        );
+    }
+}
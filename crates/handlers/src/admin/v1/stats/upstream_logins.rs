@@ -0,0 +1,132 @@
+// Copyright 2026 Element Creations Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+// Please see LICENSE files in the repository root for full details.
+
+use std::collections::BTreeMap;
+
+use aide::{OperationIo, transform::TransformOperation};
+use axum::{Json, response::IntoResponse};
+use chrono::NaiveDate;
+use hyper::StatusCode;
+use mas_axum_utils::record_error;
+use mas_storage::stats::StatsMetric;
+use schemars::JsonSchema;
+use serde::Serialize;
+use ulid::Ulid;
+
+use super::{InvalidStatsRange, StatsRange};
+use crate::{
+    admin::{call_context::CallContext, response::ErrorResponse},
+    impl_from_error_for_route,
+};
+
+#[derive(Debug, thiserror::Error, OperationIo)]
+#[aide(output_with = "Json<ErrorResponse>")]
+pub enum RouteError {
+    #[error(transparent)]
+    Internal(Box<dyn std::error::Error + Send + Sync + 'static>),
+
+    #[error(transparent)]
+    InvalidRange(#[from] InvalidStatsRange),
+}
+
+impl_from_error_for_route!(mas_storage::RepositoryError);
+
+impl IntoResponse for RouteError {
+    fn into_response(self) -> axum::response::Response {
+        let error = ErrorResponse::from_error(&self);
+        let sentry_event_id = record_error!(self, Self::Internal(_));
+        let status = match self {
+            Self::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            Self::InvalidRange(_) => StatusCode::BAD_REQUEST,
+        };
+        (status, sentry_event_id, Json(error)).into_response()
+    }
+}
+
+/// Statistics about logins through upstream providers on a single day
+#[derive(Serialize, JsonSchema)]
+pub struct UpstreamLoginStatsDay {
+    /// The day, in UTC
+    day: NaiveDate,
+
+    /// The total number of logins through an upstream provider
+    total: usize,
+
+    /// The number of logins, keyed by the ID of the upstream provider
+    by_provider: BTreeMap<String, usize>,
+}
+
+/// Daily statistics about logins through upstream providers
+#[derive(Serialize, JsonSchema)]
+pub struct UpstreamLoginStats {
+    /// The statistics for each day statistics were computed for, oldest first
+    days: Vec<UpstreamLoginStatsDay>,
+}
+
+pub fn doc(operation: TransformOperation) -> TransformOperation {
+    operation
+        .id("getUpstreamLoginStats")
+        .summary("Get daily statistics about logins through upstream providers")
+        .description(
+            "Statistics are computed once a day, shortly after midnight UTC, for the previous day. Only providers which were enabled at that time are counted.",
+        )
+        .tag("stats")
+        .response_with::<200, Json<UpstreamLoginStats>, _>(|t| {
+            let day = NaiveDate::default();
+            t.description("Daily statistics about logins through upstream providers")
+                .example(UpstreamLoginStats {
+                    days: vec![UpstreamLoginStatsDay {
+                        day,
+                        total: 17,
+                        by_provider: BTreeMap::from([
+                            (Ulid::from_bytes([0x01; 16]).to_string(), 15),
+                            (Ulid::from_bytes([0x02; 16]).to_string(), 2),
+                        ]),
+                    }],
+                })
+        })
+        .response_with::<400, RouteError, _>(|t| {
+            let response = ErrorResponse::from_error(&RouteError::InvalidRange(
+                InvalidStatsRange::Inverted,
+            ));
+            t.description("The range of days is invalid").example(response)
+        })
+}
+
+#[tracing::instrument(name = "handler.admin.v1.stats.upstream_logins", skip_all)]
+pub async fn handler(
+    CallContext {
+        mut repo, clock, ..
+    }: CallContext,
+    range: StatsRange,
+) -> Result<Json<UpstreamLoginStats>, RouteError> {
+    let (since, until) = range.resolve(clock.now())?;
+
+    let mut days = BTreeMap::new();
+    let stats = repo
+        .stats()
+        .list(StatsMetric::UpstreamLogins, since, until)
+        .await?;
+    for stat in stats {
+        let entry = days
+            .entry(stat.day)
+            .or_insert_with(|| UpstreamLoginStatsDay {
+                day: stat.day,
+                total: 0,
+                by_provider: BTreeMap::new(),
+            });
+
+        entry.total += stat.value;
+        if let Some(provider_id) = stat.dimension {
+            entry
+                .by_provider
+                .insert(provider_id.to_string(), stat.value);
+        }
+    }
+
+    Ok(Json(UpstreamLoginStats {
+        days: days.into_values().collect(),
+    }))
+}
@@ -0,0 +1,223 @@
+// Copyright 2026 Element Creations Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+// Please see LICENSE files in the repository root for full details.
+
+use std::collections::BTreeMap;
+
+use aide::{OperationIo, transform::TransformOperation};
+use axum::{Json, response::IntoResponse};
+use chrono::NaiveDate;
+use hyper::StatusCode;
+use mas_axum_utils::record_error;
+use mas_storage::stats::StatsMetric;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use super::{InvalidStatsRange, StatsRange};
+use crate::{
+    admin::{call_context::CallContext, response::ErrorResponse},
+    impl_from_error_for_route,
+};
+
+#[derive(Debug, thiserror::Error, OperationIo)]
+#[aide(output_with = "Json<ErrorResponse>")]
+pub enum RouteError {
+    #[error(transparent)]
+    Internal(Box<dyn std::error::Error + Send + Sync + 'static>),
+
+    #[error(transparent)]
+    InvalidRange(#[from] InvalidStatsRange),
+}
+
+impl_from_error_for_route!(mas_storage::RepositoryError);
+
+impl IntoResponse for RouteError {
+    fn into_response(self) -> axum::response::Response {
+        let error = ErrorResponse::from_error(&self);
+        let sentry_event_id = record_error!(self, Self::Internal(_));
+        let status = match self {
+            Self::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            Self::InvalidRange(_) => StatusCode::BAD_REQUEST,
+        };
+        (status, sentry_event_id, Json(error)).into_response()
+    }
+}
+
+/// Statistics about users on a single day
+#[derive(Serialize, JsonSchema)]
+pub struct UserStatsDay {
+    /// The day, in UTC
+    day: NaiveDate,
+
+    /// The number of users which were active on that day
+    daily_active_users: usize,
+
+    /// The number of users which were active in the 30 days up to the end of
+    /// that day
+    monthly_active_users: usize,
+
+    /// The number of users registered on that day
+    new_registrations: usize,
+}
+
+impl UserStatsDay {
+    fn new(day: NaiveDate) -> Self {
+        Self {
+            day,
+            daily_active_users: 0,
+            monthly_active_users: 0,
+            new_registrations: 0,
+        }
+    }
+}
+
+/// Daily statistics about users
+#[derive(Serialize, JsonSchema)]
+pub struct UserStats {
+    /// The statistics for each day statistics were computed for, oldest first
+    days: Vec<UserStatsDay>,
+}
+
+pub fn doc(operation: TransformOperation) -> TransformOperation {
+    operation
+        .id("getUserStats")
+        .summary("Get daily statistics about users")
+        .description(
+            r"Statistics are computed once a day, shortly after midnight UTC, for the previous day.
+Activity is based on the last time a session was active, so a user active on a day and again on the next day before statistics were computed is not counted in the daily active users.",
+        )
+        .tag("stats")
+        .response_with::<200, Json<UserStats>, _>(|t| {
+            let day = NaiveDate::default();
+            t.description("Daily statistics about users").example(UserStats {
+                days: vec![UserStatsDay {
+                    day,
+                    daily_active_users: 42,
+                    monthly_active_users: 123,
+                    new_registrations: 5,
+                }],
+            })
+        })
+        .response_with::<400, RouteError, _>(|t| {
+            let response = ErrorResponse::from_error(&RouteError::InvalidRange(
+                InvalidStatsRange::Inverted,
+            ));
+            t.description("The range of days is invalid").example(response)
+        })
+}
+
+#[tracing::instrument(name = "handler.admin.v1.stats.users", skip_all)]
+pub async fn handler(
+    CallContext {
+        mut repo, clock, ..
+    }: CallContext,
+    range: StatsRange,
+) -> Result<Json<UserStats>, RouteError> {
+    let (since, until) = range.resolve(clock.now())?;
+
+    let mut days = BTreeMap::new();
+    for metric in [
+        StatsMetric::DailyActiveUsers,
+        StatsMetric::MonthlyActiveUsers,
+        StatsMetric::NewRegistrations,
+    ] {
+        for stat in repo.stats().list(metric, since, until).await? {
+            let entry = days
+                .entry(stat.day)
+                .or_insert_with(|| UserStatsDay::new(stat.day));
+            match metric {
+                StatsMetric::DailyActiveUsers => entry.daily_active_users = stat.value,
+                StatsMetric::MonthlyActiveUsers => entry.monthly_active_users = stat.value,
+                _ => entry.new_registrations = stat.value,
+            }
+        }
+    }
+
+    Ok(Json(UserStats {
+        days: days.into_values().collect(),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+    use hyper::{Request, StatusCode};
+    use mas_storage::stats::{DailyStat, StatsMetric};
+    use sqlx::PgPool;
+
+    use crate::test_utils::{RequestBuilderExt, ResponseExt, TestState, setup};
+
+    #[sqlx::test(migrator = "mas_storage_pg::MIGRATOR")]
+    async fn test_user_stats(pool: PgPool) {
+        setup();
+        let mut state = TestState::from_pool(pool).await.unwrap();
+        let token = state.token_with_scope("urn:mas:admin").await;
+
+        let day1 = NaiveDate::from_ymd_opt(2026, 10, 1).unwrap();
+        let day2 = NaiveDate::from_ymd_opt(2026, 10, 2).unwrap();
+        let mut repo = state.repository().await.unwrap();
+        for (day, dau, mau) in [(day1, 10, 100), (day2, 12, 101)] {
+            repo.stats()
+                .replace_day(
+                    &state.clock,
+                    day,
+                    &[
+                        DailyStat {
+                            day,
+                            metric: StatsMetric::DailyActiveUsers,
+                            dimension: None,
+                            value: dau,
+                        },
+                        DailyStat {
+                            day,
+                            metric: StatsMetric::MonthlyActiveUsers,
+                            dimension: None,
+                            value: mau,
+                        },
+                    ],
+                )
+                .await
+                .unwrap();
+        }
+        repo.save().await.unwrap();
+
+        let request = Request::get("/api/admin/v1/stats/users?since=2026-10-01&until=2026-10-31")
+            .bearer(&token)
+            .empty();
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::OK);
+        let body: serde_json::Value = response.json();
+        assert_eq!(
+            body,
+            serde_json::json!({
+                "days": [
+                    {
+                        "day": "2026-10-01",
+                        "daily_active_users": 10,
+                        "monthly_active_users": 100,
+                        "new_registrations": 0,
+                    },
+                    {
+                        "day": "2026-10-02",
+                        "daily_active_users": 12,
+                        "monthly_active_users": 101,
+                        "new_registrations": 0,
+                    },
+                ],
+            })
+        );
+
+        let request = Request::get("/api/admin/v1/stats/users?since=2026-10-02&until=2026-10-01")
+            .bearer(&token)
+            .empty();
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::BAD_REQUEST);
+
+        let request = Request::get("/api/admin/v1/stats/users?since=2020-01-01&until=2026-10-01")
+            .bearer(&token)
+            .empty();
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::BAD_REQUEST);
+    }
+}
@@ -0,0 +1,247 @@
+// Copyright 2026 Element Creations Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+// Please see LICENSE files in the repository root for full details.
+
+use aide::{NoApi, OperationIo, transform::TransformOperation};
+use axum::{Json, extract::State, response::IntoResponse};
+use hyper::StatusCode;
+use mas_axum_utils::record_error;
+use mas_data_model::BoxRng;
+use mas_keystore::{Encrypter, Keystore};
+use ulid::Ulid;
+
+use crate::{
+    ClientCertificateCache, MetadataCache, UpstreamClaimsSyncer,
+    admin::{
+        call_context::CallContext,
+        model::UpstreamOAuthAccessToken,
+        params::UlidPathParam,
+        response::{ErrorResponse, SingleResponse},
+    },
+    impl_from_error_for_route,
+};
+
+#[derive(Debug, thiserror::Error, OperationIo)]
+#[aide(output_with = "Json<ErrorResponse>")]
+pub enum RouteError {
+    #[error(transparent)]
+    Internal(Box<dyn std::error::Error + Send + Sync + 'static>),
+
+    #[error("Upstream OAuth 2.0 Link ID {0} not found")]
+    NotFound(Ulid),
+
+    #[error("No upstream access token is available for upstream OAuth 2.0 Link ID {0}")]
+    NoAccessToken(Ulid),
+}
+
+impl_from_error_for_route!(mas_storage::RepositoryError);
+
+impl IntoResponse for RouteError {
+    fn into_response(self) -> axum::response::Response {
+        let error = ErrorResponse::from_error(&self);
+        let sentry_event_id = record_error!(self, Self::Internal(_));
+        let status = match self {
+            Self::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            Self::NotFound(_) => StatusCode::NOT_FOUND,
+            Self::NoAccessToken(_) => StatusCode::CONFLICT,
+        };
+        (status, sentry_event_id, Json(error)).into_response()
+    }
+}
+
+pub fn doc(operation: TransformOperation) -> TransformOperation {
+    operation
+        .id("getUpstreamOAuthLinkAccessToken")
+        .summary("Get an access token issued by the upstream provider of a link")
+        .description(
+            "This returns the access token stored on the link, which can be used to call the APIs of the upstream provider on behalf of the user.
+The access token is refreshed first if it expired or is about to.
+This only works for providers which have `store_tokens` enabled.",
+        )
+        .tag("upstream-oauth-link")
+        .response_with::<200, Json<SingleResponse<UpstreamOAuthAccessToken>>, _>(|t| {
+            let [sample] = UpstreamOAuthAccessToken::samples();
+            let response = SingleResponse::new_canonical(sample);
+            t.description("An upstream access token is available")
+                .example(response)
+        })
+        .response_with::<404, RouteError, _>(|t| {
+            let response = ErrorResponse::from_error(&RouteError::NotFound(Ulid::nil()));
+            t.description("Upstream OAuth 2.0 link was not found")
+                .example(response)
+        })
+        .response_with::<409, RouteError, _>(|t| {
+            let response = ErrorResponse::from_error(&RouteError::NoAccessToken(Ulid::nil()));
+            t.description("No upstream access token is available for this link")
+                .example(response)
+        })
+}
+
+#[tracing::instrument(name = "handler.admin.v1.upstream_oauth_links.access_token", skip_all)]
+pub async fn handler(
+    CallContext {
+        mut repo, clock, ..
+    }: CallContext,
+    NoApi(mut rng): NoApi<BoxRng>,
+    NoApi(State(http_client)): NoApi<State<reqwest::Client>>,
+    NoApi(State(metadata_cache)): NoApi<State<MetadataCache>>,
+    NoApi(State(certificate_cache)): NoApi<State<ClientCertificateCache>>,
+    NoApi(State(keystore)): NoApi<State<Keystore>>,
+    NoApi(State(encrypter)): NoApi<State<Encrypter>>,
+    id: UlidPathParam,
+) -> Result<Json<SingleResponse<UpstreamOAuthAccessToken>>, RouteError> {
+    let link = repo
+        .upstream_oauth_link()
+        .lookup(*id)
+        .await?
+        .ok_or(RouteError::NotFound(*id))?;
+
+    let provider = repo
+        .upstream_oauth_provider()
+        .lookup(link.provider_id)
+        .await?
+        .ok_or_else(|| RouteError::Internal("Upstream OAuth 2.0 provider not found".into()))?;
+
+    let syncer = UpstreamClaimsSyncer::new(
+        http_client,
+        metadata_cache,
+        certificate_cache,
+        keystore,
+        encrypter,
+    );
+    let (access_token, expires_at) = syncer
+        .access_token(&mut rng, &clock, &mut repo, &provider, &link)
+        .await
+        .map_err(|e| RouteError::Internal(e.into()))?
+        .ok_or(RouteError::NoAccessToken(link.id))?;
+
+    // The tokens may have been refreshed
+    repo.save().await?;
+
+    Ok(Json(SingleResponse::new_canonical(
+        UpstreamOAuthAccessToken::new(link.id, access_token, expires_at),
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+    use hyper::{Request, StatusCode};
+    use mas_data_model::Clock;
+    use sqlx::PgPool;
+    use ulid::Ulid;
+
+    use super::super::test_utils;
+    use crate::test_utils::{RequestBuilderExt, ResponseExt, TestState, setup};
+
+    #[sqlx::test(migrator = "mas_storage_pg::MIGRATOR")]
+    async fn test_access_token(pool: PgPool) {
+        setup();
+        let mut state = TestState::from_pool(pool).await.unwrap();
+        let token = state.token_with_scope("urn:mas:admin").await;
+        let mut rng = state.rng();
+
+        // Provision a provider which stores tokens, and a link
+        let mut repo = state.repository().await.unwrap();
+        let provider = repo
+            .upstream_oauth_provider()
+            .add(
+                &mut rng,
+                &state.clock,
+                mas_storage::upstream_oauth2::UpstreamOAuthProviderParams {
+                    store_tokens: true,
+                    allow_initiated_login: false,
+                    encrypted_client_keys: None,
+                    ..test_utils::oidc_provider_params("provider1")
+                },
+            )
+            .await
+            .unwrap();
+        let link = repo
+            .upstream_oauth_link()
+            .add(
+                &mut rng,
+                &state.clock,
+                &provider,
+                "subject1".to_owned(),
+                None,
+            )
+            .await
+            .unwrap();
+        repo.save().await.unwrap();
+
+        // Nothing is stored on the link yet
+        let link_id = link.id;
+        let request = Request::post(format!(
+            "/api/admin/v1/upstream-oauth-links/{link_id}/access-token"
+        ))
+        .bearer(&token)
+        .empty();
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::CONFLICT);
+
+        // Store an access token on the link
+        let mut repo = state.repository().await.unwrap();
+        let encrypted_access_token = state
+            .encrypter
+            .encrypt_to_string(b"upstream-access-token")
+            .unwrap();
+        repo.upstream_oauth_link()
+            .set_encrypted_access_token(
+                &link,
+                Some(encrypted_access_token),
+                Some(state.clock.now() + Duration::hours(1)),
+            )
+            .await
+            .unwrap();
+        repo.save().await.unwrap();
+
+        let request = Request::post(format!(
+            "/api/admin/v1/upstream-oauth-links/{link_id}/access-token"
+        ))
+        .bearer(&token)
+        .empty();
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::OK);
+        let body: serde_json::Value = response.json();
+        assert_eq!(body["data"]["type"], "upstream-oauth-access-token");
+        assert_eq!(
+            body["data"]["attributes"],
+            serde_json::json!({
+                "access_token": "upstream-access-token",
+                "expires_at": "2022-01-16T15:40:00Z",
+            })
+        );
+        assert_eq!(
+            body["links"]["self"],
+            format!("/api/admin/v1/upstream-oauth-links/{link_id}/access-token")
+        );
+
+        // Once it expired, without a refresh token, it is not available anymore
+        state.clock.advance(Duration::hours(2));
+        let request = Request::post(format!(
+            "/api/admin/v1/upstream-oauth-links/{link_id}/access-token"
+        ))
+        .bearer(&token)
+        .empty();
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::CONFLICT);
+    }
+
+    #[sqlx::test(migrator = "mas_storage_pg::MIGRATOR")]
+    async fn test_not_found(pool: PgPool) {
+        setup();
+        let mut state = TestState::from_pool(pool).await.unwrap();
+        let token = state.token_with_scope("urn:mas:admin").await;
+
+        let link_id = Ulid::nil();
+        let request = Request::post(format!(
+            "/api/admin/v1/upstream-oauth-links/{link_id}/access-token"
+        ))
+        .bearer(&token)
+        .empty();
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::NOT_FOUND);
+    }
+}
@@ -3,12 +3,14 @@
 // SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
 // Please see LICENSE files in the repository root for full details.
 
+mod access_token;
 mod add;
 mod delete;
 mod get;
 mod list;
 
 pub use self::{
+    access_token::{doc as access_token_doc, handler as access_token},
     add::{doc as add_doc, handler as add},
     delete::{doc as delete_doc, handler as delete},
     get::{doc as get_doc, handler as get},
@@ -51,6 +53,11 @@ mod test_utils {
             forward_login_hint: false,
             ui_order: 0,
             on_backchannel_logout: UpstreamOAuthProviderOnBackchannelLogout::DoNothing,
+            saml: None,
+            email_domains: Vec::new(),
+            store_tokens: false,
+            allow_initiated_login: false,
+            encrypted_client_keys: None,
         }
     }
 }
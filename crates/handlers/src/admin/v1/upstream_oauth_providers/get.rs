@@ -118,6 +118,11 @@ mod tests {
             additional_authorization_parameters: vec![],
             forward_login_hint: false,
             on_backchannel_logout: UpstreamOAuthProviderOnBackchannelLogout::DoNothing,
+            saml: None,
+            email_domains: Vec::new(),
+            store_tokens: false,
+            allow_initiated_login: false,
+            encrypted_client_keys: None,
             ui_order: 0,
         };
 
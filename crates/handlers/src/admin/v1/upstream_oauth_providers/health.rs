@@ -0,0 +1,216 @@
+// Copyright 2026 Element Creations Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+// Please see LICENSE files in the repository root for full details.
+
+use aide::{OperationIo, transform::TransformOperation};
+use axum::{Json, response::IntoResponse};
+use hyper::StatusCode;
+use mas_axum_utils::record_error;
+use mas_storage::{RepositoryAccess, upstream_oauth2::UpstreamOAuthProviderRepository};
+
+use crate::{
+    admin::{
+        call_context::CallContext,
+        model::UpstreamOAuthProviderHealth,
+        params::UlidPathParam,
+        response::{ErrorResponse, SingleResponse},
+    },
+    impl_from_error_for_route,
+};
+
+#[derive(Debug, thiserror::Error, OperationIo)]
+#[aide(output_with = "Json<ErrorResponse>")]
+pub enum RouteError {
+    #[error(transparent)]
+    Internal(Box<dyn std::error::Error + Send + Sync + 'static>),
+
+    #[error("Provider not found")]
+    NotFound,
+
+    #[error("Provider was not checked yet")]
+    NotChecked,
+}
+
+impl_from_error_for_route!(mas_storage::RepositoryError);
+
+impl IntoResponse for RouteError {
+    fn into_response(self) -> axum::response::Response {
+        let error = ErrorResponse::from_error(&self);
+        let sentry_event_id = record_error!(self, Self::Internal(_));
+        let status = match self {
+            Self::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            Self::NotFound | Self::NotChecked => StatusCode::NOT_FOUND,
+        };
+
+        (status, sentry_event_id, Json(error)).into_response()
+    }
+}
+
+pub fn doc(operation: TransformOperation) -> TransformOperation {
+    operation
+        .id("getUpstreamOAuthProviderHealth")
+        .summary("Get the health of an upstream OAuth provider")
+        .description(
+            "Providers are checked periodically in the background. This returns the outcome of the last check.",
+        )
+        .tag("upstream-oauth-provider")
+        .response_with::<200, Json<SingleResponse<UpstreamOAuthProviderHealth>>, _>(|t| {
+            let [sample, ..] = UpstreamOAuthProviderHealth::samples();
+            t.description("The health of the upstream OAuth provider")
+                .example(SingleResponse::new_canonical(sample))
+        })
+        .response_with::<404, Json<ErrorResponse>, _>(|t| {
+            t.description("Provider not found, or not checked yet")
+        })
+}
+
+#[tracing::instrument(name = "handler.admin.v1.upstream_oauth_providers.health", skip_all)]
+pub async fn handler(
+    CallContext { mut repo, .. }: CallContext,
+    id: UlidPathParam,
+) -> Result<Json<SingleResponse<UpstreamOAuthProviderHealth>>, RouteError> {
+    let provider = repo
+        .upstream_oauth_provider()
+        .lookup(*id)
+        .await?
+        .ok_or(RouteError::NotFound)?;
+
+    let health = repo
+        .upstream_oauth_provider()
+        .health(&provider)
+        .await?
+        .ok_or(RouteError::NotChecked)?;
+
+    Ok(Json(SingleResponse::new_canonical(
+        UpstreamOAuthProviderHealth::from(health),
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use hyper::{Request, StatusCode};
+    use mas_data_model::{
+        UpstreamOAuthProviderClaimsImports, UpstreamOAuthProviderDiscoveryMode,
+        UpstreamOAuthProviderHealthCheck, UpstreamOAuthProviderHealthChecks,
+        UpstreamOAuthProviderOnBackchannelLogout, UpstreamOAuthProviderPkceMode,
+        UpstreamOAuthProviderTokenAuthMethod,
+    };
+    use mas_iana::jose::JsonWebSignatureAlg;
+    use mas_storage::{
+        RepositoryAccess,
+        upstream_oauth2::{UpstreamOAuthProviderParams, UpstreamOAuthProviderRepository},
+    };
+    use oauth2_types::scope::{OPENID, Scope};
+    use sqlx::PgPool;
+
+    use crate::test_utils::{RequestBuilderExt, ResponseExt, TestState, setup};
+
+    #[sqlx::test(migrator = "mas_storage_pg::MIGRATOR")]
+    async fn test_get_health(pool: PgPool) {
+        setup();
+        let mut state = TestState::from_pool(pool).await.unwrap();
+        let admin_token = state.token_with_scope("urn:mas:admin").await;
+
+        let mut repo = state.repository().await.unwrap();
+        let provider = repo
+            .upstream_oauth_provider()
+            .add(
+                &mut state.rng(),
+                &state.clock,
+                UpstreamOAuthProviderParams {
+                    issuer: Some("https://example.com/".to_owned()),
+                    human_name: None,
+                    brand_name: None,
+                    scope: Scope::from_iter([OPENID]),
+                    token_endpoint_auth_method: UpstreamOAuthProviderTokenAuthMethod::None,
+                    token_endpoint_signing_alg: None,
+                    id_token_signed_response_alg: JsonWebSignatureAlg::Rs256,
+                    client_id: "client".to_owned(),
+                    encrypted_client_secret: None,
+                    claims_imports: UpstreamOAuthProviderClaimsImports::default(),
+                    authorization_endpoint_override: None,
+                    token_endpoint_override: None,
+                    userinfo_endpoint_override: None,
+                    fetch_userinfo: false,
+                    userinfo_signed_response_alg: None,
+                    jwks_uri_override: None,
+                    discovery_mode: UpstreamOAuthProviderDiscoveryMode::Oidc,
+                    pkce_mode: UpstreamOAuthProviderPkceMode::Auto,
+                    response_mode: None,
+                    additional_authorization_parameters: Vec::new(),
+                    forward_login_hint: false,
+                    on_backchannel_logout: UpstreamOAuthProviderOnBackchannelLogout::DoNothing,
+                    ui_order: 0,
+                    saml: None,
+                    email_domains: Vec::new(),
+                    store_tokens: false,
+                    allow_initiated_login: false,
+                    encrypted_client_keys: None,
+                },
+            )
+            .await
+            .unwrap();
+        Box::new(repo).save().await.unwrap();
+
+        let path = format!(
+            "/api/admin/v1/upstream-oauth-providers/{}/health",
+            provider.id
+        );
+
+        // Not checked yet
+        let request = Request::get(&path).bearer(&admin_token).empty();
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::NOT_FOUND);
+
+        let mut repo = state.repository().await.unwrap();
+        repo.upstream_oauth_provider()
+            .record_health(
+                &state.clock,
+                &provider,
+                UpstreamOAuthProviderHealthChecks {
+                    discovery: UpstreamOAuthProviderHealthCheck::Failed {
+                        error: "connection refused".to_owned(),
+                    },
+                    jwks: UpstreamOAuthProviderHealthCheck::Skipped,
+                    token_endpoint: UpstreamOAuthProviderHealthCheck::Skipped,
+                },
+            )
+            .await
+            .unwrap();
+        Box::new(repo).save().await.unwrap();
+
+        let request = Request::get(&path).bearer(&admin_token).empty();
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::OK);
+        let body: serde_json::Value = response.json();
+        insta::assert_json_snapshot!(body, @r###"
+        {
+          "data": {
+            "type": "upstream-oauth-provider-health",
+            "id": "01FSHN9AG0MZAA6S4AF7CTV32E",
+            "attributes": {
+              "healthy": false,
+              "checked_at": "2022-01-16T14:40:00Z",
+              "discovery": {
+                "status": "failed",
+                "error": "connection refused"
+              },
+              "jwks": {
+                "status": "skipped"
+              },
+              "token_endpoint": {
+                "status": "skipped"
+              }
+            },
+            "links": {
+              "self": "/api/admin/v1/upstream-oauth-providers/01FSHN9AG0MZAA6S4AF7CTV32E/health"
+            }
+          },
+          "links": {
+            "self": "/api/admin/v1/upstream-oauth-providers/01FSHN9AG0MZAA6S4AF7CTV32E/health"
+          }
+        }
+        "###);
+    }
+}
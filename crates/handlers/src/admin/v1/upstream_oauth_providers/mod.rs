@@ -4,9 +4,11 @@
 // Please see LICENSE files in the repository root for full details.
 
 mod get;
+mod health;
 mod list;
 
 pub use self::{
     get::{doc as get_doc, handler as get},
+    health::{doc as health_doc, handler as health},
     list::{doc as list_doc, handler as list},
 };
@@ -5,7 +5,7 @@
 // Please see LICENSE files in the repository root for full details.
 
 use aide::{NoApi, OperationIo, transform::TransformOperation};
-use axum::{Json, response::IntoResponse};
+use axum::{Json, extract::State, response::IntoResponse};
 use hyper::StatusCode;
 use mas_axum_utils::record_error;
 use mas_data_model::BoxRng;
@@ -16,6 +16,7 @@ use tracing::info;
 use ulid::Ulid;
 
 use crate::{
+    IntrospectionCache,
     admin::{
         call_context::CallContext,
         model::{Resource, User},
@@ -97,6 +98,7 @@ pub async fn handler(
         mut repo, clock, ..
     }: CallContext,
     NoApi(mut rng): NoApi<BoxRng>,
+    NoApi(State(introspection_cache)): NoApi<State<IntrospectionCache>>,
     id: UlidPathParam,
     body: Option<Json<Request>>,
 ) -> Result<Json<SingleResponse<User>>, RouteError> {
@@ -121,6 +123,9 @@ pub async fn handler(
 
     repo.save().await?;
 
+    // Deactivated users can't use their tokens anymore
+    introspection_cache.invalidate_user(user.id).await;
+
     Ok(Json(SingleResponse::new(
         User::from(user),
         format!("/api/admin/v1/users/{id}/deactivate"),
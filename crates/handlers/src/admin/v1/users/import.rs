@@ -0,0 +1,342 @@
+// Copyright 2026 Element Creations Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+// Please see LICENSE files in the repository root for full details.
+
+use std::str::FromStr;
+
+use aide::{NoApi, OperationIo, transform::TransformOperation};
+use axum::{Json, extract::State, response::IntoResponse};
+use hyper::StatusCode;
+use mas_axum_utils::record_error;
+use mas_data_model::BoxRng;
+use mas_storage::{
+    queue::{ImportUsersJob, QueueJobRepositoryExt as _},
+    user::{ImportedPassword, ImportedUpstreamLink, UserImportRecord},
+};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use ulid::Ulid;
+
+use super::username_valid;
+use crate::{
+    admin::{call_context::CallContext, response::ErrorResponse},
+    impl_from_error_for_route,
+    passwords::PasswordManager,
+};
+
+/// The maximum number of users which can be imported in a single request
+const MAX_USERS: usize = 1000;
+
+#[derive(Debug, thiserror::Error, OperationIo)]
+#[aide(output_with = "Json<ErrorResponse>")]
+pub enum RouteError {
+    #[error(transparent)]
+    Internal(Box<dyn std::error::Error + Send + Sync + 'static>),
+
+    #[error("No users to import")]
+    Empty,
+
+    #[error("Too many users to import, at most {MAX_USERS} can be imported at once")]
+    TooManyUsers,
+
+    #[error("Username of user #{0} is not valid")]
+    UsernameNotValid(usize),
+
+    #[error("Email of user #{0} is not valid")]
+    EmailNotValid(usize),
+
+    #[error("Password of user #{0} uses unknown hashing scheme version {1}")]
+    UnknownPasswordScheme(usize, u16),
+}
+
+impl_from_error_for_route!(mas_storage::RepositoryError);
+
+impl IntoResponse for RouteError {
+    fn into_response(self) -> axum::response::Response {
+        let error = ErrorResponse::from_error(&self);
+        let sentry_event_id = record_error!(self, Self::Internal(_));
+        let status = match self {
+            Self::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            Self::Empty
+            | Self::TooManyUsers
+            | Self::UsernameNotValid(_)
+            | Self::EmailNotValid(_)
+            | Self::UnknownPasswordScheme(_, _) => StatusCode::BAD_REQUEST,
+        };
+        (status, sentry_event_id, Json(error)).into_response()
+    }
+}
+
+/// # A password hash to import
+#[derive(Deserialize, JsonSchema)]
+pub struct ImportPassword {
+    /// The version of the password hashing scheme used to produce the hash,
+    /// as configured in the `passwords.schemes` section
+    #[schemars(example = &1)]
+    version: u16,
+
+    /// The password hash
+    #[schemars(example = &"$2b$12$ZXhhbXBsZSBzYWx0IGhlcmUuZXhhbXBsZSBoYXNoIGhlcmU")]
+    hash: String,
+}
+
+/// # A link to an upstream provider to import
+#[derive(Deserialize, JsonSchema)]
+pub struct ImportUpstreamLink {
+    /// The ID of the upstream provider
+    #[schemars(with = "crate::admin::schema::Ulid")]
+    provider_id: Ulid,
+
+    /// The subject of the user on the upstream provider
+    subject: String,
+
+    /// A human-readable name for the upstream account
+    human_account_name: Option<String>,
+}
+
+/// # A user to import
+#[derive(Deserialize, JsonSchema)]
+pub struct ImportUser {
+    /// The username (localpart) of the user
+    #[schemars(example = &"alice")]
+    username: String,
+
+    /// The email addresses of the user
+    #[serde(default)]
+    emails: Vec<String>,
+
+    /// The password hash of the user
+    password: Option<ImportPassword>,
+
+    /// Whether the user can request admin privileges
+    #[serde(default)]
+    admin: bool,
+
+    /// Whether the user should be locked
+    #[serde(default)]
+    locked: bool,
+
+    /// The links to upstream providers
+    #[serde(default)]
+    upstream_links: Vec<ImportUpstreamLink>,
+
+    /// The display name to set on the homeserver
+    display_name: Option<String>,
+}
+
+impl From<ImportUser> for UserImportRecord {
+    fn from(user: ImportUser) -> Self {
+        Self {
+            username: user.username,
+            emails: user.emails,
+            password: user.password.map(|p| ImportedPassword {
+                version: p.version,
+                hash: p.hash,
+            }),
+            admin: user.admin,
+            locked: user.locked,
+            upstream_links: user
+                .upstream_links
+                .into_iter()
+                .map(|l| ImportedUpstreamLink {
+                    provider_id: l.provider_id,
+                    subject: l.subject,
+                    human_account_name: l.human_account_name,
+                })
+                .collect(),
+            display_name: user.display_name,
+        }
+    }
+}
+
+/// # JSON payload for the `POST /api/admin/v1/users/import` endpoint
+#[derive(Deserialize, JsonSchema)]
+#[serde(rename = "ImportUsersRequest")]
+pub struct Request {
+    /// The users to import
+    users: Vec<ImportUser>,
+}
+
+pub fn doc(operation: TransformOperation) -> TransformOperation {
+    operation
+        .id("importUsers")
+        .summary("Import users in bulk")
+        .description(
+            r"Schedule a job to import a batch of users, with their email addresses, password hashes and upstream links.
+The request is validated upfront, but the import itself happens in the background: users which already exist, or which conflict with existing email addresses or upstream links, are skipped.
+This endpoint ignores any policy which would normally prevent the users from being created.",
+        )
+        .tag("user")
+        .response_with::<202, (), _>(|t| t.description("The import was scheduled"))
+        .response_with::<400, RouteError, _>(|t| {
+            let response = ErrorResponse::from_error(&RouteError::UsernameNotValid(0));
+            t.description("One of the users is not valid")
+                .example(response)
+        })
+}
+
+#[tracing::instrument(name = "handler.admin.v1.users.import", skip_all)]
+pub async fn handler(
+    CallContext {
+        mut repo, clock, ..
+    }: CallContext,
+    NoApi(mut rng): NoApi<BoxRng>,
+    State(password_manager): State<PasswordManager>,
+    Json(params): Json<Request>,
+) -> Result<StatusCode, RouteError> {
+    if params.users.is_empty() {
+        return Err(RouteError::Empty);
+    }
+
+    if params.users.len() > MAX_USERS {
+        return Err(RouteError::TooManyUsers);
+    }
+
+    // Validate everything we can before scheduling the job, so that mistakes
+    // are reported to the caller instead of in the worker logs
+    for (index, user) in params.users.iter().enumerate() {
+        if !username_valid(&user.username) {
+            return Err(RouteError::UsernameNotValid(index));
+        }
+
+        if user
+            .emails
+            .iter()
+            .any(|email| lettre::Address::from_str(email).is_err())
+        {
+            return Err(RouteError::EmailNotValid(index));
+        }
+
+        if let Some(password) = &user.password
+            && !password_manager.has_scheme(password.version)
+        {
+            return Err(RouteError::UnknownPasswordScheme(index, password.version));
+        }
+    }
+
+    let users: Vec<UserImportRecord> = params.users.into_iter().map(Into::into).collect();
+    tracing::info!(users.count = users.len(), "Scheduling user import");
+
+    let user_import_id = repo.user_import().add(&mut rng, &clock, users).await?;
+    repo.queue_job()
+        .schedule_job(&mut rng, &clock, ImportUsersJob::new(user_import_id))
+        .await?;
+
+    repo.save().await?;
+
+    Ok(StatusCode::ACCEPTED)
+}
+
+#[cfg(test)]
+mod tests {
+    use hyper::{Request, StatusCode};
+    use mas_matrix::HomeserverConnection;
+    use mas_storage::{
+        RepositoryAccess,
+        user::{UserEmailFilter, UserEmailRepository, UserPasswordRepository, UserRepository},
+    };
+    use sqlx::PgPool;
+
+    use crate::test_utils::{RequestBuilderExt, ResponseExt, TestState, setup};
+
+    #[sqlx::test(migrator = "mas_storage_pg::MIGRATOR")]
+    async fn test_import_users(pool: PgPool) {
+        setup();
+        let mut state = TestState::from_pool(pool).await.unwrap();
+        let token = state.token_with_scope("urn:mas:admin").await;
+
+        // Create a user which already exists, it should be skipped
+        let mut repo = state.repository().await.unwrap();
+        repo.user()
+            .add(&mut state.rng(), &state.clock, "bob".to_owned())
+            .await
+            .unwrap();
+        repo.save().await.unwrap();
+
+        let request = Request::post("/api/admin/v1/users/import")
+            .bearer(&token)
+            .json(serde_json::json!({
+                "users": [
+                    {
+                        "username": "alice",
+                        "emails": ["alice@example.com"],
+                        "password": { "version": 1, "hash": "hash" },
+                        "admin": true,
+                    },
+                    { "username": "bob" },
+                ],
+            }));
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::ACCEPTED);
+
+        state.run_jobs_in_queue().await;
+
+        let mut repo = state.repository().await.unwrap();
+        let alice = repo
+            .user()
+            .find_by_username("alice")
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(alice.can_request_admin);
+
+        let password = repo.user_password().active(&alice).await.unwrap().unwrap();
+        assert_eq!(password.version, 1);
+        assert_eq!(password.hashed_password, "hash");
+
+        let emails = repo
+            .user_email()
+            .count(UserEmailFilter::new().for_user(&alice))
+            .await
+            .unwrap();
+        assert_eq!(emails, 1);
+
+        // Alice should also have been provisioned on the homeserver
+        let result = state.homeserver_connection.query_user("alice").await;
+        assert!(result.is_ok());
+    }
+
+    #[sqlx::test(migrator = "mas_storage_pg::MIGRATOR")]
+    async fn test_import_invalid_users(pool: PgPool) {
+        setup();
+        let mut state = TestState::from_pool(pool).await.unwrap();
+        let token = state.token_with_scope("urn:mas:admin").await;
+
+        let request = Request::post("/api/admin/v1/users/import")
+            .bearer(&token)
+            .json(serde_json::json!({
+                "users": [
+                    { "username": "alice" },
+                    { "username": "Not A Valid Username" },
+                ],
+            }));
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::BAD_REQUEST);
+        let body: serde_json::Value = response.json();
+        assert_eq!(
+            body["errors"][0]["title"],
+            "Username of user #1 is not valid"
+        );
+
+        let request = Request::post("/api/admin/v1/users/import")
+            .bearer(&token)
+            .json(serde_json::json!({
+                "users": [
+                    { "username": "alice", "password": { "version": 42, "hash": "hash" } },
+                ],
+            }));
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::BAD_REQUEST);
+        let body: serde_json::Value = response.json();
+        assert_eq!(
+            body["errors"][0]["title"],
+            "Password of user #0 uses unknown hashing scheme version 42"
+        );
+
+        // Nothing should have been imported
+        state.run_jobs_in_queue().await;
+        let mut repo = state.repository().await.unwrap();
+        assert!(!repo.user().exists("alice").await.unwrap());
+    }
+}
@@ -4,13 +4,14 @@
 // SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
 // Please see LICENSE files in the repository root for full details.
 
-use aide::{OperationIo, transform::TransformOperation};
-use axum::{Json, response::IntoResponse};
+use aide::{NoApi, OperationIo, transform::TransformOperation};
+use axum::{Json, extract::State, response::IntoResponse};
 use hyper::StatusCode;
 use mas_axum_utils::record_error;
 use ulid::Ulid;
 
 use crate::{
+    IntrospectionCache,
     admin::{
         call_context::CallContext,
         model::{Resource, User},
@@ -69,6 +70,7 @@ pub async fn handler(
     CallContext {
         mut repo, clock, ..
     }: CallContext,
+    NoApi(State(introspection_cache)): NoApi<State<IntrospectionCache>>,
     id: UlidPathParam,
 ) -> Result<Json<SingleResponse<User>>, RouteError> {
     let id = *id;
@@ -82,6 +84,9 @@ pub async fn handler(
 
     repo.save().await?;
 
+    // Locked users can't use their tokens anymore
+    introspection_cache.invalidate_user(user.id).await;
+
     Ok(Json(SingleResponse::new(
         User::from(user),
         format!("/api/admin/v1/users/{id}/lock"),
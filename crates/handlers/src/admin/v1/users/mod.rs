@@ -8,22 +8,29 @@ mod add;
 mod by_username;
 mod deactivate;
 mod get;
+mod import;
 mod list;
 mod lock;
 mod reactivate;
+mod rename;
 mod set_admin;
+mod set_display_name;
 mod set_password;
 mod unlock;
 
+pub(crate) use self::add::username_valid;
 pub use self::{
     add::{doc as add_doc, handler as add},
     by_username::{doc as by_username_doc, handler as by_username},
     deactivate::{doc as deactivate_doc, handler as deactivate},
     get::{doc as get_doc, handler as get},
+    import::{doc as import_doc, handler as import},
     list::{doc as list_doc, handler as list},
     lock::{doc as lock_doc, handler as lock},
     reactivate::{doc as reactivate_doc, handler as reactivate},
+    rename::{doc as rename_doc, handler as rename},
     set_admin::{doc as set_admin_doc, handler as set_admin},
+    set_display_name::{doc as set_display_name_doc, handler as set_display_name},
     set_password::{doc as set_password_doc, handler as set_password},
     unlock::{doc as unlock_doc, handler as unlock},
 };
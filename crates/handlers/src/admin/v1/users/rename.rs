@@ -0,0 +1,367 @@
+// Copyright 2026 Element Creations Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+// Please see LICENSE files in the repository root for full details.
+
+use std::sync::Arc;
+
+use aide::{NoApi, OperationIo, transform::TransformOperation};
+use axum::{Json, extract::State, response::IntoResponse};
+use hyper::StatusCode;
+use mas_axum_utils::record_error;
+use mas_data_model::BoxRng;
+use mas_matrix::HomeserverConnection;
+use mas_storage::queue::{ProvisionUserJob, QueueJobRepositoryExt as _};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use tracing::info;
+use ulid::Ulid;
+
+use super::username_valid;
+use crate::{
+    admin::{
+        call_context::CallContext,
+        model::{Resource, User},
+        params::UlidPathParam,
+        response::{ErrorResponse, SingleResponse},
+    },
+    impl_from_error_for_route,
+};
+
+#[derive(Debug, thiserror::Error, OperationIo)]
+#[aide(output_with = "Json<ErrorResponse>")]
+pub enum RouteError {
+    #[error(transparent)]
+    Internal(Box<dyn std::error::Error + Send + Sync + 'static>),
+
+    #[error(transparent)]
+    Homeserver(anyhow::Error),
+
+    #[error("User ID {0} not found")]
+    NotFound(Ulid),
+
+    #[error("User ID {0} is deactivated")]
+    UserDeactivated(Ulid),
+
+    #[error("Username is not valid")]
+    UsernameNotValid,
+
+    #[error("User already exists")]
+    UserAlreadyExists,
+
+    #[error("Username is reserved by the homeserver")]
+    UsernameReserved,
+}
+
+impl_from_error_for_route!(mas_storage::RepositoryError);
+
+impl IntoResponse for RouteError {
+    fn into_response(self) -> axum::response::Response {
+        let error = ErrorResponse::from_error(&self);
+        let sentry_event_id = record_error!(self, Self::Internal(_) | Self::Homeserver(_));
+        let status = match self {
+            Self::Internal(_) | Self::Homeserver(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            Self::NotFound(_) => StatusCode::NOT_FOUND,
+            Self::UsernameNotValid => StatusCode::BAD_REQUEST,
+            Self::UserDeactivated(_) | Self::UserAlreadyExists | Self::UsernameReserved => {
+                StatusCode::CONFLICT
+            }
+        };
+        (status, sentry_event_id, Json(error)).into_response()
+    }
+}
+
+/// # JSON payload for the `POST /api/admin/v1/users/:id/rename` endpoint
+#[derive(Deserialize, JsonSchema)]
+#[serde(rename = "UserRenameRequest")]
+pub struct Request {
+    /// The new username of the user.
+    #[schemars(example = &"alice2")]
+    username: String,
+}
+
+pub fn doc(operation: TransformOperation) -> TransformOperation {
+    operation
+        .id("renameUser")
+        .summary("Change the username of a user")
+        .description(
+            r"This changes the username of the user, and schedules a job to provision the new Matrix ID on the homeserver.
+The new username must be available on the homeserver.
+Existing sessions are kept, and their devices are moved to the new Matrix ID, but nothing is migrated from the old Matrix account on the homeserver: rooms, profile and account data stay on the old Matrix ID, apart from the display name which is copied over.
+The old Matrix ID is deactivated on the homeserver, which keeps the old username reserved.",
+        )
+        .tag("user")
+        .response_with::<200, Json<SingleResponse<User>>, _>(|t| {
+            let [sample, ..] = User::samples();
+            let id = sample.id();
+            let response = SingleResponse::new(sample, format!("/api/admin/v1/users/{id}/rename"));
+            t.description("User was renamed").example(response)
+        })
+        .response_with::<400, RouteError, _>(|t| {
+            let response = ErrorResponse::from_error(&RouteError::UsernameNotValid);
+            t.description("Username is not valid").example(response)
+        })
+        .response_with::<404, RouteError, _>(|t| {
+            let response = ErrorResponse::from_error(&RouteError::NotFound(Ulid::nil()));
+            t.description("User ID not found").example(response)
+        })
+        .response_with::<409, RouteError, _>(|t| {
+            let response = ErrorResponse::from_error(&RouteError::UserAlreadyExists);
+            t.description("User already exists").example(response)
+        })
+        .response_with::<409, RouteError, _>(|t| {
+            let response = ErrorResponse::from_error(&RouteError::UsernameReserved);
+            t.description("Username is reserved by the homeserver")
+                .example(response)
+        })
+}
+
+#[tracing::instrument(name = "handler.admin.v1.users.rename", skip_all)]
+pub async fn handler(
+    CallContext {
+        mut repo, clock, ..
+    }: CallContext,
+    NoApi(mut rng): NoApi<BoxRng>,
+    State(homeserver): State<Arc<dyn HomeserverConnection>>,
+    id: UlidPathParam,
+    Json(params): Json<Request>,
+) -> Result<Json<SingleResponse<User>>, RouteError> {
+    let id = *id;
+    let user = repo
+        .user()
+        .lookup(id)
+        .await?
+        .ok_or(RouteError::NotFound(id))?;
+
+    // Renaming to the same username is a no-op
+    if user.username == params.username {
+        repo.cancel().await?;
+        return Ok(Json(SingleResponse::new(
+            User::from(user),
+            format!("/api/admin/v1/users/{id}/rename"),
+        )));
+    }
+
+    if user.deactivated_at.is_some() {
+        return Err(RouteError::UserDeactivated(id));
+    }
+
+    if !username_valid(&params.username) {
+        return Err(RouteError::UsernameNotValid);
+    }
+
+    if repo.user().exists(&params.username).await? {
+        return Err(RouteError::UserAlreadyExists);
+    }
+
+    let homeserver_available = homeserver
+        .is_localpart_available(&params.username)
+        .await
+        .map_err(RouteError::Homeserver)?;
+
+    if !homeserver_available {
+        return Err(RouteError::UsernameReserved);
+    }
+
+    // The old account may not exist on the homeserver, in which case there is
+    // nothing to carry over or to deactivate
+    let old_profile = homeserver.query_user(&user.username).await.ok();
+
+    // Carry the display name over to the new Matrix ID
+    let display_name = old_profile
+        .as_ref()
+        .and_then(|profile| profile.displayname.clone());
+
+    let old_username = user.username.clone();
+    let user = repo.user().set_username(user, params.username).await?;
+
+    // Deactivate the old Matrix ID before committing the rename, so that its
+    // access tokens stop working. The deactivated account also keeps the old
+    // username reserved on the homeserver, so that it can't be given to someone
+    // else
+    if old_profile.is_some_and(|profile| !profile.deactivated) {
+        homeserver
+            .delete_user(&old_username, false)
+            .await
+            .map_err(RouteError::Homeserver)?;
+    }
+
+    // Provision the new Matrix ID once the rename is committed. This also
+    // syncs the email addresses and devices with the new Matrix ID
+    let mut provision_job = ProvisionUserJob::new(&user);
+    if let Some(display_name) = display_name {
+        provision_job = provision_job.set_display_name(display_name);
+    }
+
+    repo.queue_job()
+        .schedule_job(&mut rng, &clock, provision_job)
+        .await?;
+
+    repo.save().await?;
+
+    info!(%user.id, %old_username, new_username = %user.username, "User renamed by admin");
+
+    Ok(Json(SingleResponse::new(
+        User::from(user),
+        format!("/api/admin/v1/users/{id}/rename"),
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use hyper::{Request, StatusCode};
+    use mas_matrix::{HomeserverConnection, ProvisionRequest};
+    use mas_storage::{RepositoryAccess, user::UserRepository};
+    use sqlx::PgPool;
+
+    use crate::test_utils::{RequestBuilderExt, ResponseExt, TestState, setup};
+
+    #[sqlx::test(migrator = "mas_storage_pg::MIGRATOR")]
+    async fn test_rename_user(pool: PgPool) {
+        setup();
+        let mut state = TestState::from_pool(pool).await.unwrap();
+        let token = state.token_with_scope("urn:mas:admin").await;
+
+        let mut repo = state.repository().await.unwrap();
+        let user = repo
+            .user()
+            .add(&mut state.rng(), &state.clock, "alice".to_owned())
+            .await
+            .unwrap();
+        repo.save().await.unwrap();
+
+        state
+            .homeserver_connection
+            .provision_user(
+                &ProvisionRequest::new(&user.username, &user.sub)
+                    .set_displayname("Alice".to_owned()),
+            )
+            .await
+            .unwrap();
+
+        let request = Request::post(format!("/api/admin/v1/users/{}/rename", user.id))
+            .bearer(&token)
+            .json(serde_json::json!({
+                "username": "alice2",
+            }));
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::OK);
+        let body: serde_json::Value = response.json();
+        assert_eq!(body["data"]["attributes"]["username"], "alice2");
+
+        // The new Matrix ID is provisioned in the background
+        assert!(
+            state
+                .homeserver_connection
+                .query_user("alice2")
+                .await
+                .is_err()
+        );
+        state.run_jobs_in_queue().await;
+
+        // The user was renamed in the database
+        let mut repo = state.repository().await.unwrap();
+        let user = repo.user().lookup(user.id).await.unwrap().unwrap();
+        assert_eq!(user.username, "alice2");
+        assert!(!repo.user().exists("alice").await.unwrap());
+        repo.save().await.unwrap();
+
+        // The new Matrix ID was provisioned with the same display name
+        let profile = state
+            .homeserver_connection
+            .query_user("alice2")
+            .await
+            .unwrap();
+        assert_eq!(profile.displayname.as_deref(), Some("Alice"));
+
+        // The old Matrix ID was deactivated
+        let profile = state
+            .homeserver_connection
+            .query_user("alice")
+            .await
+            .unwrap();
+        assert!(profile.deactivated);
+
+        // The old username can't be given to another user
+        let mut repo = state.repository().await.unwrap();
+        let bob = repo
+            .user()
+            .add(&mut state.rng(), &state.clock, "bob".to_owned())
+            .await
+            .unwrap();
+        repo.save().await.unwrap();
+
+        let request = Request::post(format!("/api/admin/v1/users/{}/rename", bob.id))
+            .bearer(&token)
+            .json(serde_json::json!({
+                "username": "alice",
+            }));
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::CONFLICT);
+        let body: serde_json::Value = response.json();
+        assert_eq!(
+            body["errors"][0]["title"],
+            "Username is reserved by the homeserver"
+        );
+    }
+
+    #[sqlx::test(migrator = "mas_storage_pg::MIGRATOR")]
+    async fn test_rename_conflicts(pool: PgPool) {
+        setup();
+        let mut state = TestState::from_pool(pool).await.unwrap();
+        let token = state.token_with_scope("urn:mas:admin").await;
+
+        let mut repo = state.repository().await.unwrap();
+        let alice = repo
+            .user()
+            .add(&mut state.rng(), &state.clock, "alice".to_owned())
+            .await
+            .unwrap();
+        repo.user()
+            .add(&mut state.rng(), &state.clock, "bob".to_owned())
+            .await
+            .unwrap();
+        repo.save().await.unwrap();
+
+        state.homeserver_connection.reserve_localpart("carol").await;
+
+        // Invalid username
+        let request = Request::post(format!("/api/admin/v1/users/{}/rename", alice.id))
+            .bearer(&token)
+            .json(serde_json::json!({
+                "username": "Not a valid username",
+            }));
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::BAD_REQUEST);
+
+        // Already taken in MAS
+        let request = Request::post(format!("/api/admin/v1/users/{}/rename", alice.id))
+            .bearer(&token)
+            .json(serde_json::json!({
+                "username": "bob",
+            }));
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::CONFLICT);
+        let body: serde_json::Value = response.json();
+        assert_eq!(body["errors"][0]["title"], "User already exists");
+
+        // Reserved on the homeserver
+        let request = Request::post(format!("/api/admin/v1/users/{}/rename", alice.id))
+            .bearer(&token)
+            .json(serde_json::json!({
+                "username": "carol",
+            }));
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::CONFLICT);
+        let body: serde_json::Value = response.json();
+        assert_eq!(
+            body["errors"][0]["title"],
+            "Username is reserved by the homeserver"
+        );
+
+        // Nothing changed
+        let mut repo = state.repository().await.unwrap();
+        let alice = repo.user().lookup(alice.id).await.unwrap().unwrap();
+        assert_eq!(alice.username, "alice");
+    }
+}
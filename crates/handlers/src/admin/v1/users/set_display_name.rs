@@ -0,0 +1,239 @@
+// Copyright 2026 Element Creations Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+// Please see LICENSE files in the repository root for full details.
+
+use std::sync::Arc;
+
+use aide::{OperationIo, transform::TransformOperation};
+use axum::{Json, extract::State, response::IntoResponse};
+use hyper::StatusCode;
+use mas_axum_utils::record_error;
+use mas_matrix::HomeserverConnection;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use tracing::info;
+use ulid::Ulid;
+
+use crate::{
+    admin::{
+        call_context::CallContext,
+        model::{Resource, User},
+        params::UlidPathParam,
+        response::{ErrorResponse, SingleResponse},
+    },
+    impl_from_error_for_route,
+};
+
+#[derive(Debug, thiserror::Error, OperationIo)]
+#[aide(output_with = "Json<ErrorResponse>")]
+pub enum RouteError {
+    #[error(transparent)]
+    Internal(Box<dyn std::error::Error + Send + Sync + 'static>),
+
+    #[error(transparent)]
+    Homeserver(anyhow::Error),
+
+    #[error("User ID {0} not found")]
+    NotFound(Ulid),
+
+    #[error("User ID {0} is deactivated")]
+    UserDeactivated(Ulid),
+
+    #[error("Display name is not valid")]
+    DisplayNameNotValid,
+}
+
+impl_from_error_for_route!(mas_storage::RepositoryError);
+
+impl IntoResponse for RouteError {
+    fn into_response(self) -> axum::response::Response {
+        let error = ErrorResponse::from_error(&self);
+        let sentry_event_id = record_error!(self, Self::Internal(_) | Self::Homeserver(_));
+        let status = match self {
+            Self::Internal(_) | Self::Homeserver(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            Self::NotFound(_) => StatusCode::NOT_FOUND,
+            Self::UserDeactivated(_) => StatusCode::CONFLICT,
+            Self::DisplayNameNotValid => StatusCode::BAD_REQUEST,
+        };
+        (status, sentry_event_id, Json(error)).into_response()
+    }
+}
+
+/// # JSON payload for the `POST /api/admin/v1/users/:id/set-display-name` endpoint
+#[derive(Deserialize, JsonSchema)]
+#[serde(rename = "UserSetDisplayNameRequest")]
+pub struct Request {
+    /// The display name to set on the homeserver. If `null`, the display name
+    /// is removed.
+    #[schemars(example = &"Alice")]
+    display_name: Option<String>,
+}
+
+pub fn doc(operation: TransformOperation) -> TransformOperation {
+    operation
+        .id("userSetDisplayName")
+        .summary("Set the display name of a user")
+        .description("The display name is stored on the homeserver, which is updated immediately.")
+        .tag("user")
+        .response_with::<200, Json<SingleResponse<User>>, _>(|t| {
+            let [sample, ..] = User::samples();
+            let id = sample.id();
+            let response =
+                SingleResponse::new(sample, format!("/api/admin/v1/users/{id}/set-display-name"));
+            t.description("The display name was set").example(response)
+        })
+        .response_with::<400, RouteError, _>(|t| {
+            let response = ErrorResponse::from_error(&RouteError::DisplayNameNotValid);
+            t.description("Display name is not valid").example(response)
+        })
+        .response_with::<404, RouteError, _>(|t| {
+            let response = ErrorResponse::from_error(&RouteError::NotFound(Ulid::nil()));
+            t.description("User ID not found").example(response)
+        })
+        .response_with::<409, RouteError, _>(|t| {
+            let response = ErrorResponse::from_error(&RouteError::UserDeactivated(Ulid::nil()));
+            t.description("User is deactivated").example(response)
+        })
+}
+
+#[tracing::instrument(name = "handler.admin.v1.users.set_display_name", skip_all)]
+pub async fn handler(
+    CallContext { mut repo, .. }: CallContext,
+    State(homeserver): State<Arc<dyn HomeserverConnection>>,
+    id: UlidPathParam,
+    Json(params): Json<Request>,
+) -> Result<Json<SingleResponse<User>>, RouteError> {
+    let id = *id;
+    let user = repo
+        .user()
+        .lookup(id)
+        .await?
+        .ok_or(RouteError::NotFound(id))?;
+
+    // Nothing is written to the database, so release the transaction before
+    // calling the homeserver, instead of holding it during the request
+    repo.cancel().await?;
+
+    if user.deactivated_at.is_some() {
+        return Err(RouteError::UserDeactivated(id));
+    }
+
+    if let Some(display_name) = &params.display_name {
+        // Same validation as the GraphQL API
+        if display_name.is_empty() || display_name.len() > 256 {
+            return Err(RouteError::DisplayNameNotValid);
+        }
+
+        homeserver
+            .set_displayname(&user.username, display_name)
+            .await
+            .map_err(RouteError::Homeserver)?;
+
+        info!(
+            %user.id,
+            %user.username,
+            display_name = display_name.as_str(),
+            "Display name set by admin"
+        );
+    } else {
+        homeserver
+            .unset_displayname(&user.username)
+            .await
+            .map_err(RouteError::Homeserver)?;
+
+        info!(%user.id, %user.username, "Display name removed by admin");
+    }
+
+    Ok(Json(SingleResponse::new(
+        User::from(user),
+        format!("/api/admin/v1/users/{id}/set-display-name"),
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use hyper::{Request, StatusCode};
+    use mas_matrix::{HomeserverConnection, ProvisionRequest};
+    use mas_storage::{RepositoryAccess, user::UserRepository};
+    use sqlx::PgPool;
+
+    use crate::test_utils::{RequestBuilderExt, ResponseExt, TestState, setup};
+
+    #[sqlx::test(migrator = "mas_storage_pg::MIGRATOR")]
+    async fn test_set_display_name(pool: PgPool) {
+        setup();
+        let mut state = TestState::from_pool(pool).await.unwrap();
+        let token = state.token_with_scope("urn:mas:admin").await;
+
+        let mut repo = state.repository().await.unwrap();
+        let user = repo
+            .user()
+            .add(&mut state.rng(), &state.clock, "alice".to_owned())
+            .await
+            .unwrap();
+        repo.save().await.unwrap();
+
+        state
+            .homeserver_connection
+            .provision_user(&ProvisionRequest::new(&user.username, &user.sub))
+            .await
+            .unwrap();
+
+        let request = Request::post(format!("/api/admin/v1/users/{}/set-display-name", user.id))
+            .bearer(&token)
+            .json(serde_json::json!({
+                "display_name": "Alice",
+            }));
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::OK);
+
+        let profile = state
+            .homeserver_connection
+            .query_user("alice")
+            .await
+            .unwrap();
+        assert_eq!(profile.displayname.as_deref(), Some("Alice"));
+
+        // Remove the display name
+        let request = Request::post(format!("/api/admin/v1/users/{}/set-display-name", user.id))
+            .bearer(&token)
+            .json(serde_json::json!({
+                "display_name": null,
+            }));
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::OK);
+
+        let profile = state
+            .homeserver_connection
+            .query_user("alice")
+            .await
+            .unwrap();
+        assert_eq!(profile.displayname, None);
+
+        // An empty display name is not valid
+        let request = Request::post(format!("/api/admin/v1/users/{}/set-display-name", user.id))
+            .bearer(&token)
+            .json(serde_json::json!({
+                "display_name": "",
+            }));
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::BAD_REQUEST);
+    }
+
+    #[sqlx::test(migrator = "mas_storage_pg::MIGRATOR")]
+    async fn test_unknown_user(pool: PgPool) {
+        setup();
+        let mut state = TestState::from_pool(pool).await.unwrap();
+        let token = state.token_with_scope("urn:mas:admin").await;
+
+        let request =
+            Request::post("/api/admin/v1/users/01040G2081040G2081040G2081/set-display-name")
+                .bearer(&token)
+                .json(serde_json::json!({
+                    "display_name": "Alice",
+                }));
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::NOT_FOUND);
+    }
+}
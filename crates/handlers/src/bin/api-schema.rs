@@ -57,6 +57,11 @@ impl_from_ref!(mas_router::UrlBuilder);
 impl_from_ref!(mas_templates::Templates);
 impl_from_ref!(Arc<dyn mas_matrix::HomeserverConnection>);
 impl_from_ref!(mas_keystore::Keystore);
+impl_from_ref!(mas_keystore::Encrypter);
+impl_from_ref!(reqwest::Client);
+impl_from_ref!(mas_handlers::MetadataCache);
+impl_from_ref!(mas_handlers::ClientCertificateCache);
+impl_from_ref!(mas_handlers::IntrospectionCache);
 impl_from_ref!(mas_handlers::passwords::PasswordManager);
 impl_from_ref!(Arc<mas_policy::PolicyFactory>);
 impl_from_ref!(mas_data_model::SiteConfig);
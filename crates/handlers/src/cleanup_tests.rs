@@ -200,6 +200,11 @@ async fn create_session_hierarchy(
             forward_login_hint: false,
             ui_order: 0,
             on_backchannel_logout: UpstreamOAuthProviderOnBackchannelLogout::LogoutAll,
+            saml: None,
+            email_domains: Vec::new(),
+            store_tokens: false,
+            allow_initiated_login: false,
+            encrypted_client_keys: None,
         };
 
         let provider = repo
@@ -0,0 +1,254 @@
+// Copyright 2026 Element Creations Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+// Please see LICENSE files in the repository root for full details.
+
+//! Heuristics to detect compatibility access tokens being used from a
+//! different network or client than the one their session was last active
+//! from
+
+use std::net::IpAddr;
+
+use hyper::HeaderMap;
+use mas_data_model::{
+    CompatSession, CompatSessionAnomalyKind, CompatTokenAnomalyDetectionConfig, UserAgent,
+};
+
+/// The header in which the homeserver gives the IP address of the client using
+/// the token it introspects
+const CLIENT_IP_HEADER: &str = "X-MAS-Client-IP";
+
+/// The header in which the homeserver gives the user agent of the client using
+/// the token it introspects
+const CLIENT_USER_AGENT_HEADER: &str = "X-MAS-Client-User-Agent";
+
+/// The client using a token, as reported by the homeserver introspecting it
+#[derive(Debug, Clone, Default)]
+pub(crate) struct TokenRequester {
+    pub ip_address: Option<IpAddr>,
+    pub user_agent: Option<String>,
+}
+
+impl TokenRequester {
+    pub(crate) fn from_headers(headers: &HeaderMap) -> Self {
+        let ip_address = headers
+            .get(CLIENT_IP_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.trim().parse().ok());
+
+        let user_agent = headers
+            .get(CLIENT_USER_AGENT_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .map(ToOwned::to_owned);
+
+        Self {
+            ip_address,
+            user_agent,
+        }
+    }
+}
+
+/// Whether both addresses share the prefix configured for their family
+fn same_network(config: CompatTokenAnomalyDetectionConfig, a: IpAddr, b: IpAddr) -> bool {
+    match (a.to_canonical(), b.to_canonical()) {
+        (IpAddr::V4(a), IpAddr::V4(b)) => {
+            let length = u32::from(config.ipv4_prefix_length.min(32));
+            let mask = u32::MAX.checked_shl(32 - length).unwrap_or(0);
+            a.to_bits() & mask == b.to_bits() & mask
+        }
+        (IpAddr::V6(a), IpAddr::V6(b)) => {
+            let length = u32::from(config.ipv6_prefix_length.min(128));
+            let mask = u128::MAX.checked_shl(128 - length).unwrap_or(0);
+            a.to_bits() & mask == b.to_bits() & mask
+        }
+        _ => false,
+    }
+}
+
+/// Whether both user agents belong to the same client software, operating
+/// system and kind of device. Versions are ignored, as clients get updated.
+fn same_client(a: &str, b: &str) -> bool {
+    let a = UserAgent::parse(a.to_owned());
+    let b = UserAgent::parse(b.to_owned());
+
+    // Nothing useful could be parsed, so the best we can do is to compare them
+    // as they are
+    if a.name.is_none() && b.name.is_none() {
+        return a.raw == b.raw;
+    }
+
+    a.name == b.name && a.os == b.os && a.device_type == b.device_type
+}
+
+/// Find what is unusual about a token of the given session being used by the
+/// given client. Nothing is reported for what wasn't recorded on the session
+/// or given by the homeserver.
+pub(crate) fn detect(
+    config: CompatTokenAnomalyDetectionConfig,
+    session: &CompatSession,
+    requester: &TokenRequester,
+) -> Vec<CompatSessionAnomalyKind> {
+    let mut anomalies = Vec::new();
+
+    if let (Some(recorded), Some(used)) = (session.last_active_ip, requester.ip_address)
+        && !same_network(config, recorded, used)
+    {
+        anomalies.push(CompatSessionAnomalyKind::IpRange);
+    }
+
+    if let (Some(recorded), Some(used)) = (&session.user_agent, &requester.user_agent)
+        && !same_client(recorded, used)
+    {
+        anomalies.push(CompatSessionAnomalyKind::UserAgent);
+    }
+
+    anomalies
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{Ipv4Addr, Ipv6Addr};
+
+    use chrono::DateTime;
+    use mas_data_model::CompatSessionState;
+    use ulid::Ulid;
+
+    use super::*;
+
+    const ELEMENT_ANDROID: &str =
+        "Element/1.6.0 (Google Pixel 7; Android 14; UP1A.231005.007; Flavour GooglePlay)";
+    const ELEMENT_ANDROID_UPDATED: &str =
+        "Element/1.6.6 (Google Pixel 7; Android 14; UP1A.231105.003; Flavour GooglePlay)";
+    const FIREFOX_LINUX: &str =
+        "Mozilla/5.0 (X11; Linux x86_64; rv:120.0) Gecko/20100101 Firefox/120.0";
+
+    fn config() -> CompatTokenAnomalyDetectionConfig {
+        CompatTokenAnomalyDetectionConfig {
+            ipv4_prefix_length: 16,
+            ipv6_prefix_length: 48,
+        }
+    }
+
+    fn session(ip: Option<IpAddr>, user_agent: Option<&str>) -> CompatSession {
+        CompatSession {
+            id: Ulid::nil(),
+            state: CompatSessionState::Valid,
+            user_id: Ulid::nil(),
+            device: None,
+            human_name: None,
+            user_session_id: None,
+            created_at: DateTime::default(),
+            is_synapse_admin: false,
+            user_agent: user_agent.map(ToOwned::to_owned),
+            last_active_at: None,
+            last_active_ip: ip,
+        }
+    }
+
+    fn requester(ip: Option<IpAddr>, user_agent: Option<&str>) -> TokenRequester {
+        TokenRequester {
+            ip_address: ip,
+            user_agent: user_agent.map(ToOwned::to_owned),
+        }
+    }
+
+    #[test]
+    fn test_ip_range() {
+        let config = config();
+        let session = session(Some(Ipv4Addr::new(192, 0, 2, 1).into()), None);
+
+        // Same /16
+        let same = requester(Some(Ipv4Addr::new(192, 0, 200, 7).into()), None);
+        assert!(detect(config, &session, &same).is_empty());
+
+        // IPv4-mapped IPv6 addresses are treated as IPv4 addresses
+        let mapped = requester(
+            Some(Ipv4Addr::new(192, 0, 3, 4).to_ipv6_mapped().into()),
+            None,
+        );
+        assert!(detect(config, &session, &mapped).is_empty());
+
+        let other = requester(Some(Ipv4Addr::new(198, 51, 100, 1).into()), None);
+        assert_eq!(
+            detect(config, &session, &other),
+            vec![CompatSessionAnomalyKind::IpRange]
+        );
+
+        let v6 = requester(Some(Ipv6Addr::LOCALHOST.into()), None);
+        assert_eq!(
+            detect(config, &session, &v6),
+            vec![CompatSessionAnomalyKind::IpRange]
+        );
+
+        // Nothing to compare to
+        let unknown = requester(None, None);
+        assert!(detect(config, &session, &unknown).is_empty());
+
+        // A zero-length prefix matches everything in the same family
+        let permissive = CompatTokenAnomalyDetectionConfig {
+            ipv4_prefix_length: 0,
+            ..config
+        };
+        assert!(detect(permissive, &session, &other).is_empty());
+    }
+
+    #[test]
+    fn test_ipv6_range() {
+        let config = config();
+        let recorded: Ipv6Addr = "2001:db8:1234:1::1".parse().unwrap();
+        let session = session(Some(recorded.into()), None);
+
+        let same: Ipv6Addr = "2001:db8:1234:ffff::2".parse().unwrap();
+        assert!(detect(config, &session, &requester(Some(same.into()), None)).is_empty());
+
+        let other: Ipv6Addr = "2001:db8:4321::1".parse().unwrap();
+        assert_eq!(
+            detect(config, &session, &requester(Some(other.into()), None)),
+            vec![CompatSessionAnomalyKind::IpRange]
+        );
+    }
+
+    #[test]
+    fn test_user_agent() {
+        let config = config();
+        let session = session(None, Some(ELEMENT_ANDROID));
+
+        // Client updates are not anomalies
+        let updated = requester(None, Some(ELEMENT_ANDROID_UPDATED));
+        assert!(detect(config, &session, &updated).is_empty());
+
+        let other = requester(None, Some(FIREFOX_LINUX));
+        assert_eq!(
+            detect(config, &session, &other),
+            vec![CompatSessionAnomalyKind::UserAgent]
+        );
+
+        // Unparseable user agents are compared as they are
+        let session = self::session(None, Some("curl"));
+        assert!(detect(config, &session, &requester(None, Some("curl"))).is_empty());
+        assert_eq!(
+            detect(config, &session, &requester(None, Some("wget"))),
+            vec![CompatSessionAnomalyKind::UserAgent]
+        );
+    }
+
+    #[test]
+    fn test_from_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert(CLIENT_IP_HEADER, "198.51.100.1".parse().unwrap());
+        headers.insert(CLIENT_USER_AGENT_HEADER, "curl/8.0".parse().unwrap());
+
+        let requester = TokenRequester::from_headers(&headers);
+        assert_eq!(
+            requester.ip_address,
+            Some(Ipv4Addr::new(198, 51, 100, 1).into())
+        );
+        assert_eq!(requester.user_agent.as_deref(), Some("curl/8.0"));
+
+        let mut headers = HeaderMap::new();
+        headers.insert(CLIENT_IP_HEADER, "not an ip".parse().unwrap());
+        let requester = TokenRequester::from_headers(&headers);
+        assert!(requester.ip_address.is_none());
+        assert!(requester.user_agent.is_none());
+    }
+}
@@ -15,6 +15,7 @@ use mas_data_model::{
     BoxClock, BoxRng, Clock, CompatSession, CompatSsoLoginState, Device, SiteConfig, TokenType,
     User,
 };
+use mas_ldap::Directory;
 use mas_matrix::HomeserverConnection;
 use mas_policy::{Policy, Requester, ViolationCode, model::CompatLogin};
 use mas_storage::{
@@ -36,8 +37,9 @@ use zeroize::Zeroizing;
 use super::{MatrixError, MatrixJsonBody};
 use crate::{
     BoundActivityTracker, Limiter, METER, RequesterFingerprint, impl_from_error_for_route,
+    ldap::DirectoryLoginError,
     passwords::{PasswordManager, PasswordVerificationResult},
-    rate_limit::PasswordCheckLimitedError,
+    rate_limit::{DirectoryPasswordCheckLimitedError, PasswordCheckLimitedError},
     session::count_user_sessions_for_limiting,
 };
 
@@ -86,8 +88,11 @@ struct LoginTypes {
 }
 
 #[tracing::instrument(name = "handlers.compat.login.get", skip_all)]
-pub(crate) async fn get(State(password_manager): State<PasswordManager>) -> impl IntoResponse {
-    let flows = if password_manager.is_enabled() {
+pub(crate) async fn get(
+    State(password_manager): State<PasswordManager>,
+    State(directory): State<Option<Arc<dyn Directory>>>,
+) -> impl IntoResponse {
+    let flows = if password_manager.is_enabled() || directory.is_some() {
         vec![
             LoginType::Password,
             LoginType::Sso {
@@ -209,6 +214,15 @@ pub enum RouteError {
     #[error("request rate limited")]
     RateLimited(#[from] PasswordCheckLimitedError),
 
+    #[error("request rate limited")]
+    DirectoryRateLimited(#[from] DirectoryPasswordCheckLimitedError),
+
+    #[error("failed to check the password against the directory")]
+    Directory(#[source] DirectoryLoginError),
+
+    #[error("user can't be provisioned from the directory: {0}")]
+    ProvisioningDenied(String),
+
     #[error("login took too long")]
     LoginTookTooLong,
 
@@ -239,16 +253,20 @@ impl From<anyhow::Error> for RouteError {
 
 impl IntoResponse for RouteError {
     fn into_response(self) -> axum::response::Response {
-        let sentry_event_id =
-            record_error!(self, Self::Internal(_) | Self::ProvisionDeviceFailed(_));
+        let sentry_event_id = record_error!(
+            self,
+            Self::Internal(_) | Self::ProvisionDeviceFailed(_) | Self::Directory(_)
+        );
         LOGIN_COUNTER.add(1, &[KeyValue::new(RESULT, "error")]);
         let response = match self {
-            Self::Internal(_) | Self::ProvisionDeviceFailed(_) => MatrixError {
-                errcode: "M_UNKNOWN",
-                error: "Internal server error",
-                status: StatusCode::INTERNAL_SERVER_ERROR,
-            },
-            Self::RateLimited(_) => MatrixError {
+            Self::Internal(_) | Self::ProvisionDeviceFailed(_) | Self::Directory(_) => {
+                MatrixError {
+                    errcode: "M_UNKNOWN",
+                    error: "Internal server error",
+                    status: StatusCode::INTERNAL_SERVER_ERROR,
+                }
+            }
+            Self::RateLimited(_) | Self::DirectoryRateLimited(_) => MatrixError {
                 errcode: "M_LIMIT_EXCEEDED",
                 error: "Too many login attempts",
                 status: StatusCode::TOO_MANY_REQUESTS,
@@ -288,7 +306,7 @@ impl IntoResponse for RouteError {
                 error: "User account has been locked",
                 status: StatusCode::UNAUTHORIZED,
             },
-            Self::PolicyRejected => MatrixError {
+            Self::ProvisioningDenied(_) | Self::PolicyRejected => MatrixError {
                 errcode: "M_FORBIDDEN",
                 error: "Login denied by the policy enforced by this service",
                 status: StatusCode::FORBIDDEN,
@@ -309,6 +327,7 @@ pub(crate) async fn post(
     mut rng: BoxRng,
     clock: BoxClock,
     State(password_manager): State<PasswordManager>,
+    State(directory): State<Option<Arc<dyn Directory>>>,
     State(repository_factory): State<BoxRepositoryFactory>,
     activity_tracker: BoundActivityTracker,
     State(homeserver): State<Arc<dyn HomeserverConnection>>,
@@ -322,7 +341,8 @@ pub(crate) async fn post(
     let user_agent = user_agent.map(|ua| ua.as_str().to_owned());
     let login_type = input.credentials.login_type();
     let mut repo = repository_factory.create().await?;
-    let (mut session, user) = match (password_manager.is_enabled(), input.credentials) {
+    let password_login_enabled = password_manager.is_enabled() || directory.is_some();
+    let (mut session, user) = match (password_login_enabled, input.credentials) {
         (
             true,
             Credentials::Password {
@@ -351,6 +371,9 @@ pub(crate) async fn post(
                 &mut rng,
                 &clock,
                 &password_manager,
+                directory.as_deref(),
+                site_config.ldap_adopt_existing_users,
+                &*homeserver,
                 &limiter,
                 requester,
                 &mut repo,
@@ -587,6 +610,10 @@ async fn token_login(
         .await?;
 
     let session_counts = count_user_sessions_for_limiting(repo, &browser_session.user).await?;
+    let user_attributes = repo
+        .upstream_oauth_link()
+        .attributes_for_user(&browser_session.user)
+        .await?;
 
     let res = policy
         .evaluate_compat_login(mas_policy::CompatLoginInput {
@@ -594,6 +621,7 @@ async fn token_login(
             login: CompatLogin::Token,
             session_replaced,
             session_counts,
+            user_attributes,
             requester,
         })
         .await?;
@@ -640,6 +668,9 @@ async fn user_password_login(
     mut rng: &mut (impl RngCore + CryptoRng + Send),
     clock: &impl Clock,
     password_manager: &PasswordManager,
+    directory: Option<&dyn Directory>,
+    adopt_existing_ldap_users: bool,
+    homeserver: &dyn HomeserverConnection,
     limiter: &Limiter,
     requester: RequesterFingerprint,
     repo: &mut BoxRepository,
@@ -650,58 +681,104 @@ async fn user_password_login(
     requested_device_id: Option<String>,
     initial_device_display_name: Option<String>,
 ) -> Result<(CompatSession, User), RouteError> {
-    // Find the user
-    let user = repo
-        .user()
-        .find_by_username(username)
-        .await?
-        .filter(|user| user.deactivated_at.is_none())
-        .ok_or(RouteError::UserNotFound)?;
+    let password = Zeroizing::new(password);
 
-    if user.locked_at.is_some() {
-        return Err(RouteError::UserLocked);
-    }
+    let user = if let Some(directory) = directory {
+        // The password is checked against the directory, which may provision the
+        // user on their first login
+        let user = match crate::ldap::authenticate(
+            &mut rng,
+            clock,
+            repo,
+            directory,
+            adopt_existing_ldap_users,
+            homeserver,
+            policy,
+            limiter,
+            requester,
+            Requester {
+                ip_address: policy_requester.ip_address,
+                user_agent: policy_requester.user_agent.clone(),
+            },
+            username,
+            &password,
+        )
+        .await
+        {
+            Ok(Some((user, _entry))) => user,
+            Ok(None) => return Err(RouteError::PasswordMismatch),
+            Err(DirectoryLoginError::RateLimited(e)) => {
+                return Err(RouteError::DirectoryRateLimited(e));
+            }
+            Err(DirectoryLoginError::ProvisioningDenied(message)) => {
+                return Err(RouteError::ProvisioningDenied(message));
+            }
+            Err(e) => return Err(RouteError::Directory(e)),
+        };
 
-    // Check the rate limit
-    limiter.check_password(requester, &user)?;
+        if user.deactivated_at.is_some() {
+            return Err(RouteError::UserNotFound);
+        }
 
-    // Lookup its password
-    let user_password = repo
-        .user_password()
-        .active(&user)
-        .await?
-        .ok_or(RouteError::NoPassword)?;
+        if user.locked_at.is_some() {
+            return Err(RouteError::UserLocked);
+        }
 
-    // Verify the password
-    let password = Zeroizing::new(password);
+        user
+    } else {
+        // Find the user
+        let user = repo
+            .user()
+            .find_by_username(username)
+            .await?
+            .filter(|user| user.deactivated_at.is_none())
+            .ok_or(RouteError::UserNotFound)?;
 
-    match password_manager
-        .verify_and_upgrade(
-            &mut rng,
-            user_password.version,
-            password,
-            user_password.hashed_password.clone(),
-        )
-        .await?
-    {
-        PasswordVerificationResult::Success(Some((version, hashed_password))) => {
-            // Save the upgraded password if needed
-            repo.user_password()
-                .add(
-                    &mut rng,
-                    clock,
-                    &user,
-                    version,
-                    hashed_password,
-                    Some(&user_password),
-                )
-                .await?;
+        if user.locked_at.is_some() {
+            return Err(RouteError::UserLocked);
         }
-        PasswordVerificationResult::Success(None) => {}
-        PasswordVerificationResult::Failure => {
-            return Err(RouteError::PasswordMismatch);
+
+        // Check the rate limit
+        limiter.check_password(requester, &user)?;
+
+        // Lookup its password
+        let user_password = repo
+            .user_password()
+            .active(&user)
+            .await?
+            .ok_or(RouteError::NoPassword)?;
+
+        // Verify the password
+        match password_manager
+            .verify_and_upgrade(
+                &mut rng,
+                user_password.version,
+                password,
+                user_password.hashed_password.clone(),
+            )
+            .await?
+        {
+            PasswordVerificationResult::Success(Some((version, hashed_password))) => {
+                // Save the upgraded password if needed
+                repo.user_password()
+                    .add(
+                        &mut rng,
+                        clock,
+                        &user,
+                        version,
+                        hashed_password,
+                        Some(&user_password),
+                    )
+                    .await?;
+            }
+            PasswordVerificationResult::Success(None) => {}
+            PasswordVerificationResult::Failure => {
+                return Err(RouteError::PasswordMismatch);
+            }
         }
-    }
+
+        user
+    };
 
     // We're about to create a device, let's explicitly acquire a lock, so that
     // any concurrent sync will read after we've committed
@@ -720,6 +797,10 @@ async fn user_password_login(
         .await?;
 
     let session_counts = count_user_sessions_for_limiting(repo, &user).await?;
+    let user_attributes = repo
+        .upstream_oauth_link()
+        .attributes_for_user(&user)
+        .await?;
 
     let res = policy
         .evaluate_compat_login(mas_policy::CompatLoginInput {
@@ -727,6 +808,7 @@ async fn user_password_login(
             login: CompatLogin::Password,
             session_replaced,
             session_counts,
+            user_attributes,
             requester: policy_requester,
         })
         .await?;
@@ -1274,6 +1356,191 @@ mod tests {
         "###);
     }
 
+    /// Test that passwords are checked against the directory when one is
+    /// configured, and that users are provisioned on their first login.
+    #[sqlx::test(migrator = "mas_storage_pg::MIGRATOR")]
+    async fn test_ldap_password_login(pool: PgPool) {
+        setup();
+        let mut state = TestState::from_pool(pool).await.unwrap();
+        let directory = mas_ldap::MockDirectory::new();
+        directory
+            .add_user(
+                "alice",
+                "password",
+                mas_ldap::DirectoryUser {
+                    dn: "uid=alice,ou=people,dc=example,dc=com".to_owned(),
+                    localpart: "alice".to_owned(),
+                    display_name: None,
+                    email: None,
+                },
+            )
+            .await;
+        state.password_manager = PasswordManager::disabled();
+        state.directory = Some(Arc::new(directory));
+
+        // Password login is still advertised
+        let request = Request::get("/_matrix/client/v3/login").empty();
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::OK);
+        let body: serde_json::Value = response.json();
+        assert_eq!(body["flows"][0]["type"], "m.login.password");
+
+        // A wrong password is rejected
+        let request = Request::post("/_matrix/client/v3/login").json(serde_json::json!({
+            "type": "m.login.password",
+            "identifier": {
+                "type": "m.id.user",
+                "user": "alice",
+            },
+            "password": "wrong",
+        }));
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::FORBIDDEN);
+
+        let mut repo = state.repository().await.unwrap();
+        assert!(
+            repo.user()
+                .find_by_username("alice")
+                .await
+                .unwrap()
+                .is_none()
+        );
+        repo.save().await.unwrap();
+
+        // The right password provisions the user and logs them in
+        let request = Request::post("/_matrix/client/v3/login").json(serde_json::json!({
+            "type": "m.login.password",
+            "identifier": {
+                "type": "m.id.user",
+                "user": "@alice:example.com",
+            },
+            "password": "password",
+        }));
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::OK);
+        let body: serde_json::Value = response.json();
+        assert_eq!(body["user_id"], "@alice:example.com");
+
+        let mut repo = state.repository().await.unwrap();
+        assert!(
+            repo.user()
+                .find_by_username("alice")
+                .await
+                .unwrap()
+                .is_some()
+        );
+        repo.save().await.unwrap();
+    }
+
+    /// Test that a directory login doesn't sign into an existing user which
+    /// wasn't provisioned from the directory, unless explicitly allowed
+    #[sqlx::test(migrator = "mas_storage_pg::MIGRATOR")]
+    async fn test_ldap_password_login_existing_user(pool: PgPool) {
+        setup();
+        let directory = Arc::new(mas_ldap::MockDirectory::new());
+        directory
+            .add_user(
+                "alice",
+                "password",
+                mas_ldap::DirectoryUser {
+                    dn: "uid=alice,ou=people,dc=example,dc=com".to_owned(),
+                    localpart: "alice".to_owned(),
+                    display_name: None,
+                    email: None,
+                },
+            )
+            .await;
+        directory
+            .add_user(
+                "bob",
+                "password",
+                mas_ldap::DirectoryUser {
+                    dn: "uid=bob,ou=people,dc=example,dc=com".to_owned(),
+                    localpart: "bob".to_owned(),
+                    display_name: None,
+                    email: Some("alice@example.com".to_owned()),
+                },
+            )
+            .await;
+
+        let mut state = TestState::from_pool(pool.clone()).await.unwrap();
+        state.password_manager = PasswordManager::disabled();
+        state.directory = Some(directory.clone());
+
+        // Alice already has a local account, with an email address
+        let mut repo = state.repository().await.unwrap();
+        let alice = repo
+            .user()
+            .add(&mut state.rng(), &state.clock, "alice".to_owned())
+            .await
+            .unwrap();
+        repo.user_email()
+            .add(
+                &mut state.rng(),
+                &state.clock,
+                &alice,
+                "alice@example.com".to_owned(),
+            )
+            .await
+            .unwrap();
+        repo.save().await.unwrap();
+
+        let login = |user: &str| {
+            Request::post("/_matrix/client/v3/login").json(serde_json::json!({
+                "type": "m.login.password",
+                "identifier": {
+                    "type": "m.id.user",
+                    "user": user,
+                },
+                "password": "password",
+            }))
+        };
+
+        // The directory entry can't take over the local account
+        let response = state.request(login("alice")).await;
+        response.assert_status(StatusCode::FORBIDDEN);
+
+        // Bob is provisioned, but without the email address used by Alice
+        let response = state.request(login("bob")).await;
+        response.assert_status(StatusCode::OK);
+
+        let mut repo = state.repository().await.unwrap();
+        let bob = repo
+            .user()
+            .find_by_ldap_dn("uid=bob,ou=people,dc=example,dc=com")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(bob.username, "bob");
+        assert!(repo.user_email().all(&bob).await.unwrap().is_empty());
+        repo.save().await.unwrap();
+
+        // Once adopting existing users is allowed, Alice gets linked to the entry
+        let mut state = TestState::from_pool_with_site_config(
+            pool,
+            SiteConfig {
+                ldap_adopt_existing_users: true,
+                ..test_site_config()
+            },
+        )
+        .await
+        .unwrap();
+        state.password_manager = PasswordManager::disabled();
+        state.directory = Some(directory);
+
+        let response = state.request(login("alice")).await;
+        response.assert_status(StatusCode::OK);
+        let body: serde_json::Value = response.json();
+        assert_eq!(body["user_id"], "@alice:example.com");
+
+        let mut repo = state.repository().await.unwrap();
+        assert_eq!(
+            repo.user().ldap_dn(&alice).await.unwrap().as_deref(),
+            Some("uid=alice,ou=people,dc=example,dc=com")
+        );
+        repo.save().await.unwrap();
+    }
+
     /// Test the response of an unsupported password identifier.
     #[sqlx::test(migrator = "mas_storage_pg::MIGRATOR")]
     async fn test_unsupported_login_identifier(pool: PgPool) {
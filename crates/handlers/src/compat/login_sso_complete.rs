@@ -110,6 +110,10 @@ pub async fn get(
     }
 
     let session_counts = count_user_sessions_for_limiting(&mut repo, &session.user).await?;
+    let user_attributes = repo
+        .upstream_oauth_link()
+        .attributes_for_user(&session.user)
+        .await?;
 
     // We can close the repository early, we don't need it at this point
     repo.save().await?;
@@ -124,6 +128,7 @@ pub async fn get(
             // which happens too late.
             session_replaced: false,
             session_counts,
+            user_attributes,
             requester: mas_policy::Requester {
                 ip_address: activity_tracker.ip(),
                 user_agent,
@@ -261,6 +266,10 @@ pub async fn post(
     };
 
     let session_counts = count_user_sessions_for_limiting(&mut repo, &session.user).await?;
+    let user_attributes = repo
+        .upstream_oauth_link()
+        .attributes_for_user(&session.user)
+        .await?;
 
     let res = policy
         .evaluate_compat_login(mas_policy::CompatLoginInput {
@@ -269,6 +278,7 @@ pub async fn post(
                 redirect_uri: login.redirect_uri.to_string(),
             },
             session_counts,
+            user_attributes,
             // We don't know if there's going to be a replacement until we received the device ID,
             // which happens too late.
             session_replaced: false,
@@ -6,7 +6,7 @@
 
 use std::sync::LazyLock;
 
-use axum::{Json, response::IntoResponse};
+use axum::{Json, extract::State, response::IntoResponse};
 use axum_extra::typed_header::TypedHeader;
 use headers::{Authorization, authorization::Bearer};
 use hyper::StatusCode;
@@ -21,7 +21,7 @@ use opentelemetry::{Key, KeyValue, metrics::Counter};
 use thiserror::Error;
 
 use super::MatrixError;
-use crate::{BoundActivityTracker, METER, impl_from_error_for_route};
+use crate::{BoundActivityTracker, IntrospectionCache, METER, impl_from_error_for_route};
 
 static LOGOUT_COUNTER: LazyLock<Counter<u64>> = LazyLock::new(|| {
     METER
@@ -81,6 +81,7 @@ pub(crate) async fn post(
     mut rng: BoxRng,
     mut repo: BoxRepository,
     activity_tracker: BoundActivityTracker,
+    State(introspection_cache): State<IntrospectionCache>,
     maybe_authorization: Option<TypedHeader<Authorization<Bearer>>>,
 ) -> Result<impl IntoResponse, RouteError> {
     let TypedHeader(authorization) = maybe_authorization.ok_or(RouteError::MissingAuthorization)?;
@@ -122,10 +123,12 @@ pub(crate) async fn post(
         .schedule_job(&mut rng, &clock, SyncDevicesJob::new(&user))
         .await?;
 
-    repo.compat_session().finish(&clock, session).await?;
+    let session = repo.compat_session().finish(&clock, session).await?;
 
     repo.save().await?;
 
+    introspection_cache.invalidate_session(session.id).await;
+
     LOGOUT_COUNTER.add(1, &[KeyValue::new(RESULT, "success")]);
 
     Ok(Json(serde_json::json!({})))
@@ -5,7 +5,7 @@
 
 use std::sync::LazyLock;
 
-use axum::{Json, response::IntoResponse};
+use axum::{Json, extract::State, response::IntoResponse};
 use axum_extra::typed_header::TypedHeader;
 use headers::{Authorization, authorization::Bearer};
 use hyper::StatusCode;
@@ -23,7 +23,7 @@ use tracing::info;
 use ulid::Ulid;
 
 use super::{MatrixError, MatrixJsonBody};
-use crate::{BoundActivityTracker, METER, impl_from_error_for_route};
+use crate::{BoundActivityTracker, IntrospectionCache, METER, impl_from_error_for_route};
 
 static LOGOUT_ALL_COUNTER: LazyLock<Counter<u64>> = LazyLock::new(|| {
     METER
@@ -130,6 +130,7 @@ pub(crate) async fn post(
     mut rng: BoxRng,
     mut repo: BoxRepository,
     activity_tracker: BoundActivityTracker,
+    State(introspection_cache): State<IntrospectionCache>,
     maybe_authorization: Option<TypedHeader<Authorization<Bearer>>>,
     input: Option<MatrixJsonBody<RequestBody>>,
 ) -> Result<impl IntoResponse, RouteError> {
@@ -195,6 +196,8 @@ pub(crate) async fn post(
 
     repo.save().await?;
 
+    introspection_cache.invalidate_user(user.id).await;
+
     LOGOUT_ALL_COUNTER.add(1, &[KeyValue::new(RESULT, "success")]);
 
     Ok(Json(serde_json::json!({})))
@@ -18,6 +18,7 @@ use mas_axum_utils::record_error;
 use serde::{Serialize, de::DeserializeOwned};
 use thiserror::Error;
 
+pub(crate) mod anomaly;
 pub(crate) mod login;
 pub(crate) mod login_sso_complete;
 pub(crate) mod login_sso_redirect;
@@ -4,22 +4,38 @@
 // SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
 // Please see LICENSE files in the repository root for full details.
 
+use std::sync::LazyLock;
+
 use axum::{Json, extract::State, response::IntoResponse};
 use chrono::Duration;
 use hyper::StatusCode;
 use mas_axum_utils::record_error;
-use mas_data_model::{BoxClock, BoxRng, Clock, SiteConfig, TokenFormatError, TokenType};
+use mas_data_model::{
+    BoxClock, BoxRng, Clock, CompatRefreshToken, SiteConfig, TokenFormatError, TokenType,
+};
 use mas_storage::{
     BoxRepository,
     compat::{CompatAccessTokenRepository, CompatRefreshTokenRepository, CompatSessionRepository},
+    queue::{QueueJobRepositoryExt as _, SyncDevicesJob},
 };
+use opentelemetry::{Key, KeyValue, metrics::Counter};
 use serde::{Deserialize, Serialize};
 use serde_with::{DurationMilliSeconds, serde_as};
 use thiserror::Error;
+use tracing::{info, warn};
 use ulid::Ulid;
 
 use super::MatrixError;
-use crate::{BoundActivityTracker, impl_from_error_for_route};
+use crate::{BoundActivityTracker, IntrospectionCache, METER, impl_from_error_for_route};
+
+static REFRESH_TOKEN_REPLAY_COUNTER: LazyLock<Counter<u64>> = LazyLock::new(|| {
+    METER
+        .u64_counter("mas.compat.refresh_token_replay")
+        .with_description("How many compatibility refresh token replays were detected")
+        .with_unit("{replay}")
+        .build()
+});
+const SESSION_REVOKED: Key = Key::from_static_str("session_revoked");
 
 #[derive(Debug, Deserialize)]
 pub struct RequestBody {
@@ -85,6 +101,56 @@ pub struct ResponseBody {
     expires_in_ms: Duration,
 }
 
+async fn refresh_token_replayed(
+    rng: &mut BoxRng,
+    clock: &BoxClock,
+    site_config: &SiteConfig,
+    introspection_cache: &IntrospectionCache,
+    mut repo: BoxRepository,
+    refresh_token: &CompatRefreshToken,
+) -> Result<(), RouteError> {
+    let revoke = site_config.revoke_session_on_refresh_token_replay;
+    warn!(
+        compat_session.id = %refresh_token.session_id,
+        compat_refresh_token.id = %refresh_token.id,
+        session_revoked = revoke,
+        "Compatibility refresh token replay detected",
+    );
+    REFRESH_TOKEN_REPLAY_COUNTER.add(1, &[KeyValue::new(SESSION_REVOKED, revoke)]);
+
+    if !revoke {
+        return Ok(());
+    }
+
+    let session = repo
+        .compat_session()
+        .lookup(refresh_token.session_id)
+        .await?
+        .ok_or(RouteError::UnknownSession(refresh_token.session_id))?;
+
+    if !session.is_valid() {
+        return Ok(());
+    }
+
+    let user = repo
+        .user()
+        .lookup(session.user_id)
+        .await?
+        .ok_or(RouteError::UnknownSession(session.id))?;
+
+    // Schedule a job to sync the devices of the user with the homeserver
+    repo.queue_job()
+        .schedule_job(rng, clock, SyncDevicesJob::new(&user))
+        .await?;
+
+    let session = repo.compat_session().finish(clock, session).await?;
+    repo.save().await?;
+
+    introspection_cache.invalidate_session(session.id).await;
+
+    Ok(())
+}
+
 #[tracing::instrument(name = "handlers.compat.refresh.post", skip_all)]
 pub(crate) async fn post(
     mut rng: BoxRng,
@@ -92,6 +158,7 @@ pub(crate) async fn post(
     mut repo: BoxRepository,
     activity_tracker: BoundActivityTracker,
     State(site_config): State<SiteConfig>,
+    State(introspection_cache): State<IntrospectionCache>,
     Json(input): Json<RequestBody>,
 ) -> Result<impl IntoResponse, RouteError> {
     let token_type = TokenType::check(&input.refresh_token)?;
@@ -100,14 +167,82 @@ pub(crate) async fn post(
         return Err(RouteError::InvalidTokenType(token_type));
     }
 
-    let refresh_token = repo
+    let mut refresh_token = repo
         .compat_refresh_token()
         .find_by_token(&input.refresh_token)
         .await?
         .ok_or(RouteError::UnknownToken)?;
 
     if !refresh_token.is_valid() {
-        return Err(RouteError::RefreshTokenConsumed(refresh_token.id));
+        // We're seeing a refresh token that already has been consumed, this might be
+        // a double-refresh or a replay attack
+        let Some(next_refresh_token_id) = refresh_token.next_refresh_token_id() else {
+            // If we don't have a 'next' refresh token, it may just be because this was
+            // before we were recording those. Let's just treat it as a replay.
+            refresh_token_replayed(
+                &mut rng,
+                &clock,
+                &site_config,
+                &introspection_cache,
+                repo,
+                &refresh_token,
+            )
+            .await?;
+            return Err(RouteError::RefreshTokenConsumed(refresh_token.id));
+        };
+
+        let next_refresh_token = repo
+            .compat_refresh_token()
+            .lookup(next_refresh_token_id)
+            .await?
+            .filter(|token| token.is_valid());
+
+        // Check if the next refresh token was already consumed or not
+        let Some(next_refresh_token) = next_refresh_token else {
+            refresh_token_replayed(
+                &mut rng,
+                &clock,
+                &site_config,
+                &introspection_cache,
+                repo,
+                &refresh_token,
+            )
+            .await?;
+            return Err(RouteError::RefreshTokenConsumed(refresh_token.id));
+        };
+
+        // Check if the associated access token was already used. If the access
+        // token is no longer present, we assume it was *not* used, like we do for
+        // OAuth 2.0 sessions
+        let next_access_token_used = repo
+            .compat_access_token()
+            .lookup(next_refresh_token.access_token_id)
+            .await?
+            .is_some_and(|access_token| access_token.is_used());
+
+        if next_access_token_used {
+            refresh_token_replayed(
+                &mut rng,
+                &clock,
+                &site_config,
+                &introspection_cache,
+                repo,
+                &refresh_token,
+            )
+            .await?;
+            return Err(RouteError::RefreshTokenConsumed(refresh_token.id));
+        }
+
+        // Looks like it's a double-refresh, client lost their refresh token on the
+        // way back. Replacing the unused refresh token below will also expire the
+        // access token that was issued with it.
+        info!(
+            compat_session.id = %refresh_token.session_id,
+            compat_refresh_token.id = %refresh_token.id,
+            "Compatibility refresh token already used, but issued refresh and access tokens are unused. Assuming those were lost; replacing those with new ones."
+        );
+
+        refresh_token = next_refresh_token;
     }
 
     let session = repo
@@ -167,6 +302,10 @@ pub(crate) async fn post(
 
     repo.save().await?;
 
+    // The previous access token might have been cached by the introspection
+    // endpoint
+    introspection_cache.invalidate_session(session.id).await;
+
     Ok(Json(ResponseBody {
         access_token: new_access_token.token,
         refresh_token: new_refresh_token.token,
@@ -4,6 +4,7 @@
 // Please see LICENSE files in the repository root for full details.
 
 use axum::http::{Request, StatusCode};
+use mas_data_model::Clock;
 use mas_matrix::{HomeserverConnection, ProvisionRequest};
 use serde::{Deserialize, Serialize};
 use zeroize::Zeroizing;
@@ -182,7 +183,11 @@ async fn test_refresh_with_consumed_token(pool: sqlx::PgPool) {
     let first_refresh_response = state.request(first_refresh_request).await;
     first_refresh_response.assert_status(StatusCode::OK);
 
-    let _first_refresh_response: RefreshResponse = first_refresh_response.json();
+    let first_refresh_response: RefreshResponse = first_refresh_response.json();
+
+    // Use the new access token, so that reusing the refresh token can't be a
+    // double-refresh
+    mark_access_token_used(&state, &first_refresh_response.access_token).await;
 
     // Try to use the same refresh token again - should fail because it's consumed
     let second_refresh_request = Request::post("/_matrix/client/v3/refresh").json(&refresh_request);
@@ -191,6 +196,143 @@ async fn test_refresh_with_consumed_token(pool: sqlx::PgPool) {
     second_refresh_response.assert_status(StatusCode::UNAUTHORIZED);
 }
 
+#[sqlx::test(migrator = "mas_storage_pg::MIGRATOR")]
+async fn test_refresh_token_replay_revokes_session(pool: sqlx::PgPool) {
+    setup();
+    let site_config = mas_data_model::SiteConfig {
+        revoke_session_on_refresh_token_replay: true,
+        ..crate::test_utils::test_site_config()
+    };
+    let state = TestState::from_pool_with_site_config(pool, site_config)
+        .await
+        .unwrap();
+
+    create_test_user(&state, "testuser").await;
+
+    let login_request = Request::post("/_matrix/client/v3/login").json(&LoginRequest {
+        credentials: LoginCredentials::Password {
+            identifier: LoginIdentifier::User {
+                user: "testuser".to_owned(),
+            },
+            password: "password".to_owned(),
+        },
+        refresh_token: true,
+    });
+
+    let login_response = state.request(login_request).await;
+    login_response.assert_status(StatusCode::OK);
+
+    let login_response: LoginResponse = login_response.json();
+    let refresh_request = RefreshRequest {
+        refresh_token: login_response
+            .refresh_token
+            .expect("Login should return a refresh token"),
+    };
+
+    // Use the refresh token once
+    let request = Request::post("/_matrix/client/v3/refresh").json(&refresh_request);
+    let response = state.request(request).await;
+    response.assert_status(StatusCode::OK);
+    let response: RefreshResponse = response.json();
+    mark_access_token_used(&state, &response.access_token).await;
+
+    // Replay it, which should finish the session
+    let request = Request::post("/_matrix/client/v3/refresh").json(&refresh_request);
+    let replay_response = state.request(request).await;
+    replay_response.assert_status(StatusCode::UNAUTHORIZED);
+
+    // The refresh token we got from the first refresh is no longer usable
+    let request = Request::post("/_matrix/client/v3/refresh").json(&RefreshRequest {
+        refresh_token: response.refresh_token,
+    });
+    let response = state.request(request).await;
+    response.assert_status(StatusCode::UNAUTHORIZED);
+}
+
+#[sqlx::test(migrator = "mas_storage_pg::MIGRATOR")]
+async fn test_refresh_token_double_refresh(pool: sqlx::PgPool) {
+    setup();
+    let site_config = mas_data_model::SiteConfig {
+        revoke_session_on_refresh_token_replay: true,
+        ..crate::test_utils::test_site_config()
+    };
+    let state = TestState::from_pool_with_site_config(pool, site_config)
+        .await
+        .unwrap();
+
+    create_test_user(&state, "testuser").await;
+
+    let login_request = Request::post("/_matrix/client/v3/login").json(&LoginRequest {
+        credentials: LoginCredentials::Password {
+            identifier: LoginIdentifier::User {
+                user: "testuser".to_owned(),
+            },
+            password: "password".to_owned(),
+        },
+        refresh_token: true,
+    });
+
+    let login_response = state.request(login_request).await;
+    login_response.assert_status(StatusCode::OK);
+
+    let login_response: LoginResponse = login_response.json();
+    let refresh_request = RefreshRequest {
+        refresh_token: login_response
+            .refresh_token
+            .expect("Login should return a refresh token"),
+    };
+
+    // Use the refresh token once, and pretend the response got lost
+    let request = Request::post("/_matrix/client/v3/refresh").json(&refresh_request);
+    let response = state.request(request).await;
+    response.assert_status(StatusCode::OK);
+    let lost_response: RefreshResponse = response.json();
+
+    // Using it again before the new tokens were used is a double-refresh, which
+    // should succeed
+    let request = Request::post("/_matrix/client/v3/refresh").json(&refresh_request);
+    let response = state.request(request).await;
+    response.assert_status(StatusCode::OK);
+    let response: RefreshResponse = response.json();
+    assert_ne!(response, lost_response);
+
+    // The access token from the lost response was expired
+    let mut repo = state.repository().await.unwrap();
+    let lost_access_token = repo
+        .compat_access_token()
+        .find_by_token(&lost_response.access_token)
+        .await
+        .unwrap()
+        .expect("access token should exist");
+    assert!(!lost_access_token.is_valid(state.clock.now()));
+    repo.cancel().await.unwrap();
+
+    // But the session wasn't finished, and the new refresh token still works
+    let request = Request::post("/_matrix/client/v3/refresh").json(&RefreshRequest {
+        refresh_token: response.refresh_token,
+    });
+    let response = state.request(request).await;
+    response.assert_status(StatusCode::OK);
+}
+
+async fn mark_access_token_used(state: &TestState, access_token: &str) {
+    let mut repo = state.repository().await.unwrap();
+
+    let access_token = repo
+        .compat_access_token()
+        .find_by_token(access_token)
+        .await
+        .unwrap()
+        .expect("access token should exist");
+
+    repo.compat_access_token()
+        .mark_used(&state.clock, access_token)
+        .await
+        .unwrap();
+
+    repo.save().await.unwrap();
+}
+
 async fn create_test_user(state: &TestState, username: &str) -> mas_data_model::User {
     let mut repo = state.repository().await.unwrap();
     let mut rng = state.rng();
@@ -54,8 +54,8 @@ use self::{
     query::Query,
 };
 use crate::{
-    BoundActivityTracker, Limiter, RequesterFingerprint, impl_from_error_for_route,
-    passwords::PasswordManager,
+    BoundActivityTracker, IntrospectionCache, Limiter, RequesterFingerprint,
+    impl_from_error_for_route, passwords::PasswordManager,
 };
 
 #[cfg(test)]
@@ -76,6 +76,7 @@ struct GraphQLState {
     password_manager: PasswordManager,
     url_builder: UrlBuilder,
     limiter: Limiter,
+    introspection_cache: IntrospectionCache,
 }
 
 #[async_trait::async_trait]
@@ -108,6 +109,10 @@ impl state::State for GraphQLState {
         &self.limiter
     }
 
+    fn introspection_cache(&self) -> &IntrospectionCache {
+        &self.introspection_cache
+    }
+
     fn clock(&self) -> BoxClock {
         let clock = SystemClock::default();
         Box::new(clock)
@@ -131,6 +136,7 @@ pub fn schema(
     password_manager: PasswordManager,
     url_builder: UrlBuilder,
     limiter: Limiter,
+    introspection_cache: IntrospectionCache,
 ) -> Schema {
     let state = GraphQLState {
         repository_factory,
@@ -140,6 +146,7 @@ pub fn schema(
         password_manager,
         url_builder,
         limiter,
+        introspection_cache,
     };
     let state: BoxState = Box::new(state);
 
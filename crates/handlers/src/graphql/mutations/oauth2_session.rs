@@ -292,6 +292,11 @@ impl OAuth2SessionMutations {
 
         repo.save().await?;
 
+        state
+            .introspection_cache()
+            .invalidate_session(session.id)
+            .await;
+
         Ok(EndOAuth2SessionPayload::Ended(Box::new(session)))
     }
 
@@ -0,0 +1,412 @@
+// Copyright 2026 Element Creations Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+// Please see LICENSE files in the repository root for full details.
+
+use anyhow::Context as _;
+use async_graphql::{Context, Description, Enum, ID, InputObject, Object};
+use mas_storage::{
+    RepositoryAccess,
+    upstream_oauth2::{
+        UpstreamOAuthLinkFilter, UpstreamOAuthLinkRepository, UpstreamOAuthProviderRepository,
+    },
+    user::{UserEmailFilter, UserEmailRepository, UserPasswordRepository, UserRepository},
+};
+use url::Url;
+
+use super::verify_password_if_needed;
+use crate::graphql::{
+    model::{NodeType, UpstreamOAuth2Link, UpstreamOAuth2Provider, User},
+    state::ContextExt,
+};
+
+#[derive(Default)]
+pub struct UpstreamOAuthMutations {
+    _private: (),
+}
+
+/// The input for the `startUpstreamOAuth2Link` mutation
+#[derive(InputObject)]
+struct StartUpstreamOAuth2LinkInput {
+    /// The ID of the upstream provider to link
+    upstream_oauth2_provider_id: ID,
+}
+
+/// The status of the `startUpstreamOAuth2Link` mutation
+#[derive(Enum, Copy, Clone, Eq, PartialEq)]
+enum StartUpstreamOAuth2LinkStatus {
+    /// The link can be started by sending the user to the returned URL
+    Started,
+
+    /// The provider was not found
+    NotFound,
+
+    /// The user already has a link to this provider
+    AlreadyLinked,
+
+    /// Linking this provider is not allowed by the policy
+    Denied,
+}
+
+/// The payload of the `startUpstreamOAuth2Link` mutation
+#[derive(Description)]
+enum StartUpstreamOAuth2LinkPayload {
+    Started {
+        provider: Box<mas_data_model::UpstreamOAuthProvider>,
+        url: Url,
+    },
+    NotFound,
+    AlreadyLinked(mas_data_model::UpstreamOAuthLink),
+    Denied {
+        violations: Vec<mas_policy::Violation>,
+    },
+}
+
+#[Object(use_type_description)]
+impl StartUpstreamOAuth2LinkPayload {
+    /// Status of the operation
+    async fn status(&self) -> StartUpstreamOAuth2LinkStatus {
+        match self {
+            Self::Started { .. } => StartUpstreamOAuth2LinkStatus::Started,
+            Self::NotFound => StartUpstreamOAuth2LinkStatus::NotFound,
+            Self::AlreadyLinked(_) => StartUpstreamOAuth2LinkStatus::AlreadyLinked,
+            Self::Denied { .. } => StartUpstreamOAuth2LinkStatus::Denied,
+        }
+    }
+
+    /// The URL to send the user to, to link the provider
+    async fn url(&self) -> Option<&Url> {
+        match self {
+            Self::Started { url, .. } => Some(url),
+            Self::NotFound | Self::AlreadyLinked(_) | Self::Denied { .. } => None,
+        }
+    }
+
+    /// The provider being linked
+    async fn provider(&self) -> Option<UpstreamOAuth2Provider> {
+        match self {
+            Self::Started { provider, .. } => {
+                Some(UpstreamOAuth2Provider::new((**provider).clone()))
+            }
+            Self::NotFound | Self::AlreadyLinked(_) | Self::Denied { .. } => None,
+        }
+    }
+
+    /// The existing link to this provider
+    async fn link(&self) -> Option<UpstreamOAuth2Link> {
+        match self {
+            Self::AlreadyLinked(link) => Some(UpstreamOAuth2Link::new(link.clone())),
+            Self::Started { .. } | Self::NotFound | Self::Denied { .. } => None,
+        }
+    }
+
+    /// The list of policy violations if linking was denied
+    async fn violations(&self) -> Option<Vec<String>> {
+        let Self::Denied { violations } = self else {
+            return None;
+        };
+
+        let messages = violations.iter().map(|v| v.msg.clone()).collect();
+        Some(messages)
+    }
+}
+
+/// The input for the `removeUpstreamOAuth2Link` mutation
+#[derive(InputObject)]
+struct RemoveUpstreamOAuth2LinkInput {
+    /// The ID of the upstream link to remove
+    upstream_oauth2_link_id: ID,
+
+    /// The user's current password. This is required if the user is not an
+    /// admin and it has a password on its account.
+    password: Option<String>,
+}
+
+/// The status of the `removeUpstreamOAuth2Link` mutation
+#[derive(Enum, Copy, Clone, Eq, PartialEq)]
+enum RemoveUpstreamOAuth2LinkStatus {
+    /// The link was removed
+    Removed,
+
+    /// The link was not found
+    NotFound,
+
+    /// The password provided is incorrect
+    IncorrectPassword,
+
+    /// The user would have no way left to log in without this link
+    LastAuthenticationMethod,
+
+    /// Removing this link is not allowed by the policy
+    Denied,
+}
+
+/// The payload of the `removeUpstreamOAuth2Link` mutation
+#[derive(Description)]
+enum RemoveUpstreamOAuth2LinkPayload {
+    Removed(mas_data_model::UpstreamOAuthLink),
+    NotFound,
+    IncorrectPassword,
+    LastAuthenticationMethod,
+    Denied {
+        violations: Vec<mas_policy::Violation>,
+    },
+}
+
+#[Object(use_type_description)]
+impl RemoveUpstreamOAuth2LinkPayload {
+    /// Status of the operation
+    async fn status(&self) -> RemoveUpstreamOAuth2LinkStatus {
+        match self {
+            Self::Removed(_) => RemoveUpstreamOAuth2LinkStatus::Removed,
+            Self::NotFound => RemoveUpstreamOAuth2LinkStatus::NotFound,
+            Self::IncorrectPassword => RemoveUpstreamOAuth2LinkStatus::IncorrectPassword,
+            Self::LastAuthenticationMethod => {
+                RemoveUpstreamOAuth2LinkStatus::LastAuthenticationMethod
+            }
+            Self::Denied { .. } => RemoveUpstreamOAuth2LinkStatus::Denied,
+        }
+    }
+
+    /// The link that was removed
+    async fn link(&self) -> Option<UpstreamOAuth2Link> {
+        match self {
+            Self::Removed(link) => Some(UpstreamOAuth2Link::new(link.clone())),
+            Self::NotFound
+            | Self::IncorrectPassword
+            | Self::LastAuthenticationMethod
+            | Self::Denied { .. } => None,
+        }
+    }
+
+    /// The user to whom the link belonged
+    async fn user(&self, ctx: &Context<'_>) -> Result<Option<User>, async_graphql::Error> {
+        let state = ctx.state();
+
+        let Self::Removed(link) = self else {
+            return Ok(None);
+        };
+
+        let Some(user_id) = link.user_id else {
+            return Ok(None);
+        };
+
+        let mut repo = state.repository().await?;
+
+        let user = repo
+            .user()
+            .lookup(user_id)
+            .await?
+            .context("User not found")?;
+
+        Ok(Some(User(user)))
+    }
+
+    /// The list of policy violations if removing the link was denied
+    async fn violations(&self) -> Option<Vec<String>> {
+        let Self::Denied { violations } = self else {
+            return None;
+        };
+
+        let messages = violations.iter().map(|v| v.msg.clone()).collect();
+        Some(messages)
+    }
+}
+
+#[Object]
+impl UpstreamOAuthMutations {
+    /// Start linking an upstream provider to the current user.
+    ///
+    /// This returns a URL to send the user to, which goes through the
+    /// provider and then asks the user to confirm the link.
+    async fn start_upstream_oauth2_link(
+        &self,
+        ctx: &Context<'_>,
+        input: StartUpstreamOAuth2LinkInput,
+    ) -> Result<StartUpstreamOAuth2LinkPayload, async_graphql::Error> {
+        let state = ctx.state();
+        let provider_id =
+            NodeType::UpstreamOAuth2Provider.extract_ulid(&input.upstream_oauth2_provider_id)?;
+        let requester = ctx.requester();
+
+        // Only allow calling this if the requester is a browser session, as the
+        // link is made by the browser going through the provider
+        let Some(browser_session) = requester.browser_session() else {
+            return Err(async_graphql::Error::new("Unauthorized"));
+        };
+
+        let mut repo = state.repository().await?;
+
+        let provider = repo
+            .upstream_oauth_provider()
+            .lookup(provider_id)
+            .await?
+            .filter(mas_data_model::UpstreamOAuthProvider::enabled);
+        let Some(provider) = provider else {
+            return Ok(StartUpstreamOAuth2LinkPayload::NotFound);
+        };
+
+        let existing = repo
+            .upstream_oauth_link()
+            .list(
+                UpstreamOAuthLinkFilter::new()
+                    .for_user(&browser_session.user)
+                    .for_provider(&provider),
+                mas_storage::Pagination::first(1),
+            )
+            .await?;
+        if let Some(edge) = existing.edges.into_iter().next() {
+            return Ok(StartUpstreamOAuth2LinkPayload::AlreadyLinked(edge.node));
+        }
+
+        let mut policy = state.policy().await?;
+        let res = policy
+            .evaluate_upstream_oauth_link(mas_policy::UpstreamOAuthLinkInput {
+                user: &browser_session.user,
+                action: mas_policy::UpstreamOAuthLinkAction::Link,
+                provider: mas_policy::UpstreamOAuthLinkProvider {
+                    id: provider.id,
+                    issuer: provider.issuer.as_deref(),
+                },
+                requester: requester.for_policy(),
+            })
+            .await?;
+        if !res.valid() {
+            return Ok(StartUpstreamOAuth2LinkPayload::Denied {
+                violations: res.violations,
+            });
+        }
+
+        let route = mas_router::UpstreamOAuth2Authorize::new(provider.id)
+            .and_then(mas_router::PostAuthAction::manage_account(None));
+        let url = state.url_builder().absolute_url_for(&route);
+
+        Ok(StartUpstreamOAuth2LinkPayload::Started {
+            provider: Box::new(provider),
+            url,
+        })
+    }
+
+    /// Remove a link to an upstream provider.
+    ///
+    /// This refuses to remove the last way the user has to log in.
+    async fn remove_upstream_oauth2_link(
+        &self,
+        ctx: &Context<'_>,
+        input: RemoveUpstreamOAuth2LinkInput,
+    ) -> Result<RemoveUpstreamOAuth2LinkPayload, async_graphql::Error> {
+        let state = ctx.state();
+        let link_id = NodeType::UpstreamOAuth2Link.extract_ulid(&input.upstream_oauth2_link_id)?;
+        let requester = ctx.requester();
+
+        let clock = state.clock();
+        let mut repo = state.repository().await?;
+
+        let link = repo.upstream_oauth_link().lookup(link_id).await?;
+        let Some(link) = link else {
+            return Ok(RemoveUpstreamOAuth2LinkPayload::NotFound);
+        };
+
+        if !requester.is_owner_or_admin(&link) {
+            return Ok(RemoveUpstreamOAuth2LinkPayload::NotFound);
+        }
+
+        let Some(user_id) = link.user_id else {
+            return Ok(RemoveUpstreamOAuth2LinkPayload::NotFound);
+        };
+
+        let user = repo
+            .user()
+            .lookup(user_id)
+            .await?
+            .context("Failed to load user")?;
+
+        let provider = repo
+            .upstream_oauth_provider()
+            .lookup(link.provider_id)
+            .await?
+            .context("Failed to load upstream provider")?;
+
+        // Validate the password input if needed
+        if !verify_password_if_needed(
+            requester,
+            state.site_config(),
+            &state.password_manager(),
+            input.password,
+            &user,
+            &mut repo,
+        )
+        .await?
+        {
+            return Ok(RemoveUpstreamOAuth2LinkPayload::IncorrectPassword);
+        }
+
+        // Admins can remove any link, regardless of the policy or whether it
+        // locks the user out
+        if !requester.is_admin() {
+            let mut policy = state.policy().await?;
+            let res = policy
+                .evaluate_upstream_oauth_link(mas_policy::UpstreamOAuthLinkInput {
+                    user: &user,
+                    action: mas_policy::UpstreamOAuthLinkAction::Unlink,
+                    provider: mas_policy::UpstreamOAuthLinkProvider {
+                        id: provider.id,
+                        issuer: provider.issuer.as_deref(),
+                    },
+                    requester: requester.for_policy(),
+                })
+                .await?;
+            if !res.valid() {
+                return Ok(RemoveUpstreamOAuth2LinkPayload::Denied {
+                    violations: res.violations,
+                });
+            }
+
+            // Links to disabled providers can't be used to log in anyway, so
+            // removing them never locks the user out
+            if provider.enabled() {
+                let site_config = state.site_config();
+
+                let has_password = site_config.password_login_enabled
+                    && repo.user_password().active(&user).await?.is_some();
+
+                // Users provisioned from the LDAP directory log in with their
+                // directory password
+                let has_directory_entry = site_config.password_login_enabled
+                    && repo.user().ldap_dn(&user).await?.is_some();
+
+                // This includes the link being removed
+                let links = repo
+                    .upstream_oauth_link()
+                    .count(
+                        UpstreamOAuthLinkFilter::new()
+                            .for_user(&user)
+                            .enabled_providers_only(),
+                    )
+                    .await?;
+
+                // Users with an email address can get back in through account
+                // recovery
+                let can_recover_with_email = site_config.password_login_enabled
+                    && site_config.account_recovery_allowed
+                    && repo
+                        .user_email()
+                        .count(UserEmailFilter::new().for_user(&user))
+                        .await?
+                        > 0;
+
+                if !has_password && !has_directory_entry && links <= 1 && !can_recover_with_email {
+                    return Ok(RemoveUpstreamOAuth2LinkPayload::LastAuthenticationMethod);
+                }
+            }
+        }
+
+        repo.upstream_oauth_link()
+            .remove(&clock, link.clone())
+            .await?;
+
+        repo.save().await?;
+
+        Ok(RemoveUpstreamOAuth2LinkPayload::Removed(link))
+    }
+}
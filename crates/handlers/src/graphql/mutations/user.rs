@@ -560,6 +560,9 @@ impl UserMutations {
 
         repo.save().await?;
 
+        // Locked users can't use their tokens anymore
+        state.introspection_cache().invalidate_user(user.id).await;
+
         Ok(LockUserPayload::Locked(user))
     }
 
@@ -981,6 +984,9 @@ impl UserMutations {
 
         repo.save().await?;
 
+        // Deactivated users can't use their tokens anymore
+        state.introspection_cache().invalidate_user(user.id).await;
+
         Ok(DeactivateUserPayload::Deactivated(user))
     }
 }
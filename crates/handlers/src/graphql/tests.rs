@@ -7,12 +7,20 @@
 use axum::http::Request;
 use hyper::StatusCode;
 use mas_axum_utils::SessionInfoExt;
-use mas_data_model::{AccessToken, Client, TokenType, User};
+use mas_data_model::{
+    AccessToken, Client, TokenType, UpstreamOAuthProviderClaimsImports,
+    UpstreamOAuthProviderDiscoveryMode, UpstreamOAuthProviderOnBackchannelLogout,
+    UpstreamOAuthProviderPkceMode, UpstreamOAuthProviderTokenAuthMethod, User,
+};
+use mas_iana::jose::JsonWebSignatureAlg;
 use mas_matrix::{HomeserverConnection, ProvisionRequest};
 use mas_router::SimpleRoute;
 use mas_storage::{
     RepositoryAccess,
     oauth2::{OAuth2AccessTokenRepository, OAuth2ClientRepository},
+    upstream_oauth2::{
+        UpstreamOAuthLinkRepository, UpstreamOAuthProviderParams, UpstreamOAuthProviderRepository,
+    },
 };
 use oauth2_types::{
     registration::ClientRegistrationResponse,
@@ -20,6 +28,7 @@ use oauth2_types::{
     scope::{OPENID, Scope, ScopeToken},
 };
 use sqlx::PgPool;
+use ulid::Ulid;
 use zeroize::Zeroizing;
 
 use crate::test_utils::{self, CookieHelper, RequestBuilderExt, ResponseExt, TestState, setup};
@@ -1078,3 +1087,152 @@ async fn test_deactivate_user_rejected_wrong_password(pool: PgPool) {
         response.data
     );
 }
+
+/// Test that the removeUpstreamOauth2Link mutation refuses to remove the last
+/// way a user has to log in.
+#[sqlx::test(migrator = "mas_storage_pg::MIGRATOR")]
+async fn test_remove_upstream_oauth2_link_last_authentication_method(pool: PgPool) {
+    setup();
+    let state = TestState::from_pool(pool).await.unwrap();
+
+    let mut rng = state.rng();
+    let mut repo = state.repository().await.unwrap();
+    let user = repo
+        .user()
+        .add(&mut rng, &state.clock, "alice".to_owned())
+        .await
+        .unwrap();
+
+    let mut links = Vec::new();
+    for issuer in ["https://first.example.com/", "https://second.example.com/"] {
+        let provider = repo
+            .upstream_oauth_provider()
+            .add(
+                &mut rng,
+                &state.clock,
+                UpstreamOAuthProviderParams {
+                    issuer: Some(issuer.to_owned()),
+                    human_name: None,
+                    brand_name: None,
+                    scope: Scope::from_iter([OPENID]),
+                    token_endpoint_auth_method: UpstreamOAuthProviderTokenAuthMethod::None,
+                    token_endpoint_signing_alg: None,
+                    id_token_signed_response_alg: JsonWebSignatureAlg::Rs256,
+                    client_id: "client".to_owned(),
+                    encrypted_client_secret: None,
+                    claims_imports: UpstreamOAuthProviderClaimsImports::default(),
+                    authorization_endpoint_override: None,
+                    token_endpoint_override: None,
+                    userinfo_endpoint_override: None,
+                    fetch_userinfo: false,
+                    userinfo_signed_response_alg: None,
+                    jwks_uri_override: None,
+                    discovery_mode: UpstreamOAuthProviderDiscoveryMode::Oidc,
+                    pkce_mode: UpstreamOAuthProviderPkceMode::Auto,
+                    response_mode: None,
+                    additional_authorization_parameters: Vec::new(),
+                    forward_login_hint: false,
+                    on_backchannel_logout: UpstreamOAuthProviderOnBackchannelLogout::DoNothing,
+                    ui_order: 0,
+                    saml: None,
+                    email_domains: Vec::new(),
+                    store_tokens: false,
+                    allow_initiated_login: false,
+                    encrypted_client_keys: None,
+                },
+            )
+            .await
+            .unwrap();
+        let link = repo
+            .upstream_oauth_link()
+            .add(
+                &mut rng,
+                &state.clock,
+                &provider,
+                "subject".to_owned(),
+                None,
+            )
+            .await
+            .unwrap();
+        repo.upstream_oauth_link()
+            .associate_to_user(&link, &user)
+            .await
+            .unwrap();
+        links.push(link);
+    }
+
+    let browser_session = repo
+        .browser_session()
+        .add(&mut rng, &state.clock, &user, None)
+        .await
+        .unwrap();
+    repo.save().await.unwrap();
+
+    let cookie_jar = state.cookie_jar();
+    let cookie_jar = cookie_jar.set_session(&browser_session);
+    let cookies = CookieHelper::new();
+    cookies.import(cookie_jar);
+
+    let remove = |link_id: Ulid| {
+        let request = Request::post("/graphql").json(serde_json::json!({
+            "query": format!(r#"
+                mutation {{
+                    removeUpstreamOauth2Link(input: {{
+                        upstreamOauth2LinkId: "upstream_oauth2_link:{link_id}"
+                    }}) {{
+                        status
+                    }}
+                }}
+            "#),
+        }));
+        cookies.with_cookies(request)
+    };
+
+    // The first link can be removed, as the user still has the second one
+    let response = state.request(remove(links[0].id)).await;
+    response.assert_status(StatusCode::OK);
+    let response: GraphQLResponse = response.json();
+    assert!(response.errors.is_empty(), "{:?}", response.errors);
+    assert_eq!(
+        response.data["removeUpstreamOauth2Link"]["status"].as_str(),
+        Some("REMOVED"),
+        "{:?}",
+        response.data
+    );
+
+    // The second one is the last way to log in
+    let response = state.request(remove(links[1].id)).await;
+    response.assert_status(StatusCode::OK);
+    let response: GraphQLResponse = response.json();
+    assert!(response.errors.is_empty(), "{:?}", response.errors);
+    assert_eq!(
+        response.data["removeUpstreamOauth2Link"]["status"].as_str(),
+        Some("LAST_AUTHENTICATION_METHOD"),
+        "{:?}",
+        response.data
+    );
+
+    // Once linked to an LDAP directory entry, the user can log in with their
+    // directory password
+    let mut repo = state.repository().await.unwrap();
+    repo.user()
+        .add_ldap_link(
+            &state.clock,
+            &user,
+            "uid=alice,ou=people,dc=example,dc=com".to_owned(),
+        )
+        .await
+        .unwrap();
+    repo.save().await.unwrap();
+
+    let response = state.request(remove(links[1].id)).await;
+    response.assert_status(StatusCode::OK);
+    let response: GraphQLResponse = response.json();
+    assert!(response.errors.is_empty(), "{:?}", response.errors);
+    assert_eq!(
+        response.data["removeUpstreamOauth2Link"]["status"].as_str(),
+        Some("REMOVED"),
+        "{:?}",
+        response.data
+    );
+}
@@ -0,0 +1,175 @@
+// Copyright 2026 Element Creations Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+// Please see LICENSE files in the repository root for full details.
+
+use std::{collections::HashMap, sync::Arc};
+
+use chrono::{DateTime, Duration, Utc};
+use mas_data_model::{CompatSession, Session};
+use oauth2_types::requests::IntrospectionResponse;
+use sha2::{Digest, Sha256};
+use tokio::sync::Mutex;
+use ulid::Ulid;
+
+/// Maximum number of entries kept in the cache. Once reached, expired entries
+/// are pruned, and new results are not cached until some room is made.
+const MAX_ENTRIES: usize = 100_000;
+
+/// The session an introspected token belongs to, kept around to record the
+/// session activity on cache hits
+#[derive(Debug, Clone)]
+pub(crate) enum CachedSession {
+    OAuth2(Session),
+    Compat(CompatSession),
+}
+
+impl CachedSession {
+    fn id(&self) -> Ulid {
+        match self {
+            Self::OAuth2(session) => session.id,
+            Self::Compat(session) => session.id,
+        }
+    }
+
+    fn user_id(&self) -> Option<Ulid> {
+        match self {
+            Self::OAuth2(session) => session.user_id,
+            Self::Compat(session) => Some(session.user_id),
+        }
+    }
+}
+
+/// The tokens are never kept in memory, only their SHA-256 hash. The
+/// response also depends on whether the introspecting client supports getting
+/// the device ID as a separate field.
+type CacheKey = ([u8; 32], bool);
+
+#[derive(Debug)]
+struct Entry {
+    response: IntrospectionResponse,
+    session: CachedSession,
+    cached_at: DateTime<Utc>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    ttl: Duration,
+    entries: Mutex<HashMap<CacheKey, Entry>>,
+}
+
+impl Inner {
+    fn is_fresh(&self, entry: &Entry, now: DateTime<Utc>) -> bool {
+        entry.cached_at + self.ttl > now && entry.response.exp.is_none_or(|exp| exp > now)
+    }
+}
+
+/// A short-lived, in-memory cache of positive access token introspection
+/// results
+///
+/// Sessions ended through this instance are invalidated right away. Other
+/// revocations are only picked up once the cached result expires, which is
+/// why the TTL should be kept short.
+#[derive(Debug, Clone, Default)]
+pub struct IntrospectionCache {
+    inner: Option<Arc<Inner>>,
+}
+
+impl IntrospectionCache {
+    /// Create a new cache, keeping results for the given duration. Passing
+    /// `None` disables the cache.
+    #[must_use]
+    pub fn new(ttl: Option<Duration>) -> Self {
+        let inner = ttl.map(|ttl| {
+            Arc::new(Inner {
+                ttl,
+                entries: Mutex::new(HashMap::new()),
+            })
+        });
+
+        Self { inner }
+    }
+
+    fn key(token: &str, explicit_device_id: bool) -> CacheKey {
+        (Sha256::digest(token).into(), explicit_device_id)
+    }
+
+    /// Get the cached introspection result of a token, if it is still fresh
+    pub(crate) async fn get(
+        &self,
+        now: DateTime<Utc>,
+        token: &str,
+        explicit_device_id: bool,
+    ) -> Option<(IntrospectionResponse, CachedSession)> {
+        let inner = self.inner.as_ref()?;
+        let key = Self::key(token, explicit_device_id);
+        let mut entries = inner.entries.lock().await;
+        let entry = entries.get(&key)?;
+
+        if !inner.is_fresh(entry, now) {
+            entries.remove(&key);
+            return None;
+        }
+
+        let mut response = entry.response.clone();
+        response.expires_in = response
+            .exp
+            .map(|expires_at| expires_at.signed_duration_since(now));
+
+        Some((response, entry.session.clone()))
+    }
+
+    /// Cache a positive introspection result of a token
+    pub(crate) async fn insert(
+        &self,
+        now: DateTime<Utc>,
+        token: &str,
+        explicit_device_id: bool,
+        response: &IntrospectionResponse,
+        session: CachedSession,
+    ) {
+        let Some(inner) = self.inner.as_ref() else {
+            return;
+        };
+
+        let mut entries = inner.entries.lock().await;
+        if entries.len() >= MAX_ENTRIES {
+            entries.retain(|_, entry| inner.is_fresh(entry, now));
+            if entries.len() >= MAX_ENTRIES {
+                return;
+            }
+        }
+
+        entries.insert(
+            Self::key(token, explicit_device_id),
+            Entry {
+                response: response.clone(),
+                session,
+                cached_at: now,
+            },
+        );
+    }
+
+    /// Forget all the cached results for tokens of the given session
+    pub(crate) async fn invalidate_session(&self, session_id: Ulid) {
+        if let Some(inner) = self.inner.as_ref() {
+            inner
+                .entries
+                .lock()
+                .await
+                .retain(|_, entry| entry.session.id() != session_id);
+        }
+    }
+
+    /// Forget all the cached results for tokens of sessions of the given user,
+    /// for when many sessions are ended at once
+    pub(crate) async fn invalidate_user(&self, user_id: Ulid) {
+        if let Some(inner) = self.inner.as_ref() {
+            inner
+                .entries
+                .lock()
+                .await
+                .retain(|_, entry| entry.session.user_id() != Some(user_id));
+        }
+    }
+}
@@ -0,0 +1,229 @@
+// Copyright 2026 Element Creations Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+// Please see LICENSE files in the repository root for full details.
+
+//! Password authentication against an LDAP directory, shared by the login
+//! form and the compatibility login API
+
+use std::sync::LazyLock;
+
+use mas_data_model::{Clock, User};
+use mas_ldap::{Directory, DirectoryUser};
+use mas_matrix::HomeserverConnection;
+use mas_policy::Policy;
+use mas_storage::{
+    BoxRepository, RepositoryAccess, RepositoryError,
+    queue::{ProvisionUserJob, QueueJobRepositoryExt as _},
+    user::{UserEmailFilter, UserEmailRepository, UserRepository},
+};
+use opentelemetry::{Key, KeyValue, metrics::Counter};
+use rand::{CryptoRng, RngCore};
+use thiserror::Error;
+
+use crate::{Limiter, METER, RequesterFingerprint, rate_limit::DirectoryPasswordCheckLimitedError};
+
+static LDAP_PROVISION_COUNTER: LazyLock<Counter<u64>> = LazyLock::new(|| {
+    METER
+        .u64_counter("mas.user.ldap_provision")
+        .with_description("Number of users provisioned from the LDAP directory")
+        .with_unit("{user}")
+        .build()
+});
+const RESULT: Key = Key::from_static_str("result");
+
+#[derive(Debug, Error)]
+pub(crate) enum DirectoryLoginError {
+    #[error(transparent)]
+    RateLimited(#[from] DirectoryPasswordCheckLimitedError),
+
+    #[error("Could not check the password against the directory")]
+    Directory(#[source] anyhow::Error),
+
+    #[error("Could not check the localpart availability on the homeserver")]
+    Homeserver(#[source] anyhow::Error),
+
+    /// The user exists in the directory, but can't be provisioned, with the
+    /// reason why
+    #[error("The directory user can't be provisioned: {0}")]
+    ProvisioningDenied(String),
+
+    #[error(transparent)]
+    Policy(#[from] mas_policy::EvaluationError),
+
+    #[error(transparent)]
+    Repository(#[from] RepositoryError),
+}
+
+/// Check a password against the directory, and find the user linked to the
+/// directory entry, provisioning them on their first login
+///
+/// An existing user with the same username which isn't linked to the entry is
+/// only signed into if `adopt_existing_users` is set, in which case the user
+/// gets linked to the entry.
+///
+/// Returns [`None`] if the credentials are wrong. The caller is responsible
+/// for checking whether the user is locked or deactivated.
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn authenticate(
+    rng: &mut (impl RngCore + CryptoRng + Send),
+    clock: &impl Clock,
+    repo: &mut BoxRepository,
+    directory: &dyn Directory,
+    adopt_existing_users: bool,
+    homeserver: &dyn HomeserverConnection,
+    policy: &mut Policy,
+    limiter: &Limiter,
+    requester: RequesterFingerprint,
+    policy_requester: mas_policy::Requester,
+    username: &str,
+    password: &str,
+) -> Result<Option<(User, DirectoryUser)>, DirectoryLoginError> {
+    limiter.check_directory_password(requester, username)?;
+
+    let Some(entry) = directory
+        .authenticate(username, password)
+        .await
+        .map_err(DirectoryLoginError::Directory)?
+    else {
+        return Ok(None);
+    };
+
+    if let Some(user) = repo.user().find_by_ldap_dn(&entry.dn).await? {
+        return Ok(Some((user, entry)));
+    }
+
+    let existing_user = repo.user().find_by_username(&entry.localpart).await?;
+    if let Some(user) = existing_user {
+        // The user was not provisioned from this entry. Signing into it would let
+        // whoever controls the directory entry take over the local user, so it
+        // needs to be explicitly allowed, and the user must not be linked to
+        // another entry.
+        let linked_dn = repo.user().ldap_dn(&user).await?;
+        if !adopt_existing_users || linked_dn.is_some() {
+            tracing::warn!(
+                user.id = %user.id,
+                localpart = entry.localpart,
+                dn = entry.dn,
+                linked_dn,
+                "A user with the same username exists, but is not linked to the directory entry"
+            );
+            LDAP_PROVISION_COUNTER.add(1, &[KeyValue::new(RESULT, "conflict")]);
+            return Err(DirectoryLoginError::ProvisioningDenied(
+                "A user with this username already exists".to_owned(),
+            ));
+        }
+
+        repo.user()
+            .add_ldap_link(clock, &user, entry.dn.clone())
+            .await?;
+
+        tracing::info!(
+            user.id = %user.id,
+            user.username = user.username,
+            dn = entry.dn,
+            "Linked an existing user to the directory entry"
+        );
+        LDAP_PROVISION_COUNTER.add(1, &[KeyValue::new(RESULT, "adopted")]);
+
+        return Ok(Some((user, entry)));
+    }
+
+    // This is the first login of this user, so they need to be provisioned.
+    // The directory is authoritative, but the username still has to be
+    // acceptable for the homeserver and the registration policy.
+    let res = policy
+        .evaluate_register(mas_policy::RegisterInput {
+            registration_method: mas_policy::RegistrationMethod::Ldap,
+            username: &entry.localpart,
+            email: entry.email.as_deref(),
+            requester: policy_requester,
+        })
+        .await?;
+
+    // An email address which isn't allowed by the policy is not imported, but
+    // doesn't prevent the login
+    let mut email = entry.email.clone();
+    for violation in &res.violations {
+        if violation.field.as_deref() == Some("email") {
+            tracing::warn!(
+                localpart = entry.localpart,
+                "Not importing the email address of the directory user: {}",
+                violation.msg
+            );
+            email = None;
+        } else {
+            tracing::warn!(
+                localpart = entry.localpart,
+                "Directory user denied by the registration policy: {}",
+                violation.msg
+            );
+            LDAP_PROVISION_COUNTER.add(1, &[KeyValue::new(RESULT, "denied")]);
+            return Err(DirectoryLoginError::ProvisioningDenied(
+                violation.msg.clone(),
+            ));
+        }
+    }
+
+    if !homeserver
+        .is_localpart_available(&entry.localpart)
+        .await
+        .map_err(DirectoryLoginError::Homeserver)?
+    {
+        tracing::warn!(
+            localpart = entry.localpart,
+            "Localpart of the directory user is not available on the homeserver"
+        );
+        LDAP_PROVISION_COUNTER.add(1, &[KeyValue::new(RESULT, "denied")]);
+        return Err(DirectoryLoginError::ProvisioningDenied(
+            "This username is not available".to_owned(),
+        ));
+    }
+
+    // Same as for other registrations, an email address can only belong to one
+    // user. It is not imported if it is already used, but the login goes on.
+    if let Some(address) = &email
+        && repo
+            .user_email()
+            .count(UserEmailFilter::new().for_email(address))
+            .await?
+            > 0
+    {
+        tracing::warn!(
+            localpart = entry.localpart,
+            "Not importing the email address of the directory user, as it is already in use"
+        );
+        email = None;
+    }
+
+    let user = repo
+        .user()
+        .add(&mut *rng, clock, entry.localpart.clone())
+        .await?;
+
+    repo.user()
+        .add_ldap_link(clock, &user, entry.dn.clone())
+        .await?;
+
+    if let Some(email) = email {
+        repo.user_email()
+            .add(&mut *rng, clock, &user, email)
+            .await?;
+    }
+
+    let mut job = ProvisionUserJob::new(&user);
+    if let Some(display_name) = entry.display_name.clone() {
+        job = job.set_display_name(display_name);
+    }
+    repo.queue_job().schedule_job(&mut *rng, clock, job).await?;
+
+    tracing::info!(
+        user.id = %user.id,
+        user.username = user.username,
+        dn = entry.dn,
+        "Provisioned user from the directory"
+    );
+    LDAP_PROVISION_COUNTER.add(1, &[KeyValue::new(RESULT, "success")]);
+
+    Ok(Some((user, entry)))
+}
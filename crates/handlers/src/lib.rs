@@ -39,8 +39,9 @@ use mas_axum_utils::{InternalError, cookies::CookieJar};
 use mas_data_model::SiteConfig;
 use mas_http::CorsLayerExt;
 use mas_keystore::{Encrypter, Keystore};
+use mas_ldap::Directory;
 use mas_matrix::HomeserverConnection;
-use mas_policy::Policy;
+use mas_policy::{Policy, PolicyFactory};
 use mas_router::{Route, UrlBuilder};
 use mas_storage::{BoxRepository, BoxRepositoryFactory};
 use mas_templates::{ErrorContext, NotFoundContext, TemplateContext, Templates};
@@ -64,6 +65,8 @@ mod activity_tracker;
 mod captcha;
 #[cfg(test)]
 mod cleanup_tests;
+mod introspection_cache;
+mod ldap;
 mod preferred_language;
 mod rate_limit;
 mod session;
@@ -104,9 +107,14 @@ pub use self::{
     graphql::{
         Schema as GraphQLSchema, schema as graphql_schema, schema_builder as graphql_schema_builder,
     },
+    introspection_cache::IntrospectionCache,
     preferred_language::PreferredLanguage,
     rate_limit::{Limiter, RequesterFingerprint},
-    upstream_oauth2::cache::MetadataCache,
+    upstream_oauth2::{
+        cache::{ClientCertificateCache, MetadataCache},
+        health::check_provider_health as check_upstream_oauth_provider_health,
+        sync::UpstreamClaimsSyncer,
+    },
 };
 
 pub fn healthcheck_router<S>() -> Router<S>
@@ -199,11 +207,14 @@ pub fn api_router<S>() -> Router<S>
 where
     S: Clone + Send + Sync + 'static,
     Keystore: FromRef<S>,
+    IntrospectionCache: FromRef<S>,
+    MetadataCache: FromRef<S>,
     UrlBuilder: FromRef<S>,
     BoxRepository: FromRequestParts<S>,
     ActivityTracker: FromRequestParts<S>,
     BoundActivityTracker: FromRequestParts<S>,
     Encrypter: FromRef<S>,
+    PasswordManager: FromRef<S>,
     reqwest::Client: FromRef<S>,
     SiteConfig: FromRef<S>,
     Templates: FromRef<S>,
@@ -211,6 +222,7 @@ where
     BoxClock: FromRequestParts<S>,
     BoxRng: FromRequestParts<S>,
     Policy: FromRequestParts<S>,
+    Arc<PolicyFactory>: FromRef<S>,
 {
     // All those routes are API-like, with a common CORS layer
     Router::new()
@@ -267,7 +279,9 @@ where
     SiteConfig: FromRef<S>,
     Arc<dyn HomeserverConnection>: FromRef<S>,
     PasswordManager: FromRef<S>,
+    Option<Arc<dyn Directory>>: FromRef<S>,
     Limiter: FromRef<S>,
+    IntrospectionCache: FromRef<S>,
     BoxRepositoryFactory: FromRef<S>,
     BoundActivityTracker: FromRequestParts<S>,
     RequesterFingerprint: FromRequestParts<S>,
@@ -345,7 +359,9 @@ where
     Templates: FromRef<S>,
     Keystore: FromRef<S>,
     PasswordManager: FromRef<S>,
+    Option<Arc<dyn Directory>>: FromRef<S>,
     MetadataCache: FromRef<S>,
+    ClientCertificateCache: FromRef<S>,
     SiteConfig: FromRef<S>,
     Limiter: FromRef<S>,
     reqwest::Client: FromRef<S>,
@@ -437,6 +453,10 @@ where
             get(self::oauth2::authorization::consent::get)
                 .post(self::oauth2::authorization::consent::post),
         )
+        .route(
+            mas_router::ClientLaunch::route(),
+            get(self::oauth2::launch::get),
+        )
         .route(
             mas_router::CompatLoginSsoComplete::route(),
             get(self::compat::login_sso_complete::get).post(self::compat::login_sso_complete::post),
@@ -445,11 +465,20 @@ where
             mas_router::UpstreamOAuth2Authorize::route(),
             get(self::upstream_oauth2::authorize::get),
         )
+        .route(
+            mas_router::UpstreamOAuth2Initiate::route(),
+            get(self::upstream_oauth2::initiate::handler)
+                .post(self::upstream_oauth2::initiate::handler),
+        )
         .route(
             mas_router::UpstreamOAuth2Callback::route(),
             get(self::upstream_oauth2::callback::handler)
                 .post(self::upstream_oauth2::callback::handler),
         )
+        .route(
+            mas_router::UpstreamOAuth2Jwks::route(),
+            get(self::upstream_oauth2::jwks::get),
+        )
         .route(
             mas_router::UpstreamOAuth2Link::route(),
             get(self::upstream_oauth2::link::get).post(self::upstream_oauth2::link::post),
@@ -458,6 +487,14 @@ where
             mas_router::UpstreamOAuth2BackchannelLogout::route(),
             post(self::upstream_oauth2::backchannel_logout::post),
         )
+        .route(
+            mas_router::UpstreamSaml2Metadata::route(),
+            get(self::upstream_oauth2::saml::metadata),
+        )
+        .route(
+            mas_router::UpstreamSaml2Acs::route(),
+            post(self::upstream_oauth2::saml::acs),
+        )
         .route(
             mas_router::DeviceCodeLink::route(),
             get(self::oauth2::device::link::get),
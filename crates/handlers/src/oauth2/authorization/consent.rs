@@ -33,8 +33,8 @@ use ulid::Ulid;
 
 use super::callback::CallbackDestination;
 use crate::{
-    BoundActivityTracker, PreferredLanguage, impl_from_error_for_route,
-    oauth2::generate_id_token,
+    BoundActivityTracker, MetadataCache, PreferredLanguage, impl_from_error_for_route,
+    oauth2::{encrypt_id_token, generate_id_token},
     session::{SessionOrFallback, count_user_sessions_for_limiting, load_session_or_fallback},
 };
 
@@ -62,6 +62,7 @@ impl_from_error_for_route!(mas_policy::LoadError);
 impl_from_error_for_route!(mas_policy::EvaluationError);
 impl_from_error_for_route!(crate::session::SessionLoadError);
 impl_from_error_for_route!(crate::oauth2::IdTokenSignatureError);
+impl_from_error_for_route!(crate::oauth2::ResponseEncryptionError);
 impl_from_error_for_route!(super::callback::IntoCallbackDestinationError);
 impl_from_error_for_route!(super::callback::CallbackDestinationError);
 
@@ -141,6 +142,10 @@ pub(crate) async fn get(
     let (csrf_token, cookie_jar) = cookie_jar.csrf_token(&clock, &mut rng);
 
     let session_counts = count_user_sessions_for_limiting(&mut repo, &session.user).await?;
+    let user_attributes = repo
+        .upstream_oauth_link()
+        .attributes_for_user(&session.user)
+        .await?;
 
     // We can close the repository early, we don't need it at this point
     repo.save().await?;
@@ -150,6 +155,7 @@ pub(crate) async fn get(
             user: Some(&session.user),
             client: &client,
             session_counts: Some(session_counts),
+            user_attributes: Some(user_attributes),
             scope: &grant.scope,
             grant_type: mas_policy::GrantType::AuthorizationCode,
             requester: mas_policy::Requester {
@@ -220,6 +226,8 @@ pub(crate) async fn post(
     PreferredLanguage(locale): PreferredLanguage,
     State(templates): State<Templates>,
     State(key_store): State<Keystore>,
+    State(http_client): State<reqwest::Client>,
+    State(metadata_cache): State<MetadataCache>,
     mut policy: Policy,
     mut repo: BoxRepository,
     activity_tracker: BoundActivityTracker,
@@ -276,12 +284,17 @@ pub(crate) async fn post(
     }
 
     let session_counts = count_user_sessions_for_limiting(&mut repo, &browser_session.user).await?;
+    let user_attributes = repo
+        .upstream_oauth_link()
+        .attributes_for_user(&browser_session.user)
+        .await?;
 
     let res = policy
         .evaluate_authorization_grant(mas_policy::AuthorizationGrantInput {
             user: Some(&browser_session.user),
             client: &client,
             session_counts: Some(session_counts),
+            user_attributes: Some(user_attributes),
             scope: &grant.scope,
             grant_type: mas_policy::GrantType::AuthorizationCode,
             requester: mas_policy::Requester {
@@ -329,7 +342,7 @@ pub(crate) async fn post(
             .get_last_authentication(&browser_session)
             .await?;
 
-        params.id_token = Some(generate_id_token(
+        let id_token = generate_id_token(
             &mut rng,
             &clock,
             &url_builder,
@@ -339,7 +352,10 @@ pub(crate) async fn post(
             &browser_session,
             None,
             last_authentication.as_ref(),
-        )?);
+        )?;
+        params.id_token = Some(
+            encrypt_id_token(&mut rng, &http_client, &metadata_cache, &client, id_token).await?,
+        );
     }
 
     // Did they request an auth code?
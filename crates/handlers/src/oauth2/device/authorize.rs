@@ -109,7 +109,7 @@ pub(crate) async fn post(
 
     client_authorization
         .credentials
-        .verify(&http_client, &encrypter, method, &client)
+        .verify(&http_client, &encrypter, clock.now(), method, &client)
         .await
         .map_err(|err| {
             if err.is_internal() {
@@ -108,6 +108,10 @@ pub(crate) async fn get(
         .map_err(InternalError::from_anyhow)?;
 
     let session_counts = count_user_sessions_for_limiting(&mut repo, &session.user).await?;
+    let user_attributes = repo
+        .upstream_oauth_link()
+        .attributes_for_user(&session.user)
+        .await?;
 
     // We can close the repository early, we don't need it at this point
     repo.save().await?;
@@ -118,6 +122,7 @@ pub(crate) async fn get(
             grant_type: mas_policy::GrantType::DeviceCode,
             client: &client,
             session_counts: Some(session_counts),
+            user_attributes: Some(user_attributes),
             scope: &grant.scope,
             user: Some(&session.user),
             requester: mas_policy::Requester {
@@ -247,6 +252,10 @@ pub(crate) async fn post(
         .map_err(InternalError::from_anyhow)?;
 
     let session_counts = count_user_sessions_for_limiting(&mut repo, &session.user).await?;
+    let user_attributes = repo
+        .upstream_oauth_link()
+        .attributes_for_user(&session.user)
+        .await?;
 
     // Evaluate the policy
     let res = policy
@@ -254,6 +263,7 @@ pub(crate) async fn post(
             grant_type: mas_policy::GrantType::DeviceCode,
             client: &client,
             session_counts: Some(session_counts),
+            user_attributes: Some(user_attributes),
             scope: &grant.scope,
             user: Some(&session.user),
             requester: mas_policy::Requester {
@@ -9,7 +9,10 @@ use mas_iana::oauth::{
     OAuthAuthorizationEndpointResponseType, OAuthClientAuthenticationMethod,
     PkceCodeChallengeMethod,
 };
-use mas_jose::jwa::SUPPORTED_SIGNING_ALGORITHMS;
+use mas_jose::{
+    jwa::SUPPORTED_SIGNING_ALGORITHMS,
+    jwe::{SUPPORTED_CONTENT_ENCRYPTION_ALGORITHMS, SUPPORTED_ENCRYPTION_ALGORITHMS},
+};
 use mas_keystore::Keystore;
 use mas_router::UrlBuilder;
 use oauth2_types::{
@@ -19,6 +22,7 @@ use oauth2_types::{
 };
 use serde::Serialize;
 
+use super::default_signing_algorithm;
 use crate::SiteConfig;
 
 #[derive(Debug, Serialize)]
@@ -100,6 +104,10 @@ pub(crate) async fn get(
     let introspection_endpoint_auth_signing_alg_values_supported =
         client_auth_signing_alg_values_supported;
 
+    // Signed introspection responses are always signed with the same algorithm
+    let introspection_signing_alg_values_supported =
+        default_signing_algorithm(&key_store).map(|alg| vec![alg]);
+
     let code_challenge_methods_supported = Some(vec![
         PkceCodeChallengeMethod::Plain,
         PkceCodeChallengeMethod::S256,
@@ -110,6 +118,13 @@ pub(crate) async fn get(
     let id_token_signing_alg_values_supported = jwt_signing_alg_values_supported.clone();
     let userinfo_signing_alg_values_supported = jwt_signing_alg_values_supported;
 
+    // This is how we can encrypt stuff for clients
+    let id_token_encryption_alg_values_supported = Some(SUPPORTED_ENCRYPTION_ALGORITHMS.to_vec());
+    let id_token_encryption_enc_values_supported =
+        Some(SUPPORTED_CONTENT_ENCRYPTION_ALGORITHMS.to_vec());
+    let userinfo_encryption_alg_values_supported = id_token_encryption_alg_values_supported.clone();
+    let userinfo_encryption_enc_values_supported = id_token_encryption_enc_values_supported.clone();
+
     let display_values_supported = Some(vec![Display::Page]);
 
     let claim_types_supported = Some(vec![ClaimType::Normal]);
@@ -159,11 +174,16 @@ pub(crate) async fn get(
         introspection_endpoint,
         introspection_endpoint_auth_methods_supported,
         introspection_endpoint_auth_signing_alg_values_supported,
+        introspection_signing_alg_values_supported,
         code_challenge_methods_supported,
         userinfo_endpoint,
         subject_types_supported,
         id_token_signing_alg_values_supported,
         userinfo_signing_alg_values_supported,
+        id_token_encryption_alg_values_supported,
+        id_token_encryption_enc_values_supported,
+        userinfo_encryption_alg_values_supported,
+        userinfo_encryption_enc_values_supported,
         display_values_supported,
         claim_types_supported,
         claims_supported,
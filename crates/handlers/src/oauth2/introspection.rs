@@ -9,21 +9,40 @@ use std::{
     sync::{Arc, LazyLock},
 };
 
-use axum::{Json, extract::State, http::HeaderValue, response::IntoResponse};
-use hyper::{HeaderMap, StatusCode};
+use axum::{
+    Json,
+    extract::State,
+    http::HeaderValue,
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, Utc};
+use hyper::{
+    HeaderMap, StatusCode,
+    header::{ACCEPT, CONTENT_TYPE},
+};
 use mas_axum_utils::{
     client_authorization::{ClientAuthorization, CredentialsVerificationError},
     record_error,
 };
 use mas_data_model::{
-    BoxClock, Clock, Device, TokenFormatError, TokenType, personal::session::PersonalSessionOwner,
+    BoxClock, BoxRng, Clock, CompatSession, Device, SiteConfig, TokenFormatError, TokenType,
+    personal::session::PersonalSessionOwner,
 };
 use mas_iana::oauth::{OAuthClientAuthenticationMethod, OAuthTokenTypeHint};
-use mas_keystore::Encrypter;
+use mas_jose::{
+    constraints::Constrainable,
+    jwt::{JsonWebSignatureHeader, Jwt},
+};
+use mas_keystore::{Encrypter, Keystore};
 use mas_matrix::HomeserverConnection;
+use mas_policy::{CompatTokenAnomalyInput, CompatTokenAnomalySession, PolicyFactory, Requester};
+use mas_router::UrlBuilder;
 use mas_storage::{
-    BoxRepository,
-    compat::{CompatAccessTokenRepository, CompatRefreshTokenRepository, CompatSessionRepository},
+    BoxRepository, Pagination,
+    compat::{
+        CompatAccessTokenRepository, CompatRefreshTokenRepository, CompatSessionAnomalyFilter,
+        CompatSessionAnomalyRepository, CompatSessionRepository,
+    },
     oauth2::{OAuth2AccessTokenRepository, OAuth2RefreshTokenRepository, OAuth2SessionRepository},
     user::UserRepository,
 };
@@ -33,10 +52,18 @@ use oauth2_types::{
     scope::{Scope, ScopeToken},
 };
 use opentelemetry::{Key, KeyValue, metrics::Counter};
+use serde::Serialize;
+use serde_with::{TimestampSeconds, serde_as, skip_serializing_none};
 use thiserror::Error;
 use ulid::Ulid;
 
-use crate::{ActivityTracker, METER, impl_from_error_for_route};
+use super::default_signing_algorithm;
+use crate::{
+    ActivityTracker, IntrospectionCache, METER,
+    compat::anomaly::{self, TokenRequester},
+    impl_from_error_for_route,
+    introspection_cache::CachedSession,
+};
 
 static INTROSPECTION_COUNTER: LazyLock<Counter<u64>> = LazyLock::new(|| {
     METER
@@ -46,8 +73,51 @@ static INTROSPECTION_COUNTER: LazyLock<Counter<u64>> = LazyLock::new(|| {
         .build()
 });
 
+static COMPAT_TOKEN_ANOMALY_COUNTER: LazyLock<Counter<u64>> = LazyLock::new(|| {
+    METER
+        .u64_counter("mas.compat.token_anomaly")
+        .with_description(
+            "Number of compatibility access tokens used from an unusual network or client",
+        )
+        .with_unit("{anomaly}")
+        .build()
+});
+
 const KIND: Key = Key::from_static_str("kind");
 const ACTIVE: Key = Key::from_static_str("active");
+const CACHED: Key = Key::from_static_str("cached");
+const REJECTED: Key = Key::from_static_str("rejected");
+
+/// The media type of signed introspection responses, as per RFC 9701
+const TOKEN_INTROSPECTION_JWT: &str = "application/token-introspection+jwt";
+
+/// The claims of a signed introspection response
+#[serde_as]
+#[skip_serializing_none]
+#[derive(Serialize)]
+struct SignedIntrospectionResponse {
+    iss: String,
+    aud: Option<String>,
+    #[serde_as(as = "TimestampSeconds")]
+    iat: DateTime<Utc>,
+    token_introspection: IntrospectionResponse,
+}
+
+/// Whether the client asked for a signed introspection response through the
+/// `Accept` header
+fn accepts_jwt(headers: &HeaderMap) -> bool {
+    headers
+        .get_all(ACCEPT)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .filter_map(|media_type| media_type.split(';').next())
+        .any(|media_type| {
+            media_type
+                .trim()
+                .eq_ignore_ascii_case(TOKEN_INTROSPECTION_JWT)
+        })
+}
 
 #[derive(Debug, Error)]
 pub enum RouteError {
@@ -127,6 +197,40 @@ pub enum RouteError {
 
     #[error("bearer token presented is invalid")]
     InvalidBearerToken,
+
+    #[error("no suitable key found for signing")]
+    InvalidSigningKey,
+
+    /// The policy rejected an unusual use of a compat access token
+    #[error("unusual use of a token of compat session {0} was rejected")]
+    CompatTokenRejected(Ulid),
+
+    /// Anomaly detection is enabled, but the homeserver didn't tell which
+    /// client is using the token
+    #[error(
+        "the X-MAS-Client-IP header is required, as compatibility token anomaly detection is enabled"
+    )]
+    MissingClientIp,
+}
+
+impl RouteError {
+    /// Whether this error means that the token is inactive, rather than that
+    /// the request itself failed
+    fn is_inactive(&self) -> bool {
+        matches!(
+            self,
+            Self::UnknownToken(_)
+                | Self::UnexpectedTokenType
+                | Self::InvalidToken(_)
+                | Self::InvalidUser(_)
+                | Self::InvalidCompatSession(_)
+                | Self::InvalidOAuthSession(_)
+                | Self::InvalidPersonalSession(_)
+                | Self::InvalidTokenFormat(_)
+                | Self::CantEncodeDeviceID(_)
+                | Self::CompatTokenRejected(_)
+        )
+    }
 }
 
 impl IntoResponse for RouteError {
@@ -138,6 +242,8 @@ impl IntoResponse for RouteError {
                 | Self::CantLoadOAuthSession(_)
                 | Self::CantLoadUser(_)
                 | Self::FailedToVerifyToken(_)
+                | Self::InvalidSigningKey
+                | Self::MissingClientIp
         );
 
         let response = match self {
@@ -147,7 +253,8 @@ impl IntoResponse for RouteError {
             | Self::CantLoadPersonalSession(_)
             | Self::CantLoadUser(_)
             | Self::CantLoadOAuth2Client(_)
-            | Self::FailedToVerifyToken(_)) => (
+            | Self::FailedToVerifyToken(_)
+            | Self::InvalidSigningKey) => (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 Json(
                     ClientError::from(ClientErrorCode::ServerError).with_description(e.to_string()),
@@ -184,7 +291,8 @@ impl IntoResponse for RouteError {
             | Self::InvalidOAuthSession(_)
             | Self::InvalidPersonalSession(_)
             | Self::InvalidTokenFormat(_)
-            | Self::CantEncodeDeviceID(_) => {
+            | Self::CantEncodeDeviceID(_)
+            | Self::CompatTokenRejected(_) => {
                 INTROSPECTION_COUNTER.add(1, &[KeyValue::new(ACTIVE.clone(), false)]);
 
                 Json(INACTIVE).into_response()
@@ -196,6 +304,15 @@ impl IntoResponse for RouteError {
             )
                 .into_response(),
 
+            e @ Self::MissingClientIp => (
+                StatusCode::BAD_REQUEST,
+                Json(
+                    ClientError::from(ClientErrorCode::InvalidRequest)
+                        .with_description(e.to_string()),
+                ),
+            )
+                .into_response(),
+
             Self::BadRequest => (
                 StatusCode::BAD_REQUEST,
                 Json(ClientError::from(ClientErrorCode::InvalidRequest)),
@@ -208,6 +325,10 @@ impl IntoResponse for RouteError {
 }
 
 impl_from_error_for_route!(mas_storage::RepositoryError);
+impl_from_error_for_route!(mas_keystore::WrongAlgorithmError);
+impl_from_error_for_route!(mas_jose::jwt::JwtSignatureError);
+impl_from_error_for_route!(mas_policy::InstantiateError);
+impl_from_error_for_route!(mas_policy::EvaluationError);
 
 const INACTIVE: IntrospectionResponse = IntrospectionResponse {
     active: false,
@@ -259,16 +380,26 @@ fn normalize_scope(mut scope: Scope) -> Scope {
     skip_all,
 )]
 pub(crate) async fn post(
+    mut rng: BoxRng,
     clock: BoxClock,
     State(http_client): State<reqwest::Client>,
     mut repo: BoxRepository,
     activity_tracker: ActivityTracker,
     State(encrypter): State<Encrypter>,
     State(homeserver): State<Arc<dyn HomeserverConnection>>,
+    State(key_store): State<Keystore>,
+    State(url_builder): State<UrlBuilder>,
+    State(introspection_cache): State<IntrospectionCache>,
+    State(policy_factory): State<Arc<PolicyFactory>>,
+    State(site_config): State<SiteConfig>,
     headers: HeaderMap,
     ClientAuthorization { credentials, form }: ClientAuthorization<IntrospectionRequest>,
-) -> Result<impl IntoResponse, RouteError> {
-    if let Some(token) = credentials.bearer_token() {
+) -> Result<Response, RouteError> {
+    let wants_jwt = accepts_jwt(&headers);
+
+    // The audience of signed responses, which is the introspecting client if it
+    // authenticated with client credentials
+    let audience = if let Some(token) = credentials.bearer_token() {
         // If the client presented a bearer token, we check with the homeserver
         // configuration if it is allowed to use the introspection endpoint
         if !homeserver
@@ -278,6 +409,8 @@ pub(crate) async fn post(
         {
             return Err(RouteError::InvalidBearerToken);
         }
+
+        None
     } else {
         // Otherwise, it presented regular client credentials, so we verify them
         let client = credentials
@@ -294,18 +427,106 @@ pub(crate) async fn post(
         };
 
         credentials
-            .verify(&http_client, &encrypter, method, &client)
+            .verify(&http_client, &encrypter, clock.now(), method, &client)
             .await?;
-    }
+
+        Some(client.client_id)
+    };
 
     let Some(form) = form else {
         return Err(RouteError::BadRequest);
     };
 
+    let reply = match introspect_token(
+        &mut rng,
+        &clock,
+        &mut repo,
+        &activity_tracker,
+        &introspection_cache,
+        &policy_factory,
+        &site_config,
+        &headers,
+        &form,
+    )
+    .await
+    {
+        Ok(reply) => {
+            repo.save().await?;
+            reply
+        }
+
+        // The anomaly which got the token rejected still needs to be recorded
+        Err(e @ RouteError::CompatTokenRejected(_)) => {
+            repo.save().await?;
+
+            if !wants_jwt {
+                return Err(e);
+            }
+
+            INTROSPECTION_COUNTER.add(1, &[KeyValue::new(ACTIVE.clone(), false)]);
+            INACTIVE
+        }
+
+        // Inactive tokens must also be reported as signed JWTs
+        Err(e) if wants_jwt && e.is_inactive() => {
+            INTROSPECTION_COUNTER.add(1, &[KeyValue::new(ACTIVE.clone(), false)]);
+            INACTIVE
+        }
+
+        Err(e) => return Err(e),
+    };
+
+    if !wants_jwt {
+        return Ok(Json(reply).into_response());
+    }
+
+    // Sign the response as per RFC 9701. Clients can't register the algorithm
+    // they expect, so use the same one as for JWT access tokens
+    let alg = default_signing_algorithm(&key_store).ok_or(RouteError::InvalidSigningKey)?;
+    let key = key_store
+        .signing_key_for_algorithm(&alg)
+        .ok_or(RouteError::InvalidSigningKey)?;
+    let signer = key.params().signing_key_for_alg(&alg)?;
+    let header = JsonWebSignatureHeader::new(alg)
+        .with_kid(key.kid().ok_or(RouteError::InvalidSigningKey)?)
+        .with_typ("token-introspection+jwt".to_owned());
+
+    let claims = SignedIntrospectionResponse {
+        iss: url_builder.oidc_issuer().to_string(),
+        aud: audience,
+        iat: clock.now(),
+        token_introspection: reply,
+    };
+
+    let token = Jwt::sign_with_rng(&mut rng, header, claims, &signer)?;
+
+    Ok((
+        [(
+            CONTENT_TYPE,
+            HeaderValue::from_static(TOKEN_INTROSPECTION_JWT),
+        )],
+        token.into_string(),
+    )
+        .into_response())
+}
+
+/// Introspect the token from the request, looking it up in the database or in
+/// the introspection cache
+async fn introspect_token(
+    rng: &mut BoxRng,
+    clock: &dyn Clock,
+    repo: &mut BoxRepository,
+    activity_tracker: &ActivityTracker,
+    introspection_cache: &IntrospectionCache,
+    policy_factory: &PolicyFactory,
+    site_config: &SiteConfig,
+    headers: &HeaderMap,
+    form: &IntrospectionRequest,
+) -> Result<IntrospectionResponse, RouteError> {
     let token = &form.token;
     let token_type = TokenType::check(token)?;
-    if let Some(hint) = form.token_type_hint
-        && token_type != hint
+    if let Some(hint) = &form.token_type_hint
+        && token_type != *hint
     {
         return Err(RouteError::UnexpectedTokenType);
     }
@@ -320,8 +541,58 @@ pub(crate) async fn post(
     let supports_explicit_device_id =
         headers.get("X-MAS-Supports-Device-Id") == Some(&HeaderValue::from_static("1"));
 
-    // XXX: we should get the IP from the client introspecting the token
-    let ip = None;
+    // The homeserver may tell us which client is using the token, which is the
+    // activity we want to record, rather than the one of the homeserver itself
+    let requester = TokenRequester::from_headers(headers);
+    let ip = requester.ip_address;
+
+    // Positive results for access tokens may have been cached
+    if matches!(
+        token_type,
+        TokenType::AccessToken | TokenType::CompatAccessToken
+    ) && let Some((reply, session)) = introspection_cache
+        .get(clock.now(), token, supports_explicit_device_id)
+        .await
+    {
+        if let CachedSession::Compat(session) = &session {
+            check_compat_token_anomalies(
+                rng,
+                clock,
+                repo,
+                policy_factory,
+                site_config,
+                &requester,
+                session,
+            )
+            .await?;
+        }
+
+        let kind = match &session {
+            CachedSession::OAuth2(session) => {
+                activity_tracker
+                    .record_oauth2_session(clock, session, ip)
+                    .await;
+                "oauth2_access_token"
+            }
+            CachedSession::Compat(session) => {
+                activity_tracker
+                    .record_compat_session(clock, session, ip)
+                    .await;
+                "compat_access_token"
+            }
+        };
+
+        INTROSPECTION_COUNTER.add(
+            1,
+            &[
+                KeyValue::new(KIND, kind),
+                KeyValue::new(ACTIVE, true),
+                KeyValue::new(CACHED, true),
+            ],
+        );
+
+        return Ok(reply);
+    }
 
     let reply = match token_type {
         TokenType::AccessToken => {
@@ -349,7 +620,7 @@ pub(crate) async fn post(
             if !access_token.is_used() {
                 access_token = repo
                     .oauth2_access_token()
-                    .mark_used(&clock, access_token)
+                    .mark_used(clock, access_token)
                     .await?;
             }
 
@@ -372,7 +643,7 @@ pub(crate) async fn post(
             };
 
             activity_tracker
-                .record_oauth2_session(&clock, &session, ip)
+                .record_oauth2_session(clock, &session, ip)
                 .await;
 
             INTROSPECTION_COUNTER.add(
@@ -383,9 +654,9 @@ pub(crate) async fn post(
                 ],
             );
 
-            let scope = normalize_scope(session.scope);
+            let scope = normalize_scope(session.scope.clone());
 
-            IntrospectionResponse {
+            let reply = IntrospectionResponse {
                 active: true,
                 scope: Some(scope),
                 client_id: Some(session.client_id.to_string()),
@@ -402,7 +673,19 @@ pub(crate) async fn post(
                 iss: None,
                 jti: Some(access_token.jti()),
                 device_id: None,
-            }
+            };
+
+            introspection_cache
+                .insert(
+                    clock.now(),
+                    token,
+                    supports_explicit_device_id,
+                    &reply,
+                    CachedSession::OAuth2(session),
+                )
+                .await;
+
+            reply
         }
 
         TokenType::RefreshToken => {
@@ -445,7 +728,7 @@ pub(crate) async fn post(
             };
 
             activity_tracker
-                .record_oauth2_session(&clock, &session, ip)
+                .record_oauth2_session(clock, &session, ip)
                 .await;
 
             INTROSPECTION_COUNTER.add(
@@ -477,7 +760,7 @@ pub(crate) async fn post(
         }
 
         TokenType::CompatAccessToken => {
-            let access_token = repo
+            let mut access_token = repo
                 .compat_access_token()
                 .find_by_token(token)
                 .await?
@@ -507,6 +790,25 @@ pub(crate) async fn post(
                 return Err(RouteError::InvalidUser(user.id))?;
             }
 
+            // If this is the first time we're using this token, mark it as used
+            if !access_token.is_used() {
+                access_token = repo
+                    .compat_access_token()
+                    .mark_used(clock, access_token)
+                    .await?;
+            }
+
+            check_compat_token_anomalies(
+                rng,
+                clock,
+                repo,
+                policy_factory,
+                site_config,
+                &requester,
+                &session,
+            )
+            .await?;
+
             // Grant the synapse admin scope if the session has the admin flag set.
             let synapse_admin_scope_opt = session.is_synapse_admin.then_some(SYNAPSE_ADMIN_SCOPE);
 
@@ -529,7 +831,7 @@ pub(crate) async fn post(
                 .collect();
 
             activity_tracker
-                .record_compat_session(&clock, &session, ip)
+                .record_compat_session(clock, &session, ip)
                 .await;
 
             INTROSPECTION_COUNTER.add(
@@ -540,7 +842,7 @@ pub(crate) async fn post(
                 ],
             );
 
-            IntrospectionResponse {
+            let reply = IntrospectionResponse {
                 active: true,
                 scope: Some(scope),
                 client_id: Some("legacy".into()),
@@ -556,8 +858,20 @@ pub(crate) async fn post(
                 aud: None,
                 iss: None,
                 jti: None,
-                device_id: session.device.map(Device::into),
-            }
+                device_id: session.device.clone().map(Device::into),
+            };
+
+            introspection_cache
+                .insert(
+                    clock.now(),
+                    token,
+                    supports_explicit_device_id,
+                    &reply,
+                    CachedSession::Compat(session),
+                )
+                .await;
+
+            reply
         }
 
         TokenType::CompatRefreshToken => {
@@ -613,7 +927,7 @@ pub(crate) async fn post(
                 .collect();
 
             activity_tracker
-                .record_compat_session(&clock, &session, ip)
+                .record_compat_session(clock, &session, ip)
                 .await;
 
             INTROSPECTION_COUNTER.add(
@@ -700,7 +1014,7 @@ pub(crate) async fn post(
             };
 
             activity_tracker
-                .record_personal_session(&clock, &session, ip)
+                .record_personal_session(clock, &session, ip)
                 .await;
 
             INTROSPECTION_COUNTER.add(
@@ -734,21 +1048,125 @@ pub(crate) async fn post(
         }
     };
 
-    repo.save().await?;
+    Ok(reply)
+}
+
+/// Look for anomalies in the use of a compatibility access token, record them,
+/// and let the policy decide whether the token should be rejected
+async fn check_compat_token_anomalies(
+    rng: &mut BoxRng,
+    clock: &dyn Clock,
+    repo: &mut BoxRepository,
+    policy_factory: &PolicyFactory,
+    site_config: &SiteConfig,
+    requester: &TokenRequester,
+    session: &CompatSession,
+) -> Result<(), RouteError> {
+    let Some(config) = site_config.compat_token_anomaly_detection else {
+        return Ok(());
+    };
+
+    // Nothing could ever be detected without the address of the client, so
+    // refuse the request instead of silently letting every token through
+    if requester.ip_address.is_none() {
+        return Err(RouteError::MissingClientIp);
+    }
+
+    let anomalies = anomaly::detect(config, session, requester);
+    if anomalies.is_empty() {
+        return Ok(());
+    }
+
+    let user = repo
+        .user()
+        .lookup(session.user_id)
+        .await?
+        .ok_or(RouteError::CantLoadUser(session.user_id))?;
+
+    // The policy is only instantiated when something unusual was detected, to
+    // keep the common path cheap
+    let mut policy = policy_factory.instantiate().await?;
+    let res = policy
+        .evaluate_compat_token_anomaly(CompatTokenAnomalyInput {
+            user: &user,
+            session: CompatTokenAnomalySession {
+                id: session.id,
+                device_id: session.device.as_ref().map(Device::as_str),
+                last_active_ip: session.last_active_ip,
+                user_agent: session.user_agent.as_deref(),
+            },
+            anomalies: &anomalies,
+            requester: Requester {
+                ip_address: requester.ip_address,
+                user_agent: requester.user_agent.clone(),
+            },
+        })
+        .await?;
+    let rejected = !res.valid();
+
+    COMPAT_TOKEN_ANOMALY_COUNTER.add(1, &[KeyValue::new(REJECTED, rejected)]);
+    tracing::warn!(
+        compat_session.id = %session.id,
+        user.id = %session.user_id,
+        ?anomalies,
+        rejected,
+        "Compatibility access token used from an unusual network or client"
+    );
+
+    // Don't record the same anomaly every time the token is introspected
+    let latest = repo
+        .compat_session_anomaly()
+        .list(
+            CompatSessionAnomalyFilter::new().for_compat_session(session),
+            Pagination::last(1),
+        )
+        .await?;
+    let already_recorded = latest.edges.first().is_some_and(|edge| {
+        edge.node.kinds == anomalies
+            && edge.node.ip_address == requester.ip_address
+            && edge.node.user_agent == requester.user_agent
+            && edge.node.rejected == rejected
+    });
+
+    if !already_recorded {
+        repo.compat_session_anomaly()
+            .add(
+                rng,
+                clock,
+                session,
+                anomalies,
+                requester.ip_address,
+                requester.user_agent.clone(),
+                rejected,
+            )
+            .await?;
+    }
+
+    if rejected {
+        return Err(RouteError::CompatTokenRejected(session.id));
+    }
 
-    Ok(Json(reply))
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use chrono::Duration;
-    use hyper::{Request, StatusCode};
+    use hyper::{
+        Request, StatusCode,
+        header::{ACCEPT, CONTENT_TYPE},
+    };
     use mas_data_model::{
-        AccessToken, Clock, RefreshToken, TokenType, personal::session::PersonalSessionOwner,
+        AccessToken, Clock, CompatSessionAnomalyKind, CompatTokenAnomalyDetectionConfig, Device,
+        RefreshToken, SiteConfig, TokenType, personal::session::PersonalSessionOwner,
     };
     use mas_iana::oauth::OAuthTokenTypeHint;
+    use mas_jose::jwt::Jwt;
     use mas_matrix::{HomeserverConnection, MockHomeserverConnection, ProvisionRequest};
-    use mas_router::{OAuth2Introspection, OAuth2RegistrationEndpoint, SimpleRoute};
+    use mas_router::{
+        OAuth2Introspection, OAuth2RegistrationEndpoint, OAuth2Revocation, SimpleRoute,
+    };
+    use mas_storage::{Pagination, compat::CompatSessionAnomalyFilter};
     use oauth2_types::{
         errors::{ClientError, ClientErrorCode},
         registration::ClientRegistrationResponse,
@@ -757,11 +1175,13 @@ mod tests {
     };
     use serde_json::json;
     use sqlx::PgPool;
+    use ulid::Ulid;
     use zeroize::Zeroizing;
 
     use crate::{
+        IntrospectionCache,
         oauth2::generate_token_pair,
-        test_utils::{RequestBuilderExt, ResponseExt, TestState, setup},
+        test_utils::{RequestBuilderExt, ResponseExt, TestState, setup, test_site_config},
     };
 
     #[sqlx::test(migrator = "mas_storage_pg::MIGRATOR")]
@@ -840,6 +1260,8 @@ mod tests {
                 &state.clock,
                 &mut repo,
                 &session,
+                Ulid::from_datetime_with_source(state.clock.now().into(), &mut state.rng()),
+                TokenType::AccessToken.generate(&mut state.rng()),
                 Duration::microseconds(5 * 60 * 1000 * 1000),
             )
             .await
@@ -1299,4 +1721,493 @@ mod tests {
         assert_eq!(session.last_active_at, Some(last_active));
         repo.save().await.unwrap();
     }
+
+    #[sqlx::test(migrator = "mas_storage_pg::MIGRATOR")]
+    async fn test_introspect_jwt_response(pool: PgPool) {
+        setup();
+        let state = TestState::from_pool(pool).await.unwrap();
+
+        // Provision a client which will be used to do introspection requests
+        let request = Request::post(OAuth2RegistrationEndpoint::PATH).json(json!({
+            "client_uri": "https://introspecting.com/",
+            "grant_types": [],
+            "token_endpoint_auth_method": "client_secret_basic",
+        }));
+
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::CREATED);
+        let client: ClientRegistrationResponse = response.json();
+        let introspecting_client_id = client.client_id;
+        let introspecting_client_secret = client.client_secret.unwrap();
+
+        // Provision a client which will be used to generate tokens
+        let request = Request::post(OAuth2RegistrationEndpoint::PATH).json(json!({
+            "client_uri": "https://client.com/",
+            "redirect_uris": ["https://client.com/"],
+            "response_types": ["code"],
+            "grant_types": ["authorization_code", "refresh_token"],
+            "token_endpoint_auth_method": "none",
+        }));
+
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::CREATED);
+        let ClientRegistrationResponse { client_id, .. } = response.json();
+
+        let mut repo = state.repository().await.unwrap();
+        let user = repo
+            .user()
+            .add(&mut state.rng(), &state.clock, "alice".to_owned())
+            .await
+            .unwrap();
+
+        let client = repo
+            .oauth2_client()
+            .find_by_client_id(&client_id)
+            .await
+            .unwrap()
+            .unwrap();
+
+        let browser_session = repo
+            .browser_session()
+            .add(&mut state.rng(), &state.clock, &user, None)
+            .await
+            .unwrap();
+
+        let session = repo
+            .oauth2_session()
+            .add_from_browser_session(
+                &mut state.rng(),
+                &state.clock,
+                &client,
+                &browser_session,
+                Scope::from_iter([OPENID]),
+            )
+            .await
+            .unwrap();
+
+        let (AccessToken { access_token, .. }, _) = generate_token_pair(
+            &mut state.rng(),
+            &state.clock,
+            &mut repo,
+            &session,
+            Ulid::from_datetime_with_source(state.clock.now().into(), &mut state.rng()),
+            TokenType::AccessToken.generate(&mut state.rng()),
+            Duration::microseconds(5 * 60 * 1000 * 1000),
+        )
+        .await
+        .unwrap();
+
+        repo.save().await.unwrap();
+
+        // Ask for a signed response
+        let request = Request::post(OAuth2Introspection::PATH)
+            .basic_auth(&introspecting_client_id, &introspecting_client_secret)
+            .header(ACCEPT, "application/token-introspection+jwt")
+            .form(json!({ "token": access_token }));
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::OK);
+        response.assert_header_value(CONTENT_TYPE, "application/token-introspection+jwt");
+
+        let jwt: Jwt<'_, serde_json::Value> = response.body().as_str().try_into().unwrap();
+        jwt.verify_with_jwks(&state.key_store.public_jwks())
+            .unwrap();
+        assert_eq!(jwt.header().typ(), Some("token-introspection+jwt"));
+        let claims = jwt.payload();
+        assert_eq!(claims["iss"], state.url_builder.oidc_issuer().as_str());
+        assert_eq!(claims["aud"], introspecting_client_id.as_str());
+        assert!(claims["iat"].is_number());
+        let token_introspection: IntrospectionResponse =
+            serde_json::from_value(claims["token_introspection"].clone()).unwrap();
+        assert!(token_introspection.active);
+        assert_eq!(token_introspection.username, Some("alice".to_owned()));
+        assert_eq!(token_introspection.client_id, Some(client_id));
+
+        // Inactive tokens are also reported in a signed response
+        let request = Request::post(OAuth2Introspection::PATH)
+            .basic_auth(&introspecting_client_id, &introspecting_client_secret)
+            .header(
+                ACCEPT,
+                "application/json, application/token-introspection+jwt",
+            )
+            .form(json!({ "token": "mat_unknownunknownunknownunknown_000000" }));
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::OK);
+        response.assert_header_value(CONTENT_TYPE, "application/token-introspection+jwt");
+
+        let jwt: Jwt<'_, serde_json::Value> = response.body().as_str().try_into().unwrap();
+        jwt.verify_with_jwks(&state.key_store.public_jwks())
+            .unwrap();
+        assert_eq!(
+            jwt.payload()["token_introspection"],
+            json!({ "active": false })
+        );
+    }
+
+    #[sqlx::test(migrator = "mas_storage_pg::MIGRATOR")]
+    async fn test_introspection_cache(pool: PgPool) {
+        setup();
+        let mut state = TestState::from_pool(pool).await.unwrap();
+        state.introspection_cache =
+            IntrospectionCache::new(Some(Duration::try_seconds(30).unwrap()));
+
+        // Provision a client which will be used to do introspection requests
+        let request = Request::post(OAuth2RegistrationEndpoint::PATH).json(json!({
+            "client_uri": "https://introspecting.com/",
+            "grant_types": [],
+            "token_endpoint_auth_method": "client_secret_basic",
+        }));
+
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::CREATED);
+        let client: ClientRegistrationResponse = response.json();
+        let introspecting_client_id = client.client_id;
+        let introspecting_client_secret = client.client_secret.unwrap();
+
+        // Provision a client which will be used to generate tokens
+        let request = Request::post(OAuth2RegistrationEndpoint::PATH).json(json!({
+            "client_uri": "https://client.com/",
+            "redirect_uris": ["https://client.com/"],
+            "response_types": ["code"],
+            "grant_types": ["authorization_code", "refresh_token"],
+            "token_endpoint_auth_method": "none",
+        }));
+
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::CREATED);
+        let ClientRegistrationResponse { client_id, .. } = response.json();
+
+        let mut repo = state.repository().await.unwrap();
+        let user = repo
+            .user()
+            .add(&mut state.rng(), &state.clock, "alice".to_owned())
+            .await
+            .unwrap();
+
+        let client = repo
+            .oauth2_client()
+            .find_by_client_id(&client_id)
+            .await
+            .unwrap()
+            .unwrap();
+
+        let browser_session = repo
+            .browser_session()
+            .add(&mut state.rng(), &state.clock, &user, None)
+            .await
+            .unwrap();
+
+        let session = repo
+            .oauth2_session()
+            .add_from_browser_session(
+                &mut state.rng(),
+                &state.clock,
+                &client,
+                &browser_session,
+                Scope::from_iter([OPENID]),
+            )
+            .await
+            .unwrap();
+
+        let (first_access_token, _) = generate_token_pair(
+            &mut state.rng(),
+            &state.clock,
+            &mut repo,
+            &session,
+            Ulid::from_datetime_with_source(state.clock.now().into(), &mut state.rng()),
+            TokenType::AccessToken.generate(&mut state.rng()),
+            Duration::microseconds(5 * 60 * 1000 * 1000),
+        )
+        .await
+        .unwrap();
+
+        let (
+            AccessToken {
+                access_token: second_access_token,
+                ..
+            },
+            RefreshToken {
+                refresh_token: second_refresh_token,
+                ..
+            },
+        ) = generate_token_pair(
+            &mut state.rng(),
+            &state.clock,
+            &mut repo,
+            &session,
+            Ulid::from_datetime_with_source(state.clock.now().into(), &mut state.rng()),
+            TokenType::AccessToken.generate(&mut state.rng()),
+            Duration::microseconds(5 * 60 * 1000 * 1000),
+        )
+        .await
+        .unwrap();
+
+        repo.save().await.unwrap();
+
+        let introspect = async |token: &str| -> IntrospectionResponse {
+            let request = Request::post(OAuth2Introspection::PATH)
+                .basic_auth(&introspecting_client_id, &introspecting_client_secret)
+                .form(json!({ "token": token }));
+            let response = state.request(request).await;
+            response.assert_status(StatusCode::OK);
+            response.json()
+        };
+
+        let first_access_token_str = first_access_token.access_token.clone();
+        assert!(introspect(&first_access_token_str).await.active);
+        assert!(introspect(&second_access_token).await.active);
+
+        // Revoke the first access token behind the back of the cache
+        let mut repo = state.repository().await.unwrap();
+        repo.oauth2_access_token()
+            .revoke(&state.clock, first_access_token)
+            .await
+            .unwrap();
+        repo.save().await.unwrap();
+
+        // The cached result is still served, with an up to date expires_in
+        state.clock.advance(Duration::try_seconds(10).unwrap());
+        let response = introspect(&first_access_token_str).await;
+        assert!(response.active);
+        assert_eq!(
+            response.expires_in,
+            Some(Duration::try_seconds(290).unwrap())
+        );
+
+        // Until it expires
+        state.clock.advance(Duration::try_seconds(30).unwrap());
+        assert!(!introspect(&first_access_token_str).await.active);
+
+        // Cache the second access token again, and revoke the session through
+        // the revocation endpoint, which invalidates the cache right away
+        assert!(introspect(&second_access_token).await.active);
+
+        let request = Request::post(OAuth2Revocation::PATH).form(json!({
+            "token": second_refresh_token,
+            "client_id": client_id,
+        }));
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::OK);
+
+        assert!(!introspect(&second_access_token).await.active);
+    }
+
+    #[sqlx::test(migrator = "mas_storage_pg::MIGRATOR")]
+    async fn test_introspection_cache_locked_user(pool: PgPool) {
+        setup();
+        let mut state = TestState::from_pool(pool).await.unwrap();
+        state.introspection_cache =
+            IntrospectionCache::new(Some(Duration::try_seconds(30).unwrap()));
+        let admin_token = state.token_with_scope("urn:mas:admin").await;
+
+        // Provision a client which will be used to do introspection requests
+        let request = Request::post(OAuth2RegistrationEndpoint::PATH).json(json!({
+            "client_uri": "https://introspecting.com/",
+            "grant_types": [],
+            "token_endpoint_auth_method": "client_secret_basic",
+        }));
+
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::CREATED);
+        let client: ClientRegistrationResponse = response.json();
+        let introspecting_client_id = client.client_id;
+        let introspecting_client_secret = client.client_secret.unwrap();
+
+        // Provision a client which will be used to generate tokens
+        let request = Request::post(OAuth2RegistrationEndpoint::PATH).json(json!({
+            "client_uri": "https://client.com/",
+            "redirect_uris": ["https://client.com/"],
+            "response_types": ["code"],
+            "grant_types": ["authorization_code", "refresh_token"],
+            "token_endpoint_auth_method": "none",
+        }));
+
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::CREATED);
+        let ClientRegistrationResponse { client_id, .. } = response.json();
+
+        let mut repo = state.repository().await.unwrap();
+        let user = repo
+            .user()
+            .add(&mut state.rng(), &state.clock, "alice".to_owned())
+            .await
+            .unwrap();
+
+        let client = repo
+            .oauth2_client()
+            .find_by_client_id(&client_id)
+            .await
+            .unwrap()
+            .unwrap();
+
+        let browser_session = repo
+            .browser_session()
+            .add(&mut state.rng(), &state.clock, &user, None)
+            .await
+            .unwrap();
+
+        let session = repo
+            .oauth2_session()
+            .add_from_browser_session(
+                &mut state.rng(),
+                &state.clock,
+                &client,
+                &browser_session,
+                Scope::from_iter([OPENID]),
+            )
+            .await
+            .unwrap();
+
+        let (AccessToken { access_token, .. }, _) = generate_token_pair(
+            &mut state.rng(),
+            &state.clock,
+            &mut repo,
+            &session,
+            Ulid::from_datetime_with_source(state.clock.now().into(), &mut state.rng()),
+            TokenType::AccessToken.generate(&mut state.rng()),
+            Duration::microseconds(5 * 60 * 1000 * 1000),
+        )
+        .await
+        .unwrap();
+
+        repo.save().await.unwrap();
+
+        let introspect = async |token: &str| -> IntrospectionResponse {
+            let request = Request::post(OAuth2Introspection::PATH)
+                .basic_auth(&introspecting_client_id, &introspecting_client_secret)
+                .form(json!({ "token": token }));
+            let response = state.request(request).await;
+            response.assert_status(StatusCode::OK);
+            response.json()
+        };
+
+        // Get the result in the cache
+        assert!(introspect(&access_token).await.active);
+
+        // Lock the user through the admin API, which invalidates the cache right
+        // away
+        let request = Request::post(format!("/api/admin/v1/users/{}/lock", user.id))
+            .bearer(&admin_token)
+            .empty();
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::OK);
+
+        assert!(!introspect(&access_token).await.active);
+    }
+
+    #[sqlx::test(migrator = "mas_storage_pg::MIGRATOR")]
+    async fn test_compat_token_anomalies(pool: PgPool) {
+        setup();
+        let site_config = SiteConfig {
+            compat_token_anomaly_detection: Some(CompatTokenAnomalyDetectionConfig {
+                ipv4_prefix_length: 16,
+                ipv6_prefix_length: 48,
+            }),
+            ..test_site_config()
+        };
+        let state = TestState::from_pool_with_site_config(pool, site_config)
+            .await
+            .unwrap();
+
+        // Provision a client which will be used to do introspection requests
+        let request = Request::post(OAuth2RegistrationEndpoint::PATH).json(json!({
+            "client_uri": "https://introspecting.com/",
+            "grant_types": [],
+            "token_endpoint_auth_method": "client_secret_basic",
+        }));
+
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::CREATED);
+        let client: ClientRegistrationResponse = response.json();
+        let introspecting_client_id = client.client_id;
+        let introspecting_client_secret = client.client_secret.unwrap();
+
+        // Provision a user with a compat session last seen from 192.0.2.1
+        let mut rng = state.rng();
+        let mut repo = state.repository().await.unwrap();
+        let user = repo
+            .user()
+            .add(&mut rng, &state.clock, "alice".to_owned())
+            .await
+            .unwrap();
+
+        state
+            .homeserver_connection
+            .provision_user(&ProvisionRequest::new(&user.username, &user.sub))
+            .await
+            .unwrap();
+
+        let device = Device::generate(&mut rng);
+        let session = repo
+            .compat_session()
+            .add(&mut rng, &state.clock, &user, device, None, false, None)
+            .await
+            .unwrap();
+        repo.compat_session()
+            .record_batch_activity(vec![(
+                session.id,
+                state.clock.now(),
+                Some([192, 0, 2, 1].into()),
+            )])
+            .await
+            .unwrap();
+
+        let access_token = TokenType::CompatAccessToken.generate(&mut rng);
+        repo.compat_access_token()
+            .add(&mut rng, &state.clock, &session, access_token.clone(), None)
+            .await
+            .unwrap();
+        repo.save().await.unwrap();
+
+        // The homeserver must say which client is using the token
+        let request = Request::post(OAuth2Introspection::PATH)
+            .basic_auth(&introspecting_client_id, &introspecting_client_secret)
+            .form(json!({ "token": access_token }));
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::BAD_REQUEST);
+
+        // Using the token from the same network isn't an anomaly
+        let request = Request::post(OAuth2Introspection::PATH)
+            .basic_auth(&introspecting_client_id, &introspecting_client_secret)
+            .header("X-MAS-Client-IP", "192.0.200.7")
+            .form(json!({ "token": access_token }));
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::OK);
+        let response: IntrospectionResponse = response.json();
+        assert!(response.active);
+
+        let mut repo = state.repository().await.unwrap();
+        let filter = CompatSessionAnomalyFilter::new().for_compat_session(&session);
+        assert_eq!(
+            repo.compat_session_anomaly().count(filter).await.unwrap(),
+            0
+        );
+        repo.cancel().await.unwrap();
+
+        // Using it from another network gets flagged, but the default policy
+        // doesn't reject it
+        for _ in 0..2 {
+            let request = Request::post(OAuth2Introspection::PATH)
+                .basic_auth(&introspecting_client_id, &introspecting_client_secret)
+                .header("X-MAS-Client-IP", "198.51.100.1")
+                .form(json!({ "token": access_token }));
+            let response = state.request(request).await;
+            response.assert_status(StatusCode::OK);
+            let response: IntrospectionResponse = response.json();
+            assert!(response.active);
+        }
+
+        // The anomaly is only recorded once
+        let mut repo = state.repository().await.unwrap();
+        let page = repo
+            .compat_session_anomaly()
+            .list(filter, Pagination::first(10))
+            .await
+            .unwrap();
+        repo.cancel().await.unwrap();
+        assert_eq!(page.edges.len(), 1);
+        let anomaly = &page.edges[0].node;
+        assert_eq!(anomaly.kinds, vec![CompatSessionAnomalyKind::IpRange]);
+        assert_eq!(anomaly.ip_address, Some([198, 51, 100, 1].into()));
+        assert!(!anomaly.rejected);
+    }
 }
@@ -0,0 +1,88 @@
+// Copyright 2026 Element Creations Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+// Please see LICENSE files in the repository root for full details.
+
+use axum::{
+    extract::{Path, State},
+    response::{IntoResponse, Redirect, Response},
+};
+use axum_extra::extract::Query;
+use hyper::StatusCode;
+use mas_axum_utils::{GenericError, InternalError};
+use mas_router::{ClientLaunchParams, UrlBuilder};
+use mas_storage::{BoxRepository, oauth2::OAuth2ClientRepository};
+use thiserror::Error;
+use ulid::Ulid;
+
+use crate::impl_from_error_for_route;
+
+#[derive(Debug, Error)]
+pub(crate) enum RouteError {
+    #[error("Client not found")]
+    ClientNotFound,
+
+    #[error("The client doesn't have an initiate_login_uri")]
+    NotLaunchable,
+
+    #[error("Invalid target_link_uri")]
+    InvalidTargetLinkUri,
+
+    #[error(transparent)]
+    Internal(Box<dyn std::error::Error>),
+}
+
+impl_from_error_for_route!(mas_storage::RepositoryError);
+
+impl IntoResponse for RouteError {
+    fn into_response(self) -> axum::response::Response {
+        match self {
+            e @ Self::ClientNotFound => GenericError::new(StatusCode::NOT_FOUND, e).into_response(),
+            e @ (Self::NotLaunchable | Self::InvalidTargetLinkUri) => {
+                GenericError::new(StatusCode::BAD_REQUEST, e).into_response()
+            }
+            Self::Internal(e) => InternalError::new(e).into_response(),
+        }
+    }
+}
+
+/// Send the user to the `initiate_login_uri` of a client, so that it starts a
+/// login with us, as described in section 4 of the `OpenID Connect Core`
+/// specification.
+#[tracing::instrument(
+    name = "handlers.oauth2.launch.get",
+    fields(client.id = %client_id),
+    skip_all,
+)]
+pub(crate) async fn get(
+    mut repo: BoxRepository,
+    State(url_builder): State<UrlBuilder>,
+    Path(client_id): Path<Ulid>,
+    Query(params): Query<ClientLaunchParams>,
+) -> Result<Response, RouteError> {
+    let client = repo
+        .oauth2_client()
+        .lookup(client_id)
+        .await?
+        .ok_or(RouteError::ClientNotFound)?;
+
+    let mut url = client.initiate_login_uri.ok_or(RouteError::NotLaunchable)?;
+
+    // The target must be on the same origin as the initiate_login_uri, so that
+    // this can't be used as an open redirect
+    if let Some(target_link_uri) = &params.target_link_uri
+        && target_link_uri.origin() != url.origin()
+    {
+        return Err(RouteError::InvalidTargetLinkUri);
+    }
+
+    {
+        let mut query = url.query_pairs_mut();
+        query.append_pair("iss", url_builder.oidc_issuer().as_str());
+        if let Some(target_link_uri) = &params.target_link_uri {
+            query.append_pair("target_link_uri", target_link_uri.as_str());
+        }
+    }
+
+    Ok(Redirect::to(url.as_str()).into_response())
+}
@@ -8,25 +8,30 @@ use std::collections::HashMap;
 
 use chrono::Duration;
 use mas_data_model::{
-    AccessToken, Authentication, AuthorizationGrant, BrowserSession, Client, Clock, RefreshToken,
-    Session, TokenType,
+    AccessToken, Authentication, AuthorizationGrant, BrowserSession, Client, Clock, JwksOrJwksUri,
+    RefreshToken, Session, SiteConfig, TokenType,
 };
-use mas_iana::jose::JsonWebSignatureAlg;
+use mas_iana::jose::{JsonWebEncryptionAlg, JsonWebEncryptionEnc, JsonWebSignatureAlg};
 use mas_jose::{
     claims::{self, hash_token},
     constraints::Constrainable,
+    jwe::JsonWebEncryptionHeader,
     jwt::{JsonWebSignatureHeader, Jwt},
 };
 use mas_keystore::Keystore;
 use mas_router::UrlBuilder;
 use mas_storage::RepositoryAccess;
 use thiserror::Error;
+use ulid::Ulid;
+
+use crate::upstream_oauth2::cache::MetadataCache;
 
 pub mod authorization;
 pub mod device;
 pub mod discovery;
 pub mod introspection;
 pub mod keys;
+pub mod launch;
 pub mod registration;
 pub mod revoke;
 pub mod token;
@@ -95,19 +100,197 @@ pub(crate) fn generate_id_token(
     Ok(id_token.into_string())
 }
 
+#[derive(Debug, Error)]
+pub(crate) enum ResponseEncryptionError {
+    #[error("The client has no JWKS to encrypt responses with")]
+    MissingJwks,
+
+    #[error("Failed to fetch the client JWKS")]
+    FetchJwks(#[from] mas_oidc_client::error::JwksError),
+
+    #[error("No key in the client JWKS can be used with the {0} algorithm")]
+    NoSuitableKey(JsonWebEncryptionAlg),
+
+    #[error(transparent)]
+    Encryption(#[from] mas_jose::jwe::JweEncryptionError),
+}
+
+/// Encrypt a response issued to a client with one of the keys from its JWKS,
+/// using the given JWE `alg` and `enc` algorithms.
+///
+/// The `cty` should be set to `JWT` if the payload is a signed JWT, which makes
+/// the result a nested JWT.
+///
+/// Key sets behind a `jwks_uri` are cached in the [`MetadataCache`], and
+/// fetched again if none of the cached keys can be used, in case the client
+/// rotated its keys.
+pub(crate) async fn encrypt_response(
+    rng: &mut (impl rand::RngCore + rand::CryptoRng + Send),
+    http_client: &reqwest::Client,
+    metadata_cache: &MetadataCache,
+    client: &Client,
+    (alg, enc): (&JsonWebEncryptionAlg, &JsonWebEncryptionEnc),
+    payload: &[u8],
+    cty: Option<&str>,
+) -> Result<String, ResponseEncryptionError> {
+    let fetched;
+    let jwks = match &client.jwks {
+        Some(JwksOrJwksUri::Jwks(jwks)) => jwks,
+        Some(JwksOrJwksUri::JwksUri(jwks_uri)) => {
+            let mut jwks = metadata_cache.get_jwks(http_client, jwks_uri).await?;
+            if jwks.encryption_key_for_algorithm(alg).is_none() {
+                jwks = metadata_cache.fetch_jwks(http_client, jwks_uri).await?;
+            }
+            fetched = jwks;
+            &*fetched
+        }
+        None => return Err(ResponseEncryptionError::MissingJwks),
+    };
+
+    let key = jwks
+        .encryption_key_for_algorithm(alg)
+        .ok_or_else(|| ResponseEncryptionError::NoSuitableKey(alg.clone()))?;
+
+    let mut header = JsonWebEncryptionHeader::new(alg.clone(), enc.clone());
+    if let Some(kid) = key.kid() {
+        header = header.with_kid(kid);
+    }
+    if let Some(cty) = cty {
+        header = header.with_cty(cty.to_owned());
+    }
+
+    Ok(mas_jose::jwe::encrypt(rng, header, payload, key)?)
+}
+
+/// Encrypt a signed ID token as a nested JWT if the client asked for its ID
+/// tokens to be encrypted, else return it as is.
+pub(crate) async fn encrypt_id_token(
+    rng: &mut (impl rand::RngCore + rand::CryptoRng + Send),
+    http_client: &reqwest::Client,
+    metadata_cache: &MetadataCache,
+    client: &Client,
+    id_token: String,
+) -> Result<String, ResponseEncryptionError> {
+    let Some(encryption) = client.id_token_encrypted_response() else {
+        return Ok(id_token);
+    };
+
+    encrypt_response(
+        rng,
+        http_client,
+        metadata_cache,
+        client,
+        encryption,
+        id_token.as_bytes(),
+        Some("JWT"),
+    )
+    .await
+}
+
+#[derive(Debug, Error)]
+#[error(transparent)]
+pub(crate) enum AccessTokenSignatureError {
+    #[error("The signing key is invalid")]
+    InvalidSigningKey,
+    Claim(#[from] mas_jose::claims::ClaimError),
+    JwtSignature(#[from] mas_jose::jwt::JwtSignatureError),
+    WrongAlgorithm(#[from] mas_keystore::WrongAlgorithmError),
+}
+
+/// Generate the string of a new access token for the given session.
+///
+/// This is a JWT signed as per RFC 9068 if the site is configured to issue
+/// those for the session's client or scope, and an opaque token otherwise. JWT
+/// access tokens are still stored like opaque ones, so that they can be
+/// introspected and revoked.
+///
+/// Returns the ID the access token must be stored with, which is also the
+/// `jti` of JWT access tokens, along with the access token string.
+/// The algorithm to sign tokens and responses with, when the consumer can't
+/// ask for a specific one
+///
+/// RS256 is the only algorithm every consumer must support, so it is preferred,
+/// but any other algorithm the keystore supports is used otherwise.
+pub(crate) fn default_signing_algorithm(key_store: &Keystore) -> Option<JsonWebSignatureAlg> {
+    if key_store
+        .signing_key_for_algorithm(&JsonWebSignatureAlg::Rs256)
+        .is_some()
+    {
+        return Some(JsonWebSignatureAlg::Rs256);
+    }
+
+    key_store.available_signing_algorithms().into_iter().next()
+}
+
+pub(crate) fn generate_access_token(
+    rng: &mut (impl rand::RngCore + rand::CryptoRng),
+    clock: &impl Clock,
+    url_builder: &UrlBuilder,
+    key_store: &Keystore,
+    site_config: &SiteConfig,
+    session: &Session,
+    ttl: Duration,
+) -> Result<(Ulid, String), AccessTokenSignatureError> {
+    let now = clock.now();
+    let id = Ulid::from_datetime_with_source(now.into(), rng);
+
+    let Some(config) = site_config
+        .jwt_access_tokens
+        .as_ref()
+        .filter(|config| config.applies_to(session.client_id, &session.scope))
+    else {
+        return Ok((id, TokenType::AccessToken.generate(rng)));
+    };
+
+    let client_id = session.client_id.to_string();
+    // Sessions without a user are on behalf of the client itself
+    let sub = session
+        .user_id
+        .map_or_else(|| client_id.clone(), |id| id.to_string());
+    let aud = config.audience.clone().unwrap_or_else(|| client_id.clone());
+
+    let mut claims = HashMap::new();
+    claims::ISS.insert(&mut claims, url_builder.oidc_issuer().to_string())?;
+    claims::SUB.insert(&mut claims, sub)?;
+    claims::AUD.insert(&mut claims, aud)?;
+    claims::IAT.insert(&mut claims, now)?;
+    claims::EXP.insert(&mut claims, now + ttl)?;
+    claims::JTI.insert(&mut claims, id.to_string())?;
+    claims::CLIENT_ID.insert(&mut claims, client_id)?;
+    claims::SCOPE.insert(&mut claims, session.scope.to_string())?;
+
+    let alg =
+        default_signing_algorithm(key_store).ok_or(AccessTokenSignatureError::InvalidSigningKey)?;
+    let key = key_store
+        .signing_key_for_algorithm(&alg)
+        .ok_or(AccessTokenSignatureError::InvalidSigningKey)?;
+
+    let signer = key.params().signing_key_for_alg(&alg)?;
+    let header = JsonWebSignatureHeader::new(alg)
+        .with_typ("at+jwt".to_owned())
+        .with_kid(
+            key.kid()
+                .ok_or(AccessTokenSignatureError::InvalidSigningKey)?,
+        );
+    let access_token = Jwt::sign_with_rng(rng, header, claims, &signer)?;
+
+    Ok((id, access_token.into_string()))
+}
+
 pub(crate) async fn generate_token_pair<R: RepositoryAccess>(
     rng: &mut (impl rand::RngCore + Send),
     clock: &impl Clock,
     repo: &mut R,
     session: &Session,
+    access_token_id: Ulid,
+    access_token_str: String,
     ttl: Duration,
 ) -> Result<(AccessToken, RefreshToken), R::Error> {
-    let access_token_str = TokenType::AccessToken.generate(rng);
     let refresh_token_str = TokenType::RefreshToken.generate(rng);
 
     let access_token = repo
         .oauth2_access_token()
-        .add(rng, clock, session, access_token_str, Some(ttl))
+        .add_with_id(access_token_id, clock, session, access_token_str, Some(ttl))
         .await?;
 
     let refresh_token = repo
@@ -8,10 +8,15 @@ use std::sync::LazyLock;
 
 use axum::{Json, extract::State, response::IntoResponse};
 use axum_extra::TypedHeader;
+use chrono::DateTime;
 use hyper::StatusCode;
 use mas_axum_utils::record_error;
-use mas_data_model::{BoxClock, BoxRng};
-use mas_iana::oauth::OAuthClientAuthenticationMethod;
+use mas_data_model::{BoxClock, BoxRng, ClientSecret};
+use mas_iana::{
+    jose::{JsonWebEncryptionAlg, JsonWebEncryptionEnc},
+    oauth::OAuthClientAuthenticationMethod,
+};
+use mas_jose::jwe::{SUPPORTED_CONTENT_ENCRYPTION_ALGORITHMS, SUPPORTED_ENCRYPTION_ALGORITHMS};
 use mas_keystore::Encrypter;
 use mas_policy::{EvaluationResult, Policy};
 use mas_storage::{BoxRepository, oauth2::OAuth2ClientRepository};
@@ -58,6 +63,12 @@ pub(crate) enum RouteError {
 
     #[error("client registration denied by the policy: {0}")]
     PolicyDenied(EvaluationResult),
+
+    #[error("{0} is not supported")]
+    UnsupportedEncryptionAlgorithm(&'static str),
+
+    #[error("no key in the client JWKS can be used for {0}")]
+    MissingEncryptionKey(&'static str),
 }
 
 impl_from_error_for_route!(mas_storage::RepositoryError);
@@ -141,6 +152,17 @@ impl IntoResponse for RouteError {
             )
                 .into_response(),
 
+            // For unsupported or unusable encryption settings, we return an
+            // `invalid_client_metadata` error with the field at fault.
+            Self::UnsupportedEncryptionAlgorithm(_) | Self::MissingEncryptionKey(_) => (
+                StatusCode::BAD_REQUEST,
+                Json(
+                    ClientError::from(ClientErrorCode::InvalidClientMetadata)
+                        .with_description(self.to_string()),
+                ),
+            )
+                .into_response(),
+
             // For policy violations, we return an `invalid_client_metadata` error with the details
             // of the violations in most cases. If a violation includes `redirect_uri` in the
             // message, we return an `invalid_redirect_uri` error instead.
@@ -208,6 +230,42 @@ fn host_is_public_suffix(url: &Url) -> bool {
     false
 }
 
+/// Check that the JWE algorithms requested by a client to encrypt some
+/// responses are supported, and that it gave us a key to use with them.
+///
+/// Returns the algorithms to store for the client, if any.
+fn check_encrypted_response(
+    metadata: &VerifiedClientMetadata,
+    encryption: Option<(&JsonWebEncryptionAlg, &JsonWebEncryptionEnc)>,
+    alg_field: &'static str,
+    enc_field: &'static str,
+) -> Result<Option<(JsonWebEncryptionAlg, JsonWebEncryptionEnc)>, RouteError> {
+    let Some((alg, enc)) = encryption else {
+        return Ok(None);
+    };
+
+    if !SUPPORTED_ENCRYPTION_ALGORITHMS.contains(alg) {
+        return Err(RouteError::UnsupportedEncryptionAlgorithm(alg_field));
+    }
+
+    // This also catches the default `enc` value, which we don't support
+    if !SUPPORTED_CONTENT_ENCRYPTION_ALGORITHMS.contains(enc) {
+        return Err(RouteError::UnsupportedEncryptionAlgorithm(enc_field));
+    }
+
+    // Keys behind a `jwks_uri` are only checked when encrypting, as they may
+    // change over time
+    match (&metadata.jwks, &metadata.jwks_uri) {
+        (Some(jwks), _) if jwks.encryption_key_for_algorithm(alg).is_none() => {
+            return Err(RouteError::MissingEncryptionKey(alg_field));
+        }
+        (None, None) => return Err(RouteError::MissingEncryptionKey(alg_field)),
+        _ => {}
+    }
+
+    Ok(Some((alg.clone(), enc.clone())))
+}
+
 /// Check if any of the URLs in the given `Localized` field is a public suffix
 fn localised_url_has_public_suffix(url: &Localized<Url>) -> bool {
     url.iter().any(|(_lang, url)| host_is_public_suffix(url))
@@ -278,6 +336,20 @@ pub(crate) async fn post(
         }
     }
 
+    let id_token_encrypted_response = check_encrypted_response(
+        &metadata,
+        metadata.id_token_encrypted_response(),
+        "id_token_encrypted_response_alg",
+        "id_token_encrypted_response_enc",
+    )?;
+
+    let userinfo_encrypted_response = check_encrypted_response(
+        &metadata,
+        metadata.userinfo_encrypted_response(),
+        "userinfo_encrypted_response_alg",
+        "userinfo_encrypted_response_enc",
+    )?;
+
     let res = policy
         .evaluate_client_registration(mas_policy::ClientRegistrationInput {
             client_metadata: &metadata,
@@ -291,7 +363,7 @@ pub(crate) async fn post(
         return Err(RouteError::PolicyDenied(res));
     }
 
-    let (client_secret, encrypted_client_secret) = match metadata.token_endpoint_auth_method {
+    let (client_secret, stored_client_secret) = match metadata.token_endpoint_auth_method {
         Some(
             OAuthClientAuthenticationMethod::ClientSecretJwt
             | OAuthClientAuthenticationMethod::ClientSecretPost
@@ -300,7 +372,11 @@ pub(crate) async fn post(
             // Let's generate a random client secret
             let client_secret = Alphanumeric.sample_string(&mut rng, 20);
             let encrypted_client_secret = encrypter.encrypt_to_string(client_secret.as_bytes())?;
-            (Some(client_secret), Some(encrypted_client_secret))
+            // Secrets issued through dynamic registration don't expire
+            (
+                Some(client_secret),
+                Some(ClientSecret::encrypted(encrypted_client_secret, None)),
+            )
         }
         _ => (None, None),
     };
@@ -334,7 +410,7 @@ pub(crate) async fn post(
                 &clock,
                 metadata.redirect_uris().to_vec(),
                 digest_hash,
-                encrypted_client_secret,
+                stored_client_secret,
                 metadata.application_type.clone(),
                 //&metadata.response_types(),
                 metadata.grant_types().to_vec(),
@@ -356,17 +432,41 @@ pub(crate) async fn post(
                 metadata.initiate_login_uri.clone(),
             )
             .await?;
+
+        let client =
+            if id_token_encrypted_response.is_some() || userinfo_encrypted_response.is_some() {
+                repo.oauth2_client()
+                    .set_encryption_preferences(
+                        client,
+                        id_token_encrypted_response,
+                        userinfo_encrypted_response,
+                    )
+                    .await?
+            } else {
+                client
+            };
+
         tracing::info!(%client.id, "Registered new client");
         REGISTRATION_COUNTER.add(1, &[KeyValue::new(RESULT, "created")]);
         client
     };
 
+    // The spec requires `client_secret_expires_at` whenever a secret is issued,
+    // with 0 meaning that it never expires
+    let client_secret_expires_at = client_secret.as_ref().map(|_| {
+        client
+            .client_secret
+            .as_ref()
+            .and_then(|secret| secret.expires_at)
+            .unwrap_or(DateTime::UNIX_EPOCH)
+    });
+
     let response = ClientRegistrationResponse {
         client_id: client.client_id.clone(),
         client_secret,
         // XXX: we should have a `created_at` field on the clients
         client_id_issued_at: Some(client.id.datetime().into()),
-        client_secret_expires_at: None,
+        client_secret_expires_at,
     };
 
     // We round-trip back to the metadata to output it in the response
@@ -507,6 +607,70 @@ mod tests {
             response.error_description.unwrap(),
             "client_uri is not using a valid domain"
         );
+
+        // Using an unsupported encryption algorithm
+        let request =
+            Request::post(mas_router::OAuth2RegistrationEndpoint::PATH).json(serde_json::json!({
+                "client_uri": "https://example.com/",
+                "redirect_uris": ["https://example.com/"],
+                "response_types": ["code"],
+                "grant_types": ["authorization_code"],
+                "token_endpoint_auth_method": "none",
+                "jwks_uri": "https://example.com/jwks.json",
+                "id_token_encrypted_response_alg": "RSA1_5",
+                "id_token_encrypted_response_enc": "A256GCM",
+            }));
+
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::BAD_REQUEST);
+        let response: ClientError = response.json();
+        assert_eq!(response.error, ClientErrorCode::InvalidClientMetadata);
+        assert_eq!(
+            response.error_description.unwrap(),
+            "id_token_encrypted_response_alg is not supported"
+        );
+
+        // Not setting the `enc` falls back to A128CBC-HS256, which is not supported
+        let request =
+            Request::post(mas_router::OAuth2RegistrationEndpoint::PATH).json(serde_json::json!({
+                "client_uri": "https://example.com/",
+                "redirect_uris": ["https://example.com/"],
+                "response_types": ["code"],
+                "grant_types": ["authorization_code"],
+                "token_endpoint_auth_method": "none",
+                "jwks_uri": "https://example.com/jwks.json",
+                "userinfo_encrypted_response_alg": "RSA-OAEP-256",
+            }));
+
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::BAD_REQUEST);
+        let response: ClientError = response.json();
+        assert_eq!(response.error, ClientErrorCode::InvalidClientMetadata);
+        assert_eq!(
+            response.error_description.unwrap(),
+            "userinfo_encrypted_response_enc is not supported"
+        );
+
+        // Asking for encryption without giving any key
+        let request =
+            Request::post(mas_router::OAuth2RegistrationEndpoint::PATH).json(serde_json::json!({
+                "client_uri": "https://example.com/",
+                "redirect_uris": ["https://example.com/"],
+                "response_types": ["code"],
+                "grant_types": ["authorization_code"],
+                "token_endpoint_auth_method": "none",
+                "id_token_encrypted_response_alg": "ECDH-ES",
+                "id_token_encrypted_response_enc": "A256GCM",
+            }));
+
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::BAD_REQUEST);
+        let response: ClientError = response.json();
+        assert_eq!(response.error, ClientErrorCode::InvalidClientMetadata);
+        assert_eq!(
+            response.error_description.unwrap(),
+            "no key in the client JWKS can be used for id_token_encrypted_response_alg"
+        );
     }
 
     #[sqlx::test(migrator = "mas_storage_pg::MIGRATOR")]
@@ -529,6 +693,7 @@ mod tests {
         response.assert_status(StatusCode::CREATED);
         let response: ClientRegistrationResponse = response.json();
         assert!(response.client_secret.is_none());
+        assert!(response.client_secret_expires_at.is_none());
 
         // A successful registration with client_secret based authentication should
         // return a client secret
@@ -545,6 +710,11 @@ mod tests {
         response.assert_status(StatusCode::CREATED);
         let response: ClientRegistrationResponse = response.json();
         assert!(response.client_secret.is_some());
+        // The secret never expires, which is reported as 0
+        assert_eq!(
+            response.client_secret_expires_at,
+            Some(chrono::DateTime::UNIX_EPOCH)
+        );
     }
     #[sqlx::test(migrator = "mas_storage_pg::MIGRATOR")]
     async fn test_registration_dedupe(pool: PgPool) {
@@ -24,7 +24,7 @@ use oauth2_types::{
 use thiserror::Error;
 use ulid::Ulid;
 
-use crate::{BoundActivityTracker, impl_from_error_for_route};
+use crate::{BoundActivityTracker, IntrospectionCache, impl_from_error_for_route};
 
 #[derive(Debug, Error)]
 pub(crate) enum RouteError {
@@ -126,6 +126,7 @@ pub(crate) async fn post(
     mut repo: BoxRepository,
     activity_tracker: BoundActivityTracker,
     State(encrypter): State<Encrypter>,
+    State(introspection_cache): State<IntrospectionCache>,
     client_authorization: ClientAuthorization<RevocationRequest>,
 ) -> Result<impl IntoResponse, RouteError> {
     let client = client_authorization
@@ -141,7 +142,7 @@ pub(crate) async fn post(
 
     client_authorization
         .credentials
-        .verify(&http_client, &encrypter, method, &client)
+        .verify(&http_client, &encrypter, clock.now(), method, &client)
         .await
         .map_err(|err| {
             if err.is_internal() {
@@ -240,10 +241,12 @@ pub(crate) async fn post(
     }
 
     // Now that we checked everything, we can end the session.
-    repo.oauth2_session().finish(&clock, session).await?;
+    let session = repo.oauth2_session().finish(&clock, session).await?;
 
     repo.save().await?;
 
+    introspection_cache.invalidate_session(session.id).await;
+
     Ok(())
 }
 
@@ -251,7 +254,7 @@ pub(crate) async fn post(
 mod tests {
     use chrono::Duration;
     use hyper::Request;
-    use mas_data_model::{AccessToken, RefreshToken};
+    use mas_data_model::{AccessToken, Clock, RefreshToken};
     use mas_router::SimpleRoute;
     use mas_storage::RepositoryAccess;
     use oauth2_types::{
@@ -331,6 +334,8 @@ mod tests {
                 &state.clock,
                 &mut repo,
                 &session,
+                Ulid::from_datetime_with_source(state.clock.now().into(), &mut state.rng()),
+                TokenType::AccessToken.generate(&mut state.rng()),
                 Duration::microseconds(5 * 60 * 1000 * 1000),
             )
             .await
@@ -398,6 +403,8 @@ mod tests {
                 &state.clock,
                 &mut repo,
                 &session,
+                Ulid::from_datetime_with_source(state.clock.now().into(), &mut state.rng()),
+                TokenType::AccessToken.generate(&mut state.rng()),
                 Duration::microseconds(5 * 60 * 1000 * 1000),
             )
             .await
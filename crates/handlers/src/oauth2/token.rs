@@ -18,7 +18,7 @@ use mas_axum_utils::{
 };
 use mas_data_model::{
     AuthorizationGrantStage, BoxClock, BoxRng, Client, Clock, Device, DeviceCodeGrantState,
-    SiteConfig, TokenType,
+    Session, SiteConfig, TokenType,
 };
 use mas_i18n::DataLocale;
 use mas_keystore::{Encrypter, Keystore};
@@ -32,6 +32,7 @@ use mas_storage::{
         OAuth2AccessTokenRepository, OAuth2AuthorizationGrantRepository,
         OAuth2RefreshTokenRepository, OAuth2SessionRepository,
     },
+    queue::{QueueJobRepositoryExt as _, SyncDevicesJob},
     user::BrowserSessionRepository,
 };
 use mas_templates::{DeviceNameContext, TemplateContext, Templates};
@@ -49,8 +50,10 @@ use thiserror::Error;
 use tracing::{debug, info, warn};
 use ulid::Ulid;
 
-use super::{generate_id_token, generate_token_pair};
-use crate::{BoundActivityTracker, METER, impl_from_error_for_route};
+use super::{encrypt_id_token, generate_access_token, generate_id_token, generate_token_pair};
+use crate::{
+    BoundActivityTracker, IntrospectionCache, METER, MetadataCache, impl_from_error_for_route,
+};
 
 static TOKEN_REQUEST_COUNTER: LazyLock<Counter<u64>> = LazyLock::new(|| {
     METER
@@ -59,8 +62,16 @@ static TOKEN_REQUEST_COUNTER: LazyLock<Counter<u64>> = LazyLock::new(|| {
         .with_unit("{request}")
         .build()
 });
+static REFRESH_TOKEN_REPLAY_COUNTER: LazyLock<Counter<u64>> = LazyLock::new(|| {
+    METER
+        .u64_counter("mas.oauth2.refresh_token_replay")
+        .with_description("How many OAuth 2.0 refresh token replays were detected")
+        .with_unit("{replay}")
+        .build()
+});
 const GRANT_TYPE: Key = Key::from_static_str("grant_type");
 const RESULT: Key = Key::from_static_str("successful");
+const SESSION_REVOKED: Key = Key::from_static_str("session_revoked");
 
 #[derive(Debug, Error)]
 pub(crate) enum RouteError {
@@ -265,6 +276,8 @@ impl_from_error_for_route!(mas_templates::TemplateError);
 impl_from_error_for_route!(mas_storage::RepositoryError);
 impl_from_error_for_route!(mas_policy::EvaluationError);
 impl_from_error_for_route!(super::IdTokenSignatureError);
+impl_from_error_for_route!(super::AccessTokenSignatureError);
+impl_from_error_for_route!(super::ResponseEncryptionError);
 
 #[tracing::instrument(
     name = "handlers.oauth2.token.post",
@@ -274,7 +287,7 @@ impl_from_error_for_route!(super::IdTokenSignatureError);
 pub(crate) async fn post(
     mut rng: BoxRng,
     clock: BoxClock,
-    State(http_client): State<reqwest::Client>,
+    (State(http_client), State(metadata_cache)): (State<reqwest::Client>, State<MetadataCache>),
     State(key_store): State<Keystore>,
     State(url_builder): State<UrlBuilder>,
     activity_tracker: BoundActivityTracker,
@@ -283,6 +296,7 @@ pub(crate) async fn post(
     State(site_config): State<SiteConfig>,
     State(encrypter): State<Encrypter>,
     State(templates): State<Templates>,
+    State(introspection_cache): State<IntrospectionCache>,
     policy: Policy,
     user_agent: Option<TypedHeader<headers::UserAgent>>,
     client_authorization: ClientAuthorization<AccessTokenRequest>,
@@ -301,7 +315,7 @@ pub(crate) async fn post(
 
     client_authorization
         .credentials
-        .verify(&http_client, &encrypter, method, &client)
+        .verify(&http_client, &encrypter, clock.now(), method, &client)
         .await
         .map_err(|err| {
             // Classify the error differntly, depending on whether it's an 'internal' error,
@@ -331,9 +345,12 @@ pub(crate) async fn post(
                 &activity_tracker,
                 &grant,
                 &client,
+                &http_client,
+                &metadata_cache,
                 &key_store,
                 &url_builder,
                 &site_config,
+                &introspection_cache,
                 repo,
                 &homeserver,
                 &templates,
@@ -348,7 +365,10 @@ pub(crate) async fn post(
                 &activity_tracker,
                 &grant,
                 &client,
+                &key_store,
+                &url_builder,
                 &site_config,
+                &introspection_cache,
                 repo,
                 user_agent,
             )
@@ -361,6 +381,8 @@ pub(crate) async fn post(
                 &activity_tracker,
                 &grant,
                 &client,
+                &key_store,
+                &url_builder,
                 &site_config,
                 repo,
                 policy,
@@ -375,6 +397,8 @@ pub(crate) async fn post(
                 &activity_tracker,
                 &grant,
                 &client,
+                &http_client,
+                &metadata_cache,
                 &key_store,
                 &url_builder,
                 &site_config,
@@ -412,9 +436,12 @@ async fn authorization_code_grant(
     activity_tracker: &BoundActivityTracker,
     grant: &AuthorizationCodeGrant,
     client: &Client,
+    http_client: &reqwest::Client,
+    metadata_cache: &MetadataCache,
     key_store: &Keystore,
     url_builder: &UrlBuilder,
     site_config: &SiteConfig,
+    introspection_cache: &IntrospectionCache,
     mut repo: BoxRepository,
     homeserver: &Arc<dyn HomeserverConnection>,
     templates: &Templates,
@@ -457,6 +484,7 @@ async fn authorization_code_grant(
                 //if !session.is_finished() {
                 repo.oauth2_session().finish(clock, session).await?;
                 repo.save().await?;
+                introspection_cache.invalidate_session(session_id).await;
                 //}
             }
 
@@ -537,11 +565,28 @@ async fn authorization_code_grant(
         .await?;
 
     let ttl = site_config.access_token_ttl;
-    let (access_token, refresh_token) =
-        generate_token_pair(&mut rng, clock, &mut repo, &session, ttl).await?;
+    let (access_token_id, access_token_str) = generate_access_token(
+        &mut rng,
+        clock,
+        url_builder,
+        key_store,
+        site_config,
+        &session,
+        ttl,
+    )?;
+    let (access_token, refresh_token) = generate_token_pair(
+        &mut rng,
+        clock,
+        &mut repo,
+        &session,
+        access_token_id,
+        access_token_str,
+        ttl,
+    )
+    .await?;
 
     let id_token = if session.scope.contains(&scope::OPENID) {
-        Some(generate_id_token(
+        let id_token = generate_id_token(
             &mut rng,
             clock,
             url_builder,
@@ -551,7 +596,8 @@ async fn authorization_code_grant(
             &browser_session,
             Some(&access_token),
             last_authentication.as_ref(),
-        )?)
+        )?;
+        Some(encrypt_id_token(&mut rng, http_client, metadata_cache, client, id_token).await?)
     } else {
         None
     };
@@ -598,13 +644,72 @@ async fn authorization_code_grant(
     Ok((params, repo))
 }
 
+/// Handle a refresh token which was replayed: log it, count it and, if
+/// configured to do so, finish the whole session and revoke all its tokens.
+///
+/// This consumes the repository, as it gets saved if the session is finished.
+async fn refresh_token_replayed(
+    rng: &mut BoxRng,
+    clock: &impl Clock,
+    site_config: &SiteConfig,
+    introspection_cache: &IntrospectionCache,
+    mut repo: BoxRepository,
+    session: Session,
+    refresh_token_id: Ulid,
+) -> Result<(), RouteError> {
+    let revoke = site_config.revoke_session_on_refresh_token_replay;
+    warn!(
+        oauth_session.id = %session.id,
+        oauth_client.id = %session.client_id,
+        oauth_refresh_token.id = %refresh_token_id,
+        session_revoked = revoke,
+        "Refresh token replay detected",
+    );
+    REFRESH_TOKEN_REPLAY_COUNTER.add(1, &[KeyValue::new(SESSION_REVOKED, revoke)]);
+
+    if !revoke {
+        return Ok(());
+    }
+
+    repo.oauth2_access_token()
+        .revoke_all_for_session(clock, &session)
+        .await?;
+    repo.oauth2_refresh_token()
+        .revoke_all_for_session(clock, &session)
+        .await?;
+
+    // If the session is associated with a user, make sure we schedule a device
+    // deletion job for all the devices associated with the session.
+    if let Some(user_id) = session.user_id {
+        let user = repo
+            .user()
+            .lookup(user_id)
+            .await?
+            .ok_or(RouteError::NoSuchOAuthSession(session.id))?;
+
+        repo.queue_job()
+            .schedule_job(rng, clock, SyncDevicesJob::new(&user))
+            .await?;
+    }
+
+    let session = repo.oauth2_session().finish(clock, session).await?;
+    repo.save().await?;
+
+    introspection_cache.invalidate_session(session.id).await;
+
+    Ok(())
+}
+
 async fn refresh_token_grant(
     rng: &mut BoxRng,
     clock: &impl Clock,
     activity_tracker: &BoundActivityTracker,
     grant: &RefreshTokenGrant,
     client: &Client,
+    key_store: &Keystore,
+    url_builder: &UrlBuilder,
     site_config: &SiteConfig,
+    introspection_cache: &IntrospectionCache,
     mut repo: BoxRepository,
     user_agent: Option<String>,
 ) -> Result<(AccessTokenResponse, BoxRepository), RouteError> {
@@ -654,6 +759,16 @@ async fn refresh_token_grant(
         let Some(next_refresh_token_id) = refresh_token.next_refresh_token_id() else {
             // If we don't have a 'next' refresh token, it may just be because this was
             // before we were recording those. Let's just treat it as a replay.
+            refresh_token_replayed(
+                rng,
+                clock,
+                site_config,
+                introspection_cache,
+                repo,
+                session,
+                refresh_token.id,
+            )
+            .await?;
             return Err(RouteError::RefreshTokenInvalid(refresh_token.id));
         };
 
@@ -670,7 +785,16 @@ async fn refresh_token_grant(
 
         // Check if the next refresh token was already consumed or not
         if !next_refresh_token.is_valid() {
-            // XXX: This is a replay, we *may* want to invalidate the session
+            refresh_token_replayed(
+                rng,
+                clock,
+                site_config,
+                introspection_cache,
+                repo,
+                session,
+                refresh_token.id,
+            )
+            .await?;
             return Err(RouteError::RefreshTokenInvalid(next_refresh_token.id));
         }
 
@@ -712,7 +836,16 @@ async fn refresh_token_grant(
                 })?;
 
             if next_access_token.is_used() {
-                // XXX: This is a replay, we *may* want to invalidate the session
+                refresh_token_replayed(
+                    rng,
+                    clock,
+                    site_config,
+                    introspection_cache,
+                    repo,
+                    session,
+                    refresh_token.id,
+                )
+                .await?;
                 return Err(RouteError::RefreshTokenInvalid(next_refresh_token.id));
             }
 
@@ -742,8 +875,25 @@ async fn refresh_token_grant(
         .await;
 
     let ttl = site_config.access_token_ttl;
-    let (new_access_token, new_refresh_token) =
-        generate_token_pair(rng, clock, &mut repo, &session, ttl).await?;
+    let (access_token_id, access_token_str) = generate_access_token(
+        rng,
+        clock,
+        url_builder,
+        key_store,
+        site_config,
+        &session,
+        ttl,
+    )?;
+    let (new_access_token, new_refresh_token) = generate_token_pair(
+        rng,
+        clock,
+        &mut repo,
+        &session,
+        access_token_id,
+        access_token_str,
+        ttl,
+    )
+    .await?;
 
     let refresh_token = repo
         .oauth2_refresh_token()
@@ -759,6 +909,10 @@ async fn refresh_token_grant(
                     .revoke(clock, access_token)
                     .await?;
             }
+
+            // The previous access token might have been cached by the
+            // introspection endpoint
+            introspection_cache.invalidate_session(session.id).await;
         }
     }
 
@@ -776,6 +930,8 @@ async fn client_credentials_grant(
     activity_tracker: &BoundActivityTracker,
     grant: &ClientCredentialsGrant,
     client: &Client,
+    key_store: &Keystore,
+    url_builder: &UrlBuilder,
     site_config: &SiteConfig,
     mut repo: BoxRepository,
     mut policy: Policy,
@@ -798,6 +954,7 @@ async fn client_credentials_grant(
             user: None,
             client,
             session_counts: None,
+            user_attributes: None,
             scope: &scope,
             grant_type: mas_policy::GrantType::ClientCredentials,
             requester: mas_policy::Requester {
@@ -824,11 +981,25 @@ async fn client_credentials_grant(
     }
 
     let ttl = site_config.access_token_ttl;
-    let access_token_str = TokenType::AccessToken.generate(rng);
+    let (access_token_id, access_token_str) = generate_access_token(
+        rng,
+        clock,
+        url_builder,
+        key_store,
+        site_config,
+        &session,
+        ttl,
+    )?;
 
     let access_token = repo
         .oauth2_access_token()
-        .add(rng, clock, &session, access_token_str, Some(ttl))
+        .add_with_id(
+            access_token_id,
+            clock,
+            &session,
+            access_token_str,
+            Some(ttl),
+        )
         .await?;
 
     let mut params = AccessTokenResponse::new(access_token.access_token).with_expires_in(ttl);
@@ -854,6 +1025,8 @@ async fn device_code_grant(
     activity_tracker: &BoundActivityTracker,
     grant: &DeviceCodeGrant,
     client: &Client,
+    http_client: &reqwest::Client,
+    metadata_cache: &MetadataCache,
     key_store: &Keystore,
     url_builder: &UrlBuilder,
     site_config: &SiteConfig,
@@ -924,11 +1097,25 @@ async fn device_code_grant(
     }
 
     let ttl = site_config.access_token_ttl;
-    let access_token_str = TokenType::AccessToken.generate(rng);
+    let (access_token_id, access_token_str) = generate_access_token(
+        rng,
+        clock,
+        url_builder,
+        key_store,
+        site_config,
+        &session,
+        ttl,
+    )?;
 
     let access_token = repo
         .oauth2_access_token()
-        .add(rng, clock, &session, access_token_str, Some(ttl))
+        .add_with_id(
+            access_token_id,
+            clock,
+            &session,
+            access_token_str,
+            Some(ttl),
+        )
         .await?;
 
     let mut params =
@@ -960,6 +1147,7 @@ async fn device_code_grant(
             Some(&access_token),
             None,
         )?;
+        let id_token = encrypt_id_token(rng, http_client, metadata_cache, client, id_token).await?;
 
         params = params.with_id_token(id_token);
     }
@@ -1218,6 +1406,149 @@ mod tests {
         assert_eq!(error, ClientErrorCode::InvalidGrant);
     }
 
+    #[sqlx::test(migrator = "mas_storage_pg::MIGRATOR")]
+    async fn test_encrypted_id_token(pool: PgPool) {
+        setup();
+        let state = TestState::from_pool(pool).await.unwrap();
+
+        // The client encrypts with an ephemeral P-256 key
+        let secret_key = p256::SecretKey::random(&mut state.rng());
+        let jwk = mas_jose::jwk::JsonWebKey::new(mas_jose::jwk::JsonWebKeyPublicParameters::from(
+            secret_key.public_key(),
+        ))
+        .with_use(mas_iana::jose::JsonWebKeyUse::Enc)
+        .with_kid("enc");
+        let decryption_key = mas_jose::jwe::JweDecryptionKey::P256(secret_key);
+
+        // Provision a client which wants its ID tokens and userinfo responses
+        // encrypted
+        let request =
+            Request::post(mas_router::OAuth2RegistrationEndpoint::PATH).json(serde_json::json!({
+                "client_uri": "https://example.com/",
+                "redirect_uris": ["https://example.com/callback"],
+                "token_endpoint_auth_method": "none",
+                "response_types": ["code"],
+                "grant_types": ["authorization_code"],
+                "jwks": { "keys": [jwk] },
+                "id_token_encrypted_response_alg": "ECDH-ES",
+                "id_token_encrypted_response_enc": "A256GCM",
+                "userinfo_encrypted_response_alg": "ECDH-ES",
+                "userinfo_encrypted_response_enc": "A256GCM",
+            }));
+
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::CREATED);
+
+        let ClientRegistrationResponse { client_id, .. } = response.json();
+
+        let mut repo = state.repository().await.unwrap();
+
+        let user = repo
+            .user()
+            .add(&mut state.rng(), &state.clock, "alice".to_owned())
+            .await
+            .unwrap();
+
+        let browser_session = repo
+            .browser_session()
+            .add(&mut state.rng(), &state.clock, &user, None)
+            .await
+            .unwrap();
+
+        let client = repo
+            .oauth2_client()
+            .find_by_client_id(&client_id)
+            .await
+            .unwrap()
+            .unwrap();
+
+        let code = "thisisaverysecurecode";
+        let grant = repo
+            .oauth2_authorization_grant()
+            .add(
+                &mut state.rng(),
+                &state.clock,
+                &client,
+                "https://example.com/callback".parse().unwrap(),
+                Scope::from_iter([OPENID]),
+                Some(AuthorizationCode {
+                    code: code.to_owned(),
+                    pkce: None,
+                }),
+                Some("state".to_owned()),
+                Some("nonce".to_owned()),
+                ResponseMode::Query,
+                false,
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+
+        let session = repo
+            .oauth2_session()
+            .add_from_browser_session(
+                &mut state.rng(),
+                &state.clock,
+                &client,
+                &browser_session,
+                grant.scope.clone(),
+            )
+            .await
+            .unwrap();
+
+        let grant = repo
+            .oauth2_authorization_grant()
+            .fulfill(&state.clock, &session, grant)
+            .await
+            .unwrap();
+
+        repo.save().await.unwrap();
+
+        let request =
+            Request::post(mas_router::OAuth2TokenEndpoint::PATH).form(serde_json::json!({
+                "grant_type": "authorization_code",
+                "code": code,
+                "redirect_uri": grant.redirect_uri,
+                "client_id": client.client_id,
+            }));
+
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::OK);
+        let AccessTokenResponse {
+            access_token,
+            id_token,
+            ..
+        } = response.json();
+
+        // The ID token is a nested JWT, signed then encrypted
+        let id_token = id_token.expect("to have an ID token");
+        let (header, payload) = mas_jose::jwe::decrypt(&id_token, &decryption_key).unwrap();
+        assert_eq!(header.kid(), Some("enc"));
+        assert_eq!(header.cty(), Some("JWT"));
+
+        let payload = String::from_utf8(payload).unwrap();
+        let jwt: mas_jose::jwt::Jwt<'_, serde_json::Value> = payload.as_str().try_into().unwrap();
+        jwt.verify_with_jwks(&state.key_store.public_jwks())
+            .unwrap();
+        assert_eq!(jwt.payload()["sub"], user.sub);
+        assert_eq!(jwt.payload()["nonce"], "nonce");
+
+        // The userinfo response is encrypted JSON
+        let request = Request::get(mas_router::OidcUserinfo::PATH)
+            .bearer(&access_token)
+            .empty();
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::OK);
+        response.assert_header_value(hyper::header::CONTENT_TYPE, "application/jwt");
+
+        let (header, payload) = mas_jose::jwe::decrypt(response.body(), &decryption_key).unwrap();
+        assert_eq!(header.cty(), None);
+        let user_info: serde_json::Value = serde_json::from_slice(&payload).unwrap();
+        assert_eq!(user_info["sub"], user.sub);
+        assert_eq!(user_info["username"], "alice");
+    }
+
     #[sqlx::test(migrator = "mas_storage_pg::MIGRATOR")]
     async fn test_refresh_token_grant(pool: PgPool) {
         setup();
@@ -1281,6 +1612,8 @@ mod tests {
                 &state.clock,
                 &mut repo,
                 &session,
+                Ulid::from_datetime_with_source(state.clock.now().into(), &mut state.rng()),
+                TokenType::AccessToken.generate(&mut state.rng()),
                 Duration::microseconds(5 * 60 * 1000 * 1000),
             )
             .await
@@ -1403,6 +1736,8 @@ mod tests {
                 &state.clock,
                 &mut repo,
                 &session,
+                Ulid::from_datetime_with_source(state.clock.now().into(), &mut state.rng()),
+                TokenType::AccessToken.generate(&mut state.rng()),
                 Duration::microseconds(5 * 60 * 1000 * 1000),
             )
             .await
@@ -1552,6 +1887,128 @@ mod tests {
         eighth_response.assert_status(StatusCode::BAD_REQUEST);
     }
 
+    #[sqlx::test(migrator = "mas_storage_pg::MIGRATOR")]
+    async fn test_refresh_token_replay_revokes_session(pool: PgPool) {
+        setup();
+        let site_config = SiteConfig {
+            revoke_session_on_refresh_token_replay: true,
+            ..crate::test_utils::test_site_config()
+        };
+        let state = TestState::from_pool_with_site_config(pool, site_config)
+            .await
+            .unwrap();
+
+        // Provision a client
+        let request =
+            Request::post(mas_router::OAuth2RegistrationEndpoint::PATH).json(serde_json::json!({
+                "client_uri": "https://example.com/",
+                "redirect_uris": ["https://example.com/callback"],
+                "token_endpoint_auth_method": "none",
+                "response_types": ["code"],
+                "grant_types": ["authorization_code", "refresh_token"],
+            }));
+
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::CREATED);
+
+        let ClientRegistrationResponse { client_id, .. } = response.json();
+
+        // Provision a user and a session with a token pair
+        let mut repo = state.repository().await.unwrap();
+
+        let user = repo
+            .user()
+            .add(&mut state.rng(), &state.clock, "alice".to_owned())
+            .await
+            .unwrap();
+
+        let browser_session = repo
+            .browser_session()
+            .add(&mut state.rng(), &state.clock, &user, None)
+            .await
+            .unwrap();
+
+        let client = repo
+            .oauth2_client()
+            .find_by_client_id(&client_id)
+            .await
+            .unwrap()
+            .unwrap();
+
+        let session = repo
+            .oauth2_session()
+            .add_from_browser_session(
+                &mut state.rng(),
+                &state.clock,
+                &client,
+                &browser_session,
+                Scope::from_iter([OPENID]),
+            )
+            .await
+            .unwrap();
+
+        let (_, RefreshToken { refresh_token, .. }) = generate_token_pair(
+            &mut state.rng(),
+            &state.clock,
+            &mut repo,
+            &session,
+            Ulid::from_datetime_with_source(state.clock.now().into(), &mut state.rng()),
+            TokenType::AccessToken.generate(&mut state.rng()),
+            Duration::microseconds(5 * 60 * 1000 * 1000),
+        )
+        .await
+        .unwrap();
+
+        repo.save().await.unwrap();
+
+        // Refresh once, and use the new access token
+        let request =
+            Request::post(mas_router::OAuth2TokenEndpoint::PATH).form(serde_json::json!({
+                "grant_type": "refresh_token",
+                "refresh_token": refresh_token,
+                "client_id": client.client_id,
+            }));
+
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::OK);
+        let response: AccessTokenResponse = response.json();
+        assert!(state.is_access_token_valid(&response.access_token).await);
+
+        // Replaying the first refresh token should fail, and finish the whole session
+        let request =
+            Request::post(mas_router::OAuth2TokenEndpoint::PATH).form(serde_json::json!({
+                "grant_type": "refresh_token",
+                "refresh_token": refresh_token,
+                "client_id": client.client_id,
+            }));
+
+        let replay_response = state.request(request).await;
+        replay_response.assert_status(StatusCode::BAD_REQUEST);
+
+        let mut repo = state.repository().await.unwrap();
+        let session = repo
+            .oauth2_session()
+            .lookup(session.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(session.is_finished());
+        repo.cancel().await.unwrap();
+
+        // The latest tokens are no longer usable
+        assert!(!state.is_access_token_valid(&response.access_token).await);
+
+        let request =
+            Request::post(mas_router::OAuth2TokenEndpoint::PATH).form(serde_json::json!({
+                "grant_type": "refresh_token",
+                "refresh_token": response.refresh_token,
+                "client_id": client.client_id,
+            }));
+
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::BAD_REQUEST);
+    }
+
     #[sqlx::test(migrator = "mas_storage_pg::MIGRATOR")]
     async fn test_client_credentials(pool: PgPool) {
         setup();
@@ -1916,4 +2373,118 @@ mod tests {
         let ClientError { error, .. } = response.json();
         assert_eq!(error, ClientErrorCode::UnsupportedGrantType);
     }
+
+    #[sqlx::test(migrator = "mas_storage_pg::MIGRATOR")]
+    async fn test_jwt_access_tokens(pool: PgPool) {
+        setup();
+        let site_config = SiteConfig {
+            jwt_access_tokens: Some(mas_data_model::JwtAccessTokensConfig {
+                clients: Vec::new(),
+                scopes: vec!["urn:mas:graphql:*".to_owned()],
+                audience: Some("https://matrix.example.com/".to_owned()),
+            }),
+            ..crate::test_utils::test_site_config()
+        };
+        let state = TestState::from_pool_with_site_config(pool, site_config)
+            .await
+            .unwrap();
+
+        // Provision a client
+        let request =
+            Request::post(mas_router::OAuth2RegistrationEndpoint::PATH).json(serde_json::json!({
+                "client_uri": "https://example.com/",
+                "token_endpoint_auth_method": "client_secret_post",
+                "grant_types": ["client_credentials"],
+            }));
+
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::CREATED);
+
+        let response: ClientRegistrationResponse = response.json();
+        let client_id = response.client_id;
+        let client_secret = response.client_secret.expect("to have a client secret");
+
+        // Without the configured scope, we get an opaque token
+        let request =
+            Request::post(mas_router::OAuth2TokenEndpoint::PATH).form(serde_json::json!({
+                "grant_type": "client_credentials",
+                "client_id": client_id,
+                "client_secret": client_secret,
+            }));
+
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::OK);
+        let response: AccessTokenResponse = response.json();
+        assert!(response.access_token.starts_with("mat_"));
+
+        // With it, we get a signed JWT
+        let request =
+            Request::post(mas_router::OAuth2TokenEndpoint::PATH).form(serde_json::json!({
+                "grant_type": "client_credentials",
+                "client_id": client_id,
+                "client_secret": client_secret,
+                "scope": "urn:mas:graphql:*",
+            }));
+
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::OK);
+        let response: AccessTokenResponse = response.json();
+        let access_token = response.access_token;
+
+        let jwt: mas_jose::jwt::Jwt<'_, serde_json::Value> =
+            access_token.as_str().try_into().unwrap();
+        jwt.verify_with_jwks(&state.key_store.public_jwks())
+            .unwrap();
+        assert_eq!(jwt.header().typ(), Some("at+jwt"));
+        let claims = jwt.payload();
+        assert_eq!(claims["iss"], state.url_builder.oidc_issuer().as_str());
+        assert_eq!(claims["sub"], client_id);
+        assert_eq!(claims["client_id"], client_id);
+        assert_eq!(claims["aud"], "https://matrix.example.com/");
+        assert_eq!(claims["scope"], "urn:mas:graphql:*");
+        assert!(claims["exp"].is_number());
+
+        // The jti is the ID of the stored access token
+        let mut repo = state.repository().await.unwrap();
+        let stored = repo
+            .oauth2_access_token()
+            .find_by_token(&access_token)
+            .await
+            .unwrap()
+            .unwrap();
+        repo.cancel().await.unwrap();
+        assert_eq!(claims["jti"], stored.jti());
+
+        // It can still be introspected
+        let request =
+            Request::post(mas_router::OAuth2Introspection::PATH).form(serde_json::json!({
+                "token": access_token,
+                "client_id": client_id,
+                "client_secret": client_secret,
+            }));
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::OK);
+        let response: serde_json::Value = response.json();
+        assert_eq!(response["active"], true);
+
+        // And revoked
+        let request = Request::post(mas_router::OAuth2Revocation::PATH).form(serde_json::json!({
+            "token": access_token,
+            "client_id": client_id,
+            "client_secret": client_secret,
+        }));
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::OK);
+
+        let request =
+            Request::post(mas_router::OAuth2Introspection::PATH).form(serde_json::json!({
+                "token": access_token,
+                "client_id": client_id,
+                "client_secret": client_secret,
+            }));
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::OK);
+        let response: serde_json::Value = response.json();
+        assert_eq!(response["active"], false);
+    }
 }
@@ -11,7 +11,7 @@ use axum::{
 };
 use hyper::StatusCode;
 use mas_axum_utils::{
-    jwt::JwtResponse,
+    jwt::{EncryptedJwtResponse, JwtResponse},
     record_error,
     user_authorization::{AuthorizationVerificationError, UserAuthorization},
 };
@@ -28,7 +28,9 @@ use serde_with::skip_serializing_none;
 use thiserror::Error;
 use ulid::Ulid;
 
-use crate::{BoundActivityTracker, impl_from_error_for_route};
+use crate::{
+    BoundActivityTracker, MetadataCache, impl_from_error_for_route, oauth2::encrypt_response,
+};
 
 #[skip_serializing_none]
 #[derive(Serialize)]
@@ -71,6 +73,8 @@ pub enum RouteError {
 impl_from_error_for_route!(mas_storage::RepositoryError);
 impl_from_error_for_route!(mas_keystore::WrongAlgorithmError);
 impl_from_error_for_route!(mas_jose::jwt::JwtSignatureError);
+impl_from_error_for_route!(serde_json::Error);
+impl_from_error_for_route!(super::ResponseEncryptionError);
 
 impl IntoResponse for RouteError {
     fn into_response(self) -> axum::response::Response {
@@ -105,6 +109,8 @@ pub async fn get(
     activity_tracker: BoundActivityTracker,
     mut repo: BoxRepository,
     State(key_store): State<Keystore>,
+    State(http_client): State<reqwest::Client>,
+    State(metadata_cache): State<MetadataCache>,
     user_authorization: UserAuthorization,
 ) -> Result<Response, RouteError> {
     let session = user_authorization.protected(&mut repo, &clock).await?;
@@ -142,7 +148,9 @@ pub async fn get(
 
     repo.save().await?;
 
-    if let Some(alg) = client.userinfo_signed_response_alg {
+    let encryption = client.userinfo_encrypted_response();
+
+    if let Some(alg) = client.userinfo_signed_response_alg.clone() {
         let key = key_store
             .signing_key_for_algorithm(&alg)
             .ok_or(RouteError::InvalidSigningKey)?;
@@ -153,12 +161,41 @@ pub async fn get(
 
         let user_info = SignedUserInfo {
             iss: url_builder.oidc_issuer().to_string(),
-            aud: client.client_id,
+            aud: client.client_id.clone(),
             user_info,
         };
 
         let token = Jwt::sign_with_rng(&mut rng, header, user_info, &signer)?;
-        Ok(JwtResponse(token).into_response())
+
+        if let Some(encryption) = encryption {
+            // Encrypt the signed response as a nested JWT
+            let token = encrypt_response(
+                &mut rng,
+                &http_client,
+                &metadata_cache,
+                &client,
+                encryption,
+                token.as_str().as_bytes(),
+                Some("JWT"),
+            )
+            .await?;
+            Ok(EncryptedJwtResponse(token).into_response())
+        } else {
+            Ok(JwtResponse(token).into_response())
+        }
+    } else if let Some(encryption) = encryption {
+        let payload = serde_json::to_vec(&user_info)?;
+        let token = encrypt_response(
+            &mut rng,
+            &http_client,
+            &metadata_cache,
+            &client,
+            encryption,
+            &payload,
+            None,
+        )
+        .await?;
+        Ok(EncryptedJwtResponse(token).into_response())
     } else {
         Ok(Json(user_info).into_response())
     }
@@ -130,6 +130,15 @@ impl PasswordManager {
         self.inner.is_some()
     }
 
+    /// Checks if a hashing scheme with the given version is configured, either
+    /// as the current scheme or as an old one kept for verification
+    #[must_use]
+    pub fn has_scheme(&self, version: SchemeVersion) -> bool {
+        self.inner.as_ref().is_some_and(|inner| {
+            inner.current_version == version || inner.other_hashers.contains_key(&version)
+        })
+    }
+
     /// Get the inner password manager
     ///
     /// # Errors
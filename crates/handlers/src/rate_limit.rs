@@ -29,6 +29,15 @@ pub enum PasswordCheckLimitedError {
     User(Ulid),
 }
 
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum DirectoryPasswordCheckLimitedError {
+    #[error("Too many password checks for requester {0}")]
+    Requester(RequesterFingerprint),
+
+    #[error("Too many password checks for username {0:?}")]
+    Username(String),
+}
+
 #[derive(Debug, Clone, thiserror::Error)]
 pub enum RegistrationLimitedError {
     #[error("Too many account registration requests for requester {0}")]
@@ -89,6 +98,7 @@ struct LimiterInner {
     account_recovery_per_email: KeyedRateLimiter<String>,
     password_check_for_requester: KeyedRateLimiter<RequesterFingerprint>,
     password_check_for_user: KeyedRateLimiter<Ulid>,
+    password_check_for_directory_username: KeyedRateLimiter<String>,
     registration_per_requester: KeyedRateLimiter<RequesterFingerprint>,
     email_authentication_per_requester: KeyedRateLimiter<RequesterFingerprint>,
     email_authentication_per_email: KeyedRateLimiter<String>,
@@ -107,6 +117,9 @@ impl LimiterInner {
             ),
             password_check_for_requester: RateLimiter::keyed(config.login.per_ip.to_quota()?),
             password_check_for_user: RateLimiter::keyed(config.login.per_account.to_quota()?),
+            password_check_for_directory_username: RateLimiter::keyed(
+                config.login.per_account.to_quota()?,
+            ),
             registration_per_requester: RateLimiter::keyed(config.registration.to_quota()?),
             email_authentication_per_email: RateLimiter::keyed(
                 config.email_authentication.per_address.to_quota()?,
@@ -154,6 +167,9 @@ impl Limiter {
                 this.inner.account_recovery_per_requester.retain_recent();
                 this.inner.password_check_for_requester.retain_recent();
                 this.inner.password_check_for_user.retain_recent();
+                this.inner
+                    .password_check_for_directory_username
+                    .retain_recent();
                 this.inner.registration_per_requester.retain_recent();
                 this.inner.email_authentication_per_email.retain_recent();
                 this.inner
@@ -221,6 +237,35 @@ impl Limiter {
         Ok(())
     }
 
+    /// Check if a password check against the directory can be performed
+    ///
+    /// Users are keyed on the username they typed, as they may not exist
+    /// locally before their first login.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the operation is rate limited
+    pub fn check_directory_password(
+        &self,
+        key: RequesterFingerprint,
+        username: &str,
+    ) -> Result<(), DirectoryPasswordCheckLimitedError> {
+        self.inner
+            .password_check_for_requester
+            .check_key(&key)
+            .map_err(|_| DirectoryPasswordCheckLimitedError::Requester(key))?;
+
+        // Directories usually compare usernames case-insensitively, so this
+        // prevents bypassing the limit with different case variations
+        let canonical_username = username.to_lowercase();
+        self.inner
+            .password_check_for_directory_username
+            .check_key(&canonical_username)
+            .map_err(|_| DirectoryPasswordCheckLimitedError::Username(canonical_username))?;
+
+        Ok(())
+    }
+
     /// Check if an account registration can be performed
     ///
     /// # Errors
@@ -373,4 +418,62 @@ mod tests {
         // The other account isn't rate-limited
         assert!(limiter.check_password(requesters[603], &bob).is_ok());
     }
+
+    #[test]
+    fn test_directory_password_check_limiter() {
+        let limiter = Limiter::new(&RateLimitingConfig::default()).unwrap();
+
+        let requesters: Vec<_> = (0..=255)
+            .flat_map(|a| (0..3).map(move |b| RequesterFingerprint::new([a, a, b, b].into())))
+            .collect();
+
+        // Three times the same IP address should be allowed
+        for _ in 0..3 {
+            assert!(
+                limiter
+                    .check_directory_password(requesters[0], "alice")
+                    .is_ok()
+            );
+        }
+
+        // But the fourth time should be rejected
+        assert!(
+            limiter
+                .check_directory_password(requesters[0], "alice")
+                .is_err()
+        );
+
+        // Changing the case of the username doesn't bypass the account-level limit
+        for requester in requesters.iter().skip(1).take(599) {
+            assert!(
+                limiter
+                    .check_directory_password(*requester, "alice")
+                    .is_ok()
+            );
+            assert!(
+                limiter
+                    .check_directory_password(*requester, "Alice")
+                    .is_ok()
+            );
+            assert!(
+                limiter
+                    .check_directory_password(*requester, "ALICE")
+                    .is_ok()
+            );
+        }
+
+        // We now have consumed 3+599*3 = 1800 cells on the username
+        assert!(
+            limiter
+                .check_directory_password(requesters[600], "aLiCe")
+                .is_err()
+        );
+
+        // Other usernames aren't rate-limited
+        assert!(
+            limiter
+                .check_directory_password(requesters[600], "bob")
+                .is_ok()
+        );
+    }
 }
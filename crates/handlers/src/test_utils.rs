@@ -32,6 +32,7 @@ use mas_data_model::{AppVersion, BoxClock, BoxRng, SiteConfig, clock::MockClock}
 use mas_email::{MailTransport, Mailer};
 use mas_i18n::Translator;
 use mas_keystore::{Encrypter, JsonWebKey, JsonWebKeySet, Keystore, PrivateKey};
+use mas_ldap::Directory;
 use mas_matrix::{HomeserverConnection, MockHomeserverConnection};
 use mas_policy::{InstantiateError, Policy, PolicyFactory};
 use mas_router::{SimpleRoute, UrlBuilder};
@@ -52,9 +53,10 @@ use tower::{Layer, Service, ServiceExt};
 use url::Url;
 
 use crate::{
-    ActivityTracker, BoundActivityTracker, Limiter, RequesterFingerprint, graphql,
+    ActivityTracker, BoundActivityTracker, IntrospectionCache, Limiter, RequesterFingerprint,
+    graphql,
     passwords::{Hasher, PasswordManager},
-    upstream_oauth2::cache::MetadataCache,
+    upstream_oauth2::cache::{ClientCertificateCache, MetadataCache},
 };
 
 /// Setup rustcrypto and tracing for tests.
@@ -84,6 +86,8 @@ pub(crate) async fn policy_factory(
         authorization_grant: "authorization_grant/violation".to_owned(),
         compat_login: "compat_login/violation".to_owned(),
         email: "email/violation".to_owned(),
+        upstream_oauth_link: "upstream_oauth_link/violation".to_owned(),
+        compat_token_anomaly: "compat_token_anomaly/violation".to_owned(),
     };
 
     let data = mas_policy::Data::new(server_name.to_owned(), None).with_rest(data);
@@ -100,15 +104,18 @@ pub(crate) struct TestState {
     pub key_store: Keystore,
     pub cookie_manager: CookieManager,
     pub metadata_cache: MetadataCache,
+    pub certificate_cache: ClientCertificateCache,
     pub encrypter: Encrypter,
     pub url_builder: UrlBuilder,
     pub homeserver_connection: Arc<MockHomeserverConnection>,
     pub policy_factory: Arc<PolicyFactory>,
     pub graphql_schema: graphql::Schema,
     pub password_manager: PasswordManager,
+    pub directory: Option<Arc<dyn Directory>>,
     pub site_config: SiteConfig,
     pub activity_tracker: ActivityTracker,
     pub limiter: Limiter,
+    pub introspection_cache: IntrospectionCache,
     pub clock: Arc<MockClock>,
     pub rng: Arc<Mutex<ChaChaRng>>,
     pub http_client: reqwest::Client,
@@ -136,6 +143,7 @@ pub fn test_site_config() -> SiteConfig {
         tos_uri: Some("https://example.com/tos".parse().unwrap()),
         imprint: None,
         password_login_enabled: true,
+        ldap_adopt_existing_users: false,
         password_registration_enabled: true,
         registration_token_required: false,
         email_change_allowed: true,
@@ -150,6 +158,10 @@ pub fn test_site_config() -> SiteConfig {
         login_with_email_allowed: true,
         plan_management_iframe_uri: None,
         session_limit: None,
+        signing_key_rotation: None,
+        jwt_access_tokens: None,
+        revoke_session_on_refresh_token_replay: false,
+        compat_token_anomaly_detection: None,
     }
 }
 
@@ -197,6 +209,7 @@ impl TestState {
         let cookie_manager = CookieManager::derive_from(url_builder.http_base(), &[0x42; 32]);
 
         let metadata_cache = MetadataCache::new();
+        let certificate_cache = ClientCertificateCache::new();
 
         let password_manager = if site_config.password_login_enabled {
             PasswordManager::new(
@@ -228,6 +241,7 @@ impl TestState {
             password_manager: password_manager.clone(),
             url_builder: url_builder.clone(),
             limiter: limiter.clone(),
+            introspection_cache: IntrospectionCache::default(),
         };
         let state: crate::graphql::BoxState = Box::new(graphql_state);
 
@@ -254,6 +268,14 @@ impl TestState {
             homeserver_connection.clone(),
             url_builder.clone(),
             &site_config,
+            &encrypter,
+            Arc::new(crate::UpstreamClaimsSyncer::new(
+                http_client.clone(),
+                metadata_cache.clone(),
+                certificate_cache.clone(),
+                key_store.clone(),
+                encrypter.clone(),
+            )),
             shutdown_token.child_token(),
         )
         .await
@@ -267,15 +289,19 @@ impl TestState {
             key_store,
             cookie_manager,
             metadata_cache,
+            certificate_cache,
             encrypter,
             url_builder,
             homeserver_connection,
             policy_factory,
             graphql_schema,
             password_manager,
+            directory: None,
             site_config,
             activity_tracker,
             limiter,
+            // The introspection cache is disabled by default in tests
+            introspection_cache: IntrospectionCache::default(),
             clock,
             rng,
             http_client,
@@ -441,6 +467,7 @@ struct TestGraphQLState {
     password_manager: PasswordManager,
     url_builder: UrlBuilder,
     limiter: Limiter,
+    introspection_cache: IntrospectionCache,
 }
 
 #[async_trait::async_trait]
@@ -477,6 +504,10 @@ impl graphql::State for TestGraphQLState {
         &self.limiter
     }
 
+    fn introspection_cache(&self) -> &IntrospectionCache {
+        &self.introspection_cache
+    }
+
     fn rng(&self) -> BoxRng {
         let mut parent_rng = self.rng.lock().expect("Failed to lock RNG");
         let rng = ChaChaRng::from_rng(&mut *parent_rng).expect("Failed to seed RNG");
@@ -538,6 +569,12 @@ impl FromRef<TestState> for PasswordManager {
     }
 }
 
+impl FromRef<TestState> for Option<Arc<dyn Directory>> {
+    fn from_ref(input: &TestState) -> Self {
+        input.directory.clone()
+    }
+}
+
 impl FromRef<TestState> for CookieManager {
     fn from_ref(input: &TestState) -> Self {
         input.cookie_manager.clone()
@@ -550,6 +587,12 @@ impl FromRef<TestState> for MetadataCache {
     }
 }
 
+impl FromRef<TestState> for ClientCertificateCache {
+    fn from_ref(input: &TestState) -> Self {
+        input.certificate_cache.clone()
+    }
+}
+
 impl FromRef<TestState> for SiteConfig {
     fn from_ref(input: &TestState) -> Self {
         input.site_config.clone()
@@ -574,6 +617,12 @@ impl FromRef<TestState> for Limiter {
     }
 }
 
+impl FromRef<TestState> for IntrospectionCache {
+    fn from_ref(input: &TestState) -> Self {
+        input.introspection_cache.clone()
+    }
+}
+
 impl FromRef<TestState> for reqwest::Client {
     fn from_ref(input: &TestState) -> Self {
         input.http_client.clone()
@@ -6,24 +6,31 @@
 
 use axum::{
     extract::{Path, State},
-    response::{IntoResponse, Redirect},
+    response::{Html, IntoResponse, Redirect, Response},
 };
 use axum_extra::extract::Query;
 use hyper::StatusCode;
 use mas_axum_utils::{GenericError, InternalError, cookies::CookieJar};
-use mas_data_model::{BoxClock, BoxRng, UpstreamOAuthProvider};
+use mas_data_model::{
+    BoxClock, BoxRng, Clock, UpstreamOAuthProvider, UpstreamOAuthProviderSamlBinding,
+};
+use mas_keystore::Encrypter;
 use mas_oidc_client::requests::authorization_code::AuthorizationRequestData;
 use mas_router::{PostAuthAction, UrlBuilder};
+use mas_saml::request::AuthnRequest;
 use mas_storage::{
     BoxRepository,
     upstream_oauth2::{UpstreamOAuthProviderRepository, UpstreamOAuthSessionRepository},
 };
+use mas_templates::{FormPostContext, Templates};
+use rand::distributions::{Alphanumeric, DistString};
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 use ulid::Ulid;
 
-use super::{UpstreamSessionsCookie, cache::LazyProviderInfos};
+use super::{UpstreamSessionsCookie, cache::LazyProviderInfos, saml::SamlSettings};
 use crate::{
-    impl_from_error_for_route, upstream_oauth2::cache::MetadataCache,
+    PreferredLanguage, impl_from_error_for_route, upstream_oauth2::cache::MetadataCache,
     views::shared::OptionalPostAuthAction,
 };
 
@@ -39,6 +46,8 @@ pub(crate) enum RouteError {
 impl_from_error_for_route!(mas_oidc_client::error::DiscoveryError);
 impl_from_error_for_route!(mas_oidc_client::error::AuthorizationError);
 impl_from_error_for_route!(mas_storage::RepositoryError);
+impl_from_error_for_route!(mas_templates::TemplateError);
+impl_from_error_for_route!(super::saml::SamlSettingsError);
 
 impl IntoResponse for RouteError {
     fn into_response(self) -> axum::response::Response {
@@ -51,11 +60,27 @@ impl IntoResponse for RouteError {
     }
 }
 
+#[derive(Deserialize)]
+pub(crate) struct AuthorizeParams {
+    login_hint: Option<String>,
+}
+
+/// The form fields of the HTTP-POST binding
+#[derive(Serialize)]
+struct SamlPostParams {
+    #[serde(rename = "SAMLRequest")]
+    saml_request: String,
+
+    #[serde(rename = "RelayState")]
+    relay_state: String,
+}
+
 #[tracing::instrument(
     name = "handlers.upstream_oauth2.authorize.get",
     fields(upstream_oauth_provider.id = %provider_id),
     skip_all,
 )]
+#[allow(clippy::too_many_arguments)]
 pub(crate) async fn get(
     mut rng: BoxRng,
     clock: BoxClock,
@@ -63,10 +88,14 @@ pub(crate) async fn get(
     mut repo: BoxRepository,
     State(url_builder): State<UrlBuilder>,
     State(http_client): State<reqwest::Client>,
+    State(encrypter): State<Encrypter>,
+    State(templates): State<Templates>,
+    PreferredLanguage(locale): PreferredLanguage,
     cookie_jar: CookieJar,
     Path(provider_id): Path<Ulid>,
     Query(query): Query<OptionalPostAuthAction>,
-) -> Result<impl IntoResponse, RouteError> {
+    Query(params): Query<AuthorizeParams>,
+) -> Result<Response, RouteError> {
     let provider = repo
         .upstream_oauth_provider()
         .lookup(provider_id)
@@ -74,6 +103,66 @@ pub(crate) async fn get(
         .filter(UpstreamOAuthProvider::enabled)
         .ok_or(RouteError::ProviderNotFound)?;
 
+    // SAML providers don't go through discovery, and send an `AuthnRequest`
+    // instead of an authorization request
+    if provider.saml.is_some() {
+        let settings = SamlSettings::load(&provider, &encrypter)?;
+
+        let relay_state = Alphanumeric.sample_string(&mut rng, 16);
+        let request = AuthnRequest::new(
+            &mut rng,
+            clock.now(),
+            provider.client_id.clone(),
+            settings.config.sso_url.clone(),
+            url_builder.upstream_saml2_acs(provider.id),
+        )
+        .with_name_id_format(settings.config.name_id_format.clone());
+
+        // The ID of the request is saved as the nonce, as the response must
+        // reference it
+        let session = repo
+            .upstream_oauth_session()
+            .add(
+                &mut rng,
+                &clock,
+                &provider,
+                relay_state.clone(),
+                None,
+                Some(request.id.clone()),
+            )
+            .await?;
+
+        let cookie_jar = UpstreamSessionsCookie::load(&cookie_jar)
+            .add(
+                session.id,
+                provider.id,
+                relay_state.clone(),
+                query.post_auth_action,
+            )
+            .save(cookie_jar, &clock);
+
+        repo.save().await?;
+
+        let response = match settings.config.sso_binding {
+            UpstreamOAuthProviderSamlBinding::Redirect => {
+                let url = request.redirect_url(&relay_state, settings.signing_key());
+                Redirect::temporary(url.as_str()).into_response()
+            }
+            UpstreamOAuthProviderSamlBinding::Post => {
+                let params = SamlPostParams {
+                    saml_request: request
+                        .post_form_value(settings.signing_key(), settings.sp_certificate.as_ref()),
+                    relay_state,
+                };
+                let context = FormPostContext::new_for_url(settings.config.sso_url.clone(), params)
+                    .with_language(&locale);
+                Html(templates.render_form_post(&context)?).into_response()
+            }
+        };
+
+        return Ok((cookie_jar, response).into_response());
+    }
+
     // First, discover the provider
     // This is done lazyly according to provider.discovery_mode and the various
     // endpoint overrides
@@ -93,16 +182,23 @@ pub(crate) async fn get(
     }
 
     // Forward the raw login hint upstream for the provider to handle however it
-    // sees fit
-    if provider.forward_login_hint
-        && let Some(PostAuthAction::ContinueAuthorizationGrant { id }) = &query.post_auth_action
-        && let Some(login_hint) = repo
-            .oauth2_authorization_grant()
-            .lookup(*id)
-            .await?
-            .and_then(|grant| grant.login_hint)
-    {
-        data = data.with_login_hint(login_hint);
+    // sees fit. The one from the authorization grant takes precedence over the
+    // one passed in the query, which is set by initiated logins.
+    if provider.forward_login_hint {
+        let grant_login_hint = if let Some(PostAuthAction::ContinueAuthorizationGrant { id }) =
+            &query.post_auth_action
+        {
+            repo.oauth2_authorization_grant()
+                .lookup(*id)
+                .await?
+                .and_then(|grant| grant.login_hint)
+        } else {
+            None
+        };
+
+        if let Some(login_hint) = grant_login_hint.or(params.login_hint) {
+            data = data.with_login_hint(login_hint);
+        }
     }
 
     let data = if let Some(methods) = lazy_metadata.pkce_methods().await? {
@@ -145,5 +241,5 @@ pub(crate) async fn get(
 
     repo.save().await?;
 
-    Ok((cookie_jar, Redirect::temporary(url.as_str())))
+    Ok((cookie_jar, Redirect::temporary(url.as_str())).into_response())
 }
@@ -4,19 +4,40 @@
 // SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
 // Please see LICENSE files in the repository root for full details.
 
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::HashMap,
+    sync::{Arc, LazyLock, PoisonError},
+};
 
 use mas_context::LogContext;
 use mas_data_model::{
     UpstreamOAuthProvider, UpstreamOAuthProviderDiscoveryMode, UpstreamOAuthProviderPkceMode,
 };
 use mas_iana::oauth::PkceCodeChallengeMethod;
-use mas_oidc_client::error::DiscoveryError;
-use mas_storage::{RepositoryAccess, upstream_oauth2::UpstreamOAuthProviderRepository};
+use mas_jose::jwk::PublicJsonWebKeySet;
+use mas_oidc_client::error::{DiscoveryError, JwksError};
+use mas_storage::{
+    BoxRepositoryFactory, RepositoryAccess, RepositoryError,
+    upstream_oauth2::UpstreamOAuthProviderRepository,
+};
 use oauth2_types::oidc::VerifiedProviderMetadata;
+use opentelemetry::{Key, KeyValue, metrics::Histogram};
 use tokio::sync::RwLock;
+use ulid::Ulid;
 use url::Url;
 
+use crate::METER;
+
+static FETCH_DURATION_HISTOGRAM: LazyLock<Histogram<u64>> = LazyLock::new(|| {
+    METER
+        .u64_histogram("mas.upstream_oauth2.metadata.fetch_duration")
+        .with_description("The time it took to fetch the metadata of an upstream provider")
+        .with_unit("ms")
+        .build()
+});
+const ISSUER: Key = Key::from_static_str("issuer");
+const RESULT: Key = Key::from_static_str("result");
+
 /// A high-level layer over metadata cache and provider configuration, which
 /// resolves endpoint overrides and discovery modes.
 pub struct LazyProviderInfos<'a> {
@@ -142,7 +163,7 @@ impl<'a> LazyProviderInfos<'a> {
     }
 }
 
-/// A simple OIDC metadata cache
+/// A simple OIDC metadata and JWKS cache
 ///
 /// It never evicts entries, does not cache failures and has no locking.
 /// It can also be refreshed in the background, and warmed up on startup.
@@ -152,6 +173,7 @@ impl<'a> LazyProviderInfos<'a> {
 pub struct MetadataCache {
     cache: Arc<RwLock<HashMap<String, Arc<VerifiedProviderMetadata>>>>,
     insecure_cache: Arc<RwLock<HashMap<String, Arc<VerifiedProviderMetadata>>>>,
+    jwks_cache: Arc<RwLock<HashMap<Url, Arc<PublicJsonWebKeySet>>>>,
 }
 
 impl MetadataCache {
@@ -209,8 +231,37 @@ impl MetadataCache {
         }))
     }
 
+    /// Fetch the metadata for the given issuer, bypassing and updating the
+    /// cache
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the metadata could not be retrieved.
     #[tracing::instrument(name = "metadata_cache.fetch", fields(%issuer), skip_all)]
-    async fn fetch(
+    pub(crate) async fn fetch(
+        &self,
+        client: &reqwest::Client,
+        issuer: &str,
+        verify: bool,
+    ) -> Result<Arc<VerifiedProviderMetadata>, DiscoveryError> {
+        let start = std::time::Instant::now();
+        let res = self.fetch_inner(client, issuer, verify).await;
+
+        let duration = start.elapsed();
+        let duration_ms = duration.as_millis().try_into().unwrap_or(u64::MAX);
+        let result = if res.is_ok() { "success" } else { "failure" };
+        FETCH_DURATION_HISTOGRAM.record(
+            duration_ms,
+            &[
+                KeyValue::new(ISSUER, issuer.to_owned()),
+                KeyValue::new(RESULT, result),
+            ],
+        );
+
+        res
+    }
+
+    async fn fetch_inner(
         &self,
         client: &reqwest::Client,
         issuer: &str,
@@ -268,6 +319,49 @@ impl MetadataCache {
         Ok(metadata)
     }
 
+    /// Fetch the JWKS at the given URI, bypassing and updating the cache
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the JWKS could not be retrieved.
+    #[tracing::instrument(name = "metadata_cache.fetch_jwks", fields(%jwks_uri), skip_all)]
+    pub async fn fetch_jwks(
+        &self,
+        client: &reqwest::Client,
+        jwks_uri: &Url,
+    ) -> Result<Arc<PublicJsonWebKeySet>, JwksError> {
+        let jwks = mas_oidc_client::requests::jose::fetch_jwks(client, jwks_uri).await?;
+        let jwks = Arc::new(jwks);
+
+        self.jwks_cache
+            .write()
+            .await
+            .insert(jwks_uri.clone(), jwks.clone());
+
+        Ok(jwks)
+    }
+
+    /// Get the JWKS at the given URI.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the JWKS could not be retrieved.
+    #[tracing::instrument(name = "metadata_cache.get_jwks", fields(%jwks_uri), skip_all)]
+    pub async fn get_jwks(
+        &self,
+        client: &reqwest::Client,
+        jwks_uri: &Url,
+    ) -> Result<Arc<PublicJsonWebKeySet>, JwksError> {
+        let cache = self.jwks_cache.read().await;
+        if let Some(jwks) = cache.get(jwks_uri) {
+            return Ok(Arc::clone(jwks));
+        }
+        // Drop the cache guard so that we don't deadlock when we try to fetch
+        drop(cache);
+
+        self.fetch_jwks(client, jwks_uri).await
+    }
+
     #[tracing::instrument(name = "metadata_cache.refresh_all", skip_all)]
     async fn refresh_all(&self, client: &reqwest::Client) {
         // Grab all the keys first to avoid locking the cache for too long
@@ -293,6 +387,124 @@ impl MetadataCache {
                 tracing::error!(issuer = %issuer, error = &e as &dyn std::error::Error, "Failed to refresh provider metadata");
             }
         }
+
+        // And refresh the key sets, in case the providers rotated their keys
+        let keys: Vec<Url> = {
+            let cache = self.jwks_cache.read().await;
+            cache.keys().cloned().collect()
+        };
+
+        for jwks_uri in keys {
+            if let Err(e) = self.fetch_jwks(client, &jwks_uri).await {
+                tracing::error!(jwks_uri = %jwks_uri, error = &e as &dyn std::error::Error, "Failed to refresh provider JWKS");
+            }
+        }
+    }
+}
+
+/// A cache of the HTTP clients presenting the client certificate of upstream
+/// providers
+///
+/// Each client is built once and reused until the client keys of its provider
+/// change. Clients of providers which were removed, disabled or lost their
+/// client certificate are evicted in the background.
+#[derive(Debug, Clone, Default)]
+pub struct ClientCertificateCache {
+    /// The clients, along with the encrypted client keys they were built from
+    clients: Arc<std::sync::RwLock<HashMap<Ulid, (String, reqwest::Client)>>>,
+}
+
+impl ClientCertificateCache {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the client of the given provider, if it was built from the given
+    /// encrypted client keys
+    pub(crate) fn get(
+        &self,
+        provider_id: Ulid,
+        encrypted_client_keys: &str,
+    ) -> Option<reqwest::Client> {
+        let clients = self.clients.read().unwrap_or_else(PoisonError::into_inner);
+        let (cached_keys, client) = clients.get(&provider_id)?;
+        (cached_keys == encrypted_client_keys).then(|| client.clone())
+    }
+
+    /// Insert the client of the given provider, replacing the one built from
+    /// its previous keys, if any
+    pub(crate) fn insert(
+        &self,
+        provider_id: Ulid,
+        encrypted_client_keys: String,
+        client: reqwest::Client,
+    ) {
+        self.clients
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(provider_id, (encrypted_client_keys, client));
+    }
+
+    /// Only keep the clients of the given providers, built from their current
+    /// client keys
+    fn retain(&self, providers: &[UpstreamOAuthProvider]) {
+        let current_keys: HashMap<Ulid, &str> = providers
+            .iter()
+            .filter_map(|provider| Some((provider.id, provider.encrypted_client_keys.as_deref()?)))
+            .collect();
+
+        self.clients
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .retain(|provider_id, (cached_keys, _)| {
+                current_keys.get(provider_id) == Some(&cached_keys.as_str())
+            });
+    }
+
+    /// Evict the clients of the providers which were removed, disabled, or
+    /// whose client keys changed
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the providers could not be loaded
+    #[tracing::instrument(name = "client_certificate_cache.evict_removed", skip_all)]
+    pub async fn evict_removed(
+        &self,
+        repository_factory: &BoxRepositoryFactory,
+    ) -> Result<(), RepositoryError> {
+        let mut repo = repository_factory.create().await?;
+        let providers = repo.upstream_oauth_provider().all_enabled().await?;
+        repo.cancel().await?;
+
+        self.retain(&providers);
+        Ok(())
+    }
+
+    /// Spawn a background task evicting the clients of removed providers at
+    /// the given interval
+    #[must_use]
+    pub fn run_eviction(
+        &self,
+        repository_factory: BoxRepositoryFactory,
+        interval: std::time::Duration,
+    ) -> tokio::task::JoinHandle<()> {
+        let cache = self.clone();
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+                let res = LogContext::new("client-certificate-cache-eviction")
+                    .run(|| cache.evict_removed(&repository_factory))
+                    .await;
+
+                if let Err(e) = res {
+                    tracing::error!(
+                        error = &e as &dyn std::error::Error,
+                        "Failed to evict the client certificates of removed providers"
+                    );
+                }
+            }
+        })
     }
 }
 
@@ -434,6 +646,11 @@ mod tests {
             additional_authorization_parameters: Vec::new(),
             forward_login_hint: false,
             on_backchannel_logout: UpstreamOAuthProviderOnBackchannelLogout::DoNothing,
+            saml: None,
+            email_domains: Vec::new(),
+            store_tokens: false,
+            allow_initiated_login: false,
+            encrypted_client_keys: None,
         };
 
         // Without any override, it should just use discovery
@@ -531,4 +748,67 @@ mod tests {
 
         assert_eq!(calls, expected_calls);
     }
+
+    #[test]
+    fn test_client_certificate_cache() {
+        let clock = MockClock::default();
+        let provider = UpstreamOAuthProvider {
+            id: Ulid::from_parts(1, 1),
+            issuer: None,
+            human_name: None,
+            brand_name: None,
+            discovery_mode: UpstreamOAuthProviderDiscoveryMode::Disabled,
+            pkce_mode: UpstreamOAuthProviderPkceMode::Auto,
+            fetch_userinfo: false,
+            userinfo_signed_response_alg: None,
+            jwks_uri_override: None,
+            authorization_endpoint_override: None,
+            scope: Scope::from_iter([OPENID]),
+            userinfo_endpoint_override: None,
+            token_endpoint_override: None,
+            client_id: "client_id".to_owned(),
+            encrypted_client_secret: None,
+            token_endpoint_signing_alg: None,
+            token_endpoint_auth_method: UpstreamOAuthProviderTokenAuthMethod::TlsClientAuth,
+            id_token_signed_response_alg: JsonWebSignatureAlg::Rs256,
+            response_mode: None,
+            created_at: clock.now(),
+            disabled_at: None,
+            claims_imports: UpstreamOAuthProviderClaimsImports::default(),
+            additional_authorization_parameters: Vec::new(),
+            forward_login_hint: false,
+            on_backchannel_logout: UpstreamOAuthProviderOnBackchannelLogout::DoNothing,
+            saml: None,
+            email_domains: Vec::new(),
+            store_tokens: false,
+            allow_initiated_login: false,
+            encrypted_client_keys: Some("keys".to_owned()),
+        };
+        let other_provider = UpstreamOAuthProvider {
+            id: Ulid::from_parts(2, 2),
+            ..provider.clone()
+        };
+
+        let cache = ClientCertificateCache::new();
+        let client = mas_http::reqwest_client();
+        cache.insert(provider.id, "keys".to_owned(), client.clone());
+        cache.insert(other_provider.id, "keys".to_owned(), client);
+
+        // Clients are only reused while the keys of the provider don't change
+        assert!(cache.get(provider.id, "keys").is_some());
+        assert!(cache.get(provider.id, "new keys").is_none());
+
+        // The client of a provider which is gone gets evicted
+        cache.retain(std::slice::from_ref(&provider));
+        assert!(cache.get(provider.id, "keys").is_some());
+        assert!(cache.get(other_provider.id, "keys").is_none());
+
+        // So does the one built from keys the provider doesn't have anymore
+        let provider = UpstreamOAuthProvider {
+            encrypted_client_keys: Some("new keys".to_owned()),
+            ..provider
+        };
+        cache.retain(std::slice::from_ref(&provider));
+        assert!(cache.get(provider.id, "keys").is_none());
+    }
 }
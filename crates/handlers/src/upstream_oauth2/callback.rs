@@ -38,8 +38,8 @@ use ulid::Ulid;
 
 use super::{
     UpstreamSessionsCookie,
-    cache::LazyProviderInfos,
-    client_credentials_for_provider,
+    cache::{ClientCertificateCache, LazyProviderInfos},
+    client_credentials_for_provider, http_client_for_provider,
     template::{AttributeMappingContext, environment},
 };
 use crate::{
@@ -151,6 +151,7 @@ impl_from_error_for_route!(mas_oidc_client::error::TokenRequestError);
 impl_from_error_for_route!(mas_oidc_client::error::IdTokenError);
 impl_from_error_for_route!(mas_oidc_client::error::UserInfoError);
 impl_from_error_for_route!(super::ProviderCredentialsError);
+impl_from_error_for_route!(super::sync::StoreTokensError);
 impl_from_error_for_route!(super::cookie::UpstreamSessionNotFound);
 
 impl IntoResponse for RouteError {
@@ -175,6 +176,7 @@ pub(crate) async fn handler(
     mut rng: BoxRng,
     clock: BoxClock,
     State(metadata_cache): State<MetadataCache>,
+    State(certificate_cache): State<ClientCertificateCache>,
     mut repo: BoxRepository,
     State(url_builder): State<UrlBuilder>,
     State(encrypter): State<Encrypter>,
@@ -295,10 +297,14 @@ pub(crate) async fn handler(
         &encrypter,
     )?;
 
+    // The client presenting the TLS client certificate of the provider, if any
+    let provider_client =
+        http_client_for_provider(&provider, &client, &certificate_cache, &encrypter)?;
+
     let redirect_uri = url_builder.upstream_oauth_callback(provider.id);
 
     let token_response = mas_oidc_client::requests::token::request_access_token(
-        &client,
+        &provider_client,
         client_credentials,
         lazy_metadata.token_endpoint().await?,
         AccessTokenRequest::AuthorizationCode(oauth2_types::requests::AuthorizationCodeGrant {
@@ -394,7 +400,7 @@ pub(crate) async fn handler(
                 };
 
                 mas_oidc_client::requests::userinfo::fetch_userinfo(
-                    &client,
+                    &provider_client,
                     lazy_metadata.userinfo_endpoint().await?,
                     token_response.access_token.as_str(),
                     Some(JwtVerificationData {
@@ -408,7 +414,7 @@ pub(crate) async fn handler(
             }
             None => {
                 mas_oidc_client::requests::userinfo::fetch_userinfo(
-                    &client,
+                    &provider_client,
                     lazy_metadata.userinfo_endpoint().await?,
                     token_response.access_token.as_str(),
                     None,
@@ -474,6 +480,16 @@ pub(crate) async fn handler(
             .await?
     };
 
+    super::sync::store_tokens(
+        &clock,
+        &mut repo,
+        &encrypter,
+        &provider,
+        &link,
+        &token_response,
+    )
+    .await?;
+
     let session = repo
         .upstream_oauth_session()
         .complete_with_link(
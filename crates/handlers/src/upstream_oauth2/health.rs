@@ -0,0 +1,257 @@
+// Copyright 2026 Element Creations Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+// Please see LICENSE files in the repository root for full details.
+
+use mas_data_model::{
+    UpstreamOAuthProvider, UpstreamOAuthProviderDiscoveryMode, UpstreamOAuthProviderHealthCheck,
+    UpstreamOAuthProviderHealthChecks,
+};
+use mas_http::RequestBuilderExt as _;
+use mas_oidc_client::error::DiscoveryError;
+
+use super::cache::{LazyProviderInfos, MetadataCache};
+
+/// Turn an error into a failed check, including its sources, as the top-level
+/// errors are usually not descriptive enough on their own
+fn failed(error: &dyn std::error::Error) -> UpstreamOAuthProviderHealthCheck {
+    let mut message = error.to_string();
+    let mut source = error.source();
+    while let Some(error) = source {
+        message.push_str(": ");
+        message.push_str(&error.to_string());
+        source = error.source();
+    }
+
+    UpstreamOAuthProviderHealthCheck::Failed { error: message }
+}
+
+/// Check that an upstream provider is reachable and correctly configured.
+///
+/// This fetches the provider metadata, bypassing and refreshing the cache,
+/// fetches its JWKS, and makes sure its token endpoint answers requests.
+#[tracing::instrument(
+    name = "upstream_oauth2.check_provider_health",
+    fields(upstream_oauth_provider.id = %provider.id),
+    skip_all,
+)]
+pub async fn check_provider_health(
+    http_client: &reqwest::Client,
+    metadata_cache: &MetadataCache,
+    provider: &UpstreamOAuthProvider,
+) -> UpstreamOAuthProviderHealthChecks {
+    // SAML providers have none of those
+    if provider.saml.is_some() {
+        return UpstreamOAuthProviderHealthChecks {
+            discovery: UpstreamOAuthProviderHealthCheck::Skipped,
+            jwks: UpstreamOAuthProviderHealthCheck::Skipped,
+            token_endpoint: UpstreamOAuthProviderHealthCheck::Skipped,
+        };
+    }
+
+    let verify = match provider.discovery_mode {
+        UpstreamOAuthProviderDiscoveryMode::Oidc => Some(true),
+        UpstreamOAuthProviderDiscoveryMode::Insecure => Some(false),
+        UpstreamOAuthProviderDiscoveryMode::Disabled => None,
+    };
+
+    let discovery = match (verify, &provider.issuer) {
+        (None, _) => UpstreamOAuthProviderHealthCheck::Skipped,
+        (Some(_), None) => failed(&DiscoveryError::MissingIssuer),
+        (Some(verify), Some(issuer)) => {
+            match metadata_cache.fetch(http_client, issuer, verify).await {
+                Ok(_) => UpstreamOAuthProviderHealthCheck::Ok,
+                Err(e) => failed(&e),
+            }
+        }
+    };
+
+    // If discovery failed, the endpoints which are not overridden can't be
+    // checked, and trying again would only report the same error
+    let discovery_failed = discovery.is_failed();
+    let mut lazy_metadata = LazyProviderInfos::new(metadata_cache, provider, http_client);
+
+    let jwks = if discovery_failed && provider.jwks_uri_override.is_none() {
+        UpstreamOAuthProviderHealthCheck::Skipped
+    } else {
+        match lazy_metadata.jwks_uri().await {
+            Ok(jwks_uri) => {
+                match mas_oidc_client::requests::jose::fetch_jwks(http_client, jwks_uri).await {
+                    Ok(_) => UpstreamOAuthProviderHealthCheck::Ok,
+                    Err(e) => failed(&e),
+                }
+            }
+            Err(DiscoveryError::Disabled) => UpstreamOAuthProviderHealthCheck::Skipped,
+            Err(e) => failed(&e),
+        }
+    };
+
+    let token_endpoint = if discovery_failed && provider.token_endpoint_override.is_none() {
+        UpstreamOAuthProviderHealthCheck::Skipped
+    } else {
+        match lazy_metadata.token_endpoint().await {
+            // We don't have anything valid to send to the token endpoint, so any
+            // answer which isn't a server error is good enough
+            Ok(token_endpoint) => match http_client
+                .post(token_endpoint.as_str())
+                .send_traced()
+                .await
+            {
+                Ok(response) if response.status().is_server_error() => {
+                    UpstreamOAuthProviderHealthCheck::Failed {
+                        error: format!("The token endpoint replied with {}", response.status()),
+                    }
+                }
+                Ok(_) => UpstreamOAuthProviderHealthCheck::Ok,
+                Err(e) => failed(&e),
+            },
+            Err(DiscoveryError::Disabled) => UpstreamOAuthProviderHealthCheck::Skipped,
+            Err(e) => failed(&e),
+        }
+    };
+
+    UpstreamOAuthProviderHealthChecks {
+        discovery,
+        jwks,
+        token_endpoint,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mas_data_model::{
+        Clock, UpstreamOAuthProviderClaimsImports, UpstreamOAuthProviderOnBackchannelLogout,
+        UpstreamOAuthProviderPkceMode, UpstreamOAuthProviderTokenAuthMethod, clock::MockClock,
+    };
+    use mas_iana::jose::JsonWebSignatureAlg;
+    use oauth2_types::scope::{OPENID, Scope};
+    use ulid::Ulid;
+    use wiremock::{
+        Mock, MockServer, ResponseTemplate,
+        matchers::{method, path},
+    };
+
+    use super::*;
+    use crate::test_utils::setup;
+
+    fn provider(issuer: String) -> UpstreamOAuthProvider {
+        UpstreamOAuthProvider {
+            id: Ulid::nil(),
+            issuer: Some(issuer),
+            human_name: Some("Example Ltd.".to_owned()),
+            brand_name: None,
+            // We can't test HTTPS requests with wiremock, so we can only test
+            // 'insecure' discovery
+            discovery_mode: UpstreamOAuthProviderDiscoveryMode::Insecure,
+            pkce_mode: UpstreamOAuthProviderPkceMode::Auto,
+            fetch_userinfo: false,
+            userinfo_signed_response_alg: None,
+            jwks_uri_override: None,
+            authorization_endpoint_override: None,
+            scope: Scope::from_iter([OPENID]),
+            userinfo_endpoint_override: None,
+            token_endpoint_override: None,
+            client_id: "client_id".to_owned(),
+            encrypted_client_secret: None,
+            token_endpoint_signing_alg: None,
+            token_endpoint_auth_method: UpstreamOAuthProviderTokenAuthMethod::None,
+            id_token_signed_response_alg: JsonWebSignatureAlg::Rs256,
+            response_mode: None,
+            created_at: MockClock::default().now(),
+            disabled_at: None,
+            claims_imports: UpstreamOAuthProviderClaimsImports::default(),
+            additional_authorization_parameters: Vec::new(),
+            forward_login_hint: false,
+            on_backchannel_logout: UpstreamOAuthProviderOnBackchannelLogout::DoNothing,
+            saml: None,
+            email_domains: Vec::new(),
+            store_tokens: false,
+            allow_initiated_login: false,
+            encrypted_client_keys: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_check_provider_health() {
+        setup();
+
+        let mock_server = MockServer::start().await;
+        let http_client = mas_http::reqwest_client();
+        let cache = MetadataCache::new();
+
+        Mock::given(method("GET"))
+            .and(path("/.well-known/openid-configuration"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "issuer": mock_server.uri(),
+                "authorization_endpoint": format!("{}/authorize", mock_server.uri()),
+                "token_endpoint": format!("{}/token", mock_server.uri()),
+                "jwks_uri": format!("{}/jwks", mock_server.uri()),
+                "scopes_supported": ["openid"],
+                "response_types_supported": ["code"],
+                "response_modes_supported": ["query", "fragment"],
+                "grant_types_supported": ["authorization_code"],
+                "subject_types_supported": ["public"],
+                "id_token_signing_alg_values_supported": ["RS256"],
+            })))
+            .mount(&mock_server)
+            .await;
+
+        Mock::given(method("GET"))
+            .and(path("/jwks"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(serde_json::json!({ "keys": [] })),
+            )
+            .mount(&mock_server)
+            .await;
+
+        // The token endpoint rejects our empty request, which is expected
+        Mock::given(method("POST"))
+            .and(path("/token"))
+            .respond_with(
+                ResponseTemplate::new(400)
+                    .set_body_json(serde_json::json!({ "error": "invalid_request" })),
+            )
+            .mount(&mock_server)
+            .await;
+
+        Mock::given(method("POST"))
+            .and(path("/broken-token"))
+            .respond_with(ResponseTemplate::new(502))
+            .mount(&mock_server)
+            .await;
+
+        // Everything is fine
+        let checks =
+            check_provider_health(&http_client, &cache, &provider(mock_server.uri())).await;
+        assert_eq!(checks.discovery, UpstreamOAuthProviderHealthCheck::Ok);
+        assert_eq!(checks.jwks, UpstreamOAuthProviderHealthCheck::Ok);
+        assert_eq!(checks.token_endpoint, UpstreamOAuthProviderHealthCheck::Ok);
+        assert!(checks.is_healthy());
+
+        // The token endpoint is broken
+        let broken = UpstreamOAuthProvider {
+            token_endpoint_override: Some(
+                format!("{}/broken-token", mock_server.uri())
+                    .parse()
+                    .unwrap(),
+            ),
+            ..provider(mock_server.uri())
+        };
+        let checks = check_provider_health(&http_client, &cache, &broken).await;
+        assert_eq!(checks.discovery, UpstreamOAuthProviderHealthCheck::Ok);
+        assert_eq!(checks.jwks, UpstreamOAuthProviderHealthCheck::Ok);
+        assert!(checks.token_endpoint.is_failed());
+        assert!(!checks.is_healthy());
+
+        // Discovery fails, so the other checks are skipped
+        let missing = provider(format!("{}/missing", mock_server.uri()));
+        let checks = check_provider_health(&http_client, &cache, &missing).await;
+        assert!(checks.discovery.is_failed());
+        assert_eq!(checks.jwks, UpstreamOAuthProviderHealthCheck::Skipped);
+        assert_eq!(
+            checks.token_endpoint,
+            UpstreamOAuthProviderHealthCheck::Skipped
+        );
+        assert!(!checks.is_healthy());
+    }
+}
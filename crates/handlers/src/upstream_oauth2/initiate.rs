@@ -0,0 +1,288 @@
+// Copyright 2026 Element Creations Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+// Please see LICENSE files in the repository root for full details.
+
+//! Entry point for logins which are started outside of MAS, either by the
+//! upstream provider itself or by a third party like a company portal.
+//!
+//! This doesn't start the authorization flow itself: it validates the request
+//! and redirects to the regular authorization endpoint, so that the state
+//! stored in the upstream sessions cookie still protects the callback.
+
+use axum::{
+    Form,
+    extract::{Path, State},
+    response::{IntoResponse, Response},
+};
+use hyper::StatusCode;
+use mas_axum_utils::{GenericError, InternalError};
+use mas_data_model::UpstreamOAuthProvider;
+use mas_router::{PostAuthAction, UpstreamOAuth2Authorize, UrlBuilder};
+use mas_storage::{
+    BoxRepository, oauth2::OAuth2ClientRepository, upstream_oauth2::UpstreamOAuthProviderRepository,
+};
+use serde::Deserialize;
+use thiserror::Error;
+use ulid::Ulid;
+use url::Url;
+
+use crate::impl_from_error_for_route;
+
+#[derive(Debug, Error)]
+pub(crate) enum RouteError {
+    #[error("Provider not found")]
+    ProviderNotFound,
+
+    #[error("Initiated logins are not allowed for this provider")]
+    NotAllowed,
+
+    #[error("The issuer doesn't match the provider")]
+    IssuerMismatch,
+
+    #[error("Client not found")]
+    ClientNotFound,
+
+    #[error("The client doesn't have an initiate_login_uri")]
+    ClientNotLaunchable,
+
+    #[error("Invalid target_link_uri")]
+    InvalidTargetLinkUri,
+
+    #[error(transparent)]
+    Internal(Box<dyn std::error::Error>),
+}
+
+impl_from_error_for_route!(mas_storage::RepositoryError);
+
+impl IntoResponse for RouteError {
+    fn into_response(self) -> axum::response::Response {
+        match self {
+            e @ Self::ProviderNotFound => {
+                GenericError::new(StatusCode::NOT_FOUND, e).into_response()
+            }
+            e @ Self::NotAllowed => GenericError::new(StatusCode::FORBIDDEN, e).into_response(),
+            e @ (Self::IssuerMismatch
+            | Self::ClientNotFound
+            | Self::ClientNotLaunchable
+            | Self::InvalidTargetLinkUri) => {
+                GenericError::new(StatusCode::BAD_REQUEST, e).into_response()
+            }
+            Self::Internal(e) => InternalError::new(e).into_response(),
+        }
+    }
+}
+
+/// The parameters of a third-party initiated login, as defined by the `OpenID
+/// Connect` specification, plus an optional target client and post-auth
+/// action.
+#[derive(Debug, Deserialize)]
+pub(crate) struct Params {
+    /// The issuer the login should be made with
+    iss: Option<String>,
+
+    /// A hint about the user to log in, forwarded to the provider if it
+    /// accepts login hints
+    login_hint: Option<String>,
+
+    /// Where the client should send the user after the login
+    target_link_uri: Option<Url>,
+
+    /// The `client_id` of the client to launch after the login
+    client_id: Option<String>,
+
+    /// What to do after the login, if no client is given
+    #[serde(flatten)]
+    post_auth_action: Option<PostAuthAction>,
+}
+
+#[tracing::instrument(
+    name = "handlers.upstream_oauth2.initiate.handler",
+    fields(upstream_oauth_provider.id = %provider_id),
+    skip_all,
+)]
+pub(crate) async fn handler(
+    mut repo: BoxRepository,
+    State(url_builder): State<UrlBuilder>,
+    Path(provider_id): Path<Ulid>,
+    Form(params): Form<Params>,
+) -> Result<Response, RouteError> {
+    let provider = repo
+        .upstream_oauth_provider()
+        .lookup(provider_id)
+        .await?
+        .filter(UpstreamOAuthProvider::enabled)
+        .ok_or(RouteError::ProviderNotFound)?;
+
+    if !provider.allow_initiated_login {
+        return Err(RouteError::NotAllowed);
+    }
+
+    // If the initiator tells us which issuer to use, it must be this provider
+    if let Some(iss) = &params.iss
+        && provider.issuer.as_deref() != Some(iss.as_str())
+    {
+        return Err(RouteError::IssuerMismatch);
+    }
+
+    let post_auth_action = if let Some(client_id) = &params.client_id {
+        let client = repo
+            .oauth2_client()
+            .find_by_client_id(client_id)
+            .await?
+            .ok_or(RouteError::ClientNotFound)?;
+
+        let initiate_login_uri = client
+            .initiate_login_uri
+            .as_ref()
+            .ok_or(RouteError::ClientNotLaunchable)?;
+
+        // We only ever redirect to URLs the client registered, so the target
+        // must be on the same origin as its initiate_login_uri
+        if let Some(target_link_uri) = &params.target_link_uri
+            && target_link_uri.origin() != initiate_login_uri.origin()
+        {
+            return Err(RouteError::InvalidTargetLinkUri);
+        }
+
+        Some(PostAuthAction::launch_client(
+            client.id,
+            params.target_link_uri,
+        ))
+    } else if params.target_link_uri.is_some() {
+        // Without a client, we have no way to tell whether the target is safe
+        // to redirect to
+        return Err(RouteError::InvalidTargetLinkUri);
+    } else {
+        params.post_auth_action
+    };
+
+    let mut destination = UpstreamOAuth2Authorize::new(provider.id);
+    if let Some(action) = post_auth_action {
+        destination = destination.and_then(action);
+    }
+    if let Some(login_hint) = params.login_hint {
+        destination = destination.with_login_hint(login_hint);
+    }
+
+    Ok(url_builder.redirect(&destination).into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use hyper::{Request, StatusCode};
+    use mas_data_model::{
+        UpstreamOAuthProviderClaimsImports, UpstreamOAuthProviderDiscoveryMode,
+        UpstreamOAuthProviderOnBackchannelLogout, UpstreamOAuthProviderPkceMode,
+        UpstreamOAuthProviderTokenAuthMethod,
+    };
+    use mas_iana::jose::JsonWebSignatureAlg;
+    use mas_router::Route;
+    use mas_storage::upstream_oauth2::UpstreamOAuthProviderParams;
+    use oauth2_types::scope::{OPENID, Scope};
+    use sqlx::PgPool;
+
+    use crate::test_utils::{RequestBuilderExt, ResponseExt, TestState, setup};
+
+    async fn add_provider(
+        state: &TestState,
+        allow_initiated_login: bool,
+    ) -> mas_data_model::UpstreamOAuthProvider {
+        let mut rng = state.rng();
+        let mut repo = state.repository().await.unwrap();
+        let provider = repo
+            .upstream_oauth_provider()
+            .add(
+                &mut rng,
+                &state.clock,
+                UpstreamOAuthProviderParams {
+                    issuer: Some("https://example.com/".to_owned()),
+                    human_name: Some("Example Ltd.".to_owned()),
+                    brand_name: None,
+                    scope: Scope::from_iter([OPENID]),
+                    token_endpoint_auth_method: UpstreamOAuthProviderTokenAuthMethod::None,
+                    token_endpoint_signing_alg: None,
+                    id_token_signed_response_alg: JsonWebSignatureAlg::Rs256,
+                    client_id: "client".to_owned(),
+                    encrypted_client_secret: None,
+                    claims_imports: UpstreamOAuthProviderClaimsImports::default(),
+                    authorization_endpoint_override: None,
+                    token_endpoint_override: None,
+                    userinfo_endpoint_override: None,
+                    fetch_userinfo: false,
+                    userinfo_signed_response_alg: None,
+                    jwks_uri_override: None,
+                    discovery_mode: UpstreamOAuthProviderDiscoveryMode::Oidc,
+                    pkce_mode: UpstreamOAuthProviderPkceMode::Auto,
+                    response_mode: None,
+                    additional_authorization_parameters: Vec::new(),
+                    forward_login_hint: true,
+                    on_backchannel_logout: UpstreamOAuthProviderOnBackchannelLogout::DoNothing,
+                    ui_order: 0,
+                    saml: None,
+                    email_domains: Vec::new(),
+                    store_tokens: false,
+                    allow_initiated_login,
+                    encrypted_client_keys: None,
+                },
+            )
+            .await
+            .unwrap();
+        repo.save().await.unwrap();
+        provider
+    }
+
+    #[sqlx::test(migrator = "mas_storage_pg::MIGRATOR")]
+    async fn test_initiate_login(pool: PgPool) {
+        setup();
+        let state = TestState::from_pool(pool).await.unwrap();
+
+        let disallowed = add_provider(&state, false).await;
+        let provider = add_provider(&state, true).await;
+
+        // Providers which didn't opt in can't be used
+        let request =
+            Request::get(&*mas_router::UpstreamOAuth2Initiate::new(disallowed.id).path_and_query())
+                .empty();
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::FORBIDDEN);
+
+        // The issuer must match the provider
+        let request = Request::get(format!(
+            "{}?iss=https%3A%2F%2Fattacker.example.com%2F",
+            mas_router::UpstreamOAuth2Initiate::new(provider.id).path_and_query()
+        ))
+        .empty();
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::BAD_REQUEST);
+
+        // A target link without a client can't be validated
+        let request = Request::get(format!(
+            "{}?target_link_uri=https%3A%2F%2Fattacker.example.com%2F",
+            mas_router::UpstreamOAuth2Initiate::new(provider.id).path_and_query()
+        ))
+        .empty();
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::BAD_REQUEST);
+
+        // The login goes through the regular authorization endpoint, with the
+        // login hint
+        let request =
+            Request::post(&*mas_router::UpstreamOAuth2Initiate::new(provider.id).path_and_query())
+                .form(serde_json::json!({
+                    "iss": "https://example.com/",
+                    "login_hint": "alice",
+                }));
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::SEE_OTHER);
+        let expected = mas_router::UpstreamOAuth2Authorize::new(provider.id)
+            .with_login_hint("alice".to_owned())
+            .path_and_query();
+        assert!(
+            response.headers()[hyper::header::LOCATION]
+                .to_str()
+                .unwrap()
+                .ends_with(&*expected)
+        );
+    }
+}
@@ -0,0 +1,186 @@
+// Copyright 2026 Element Creations Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+// Please see LICENSE files in the repository root for full details.
+
+//! Publishes the public part of the keys a provider uses for
+//! `private_key_jwt`, so that the provider can verify the client assertions.
+
+use axum::{
+    Json,
+    extract::{Path, State},
+    response::{IntoResponse, Response},
+};
+use hyper::StatusCode;
+use mas_axum_utils::{GenericError, InternalError};
+use mas_data_model::UpstreamOAuthProvider;
+use mas_keystore::Encrypter;
+use mas_storage::{BoxRepository, upstream_oauth2::UpstreamOAuthProviderRepository};
+use thiserror::Error;
+use ulid::Ulid;
+
+use super::keystore_for_provider;
+use crate::impl_from_error_for_route;
+
+#[derive(Debug, Error)]
+pub(crate) enum RouteError {
+    #[error("Provider not found")]
+    ProviderNotFound,
+
+    #[error(transparent)]
+    Internal(Box<dyn std::error::Error + Send + Sync + 'static>),
+}
+
+impl_from_error_for_route!(mas_storage::RepositoryError);
+impl_from_error_for_route!(super::ProviderCredentialsError);
+
+impl IntoResponse for RouteError {
+    fn into_response(self) -> Response {
+        match self {
+            Self::Internal(e) => InternalError::new(e).into_response(),
+            e @ Self::ProviderNotFound => {
+                GenericError::new(StatusCode::NOT_FOUND, e).into_response()
+            }
+        }
+    }
+}
+
+#[tracing::instrument(
+    name = "handlers.upstream_oauth2.jwks.get",
+    fields(upstream_oauth_provider.id = %provider_id),
+    skip_all,
+)]
+pub(crate) async fn get(
+    mut repo: BoxRepository,
+    State(encrypter): State<Encrypter>,
+    Path(provider_id): Path<Ulid>,
+) -> Result<Response, RouteError> {
+    let provider = repo
+        .upstream_oauth_provider()
+        .lookup(provider_id)
+        .await?
+        .filter(UpstreamOAuthProvider::enabled)
+        .ok_or(RouteError::ProviderNotFound)?;
+
+    // Providers without their own keys use the global keys, which are
+    // published on the regular JWKS endpoint
+    let keystore =
+        keystore_for_provider(&provider, &encrypter)?.ok_or(RouteError::ProviderNotFound)?;
+
+    Ok(Json(keystore.public_jwks()).into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use hyper::{Request, StatusCode};
+    use mas_data_model::{
+        UpstreamOAuthProviderClaimsImports, UpstreamOAuthProviderDiscoveryMode,
+        UpstreamOAuthProviderOnBackchannelLogout, UpstreamOAuthProviderPkceMode,
+        UpstreamOAuthProviderTokenAuthMethod,
+    };
+    use mas_iana::jose::JsonWebSignatureAlg;
+    use mas_router::Route;
+    use mas_storage::upstream_oauth2::UpstreamOAuthProviderParams;
+    use oauth2_types::scope::{OPENID, Scope};
+    use sqlx::PgPool;
+
+    use crate::test_utils::{RequestBuilderExt, ResponseExt, TestState, setup};
+
+    async fn add_provider(
+        state: &TestState,
+        encrypted_client_keys: Option<String>,
+    ) -> mas_data_model::UpstreamOAuthProvider {
+        let mut rng = state.rng();
+        let mut repo = state.repository().await.unwrap();
+        let provider = repo
+            .upstream_oauth_provider()
+            .add(
+                &mut rng,
+                &state.clock,
+                UpstreamOAuthProviderParams {
+                    issuer: Some("https://example.com/".to_owned()),
+                    human_name: Some("Example Ltd.".to_owned()),
+                    brand_name: None,
+                    scope: Scope::from_iter([OPENID]),
+                    token_endpoint_auth_method: UpstreamOAuthProviderTokenAuthMethod::PrivateKeyJwt,
+                    token_endpoint_signing_alg: Some(JsonWebSignatureAlg::Es256),
+                    id_token_signed_response_alg: JsonWebSignatureAlg::Rs256,
+                    client_id: "client".to_owned(),
+                    encrypted_client_secret: None,
+                    claims_imports: UpstreamOAuthProviderClaimsImports::default(),
+                    authorization_endpoint_override: None,
+                    token_endpoint_override: None,
+                    userinfo_endpoint_override: None,
+                    fetch_userinfo: false,
+                    userinfo_signed_response_alg: None,
+                    jwks_uri_override: None,
+                    discovery_mode: UpstreamOAuthProviderDiscoveryMode::Oidc,
+                    pkce_mode: UpstreamOAuthProviderPkceMode::Auto,
+                    response_mode: None,
+                    additional_authorization_parameters: Vec::new(),
+                    forward_login_hint: false,
+                    on_backchannel_logout: UpstreamOAuthProviderOnBackchannelLogout::DoNothing,
+                    ui_order: 0,
+                    saml: None,
+                    email_domains: Vec::new(),
+                    store_tokens: false,
+                    allow_initiated_login: false,
+                    encrypted_client_keys,
+                },
+            )
+            .await
+            .unwrap();
+        repo.save().await.unwrap();
+        provider
+    }
+
+    #[sqlx::test(migrator = "mas_storage_pg::MIGRATOR")]
+    async fn test_provider_jwks(pool: PgPool) {
+        setup();
+        let state = TestState::from_pool(pool).await.unwrap();
+
+        // Providers without their own keys don't have a key set
+        let provider = add_provider(&state, None).await;
+        let request =
+            Request::get(&*mas_router::UpstreamOAuth2Jwks::new(provider.id).path_and_query())
+                .empty();
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::NOT_FOUND);
+
+        // Both the current and the next key are published during a rotation
+        let client_keys = serde_json::json!({
+            "keys": [
+                {
+                    "kid": "current",
+                    "key": include_str!("../../../keystore/tests/keys/ec-p256.sec1.pem"),
+                },
+                {
+                    "kid": "next",
+                    "key": include_str!("../../../keystore/tests/keys/rsa.pkcs1.pem"),
+                },
+            ],
+        });
+        let encrypted = state
+            .encrypter
+            .encrypt_to_string(client_keys.to_string().as_bytes())
+            .unwrap();
+        let provider = add_provider(&state, Some(encrypted)).await;
+
+        let request =
+            Request::get(&*mas_router::UpstreamOAuth2Jwks::new(provider.id).path_and_query())
+                .empty();
+        let response = state.request(request).await;
+        response.assert_status(StatusCode::OK);
+        let jwks: serde_json::Value = response.json();
+        let kids: Vec<&str> = jwks["keys"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|key| key["kid"].as_str().unwrap())
+            .collect();
+        assert_eq!(kids, ["current", "next"]);
+
+        // Private parameters are never published
+        assert!(jwks["keys"][0].get("d").is_none());
+    }
+}
@@ -23,10 +23,9 @@ use mas_axum_utils::{
     record_error,
 };
 use mas_data_model::{
-    BoxClock, BoxRng, UpstreamOAuthAuthorizationSession, UpstreamOAuthProviderOnConflict,
-    UserRegistration,
+    BoxClock, BoxRng, Clock, UpstreamOAuthAuthorizationSession, UpstreamOAuthLink,
+    UpstreamOAuthProviderOnConflict, User, UserRegistration,
 };
-use mas_jose::jwt::Jwt;
 use mas_matrix::HomeserverConnection;
 use mas_policy::Policy;
 use mas_router::UrlBuilder;
@@ -43,13 +42,18 @@ use mas_templates::{
 };
 use minijinja::Environment;
 use opentelemetry::{Key, KeyValue, metrics::Counter};
+use rand::RngCore;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 use ulid::Ulid;
 
 use super::{
     UpstreamSessionsCookie,
-    template::{AttributeMappingContext, environment},
+    sync::{
+        DEFAULT_DISPLAYNAME_TEMPLATE, DEFAULT_EMAIL_TEMPLATE, apply_account_status_import,
+        apply_authorization_imports, apply_claims_imports, context_from_session,
+    },
+    template::environment,
 };
 use crate::{
     BoundActivityTracker, METER, PreferredLanguage, SiteConfig, impl_from_error_for_route,
@@ -73,8 +77,6 @@ static REGISTRATION_COUNTER: LazyLock<Counter<u64>> = LazyLock::new(|| {
 const PROVIDER: Key = Key::from_static_str("provider");
 
 const DEFAULT_LOCALPART_TEMPLATE: &str = "{{ user.preferred_username }}";
-const DEFAULT_DISPLAYNAME_TEMPLATE: &str = "{{ user.name }}";
-const DEFAULT_EMAIL_TEMPLATE: &str = "{{ user.email }}";
 
 #[derive(Debug, Error)]
 pub(crate) enum RouteError {
@@ -119,6 +121,10 @@ pub(crate) enum RouteError {
     #[error("Invalid form action")]
     InvalidFormAction,
 
+    /// Linking this provider was denied by the policy
+    #[error("Linking this provider is not allowed: {0}")]
+    LinkDenied(mas_policy::EvaluationResult),
+
     #[error("Homeserver connection error")]
     HomeserverConnection(#[source] anyhow::Error),
 
@@ -148,6 +154,7 @@ impl IntoResponse for RouteError {
 
         let status_code = match self {
             Self::LinkNotFound => StatusCode::NOT_FOUND,
+            Self::LinkDenied(_) => StatusCode::FORBIDDEN,
             _ => StatusCode::INTERNAL_SERVER_ERROR,
         };
 
@@ -201,6 +208,39 @@ fn render_attribute_template(
     }
 }
 
+/// Apply the claims imports of the provider to a user logging in through an
+/// existing link
+///
+/// The account status, admin flag and attributes imports are always applied,
+/// while the other imports are only re-applied if the provider is configured to
+/// do so.
+async fn apply_imports_on_login(
+    rng: &mut (dyn RngCore + Send),
+    clock: &dyn Clock,
+    repo: &mut BoxRepository,
+    link: &UpstreamOAuthLink,
+    upstream_session: &UpstreamOAuthAuthorizationSession,
+    user: User,
+) -> Result<User, RouteError> {
+    let provider = repo
+        .upstream_oauth_provider()
+        .lookup(link.provider_id)
+        .await?
+        .ok_or(RouteError::ProviderNotFound(link.provider_id))?;
+
+    let context = context_from_session(upstream_session)?;
+
+    let user =
+        apply_account_status_import(rng, clock, repo, &provider, link, user, &context).await?;
+    let user = apply_authorization_imports(repo, &provider, link, user, &context).await?;
+
+    if provider.claims_imports.resync.on_login && user.deactivated_at.is_none() {
+        apply_claims_imports(rng, clock, repo, &provider, &user, &context).await?;
+    }
+
+    Ok(user)
+}
+
 #[derive(Deserialize, Serialize)]
 #[serde(rename_all = "lowercase", tag = "action")]
 pub(crate) enum FormData {
@@ -276,6 +316,18 @@ pub(crate) async fn get(
         (Some(session), Some(user_id)) if session.user.id == user_id => {
             // Session already linked, and link matches the currently logged
             // user. Mark the session as consumed and renew the authentication.
+            // If this locks the user, the browser session will stop being
+            // active on the next request
+            apply_imports_on_login(
+                &mut rng,
+                &clock,
+                &mut repo,
+                &link,
+                &upstream_session,
+                session.user.clone(),
+            )
+            .await?;
+
             let upstream_session = repo
                 .upstream_oauth_session()
                 .consume(&clock, upstream_session, &session)
@@ -332,6 +384,12 @@ pub(crate) async fn get(
                 .await?
                 .ok_or(RouteError::UserNotFound(user_id))?;
 
+            // The upstream provider may lock or deactivate the user, so this
+            // needs to happen before the checks below
+            let user =
+                apply_imports_on_login(&mut rng, &clock, &mut repo, &link, &upstream_session, user)
+                    .await?;
+
             // Check that the user is not locked or deactivated
             if user.deactivated_at.is_some() {
                 // The account is deactivated, show the 'account deactivated' fallback
@@ -390,8 +448,6 @@ pub(crate) async fn get(
         (None, None) => {
             // Session not linked and used not logged in: suggest creating an
             // account or logging in an existing user
-            let id_token = upstream_session.id_token().map(Jwt::try_from).transpose()?;
-
             let provider = repo
                 .upstream_oauth_provider()
                 .lookup(link.provider_id)
@@ -400,18 +456,7 @@ pub(crate) async fn get(
 
             let env = environment();
 
-            let mut context = AttributeMappingContext::new();
-            if let Some(id_token) = id_token {
-                let (_, payload) = id_token.into_parts();
-                context = context.with_id_token_claims(payload);
-            }
-            if let Some(extra_callback_parameters) = upstream_session.extra_callback_parameters() {
-                context = context.with_extra_callback_parameters(extra_callback_parameters.clone());
-            }
-            if let Some(userinfo) = upstream_session.userinfo() {
-                context = context.with_userinfo_claims(userinfo.clone());
-            }
-            let context = context.build();
+            let context = context_from_session(&upstream_session)?;
 
             let displayname = if provider.claims_imports.displayname.ignore() {
                 None
@@ -899,6 +944,30 @@ pub(crate) async fn post(
         (Some(session), None, FormData::Link) => {
             // The user is already logged in, the link is not linked to any user, and the
             // user asked to link their account.
+            let provider = repo
+                .upstream_oauth_provider()
+                .lookup(link.provider_id)
+                .await?
+                .ok_or(RouteError::ProviderNotFound(link.provider_id))?;
+
+            let res = policy
+                .evaluate_upstream_oauth_link(mas_policy::UpstreamOAuthLinkInput {
+                    user: &session.user,
+                    action: mas_policy::UpstreamOAuthLinkAction::Link,
+                    provider: mas_policy::UpstreamOAuthLinkProvider {
+                        id: provider.id,
+                        issuer: provider.issuer.as_deref(),
+                    },
+                    requester: mas_policy::Requester {
+                        ip_address: activity_tracker.ip(),
+                        user_agent: user_agent.clone(),
+                    },
+                })
+                .await?;
+            if !res.valid() {
+                return Err(RouteError::LinkDenied(res));
+            }
+
             repo.upstream_oauth_link()
                 .associate_to_user(&link, &session.user)
                 .await?;
@@ -946,8 +1015,6 @@ pub(crate) async fn post(
             let import_display_name = import_display_name.is_some();
             let accept_terms = accept_terms.is_some();
 
-            let id_token = upstream_session.id_token().map(Jwt::try_from).transpose()?;
-
             let provider = repo
                 .upstream_oauth_provider()
                 .lookup(link.provider_id)
@@ -957,18 +1024,7 @@ pub(crate) async fn post(
             // Let's try to import the claims from the ID token
             let env = environment();
 
-            let mut context = AttributeMappingContext::new();
-            if let Some(id_token) = id_token {
-                let (_, payload) = id_token.into_parts();
-                context = context.with_id_token_claims(payload);
-            }
-            if let Some(extra_callback_parameters) = upstream_session.extra_callback_parameters() {
-                context = context.with_extra_callback_parameters(extra_callback_parameters.clone());
-            }
-            if let Some(userinfo) = upstream_session.userinfo() {
-                context = context.with_userinfo_claims(userinfo.clone());
-            }
-            let context = context.build();
+            let context = context_from_session(&upstream_session)?;
 
             // Create a template context in case we need to re-render because of an error
             let mut ctx = UpstreamRegister::new(link.clone(), provider.clone());
@@ -1255,13 +1311,17 @@ mod tests {
     use mas_data_model::{
         UpstreamOAuthAuthorizationSession, UpstreamOAuthLink, UpstreamOAuthProviderClaimsImports,
         UpstreamOAuthProviderImportPreference, UpstreamOAuthProviderLocalpartPreference,
+        UpstreamOAuthProviderResyncPreference, UpstreamOAuthProviderSubjectPreference,
         UpstreamOAuthProviderTokenAuthMethod, UserEmailAuthentication, UserRegistration,
     };
     use mas_iana::jose::JsonWebSignatureAlg;
     use mas_jose::jwt::{JsonWebSignatureHeader, Jwt};
     use mas_keystore::Keystore;
     use mas_router::Route;
-    use mas_storage::{Repository, RepositoryError, upstream_oauth2::UpstreamOAuthProviderParams};
+    use mas_storage::{
+        Repository, RepositoryError, upstream_oauth2::UpstreamOAuthProviderParams,
+        user::UserEmailFilter,
+    };
     use oauth2_types::scope::{OPENID, Scope};
     use rand_chacha::ChaChaRng;
     use serde_json::Value;
@@ -1345,6 +1405,11 @@ mod tests {
                     ui_order: 0,
                     on_backchannel_logout:
                         mas_data_model::UpstreamOAuthProviderOnBackchannelLogout::DoNothing,
+                    saml: None,
+                    email_domains: Vec::new(),
+                    store_tokens: false,
+                    allow_initiated_login: false,
+                    encrypted_client_keys: None,
                 },
             )
             .await
@@ -1542,6 +1607,11 @@ mod tests {
                     ui_order: 0,
                     on_backchannel_logout:
                         mas_data_model::UpstreamOAuthProviderOnBackchannelLogout::DoNothing,
+                    saml: None,
+                    email_domains: Vec::new(),
+                    store_tokens: false,
+                    allow_initiated_login: false,
+                    encrypted_client_keys: None,
                 },
             )
             .await
@@ -1702,6 +1772,11 @@ mod tests {
                     forward_login_hint: false,
                     on_backchannel_logout:
                         mas_data_model::UpstreamOAuthProviderOnBackchannelLogout::DoNothing,
+                    saml: None,
+                    email_domains: Vec::new(),
+                    store_tokens: false,
+                    allow_initiated_login: false,
+                    encrypted_client_keys: None,
                     ui_order: 0,
                 },
             )
@@ -1818,6 +1893,11 @@ mod tests {
                     forward_login_hint: false,
                     on_backchannel_logout:
                         mas_data_model::UpstreamOAuthProviderOnBackchannelLogout::DoNothing,
+                    saml: None,
+                    email_domains: Vec::new(),
+                    store_tokens: false,
+                    allow_initiated_login: false,
+                    encrypted_client_keys: None,
                     ui_order: 0,
                 },
             )
@@ -1987,6 +2067,11 @@ mod tests {
                     forward_login_hint: false,
                     on_backchannel_logout:
                         mas_data_model::UpstreamOAuthProviderOnBackchannelLogout::DoNothing,
+                    saml: None,
+                    email_domains: Vec::new(),
+                    store_tokens: false,
+                    allow_initiated_login: false,
+                    encrypted_client_keys: None,
                     ui_order: 0,
                 },
             )
@@ -2135,6 +2220,11 @@ mod tests {
                     forward_login_hint: false,
                     on_backchannel_logout:
                         mas_data_model::UpstreamOAuthProviderOnBackchannelLogout::DoNothing,
+                    saml: None,
+                    email_domains: Vec::new(),
+                    store_tokens: false,
+                    allow_initiated_login: false,
+                    encrypted_client_keys: None,
                     ui_order: 0,
                 },
             )
@@ -2254,6 +2344,11 @@ mod tests {
                     forward_login_hint: false,
                     on_backchannel_logout:
                         mas_data_model::UpstreamOAuthProviderOnBackchannelLogout::DoNothing,
+                    saml: None,
+                    email_domains: Vec::new(),
+                    store_tokens: false,
+                    allow_initiated_login: false,
+                    encrypted_client_keys: None,
                     ui_order: 0,
                 },
             )
@@ -2345,4 +2440,259 @@ mod tests {
         assert!(old_link_result.is_some(), "Old link should still exist");
         assert_eq!(old_link_result.unwrap().user_id, Some(user.id));
     }
+
+    #[sqlx::test(migrator = "mas_storage_pg::MIGRATOR")]
+    async fn test_resync_claims_on_login(pool: PgPool) {
+        let subject = "subject";
+
+        setup();
+        let state = TestState::from_pool(pool).await.unwrap();
+        let mut rng = state.rng();
+        let cookies = CookieHelper::new();
+
+        let claims_imports = UpstreamOAuthProviderClaimsImports {
+            email: UpstreamOAuthProviderImportPreference {
+                action: mas_data_model::UpstreamOAuthProviderImportAction::Force,
+                template: None,
+            },
+            resync: UpstreamOAuthProviderResyncPreference {
+                on_login: true,
+                in_background: false,
+            },
+            ..UpstreamOAuthProviderClaimsImports::default()
+        };
+
+        // The email changed upstream since the account was created
+        let id_token_claims = serde_json::json!({
+            "preferred_username": "john",
+            "email": "new@example.com",
+            "email_verified": true,
+        });
+
+        let id_token = sign_token(&mut rng, &state.key_store, id_token_claims.clone()).unwrap();
+
+        let mut repo = state.repository().await.unwrap();
+        let provider = repo
+            .upstream_oauth_provider()
+            .add(
+                &mut rng,
+                &state.clock,
+                UpstreamOAuthProviderParams {
+                    issuer: Some("https://example.com/".to_owned()),
+                    human_name: Some("Example Ltd.".to_owned()),
+                    brand_name: None,
+                    scope: Scope::from_iter([OPENID]),
+                    token_endpoint_auth_method: UpstreamOAuthProviderTokenAuthMethod::None,
+                    token_endpoint_signing_alg: None,
+                    id_token_signed_response_alg: JsonWebSignatureAlg::Rs256,
+                    client_id: "client".to_owned(),
+                    encrypted_client_secret: None,
+                    claims_imports,
+                    authorization_endpoint_override: None,
+                    token_endpoint_override: None,
+                    userinfo_endpoint_override: None,
+                    fetch_userinfo: false,
+                    userinfo_signed_response_alg: None,
+                    jwks_uri_override: None,
+                    discovery_mode: mas_data_model::UpstreamOAuthProviderDiscoveryMode::Oidc,
+                    pkce_mode: mas_data_model::UpstreamOAuthProviderPkceMode::Auto,
+                    response_mode: None,
+                    additional_authorization_parameters: Vec::new(),
+                    forward_login_hint: false,
+                    on_backchannel_logout:
+                        mas_data_model::UpstreamOAuthProviderOnBackchannelLogout::DoNothing,
+                    saml: None,
+                    email_domains: Vec::new(),
+                    store_tokens: false,
+                    allow_initiated_login: false,
+                    encrypted_client_keys: None,
+                    ui_order: 0,
+                },
+            )
+            .await
+            .unwrap();
+
+        let (link, session) = add_linked_upstream_session(
+            &mut rng,
+            &state.clock,
+            &mut repo,
+            &provider,
+            subject,
+            &id_token.into_string(),
+            id_token_claims,
+        )
+        .await
+        .unwrap();
+
+        let user = repo
+            .user()
+            .add(&mut rng, &state.clock, "john".to_owned())
+            .await
+            .unwrap();
+        repo.user_email()
+            .add(&mut rng, &state.clock, &user, "old@example.com".to_owned())
+            .await
+            .unwrap();
+        repo.upstream_oauth_link()
+            .associate_to_user(&link, &user)
+            .await
+            .unwrap();
+
+        repo.save().await.unwrap();
+
+        let cookie_jar = state.cookie_jar();
+        let upstream_sessions = UpstreamSessionsCookie::default()
+            .add(session.id, provider.id, "state".to_owned(), None)
+            .add_link_to_session(session.id, link.id)
+            .unwrap();
+        let cookie_jar = upstream_sessions.save(cookie_jar, &state.clock);
+        cookies.import(cookie_jar);
+
+        let request = Request::get(&*mas_router::UpstreamOAuth2Link::new(link.id).path()).empty();
+        let request = cookies.with_cookies(request);
+        let response = state.request(request).await;
+        cookies.save_cookies(&response);
+        response.assert_status(StatusCode::SEE_OTHER);
+
+        // The new email was imported, and the old one was kept
+        let mut repo = state.repository().await.unwrap();
+        let count = repo
+            .user_email()
+            .count(UserEmailFilter::new().for_user(&user))
+            .await
+            .unwrap();
+        assert_eq!(count, 2);
+
+        let count = repo
+            .user_email()
+            .count(
+                UserEmailFilter::new()
+                    .for_user(&user)
+                    .for_email("new@example.com"),
+            )
+            .await
+            .unwrap();
+        assert_eq!(count, 1);
+    }
+
+    #[sqlx::test(migrator = "mas_storage_pg::MIGRATOR")]
+    async fn test_authorization_imports_on_login(pool: PgPool) {
+        let subject = "subject";
+
+        setup();
+        let state = TestState::from_pool(pool).await.unwrap();
+        let mut rng = state.rng();
+        let cookies = CookieHelper::new();
+
+        let claims_imports = UpstreamOAuthProviderClaimsImports {
+            can_request_admin: UpstreamOAuthProviderSubjectPreference {
+                template: Some("{{ 'admins' in user.groups }}".to_owned()),
+            },
+            attributes: UpstreamOAuthProviderSubjectPreference {
+                template: Some(r#"{{ {"groups": user.groups} | tojson }}"#.to_owned()),
+            },
+            ..UpstreamOAuthProviderClaimsImports::default()
+        };
+
+        let id_token_claims = serde_json::json!({
+            "preferred_username": "john",
+            "groups": ["admins", "staff"],
+        });
+
+        let id_token = sign_token(&mut rng, &state.key_store, id_token_claims.clone()).unwrap();
+
+        let mut repo = state.repository().await.unwrap();
+        let provider = repo
+            .upstream_oauth_provider()
+            .add(
+                &mut rng,
+                &state.clock,
+                UpstreamOAuthProviderParams {
+                    issuer: Some("https://example.com/".to_owned()),
+                    human_name: Some("Example Ltd.".to_owned()),
+                    brand_name: None,
+                    scope: Scope::from_iter([OPENID]),
+                    token_endpoint_auth_method: UpstreamOAuthProviderTokenAuthMethod::None,
+                    token_endpoint_signing_alg: None,
+                    id_token_signed_response_alg: JsonWebSignatureAlg::Rs256,
+                    client_id: "client".to_owned(),
+                    encrypted_client_secret: None,
+                    claims_imports,
+                    authorization_endpoint_override: None,
+                    token_endpoint_override: None,
+                    userinfo_endpoint_override: None,
+                    fetch_userinfo: false,
+                    userinfo_signed_response_alg: None,
+                    jwks_uri_override: None,
+                    discovery_mode: mas_data_model::UpstreamOAuthProviderDiscoveryMode::Oidc,
+                    pkce_mode: mas_data_model::UpstreamOAuthProviderPkceMode::Auto,
+                    response_mode: None,
+                    additional_authorization_parameters: Vec::new(),
+                    forward_login_hint: false,
+                    on_backchannel_logout:
+                        mas_data_model::UpstreamOAuthProviderOnBackchannelLogout::DoNothing,
+                    saml: None,
+                    email_domains: Vec::new(),
+                    store_tokens: false,
+                    allow_initiated_login: false,
+                    encrypted_client_keys: None,
+                    ui_order: 0,
+                },
+            )
+            .await
+            .unwrap();
+
+        let (link, session) = add_linked_upstream_session(
+            &mut rng,
+            &state.clock,
+            &mut repo,
+            &provider,
+            subject,
+            &id_token.into_string(),
+            id_token_claims,
+        )
+        .await
+        .unwrap();
+
+        let user = repo
+            .user()
+            .add(&mut rng, &state.clock, "john".to_owned())
+            .await
+            .unwrap();
+        repo.upstream_oauth_link()
+            .associate_to_user(&link, &user)
+            .await
+            .unwrap();
+
+        repo.save().await.unwrap();
+
+        let cookie_jar = state.cookie_jar();
+        let upstream_sessions = UpstreamSessionsCookie::default()
+            .add(session.id, provider.id, "state".to_owned(), None)
+            .add_link_to_session(session.id, link.id)
+            .unwrap();
+        let cookie_jar = upstream_sessions.save(cookie_jar, &state.clock);
+        cookies.import(cookie_jar);
+
+        let request = Request::get(&*mas_router::UpstreamOAuth2Link::new(link.id).path()).empty();
+        let request = cookies.with_cookies(request);
+        let response = state.request(request).await;
+        cookies.save_cookies(&response);
+        response.assert_status(StatusCode::SEE_OTHER);
+
+        // The user was granted admin rights, and its groups were stored
+        let mut repo = state.repository().await.unwrap();
+        let user = repo.user().lookup(user.id).await.unwrap().unwrap();
+        assert!(user.can_request_admin);
+
+        let attributes = repo
+            .upstream_oauth_link()
+            .attributes_for_user(&user)
+            .await
+            .unwrap();
+        assert_eq!(
+            attributes.get("groups"),
+            Some(&serde_json::json!(["admins", "staff"]))
+        );
+    }
 }
@@ -7,8 +7,8 @@
 use std::string::FromUtf8Error;
 
 use mas_data_model::{UpstreamOAuthProvider, UpstreamOAuthProviderTokenAuthMethod};
-use mas_iana::jose::JsonWebSignatureAlg;
-use mas_keystore::{DecryptError, Encrypter, Keystore};
+use mas_iana::jose::{JsonWebKeyUse, JsonWebSignatureAlg};
+use mas_keystore::{DecryptError, Encrypter, JsonWebKey, JsonWebKeySet, Keystore, PrivateKey};
 use mas_oidc_client::types::client_credentials::ClientCredentials;
 use pkcs8::DecodePrivateKey;
 use serde::Deserialize;
@@ -20,10 +20,15 @@ pub(crate) mod backchannel_logout;
 pub(crate) mod cache;
 pub(crate) mod callback;
 mod cookie;
+pub(crate) mod health;
+pub(crate) mod initiate;
+pub(crate) mod jwks;
 pub(crate) mod link;
+pub(crate) mod saml;
+pub(crate) mod sync;
 mod template;
 
-use self::cookie::UpstreamSessions as UpstreamSessionsCookie;
+use self::{cache::ClientCertificateCache, cookie::UpstreamSessions as UpstreamSessionsCookie};
 
 #[derive(Debug, Error)]
 #[allow(clippy::enum_variant_names)]
@@ -54,6 +59,39 @@ enum ProviderCredentialsError {
         #[from]
         inner: pkcs8::Error,
     },
+
+    #[error("Provider doesn't have a client certificate")]
+    MissingClientCertificate,
+
+    #[error("Could not decrypt client keys")]
+    DecryptClientKeys {
+        #[source]
+        inner: DecryptError,
+    },
+
+    #[error("Invalid JSON in client keys")]
+    InvalidClientKeysJson {
+        #[source]
+        inner: serde_json::Error,
+    },
+
+    #[error("Could not load client private key")]
+    InvalidClientKey {
+        #[from]
+        inner: mas_keystore::LoadError,
+    },
+
+    #[error("Could not load client certificate")]
+    InvalidClientCertificate {
+        #[from]
+        inner: mas_http::ClientCertificateError,
+    },
+
+    #[error("Could not build HTTP client with the client certificate")]
+    ClientCertificateHttpClient {
+        #[from]
+        inner: rustls::Error,
+    },
 }
 
 #[derive(Debug, Deserialize)]
@@ -63,6 +101,118 @@ pub struct SignInWithApple {
     pub key_id: String,
 }
 
+/// The key material used to authenticate against a provider, as stored
+/// encrypted in the database
+#[derive(Debug, Deserialize)]
+struct ClientKeys {
+    #[serde(default)]
+    keys: Vec<ClientKey>,
+    certificate: Option<ClientKeysCertificate>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ClientKey {
+    kid: String,
+    key: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ClientKeysCertificate {
+    certificate: String,
+    private_key: String,
+}
+
+impl ClientKeys {
+    /// Decrypt the key material of the provider, if it has any
+    fn for_provider(
+        provider: &UpstreamOAuthProvider,
+        encrypter: &Encrypter,
+    ) -> Result<Option<Self>, ProviderCredentialsError> {
+        let Some(encrypted_client_keys) = provider.encrypted_client_keys.as_deref() else {
+            return Ok(None);
+        };
+
+        let decrypted = encrypter
+            .decrypt_string(encrypted_client_keys)
+            .map_err(|inner| ProviderCredentialsError::DecryptClientKeys { inner })?;
+        let keys = serde_json::from_slice(&decrypted)
+            .map_err(|inner| ProviderCredentialsError::InvalidClientKeysJson { inner })?;
+        Ok(Some(keys))
+    }
+
+    /// Build a keystore out of the private keys, if there are any
+    fn keystore(&self) -> Result<Option<Keystore>, ProviderCredentialsError> {
+        if self.keys.is_empty() {
+            return Ok(None);
+        }
+
+        let keys = self
+            .keys
+            .iter()
+            .map(|key| {
+                let private_key = PrivateKey::load_pem(&key.key)?;
+                Ok(JsonWebKey::new(private_key)
+                    .with_kid(key.kid.clone())
+                    .with_use(JsonWebKeyUse::Sig))
+            })
+            .collect::<Result<Vec<_>, ProviderCredentialsError>>()?;
+
+        Ok(Some(Keystore::new(JsonWebKeySet::new(keys))))
+    }
+}
+
+/// Get the keystore used to sign the client assertions sent to the provider,
+/// if it has its own keys
+fn keystore_for_provider(
+    provider: &UpstreamOAuthProvider,
+    encrypter: &Encrypter,
+) -> Result<Option<Keystore>, ProviderCredentialsError> {
+    match ClientKeys::for_provider(provider, encrypter)? {
+        Some(client_keys) => client_keys.keystore(),
+        None => Ok(None),
+    }
+}
+
+/// Get the HTTP client to use for the requests authenticated with the client
+/// credentials of the provider.
+///
+/// If the provider has a client certificate, this returns a client presenting
+/// it, built once and kept in the [`ClientCertificateCache`] until the keys of
+/// the provider change, else it returns the shared client.
+fn http_client_for_provider(
+    provider: &UpstreamOAuthProvider,
+    http_client: &reqwest::Client,
+    certificate_cache: &ClientCertificateCache,
+    encrypter: &Encrypter,
+) -> Result<reqwest::Client, ProviderCredentialsError> {
+    let Some(encrypted_client_keys) = provider.encrypted_client_keys.as_deref() else {
+        return Ok(http_client.clone());
+    };
+
+    if let Some(client) = certificate_cache.get(provider.id, encrypted_client_keys) {
+        return Ok(client);
+    }
+
+    let certificate = ClientKeys::for_provider(provider, encrypter)?
+        .and_then(|client_keys| client_keys.certificate);
+
+    let Some(certificate) = certificate else {
+        return Ok(http_client.clone());
+    };
+
+    let certificate =
+        mas_http::ClientCertificate::from_pem(&certificate.certificate, &certificate.private_key)?;
+    let client = mas_http::reqwest_client_with_certificate(&certificate)?;
+
+    certificate_cache.insert(
+        provider.id,
+        encrypted_client_keys.to_owned(),
+        client.clone(),
+    );
+
+    Ok(client)
+}
+
 fn client_credentials_for_provider(
     provider: &UpstreamOAuthProvider,
     token_endpoint: &Url,
@@ -82,6 +232,11 @@ fn client_credentials_for_provider(
         })
         .transpose()?;
 
+    let client_keys = ClientKeys::for_provider(provider, encrypter)?;
+    let has_client_certificate = client_keys
+        .as_ref()
+        .is_some_and(|client_keys| client_keys.certificate.is_some());
+
     let client_credentials = match provider.token_endpoint_auth_method {
         UpstreamOAuthProviderTokenAuthMethod::None => ClientCredentials::None { client_id },
 
@@ -114,15 +269,42 @@ fn client_credentials_for_provider(
             }
         }
 
-        UpstreamOAuthProviderTokenAuthMethod::PrivateKeyJwt => ClientCredentials::PrivateKeyJwt {
-            client_id,
-            keystore: keystore.clone(),
-            signing_algorithm: provider
-                .token_endpoint_signing_alg
-                .clone()
-                .unwrap_or(JsonWebSignatureAlg::Rs256),
-            token_endpoint: token_endpoint.clone(),
-        },
+        UpstreamOAuthProviderTokenAuthMethod::PrivateKeyJwt => {
+            // Use the keys of the provider if it has its own, else the global ones
+            let keystore = match &client_keys {
+                Some(client_keys) => client_keys.keystore()?,
+                None => None,
+            }
+            .unwrap_or_else(|| keystore.clone());
+
+            ClientCredentials::PrivateKeyJwt {
+                client_id,
+                keystore,
+                signing_algorithm: provider
+                    .token_endpoint_signing_alg
+                    .clone()
+                    .unwrap_or(JsonWebSignatureAlg::Rs256),
+                token_endpoint: token_endpoint.clone(),
+            }
+        }
+
+        // The certificate itself is presented by the HTTP client returned by
+        // `http_client_for_provider`
+        UpstreamOAuthProviderTokenAuthMethod::TlsClientAuth => {
+            if !has_client_certificate {
+                return Err(ProviderCredentialsError::MissingClientCertificate);
+            }
+
+            ClientCredentials::TlsClientAuth { client_id }
+        }
+
+        UpstreamOAuthProviderTokenAuthMethod::SelfSignedTlsClientAuth => {
+            if !has_client_certificate {
+                return Err(ProviderCredentialsError::MissingClientCertificate);
+            }
+
+            ClientCredentials::SelfSignedTlsClientAuth { client_id }
+        }
 
         UpstreamOAuthProviderTokenAuthMethod::SignInWithApple => {
             let params = client_secret.ok_or(ProviderCredentialsError::MissingClientSecret)?;
@@ -0,0 +1,497 @@
+// Copyright 2026 Element Creations Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+// Please see LICENSE files in the repository root for full details.
+
+//! Handlers for upstream SAML 2.0 identity providers: the service provider
+//! metadata, and the assertion consumer service receiving the responses.
+
+use std::{string::FromUtf8Error, sync::LazyLock};
+
+use axum::{
+    Form,
+    extract::{Path, State},
+    response::{Html, IntoResponse, Response},
+};
+use axum_extra::typed_header::TypedHeader;
+use headers::ContentType;
+use hyper::StatusCode;
+use mas_axum_utils::{GenericError, InternalError, cookies::CookieJar};
+use mas_data_model::{
+    BoxClock, BoxRng, Clock, UpstreamOAuthProvider, UpstreamOAuthProviderSamlConfig,
+};
+use mas_keystore::{DecryptError, Encrypter};
+use mas_router::UrlBuilder;
+use mas_saml::{
+    Certificate, CertificateError, PrivateKeyError, RsaPrivateKey,
+    metadata::ServiceProviderMetadata,
+    response::{Assertion, ResponseError, ResponseValidation},
+};
+use mas_storage::{
+    BoxRepository,
+    upstream_oauth2::{
+        UpstreamOAuthLinkRepository, UpstreamOAuthProviderRepository,
+        UpstreamOAuthSessionRepository,
+    },
+};
+use mas_templates::{FormPostContext, Templates};
+use opentelemetry::{Key, KeyValue, metrics::Counter};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use ulid::Ulid;
+
+use super::{
+    UpstreamSessionsCookie,
+    template::{AttributeMappingContext, environment},
+};
+use crate::{METER, PreferredLanguage, impl_from_error_for_route};
+
+static ACS_COUNTER: LazyLock<Counter<u64>> = LazyLock::new(|| {
+    METER
+        .u64_counter("mas.upstream_oauth2.saml_acs")
+        .with_description("Number of SAML responses received on the assertion consumer service")
+        .build()
+});
+const PROVIDER: Key = Key::from_static_str("provider");
+const RESULT: Key = Key::from_static_str("result");
+
+#[derive(Debug, Error)]
+pub(crate) enum SamlSettingsError {
+    #[error("Provider is not a SAML provider")]
+    NotSaml,
+
+    #[error("Invalid certificate in the provider configuration")]
+    Certificate(#[from] CertificateError),
+
+    #[error("Could not decrypt the service provider private key")]
+    Decrypt(#[from] DecryptError),
+
+    #[error("Service provider private key is not valid UTF-8")]
+    Utf8(#[from] FromUtf8Error),
+
+    #[error("Could not parse the service provider private key")]
+    PrivateKey(#[from] PrivateKeyError),
+}
+
+/// The parsed settings of a SAML provider
+pub(crate) struct SamlSettings<'a> {
+    pub config: &'a UpstreamOAuthProviderSamlConfig,
+    pub idp_certificates: Vec<Certificate>,
+    pub sp_certificate: Option<Certificate>,
+    pub sp_private_key: Option<RsaPrivateKey>,
+}
+
+impl<'a> SamlSettings<'a> {
+    /// Parse the SAML settings of a provider, decrypting the private key of
+    /// the service provider if there is one
+    pub fn load(
+        provider: &'a UpstreamOAuthProvider,
+        encrypter: &Encrypter,
+    ) -> Result<Self, SamlSettingsError> {
+        let config = provider.saml.as_ref().ok_or(SamlSettingsError::NotSaml)?;
+
+        let idp_certificates = config
+            .idp_certificates
+            .iter()
+            .map(|pem| Certificate::parse(pem))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let sp_certificate = config
+            .sp_certificate
+            .as_deref()
+            .map(Certificate::parse)
+            .transpose()?;
+
+        // The private key is stored encrypted in place of the client secret
+        let sp_private_key = provider
+            .encrypted_client_secret
+            .as_deref()
+            .map(|encrypted| -> Result<_, SamlSettingsError> {
+                let decrypted = encrypter.decrypt_string(encrypted)?;
+                let pem = String::from_utf8(decrypted)?;
+                Ok(mas_saml::private_key_from_pem(&pem)?)
+            })
+            .transpose()?;
+
+        Ok(Self {
+            config,
+            idp_certificates,
+            sp_certificate,
+            sp_private_key,
+        })
+    }
+
+    /// The key to sign authentication requests with, if they should be signed
+    pub fn signing_key(&self) -> Option<&RsaPrivateKey> {
+        if self.config.sign_authn_requests {
+            self.sp_private_key.as_ref()
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub(crate) enum RouteError {
+    #[error("Session not found")]
+    SessionNotFound,
+
+    #[error("Provider not found")]
+    ProviderNotFound,
+
+    #[error("Provider mismatch")]
+    ProviderMismatch,
+
+    #[error("Session already completed")]
+    AlreadyCompleted,
+
+    #[error("State parameter mismatch")]
+    StateMismatch,
+
+    #[error("Missing RelayState parameter")]
+    MissingRelayState,
+
+    #[error("Missing session cookie")]
+    MissingCookie,
+
+    #[error("Invalid SAML response")]
+    InvalidResponse(#[from] ResponseError),
+
+    #[error("Could not extract subject from the assertion")]
+    ExtractSubject(#[source] minijinja::Error),
+
+    #[error("Subject is empty")]
+    EmptySubject,
+
+    #[error(transparent)]
+    Internal(Box<dyn std::error::Error + Send + Sync + 'static>),
+}
+
+impl_from_error_for_route!(mas_templates::TemplateError);
+impl_from_error_for_route!(mas_storage::RepositoryError);
+impl_from_error_for_route!(SamlSettingsError);
+impl_from_error_for_route!(super::cookie::UpstreamSessionNotFound);
+
+impl IntoResponse for RouteError {
+    fn into_response(self) -> axum::response::Response {
+        match self {
+            Self::Internal(e) => InternalError::new(e).into_response(),
+            e @ (Self::ProviderNotFound | Self::SessionNotFound) => {
+                GenericError::new(StatusCode::NOT_FOUND, e).into_response()
+            }
+            e => GenericError::new(StatusCode::BAD_REQUEST, e).into_response(),
+        }
+    }
+}
+
+fn samlmetadata() -> mime::Mime {
+    "application/samlmetadata+xml".parse().unwrap()
+}
+
+#[tracing::instrument(
+    name = "handlers.upstream_oauth2.saml.metadata",
+    fields(upstream_oauth_provider.id = %provider_id),
+    skip_all,
+)]
+pub(crate) async fn metadata(
+    mut repo: BoxRepository,
+    State(url_builder): State<UrlBuilder>,
+    State(encrypter): State<Encrypter>,
+    Path(provider_id): Path<Ulid>,
+) -> Result<Response, RouteError> {
+    let provider = repo
+        .upstream_oauth_provider()
+        .lookup(provider_id)
+        .await?
+        .filter(UpstreamOAuthProvider::enabled)
+        .filter(|provider| provider.saml.is_some())
+        .ok_or(RouteError::ProviderNotFound)?;
+
+    let settings = SamlSettings::load(&provider, &encrypter)?;
+    let acs_url = url_builder.upstream_saml2_acs(provider.id);
+
+    let metadata = ServiceProviderMetadata {
+        entity_id: &provider.client_id,
+        assertion_consumer_service_url: &acs_url,
+        certificate: settings.sp_certificate.as_ref(),
+        name_id_format: settings.config.name_id_format.as_deref(),
+        authn_requests_signed: settings.signing_key().is_some(),
+        want_assertions_signed: settings.config.want_assertions_signed,
+        allow_cbc_encryption: settings.config.allow_cbc_encryption,
+    };
+
+    Ok((
+        TypedHeader(ContentType::from(samlmetadata())),
+        metadata.to_xml(),
+    )
+        .into_response())
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct AcsParams {
+    #[serde(rename = "SAMLResponse")]
+    saml_response: String,
+
+    #[serde(rename = "RelayState", skip_serializing_if = "Option::is_none")]
+    relay_state: Option<String>,
+
+    /// An extra parameter to track whether the POST request was re-made by us
+    /// to the same URL to escape Same-Site cookies restrictions
+    #[serde(default)]
+    did_mas_repost_to_itself: bool,
+}
+
+/// Convert the assertion into a claims object, which is then used in the
+/// claims import templates as the `user` variable
+fn assertion_claims(assertion: &Assertion) -> serde_json::Value {
+    let mut claims = serde_json::Map::new();
+
+    for attribute in &assertion.attributes {
+        let value = match attribute.values.as_slice() {
+            [value] => serde_json::Value::String(value.clone()),
+            values => values.iter().cloned().collect(),
+        };
+
+        if let Some(friendly_name) = &attribute.friendly_name {
+            claims.insert(friendly_name.clone(), value.clone());
+        }
+        claims.insert(attribute.name.clone(), value);
+    }
+
+    // Those take precedence over attributes with the same name
+    claims.insert("sub".to_owned(), assertion.name_id.clone().into());
+    if let Some(name_id_format) = &assertion.name_id_format {
+        claims.insert("name_id_format".to_owned(), name_id_format.clone().into());
+    }
+    if let Some(session_index) = &assertion.session_index {
+        claims.insert("session_index".to_owned(), session_index.clone().into());
+    }
+
+    serde_json::Value::Object(claims)
+}
+
+#[tracing::instrument(
+    name = "handlers.upstream_oauth2.saml.acs",
+    fields(upstream_oauth_provider.id = %provider_id),
+    skip_all,
+)]
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn acs(
+    mut rng: BoxRng,
+    clock: BoxClock,
+    mut repo: BoxRepository,
+    State(url_builder): State<UrlBuilder>,
+    State(encrypter): State<Encrypter>,
+    State(templates): State<Templates>,
+    PreferredLanguage(locale): PreferredLanguage,
+    cookie_jar: CookieJar,
+    Path(provider_id): Path<Ulid>,
+    Form(params): Form<AcsParams>,
+) -> Result<Response, RouteError> {
+    let provider = repo
+        .upstream_oauth_provider()
+        .lookup(provider_id)
+        .await?
+        .filter(UpstreamOAuthProvider::enabled)
+        .filter(|provider| provider.saml.is_some())
+        .ok_or(RouteError::ProviderNotFound)?;
+
+    let sessions_cookie = UpstreamSessionsCookie::load(&cookie_jar);
+
+    // Responses are always a cross-site form POST, so the session cookie, which
+    // has a `Same-Site` policy set to `Lax`, is missing. Like for the OAuth 2.0
+    // `form_post` response mode, we render a form which posts the same values
+    // back to the same URL, and set a flag to avoid doing it in a loop.
+    if sessions_cookie.is_empty() && !params.did_mas_repost_to_itself {
+        let params = AcsParams {
+            did_mas_repost_to_itself: true,
+            ..params
+        };
+        let context = FormPostContext::new_for_current_url(params).with_language(&locale);
+        let html = templates.render_form_post(&context)?;
+        return Ok(Html(html).into_response());
+    }
+
+    // Unsolicited responses are not supported, so we always expect the relay
+    // state we sent in the request
+    let Some(relay_state) = params.relay_state else {
+        return Err(RouteError::MissingRelayState);
+    };
+
+    let (session_id, _post_auth_action) = sessions_cookie
+        .find_session(provider_id, &relay_state)
+        .map_err(|_| RouteError::MissingCookie)?;
+
+    let session = repo
+        .upstream_oauth_session()
+        .lookup(session_id)
+        .await?
+        .ok_or(RouteError::SessionNotFound)?;
+
+    if provider.id != session.provider_id {
+        return Err(RouteError::ProviderMismatch);
+    }
+
+    if relay_state != session.state_str {
+        return Err(RouteError::StateMismatch);
+    }
+
+    if !session.is_pending() {
+        return Err(RouteError::AlreadyCompleted);
+    }
+
+    let settings = SamlSettings::load(&provider, &encrypter)?;
+    let acs_url = url_builder.upstream_saml2_acs(provider.id);
+
+    // The nonce of the session is the ID of the AuthnRequest we sent
+    let validation = ResponseValidation {
+        identity_provider_entity_id: &settings.config.idp_entity_id,
+        identity_provider_certificates: &settings.idp_certificates,
+        service_provider_entity_id: &provider.client_id,
+        assertion_consumer_service_url: &acs_url,
+        in_response_to: Some(session.nonce.as_deref().ok_or(RouteError::StateMismatch)?),
+        now: clock.now(),
+        decryption_key: settings.sp_private_key.as_ref(),
+        require_signed_assertions: settings.config.want_assertions_signed,
+        require_encrypted_assertions: settings.config.want_assertions_encrypted,
+        allow_cbc_encryption: settings.config.allow_cbc_encryption,
+    };
+
+    let assertion = match validation.validate(&params.saml_response) {
+        Ok(assertion) => assertion,
+        Err(e) => {
+            ACS_COUNTER.add(
+                1,
+                &[
+                    KeyValue::new(PROVIDER, provider_id.to_string()),
+                    KeyValue::new(RESULT, "error"),
+                ],
+            );
+            return Err(e.into());
+        }
+    };
+
+    ACS_COUNTER.add(
+        1,
+        &[
+            KeyValue::new(PROVIDER, provider_id.to_string()),
+            KeyValue::new(RESULT, "success"),
+        ],
+    );
+
+    // The attributes are exposed to the templates like the userinfo claims
+    // of OIDC providers, which is also how they are stored on the session
+    let userinfo = assertion_claims(&assertion);
+    let context = AttributeMappingContext::new()
+        .with_userinfo_claims(userinfo.clone())
+        .build();
+
+    let env = environment();
+
+    let template = provider
+        .claims_imports
+        .subject
+        .template
+        .as_deref()
+        .unwrap_or("{{ user.sub }}");
+    let subject = env
+        .render_str(template, context.clone())
+        .map_err(RouteError::ExtractSubject)?;
+
+    if subject.is_empty() {
+        return Err(RouteError::EmptySubject);
+    }
+
+    let maybe_link = repo
+        .upstream_oauth_link()
+        .find_by_subject(&provider, &subject)
+        .await?;
+
+    let link = if let Some(link) = maybe_link {
+        link
+    } else {
+        let human_account_name = provider
+            .claims_imports
+            .account_name
+            .template
+            .as_deref()
+            .and_then(|template| match env.render_str(template, context) {
+                Ok(name) => Some(name),
+                Err(e) => {
+                    tracing::warn!(
+                        error = &e as &dyn std::error::Error,
+                        "Failed to render account name"
+                    );
+                    None
+                }
+            });
+
+        repo.upstream_oauth_link()
+            .add(&mut rng, &clock, &provider, subject, human_account_name)
+            .await?
+    };
+
+    let session = repo
+        .upstream_oauth_session()
+        .complete_with_link(&clock, session, &link, None, None, None, Some(userinfo))
+        .await?;
+
+    let cookie_jar = sessions_cookie
+        .add_link_to_session(session.id, link.id)?
+        .save(cookie_jar, &clock);
+
+    repo.save().await?;
+
+    Ok((
+        cookie_jar,
+        url_builder.redirect(&mas_router::UpstreamOAuth2Link::new(link.id)),
+    )
+        .into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use mas_saml::response::Attribute;
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_assertion_claims() {
+        let assertion = Assertion {
+            id: "_assertion".to_owned(),
+            issuer: "https://idp.example.com/".to_owned(),
+            name_id: "alice".to_owned(),
+            name_id_format: Some("urn:oasis:names:tc:SAML:2.0:nameid-format:persistent".to_owned()),
+            session_index: None,
+            attributes: vec![
+                Attribute {
+                    name: "urn:oid:0.9.2342.19200300.100.1.3".to_owned(),
+                    friendly_name: Some("mail".to_owned()),
+                    values: vec!["alice@example.com".to_owned()],
+                },
+                Attribute {
+                    name: "groups".to_owned(),
+                    friendly_name: None,
+                    values: vec!["admins".to_owned(), "users".to_owned()],
+                },
+                Attribute {
+                    name: "sub".to_owned(),
+                    friendly_name: None,
+                    values: vec!["not-the-subject".to_owned()],
+                },
+            ],
+        };
+
+        assert_eq!(
+            assertion_claims(&assertion),
+            json!({
+                "sub": "alice",
+                "name_id_format": "urn:oasis:names:tc:SAML:2.0:nameid-format:persistent",
+                "mail": "alice@example.com",
+                "urn:oid:0.9.2342.19200300.100.1.3": "alice@example.com",
+                "groups": ["admins", "users"],
+            })
+        );
+    }
+}
@@ -0,0 +1,810 @@
+// Copyright 2026 Element Creations Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+// Please see LICENSE files in the repository root for full details.
+
+//! Apply the claims imports of an upstream provider to an already linked
+//! user, either on login or in the background
+
+use std::collections::HashMap;
+
+use anyhow::Context as _;
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use mas_data_model::{
+    Clock, UpstreamOAuthAuthorizationSession, UpstreamOAuthLink, UpstreamOAuthProvider,
+    UpstreamOAuthProviderHealthChecks, User,
+};
+use mas_jose::{
+    jwk::PublicJsonWebKeySet,
+    jwt::{Jwt, JwtDecodeError},
+};
+use mas_keystore::{Encrypter, Keystore};
+use mas_oidc_client::requests::jose::JwtVerificationData;
+use mas_storage::{
+    BoxRepository, RepositoryAccess, RepositoryError, RepositoryFactory,
+    queue::{DeactivateUserJob, ProvisionUserJob, QueueJobRepositoryExt as _},
+    upstream_oauth2::UpstreamOAuthLinkRepository,
+    user::{UserEmailFilter, UserEmailRepository, UserRepository},
+};
+use mas_tasks::UpstreamOAuthClaimsSync;
+use minijinja::Environment;
+use oauth2_types::requests::{AccessTokenRequest, AccessTokenResponse, RefreshTokenGrant};
+use rand::RngCore;
+use thiserror::Error;
+use url::Url;
+
+use super::{
+    cache::{ClientCertificateCache, LazyProviderInfos, MetadataCache},
+    client_credentials_for_provider, http_client_for_provider,
+    template::{AttributeMappingContext, environment},
+};
+
+pub(crate) const DEFAULT_DISPLAYNAME_TEMPLATE: &str = "{{ user.name }}";
+pub(crate) const DEFAULT_EMAIL_TEMPLATE: &str = "{{ user.email }}";
+
+/// Render an attribute template, logging and ignoring any error
+fn render(environment: &Environment, template: &str, context: &minijinja::Value) -> Option<String> {
+    match environment.render_str(template, context) {
+        Ok(value) if value.is_empty() => None,
+        Ok(value) => Some(value),
+        Err(e) => {
+            tracing::warn!(
+                error = &e as &dyn std::error::Error,
+                %template,
+                "Error while rendering template"
+            );
+            None
+        }
+    }
+}
+
+/// Build the attribute mapping context from the claims stored on a completed
+/// upstream authorization session
+///
+/// # Errors
+///
+/// Returns an error if the stored ID token can't be decoded
+pub(crate) fn context_from_session(
+    upstream_session: &UpstreamOAuthAuthorizationSession,
+) -> Result<minijinja::Value, JwtDecodeError> {
+    let mut context = AttributeMappingContext::new();
+    if let Some(id_token) = upstream_session.id_token() {
+        let (_, payload) = Jwt::try_from(id_token)?.into_parts();
+        context = context.with_id_token_claims(payload);
+    }
+    if let Some(extra_callback_parameters) = upstream_session.extra_callback_parameters() {
+        context = context.with_extra_callback_parameters(extra_callback_parameters.clone());
+    }
+    if let Some(userinfo) = upstream_session.userinfo() {
+        context = context.with_userinfo_claims(userinfo.clone());
+    }
+    Ok(context.build())
+}
+
+/// Apply the admin flag and user attributes imports of the provider to a
+/// user
+///
+/// Those are applied on registration and on every login, regardless of the
+/// resync preferences of the provider.
+///
+/// # Errors
+///
+/// Returns an error if the repository fails
+pub(crate) async fn apply_authorization_imports(
+    repo: &mut BoxRepository,
+    provider: &UpstreamOAuthProvider,
+    link: &UpstreamOAuthLink,
+    mut user: User,
+    context: &minijinja::Value,
+) -> Result<User, RepositoryError> {
+    let env = environment();
+    let imports = &provider.claims_imports;
+
+    if let Some(template) = imports.can_request_admin.template.as_deref()
+        && let Some(value) = render(&env, template, context)
+    {
+        let can_request_admin = value.trim().eq_ignore_ascii_case("true");
+        if can_request_admin != user.can_request_admin {
+            tracing::info!(
+                upstream_oauth_provider.id = %provider.id,
+                user.id = %user.id,
+                can_request_admin,
+                "Updating the admin flag of the user from the upstream provider"
+            );
+            user = repo
+                .user()
+                .set_can_request_admin(user, can_request_admin)
+                .await?;
+        }
+    }
+
+    if let Some(template) = imports.attributes.template.as_deref()
+        && let Some(value) = render(&env, template, context)
+    {
+        match serde_json::from_str::<serde_json::Map<String, serde_json::Value>>(&value) {
+            Ok(attributes) => {
+                repo.upstream_oauth_link()
+                    .set_attributes(link, Some(attributes))
+                    .await?;
+            }
+            Err(e) => {
+                tracing::warn!(
+                    error = &e as &dyn std::error::Error,
+                    upstream_oauth_provider.id = %provider.id,
+                    "The attributes template did not render to a JSON object"
+                );
+            }
+        }
+    }
+
+    Ok(user)
+}
+
+/// Apply the account status import of the provider to an existing user
+///
+/// Deactivated users are never reactivated, as deactivation is not reversible
+/// on the homeserver side. For the same reason, users are only deactivated if
+/// the provider explicitly allows it, and are locked instead otherwise.
+///
+/// Users are only unlocked if the lock was set by this import, so that locks
+/// set by an admin are left alone.
+///
+/// # Errors
+///
+/// Returns an error if the repository fails
+pub(crate) async fn apply_account_status_import(
+    rng: &mut (dyn RngCore + Send),
+    clock: &dyn Clock,
+    repo: &mut BoxRepository,
+    provider: &UpstreamOAuthProvider,
+    link: &UpstreamOAuthLink,
+    user: User,
+    context: &minijinja::Value,
+) -> Result<User, RepositoryError> {
+    let Some(template) = provider.claims_imports.account_status.template.as_deref() else {
+        return Ok(user);
+    };
+
+    let env = environment();
+    let Some(status) = render(&env, template, context) else {
+        return Ok(user);
+    };
+
+    if user.deactivated_at.is_some() {
+        return Ok(user);
+    }
+
+    let status = match status.trim() {
+        "deactivated" if !provider.claims_imports.account_status.allow_deactivation => "locked",
+        status => status,
+    };
+
+    let user = match status {
+        "active" if user.locked_at.is_some() => {
+            let user_locked_at = repo.upstream_oauth_link().get_user_locked_at(link).await?;
+            if user_locked_at.is_some() && user_locked_at == user.locked_at {
+                tracing::info!(
+                    upstream_oauth_provider.id = %provider.id,
+                    user.id = %user.id,
+                    "Unlocking user as requested by the upstream provider"
+                );
+                repo.upstream_oauth_link()
+                    .set_user_locked_at(link, None)
+                    .await?;
+                repo.user().unlock(user).await?
+            } else {
+                // The user was locked by someone else, leave it alone
+                user
+            }
+        }
+
+        "locked" if user.locked_at.is_none() => {
+            tracing::info!(
+                upstream_oauth_provider.id = %provider.id,
+                user.id = %user.id,
+                "Locking user as requested by the upstream provider"
+            );
+            let user = repo.user().lock(clock, user).await?;
+            repo.upstream_oauth_link()
+                .set_user_locked_at(link, user.locked_at)
+                .await?;
+            user
+        }
+
+        "deactivated" => {
+            tracing::info!(
+                upstream_oauth_provider.id = %provider.id,
+                user.id = %user.id,
+                "Deactivating user as requested by the upstream provider"
+            );
+            let user = repo.user().deactivate(clock, user).await?;
+            repo.queue_job()
+                .schedule_job(rng, clock, DeactivateUserJob::new(&user, false))
+                .await?;
+            user
+        }
+
+        "active" | "locked" => user,
+
+        other => {
+            tracing::warn!(
+                upstream_oauth_provider.id = %provider.id,
+                user.id = %user.id,
+                "Unknown account status {other:?} returned by the upstream provider, ignoring"
+            );
+            user
+        }
+    };
+
+    Ok(user)
+}
+
+/// Re-apply the forced or required claims imports of the provider to an
+/// existing user
+///
+/// Unlike during registration, a claim which fails to render is never fatal
+/// here: it is logged and skipped. Emails are only added if they aren't
+/// already used by another user, and existing emails are never removed.
+///
+/// Returns `true` if anything was imported.
+///
+/// # Errors
+///
+/// Returns an error if the repository fails
+pub(crate) async fn apply_claims_imports(
+    rng: &mut (dyn RngCore + Send),
+    clock: &dyn Clock,
+    repo: &mut BoxRepository,
+    provider: &UpstreamOAuthProvider,
+    user: &User,
+    context: &minijinja::Value,
+) -> Result<bool, RepositoryError> {
+    let env = environment();
+    let imports = &provider.claims_imports;
+
+    let display_name = if imports.displayname.is_forced_or_required() {
+        let template = imports
+            .displayname
+            .template
+            .as_deref()
+            .unwrap_or(DEFAULT_DISPLAYNAME_TEMPLATE);
+        render(&env, template, context)
+    } else {
+        None
+    };
+
+    let email = if imports.email.is_forced_or_required() {
+        let template = imports
+            .email
+            .template
+            .as_deref()
+            .unwrap_or(DEFAULT_EMAIL_TEMPLATE);
+        render(&env, template, context)
+    } else {
+        None
+    };
+
+    let mut email_added = false;
+    if let Some(email) = email {
+        let already_owned = repo
+            .user_email()
+            .count(UserEmailFilter::new().for_user(user).for_email(&email))
+            .await?
+            > 0;
+
+        if !already_owned {
+            let used_elsewhere = repo
+                .user_email()
+                .count(UserEmailFilter::new().for_email(&email))
+                .await?
+                > 0;
+
+            if used_elsewhere {
+                tracing::warn!(
+                    upstream_oauth_provider.id = %provider.id,
+                    user.id = %user.id,
+                    "Upstream provider returned an email which is already used by another user, not importing it"
+                );
+            } else {
+                repo.user_email().add(rng, clock, user, email).await?;
+                email_added = true;
+            }
+        }
+    }
+
+    if display_name.is_none() && !email_added {
+        return Ok(false);
+    }
+
+    // Provisioning the user again syncs both the display name and the emails to
+    // the homeserver
+    let mut job = ProvisionUserJob::new(user);
+    if let Some(display_name) = display_name {
+        job = job.set_display_name(display_name);
+    }
+    repo.queue_job().schedule_job(rng, clock, job).await?;
+
+    Ok(true)
+}
+
+/// Refreshes the claims and tokens of linked users using the refresh tokens
+/// stored on their upstream links
+#[derive(Clone)]
+pub struct UpstreamClaimsSyncer {
+    http_client: reqwest::Client,
+    metadata_cache: MetadataCache,
+    certificate_cache: ClientCertificateCache,
+    keystore: Keystore,
+    encrypter: Encrypter,
+}
+
+impl UpstreamClaimsSyncer {
+    #[must_use]
+    pub fn new(
+        http_client: reqwest::Client,
+        metadata_cache: MetadataCache,
+        certificate_cache: ClientCertificateCache,
+        keystore: Keystore,
+        encrypter: Encrypter,
+    ) -> Self {
+        Self {
+            http_client,
+            metadata_cache,
+            certificate_cache,
+            keystore,
+            encrypter,
+        }
+    }
+
+    /// Get new tokens from the provider using the given encrypted refresh
+    /// token
+    ///
+    /// This doesn't touch the database, so that no transaction is held while
+    /// talking to the provider.
+    async fn request_refresh(
+        &self,
+        mut rng: &mut (dyn RngCore + Send),
+        clock: &dyn Clock,
+        provider: &UpstreamOAuthProvider,
+        encrypted_refresh_token: &str,
+    ) -> Result<AccessTokenResponse, anyhow::Error> {
+        let refresh_token = self.encrypter.decrypt_string(encrypted_refresh_token)?;
+        let refresh_token = String::from_utf8(refresh_token)?;
+
+        let mut lazy_metadata =
+            LazyProviderInfos::new(&self.metadata_cache, provider, &self.http_client);
+
+        let client_credentials = client_credentials_for_provider(
+            provider,
+            lazy_metadata.token_endpoint().await?,
+            &self.keystore,
+            &self.encrypter,
+        )?;
+        let http_client = http_client_for_provider(
+            provider,
+            &self.http_client,
+            &self.certificate_cache,
+            &self.encrypter,
+        )?;
+
+        let token_response = mas_oidc_client::requests::token::request_access_token(
+            &http_client,
+            client_credentials,
+            lazy_metadata.token_endpoint().await?,
+            AccessTokenRequest::RefreshToken(RefreshTokenGrant {
+                refresh_token,
+                scope: None,
+            }),
+            clock.now(),
+            &mut rng,
+        )
+        .await
+        .context("Failed to refresh the upstream access token")?;
+
+        Ok(token_response)
+    }
+
+    /// Get new tokens from the provider using the refresh token stored on the
+    /// link, and store them back on the link
+    ///
+    /// Returns `None` if there is no refresh token stored on the link
+    async fn refresh(
+        &self,
+        rng: &mut (dyn RngCore + Send),
+        clock: &dyn Clock,
+        repo: &mut BoxRepository,
+        provider: &UpstreamOAuthProvider,
+        link: &UpstreamOAuthLink,
+    ) -> Result<Option<AccessTokenResponse>, anyhow::Error> {
+        let Some(encrypted_refresh_token) = repo
+            .upstream_oauth_link()
+            .get_encrypted_refresh_token(link)
+            .await?
+        else {
+            return Ok(None);
+        };
+
+        let token_response = self
+            .request_refresh(rng, clock, provider, &encrypted_refresh_token)
+            .await?;
+
+        // Store the new tokens, as the provider may have rotated the refresh token
+        store_tokens(
+            clock,
+            repo,
+            &self.encrypter,
+            provider,
+            link,
+            &token_response,
+        )
+        .await?;
+
+        Ok(Some(token_response))
+    }
+
+    /// Verify an ID token using the cached JWKS of the provider
+    ///
+    /// The JWKS is fetched again if the verification fails, in case the
+    /// provider rotated its keys.
+    async fn verify_id_token(
+        &self,
+        clock: &dyn Clock,
+        provider: &UpstreamOAuthProvider,
+        jwks_uri: &Url,
+        id_token: &str,
+    ) -> Result<HashMap<String, serde_json::Value>, anyhow::Error> {
+        let verify = |jwks: &PublicJsonWebKeySet| {
+            mas_oidc_client::requests::jose::verify_id_token(
+                id_token,
+                JwtVerificationData {
+                    issuer: provider.issuer.as_deref(),
+                    jwks,
+                    signing_algorithm: &provider.id_token_signed_response_alg,
+                    client_id: &provider.client_id,
+                },
+                None,
+                clock.now(),
+            )
+            .map(|id_token| id_token.into_parts().1)
+        };
+
+        let jwks = self
+            .metadata_cache
+            .get_jwks(&self.http_client, jwks_uri)
+            .await?;
+        if let Ok(claims) = verify(&jwks) {
+            return Ok(claims);
+        }
+
+        let jwks = self
+            .metadata_cache
+            .fetch_jwks(&self.http_client, jwks_uri)
+            .await?;
+        Ok(verify(&jwks)?)
+    }
+
+    /// Get the claims of the upstream account from the ID token of a token
+    /// response and from the userinfo endpoint, and build the attribute mapping
+    /// context from them
+    ///
+    /// Returns `None` if the provider gave us no claims at all.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the claims could not be fetched or verified, or if
+    /// they are about another account than the one behind the link
+    async fn fetch_claims(
+        &self,
+        clock: &dyn Clock,
+        provider: &UpstreamOAuthProvider,
+        link: &UpstreamOAuthLink,
+        token_response: &AccessTokenResponse,
+    ) -> Result<Option<minijinja::Value>, anyhow::Error> {
+        let mut lazy_metadata =
+            LazyProviderInfos::new(&self.metadata_cache, provider, &self.http_client);
+
+        let mut context = AttributeMappingContext::new();
+        let mut has_claims = false;
+        let mut id_token_subject = None;
+
+        if let Some(id_token) = token_response.id_token.as_deref() {
+            let claims = self
+                .verify_id_token(clock, provider, lazy_metadata.jwks_uri().await?, id_token)
+                .await?;
+
+            id_token_subject = claims
+                .get("sub")
+                .and_then(serde_json::Value::as_str)
+                .map(ToOwned::to_owned);
+            context = context.with_id_token_claims(claims);
+            has_claims = true;
+        }
+
+        if provider.fetch_userinfo {
+            let jwks = if provider.userinfo_signed_response_alg.is_some() {
+                Some(
+                    self.metadata_cache
+                        .get_jwks(&self.http_client, lazy_metadata.jwks_uri().await?)
+                        .await?,
+                )
+            } else {
+                None
+            };
+
+            let http_client = http_client_for_provider(
+                provider,
+                &self.http_client,
+                &self.certificate_cache,
+                &self.encrypter,
+            )?;
+            let userinfo = mas_oidc_client::requests::userinfo::fetch_userinfo(
+                &http_client,
+                lazy_metadata.userinfo_endpoint().await?,
+                token_response.access_token.as_str(),
+                provider
+                    .userinfo_signed_response_alg
+                    .as_ref()
+                    .zip(jwks.as_deref())
+                    .map(|(signing_algorithm, jwks)| JwtVerificationData {
+                        issuer: provider.issuer.as_deref(),
+                        jwks,
+                        signing_algorithm,
+                        client_id: &provider.client_id,
+                    }),
+            )
+            .await?;
+
+            // The userinfo response must be about the same user as the ID token
+            if let Some(id_token_subject) = &id_token_subject
+                && userinfo.get("sub").and_then(serde_json::Value::as_str)
+                    != Some(id_token_subject.as_str())
+            {
+                anyhow::bail!("The userinfo response is about another user than the ID token");
+            }
+
+            context = context.with_userinfo_claims(serde_json::json!(userinfo));
+            has_claims = true;
+        }
+
+        if !has_claims {
+            return Ok(None);
+        }
+
+        let context = context.build();
+
+        // Make sure the provider still gives us claims about the account behind the
+        // link, the same way the callback finds the link on login
+        let template = provider
+            .claims_imports
+            .subject
+            .template
+            .as_deref()
+            .unwrap_or("{{ user.sub }}");
+        let subject = environment()
+            .render_str(template, context.clone())
+            .context("Failed to render the subject template")?;
+        if subject != link.subject {
+            anyhow::bail!("The upstream provider returned claims about another subject");
+        }
+
+        Ok(Some(context))
+    }
+
+    /// Get the upstream access token stored on a link, refreshing it first if
+    /// it expired or is about to
+    ///
+    /// Returns `None` if the provider doesn't store tokens, or if there is no
+    /// usable access token stored on the link
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the access token could not be decrypted or
+    /// refreshed
+    pub(crate) async fn access_token(
+        &self,
+        rng: &mut (dyn RngCore + Send),
+        clock: &dyn Clock,
+        repo: &mut BoxRepository,
+        provider: &UpstreamOAuthProvider,
+        link: &UpstreamOAuthLink,
+    ) -> Result<Option<(String, Option<DateTime<Utc>>)>, anyhow::Error> {
+        if !provider.store_tokens {
+            return Ok(None);
+        }
+
+        let stored = repo
+            .upstream_oauth_link()
+            .get_encrypted_access_token(link)
+            .await?;
+
+        // Refresh the token if it is missing or expires in less than a minute
+        let fresh = stored.as_ref().is_some_and(|(_, expires_at)| {
+            expires_at.is_none_or(|expires_at| expires_at > clock.now() + Duration::minutes(1))
+        });
+
+        if !fresh
+            && let Some(token_response) = self.refresh(rng, clock, repo, provider, link).await?
+        {
+            let expires_at = token_response
+                .expires_in
+                .map(|expires_in| clock.now() + expires_in);
+            return Ok(Some((token_response.access_token, expires_at)));
+        }
+
+        let Some((encrypted_access_token, expires_at)) = stored else {
+            return Ok(None);
+        };
+
+        if expires_at.is_some_and(|expires_at| expires_at <= clock.now()) {
+            return Ok(None);
+        }
+
+        let access_token = self.encrypter.decrypt_string(&encrypted_access_token)?;
+        let access_token = String::from_utf8(access_token)?;
+        Ok(Some((access_token, expires_at)))
+    }
+}
+
+/// An error which can happen when storing the tokens obtained from an upstream
+/// provider
+#[derive(Debug, Error)]
+pub(crate) enum StoreTokensError {
+    #[error(transparent)]
+    Repository(#[from] RepositoryError),
+
+    #[error(transparent)]
+    Encryption(#[from] mas_keystore::aead::Error),
+}
+
+/// Store the tokens obtained from an upstream provider on the link, if they
+/// are needed later on
+///
+/// The refresh token is kept if the provider stores tokens or refreshes claims
+/// in the background. If the provider didn't give us a new one, the previous
+/// one is kept.
+pub(crate) async fn store_tokens(
+    clock: &dyn Clock,
+    repo: &mut BoxRepository,
+    encrypter: &Encrypter,
+    provider: &UpstreamOAuthProvider,
+    link: &UpstreamOAuthLink,
+    token_response: &AccessTokenResponse,
+) -> Result<(), StoreTokensError> {
+    if (provider.store_tokens || provider.claims_imports.resync.in_background)
+        && let Some(refresh_token) = token_response.refresh_token.as_deref()
+    {
+        let encrypted_refresh_token = encrypter.encrypt_to_string(refresh_token.as_bytes())?;
+        repo.upstream_oauth_link()
+            .set_encrypted_refresh_token(link, Some(encrypted_refresh_token))
+            .await?;
+    }
+
+    if provider.store_tokens {
+        let encrypted_access_token =
+            encrypter.encrypt_to_string(token_response.access_token.as_bytes())?;
+        let expires_at = token_response
+            .expires_in
+            .map(|expires_in| clock.now() + expires_in);
+        repo.upstream_oauth_link()
+            .set_encrypted_access_token(link, Some(encrypted_access_token), expires_at)
+            .await?;
+    }
+
+    Ok(())
+}
+
+#[async_trait]
+impl UpstreamOAuthClaimsSync for UpstreamClaimsSyncer {
+    async fn sync_link(
+        &self,
+        rng: &mut (dyn RngCore + Send),
+        clock: &dyn Clock,
+        repositories: &(dyn RepositoryFactory + Send + Sync),
+        provider: &UpstreamOAuthProvider,
+        link: &UpstreamOAuthLink,
+        user: &User,
+    ) -> Result<(), anyhow::Error> {
+        let mut repo = repositories.create().await?;
+        let encrypted_refresh_token = repo
+            .upstream_oauth_link()
+            .get_encrypted_refresh_token(link)
+            .await?;
+        repo.cancel().await?;
+
+        let Some(encrypted_refresh_token) = encrypted_refresh_token else {
+            return Ok(());
+        };
+
+        // Don't hold a database connection while talking to the provider
+        let token_response = self
+            .request_refresh(rng, clock, provider, &encrypted_refresh_token)
+            .await?;
+        let claims = self
+            .fetch_claims(clock, provider, link, &token_response)
+            .await;
+
+        let mut repo = repositories.create().await?;
+
+        // Store the new tokens even if we couldn't get the claims, as the provider
+        // may have rotated the refresh token
+        store_tokens(
+            clock,
+            &mut repo,
+            &self.encrypter,
+            provider,
+            link,
+            &token_response,
+        )
+        .await?;
+
+        if let Ok(Some(context)) = &claims {
+            // The user may have changed while we were talking to the provider
+            let user = repo.user().lookup(user.id).await?.filter(User::is_valid);
+
+            if let Some(user) = user {
+                let user = apply_account_status_import(
+                    rng, clock, &mut repo, provider, link, user, context,
+                )
+                .await?;
+                let user =
+                    apply_authorization_imports(&mut repo, provider, link, user, context).await?;
+                if user.deactivated_at.is_none() {
+                    apply_claims_imports(rng, clock, &mut repo, provider, &user, context).await?;
+                }
+            }
+        }
+
+        repo.save().await?;
+        claims?;
+
+        Ok(())
+    }
+
+    async fn refresh_tokens(
+        &self,
+        rng: &mut (dyn RngCore + Send),
+        clock: &dyn Clock,
+        repositories: &(dyn RepositoryFactory + Send + Sync),
+        provider: &UpstreamOAuthProvider,
+        link: &UpstreamOAuthLink,
+    ) -> Result<(), anyhow::Error> {
+        let mut repo = repositories.create().await?;
+        let encrypted_refresh_token = repo
+            .upstream_oauth_link()
+            .get_encrypted_refresh_token(link)
+            .await?;
+        repo.cancel().await?;
+
+        let Some(encrypted_refresh_token) = encrypted_refresh_token else {
+            return Ok(());
+        };
+
+        // Don't hold a database connection while talking to the provider
+        let token_response = self
+            .request_refresh(rng, clock, provider, &encrypted_refresh_token)
+            .await?;
+
+        let mut repo = repositories.create().await?;
+        store_tokens(
+            clock,
+            &mut repo,
+            &self.encrypter,
+            provider,
+            link,
+            &token_response,
+        )
+        .await?;
+        repo.save().await?;
+
+        Ok(())
+    }
+
+    async fn check_health(
+        &self,
+        provider: &UpstreamOAuthProvider,
+    ) -> UpstreamOAuthProviderHealthChecks {
+        super::health::check_provider_health(&self.http_client, &self.metadata_cache, provider)
+            .await
+    }
+}
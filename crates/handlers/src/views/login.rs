@@ -4,7 +4,10 @@
 // SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
 // Please see LICENSE files in the repository root for full details.
 
-use std::sync::{Arc, LazyLock};
+use std::{
+    str::FromStr,
+    sync::{Arc, LazyLock},
+};
 
 use axum::{
     extract::{Form, State},
@@ -17,9 +20,11 @@ use mas_axum_utils::{
     cookies::CookieJar,
     csrf::{CsrfExt, ProtectedForm},
 };
-use mas_data_model::{BoxClock, BoxRng, Clock};
+use mas_data_model::{BoxClock, BoxRng, Clock, Password, UpstreamOAuthProvider};
 use mas_i18n::DataLocale;
+use mas_ldap::Directory;
 use mas_matrix::HomeserverConnection;
+use mas_policy::Policy;
 use mas_router::{UpstreamOAuth2Authorize, UrlBuilder};
 use mas_storage::{
     BoxRepository, RepositoryAccess,
@@ -38,6 +43,7 @@ use zeroize::Zeroizing;
 use super::shared::{LoginHint, OptionalPostAuthAction, QueryLoginHint};
 use crate::{
     BoundActivityTracker, Limiter, METER, PreferredLanguage, RequesterFingerprint, SiteConfig,
+    ldap::DirectoryLoginError,
     passwords::{PasswordManager, PasswordVerificationResult},
     session::{SessionOrFallback, load_session_or_fallback},
 };
@@ -54,6 +60,7 @@ const RESULT: Key = Key::from_static_str("result");
 #[derive(Debug, Deserialize, Serialize)]
 pub(crate) struct LoginForm {
     username: String,
+    #[serde(default)]
     password: String,
 }
 
@@ -61,6 +68,15 @@ impl ToFormState for LoginForm {
     type Field = LoginFormField;
 }
 
+/// How the password of the user was checked
+enum LoginAuthentication {
+    /// Against the password hash stored in the database
+    Password(Password),
+
+    /// Against the LDAP directory, by binding as the given DN
+    Ldap { dn: String },
+}
+
 #[tracing::instrument(name = "handlers.views.login.get", skip_all)]
 pub(crate) async fn get(
     mut rng: BoxRng,
@@ -100,6 +116,22 @@ pub(crate) async fn get(
 
     let providers = repo.upstream_oauth_provider().all_enabled().await?;
 
+    // If the client hinted at an email address handled by one of the upstream
+    // providers, send the user straight to it. The email is passed along, so
+    // that the provider can forward it if configured to.
+    if let LoginHint::Email(email) = query_login_hint.parse_login_hint(homeserver.homeserver())
+        && let Some(provider) = provider_for_email(&providers, &email)
+    {
+        let mut destination =
+            UpstreamOAuth2Authorize::new(provider.id).with_login_hint(email.to_string());
+
+        if let Some(action) = query.post_auth_action {
+            destination = destination.and_then(action);
+        }
+
+        return Ok((cookie_jar, url_builder.redirect(&destination)).into_response());
+    }
+
     // If password-based login is disabled, and there is only one upstream provider,
     // we can directly start an authorization flow
     if !site_config.password_login_enabled && providers.len() == 1 {
@@ -139,38 +171,51 @@ pub(crate) async fn post(
     State(site_config): State<SiteConfig>,
     State(templates): State<Templates>,
     State(url_builder): State<UrlBuilder>,
-    State(limiter): State<Limiter>,
     State(homeserver): State<Arc<dyn HomeserverConnection>>,
+    (State(directory), mut policy): (State<Option<Arc<dyn Directory>>>, Policy),
     mut repo: BoxRepository,
     activity_tracker: BoundActivityTracker,
-    requester: RequesterFingerprint,
+    (State(limiter), requester): (State<Limiter>, RequesterFingerprint),
     (Query(query), Query(query_login_hint)): (Query<OptionalPostAuthAction>, Query<QueryLoginHint>),
     cookie_jar: CookieJar,
     user_agent: Option<TypedHeader<headers::UserAgent>>,
     Form(form): Form<ProtectedForm<LoginForm>>,
 ) -> Result<Response, InternalError> {
     let user_agent = user_agent.map(|ua| ua.as_str().to_owned());
-    if !site_config.password_login_enabled {
+    let providers = repo.upstream_oauth_provider().all_enabled().await?;
+    let home_realm_discovery = providers
+        .iter()
+        .any(|provider| !provider.email_domains.is_empty());
+
+    if !site_config.password_login_enabled && !home_realm_discovery {
         // XXX: is it necessary to have better errors here?
         return Ok(StatusCode::METHOD_NOT_ALLOWED.into_response());
     }
 
     let form = cookie_jar.verify_form(&clock, form)?;
 
-    // Validate the form
-    let mut form_state = form.to_form_state();
+    // If the user entered an email address handled by one of the upstream
+    // providers, send them to it instead of checking a password
+    if let Ok(email) = lettre::Address::from_str(form.username.trim())
+        && let Some(provider) = provider_for_email(&providers, &email)
+    {
+        let mut destination =
+            UpstreamOAuth2Authorize::new(provider.id).with_login_hint(email.to_string());
 
-    if form.username.is_empty() {
-        form_state.add_error_on_field(LoginFormField::Username, FieldError::Required);
-    }
+        if let Some(action) = query.post_auth_action {
+            destination = destination.and_then(action);
+        }
 
-    if form.password.is_empty() {
-        form_state.add_error_on_field(LoginFormField::Password, FieldError::Required);
+        return Ok((cookie_jar, url_builder.redirect(&destination)).into_response());
     }
 
-    if !form_state.is_valid() {
-        tracing::warn!("Invalid login form: {form_state:?}");
-        PASSWORD_LOGIN_COUNTER.add(1, &[KeyValue::new(RESULT, "error")]);
+    // Validate the form
+    let mut form_state = form.to_form_state();
+
+    if !site_config.password_login_enabled {
+        // The identifier didn't match any provider, and there is no password login
+        // to fall back to
+        form_state.add_error_on_field(LoginFormField::Username, FieldError::Invalid);
         return render(
             locale,
             cookie_jar,
@@ -187,37 +232,16 @@ pub(crate) async fn post(
         .await;
     }
 
-    // Extract the localpart of the MXID, fallback to the bare username
-    let username = homeserver
-        .localpart(&form.username)
-        .unwrap_or(&form.username);
+    if form.username.is_empty() {
+        form_state.add_error_on_field(LoginFormField::Username, FieldError::Required);
+    }
 
-    // First, lookup the user
-    let Some(user) = get_user_by_email_or_by_username(&site_config, &mut repo, username).await?
-    else {
-        tracing::warn!(username, "User not found");
-        let form_state = form_state.with_error_on_form(FormError::InvalidCredentials);
-        PASSWORD_LOGIN_COUNTER.add(1, &[KeyValue::new(RESULT, "error")]);
-        return render(
-            locale,
-            cookie_jar,
-            form_state,
-            query,
-            &mut repo,
-            &clock,
-            &mut rng,
-            &templates,
-            &homeserver,
-            &site_config,
-            query_login_hint,
-        )
-        .await;
-    };
+    if form.password.is_empty() {
+        form_state.add_error_on_field(LoginFormField::Password, FieldError::Required);
+    }
 
-    // Check the rate limit
-    if let Err(e) = limiter.check_password(requester, &user) {
-        tracing::warn!(error = &e as &dyn std::error::Error, "ratelimit exceeded");
-        let form_state = form_state.with_error_on_form(FormError::RateLimitExceeded);
+    if !form_state.is_valid() {
+        tracing::warn!("Invalid login form: {form_state:?}");
         PASSWORD_LOGIN_COUNTER.add(1, &[KeyValue::new(RESULT, "error")]);
         return render(
             locale,
@@ -235,59 +259,127 @@ pub(crate) async fn post(
         .await;
     }
 
-    // And its password
-    let Some(user_password) = repo.user_password().active(&user).await? else {
-        // There is no password for this user, but we don't want to disclose that. Show
-        // a generic 'invalid credentials' error instead
-        tracing::warn!(username, "No password for user");
-        let form_state = form_state.with_error_on_form(FormError::InvalidCredentials);
-        PASSWORD_LOGIN_COUNTER.add(1, &[KeyValue::new(RESULT, "error")]);
-        return render(
-            locale,
-            cookie_jar,
-            form_state,
-            query,
-            &mut repo,
-            &clock,
+    // Extract the localpart of the MXID, fallback to the bare username
+    let username = homeserver
+        .localpart(&form.username)
+        .unwrap_or(&form.username);
+
+    let (user, authentication) = if let Some(directory) = directory.as_deref() {
+        let result = crate::ldap::authenticate(
             &mut rng,
-            &templates,
-            &homeserver,
-            &site_config,
-            query_login_hint,
+            &clock,
+            &mut repo,
+            directory,
+            site_config.ldap_adopt_existing_users,
+            &*homeserver,
+            &mut policy,
+            &limiter,
+            requester,
+            mas_policy::Requester {
+                ip_address: activity_tracker.ip(),
+                user_agent: user_agent.clone(),
+            },
+            username,
+            &form.password,
         )
         .await;
-    };
 
-    let password = Zeroizing::new(form.password);
-
-    // Verify the password, and upgrade it on-the-fly if needed
-    let user_password = match password_manager
-        .verify_and_upgrade(
-            &mut rng,
-            user_password.version,
-            password,
-            user_password.hashed_password.clone(),
-        )
-        .await
-    {
-        Ok(PasswordVerificationResult::Success(Some((version, new_password_hash)))) => {
-            // Save the upgraded password
-            repo.user_password()
-                .add(
+        match result {
+            Ok(Some((user, entry))) => (user, LoginAuthentication::Ldap { dn: entry.dn }),
+            Ok(None) => {
+                tracing::warn!(username, "Failed to verify password against the directory");
+                let form_state = form_state.with_error_on_form(FormError::InvalidCredentials);
+                PASSWORD_LOGIN_COUNTER.add(1, &[KeyValue::new(RESULT, "mismatch")]);
+                return render(
+                    locale,
+                    cookie_jar,
+                    form_state,
+                    query,
+                    &mut repo,
+                    &clock,
                     &mut rng,
+                    &templates,
+                    &homeserver,
+                    &site_config,
+                    query_login_hint,
+                )
+                .await;
+            }
+            Err(DirectoryLoginError::RateLimited(e)) => {
+                tracing::warn!(error = &e as &dyn std::error::Error, "ratelimit exceeded");
+                let form_state = form_state.with_error_on_form(FormError::RateLimitExceeded);
+                PASSWORD_LOGIN_COUNTER.add(1, &[KeyValue::new(RESULT, "error")]);
+                return render(
+                    locale,
+                    cookie_jar,
+                    form_state,
+                    query,
+                    &mut repo,
                     &clock,
-                    &user,
-                    version,
-                    new_password_hash,
-                    Some(&user_password),
+                    &mut rng,
+                    &templates,
+                    &homeserver,
+                    &site_config,
+                    query_login_hint,
                 )
-                .await?
+                .await;
+            }
+            Err(DirectoryLoginError::ProvisioningDenied(message)) => {
+                tracing::warn!(username, message, "Directory user can't be provisioned");
+                let form_state = form_state.with_error_on_form(FormError::Policy {
+                    code: None,
+                    message,
+                });
+                PASSWORD_LOGIN_COUNTER.add(1, &[KeyValue::new(RESULT, "error")]);
+                return render(
+                    locale,
+                    cookie_jar,
+                    form_state,
+                    query,
+                    &mut repo,
+                    &clock,
+                    &mut rng,
+                    &templates,
+                    &homeserver,
+                    &site_config,
+                    query_login_hint,
+                )
+                .await;
+            }
+            Err(e) => {
+                PASSWORD_LOGIN_COUNTER.add(1, &[KeyValue::new(RESULT, "error")]);
+                return Err(InternalError::from(e));
+            }
         }
-        Ok(PasswordVerificationResult::Success(None)) => user_password,
-        Ok(PasswordVerificationResult::Failure) => {
-            tracing::warn!(username, "Failed to verify/upgrade password for user");
+    } else {
+        // First, lookup the user
+        let Some(user) =
+            get_user_by_email_or_by_username(&site_config, &mut repo, username).await?
+        else {
+            tracing::warn!(username, "User not found");
             let form_state = form_state.with_error_on_form(FormError::InvalidCredentials);
-            PASSWORD_LOGIN_COUNTER.add(1, &[KeyValue::new(RESULT, "mismatch")]);
+            PASSWORD_LOGIN_COUNTER.add(1, &[KeyValue::new(RESULT, "error")]);
+            return render(
+                locale,
+                cookie_jar,
+                form_state,
+                query,
+                &mut repo,
+                &clock,
+                &mut rng,
+                &templates,
+                &homeserver,
+                &site_config,
+                query_login_hint,
+            )
+            .await;
+        };
+
+        // Check the rate limit
+        if let Err(e) = limiter.check_password(requester, &user) {
+            tracing::warn!(error = &e as &dyn std::error::Error, "ratelimit exceeded");
+            let form_state = form_state.with_error_on_form(FormError::RateLimitExceeded);
+            PASSWORD_LOGIN_COUNTER.add(1, &[KeyValue::new(RESULT, "error")]);
             return render(
                 locale,
                 cookie_jar,
@@ -303,7 +395,79 @@ pub(crate) async fn post(
             )
             .await;
         }
-        Err(err) => return Err(InternalError::from_anyhow(err)),
+
+        // And its password
+        let Some(user_password) = repo.user_password().active(&user).await? else {
+            // There is no password for this user, but we don't want to disclose that. Show
+            // a generic 'invalid credentials' error instead
+            tracing::warn!(username, "No password for user");
+            let form_state = form_state.with_error_on_form(FormError::InvalidCredentials);
+            PASSWORD_LOGIN_COUNTER.add(1, &[KeyValue::new(RESULT, "error")]);
+            return render(
+                locale,
+                cookie_jar,
+                form_state,
+                query,
+                &mut repo,
+                &clock,
+                &mut rng,
+                &templates,
+                &homeserver,
+                &site_config,
+                query_login_hint,
+            )
+            .await;
+        };
+
+        let password = Zeroizing::new(form.password);
+
+        // Verify the password, and upgrade it on-the-fly if needed
+        let user_password = match password_manager
+            .verify_and_upgrade(
+                &mut rng,
+                user_password.version,
+                password,
+                user_password.hashed_password.clone(),
+            )
+            .await
+        {
+            Ok(PasswordVerificationResult::Success(Some((version, new_password_hash)))) => {
+                // Save the upgraded password
+                repo.user_password()
+                    .add(
+                        &mut rng,
+                        &clock,
+                        &user,
+                        version,
+                        new_password_hash,
+                        Some(&user_password),
+                    )
+                    .await?
+            }
+            Ok(PasswordVerificationResult::Success(None)) => user_password,
+            Ok(PasswordVerificationResult::Failure) => {
+                tracing::warn!(username, "Failed to verify/upgrade password for user");
+                let form_state = form_state.with_error_on_form(FormError::InvalidCredentials);
+                PASSWORD_LOGIN_COUNTER.add(1, &[KeyValue::new(RESULT, "mismatch")]);
+                return render(
+                    locale,
+                    cookie_jar,
+                    form_state,
+                    query,
+                    &mut repo,
+                    &clock,
+                    &mut rng,
+                    &templates,
+                    &homeserver,
+                    &site_config,
+                    query_login_hint,
+                )
+                .await;
+            }
+            Err(err) => return Err(InternalError::from_anyhow(err)),
+        };
+
+        (user, LoginAuthentication::Password(user_password))
     };
 
     // Now that we have checked the user password, we now want to show an error if
@@ -341,9 +505,18 @@ pub(crate) async fn post(
         .await?;
 
     // And mark it as authenticated by the password
-    repo.browser_session()
-        .authenticate_with_password(&mut rng, &clock, &user_session, &user_password)
-        .await?;
+    match authentication {
+        LoginAuthentication::Password(user_password) => {
+            repo.browser_session()
+                .authenticate_with_password(&mut rng, &clock, &user_session, &user_password)
+                .await?;
+        }
+        LoginAuthentication::Ldap { dn } => {
+            repo.browser_session()
+                .authenticate_with_ldap(&mut rng, &clock, &user_session, &dn)
+                .await?;
+        }
+    }
 
     repo.save().await?;
 
@@ -380,6 +553,20 @@ async fn get_user_by_email_or_by_username<R: RepositoryAccess>(
     Ok(user)
 }
 
+/// Find the upstream provider which handles the domain of the given email
+/// address, if any
+///
+/// Only email addresses are routed: other kinds of identifiers and login hints,
+/// like Matrix IDs, always go through the regular login page.
+fn provider_for_email<'a>(
+    providers: &'a [UpstreamOAuthProvider],
+    email: &lettre::Address,
+) -> Option<&'a UpstreamOAuthProvider> {
+    providers
+        .iter()
+        .find(|provider| provider.handles_email_domain(email.domain()))
+}
+
 fn handle_login_hint(
     mut ctx: LoginContext,
     query_login_hint: &QueryLoginHint,
@@ -419,9 +606,20 @@ async fn render(
     let (csrf_token, cookie_jar) = cookie_jar.csrf_token(clock, rng);
     let providers = repo.upstream_oauth_provider().all_enabled().await?;
 
+    // Providers which handle email domains are reached by entering an email
+    // address, so we don't show a button for them
+    let home_realm_discovery = providers
+        .iter()
+        .any(|provider| !provider.email_domains.is_empty());
+    let providers = providers
+        .into_iter()
+        .filter(|provider| provider.email_domains.is_empty())
+        .collect();
+
     let ctx = LoginContext::default()
         .with_form_state(form_state)
-        .with_upstream_providers(providers);
+        .with_upstream_providers(providers)
+        .with_home_realm_discovery(home_realm_discovery);
 
     let ctx = handle_login_hint(ctx, &query_login_hint, homeserver, site_config);
 
@@ -442,19 +640,24 @@ async fn render(
 
 #[cfg(test)]
 mod test {
+    use std::sync::Arc;
+
     use hyper::{
         Request, StatusCode,
         header::{CONTENT_TYPE, LOCATION},
     };
     use mas_data_model::{
-        UpstreamOAuthProviderClaimsImports, UpstreamOAuthProviderOnBackchannelLogout,
-        UpstreamOAuthProviderTokenAuthMethod,
+        AuthenticationMethod, UpstreamOAuthProviderClaimsImports,
+        UpstreamOAuthProviderOnBackchannelLogout, UpstreamOAuthProviderTokenAuthMethod,
     };
     use mas_iana::jose::JsonWebSignatureAlg;
+    use mas_ldap::{DirectoryUser, MockDirectory};
+    use mas_matrix::HomeserverConnection;
     use mas_router::Route;
     use mas_storage::{
-        RepositoryAccess,
+        Pagination, RepositoryAccess,
         upstream_oauth2::{UpstreamOAuthProviderParams, UpstreamOAuthProviderRepository},
+        user::BrowserSessionFilter,
     };
     use mas_templates::escape_html;
     use oauth2_types::scope::OPENID;
@@ -525,6 +728,11 @@ mod test {
                     forward_login_hint: false,
                     ui_order: 0,
                     on_backchannel_logout: UpstreamOAuthProviderOnBackchannelLogout::DoNothing,
+                    saml: None,
+                    email_domains: Vec::new(),
+                    store_tokens: false,
+                    allow_initiated_login: false,
+                    encrypted_client_keys: None,
                 },
             )
             .await
@@ -568,6 +776,11 @@ mod test {
                     forward_login_hint: false,
                     ui_order: 1,
                     on_backchannel_logout: UpstreamOAuthProviderOnBackchannelLogout::DoNothing,
+                    saml: None,
+                    email_domains: Vec::new(),
+                    store_tokens: false,
+                    allow_initiated_login: false,
+                    encrypted_client_keys: None,
                 },
             )
             .await
@@ -593,6 +806,112 @@ mod test {
         );
     }
 
+    #[sqlx::test(migrator = "mas_storage_pg::MIGRATOR")]
+    async fn test_home_realm_discovery(pool: PgPool) {
+        setup();
+        let state = TestState::from_pool(pool).await.unwrap();
+        let mut rng = state.rng();
+        let cookies = CookieHelper::new();
+
+        let mut repo = state.repository().await.unwrap();
+        let provider = repo
+            .upstream_oauth_provider()
+            .add(
+                &mut rng,
+                &state.clock,
+                UpstreamOAuthProviderParams {
+                    issuer: Some("https://corp.example.com/".to_owned()),
+                    human_name: Some("Corp".to_owned()),
+                    brand_name: None,
+                    scope: [OPENID].into_iter().collect(),
+                    token_endpoint_auth_method: UpstreamOAuthProviderTokenAuthMethod::None,
+                    token_endpoint_signing_alg: None,
+                    id_token_signed_response_alg: JsonWebSignatureAlg::Rs256,
+                    fetch_userinfo: false,
+                    userinfo_signed_response_alg: None,
+                    client_id: "client".to_owned(),
+                    encrypted_client_secret: None,
+                    claims_imports: UpstreamOAuthProviderClaimsImports::default(),
+                    authorization_endpoint_override: None,
+                    token_endpoint_override: None,
+                    userinfo_endpoint_override: None,
+                    jwks_uri_override: None,
+                    discovery_mode: mas_data_model::UpstreamOAuthProviderDiscoveryMode::Oidc,
+                    pkce_mode: mas_data_model::UpstreamOAuthProviderPkceMode::Auto,
+                    response_mode: None,
+                    additional_authorization_parameters: Vec::new(),
+                    forward_login_hint: false,
+                    ui_order: 0,
+                    on_backchannel_logout: UpstreamOAuthProviderOnBackchannelLogout::DoNothing,
+                    saml: None,
+                    email_domains: vec!["corp.example.com".to_owned(), "*.corp.test".to_owned()],
+                    store_tokens: false,
+                    allow_initiated_login: false,
+                    encrypted_client_keys: None,
+                },
+            )
+            .await
+            .unwrap();
+        repo.save().await.unwrap();
+
+        let provider_login = mas_router::UpstreamOAuth2Authorize::new(provider.id);
+
+        // The provider handles an email domain, so it shouldn't get a button
+        let request = cookies.with_cookies(Request::get("/login").empty());
+        let response = state.request(request).await;
+        cookies.save_cookies(&response);
+        response.assert_status(StatusCode::OK);
+        assert!(
+            !response
+                .body()
+                .contains(&escape_html(&provider_login.path_and_query()))
+        );
+        let csrf_token = response
+            .body()
+            .split("name=\"csrf\" value=\"")
+            .nth(1)
+            .unwrap()
+            .split('\"')
+            .next()
+            .unwrap();
+
+        // A login hint in one of the domains redirects to the provider, passing the
+        // login hint along
+        let response = state
+            .request(Request::get("/login?login_hint=alice@CORP.example.com").empty())
+            .await;
+        response.assert_status(StatusCode::SEE_OTHER);
+        response.assert_header_value(
+            LOCATION,
+            &mas_router::UpstreamOAuth2Authorize::new(provider.id)
+                .with_login_hint("alice@CORP.example.com".to_owned())
+                .path_and_query(),
+        );
+
+        // So does entering an email address in a subdomain, without a password
+        let request = Request::post("/login").form(serde_json::json!({
+            "csrf": csrf_token,
+            "username": "bob@eu.corp.test",
+        }));
+        let response = state.request(cookies.with_cookies(request)).await;
+        response.assert_status(StatusCode::SEE_OTHER);
+        response.assert_header_value(
+            LOCATION,
+            &mas_router::UpstreamOAuth2Authorize::new(provider.id)
+                .with_login_hint("bob@eu.corp.test".to_owned())
+                .path_and_query(),
+        );
+
+        // Other identifiers fall back to the password login
+        let request = Request::post("/login").form(serde_json::json!({
+            "csrf": csrf_token,
+            "username": "carol@corp.test",
+        }));
+        let response = state.request(cookies.with_cookies(request)).await;
+        response.assert_status(StatusCode::OK);
+        assert!(response.body().contains("carol@corp.test"));
+    }
+
     async fn user_with_password(
         state: &TestState,
         username: &str,
@@ -938,4 +1257,119 @@ mod test {
         assert!(!response.body().contains("Account deleted"));
         assert!(response.body().contains("Invalid credentials"));
     }
+
+    #[sqlx::test(migrator = "mas_storage_pg::MIGRATOR")]
+    async fn test_ldap_login(pool: PgPool) {
+        setup();
+        let mut state = TestState::from_pool(pool).await.unwrap();
+        let cookies = CookieHelper::new();
+
+        let directory = MockDirectory::new();
+        directory
+            .add_user(
+                "alice",
+                "hunter2",
+                DirectoryUser {
+                    dn: "uid=alice,ou=people,dc=example,dc=com".to_owned(),
+                    localpart: "alice".to_owned(),
+                    display_name: Some("Alice Liddell".to_owned()),
+                    email: Some("alice@example.com".to_owned()),
+                },
+            )
+            .await;
+        state.directory = Some(Arc::new(directory));
+
+        // Render the login page to get a CSRF token
+        let request = Request::get("/login").empty();
+        let request = cookies.with_cookies(request);
+        let response = state.request(request).await;
+        cookies.save_cookies(&response);
+        response.assert_status(StatusCode::OK);
+        let csrf_token = response
+            .body()
+            .split("name=\"csrf\" value=\"")
+            .nth(1)
+            .unwrap()
+            .split('\"')
+            .next()
+            .unwrap();
+
+        // A wrong password doesn't provision the user
+        let request = Request::post("/login").form(serde_json::json!({
+            "csrf": csrf_token,
+            "username": "alice",
+            "password": "badpassword",
+        }));
+        let request = cookies.with_cookies(request);
+        let response = state.request(request).await;
+        cookies.save_cookies(&response);
+        response.assert_status(StatusCode::OK);
+        assert!(response.body().contains("Invalid credentials"));
+
+        let mut repo = state.repository().await.unwrap();
+        assert!(
+            repo.user()
+                .find_by_username("alice")
+                .await
+                .unwrap()
+                .is_none()
+        );
+        repo.save().await.unwrap();
+
+        // The right password provisions the user on their first login
+        let request = Request::post("/login").form(serde_json::json!({
+            "csrf": csrf_token,
+            "username": "alice",
+            "password": "hunter2",
+        }));
+        let request = cookies.with_cookies(request);
+        let response = state.request(request).await;
+        cookies.save_cookies(&response);
+        response.assert_status(StatusCode::SEE_OTHER);
+
+        state.run_jobs_in_queue().await;
+
+        let mut repo = state.repository().await.unwrap();
+        let user = repo
+            .user()
+            .find_by_username("alice")
+            .await
+            .unwrap()
+            .expect("user should have been provisioned");
+
+        let emails = repo.user_email().all(&user).await.unwrap();
+        assert_eq!(emails.len(), 1);
+        assert_eq!(emails[0].email, "alice@example.com");
+
+        // The session records that the password was checked by the directory
+        let sessions = repo
+            .browser_session()
+            .list(
+                BrowserSessionFilter::new().for_user(&user),
+                Pagination::first(10),
+            )
+            .await
+            .unwrap();
+        assert_eq!(sessions.edges.len(), 1);
+        let authentication = repo
+            .browser_session()
+            .get_last_authentication(&sessions.edges[0].node)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            authentication.authentication_method,
+            AuthenticationMethod::Ldap {
+                dn: "uid=alice,ou=people,dc=example,dc=com".to_owned()
+            }
+        );
+        repo.save().await.unwrap();
+
+        let matrix_user = state
+            .homeserver_connection
+            .query_user("alice")
+            .await
+            .unwrap();
+        assert_eq!(matrix_user.displayname.as_deref(), Some("Alice Liddell"));
+    }
 }
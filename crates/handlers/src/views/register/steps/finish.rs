@@ -27,7 +27,9 @@ use ulid::Ulid;
 
 use super::super::cookie::UserRegistrationSessions;
 use crate::{
-    BoundActivityTracker, METER, PreferredLanguage, views::shared::OptionalPostAuthAction,
+    BoundActivityTracker, METER, PreferredLanguage,
+    upstream_oauth2::sync::{apply_authorization_imports, context_from_session},
+    views::shared::OptionalPostAuthAction,
 };
 
 static PASSWORD_REGISTER_COUNTER: LazyLock<Counter<u64>> = LazyLock::new(|| {
@@ -282,7 +284,7 @@ pub(crate) async fn get(
         .save(cookie_jar, &clock);
 
     // Now we can start the user creation
-    let user = repo
+    let mut user = repo
         .user()
         .add(&mut rng, &clock, registration.username)
         .await?;
@@ -328,6 +330,17 @@ pub(crate) async fn get(
             .associate_to_user(&upstream_link, &user)
             .await?;
 
+        // Apply the admin flag and attributes imports now that we have a user
+        let provider = repo
+            .upstream_oauth_provider()
+            .lookup(upstream_link.provider_id)
+            .await?
+            .context("Could not load the upstream OAuth provider")
+            .map_err(InternalError::from_anyhow)?;
+        let context = context_from_session(&upstream_session)?;
+        user = apply_authorization_imports(&mut repo, &provider, &upstream_link, user, &context)
+            .await?;
+
         repo.browser_session()
             .authenticate_with_upstream(&mut rng, &clock, &user_session, &upstream_session)
             .await?;
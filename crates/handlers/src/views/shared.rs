@@ -11,7 +11,7 @@ use mas_router::{PostAuthAction, Route, UrlBuilder};
 use mas_storage::{
     RepositoryAccess,
     compat::CompatSsoLoginRepository,
-    oauth2::OAuth2AuthorizationGrantRepository,
+    oauth2::{OAuth2AuthorizationGrantRepository, OAuth2ClientRepository},
     upstream_oauth2::{UpstreamOAuthLinkRepository, UpstreamOAuthProviderRepository},
 };
 use mas_templates::{PostAuthContext, PostAuthContextInner};
@@ -102,6 +102,15 @@ impl OptionalPostAuthAction {
             }
 
             PostAuthAction::ManageAccount { .. } => PostAuthContextInner::ManageAccount,
+
+            PostAuthAction::LaunchClient { id, .. } => {
+                let Some(client) = repo.oauth2_client().lookup(id).await? else {
+                    warn!(%id, "Failed to load OAuth 2.0 client, it was likely deleted or is an invalid ID");
+                    return Ok(None);
+                };
+                let client = Box::new(client);
+                PostAuthContextInner::LaunchClient { client }
+            }
         };
 
         Ok(Some(PostAuthContext {
@@ -16,7 +16,10 @@ mod reqwest;
 
 pub use self::{
     ext::{CorsLayerExt, set_propagator},
-    reqwest::{RequestBuilderExt, client as reqwest_client},
+    reqwest::{
+        ClientCertificate, ClientCertificateError, RequestBuilderExt, client as reqwest_client,
+        client_with_certificate as reqwest_client_with_certificate,
+    },
 };
 
 static METER: LazyLock<opentelemetry::metrics::Meter> = LazyLock::new(|| {
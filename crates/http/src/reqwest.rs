@@ -29,7 +29,9 @@ use opentelemetry_semantic_conventions::{
         NETWORK_TYPE, SERVER_ADDRESS, SERVER_PORT, URL_FULL, URL_SCHEME, USER_AGENT_ORIGINAL,
     },
 };
-use rustls_platform_verifier::ConfigVerifierExt;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, pem::PemObject as _};
+use rustls_platform_verifier::{BuilderVerifierExt as _, ConfigVerifierExt as _};
+use thiserror::Error;
 use tokio::time::Instant;
 use tower::{BoxError, Service as _};
 use tracing::Instrument;
@@ -83,6 +85,67 @@ impl reqwest::dns::Resolve for TracingResolver {
     }
 }
 
+/// A TLS client certificate, used to authenticate against servers requiring
+/// mutual TLS
+#[derive(Debug)]
+pub struct ClientCertificate {
+    chain: Vec<CertificateDer<'static>>,
+    key: PrivateKeyDer<'static>,
+}
+
+/// An error which can occur when loading a [`ClientCertificate`]
+#[derive(Debug, Error)]
+pub enum ClientCertificateError {
+    /// The certificate chain could not be parsed
+    #[error("Invalid certificate chain")]
+    InvalidCertificate(#[source] rustls::pki_types::pem::Error),
+
+    /// The PEM document did not contain any certificate
+    #[error("No certificate found")]
+    MissingCertificate,
+
+    /// The private key could not be parsed
+    #[error("Invalid private key")]
+    InvalidPrivateKey(#[source] rustls::pki_types::pem::Error),
+}
+
+impl ClientCertificate {
+    /// Load a client certificate from PEM encoded documents
+    ///
+    /// The certificate document can contain a full chain, starting with the
+    /// leaf certificate. The private key can be in PKCS#1, PKCS#8 or SEC1
+    /// format.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if either the certificate chain or the private key
+    /// could not be parsed
+    pub fn from_pem(certificate: &str, private_key: &str) -> Result<Self, ClientCertificateError> {
+        let chain = CertificateDer::pem_slice_iter(certificate.as_bytes())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(ClientCertificateError::InvalidCertificate)?;
+
+        if chain.is_empty() {
+            return Err(ClientCertificateError::MissingCertificate);
+        }
+
+        let key = PrivateKeyDer::from_pem_slice(private_key.as_bytes())
+            .map_err(ClientCertificateError::InvalidPrivateKey)?;
+
+        Ok(Self { chain, key })
+    }
+}
+
+fn builder(tls_config: rustls::ClientConfig) -> reqwest::ClientBuilder {
+    // TODO: can/should we limit in-flight requests?
+    reqwest::Client::builder()
+        .dns_resolver(Arc::new(TracingResolver::new()))
+        .use_preconfigured_tls(tls_config)
+        .user_agent(USER_AGENT)
+        .timeout(Duration::from_mins(1))
+        .connect_timeout(Duration::from_secs(30))
+}
+
 /// Create a new [`reqwest::Client`] with sane parameters
 ///
 /// # Panics
@@ -90,24 +153,45 @@ impl reqwest::dns::Resolve for TracingResolver {
 /// Panics if the client fails to build, which should never happen
 #[must_use]
 pub fn client() -> reqwest::Client {
-    // TODO: can/should we limit in-flight requests?
-
     // The explicit typing here is because `use_preconfigured_tls` accepts
     // `Any`, but wants a `ClientConfig` under the hood. This helps us detect
     // breaking changes in the rustls-platform-verifier API.
     let tls_config: rustls::ClientConfig =
         rustls::ClientConfig::with_platform_verifier().expect("failed to create TLS config");
 
-    reqwest::Client::builder()
-        .dns_resolver(Arc::new(TracingResolver::new()))
-        .use_preconfigured_tls(tls_config)
-        .user_agent(USER_AGENT)
-        .timeout(Duration::from_secs(60))
-        .connect_timeout(Duration::from_secs(30))
+    builder(tls_config)
         .build()
         .expect("failed to create HTTP client")
 }
 
+/// Create a new [`reqwest::Client`] which presents the given certificate when
+/// the server asks for one, as used by the mutual TLS client authentication
+/// methods of [RFC 8705]
+///
+/// The client is otherwise configured like the one returned by [`client`].
+///
+/// # Errors
+///
+/// Returns an error if the TLS configuration could not be built, for example
+/// if the private key doesn't match a supported algorithm
+///
+/// # Panics
+///
+/// Panics if the client fails to build, which should never happen
+///
+/// [RFC 8705]: https://www.rfc-editor.org/rfc/rfc8705.html
+pub fn client_with_certificate(
+    certificate: &ClientCertificate,
+) -> Result<reqwest::Client, rustls::Error> {
+    let tls_config: rustls::ClientConfig = rustls::ClientConfig::builder()
+        .with_platform_verifier()?
+        .with_client_auth_cert(certificate.chain.clone(), certificate.key.clone_key())?;
+
+    Ok(builder(tls_config)
+        .build()
+        .expect("failed to create HTTP client"))
+}
+
 async fn send_traced(
     request: reqwest::RequestBuilder,
 ) -> Result<reqwest::Response, reqwest::Error> {
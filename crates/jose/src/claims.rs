@@ -549,7 +549,16 @@ mod oidc_frontchannel {
     pub const SID: Claim<String> = Claim::new("sid");
 }
 
-pub use self::{oidc_core::*, oidc_frontchannel::*, rfc7519::*};
+/// Claims defined in RFC8693 sec. 4, as used in JWT access tokens (RFC9068)
+/// <https://www.rfc-editor.org/rfc/rfc8693.html#section-4>
+mod rfc8693 {
+    use super::Claim;
+
+    pub const SCOPE: Claim<String> = Claim::new("scope");
+    pub const CLIENT_ID: Claim<String> = Claim::new("client_id");
+}
+
+pub use self::{oidc_core::*, oidc_frontchannel::*, rfc7519::*, rfc8693::*};
 
 #[cfg(test)]
 mod tests {
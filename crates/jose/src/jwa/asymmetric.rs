@@ -4,8 +4,10 @@
 // SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
 // Please see LICENSE files in the repository root for full details.
 
+use std::sync::Arc;
+
 use digest::Digest;
-use mas_iana::jose::{JsonWebKeyEcEllipticCurve, JsonWebSignatureAlg};
+use mas_iana::jose::{JsonWebKeyEcEllipticCurve, JsonWebKeyOkpEllipticCurve, JsonWebSignatureAlg};
 use sha2::{Sha256, Sha384, Sha512};
 use signature::rand_core::CryptoRngCore;
 use thiserror::Error;
@@ -27,6 +29,12 @@ pub enum AsymmetricKeyFromJwkError {
         inner: elliptic_curve::Error,
     },
 
+    #[error("Invalid Octet Key Pair parameters")]
+    OctetKeyPair {
+        #[from]
+        inner: signature::Error,
+    },
+
     #[error("Unsupported algorithm {alg}")]
     UnsupportedAlgorithm { alg: JsonWebSignatureAlg },
 
@@ -34,6 +42,18 @@ pub enum AsymmetricKeyFromJwkError {
     KeyNotSuitable { alg: JsonWebSignatureAlg },
 }
 
+/// A signing key whose private part is held outside of this process, for
+/// example in a hardware security module
+pub trait ExternalSigningKey: Send + Sync {
+    /// Sign the given message, returning the signature encoded as it should
+    /// appear in a JWS
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the signing operation failed
+    fn try_sign(&self, msg: &[u8]) -> Result<Vec<u8>, signature::Error>;
+}
+
 /// An enum of all supported asymmetric signature algorithms verifying keys
 #[non_exhaustive]
 pub enum AsymmetricSigningKey {
@@ -46,6 +66,8 @@ pub enum AsymmetricSigningKey {
     Es256(super::Es256SigningKey),
     Es384(super::Es384SigningKey),
     Es256K(super::Es256KSigningKey),
+    EdDsa(super::EdDsaSigningKey),
+    External(Arc<dyn ExternalSigningKey>),
 }
 
 impl AsymmetricSigningKey {
@@ -121,6 +143,21 @@ impl AsymmetricSigningKey {
         Self::Es256K(ecdsa::SigningKey::from(key))
     }
 
+    /// Create a new signing key with the `EdDSA` algorithm from the given
+    /// Ed25519 private key.
+    #[must_use]
+    pub fn eddsa(key: ed25519_dalek::SigningKey) -> Self {
+        Self::EdDsa(key)
+    }
+
+    /// Create a new signing key which delegates the signing operation to an
+    /// external key. The caller is responsible for making sure the key is
+    /// suitable for the algorithm advertised in the JWS header.
+    #[must_use]
+    pub fn external(key: Arc<dyn ExternalSigningKey>) -> Self {
+        Self::External(key)
+    }
+
     /// Create a new signing key for the given algorithm from the given private
     /// JWK parameters.
     ///
@@ -167,7 +204,15 @@ impl AsymmetricSigningKey {
                 Ok(Self::es256k(params.try_into()?))
             }
 
-            (JsonWebKeyPrivateParameters::Okp(_params), _) => {
+            (JsonWebKeyPrivateParameters::Okp(params), JsonWebSignatureAlg::EdDsa)
+                if params.crv == JsonWebKeyOkpEllipticCurve::Ed25519 =>
+            {
+                Ok(Self::eddsa(params.try_into()?))
+            }
+
+            (JsonWebKeyPrivateParameters::Okp(params), JsonWebSignatureAlg::EdDsa)
+                if params.crv == JsonWebKeyOkpEllipticCurve::Ed448 =>
+            {
                 Err(AsymmetricKeyFromJwkError::UnsupportedAlgorithm { alg: alg.clone() })
             }
 
@@ -230,6 +275,12 @@ impl From<super::Es256KSigningKey> for AsymmetricSigningKey {
     }
 }
 
+impl From<super::EdDsaSigningKey> for AsymmetricSigningKey {
+    fn from(key: super::EdDsaSigningKey) -> Self {
+        Self::EdDsa(key)
+    }
+}
+
 impl signature::RandomizedSigner<Signature> for AsymmetricSigningKey {
     fn try_sign_with_rng(
         &self,
@@ -273,6 +324,15 @@ impl signature::RandomizedSigner<Signature> for AsymmetricSigningKey {
                 let signature: ecdsa::Signature<_> = key.try_sign_with_rng(rng, msg)?;
                 Ok(Signature::from_signature(&signature))
             }
+            Self::EdDsa(key) => {
+                // EdDSA signatures are deterministic, the RNG is not used
+                let signature: ed25519_dalek::Signature = signature::Signer::try_sign(key, msg)?;
+                Ok(Signature::from_signature(&signature))
+            }
+            Self::External(key) => {
+                // External keys use their own source of randomness, if any
+                Ok(Signature::new(key.try_sign(msg)?))
+            }
         }
     }
 }
@@ -289,6 +349,7 @@ pub enum AsymmetricVerifyingKey {
     Es256(super::Es256VerifyingKey),
     Es384(super::Es384VerifyingKey),
     Es256K(super::Es256KVerifyingKey),
+    EdDsa(super::EdDsaVerifyingKey),
 }
 
 impl AsymmetricVerifyingKey {
@@ -355,6 +416,13 @@ impl AsymmetricVerifyingKey {
         Self::Es256K(ecdsa::VerifyingKey::from(key))
     }
 
+    /// Create a new verifying key with the `EdDSA` algorithm from the given
+    /// Ed25519 public key.
+    #[must_use]
+    pub fn eddsa(key: ed25519_dalek::VerifyingKey) -> Self {
+        Self::EdDsa(key)
+    }
+
     /// Create a new verifying key for the given algorithm from the given public
     /// JWK parameters.
     ///
@@ -401,7 +469,15 @@ impl AsymmetricVerifyingKey {
                 Ok(Self::es256k(params.try_into()?))
             }
 
-            (JsonWebKeyPublicParameters::Okp(_params), _) => {
+            (JsonWebKeyPublicParameters::Okp(params), JsonWebSignatureAlg::EdDsa)
+                if params.crv == JsonWebKeyOkpEllipticCurve::Ed25519 =>
+            {
+                Ok(Self::eddsa(params.try_into()?))
+            }
+
+            (JsonWebKeyPublicParameters::Okp(params), JsonWebSignatureAlg::EdDsa)
+                if params.crv == JsonWebKeyOkpEllipticCurve::Ed448 =>
+            {
                 Err(AsymmetricKeyFromJwkError::UnsupportedAlgorithm { alg: alg.clone() })
             }
 
@@ -464,6 +540,12 @@ impl From<super::Es256KVerifyingKey> for AsymmetricVerifyingKey {
     }
 }
 
+impl From<super::EdDsaVerifyingKey> for AsymmetricVerifyingKey {
+    fn from(key: super::EdDsaVerifyingKey) -> Self {
+        Self::EdDsa(key)
+    }
+}
+
 impl signature::Verifier<Signature> for AsymmetricVerifyingKey {
     fn verify(&self, msg: &[u8], signature: &Signature) -> Result<(), ecdsa::Error> {
         match self {
@@ -503,6 +585,10 @@ impl signature::Verifier<Signature> for AsymmetricVerifyingKey {
                 let signature: ecdsa::Signature<_> = signature.to_signature()?;
                 key.verify(msg, &signature)
             }
+            Self::EdDsa(key) => {
+                let signature: ed25519_dalek::Signature = signature.to_signature()?;
+                key.verify(msg, &signature)
+            }
         }
     }
 }
@@ -13,7 +13,9 @@ mod signature;
 mod symmetric;
 
 pub use self::{
-    asymmetric::{AsymmetricKeyFromJwkError, AsymmetricSigningKey, AsymmetricVerifyingKey},
+    asymmetric::{
+        AsymmetricKeyFromJwkError, AsymmetricSigningKey, AsymmetricVerifyingKey, ExternalSigningKey,
+    },
     symmetric::{InvalidAlgorithm, SymmetricKey},
 };
 
@@ -42,8 +44,11 @@ pub type Es384VerifyingKey = ecdsa::VerifyingKey<p384::NistP384>;
 pub type Es256KSigningKey = ecdsa::SigningKey<k256::Secp256k1>;
 pub type Es256KVerifyingKey = ecdsa::VerifyingKey<k256::Secp256k1>;
 
+pub type EdDsaSigningKey = ed25519_dalek::SigningKey;
+pub type EdDsaVerifyingKey = ed25519_dalek::VerifyingKey;
+
 /// All the signing algorithms supported by this crate.
-pub const SUPPORTED_SIGNING_ALGORITHMS: [JsonWebSignatureAlg; 12] = [
+pub const SUPPORTED_SIGNING_ALGORITHMS: [JsonWebSignatureAlg; 13] = [
     JsonWebSignatureAlg::Hs256,
     JsonWebSignatureAlg::Hs384,
     JsonWebSignatureAlg::Hs512,
@@ -56,4 +61,5 @@ pub const SUPPORTED_SIGNING_ALGORITHMS: [JsonWebSignatureAlg; 12] = [
     JsonWebSignatureAlg::Es256,
     JsonWebSignatureAlg::Es384,
     JsonWebSignatureAlg::Es256K,
+    JsonWebSignatureAlg::EdDsa,
 ];
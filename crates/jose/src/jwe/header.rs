@@ -0,0 +1,128 @@
+// Copyright 2026 Element Creations Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+// Please see LICENSE files in the repository root for full details.
+
+use mas_iana::jose::{JsonWebEncryptionAlg, JsonWebEncryptionEnc};
+use serde::{Deserialize, Serialize};
+use serde_with::skip_serializing_none;
+
+use crate::{base64::Base64UrlNoPad, jwk::PublicJsonWebKey};
+
+#[skip_serializing_none]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct JsonWebEncryptionHeader {
+    alg: JsonWebEncryptionAlg,
+
+    enc: JsonWebEncryptionEnc,
+
+    #[serde(default)]
+    kid: Option<String>,
+
+    #[serde(default)]
+    epk: Option<Box<PublicJsonWebKey>>,
+
+    #[serde(default)]
+    apu: Option<Base64UrlNoPad>,
+
+    #[serde(default)]
+    apv: Option<Base64UrlNoPad>,
+
+    #[serde(default)]
+    typ: Option<String>,
+
+    #[serde(default)]
+    cty: Option<String>,
+}
+
+impl JsonWebEncryptionHeader {
+    #[must_use]
+    pub fn new(alg: JsonWebEncryptionAlg, enc: JsonWebEncryptionEnc) -> Self {
+        Self {
+            alg,
+            enc,
+            kid: None,
+            epk: None,
+            apu: None,
+            apv: None,
+            typ: None,
+            cty: None,
+        }
+    }
+
+    #[must_use]
+    pub const fn alg(&self) -> &JsonWebEncryptionAlg {
+        &self.alg
+    }
+
+    #[must_use]
+    pub const fn enc(&self) -> &JsonWebEncryptionEnc {
+        &self.enc
+    }
+
+    #[must_use]
+    pub fn kid(&self) -> Option<&str> {
+        self.kid.as_deref()
+    }
+
+    #[must_use]
+    pub fn with_kid(mut self, kid: impl Into<String>) -> Self {
+        self.kid = Some(kid.into());
+        self
+    }
+
+    #[must_use]
+    pub fn epk(&self) -> Option<&PublicJsonWebKey> {
+        self.epk.as_deref()
+    }
+
+    #[must_use]
+    pub(crate) fn with_epk(mut self, epk: PublicJsonWebKey) -> Self {
+        self.epk = Some(Box::new(epk));
+        self
+    }
+
+    #[must_use]
+    pub fn apu(&self) -> Option<&[u8]> {
+        self.apu.as_ref().map(Base64UrlNoPad::as_bytes)
+    }
+
+    #[must_use]
+    pub fn with_apu(mut self, apu: Vec<u8>) -> Self {
+        self.apu = Some(Base64UrlNoPad::new(apu));
+        self
+    }
+
+    #[must_use]
+    pub fn apv(&self) -> Option<&[u8]> {
+        self.apv.as_ref().map(Base64UrlNoPad::as_bytes)
+    }
+
+    #[must_use]
+    pub fn with_apv(mut self, apv: Vec<u8>) -> Self {
+        self.apv = Some(Base64UrlNoPad::new(apv));
+        self
+    }
+
+    #[must_use]
+    pub fn typ(&self) -> Option<&str> {
+        self.typ.as_deref()
+    }
+
+    #[must_use]
+    pub fn with_typ(mut self, typ: String) -> Self {
+        self.typ = Some(typ);
+        self
+    }
+
+    #[must_use]
+    pub fn cty(&self) -> Option<&str> {
+        self.cty.as_deref()
+    }
+
+    #[must_use]
+    pub fn with_cty(mut self, cty: String) -> Self {
+        self.cty = Some(cty);
+        self
+    }
+}
@@ -0,0 +1,15 @@
+// Copyright 2026 Element Creations Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+// Please see LICENSE files in the repository root for full details.
+
+mod encrypted;
+mod header;
+
+pub use self::{
+    encrypted::{
+        JweDecryptionError, JweDecryptionKey, JweEncryptionError,
+        SUPPORTED_CONTENT_ENCRYPTION_ALGORITHMS, SUPPORTED_ENCRYPTION_ALGORITHMS, decrypt, encrypt,
+    },
+    header::JsonWebEncryptionHeader,
+};
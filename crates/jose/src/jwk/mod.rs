@@ -7,8 +7,8 @@
 //! Ref: <https://www.rfc-editor.org/rfc/rfc7517.html>
 
 use mas_iana::jose::{
-    JsonWebKeyEcEllipticCurve, JsonWebKeyOperation, JsonWebKeyType, JsonWebKeyUse,
-    JsonWebSignatureAlg,
+    JsonWebEncryptionAlg, JsonWebKeyEcEllipticCurve, JsonWebKeyOperation, JsonWebKeyType,
+    JsonWebKeyUse, JsonWebSignatureAlg,
 };
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
@@ -36,7 +36,7 @@ pub trait ParametersInfo {
 
 /// An utilitary trait to figure out the [`JsonWebKeyEcEllipticCurve`] value for
 /// elliptic curves
-trait JwkEcCurve {
+pub(crate) trait JwkEcCurve {
     const CRV: JsonWebKeyEcEllipticCurve;
 }
 
@@ -379,6 +379,43 @@ impl<P> JsonWebKeySet<P> {
     }
 }
 
+impl PublicJsonWebKeySet {
+    /// Find a key which can be used to encrypt content with the given key
+    /// management algorithm. Returns `None` if no suitable key was found.
+    #[must_use]
+    pub fn encryption_key_for_algorithm(
+        &self,
+        alg: &JsonWebEncryptionAlg,
+    ) -> Option<&PublicJsonWebKey> {
+        self.keys.iter().find(|key| {
+            if key.r#use == Some(JsonWebKeyUse::Sig) {
+                return false;
+            }
+
+            // The `alg` parameter of the key is parsed as a signature algorithm,
+            // so compare their string representation instead
+            if key
+                .alg
+                .as_ref()
+                .is_some_and(|a| a.to_string() != alg.to_string())
+            {
+                return false;
+            }
+
+            match (alg, &key.parameters) {
+                (JsonWebEncryptionAlg::RsaOaep256, JsonWebKeyPublicParameters::Rsa(_)) => true,
+                (JsonWebEncryptionAlg::EcdhEs, JsonWebKeyPublicParameters::Ec(params)) => {
+                    matches!(
+                        params.crv,
+                        JsonWebKeyEcEllipticCurve::P256 | JsonWebKeyEcEllipticCurve::P384
+                    )
+                }
+                _ => false,
+            }
+        })
+    }
+}
+
 impl<P> FromIterator<JsonWebKey<P>> for JsonWebKeySet<P> {
     fn from_iter<T: IntoIterator<Item = JsonWebKey<P>>>(iter: T) -> Self {
         let keys = iter.into_iter().collect();
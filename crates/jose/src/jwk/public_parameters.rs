@@ -153,7 +153,7 @@ impl ParametersInfo for EcPublicParameters {
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub struct OkpPublicParameters {
-    crv: JsonWebKeyOkpEllipticCurve,
+    pub(crate) crv: JsonWebKeyOkpEllipticCurve,
 
     #[schemars(with = "String")]
     x: Base64UrlNoPad,
@@ -317,6 +317,59 @@ mod ec_impls {
     }
 }
 
+mod okp_impls {
+    use mas_iana::jose::JsonWebKeyOkpEllipticCurve;
+
+    use super::{JsonWebKeyPublicParameters, OkpPublicParameters};
+    use crate::base64::Base64UrlNoPad;
+
+    impl TryFrom<OkpPublicParameters> for ed25519_dalek::VerifyingKey {
+        type Error = signature::Error;
+        fn try_from(value: OkpPublicParameters) -> Result<Self, Self::Error> {
+            Self::try_from(&value)
+        }
+    }
+
+    impl TryFrom<&OkpPublicParameters> for ed25519_dalek::VerifyingKey {
+        type Error = signature::Error;
+
+        fn try_from(value: &OkpPublicParameters) -> Result<Self, Self::Error> {
+            if value.crv != JsonWebKeyOkpEllipticCurve::Ed25519 {
+                return Err(signature::Error::new());
+            }
+
+            ed25519_dalek::VerifyingKey::try_from(value.x.as_bytes())
+        }
+    }
+
+    impl From<ed25519_dalek::VerifyingKey> for JsonWebKeyPublicParameters {
+        fn from(key: ed25519_dalek::VerifyingKey) -> Self {
+            (&key).into()
+        }
+    }
+
+    impl From<&ed25519_dalek::VerifyingKey> for JsonWebKeyPublicParameters {
+        fn from(key: &ed25519_dalek::VerifyingKey) -> Self {
+            Self::Okp(key.into())
+        }
+    }
+
+    impl From<ed25519_dalek::VerifyingKey> for OkpPublicParameters {
+        fn from(key: ed25519_dalek::VerifyingKey) -> Self {
+            (&key).into()
+        }
+    }
+
+    impl From<&ed25519_dalek::VerifyingKey> for OkpPublicParameters {
+        fn from(key: &ed25519_dalek::VerifyingKey) -> Self {
+            OkpPublicParameters {
+                crv: JsonWebKeyOkpEllipticCurve::Ed25519,
+                x: Base64UrlNoPad::new(key.to_bytes().to_vec()),
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
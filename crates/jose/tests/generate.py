@@ -3,15 +3,20 @@
 # SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
 # Please see LICENSE files in the repository root for full details.
 
-# Generates test keys, JWKS and JWTs
+# Generates test keys, JWKS, JWTs and JWEs
 # Required the `openssl` binary and the `authlib` python library
 
 import json
 import subprocess
+from base64 import urlsafe_b64encode
+from os import urandom
 from pathlib import Path
 from typing import List
 
 from authlib.jose import JsonWebKey, JsonWebSignature, KeySet
+from cryptography.hazmat.primitives import hashes, serialization
+from cryptography.hazmat.primitives.asymmetric import ec, padding
+from cryptography.hazmat.primitives.ciphers.aead import AESGCM
 
 output_path = Path(__file__).parent
 
@@ -141,3 +146,75 @@ sign_jwt("ES512", "es512.jwt", p521_key)
 sign_jwt("ES256K", "es256k.jwt", k256_key)
 sign_jwt("EdDSA", "eddsa-ed25519.jwt", ed25519_key)
 sign_jwt("EdDSA", "eddsa-ed448.jwt", ed448_key)
+
+
+# JWEs are encrypted with the `cryptography` library directly, which gives
+# known answers from an implementation independent from ours
+jwes_path = output_path / "jwes"
+jwes_path.mkdir(parents=True, exist_ok=True)
+
+
+def b64(data: bytes) -> str:
+    return urlsafe_b64encode(data).decode().rstrip("=")
+
+
+def load_public_key(name: str):
+    with open(keys_path / name, "rb") as f:
+        return serialization.load_pem_private_key(f.read(), None).public_key()
+
+
+def concat_kdf(z: bytes, enc: str, apu: bytes, apv: bytes) -> bytes:
+    """Concat KDF from RFC 7518 sec. 4.6.2, for a 256-bit key"""
+    digest = hashes.Hash(hashes.SHA256())
+    digest.update((1).to_bytes(4, "big") + z)
+    for field in [enc.encode(), apu, apv]:
+        digest.update(len(field).to_bytes(4, "big") + field)
+    digest.update((256).to_bytes(4, "big"))
+    return digest.finalize()
+
+
+def encrypt_jwe(alg: str, filename: str, key_name: str):
+    """Encrypt a JWE with A256GCM to the given key"""
+    public_key = load_public_key(key_name)
+    protected = {"alg": alg, "enc": "A256GCM", "kid": key_name}
+
+    if alg == "RSA-OAEP-256":
+        cek = AESGCM.generate_key(bit_length=256)
+        oaep = padding.OAEP(
+            mgf=padding.MGF1(algorithm=hashes.SHA256()),
+            algorithm=hashes.SHA256(),
+            label=None,
+        )
+        encrypted_key = public_key.encrypt(cek, oaep)
+    elif alg == "ECDH-ES":
+        ephemeral = ec.generate_private_key(public_key.curve)
+        numbers = ephemeral.public_key().public_numbers()
+        size = (public_key.curve.key_size + 7) // 8
+        protected["epk"] = {
+            "kty": "EC",
+            "crv": {"secp256r1": "P-256", "secp384r1": "P-384"}[public_key.curve.name],
+            "x": b64(numbers.x.to_bytes(size, "big")),
+            "y": b64(numbers.y.to_bytes(size, "big")),
+        }
+        protected["apu"] = b64(b"Alice")
+        protected["apv"] = b64(b"Bob")
+        z = ephemeral.exchange(ec.ECDH(), public_key)
+        cek = concat_kdf(z, "A256GCM", b"Alice", b"Bob")
+        encrypted_key = b""
+    else:
+        raise ValueError(alg)
+
+    header = b64(json.dumps(protected).encode())
+    iv = urandom(12)
+    payload = b'{"hello":"world"}'
+    sealed = AESGCM(cek).encrypt(iv, payload, header.encode())
+    ciphertext, tag = sealed[:-16], sealed[-16:]
+
+    jwe = ".".join([header, b64(encrypted_key), b64(iv), b64(ciphertext), b64(tag)])
+    with open(jwes_path / filename, "w") as f:
+        f.write(jwe)
+
+
+encrypt_jwe("RSA-OAEP-256", "rsa-oaep-256.jwe", "rsa.priv.pem")
+encrypt_jwe("ECDH-ES", "ecdh-es-p256.jwe", "p256.priv.pem")
+encrypt_jwe("ECDH-ES", "ecdh-es-p384.jwe", "p384.priv.pem")
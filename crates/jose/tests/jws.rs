@@ -213,7 +213,7 @@ asymetric_jwt_test!(es256, Es256, ES256_JWT);
 asymetric_jwt_test!(es384, Es384, ES384_JWT);
 asymetric_jwt_test!(es512, Es512, ES512_JWT, supported = false);
 asymetric_jwt_test!(es256k, Es256K, ES256K_JWT);
-asymetric_jwt_test!(eddsa_ed25519, EdDsa, EDDSA_ED25519_JWT, supported = false);
+asymetric_jwt_test!(eddsa_ed25519, EdDsa, EDDSA_ED25519_JWT);
 asymetric_jwt_test!(eddsa_ed448, EdDsa, EDDSA_ED448_JWT, supported = false);
 
 #[test]
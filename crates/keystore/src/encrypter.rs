@@ -4,18 +4,30 @@
 // SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
 // Please see LICENSE files in the repository root for full details.
 
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 
 use aead::Aead;
 use base64ct::{Base64, Encoding};
 use chacha20poly1305::{ChaCha20Poly1305, KeyInit};
+use der::zeroize::Zeroizing;
 use generic_array::GenericArray;
 use thiserror::Error;
 
+/// The version prefix of ciphertexts which carry the identifier of the key
+/// used to encrypt them
+const VERSION_PREFIX: &str = "v1";
+
 /// Helps encrypting and decrypting data
+///
+/// Ciphertexts produced by [`Encrypter::encrypt_to_string`] take the form
+/// `v1:<key id>:<base64>` when a versioned key is active, so that they can be
+/// decrypted after the active key changed. Ciphertexts without a prefix were
+/// encrypted with the main key.
 #[derive(Clone)]
 pub struct Encrypter {
     aead: Arc<ChaCha20Poly1305>,
+    keys: Arc<HashMap<String, ChaCha20Poly1305>>,
+    active_key_id: Option<Arc<str>>,
 }
 
 #[derive(Debug, Error)]
@@ -24,6 +36,7 @@ pub enum DecryptError {
     Aead(#[from] aead::Error),
     Base64(#[from] base64ct::Error),
     Shape,
+    UnknownKey(String),
 }
 
 impl Encrypter {
@@ -33,10 +46,46 @@ impl Encrypter {
         let key = GenericArray::from_slice(key);
         let aead = ChaCha20Poly1305::new(key);
         let aead = Arc::new(aead);
-        Self { aead }
+        Self {
+            aead,
+            keys: Arc::default(),
+            active_key_id: None,
+        }
+    }
+
+    /// Add versioned keys to this [`Encrypter`], with their identifiers.
+    ///
+    /// The first key is used to encrypt new payloads, while the others are
+    /// only used to decrypt payloads encrypted before it became active.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a key identifier contains a `:`
+    #[must_use]
+    pub fn with_keys(mut self, keys: impl IntoIterator<Item = (String, [u8; 32])>) -> Self {
+        let mut versioned = HashMap::new();
+        for (id, key) in keys {
+            assert!(!id.contains(':'), "key identifiers must not contain ':'");
+            if self.active_key_id.is_none() {
+                self.active_key_id = Some(id.as_str().into());
+            }
+
+            let aead = ChaCha20Poly1305::new(GenericArray::from_slice(&key));
+            versioned.insert(id, aead);
+        }
+
+        self.keys = Arc::new(versioned);
+        self
+    }
+
+    /// Get the identifier of the key used to encrypt new payloads, if it is a
+    /// versioned key
+    #[must_use]
+    pub fn active_key_id(&self) -> Option<&str> {
+        self.active_key_id.as_deref()
     }
 
-    /// Encrypt a payload
+    /// Encrypt a payload with the main key
     ///
     /// # Errors
     ///
@@ -47,7 +96,7 @@ impl Encrypter {
         Ok(encrypted)
     }
 
-    /// Decrypts a payload
+    /// Decrypts a payload with the main key
     ///
     /// # Errors
     ///
@@ -58,25 +107,57 @@ impl Encrypter {
         Ok(encrypted)
     }
 
-    /// Encrypt a payload to a self-contained base64-encoded string
+    /// Encrypt a payload to a self-contained base64-encoded string, prefixed
+    /// with the identifier of the active key if there is one
     ///
     /// # Errors
     ///
     /// Will return `Err` when the payload failed to encrypt
+    ///
+    /// # Panics
+    ///
+    /// Panics if the active key is not in the keyring, which the constructors
+    /// guarantee never happens
     pub fn encrypt_to_string(&self, decrypted: &[u8]) -> Result<String, aead::Error> {
-        let nonce = rand::random();
-        let encrypted = self.encrypt(&nonce, decrypted)?;
+        let (prefix, aead) = match &self.active_key_id {
+            Some(id) => (
+                Some(id),
+                self.keys
+                    .get(&**id)
+                    .expect("the active key is always known"),
+            ),
+            None => (None, &*self.aead),
+        };
+
+        let nonce: [u8; 12] = rand::random();
+        let encrypted = aead.encrypt(GenericArray::from_slice(&nonce), decrypted)?;
         let encrypted = [&nonce[..], &encrypted].concat();
         let encrypted = Base64::encode_string(&encrypted);
-        Ok(encrypted)
+
+        Ok(match prefix {
+            Some(id) => format!("{VERSION_PREFIX}:{id}:{encrypted}"),
+            None => encrypted,
+        })
     }
 
     /// Decrypt a payload from a self-contained base64-encoded string
     ///
     /// # Errors
     ///
-    /// Will return `Err` when the payload failed to decrypt
+    /// Will return `Err` when the payload failed to decrypt, or if it was
+    /// encrypted with an unknown key
     pub fn decrypt_string(&self, encrypted: &str) -> Result<Vec<u8>, DecryptError> {
+        let (aead, encrypted) = match split_key_id(encrypted)? {
+            Some((id, encrypted)) => {
+                let aead = self
+                    .keys
+                    .get(id)
+                    .ok_or_else(|| DecryptError::UnknownKey(id.to_owned()))?;
+                (aead, encrypted)
+            }
+            None => (&*self.aead, encrypted),
+        };
+
         let encrypted = Base64::decode_vec(encrypted)?;
 
         let nonce: &[u8; 12] = encrypted
@@ -87,8 +168,98 @@ impl Encrypter {
 
         let payload = encrypted.get(12..).ok_or(DecryptError::Shape)?;
 
-        let decrypted_client_secret = self.decrypt(nonce, payload)?;
+        let decrypted_client_secret = aead.decrypt(GenericArray::from_slice(nonce), payload)?;
 
         Ok(decrypted_client_secret)
     }
+
+    /// Returns `true` if the given payload was not encrypted with the active
+    /// key, and should be re-encrypted with it
+    #[must_use]
+    pub fn needs_reencryption(&self, encrypted: &str) -> bool {
+        let key_id = split_key_id(encrypted)
+            .ok()
+            .flatten()
+            .map(|(key_id, _)| key_id);
+        key_id != self.active_key_id()
+    }
+
+    /// Decrypt a payload and encrypt it again with the active key
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` when the payload failed to decrypt or to encrypt
+    pub fn reencrypt_string(&self, encrypted: &str) -> Result<String, DecryptError> {
+        let decrypted = Zeroizing::new(self.decrypt_string(encrypted)?);
+        Ok(self.encrypt_to_string(&decrypted)?)
+    }
+}
+
+/// Split the key identifier out of a versioned ciphertext.
+///
+/// Returns `None` if the ciphertext has no prefix, meaning it was encrypted
+/// with the main key.
+fn split_key_id(encrypted: &str) -> Result<Option<(&str, &str)>, DecryptError> {
+    // The base64 alphabet doesn't include ':', so unprefixed payloads never
+    // contain it
+    let Some((version, rest)) = encrypted.split_once(':') else {
+        return Ok(None);
+    };
+
+    if version != VERSION_PREFIX {
+        return Err(DecryptError::Shape);
+    }
+
+    let (key_id, encrypted) = rest.split_once(':').ok_or(DecryptError::Shape)?;
+    Ok(Some((key_id, encrypted)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_legacy_payloads() {
+        let encrypter = Encrypter::new(&[0x42; 32]);
+        let payload = encrypter.encrypt_to_string(b"hello").unwrap();
+        assert!(!payload.contains(':'));
+        assert_eq!(encrypter.decrypt_string(&payload).unwrap(), b"hello");
+        assert!(!encrypter.needs_reencryption(&payload));
+
+        // Payloads payload with the main key can still be decrypted once
+        // versioned keys are added, but should be re-payload
+        let encrypter = encrypter.with_keys([("k1".to_owned(), [0x01; 32])]);
+        assert_eq!(encrypter.decrypt_string(&payload).unwrap(), b"hello");
+        assert!(encrypter.needs_reencryption(&payload));
+
+        let reencrypted = encrypter.reencrypt_string(&payload).unwrap();
+        assert!(reencrypted.starts_with("v1:k1:"));
+        assert!(!encrypter.needs_reencryption(&reencrypted));
+        assert_eq!(encrypter.decrypt_string(&reencrypted).unwrap(), b"hello");
+    }
+
+    #[test]
+    fn test_key_rotation() {
+        let old = Encrypter::new(&[0x42; 32]).with_keys([("k1".to_owned(), [0x01; 32])]);
+        let encrypted = old.encrypt_to_string(b"hello").unwrap();
+        assert!(encrypted.starts_with("v1:k1:"));
+
+        // The new key is active, the old one is still used for decryption
+        let new = Encrypter::new(&[0x42; 32])
+            .with_keys([("k2".to_owned(), [0x02; 32]), ("k1".to_owned(), [0x01; 32])]);
+        assert_eq!(new.active_key_id(), Some("k2"));
+        assert_eq!(new.decrypt_string(&encrypted).unwrap(), b"hello");
+        assert!(new.needs_reencryption(&encrypted));
+
+        let reencrypted = new.reencrypt_string(&encrypted).unwrap();
+        assert!(reencrypted.starts_with("v1:k2:"));
+
+        // Once the old key is removed, its payloads can't be decrypted anymore
+        let newer = Encrypter::new(&[0x42; 32]).with_keys([("k2".to_owned(), [0x02; 32])]);
+        assert!(matches!(
+            newer.decrypt_string(&encrypted),
+            Err(DecryptError::UnknownKey(id)) if id == "k1"
+        ));
+        assert_eq!(newer.decrypt_string(&reencrypted).unwrap(), b"hello");
+    }
 }
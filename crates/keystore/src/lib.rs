@@ -24,10 +24,14 @@ use rsa::BigUint;
 use thiserror::Error;
 
 mod encrypter;
+mod pkcs11;
 
 pub use aead;
 
-pub use self::encrypter::{DecryptError, Encrypter};
+pub use self::{
+    encrypter::{DecryptError, Encrypter},
+    pkcs11::{Pkcs11Error, Pkcs11Key, Pkcs11Token},
+};
 
 /// Error type used when a key could not be loaded
 #[derive(Debug, Error)]
@@ -119,12 +123,14 @@ impl LoadError {
 
 /// A single private key
 #[non_exhaustive]
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum PrivateKey {
     Rsa(Box<rsa::RsaPrivateKey>),
     EcP256(Box<elliptic_curve::SecretKey<p256::NistP256>>),
     EcP384(Box<elliptic_curve::SecretKey<p384::NistP384>>),
     EcK256(Box<elliptic_curve::SecretKey<k256::Secp256k1>>),
+    Ed25519(Box<ed25519_dalek::SigningKey>),
+    Pkcs11(Pkcs11Key),
 }
 
 /// Error returned when the key can't be used for the requested algorithm
@@ -160,6 +166,7 @@ impl PrivateKey {
                 k256::Secp256k1::OID => Ok(Self::EcK256(Box::new(info.try_into()?))),
                 oid => Err(LoadError::UnknownEllipticCurveOid { oid }),
             },
+            ed25519_dalek::pkcs8::ALGORITHM_OID => Ok(Self::Ed25519(Box::new(info.try_into()?))),
             oid => Err(LoadError::UnknownAlgorithmOid { oid }),
         }
     }
@@ -182,17 +189,20 @@ impl PrivateKey {
     /// Serialize the key as a DER document
     ///
     /// It will use the most common format depending on the key type: PKCS1 for
-    /// RSA keys and SEC1 for elliptic curve keys
+    /// RSA keys, SEC1 for elliptic curve keys and PKCS8 for Ed25519 keys
     ///
     /// # Errors
     ///
-    /// Returns an error if the encoding failed
+    /// Returns an error if the encoding failed, or if the key is held in a
+    /// PKCS#11 token, as it can't be exported
     pub fn to_der(&self) -> Result<Zeroizing<Vec<u8>>, pkcs1::Error> {
         let der = match self {
             PrivateKey::Rsa(key) => key.to_pkcs1_der()?.to_bytes(),
             PrivateKey::EcP256(key) => to_sec1_der(key)?,
             PrivateKey::EcP384(key) => to_sec1_der(key)?,
             PrivateKey::EcK256(key) => to_sec1_der(key)?,
+            PrivateKey::Ed25519(key) => to_ed25519_pkcs8_der(key)?,
+            PrivateKey::Pkcs11(_) => return Err(pkcs1::Error::Crypto),
         };
 
         Ok(der)
@@ -202,13 +212,16 @@ impl PrivateKey {
     ///
     /// # Errors
     ///
-    /// Returns an error if the encoding failed
+    /// Returns an error if the encoding failed, or if the key is held in a
+    /// PKCS#11 token, as it can't be exported
     pub fn to_pkcs8_der(&self) -> Result<Zeroizing<Vec<u8>>, pkcs8::Error> {
         let der = match self {
             PrivateKey::Rsa(key) => key.to_pkcs8_der()?,
             PrivateKey::EcP256(key) => key.to_pkcs8_der()?,
             PrivateKey::EcP384(key) => key.to_pkcs8_der()?,
             PrivateKey::EcK256(key) => key.to_pkcs8_der()?,
+            PrivateKey::Ed25519(key) => return to_ed25519_pkcs8_der(key),
+            PrivateKey::Pkcs11(_) => return Err(pkcs8::Error::KeyMalformed),
         };
 
         Ok(der.to_bytes())
@@ -217,11 +230,12 @@ impl PrivateKey {
     /// Serialize the key as a PEM document
     ///
     /// It will use the most common format depending on the key type: PKCS1 for
-    /// RSA keys and SEC1 for elliptic curve keys
+    /// RSA keys, SEC1 for elliptic curve keys and PKCS8 for Ed25519 keys
     ///
     /// # Errors
     ///
-    /// Returns an error if the encoding failed
+    /// Returns an error if the encoding failed, or if the key is held in a
+    /// PKCS#11 token, as it can't be exported
     pub fn to_pem(
         &self,
         line_ending: pem_rfc7468::LineEnding,
@@ -231,6 +245,8 @@ impl PrivateKey {
             PrivateKey::EcP256(key) => to_sec1_pem(key, line_ending)?,
             PrivateKey::EcP384(key) => to_sec1_pem(key, line_ending)?,
             PrivateKey::EcK256(key) => to_sec1_pem(key, line_ending)?,
+            PrivateKey::Ed25519(key) => to_ed25519_pkcs8_pem(key, line_ending)?,
+            PrivateKey::Pkcs11(_) => return Err(pkcs1::Error::Crypto),
         };
 
         Ok(pem)
@@ -442,6 +458,12 @@ impl PrivateKey {
                 AsymmetricVerifyingKey::es256k(key.public_key())
             }
 
+            (Self::Ed25519(key), JsonWebSignatureAlg::EdDsa) => {
+                AsymmetricVerifyingKey::eddsa(key.verifying_key())
+            }
+
+            (Self::Pkcs11(key), alg) => key.verifying_key_for_alg(alg)?,
+
             _ => return Err(WrongAlgorithmError),
         };
 
@@ -484,6 +506,12 @@ impl PrivateKey {
                 AsymmetricSigningKey::es256k(*key.clone())
             }
 
+            (Self::Ed25519(key), JsonWebSignatureAlg::EdDsa) => {
+                AsymmetricSigningKey::eddsa(*key.clone())
+            }
+
+            (Self::Pkcs11(key), alg) => key.signing_key_for_alg(alg)?,
+
             _ => return Err(WrongAlgorithmError),
         };
 
@@ -517,6 +545,12 @@ impl PrivateKey {
         let key = elliptic_curve::SecretKey::random(&mut rng);
         Self::EcK256(Box::new(key))
     }
+
+    /// Generate an Edwards Curve key for the Ed25519 curve
+    pub fn generate_ed25519<R: RngCore + CryptoRng>(mut rng: R) -> Self {
+        let key = ed25519_dalek::SigningKey::generate(&mut rng);
+        Self::Ed25519(Box::new(key))
+    }
 }
 
 // The default implementation of SecretKey::to_sec1_pem/der do not include the
@@ -561,6 +595,30 @@ where
     ))
 }
 
+// The PKCS8 encoder of ed25519-dalek includes the public key in the document,
+// which OpenSSL doesn't do. This encodes only the private key, so that it
+// matches what OpenSSL outputs.
+fn to_ed25519_pkcs8_der(
+    key: &ed25519_dalek::SigningKey,
+) -> Result<Zeroizing<Vec<u8>>, pkcs8::Error> {
+    let keypair = ed25519_dalek::pkcs8::KeypairBytes {
+        secret_key: key.to_bytes(),
+        public_key: None,
+    };
+    Ok(keypair.to_pkcs8_der()?.to_bytes())
+}
+
+fn to_ed25519_pkcs8_pem(
+    key: &ed25519_dalek::SigningKey,
+    line_ending: pem_rfc7468::LineEnding,
+) -> Result<Zeroizing<String>, pkcs8::Error> {
+    let keypair = ed25519_dalek::pkcs8::KeypairBytes {
+        secret_key: key.to_bytes(),
+        public_key: None,
+    };
+    keypair.to_pkcs8_pem(line_ending)
+}
+
 impl From<&PrivateKey> for JsonWebKeyPublicParameters {
     fn from(val: &PrivateKey) -> Self {
         match val {
@@ -568,6 +626,8 @@ impl From<&PrivateKey> for JsonWebKeyPublicParameters {
             PrivateKey::EcP256(key) => key.public_key().into(),
             PrivateKey::EcP384(key) => key.public_key().into(),
             PrivateKey::EcK256(key) => key.public_key().into(),
+            PrivateKey::Ed25519(key) => key.verifying_key().into(),
+            PrivateKey::Pkcs11(key) => key.public_parameters(),
         }
     }
 }
@@ -579,6 +639,8 @@ impl ParametersInfo for PrivateKey {
             PrivateKey::EcP256(_) | PrivateKey::EcP384(_) | PrivateKey::EcK256(_) => {
                 JsonWebKeyType::Ec
             }
+            PrivateKey::Ed25519(_) => JsonWebKeyType::Okp,
+            PrivateKey::Pkcs11(key) => key.kty(),
         }
     }
 
@@ -595,6 +657,8 @@ impl ParametersInfo for PrivateKey {
             PrivateKey::EcP256(_) => &[JsonWebSignatureAlg::Es256],
             PrivateKey::EcP384(_) => &[JsonWebSignatureAlg::Es384],
             PrivateKey::EcK256(_) => &[JsonWebSignatureAlg::Es256K],
+            PrivateKey::Ed25519(_) => &[JsonWebSignatureAlg::EdDsa],
+            PrivateKey::Pkcs11(key) => key.possible_algs(),
         }
     }
 }
@@ -608,17 +672,40 @@ impl Thumbprint for PrivateKey {
 /// A structure to store a list of [`PrivateKey`]. The keys are held in an
 /// [`Arc`] to ensure they are only loaded once in memory and allow cheap
 /// cloning
+///
+/// On top of the keys used for signing, the keystore can hold keys which are
+/// only published in the public JWKS, either because they are not used yet or
+/// because they are not used anymore.
 #[derive(Clone, Default)]
 pub struct Keystore {
     keys: Arc<JsonWebKeySet<PrivateKey>>,
+    verification_keys: Arc<JsonWebKeySet<PrivateKey>>,
 }
 
 impl Keystore {
     /// Create a keystore out of a JSON Web Key Set
     #[must_use]
     pub fn new(keys: JsonWebKeySet<PrivateKey>) -> Self {
-        let keys = Arc::new(keys);
-        Self { keys }
+        Self::with_verification_keys(keys, JsonWebKeySet::default())
+    }
+
+    /// Create a keystore out of a JSON Web Key Set used for signing, and a set
+    /// of keys which are only published for verification
+    #[must_use]
+    pub fn with_verification_keys(
+        keys: JsonWebKeySet<PrivateKey>,
+        verification_keys: JsonWebKeySet<PrivateKey>,
+    ) -> Self {
+        Self {
+            keys: Arc::new(keys),
+            verification_keys: Arc::new(verification_keys),
+        }
+    }
+
+    /// Get the keys which are only published for verification
+    #[must_use]
+    pub fn verification_keys(&self) -> &JsonWebKeySet<PrivateKey> {
+        &self.verification_keys
     }
 
     /// Get the public JSON Web Key Set for the keys stored in this [`Keystore`]
@@ -626,6 +713,7 @@ impl Keystore {
     pub fn public_jwks(&self) -> PublicJsonWebKeySet {
         self.keys
             .iter()
+            .chain(self.verification_keys.iter())
             .map(|key| {
                 key.cloned_map(|params: &PrivateKey| JsonWebKeyPublicParameters::from(params))
             })
@@ -0,0 +1,557 @@
+// Copyright 2026 Element Creations Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+// Please see LICENSE files in the repository root for full details.
+
+//! Signing keys held in a PKCS#11 token, like a hardware security module.
+//!
+//! The private part of those keys never leaves the token: signing operations
+//! are delegated to the PKCS#11 module, and only the public part is exported
+//! to be published in the JWKS.
+
+use std::{
+    fmt,
+    sync::{Arc, Mutex},
+};
+
+use camino::Utf8Path;
+use cryptoki::{
+    context::{CInitializeArgs, Pkcs11},
+    error::RvError,
+    mechanism::Mechanism,
+    object::{Attribute, AttributeType, KeyType, ObjectClass, ObjectHandle},
+    session::{Session, UserType},
+    slot::Slot,
+    types::AuthPin,
+};
+use der::{Decode, asn1::OctetStringRef};
+use mas_iana::jose::{JsonWebKeyType, JsonWebSignatureAlg};
+use mas_jose::{
+    jwa::{AsymmetricSigningKey, AsymmetricVerifyingKey, ExternalSigningKey},
+    jwk::JsonWebKeyPublicParameters,
+};
+use pkcs8::AssociatedOid;
+use rsa::BigUint;
+use sha2::{Digest, Sha256, Sha384};
+use thiserror::Error;
+use tokio::runtime::{Handle, RuntimeFlavor};
+
+use crate::WrongAlgorithmError;
+
+/// Error type used when a PKCS#11 token or key could not be loaded
+#[derive(Debug, Error)]
+pub enum Pkcs11Error {
+    #[error("PKCS#11 operation failed")]
+    Cryptoki {
+        #[from]
+        inner: cryptoki::error::Error,
+    },
+
+    #[error("No PKCS#11 token with label {label:?}")]
+    TokenNotFound { label: String },
+
+    #[error("No private key with label {label:?} in the PKCS#11 token")]
+    KeyNotFound { label: String },
+
+    #[error("Multiple private keys with label {label:?} in the PKCS#11 token")]
+    AmbiguousKey { label: String },
+
+    #[error("No public key with label {label:?} in the PKCS#11 token")]
+    PublicKeyNotFound { label: String },
+
+    #[error("Multiple public keys with label {label:?} in the PKCS#11 token")]
+    AmbiguousPublicKey { label: String },
+
+    #[error("Missing attribute {attribute} on PKCS#11 key {label:?}")]
+    MissingAttribute {
+        label: String,
+        attribute: AttributeType,
+    },
+
+    #[error("Unsupported PKCS#11 key type {key_type}")]
+    UnsupportedKeyType { key_type: KeyType },
+
+    #[error("Unknown Elliptic Curve OID {oid}")]
+    UnknownEllipticCurveOid { oid: const_oid::ObjectIdentifier },
+
+    #[error("Invalid RSA public key")]
+    Rsa {
+        #[from]
+        inner: rsa::errors::Error,
+    },
+
+    #[error("Invalid Elliptic Curve public key")]
+    EllipticCurve {
+        #[from]
+        inner: elliptic_curve::Error,
+    },
+
+    #[error(transparent)]
+    Der {
+        #[from]
+        inner: der::Error,
+    },
+}
+
+/// Whether the error means that the session is no longer usable, and that a
+/// new one should be opened
+fn is_session_error(error: &cryptoki::error::Error) -> bool {
+    matches!(
+        error,
+        cryptoki::error::Error::Pkcs11(
+            RvError::SessionHandleInvalid
+                | RvError::SessionClosed
+                | RvError::UserNotLoggedIn
+                | RvError::DeviceRemoved
+                | RvError::TokenNotPresent,
+            _
+        )
+    )
+}
+
+/// Whether the error means that an object handle is no longer valid, and
+/// that the object should be looked up again
+fn is_handle_error(error: &cryptoki::error::Error) -> bool {
+    matches!(
+        error,
+        cryptoki::error::Error::Pkcs11(RvError::ObjectHandleInvalid | RvError::KeyHandleInvalid, _)
+    )
+}
+
+struct Pkcs11TokenInner {
+    label: String,
+    pkcs11: Pkcs11,
+    slot: Slot,
+    pin: AuthPin,
+    session: Mutex<Session>,
+}
+
+impl Pkcs11TokenInner {
+    /// Open a new session on the token, and log in with the user PIN
+    fn login(pkcs11: &Pkcs11, slot: Slot, pin: &AuthPin) -> Result<Session, Pkcs11Error> {
+        let session = pkcs11.open_ro_session(slot)?;
+        match session.login(UserType::User, Some(pin)) {
+            // The login state is shared by all the sessions of the application
+            Ok(()) | Err(cryptoki::error::Error::Pkcs11(RvError::UserAlreadyLoggedIn, _)) => {}
+            Err(e) => return Err(e.into()),
+        }
+
+        Ok(session)
+    }
+
+    /// Replace the session with a new, logged-in one
+    fn reopen(&self, session: &mut Session) -> Result<(), Pkcs11Error> {
+        *session = Self::login(&self.pkcs11, self.slot, &self.pin)?;
+        Ok(())
+    }
+}
+
+/// A logged-in session on a PKCS#11 token, from which keys can be loaded
+///
+/// The session is shared by all the keys loaded from it, and serializes the
+/// signing operations. If it gets closed, for example because the token was
+/// reset, a new one is opened and logged in on the next operation.
+#[derive(Clone)]
+pub struct Pkcs11Token {
+    inner: Arc<Pkcs11TokenInner>,
+}
+
+impl fmt::Debug for Pkcs11Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Pkcs11Token")
+            .field("label", &self.inner.label)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Pkcs11Token {
+    /// Load the given PKCS#11 module, and log in the token with the given
+    /// label using the user PIN
+    ///
+    /// This does blocking calls to the PKCS#11 module, and should not be called
+    /// from an async context.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the module could not be loaded, if no token with
+    /// this label was found, or if the login failed
+    pub fn open(module: &Utf8Path, label: &str, pin: &str) -> Result<Self, Pkcs11Error> {
+        let pkcs11 = Pkcs11::new(module)?;
+        match pkcs11.initialize(CInitializeArgs::OsThreads) {
+            // The module might already be initialized if multiple tokens from the same module are
+            // used
+            Ok(())
+            | Err(cryptoki::error::Error::Pkcs11(RvError::CryptokiAlreadyInitialized, _)) => {}
+            Err(e) => return Err(e.into()),
+        }
+
+        let mut slot = None;
+        for candidate in pkcs11.get_slots_with_token()? {
+            if pkcs11.get_token_info(candidate)?.label() == label {
+                slot = Some(candidate);
+                break;
+            }
+        }
+        let slot = slot.ok_or_else(|| Pkcs11Error::TokenNotFound {
+            label: label.to_owned(),
+        })?;
+
+        let pin = AuthPin::new(pin.to_owned());
+        let session = Pkcs11TokenInner::login(&pkcs11, slot, &pin)?;
+
+        Ok(Self {
+            inner: Arc::new(Pkcs11TokenInner {
+                label: label.to_owned(),
+                pkcs11,
+                slot,
+                pin,
+                session: Mutex::new(session),
+            }),
+        })
+    }
+
+    /// Load the signing key with the given label from this token
+    ///
+    /// The private key is looked up by its label, and the public key by the
+    /// same label and the `CKA_ID` of the private key. Only RSA keys and ECDSA
+    /// keys on the P-256 and P-384 curves are supported.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the key could not be found, if multiple keys
+    /// match, or if it is not supported
+    pub fn load_key(&self, label: &str) -> Result<Pkcs11Key, Pkcs11Error> {
+        let mut session = self
+            .inner
+            .session
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+
+        let (handle, public_key) = match load_key(&session, label) {
+            Err(Pkcs11Error::Cryptoki { inner }) if is_session_error(&inner) => {
+                self.inner.reopen(&mut session)?;
+                load_key(&session, label)?
+            }
+            res => res?,
+        };
+        drop(session);
+
+        Ok(Pkcs11Key {
+            inner: Arc::new(Pkcs11KeyInner {
+                label: label.to_owned(),
+                token: self.inner.clone(),
+                handle: Mutex::new(handle),
+                public_key,
+            }),
+        })
+    }
+}
+
+/// Find the private key with the given label, returning its handle
+fn find_private_key(session: &Session, label: &str) -> Result<ObjectHandle, Pkcs11Error> {
+    let mut handles = session.find_objects(&[
+        Attribute::Class(ObjectClass::PRIVATE_KEY),
+        Attribute::Label(label.as_bytes().to_vec()),
+    ])?;
+
+    match handles.len() {
+        0 => Err(Pkcs11Error::KeyNotFound {
+            label: label.to_owned(),
+        }),
+        1 => Ok(handles.remove(0)),
+        _ => Err(Pkcs11Error::AmbiguousKey {
+            label: label.to_owned(),
+        }),
+    }
+}
+
+/// Find the private key with the given label and its matching public key
+fn load_key(session: &Session, label: &str) -> Result<(ObjectHandle, PublicKey), Pkcs11Error> {
+    let handle = find_private_key(session, label)?;
+
+    // Match the public key on the CKA_ID of the private key as well, as labels
+    // are not necessarily unique
+    let mut template = vec![
+        Attribute::Class(ObjectClass::PUBLIC_KEY),
+        Attribute::Label(label.as_bytes().to_vec()),
+    ];
+    for attribute in session.get_attributes(handle, &[AttributeType::Id])? {
+        if let Attribute::Id(id) = attribute {
+            template.push(Attribute::Id(id));
+        }
+    }
+
+    let mut public_handles = session.find_objects(&template)?;
+    let public_handle = match public_handles.len() {
+        0 => {
+            return Err(Pkcs11Error::PublicKeyNotFound {
+                label: label.to_owned(),
+            });
+        }
+        1 => public_handles.remove(0),
+        _ => {
+            return Err(Pkcs11Error::AmbiguousPublicKey {
+                label: label.to_owned(),
+            });
+        }
+    };
+
+    let attributes = session.get_attributes(
+        public_handle,
+        &[
+            AttributeType::KeyType,
+            AttributeType::Modulus,
+            AttributeType::PublicExponent,
+            AttributeType::EcParams,
+            AttributeType::EcPoint,
+        ],
+    )?;
+
+    let public_key = PublicKey::from_attributes(label, &attributes)?;
+
+    Ok((handle, public_key))
+}
+
+/// The public part of a key held in a PKCS#11 token
+#[derive(Debug)]
+enum PublicKey {
+    Rsa(rsa::RsaPublicKey),
+    EcP256(p256::PublicKey),
+    EcP384(p384::PublicKey),
+}
+
+impl PublicKey {
+    fn from_attributes(label: &str, attributes: &[Attribute]) -> Result<Self, Pkcs11Error> {
+        let missing = |attribute| Pkcs11Error::MissingAttribute {
+            label: label.to_owned(),
+            attribute,
+        };
+
+        let mut key_type = None;
+        let mut modulus = None;
+        let mut public_exponent = None;
+        let mut ec_params = None;
+        let mut ec_point = None;
+        for attribute in attributes {
+            match attribute {
+                Attribute::KeyType(value) => key_type = Some(*value),
+                Attribute::Modulus(value) => modulus = Some(value.as_slice()),
+                Attribute::PublicExponent(value) => public_exponent = Some(value.as_slice()),
+                Attribute::EcParams(value) => ec_params = Some(value.as_slice()),
+                Attribute::EcPoint(value) => ec_point = Some(value.as_slice()),
+                _ => {}
+            }
+        }
+
+        match key_type.ok_or_else(|| missing(AttributeType::KeyType))? {
+            KeyType::RSA => {
+                let n = modulus.ok_or_else(|| missing(AttributeType::Modulus))?;
+                let e = public_exponent.ok_or_else(|| missing(AttributeType::PublicExponent))?;
+                let key =
+                    rsa::RsaPublicKey::new(BigUint::from_bytes_be(n), BigUint::from_bytes_be(e))?;
+                Ok(Self::Rsa(key))
+            }
+
+            KeyType::EC => {
+                let params = ec_params.ok_or_else(|| missing(AttributeType::EcParams))?;
+                let point = ec_point.ok_or_else(|| missing(AttributeType::EcPoint))?;
+                // The point is supposed to be wrapped in a DER OCTET STRING, but some modules
+                // return it raw
+                let point = OctetStringRef::from_der(point).map_or(point, |s| s.as_bytes());
+
+                match const_oid::ObjectIdentifier::from_der(params)? {
+                    p256::NistP256::OID => {
+                        Ok(Self::EcP256(p256::PublicKey::from_sec1_bytes(point)?))
+                    }
+                    p384::NistP384::OID => {
+                        Ok(Self::EcP384(p384::PublicKey::from_sec1_bytes(point)?))
+                    }
+                    oid => Err(Pkcs11Error::UnknownEllipticCurveOid { oid }),
+                }
+            }
+
+            key_type => Err(Pkcs11Error::UnsupportedKeyType { key_type }),
+        }
+    }
+}
+
+struct Pkcs11KeyInner {
+    label: String,
+    token: Arc<Pkcs11TokenInner>,
+    handle: Mutex<ObjectHandle>,
+    public_key: PublicKey,
+}
+
+/// A signing key held in a PKCS#11 token
+///
+/// This is cheap to clone, as the key is held in an [`Arc`].
+#[derive(Clone)]
+pub struct Pkcs11Key {
+    inner: Arc<Pkcs11KeyInner>,
+}
+
+impl fmt::Debug for Pkcs11Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Pkcs11Key")
+            .field("label", &self.inner.label)
+            .field("public_key", &self.inner.public_key)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Pkcs11Key {
+    /// The label of the key in the token
+    #[must_use]
+    pub fn label(&self) -> &str {
+        &self.inner.label
+    }
+
+    /// Sign the message with the given mechanism, returning the signature
+    /// encoded as it should appear in a JWS
+    ///
+    /// This does blocking calls to the PKCS#11 module. If the session was
+    /// closed, it is reopened and the operation is retried once.
+    fn sign(&self, mechanism: SigningMechanism, msg: &[u8]) -> Result<Vec<u8>, Pkcs11Error> {
+        // The token only does raw ECDSA, so the message has to be hashed
+        // beforehand. It then returns the signature as the concatenation of r
+        // and s, which is what JWS expects.
+        let (mechanism, data) = match mechanism {
+            SigningMechanism::Sha256RsaPkcs => (Mechanism::Sha256RsaPkcs, msg.to_vec()),
+            SigningMechanism::Sha384RsaPkcs => (Mechanism::Sha384RsaPkcs, msg.to_vec()),
+            SigningMechanism::Sha512RsaPkcs => (Mechanism::Sha512RsaPkcs, msg.to_vec()),
+            SigningMechanism::EcdsaSha256 => (Mechanism::Ecdsa, Sha256::digest(msg).to_vec()),
+            SigningMechanism::EcdsaSha384 => (Mechanism::Ecdsa, Sha384::digest(msg).to_vec()),
+        };
+
+        let token = &self.inner.token;
+        let mut session = token
+            .session
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        let mut handle = self
+            .inner
+            .handle
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+
+        match session.sign(&mechanism, *handle, &data) {
+            Ok(signature) => return Ok(signature),
+            Err(e) if is_session_error(&e) => token.reopen(&mut session)?,
+            // Handles might change when another key reopened the session
+            Err(e) if is_handle_error(&e) => {}
+            Err(e) => return Err(e.into()),
+        }
+
+        *handle = find_private_key(&session, &self.inner.label)?;
+        Ok(session.sign(&mechanism, *handle, &data)?)
+    }
+
+    pub(crate) fn public_parameters(&self) -> JsonWebKeyPublicParameters {
+        match &self.inner.public_key {
+            PublicKey::Rsa(key) => key.clone().into(),
+            PublicKey::EcP256(key) => (*key).into(),
+            PublicKey::EcP384(key) => (*key).into(),
+        }
+    }
+
+    pub(crate) fn kty(&self) -> JsonWebKeyType {
+        match &self.inner.public_key {
+            PublicKey::Rsa(_) => JsonWebKeyType::Rsa,
+            PublicKey::EcP256(_) | PublicKey::EcP384(_) => JsonWebKeyType::Ec,
+        }
+    }
+
+    pub(crate) fn possible_algs(&self) -> &'static [JsonWebSignatureAlg] {
+        match &self.inner.public_key {
+            PublicKey::Rsa(_) => &[
+                JsonWebSignatureAlg::Rs256,
+                JsonWebSignatureAlg::Rs384,
+                JsonWebSignatureAlg::Rs512,
+            ],
+            PublicKey::EcP256(_) => &[JsonWebSignatureAlg::Es256],
+            PublicKey::EcP384(_) => &[JsonWebSignatureAlg::Es384],
+        }
+    }
+
+    pub(crate) fn verifying_key_for_alg(
+        &self,
+        alg: &JsonWebSignatureAlg,
+    ) -> Result<AsymmetricVerifyingKey, WrongAlgorithmError> {
+        let key = match (&self.inner.public_key, alg) {
+            (PublicKey::Rsa(key), JsonWebSignatureAlg::Rs256) => {
+                AsymmetricVerifyingKey::rs256(key.clone())
+            }
+            (PublicKey::Rsa(key), JsonWebSignatureAlg::Rs384) => {
+                AsymmetricVerifyingKey::rs384(key.clone())
+            }
+            (PublicKey::Rsa(key), JsonWebSignatureAlg::Rs512) => {
+                AsymmetricVerifyingKey::rs512(key.clone())
+            }
+            (PublicKey::EcP256(key), JsonWebSignatureAlg::Es256) => {
+                AsymmetricVerifyingKey::es256(*key)
+            }
+            (PublicKey::EcP384(key), JsonWebSignatureAlg::Es384) => {
+                AsymmetricVerifyingKey::es384(*key)
+            }
+            _ => return Err(WrongAlgorithmError),
+        };
+
+        Ok(key)
+    }
+
+    pub(crate) fn signing_key_for_alg(
+        &self,
+        alg: &JsonWebSignatureAlg,
+    ) -> Result<AsymmetricSigningKey, WrongAlgorithmError> {
+        let mechanism = match (&self.inner.public_key, alg) {
+            (PublicKey::Rsa(_), JsonWebSignatureAlg::Rs256) => SigningMechanism::Sha256RsaPkcs,
+            (PublicKey::Rsa(_), JsonWebSignatureAlg::Rs384) => SigningMechanism::Sha384RsaPkcs,
+            (PublicKey::Rsa(_), JsonWebSignatureAlg::Rs512) => SigningMechanism::Sha512RsaPkcs,
+            (PublicKey::EcP256(_), JsonWebSignatureAlg::Es256) => SigningMechanism::EcdsaSha256,
+            (PublicKey::EcP384(_), JsonWebSignatureAlg::Es384) => SigningMechanism::EcdsaSha384,
+            _ => return Err(WrongAlgorithmError),
+        };
+
+        Ok(AsymmetricSigningKey::external(Arc::new(Pkcs11Signer {
+            key: self.clone(),
+            mechanism,
+        })))
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum SigningMechanism {
+    Sha256RsaPkcs,
+    Sha384RsaPkcs,
+    Sha512RsaPkcs,
+    EcdsaSha256,
+    EcdsaSha384,
+}
+
+/// A [`Pkcs11Key`] bound to a signing mechanism
+struct Pkcs11Signer {
+    key: Pkcs11Key,
+    mechanism: SigningMechanism,
+}
+
+impl ExternalSigningKey for Pkcs11Signer {
+    fn try_sign(&self, msg: &[u8]) -> Result<Vec<u8>, signature::Error> {
+        let mechanism = self.mechanism;
+        let result = match Handle::try_current() {
+            // Run the operation on the blocking thread pool, so that waiting on the
+            // token or on the session lock doesn't hold up other tasks
+            Ok(handle) if handle.runtime_flavor() == RuntimeFlavor::MultiThread => {
+                let key = self.key.clone();
+                let msg = msg.to_vec();
+                let task = handle.spawn_blocking(move || key.sign(mechanism, &msg));
+                tokio::task::block_in_place(|| handle.block_on(task))
+                    .map_err(signature::Error::from_source)?
+            }
+
+            // Other runtimes can't wait on the blocking thread pool
+            _ => self.key.sign(mechanism, msg),
+        };
+
+        result.map_err(signature::Error::from_source)
+    }
+}
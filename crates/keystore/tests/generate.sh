@@ -32,3 +32,8 @@ convert "ec-p384.sec1" "ec-p384"
 
 openssl ecparam -genkey -name secp256k1 -noout -out "${KEYS}/ec-k256.sec1.pem"
 convert "ec-k256.sec1" "ec-k256"
+
+openssl genpkey -algorithm ed25519 -out "${KEYS}/ed25519.pkcs8.pem"
+openssl asn1parse -noout -in "${KEYS}/ed25519.pkcs8.pem" -out "${KEYS}/ed25519.pkcs8.der"
+openssl pkcs8 -topk8 -passout env:PASSWORD -in "${KEYS}/ed25519.pkcs8.pem" -out "${KEYS}/ed25519.pkcs8.encrypted.pem"
+openssl asn1parse -noout -in "${KEYS}/ed25519.pkcs8.encrypted.pem" -out "${KEYS}/ed25519.pkcs8.encrypted.der"
@@ -7,6 +7,7 @@
 use der::pem::LineEnding;
 use mas_iana::jose::JsonWebSignatureAlg;
 use mas_jose::{
+    constraints::Constrainable,
     jwk::ParametersInfo,
     jwt::{JsonWebSignatureHeader, Jwt},
 };
@@ -109,6 +110,8 @@ plain_test!(plain_ec_k256_sec1_pem, EcK256, "ec-k256.sec1.pem");
 plain_test!(plain_ec_k256_sec1_der, EcK256, "ec-k256.sec1.der");
 plain_test!(plain_ec_k256_pkcs8_pem, EcK256, "ec-k256.pkcs8.pem");
 plain_test!(plain_ec_k256_pkcs8_der, EcK256, "ec-k256.pkcs8.der");
+plain_test!(plain_ed25519_pkcs8_pem, Ed25519, "ed25519.pkcs8.pem");
+plain_test!(plain_ed25519_pkcs8_der, Ed25519, "ed25519.pkcs8.der");
 
 enc_test!(enc_rsa_pkcs8_pem, Rsa, "rsa.pkcs8.encrypted.pem");
 enc_test!(enc_rsa_pkcs8_der, Rsa, "rsa.pkcs8.encrypted.der");
@@ -118,6 +121,16 @@ enc_test!(enc_ec_p384_pkcs8_pem, EcP384, "ec-p384.pkcs8.encrypted.pem");
 enc_test!(enc_ec_p384_pkcs8_der, EcP384, "ec-p384.pkcs8.encrypted.der");
 enc_test!(enc_ec_k256_pkcs8_pem, EcK256, "ec-k256.pkcs8.encrypted.pem");
 enc_test!(enc_ec_k256_pkcs8_der, EcK256, "ec-k256.pkcs8.encrypted.der");
+enc_test!(
+    enc_ed25519_pkcs8_pem,
+    Ed25519,
+    "ed25519.pkcs8.encrypted.pem"
+);
+enc_test!(
+    enc_ed25519_pkcs8_der,
+    Ed25519,
+    "ed25519.pkcs8.encrypted.der"
+);
 
 // Test PEM/DER serialization
 pem_test!(serialize_rsa_pkcs1_pem, "rsa.pkcs1");
@@ -128,6 +141,8 @@ pem_test!(serialize_ec_p384_sec1_pem, "ec-p384.sec1");
 der_test!(serialize_ec_p384_sec1_der, "ec-p384.sec1");
 pem_test!(serialize_ec_k256_sec1_pem, "ec-k256.sec1");
 der_test!(serialize_ec_k256_sec1_der, "ec-k256.sec1");
+pem_test!(serialize_ed25519_pkcs8_pem, "ed25519.pkcs8");
+der_test!(serialize_ed25519_pkcs8_der, "ed25519.pkcs8");
 
 #[test]
 fn load_encrypted_as_unencrypted_error() {
@@ -208,3 +223,26 @@ fn generate_sign_and_verify() {
         token.verify_with_jwks(&jwks).unwrap();
     }
 }
+
+#[test]
+fn verification_keys_are_published_but_not_used() {
+    let mut rng = rand_chacha::ChaChaRng::seed_from_u64(42);
+    let signing = PrivateKey::generate_ec_p256(&mut rng);
+    let verification = PrivateKey::generate_ec_p256(&mut rng);
+
+    let keyset = Keystore::with_verification_keys(
+        JsonWebKeySet::new(vec![JsonWebKey::new(signing).with_kid("signing")]),
+        JsonWebKeySet::new(vec![JsonWebKey::new(verification).with_kid("verification")]),
+    );
+
+    // Both keys are published
+    let jwks = keyset.public_jwks();
+    let kids: Vec<_> = jwks.iter().filter_map(|key| key.kid()).collect();
+    assert_eq!(kids, vec!["signing", "verification"]);
+
+    // But only the signing key is used for signing
+    let key = keyset
+        .signing_key_for_algorithm(&JsonWebSignatureAlg::Es256)
+        .unwrap();
+    assert_eq!(key.kid(), Some("signing"));
+}
@@ -0,0 +1,163 @@
+// Copyright 2026 Element Creations Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+// Please see LICENSE files in the repository root for full details.
+
+//! Tests for keys held in a PKCS#11 token.
+//!
+//! Those need a `SoftHSMv2` token, and are ignored by default. To run them:
+//!
+//! ```sh
+//! softhsm2-util --init-token --free --label mas-test --so-pin 1234 --pin 1234
+//! cargo test -p mas-keystore --test pkcs11 -- --ignored
+//! ```
+//!
+//! The module path, token label and PIN can be overridden with the
+//! `MAS_TEST_PKCS11_MODULE`, `MAS_TEST_PKCS11_TOKEN` and `MAS_TEST_PKCS11_PIN`
+//! environment variables.
+
+use camino::Utf8Path;
+use cryptoki::{
+    context::{CInitializeArgs, Pkcs11},
+    mechanism::Mechanism,
+    object::Attribute,
+    session::{Session, UserType},
+    types::AuthPin,
+};
+use der::Encode;
+use mas_iana::jose::JsonWebSignatureAlg;
+use mas_jose::{
+    constraints::Constrainable,
+    jwk::{JsonWebKeyPublicParameters, ParametersInfo},
+    jwt::{JsonWebSignatureHeader, Jwt},
+};
+use mas_keystore::{JsonWebKey, JsonWebKeySet, Keystore, Pkcs11Token, PrivateKey};
+use pkcs8::AssociatedOid;
+
+fn env_or(name: &str, default: &str) -> String {
+    std::env::var(name).unwrap_or_else(|_| default.to_owned())
+}
+
+/// Generate a session key pair with the given label. Session objects go away
+/// with the session, so the token doesn't get polluted.
+fn generate_key_pair(session: &Session, label: &str, mechanism: &Mechanism, public: &[Attribute]) {
+    let label = Attribute::Label(label.as_bytes().to_vec());
+    let mut public = public.to_vec();
+    public.extend([
+        Attribute::Token(false),
+        Attribute::Verify(true),
+        label.clone(),
+    ]);
+    let private = [
+        Attribute::Token(false),
+        Attribute::Private(true),
+        Attribute::Sensitive(true),
+        Attribute::Sign(true),
+        label,
+    ];
+
+    session
+        .generate_key_pair(mechanism, &public, &private)
+        .unwrap();
+}
+
+#[test]
+#[ignore = "requires a SoftHSMv2 token, see the module documentation"]
+fn test_pkcs11_signing() {
+    let module = env_or("MAS_TEST_PKCS11_MODULE", "/usr/lib/softhsm/libsofthsm2.so");
+    let token_label = env_or("MAS_TEST_PKCS11_TOKEN", "mas-test");
+    let pin = env_or("MAS_TEST_PKCS11_PIN", "1234");
+
+    // Provision the keys with a separate context, which needs to be kept
+    // around for the session keys to stay alive
+    let pkcs11 = Pkcs11::new(&module).unwrap();
+    pkcs11.initialize(CInitializeArgs::OsThreads).unwrap();
+    let slot = pkcs11
+        .get_slots_with_token()
+        .unwrap()
+        .into_iter()
+        .find(|slot| pkcs11.get_token_info(*slot).unwrap().label() == token_label)
+        .expect("token not found");
+    let session = pkcs11.open_rw_session(slot).unwrap();
+    session
+        .login(UserType::User, Some(&AuthPin::new(pin.clone())))
+        .unwrap();
+
+    generate_key_pair(
+        &session,
+        "test-rsa",
+        &Mechanism::RsaPkcsKeyPairGen,
+        &[
+            Attribute::ModulusBits(2048.into()),
+            Attribute::PublicExponent(vec![0x01, 0x00, 0x01]),
+        ],
+    );
+    generate_key_pair(
+        &session,
+        "test-ec-p256",
+        &Mechanism::EccKeyPairGen,
+        &[Attribute::EcParams(p256::NistP256::OID.to_der().unwrap())],
+    );
+    generate_key_pair(
+        &session,
+        "test-ec-p384",
+        &Mechanism::EccKeyPairGen,
+        &[Attribute::EcParams(p384::NistP384::OID.to_der().unwrap())],
+    );
+
+    let token = Pkcs11Token::open(Utf8Path::new(&module), &token_label, &pin).unwrap();
+
+    // Unknown keys are reported as such
+    assert!(matches!(
+        token.load_key("unknown"),
+        Err(mas_keystore::Pkcs11Error::KeyNotFound { .. })
+    ));
+
+    let keys: Vec<_> = ["test-rsa", "test-ec-p256", "test-ec-p384"]
+        .into_iter()
+        .map(|label| {
+            let key = PrivateKey::Pkcs11(token.load_key(label).unwrap());
+            JsonWebKey::new(key).with_kid(label)
+        })
+        .collect();
+
+    // Private keys can't be exported out of the token
+    assert!(keys[0].params().to_pem(der::pem::LineEnding::LF).is_err());
+    assert!(keys[1].params().to_pkcs8_der().is_err());
+
+    let keystore = Keystore::new(JsonWebKeySet::new(keys));
+
+    // The public keys are exported in the JWKS
+    let jwks = keystore.public_jwks();
+    assert_eq!(jwks.len(), 3);
+    let rsa = jwks
+        .iter()
+        .find(|key| key.kid() == Some("test-rsa"))
+        .unwrap();
+    assert!(matches!(rsa.params(), JsonWebKeyPublicParameters::Rsa(_)));
+
+    // Each key can sign JWTs with all its algorithms, which can be verified
+    // with the public key
+    let mut tested = Vec::new();
+    for key in keystore.iter() {
+        for alg in key.params().possible_algs() {
+            let header = JsonWebSignatureHeader::new(alg.clone());
+            let signer = key.params().signing_key_for_alg(alg).unwrap();
+            let jwt = Jwt::sign(header, "hello", &signer).unwrap();
+            let verifier = key.params().verifying_key_for_alg(alg).unwrap();
+            jwt.verify(&verifier).unwrap();
+            tested.push(alg.clone());
+        }
+    }
+
+    assert_eq!(
+        tested,
+        [
+            JsonWebSignatureAlg::Rs256,
+            JsonWebSignatureAlg::Rs384,
+            JsonWebSignatureAlg::Rs512,
+            JsonWebSignatureAlg::Es256,
+            JsonWebSignatureAlg::Es384,
+        ]
+    );
+}
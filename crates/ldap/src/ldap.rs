@@ -0,0 +1,381 @@
+// Copyright 2026 Element Creations Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+// Please see LICENSE files in the repository root for full details.
+
+use std::time::Duration;
+
+use anyhow::{Context, bail};
+use async_trait::async_trait;
+use ldap3::{Ldap, LdapConnAsync, LdapConnSettings, Scope, SearchEntry, dn_escape, ldap_escape};
+
+use crate::DirectoryUser;
+
+/// The result code returned by the server when a bind fails because of wrong
+/// credentials, or because the entry does not exist
+const INVALID_CREDENTIALS: u32 = 49;
+
+/// The placeholder replaced by the username in templates
+const USERNAME_PLACEHOLDER: &str = "{username}";
+
+/// How to find the entry of a user and check their password
+#[derive(Debug, Clone)]
+pub enum BindMethod {
+    /// Bind directly as the user, with a DN built from a template
+    Direct {
+        /// The template of the DN, in which `{username}` is replaced by the
+        /// escaped username
+        dn_template: String,
+    },
+
+    /// Search the entry of the user first, then bind as the user with the DN
+    /// of the entry found
+    SearchAndBind {
+        /// The DN under which to search for users
+        base_dn: String,
+
+        /// The search filter, in which `{username}` is replaced by the escaped
+        /// username
+        filter: String,
+
+        /// The DN to bind as to perform the search, if anonymous searches are
+        /// not allowed
+        bind_dn: Option<String>,
+
+        /// The password of `bind_dn`
+        bind_password: Option<String>,
+    },
+}
+
+/// Which attributes of the entry are mapped to the user profile
+#[derive(Debug, Clone)]
+pub struct AttributeMapping {
+    /// The attribute holding the localpart, like `uid` or `sAMAccountName`
+    pub localpart: String,
+
+    /// The attribute holding the display name, like `cn` or `displayName`
+    pub display_name: Option<String>,
+
+    /// The attribute holding the email address, like `mail`
+    pub email: Option<String>,
+}
+
+impl AttributeMapping {
+    fn names(&self) -> Vec<&str> {
+        std::iter::once(self.localpart.as_str())
+            .chain(self.display_name.as_deref())
+            .chain(self.email.as_deref())
+            .collect()
+    }
+
+    fn map(&self, entry: &SearchEntry) -> Result<DirectoryUser, anyhow::Error> {
+        let localpart = first_value(entry, &self.localpart)
+            .with_context(|| format!("Entry has no {:?} attribute", self.localpart))?
+            // Matrix localparts are lowercase, when directories usually
+            // compare usernames case-insensitively
+            .to_lowercase();
+
+        let display_name = self
+            .display_name
+            .as_deref()
+            .and_then(|name| first_value(entry, name))
+            .map(ToOwned::to_owned);
+
+        let email = self
+            .email
+            .as_deref()
+            .and_then(|name| first_value(entry, name))
+            .map(ToOwned::to_owned);
+
+        Ok(DirectoryUser {
+            dn: entry.dn.clone(),
+            localpart,
+            display_name,
+            email,
+        })
+    }
+}
+
+/// Get the first value of an attribute, ignoring the case of its name
+fn first_value<'a>(entry: &'a SearchEntry, name: &str) -> Option<&'a str> {
+    entry
+        .attrs
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+        .and_then(|(_, values)| values.first())
+        .map(String::as_str)
+}
+
+/// A [`crate::Directory`] backed by an LDAP server
+#[derive(Debug, Clone)]
+pub struct LdapDirectory {
+    url: String,
+    starttls: bool,
+    timeout: Duration,
+    bind_method: BindMethod,
+    attributes: AttributeMapping,
+}
+
+impl LdapDirectory {
+    /// Create a new LDAP directory
+    ///
+    /// # Parameters
+    ///
+    /// * `url` - The URL of the server, with the `ldap` or `ldaps` scheme.
+    /// * `bind_method` - How to find the entry of a user.
+    /// * `attributes` - Which attributes are mapped to the user profile.
+    #[must_use]
+    pub fn new(
+        url: impl Into<String>,
+        bind_method: BindMethod,
+        attributes: AttributeMapping,
+    ) -> Self {
+        Self {
+            url: url.into(),
+            starttls: false,
+            timeout: Duration::from_secs(10),
+            bind_method,
+            attributes,
+        }
+    }
+
+    /// Upgrade plain connections to TLS with the `StartTLS` operation
+    #[must_use]
+    pub fn with_starttls(mut self, starttls: bool) -> Self {
+        self.starttls = starttls;
+        self
+    }
+
+    /// Set the timeout of connections and of each operation
+    #[must_use]
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    async fn connect(&self) -> Result<Ldap, anyhow::Error> {
+        let settings = LdapConnSettings::new()
+            .set_conn_timeout(self.timeout)
+            .set_starttls(self.starttls);
+
+        let (conn, ldap) = LdapConnAsync::with_settings(settings, &self.url)
+            .await
+            .context("Failed to connect to the LDAP server")?;
+
+        tokio::spawn(async move {
+            if let Err(e) = conn.drive().await {
+                tracing::warn!(
+                    error = &e as &dyn std::error::Error,
+                    "LDAP connection failed"
+                );
+            }
+        });
+
+        Ok(ldap)
+    }
+
+    async fn authenticate_on(
+        &self,
+        ldap: &mut Ldap,
+        username: &str,
+        password: &str,
+    ) -> Result<Option<DirectoryUser>, anyhow::Error> {
+        let attributes = self.attributes.names();
+
+        let (dn, entry) = match &self.bind_method {
+            BindMethod::Direct { dn_template } => {
+                let dn = dn_template.replace(USERNAME_PLACEHOLDER, &dn_escape(username));
+                (dn, None)
+            }
+
+            BindMethod::SearchAndBind {
+                base_dn,
+                filter,
+                bind_dn,
+                bind_password,
+            } => {
+                if let Some(bind_dn) = bind_dn {
+                    ldap.with_timeout(self.timeout)
+                        .simple_bind(bind_dn, bind_password.as_deref().unwrap_or_default())
+                        .await?
+                        .success()
+                        .context("Failed to bind with the search account")?;
+                }
+
+                let filter = filter.replace(USERNAME_PLACEHOLDER, &ldap_escape(username));
+                let (entries, _result) = ldap
+                    .with_timeout(self.timeout)
+                    .search(base_dn, Scope::Subtree, &filter, &attributes)
+                    .await?
+                    .success()
+                    .context("Failed to search for the user")?;
+
+                let mut entries = entries.into_iter();
+                let Some(entry) = entries.next() else {
+                    tracing::info!(username, "User not found in the directory");
+                    return Ok(None);
+                };
+
+                if entries.next().is_some() {
+                    bail!("The search filter matched more than one entry");
+                }
+
+                let entry = SearchEntry::construct(entry);
+                (entry.dn.clone(), Some(entry))
+            }
+        };
+
+        let result = ldap
+            .with_timeout(self.timeout)
+            .simple_bind(&dn, password)
+            .await?;
+
+        if result.rc == INVALID_CREDENTIALS {
+            return Ok(None);
+        }
+
+        result.success().context("Failed to bind as the user")?;
+
+        let entry = if let Some(entry) = entry {
+            entry
+        } else {
+            // With a direct bind, we only read the entry once bound as the user
+            let (entries, _result) = ldap
+                .with_timeout(self.timeout)
+                .search(&dn, Scope::Base, "(objectClass=*)", &attributes)
+                .await?
+                .success()
+                .context("Failed to read the user entry")?;
+
+            let entry = entries
+                .into_iter()
+                .next()
+                .context("The user entry could not be read")?;
+            SearchEntry::construct(entry)
+        };
+
+        Ok(Some(self.attributes.map(&entry)?))
+    }
+}
+
+#[async_trait]
+impl crate::Directory for LdapDirectory {
+    async fn authenticate(
+        &self,
+        username: &str,
+        password: &str,
+    ) -> Result<Option<DirectoryUser>, anyhow::Error> {
+        // Binding with an empty password is an unauthenticated bind, which
+        // succeeds on most servers, so those must never be sent
+        if username.is_empty() || password.is_empty() {
+            return Ok(None);
+        }
+
+        let mut ldap = self.connect().await?;
+        let result = self.authenticate_on(&mut ldap, username, password).await;
+
+        if let Err(e) = ldap.unbind().await {
+            tracing::debug!(
+                error = &e as &dyn std::error::Error,
+                "Failed to unbind from the LDAP server"
+            );
+        }
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn entry(attrs: &[(&str, &[&str])]) -> SearchEntry {
+        SearchEntry {
+            dn: "uid=alice,ou=people,dc=example,dc=com".to_owned(),
+            attrs: attrs
+                .iter()
+                .map(|(name, values)| {
+                    (
+                        (*name).to_owned(),
+                        values.iter().map(|v| (*v).to_owned()).collect(),
+                    )
+                })
+                .collect(),
+            bin_attrs: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_attribute_mapping() {
+        let mapping = AttributeMapping {
+            localpart: "uid".to_owned(),
+            display_name: Some("displayName".to_owned()),
+            email: Some("mail".to_owned()),
+        };
+        assert_eq!(mapping.names(), ["uid", "displayName", "mail"]);
+
+        // Attribute names are case-insensitive
+        let user = mapping
+            .map(&entry(&[
+                ("UID", &["Alice"]),
+                ("displayname", &["Alice Liddell"]),
+                ("mail", &["alice@example.com", "alice@example.org"]),
+            ]))
+            .unwrap();
+        assert_eq!(
+            user,
+            DirectoryUser {
+                dn: "uid=alice,ou=people,dc=example,dc=com".to_owned(),
+                localpart: "alice".to_owned(),
+                display_name: Some("Alice Liddell".to_owned()),
+                email: Some("alice@example.com".to_owned()),
+            }
+        );
+
+        // Optional attributes can be missing
+        let user = mapping.map(&entry(&[("uid", &["alice"])])).unwrap();
+        assert_eq!(user.display_name, None);
+        assert_eq!(user.email, None);
+
+        // But the localpart can't
+        assert!(mapping.map(&entry(&[("mail", &["a@b.c"])])).is_err());
+    }
+
+    #[test]
+    fn test_templates_are_escaped() {
+        let filter = "(&(objectClass=person)(uid={username}))";
+        assert_eq!(
+            filter.replace(USERNAME_PLACEHOLDER, &ldap_escape("*)(uid=admin")),
+            r"(&(objectClass=person)(uid=\2a\29\28uid=admin))"
+        );
+
+        let dn_template = "uid={username},ou=people,dc=example,dc=com";
+        assert_eq!(
+            dn_template.replace(USERNAME_PLACEHOLDER, &dn_escape("alice,ou=admins")),
+            r"uid=alice\2cou=admins,ou=people,dc=example,dc=com"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_empty_password_is_rejected() {
+        use crate::Directory as _;
+
+        // This would fail to connect if it tried to
+        let directory = LdapDirectory::new(
+            "ldap://127.0.0.1:1",
+            BindMethod::Direct {
+                dn_template: "uid={username},dc=example,dc=com".to_owned(),
+            },
+            AttributeMapping {
+                localpart: "uid".to_owned(),
+                display_name: None,
+                email: None,
+            },
+        );
+
+        assert_eq!(directory.authenticate("alice", "").await.unwrap(), None);
+        assert_eq!(directory.authenticate("", "hunter2").await.unwrap(), None);
+    }
+}
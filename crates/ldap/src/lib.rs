@@ -0,0 +1,54 @@
+// Copyright 2026 Element Creations Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+// Please see LICENSE files in the repository root for full details.
+
+//! Password authentication against an LDAP directory, like `OpenLDAP` or
+//! Active Directory.
+
+mod ldap;
+mod mock;
+
+pub use self::{
+    ldap::{AttributeMapping, BindMethod, LdapDirectory},
+    mock::Directory as MockDirectory,
+};
+
+/// A user entry of the directory, returned after a successful authentication
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DirectoryUser {
+    /// The distinguished name of the entry
+    pub dn: String,
+
+    /// The localpart of the user, as mapped from the directory attributes
+    pub localpart: String,
+
+    /// The display name of the user, if mapped and present
+    pub display_name: Option<String>,
+
+    /// The email address of the user, if mapped and present
+    pub email: Option<String>,
+}
+
+#[async_trait::async_trait]
+pub trait Directory: Send + Sync {
+    /// Check the credentials of a user against the directory.
+    ///
+    /// Returns [`None`] if the user does not exist or if the password is
+    /// wrong, without telling those cases apart.
+    ///
+    /// # Parameters
+    ///
+    /// * `username` - The username typed by the user.
+    /// * `password` - The password typed by the user.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the directory could not be reached, or if it
+    /// returned an unexpected response.
+    async fn authenticate(
+        &self,
+        username: &str,
+        password: &str,
+    ) -> Result<Option<DirectoryUser>, anyhow::Error>;
+}
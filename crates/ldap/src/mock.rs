@@ -0,0 +1,102 @@
+// Copyright 2026 Element Creations Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+// Please see LICENSE files in the repository root for full details.
+
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use tokio::sync::RwLock;
+
+use crate::DirectoryUser;
+
+/// A mock implementation of a [`crate::Directory`], which keeps users in
+/// memory
+#[derive(Default)]
+pub struct Directory {
+    users: RwLock<HashMap<String, (String, DirectoryUser)>>,
+    unavailable: RwLock<bool>,
+}
+
+impl Directory {
+    /// Create a new, empty, mock directory
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a user to the directory, who can authenticate with the given
+    /// username and password
+    pub async fn add_user(
+        &self,
+        username: impl Into<String>,
+        password: impl Into<String>,
+        user: DirectoryUser,
+    ) {
+        self.users
+            .write()
+            .await
+            .insert(username.into(), (password.into(), user));
+    }
+
+    /// Make the directory fail all the authentication attempts, as if the
+    /// server was unreachable
+    pub async fn set_unavailable(&self, unavailable: bool) {
+        *self.unavailable.write().await = unavailable;
+    }
+}
+
+#[async_trait]
+impl crate::Directory for Directory {
+    async fn authenticate(
+        &self,
+        username: &str,
+        password: &str,
+    ) -> Result<Option<DirectoryUser>, anyhow::Error> {
+        if *self.unavailable.read().await {
+            anyhow::bail!("The directory is unavailable");
+        }
+
+        let users = self.users.read().await;
+        let user = users
+            .get(username)
+            .filter(|(expected, _)| !password.is_empty() && expected == password)
+            .map(|(_, user)| user.clone());
+
+        Ok(user)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Directory as _;
+
+    #[tokio::test]
+    async fn test_mock_directory() {
+        let directory = Directory::new();
+        let user = DirectoryUser {
+            dn: "uid=alice,dc=example,dc=com".to_owned(),
+            localpart: "alice".to_owned(),
+            display_name: None,
+            email: None,
+        };
+        directory.add_user("alice", "hunter2", user.clone()).await;
+
+        assert_eq!(
+            directory.authenticate("alice", "hunter2").await.unwrap(),
+            Some(user)
+        );
+        assert_eq!(
+            directory.authenticate("alice", "wrong").await.unwrap(),
+            None
+        );
+        assert_eq!(
+            directory.authenticate("bob", "hunter2").await.unwrap(),
+            None
+        );
+
+        directory.set_unavailable(true).await;
+        assert!(directory.authenticate("alice", "hunter2").await.is_err());
+    }
+}
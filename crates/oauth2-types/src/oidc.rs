@@ -475,6 +475,13 @@ pub struct ProviderMetadata {
     /// [`OAuthClientAuthenticationMethod::ClientSecretJwt`].
     pub introspection_endpoint_auth_signing_alg_values_supported: Option<Vec<JsonWebSignatureAlg>>,
 
+    /// JSON array containing a list of the JWS signing algorithms supported by
+    /// the introspection endpoint to sign the response, as defined in [RFC
+    /// 9701].
+    ///
+    /// [RFC 9701]: https://www.rfc-editor.org/rfc/rfc9701
+    pub introspection_signing_alg_values_supported: Option<Vec<JsonWebSignatureAlg>>,
+
     /// [PKCE code challenge methods] supported by this authorization server.
     /// If omitted, the authorization server does not support PKCE.
     ///
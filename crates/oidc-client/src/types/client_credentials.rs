@@ -35,6 +35,8 @@ pub const CLIENT_SUPPORTED_AUTH_METHODS: &[OAuthClientAuthenticationMethod] = &[
     OAuthClientAuthenticationMethod::ClientSecretPost,
     OAuthClientAuthenticationMethod::ClientSecretJwt,
     OAuthClientAuthenticationMethod::PrivateKeyJwt,
+    OAuthClientAuthenticationMethod::TlsClientAuth,
+    OAuthClientAuthenticationMethod::SelfSignedTlsClientAuth,
 ];
 
 /// The credentials obtained during registration, to authenticate a client on
@@ -98,6 +100,31 @@ pub enum ClientCredentials {
         token_endpoint: Url,
     },
 
+    /// The client authenticates with a TLS client certificate issued by a
+    /// certificate authority trusted by the server, as defined in [RFC 8705].
+    ///
+    /// Only the client ID is sent with the request: the HTTP client used to
+    /// make the request must be configured to present the certificate, for
+    /// example with `mas_http::reqwest_client_with_certificate`.
+    ///
+    /// [RFC 8705]: https://www.rfc-editor.org/rfc/rfc8705.html
+    TlsClientAuth {
+        /// The unique ID for the client.
+        client_id: String,
+    },
+
+    /// The client authenticates with a self-signed TLS client certificate,
+    /// registered with the server, as defined in [RFC 8705].
+    ///
+    /// Only the client ID is sent with the request: the HTTP client used to
+    /// make the request must be configured to present the certificate.
+    ///
+    /// [RFC 8705]: https://www.rfc-editor.org/rfc/rfc8705.html
+    SelfSignedTlsClientAuth {
+        /// The unique ID for the client.
+        client_id: String,
+    },
+
     /// The client authenticates like Sign in with Apple wants
     SignInWithApple {
         /// The unique ID for the client.
@@ -124,6 +151,8 @@ impl ClientCredentials {
             | ClientCredentials::ClientSecretPost { client_id, .. }
             | ClientCredentials::ClientSecretJwt { client_id, .. }
             | ClientCredentials::PrivateKeyJwt { client_id, .. }
+            | ClientCredentials::TlsClientAuth { client_id }
+            | ClientCredentials::SelfSignedTlsClientAuth { client_id }
             | ClientCredentials::SignInWithApple { client_id, .. } => client_id,
         }
     }
@@ -138,13 +167,19 @@ impl ClientCredentials {
         rng: &mut impl Rng,
     ) -> Result<reqwest::RequestBuilder, CredentialsError> {
         let request = match self {
-            ClientCredentials::None { client_id } => request.form(&RequestWithClientCredentials {
-                body: form,
-                client_id: Some(client_id),
-                client_secret: None,
-                client_assertion: None,
-                client_assertion_type: None,
-            }),
+            // With mutual TLS, the client is authenticated at the transport level, so
+            // only the client ID is sent in the body
+            ClientCredentials::None { client_id }
+            | ClientCredentials::TlsClientAuth { client_id }
+            | ClientCredentials::SelfSignedTlsClientAuth { client_id } => {
+                request.form(&RequestWithClientCredentials {
+                    body: form,
+                    client_id: Some(client_id),
+                    client_secret: None,
+                    client_assertion: None,
+                    client_assertion_type: None,
+                })
+            }
 
             ClientCredentials::ClientSecretBasic {
                 client_id,
@@ -278,6 +313,14 @@ impl fmt::Debug for ClientCredentials {
                 .debug_struct("None")
                 .field("client_id", client_id)
                 .finish(),
+            Self::TlsClientAuth { client_id } => f
+                .debug_struct("TlsClientAuth")
+                .field("client_id", client_id)
+                .finish(),
+            Self::SelfSignedTlsClientAuth { client_id } => f
+                .debug_struct("SelfSignedTlsClientAuth")
+                .field("client_id", client_id)
+                .finish(),
             Self::ClientSecretBasic { client_id, .. } => f
                 .debug_struct("ClientSecretBasic")
                 .field("client_id", client_id)
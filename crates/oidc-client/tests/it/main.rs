@@ -65,6 +65,7 @@ fn keystore(alg: &JsonWebSignatureAlg) -> Keystore {
         | JsonWebSignatureAlg::Ps512 => PrivateKey::generate_rsa(&mut rng).unwrap(),
         JsonWebSignatureAlg::Es256 => PrivateKey::generate_ec_p256(&mut rng),
         JsonWebSignatureAlg::Es384 => PrivateKey::generate_ec_p384(&mut rng),
+        JsonWebSignatureAlg::EdDsa => PrivateKey::generate_ed25519(&mut rng),
         _ => unimplemented!(),
     };
 
@@ -148,6 +149,9 @@ fn client_credentials(
                 token_endpoint: issuer.join("token").unwrap(),
             }
         }
+        OAuthClientAuthenticationMethod::TlsClientAuth => ClientCredentials::TlsClientAuth {
+            client_id: CLIENT_ID.to_owned(),
+        },
         _ => unimplemented!(),
     }
 }
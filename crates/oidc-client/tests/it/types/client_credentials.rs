@@ -8,7 +8,10 @@ use std::collections::HashMap;
 
 use base64ct::Encoding;
 use http::header::AUTHORIZATION;
-use mas_iana::oauth::{OAuthAccessTokenType, OAuthClientAuthenticationMethod};
+use mas_iana::{
+    jose::JsonWebSignatureAlg,
+    oauth::{OAuthAccessTokenType, OAuthClientAuthenticationMethod},
+};
 use mas_jose::{
     claims::{self, TimeOptions},
     jwt::Jwt,
@@ -25,7 +28,7 @@ use wiremock::{
     matchers::{header, method, path},
 };
 
-use crate::{ACCESS_TOKEN, CLIENT_ID, CLIENT_SECRET, client_credentials, init_test, now};
+use crate::{ACCESS_TOKEN, CLIENT_ID, CLIENT_SECRET, client_credentials, init_test, keystore, now};
 
 #[tokio::test]
 async fn pass_none() {
@@ -250,9 +253,22 @@ async fn pass_client_secret_jwt() {
 
 #[tokio::test]
 async fn pass_private_key_jwt() {
+    private_key_jwt(JsonWebSignatureAlg::Es256).await;
+}
+
+#[tokio::test]
+async fn pass_private_key_jwt_eddsa() {
+    private_key_jwt(JsonWebSignatureAlg::EdDsa).await;
+}
+
+async fn private_key_jwt(signing_algorithm: JsonWebSignatureAlg) {
     let (http_client, mock_server, issuer) = init_test().await;
-    let client_credentials =
-        client_credentials(&OAuthClientAuthenticationMethod::PrivateKeyJwt, &issuer);
+    let client_credentials = ClientCredentials::PrivateKeyJwt {
+        client_id: CLIENT_ID.to_owned(),
+        keystore: keystore(&signing_algorithm),
+        signing_algorithm,
+        token_endpoint: issuer.join("token").unwrap(),
+    };
     let token_endpoint = issuer.join("token").unwrap();
     let endpoint = token_endpoint.to_string();
     let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(42);
@@ -324,6 +340,65 @@ async fn pass_private_key_jwt() {
     .unwrap();
 }
 
+#[tokio::test]
+async fn pass_tls_client_auth() {
+    let (http_client, mock_server, issuer) = init_test().await;
+    let client_credentials =
+        client_credentials(&OAuthClientAuthenticationMethod::TlsClientAuth, &issuer);
+    let token_endpoint = issuer.join("token").unwrap();
+    let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(42);
+
+    Mock::given(method("POST"))
+        .and(path("/token"))
+        .and(|req: &Request| {
+            let query_pairs = form_urlencoded::parse(&req.body).collect::<HashMap<_, _>>();
+
+            if req.headers.contains_key(AUTHORIZATION) {
+                println!("`tls_client_auth` client authentication should not use the Authorization header");
+                return false;
+            }
+            if query_pairs
+                .get("client_id")
+                .filter(|s| *s == CLIENT_ID)
+                .is_none()
+            {
+                println!("Wrong or missing client ID");
+                return false;
+            }
+            if query_pairs.contains_key("client_secret")
+                || query_pairs.contains_key("client_assertion")
+            {
+                println!("`tls_client_auth` client authentication should only send the client ID");
+                return false;
+            }
+
+            true
+        })
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(AccessTokenResponse {
+                access_token: ACCESS_TOKEN.to_owned(),
+                refresh_token: None,
+                id_token: None,
+                token_type: OAuthAccessTokenType::Bearer,
+                expires_in: None,
+                scope: None,
+            }),
+        )
+        .mount(&mock_server)
+        .await;
+
+    access_token_with_client_credentials(
+        &http_client,
+        client_credentials,
+        &token_endpoint,
+        None,
+        now(),
+        &mut rng,
+    )
+    .await
+    .unwrap();
+}
+
 fn verify_client_jwt(
     claims: &mut HashMap<String, Value>,
     token_endpoint: &String,
@@ -12,7 +12,8 @@
 use std::path::{Path, PathBuf};
 
 use mas_policy::model::{
-    AuthorizationGrantInput, ClientRegistrationInput, CompatLoginInput, EmailInput, RegisterInput,
+    AuthorizationGrantInput, ClientRegistrationInput, CompatLoginInput, CompatTokenAnomalyInput,
+    EmailInput, RegisterInput, UpstreamOAuthLinkInput,
 };
 use schemars::{JsonSchema, generate::SchemaSettings};
 
@@ -44,4 +45,6 @@ fn main() {
     write_schema::<AuthorizationGrantInput>(output_root, "authorization_grant_input.json");
     write_schema::<CompatLoginInput>(output_root, "compat_login_input.json");
     write_schema::<EmailInput>(output_root, "email_input.json");
+    write_schema::<UpstreamOAuthLinkInput>(output_root, "upstream_oauth_link_input.json");
+    write_schema::<CompatTokenAnomalyInput>(output_root, "compat_token_anomaly_input.json");
 }
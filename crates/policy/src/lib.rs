@@ -6,7 +6,7 @@
 
 pub mod model;
 
-use std::sync::Arc;
+use std::{collections::HashSet, sync::Arc};
 
 use arc_swap::ArcSwap;
 use mas_data_model::{SessionLimitConfig, Ulid};
@@ -20,8 +20,9 @@ use tokio::io::{AsyncRead, AsyncReadExt};
 
 pub use self::model::{
     AuthorizationGrantInput, ClientRegistrationInput, Code as ViolationCode, CompatLoginInput,
-    EmailInput, EvaluationResult, GrantType, RegisterInput, RegistrationMethod, Requester,
-    Violation,
+    CompatTokenAnomalyInput, CompatTokenAnomalySession, EmailInput, EvaluationResult, GrantType,
+    RegisterInput, RegistrationMethod, Requester, UpstreamOAuthLinkAction, UpstreamOAuthLinkInput,
+    UpstreamOAuthLinkProvider, Violation,
 };
 
 #[derive(Debug, Error)]
@@ -75,10 +76,12 @@ pub struct Entrypoints {
     pub authorization_grant: String,
     pub compat_login: String,
     pub email: String,
+    pub upstream_oauth_link: String,
+    pub compat_token_anomaly: String,
 }
 
 impl Entrypoints {
-    fn all(&self) -> [&str; 5] {
+    fn required(&self) -> [&str; 5] {
         [
             self.register.as_str(),
             self.client_registration.as_str(),
@@ -87,6 +90,15 @@ impl Entrypoints {
             self.email.as_str(),
         ]
     }
+
+    /// Entrypoints which custom policies built before they were introduced
+    /// don't have. Evaluating a missing one never results in a violation.
+    fn optional(&self) -> [&str; 2] {
+        [
+            self.upstream_oauth_link.as_str(),
+            self.compat_token_anomaly.as_str(),
+        ]
+    }
 }
 
 #[derive(Debug)]
@@ -255,11 +267,18 @@ impl PolicyFactory {
         };
 
         // Try to instantiate
-        factory
+        let policy = factory
             .instantiate()
             .await
             .map_err(LoadError::Instantiate)?;
 
+        for entrypoint in &policy.missing_entrypoints {
+            tracing::warn!(
+                %entrypoint,
+                "The policy is missing an optional entrypoint, it will never result in a violation"
+            );
+        }
+
         Ok(factory)
     }
 
@@ -327,7 +346,7 @@ impl PolicyFactory {
         // Check that we have the required entrypoints
         let policy_entrypoints = runtime.entrypoints();
 
-        for e in self.entrypoints.all() {
+        for e in self.entrypoints.required() {
             if !policy_entrypoints.contains(e) {
                 return Err(InstantiateError::MissingEntrypoint {
                     entrypoint: e.to_owned(),
@@ -335,6 +354,14 @@ impl PolicyFactory {
             }
         }
 
+        let missing_entrypoints = self
+            .entrypoints
+            .optional()
+            .into_iter()
+            .filter(|e| !policy_entrypoints.contains(*e))
+            .map(ToOwned::to_owned)
+            .collect();
+
         let instance = runtime
             .with_data(&mut store, data)
             .await
@@ -344,6 +371,7 @@ impl PolicyFactory {
             store,
             instance,
             entrypoints: self.entrypoints.clone(),
+            missing_entrypoints,
         })
     }
 }
@@ -352,6 +380,8 @@ pub struct Policy {
     store: Store<()>,
     instance: opa_wasm::Policy<opa_wasm::DefaultContext>,
     entrypoints: Entrypoints,
+    /// Optional entrypoints the policy doesn't have
+    missing_entrypoints: HashSet<String>,
 }
 
 #[derive(Debug, Error)]
@@ -486,6 +516,89 @@ impl Policy {
 
         Ok(res)
     }
+
+    /// Evaluate the `upstream_oauth_link` entrypoint.
+    ///
+    /// This entrypoint is optional, and results in no violation if the policy
+    /// doesn't have it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the policy engine fails to evaluate the entrypoint.
+    #[tracing::instrument(
+        name = "policy.evaluate.upstream_oauth_link",
+        skip_all,
+        fields(
+            %input.user.id,
+            %input.provider.id,
+            ?input.action,
+        ),
+    )]
+    pub async fn evaluate_upstream_oauth_link(
+        &mut self,
+        input: UpstreamOAuthLinkInput<'_>,
+    ) -> Result<EvaluationResult, EvaluationError> {
+        if self
+            .missing_entrypoints
+            .contains(&self.entrypoints.upstream_oauth_link)
+        {
+            return Ok(EvaluationResult {
+                violations: Vec::new(),
+            });
+        }
+
+        let [res]: [EvaluationResult; 1] = self
+            .instance
+            .evaluate(
+                &mut self.store,
+                &self.entrypoints.upstream_oauth_link,
+                &input,
+            )
+            .await?;
+
+        Ok(res)
+    }
+
+    /// Evaluate the `compat_token_anomaly` entrypoint.
+    ///
+    /// This entrypoint is optional, and results in no violation if the policy
+    /// doesn't have it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the policy engine fails to evaluate the entrypoint.
+    #[tracing::instrument(
+        name = "policy.evaluate.compat_token_anomaly",
+        skip_all,
+        fields(
+            %input.user.id,
+            %input.session.id,
+        ),
+    )]
+    pub async fn evaluate_compat_token_anomaly(
+        &mut self,
+        input: CompatTokenAnomalyInput<'_>,
+    ) -> Result<EvaluationResult, EvaluationError> {
+        if self
+            .missing_entrypoints
+            .contains(&self.entrypoints.compat_token_anomaly)
+        {
+            return Ok(EvaluationResult {
+                violations: Vec::new(),
+            });
+        }
+
+        let [res]: [EvaluationResult; 1] = self
+            .instance
+            .evaluate(
+                &mut self.store,
+                &self.entrypoints.compat_token_anomaly,
+                &input,
+            )
+            .await?;
+
+        Ok(res)
+    }
 }
 
 #[cfg(test)]
@@ -502,6 +615,8 @@ mod tests {
             authorization_grant: "authorization_grant/violation".to_owned(),
             compat_login: "compat_login/violation".to_owned(),
             email: "email/violation".to_owned(),
+            upstream_oauth_link: "upstream_oauth_link/violation".to_owned(),
+            compat_token_anomaly: "compat_token_anomaly/violation".to_owned(),
         }
     }
 
@@ -11,7 +11,7 @@
 
 use std::net::IpAddr;
 
-use mas_data_model::{Client, User};
+use mas_data_model::{Client, CompatSessionAnomalyKind, Ulid, User};
 use oauth2_types::{registration::VerifiedClientMetadata, scope::Scope};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
@@ -52,6 +52,15 @@ pub enum Code {
 
     /// The user has reached their session limit.
     TooManySessions,
+
+    /// The upstream provider is not allowed to be linked.
+    UpstreamProviderNotAllowed,
+
+    /// The link to the upstream provider is not allowed to be removed.
+    UpstreamLinkLocked,
+
+    /// The compatibility access token was used in an unusual way.
+    CompatTokenAnomaly,
 }
 
 impl Code {
@@ -70,6 +79,9 @@ impl Code {
             Self::EmailNotAllowed => "email-not-allowed",
             Self::EmailBanned => "email-banned",
             Self::TooManySessions => "too-many-sessions",
+            Self::UpstreamProviderNotAllowed => "upstream-provider-not-allowed",
+            Self::UpstreamLinkLocked => "upstream-link-locked",
+            Self::CompatTokenAnomaly => "compat-token-anomaly",
         }
     }
 }
@@ -131,6 +143,9 @@ pub enum RegistrationMethod {
 
     #[serde(rename = "upstream-oauth2")]
     UpstreamOAuth2,
+
+    #[serde(rename = "ldap")]
+    Ldap,
 }
 
 /// Input for the user registration policy.
@@ -176,6 +191,10 @@ pub struct AuthorizationGrantInput<'a> {
     /// Not populated if it's not a user logging in.
     pub session_counts: Option<SessionCounts>,
 
+    /// Attributes of the user imported from upstream identity providers.
+    /// Not populated if it's not a user logging in.
+    pub user_attributes: Option<serde_json::Map<String, serde_json::Value>>,
+
     #[schemars(with = "std::collections::HashMap<String, serde_json::Value>")]
     pub client: &'a Client,
 
@@ -197,6 +216,9 @@ pub struct CompatLoginInput<'a> {
     /// How many sessions the user has.
     pub session_counts: SessionCounts,
 
+    /// Attributes of the user imported from upstream identity providers.
+    pub user_attributes: serde_json::Map<String, serde_json::Value>,
+
     /// Whether a session will be replaced by this login
     pub session_replaced: bool,
 
@@ -241,3 +263,75 @@ pub struct EmailInput<'a> {
 
     pub requester: Requester,
 }
+
+#[derive(Serialize, Debug, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum UpstreamOAuthLinkAction {
+    /// The user is linking their account to the provider
+    Link,
+
+    /// The user is removing the link to the provider
+    Unlink,
+}
+
+/// The upstream provider in an [`UpstreamOAuthLinkInput`]
+#[derive(Serialize, Debug, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct UpstreamOAuthLinkProvider<'a> {
+    /// The ID of the provider, as set in the configuration
+    #[schemars(with = "String")]
+    pub id: Ulid,
+
+    /// The issuer of the provider, if known
+    pub issuer: Option<&'a str>,
+}
+
+/// Input for the upstream OAuth 2.0 link policy.
+#[derive(Serialize, Debug, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct UpstreamOAuthLinkInput<'a> {
+    #[schemars(with = "std::collections::HashMap<String, serde_json::Value>")]
+    pub user: &'a User,
+
+    pub action: UpstreamOAuthLinkAction,
+
+    pub provider: UpstreamOAuthLinkProvider<'a>,
+
+    pub requester: Requester,
+}
+
+/// The compatibility session in a [`CompatTokenAnomalyInput`]
+#[derive(Serialize, Debug, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct CompatTokenAnomalySession<'a> {
+    /// The ID of the session
+    #[schemars(with = "String")]
+    pub id: Ulid,
+
+    /// The device ID of the session, if any
+    pub device_id: Option<&'a str>,
+
+    /// The IP address the session was last active from
+    pub last_active_ip: Option<IpAddr>,
+
+    /// The user agent the session was last active from
+    pub user_agent: Option<&'a str>,
+}
+
+/// Input for the compatibility access token anomaly policy.
+#[derive(Serialize, Debug, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct CompatTokenAnomalyInput<'a> {
+    #[schemars(with = "std::collections::HashMap<String, serde_json::Value>")]
+    pub user: &'a User,
+
+    pub session: CompatTokenAnomalySession<'a>,
+
+    /// What was unusual about this use of the token, either `ip_range` or
+    /// `user_agent`
+    #[schemars(with = "Vec<String>")]
+    pub anomalies: &'a [CompatSessionAnomalyKind],
+
+    /// The entity using the token
+    pub requester: Requester,
+}
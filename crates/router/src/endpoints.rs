@@ -6,6 +6,7 @@
 
 use serde::{Deserialize, Serialize};
 use ulid::Ulid;
+use url::Url;
 
 use crate::UrlBuilder;
 pub use crate::traits::*;
@@ -30,6 +31,11 @@ pub enum PostAuthAction {
         #[serde(flatten)]
         action: Option<AccountAction>,
     },
+    LaunchClient {
+        id: Ulid,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        target_link_uri: Option<Url>,
+    },
 }
 
 impl PostAuthAction {
@@ -58,6 +64,14 @@ impl PostAuthAction {
         PostAuthAction::ManageAccount { action }
     }
 
+    #[must_use]
+    pub const fn launch_client(id: Ulid, target_link_uri: Option<Url>) -> Self {
+        PostAuthAction::LaunchClient {
+            id,
+            target_link_uri,
+        }
+    }
+
     pub fn go_next(&self, url_builder: &UrlBuilder) -> axum::response::Redirect {
         match self {
             Self::ContinueAuthorizationGrant { id } => url_builder.redirect(&Consent(*id)),
@@ -72,6 +86,10 @@ impl PostAuthAction {
             Self::ManageAccount { action } => url_builder.redirect(&Account {
                 action: action.clone(),
             }),
+            Self::LaunchClient {
+                id,
+                target_link_uri,
+            } => url_builder.redirect(&ClientLaunch::new(*id, target_link_uri.clone())),
         }
     }
 }
@@ -701,30 +719,85 @@ impl Route for CompatLoginSsoComplete {
     }
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ClientLaunchParams {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target_link_uri: Option<Url>,
+}
+
+/// `GET /clients/{id}/launch`
+pub struct ClientLaunch {
+    id: Ulid,
+    query: Option<ClientLaunchParams>,
+}
+
+impl ClientLaunch {
+    #[must_use]
+    pub fn new(id: Ulid, target_link_uri: Option<Url>) -> Self {
+        Self {
+            id,
+            query: target_link_uri.map(|target_link_uri| ClientLaunchParams {
+                target_link_uri: Some(target_link_uri),
+            }),
+        }
+    }
+}
+
+impl Route for ClientLaunch {
+    type Query = ClientLaunchParams;
+
+    fn query(&self) -> Option<&Self::Query> {
+        self.query.as_ref()
+    }
+
+    fn route() -> &'static str {
+        "/clients/{client_id}/launch"
+    }
+
+    fn path(&self) -> std::borrow::Cow<'static, str> {
+        format!("/clients/{}/launch", self.id).into()
+    }
+}
+
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+pub struct UpstreamOAuth2AuthorizeParams {
+    #[serde(flatten)]
+    post_auth_action: Option<PostAuthAction>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    login_hint: Option<String>,
+}
+
 /// `GET /upstream/authorize/{id}`
 pub struct UpstreamOAuth2Authorize {
     id: Ulid,
-    post_auth_action: Option<PostAuthAction>,
+    params: UpstreamOAuth2AuthorizeParams,
 }
 
 impl UpstreamOAuth2Authorize {
     #[must_use]
-    pub const fn new(id: Ulid) -> Self {
+    pub fn new(id: Ulid) -> Self {
         Self {
             id,
-            post_auth_action: None,
+            params: UpstreamOAuth2AuthorizeParams::default(),
         }
     }
 
     #[must_use]
     pub fn and_then(mut self, action: PostAuthAction) -> Self {
-        self.post_auth_action = Some(action);
+        self.params.post_auth_action = Some(action);
+        self
+    }
+
+    #[must_use]
+    pub fn with_login_hint(mut self, login_hint: String) -> Self {
+        self.params.login_hint = Some(login_hint);
         self
     }
 }
 
 impl Route for UpstreamOAuth2Authorize {
-    type Query = PostAuthAction;
+    type Query = UpstreamOAuth2AuthorizeParams;
     fn route() -> &'static str {
         "/upstream/authorize/{provider_id}"
     }
@@ -734,7 +807,57 @@ impl Route for UpstreamOAuth2Authorize {
     }
 
     fn query(&self) -> Option<&Self::Query> {
-        self.post_auth_action.as_ref()
+        if self.params.post_auth_action.is_none() && self.params.login_hint.is_none() {
+            None
+        } else {
+            Some(&self.params)
+        }
+    }
+}
+
+/// `GET|POST /upstream/initiate/{id}`
+pub struct UpstreamOAuth2Initiate {
+    id: Ulid,
+}
+
+impl UpstreamOAuth2Initiate {
+    #[must_use]
+    pub const fn new(id: Ulid) -> Self {
+        Self { id }
+    }
+}
+
+impl Route for UpstreamOAuth2Initiate {
+    type Query = ();
+    fn route() -> &'static str {
+        "/upstream/initiate/{provider_id}"
+    }
+
+    fn path(&self) -> std::borrow::Cow<'static, str> {
+        format!("/upstream/initiate/{}", self.id).into()
+    }
+}
+
+/// `GET /upstream/jwks/{id}`
+pub struct UpstreamOAuth2Jwks {
+    id: Ulid,
+}
+
+impl UpstreamOAuth2Jwks {
+    #[must_use]
+    pub const fn new(id: Ulid) -> Self {
+        Self { id }
+    }
+}
+
+impl Route for UpstreamOAuth2Jwks {
+    type Query = ();
+    fn route() -> &'static str {
+        "/upstream/jwks/{provider_id}"
+    }
+
+    fn path(&self) -> std::borrow::Cow<'static, str> {
+        format!("/upstream/jwks/{}", self.id).into()
     }
 }
 
@@ -807,6 +930,52 @@ impl Route for UpstreamOAuth2BackchannelLogout {
     }
 }
 
+/// `GET /upstream/saml2/{id}/metadata`
+pub struct UpstreamSaml2Metadata {
+    id: Ulid,
+}
+
+impl UpstreamSaml2Metadata {
+    #[must_use]
+    pub const fn new(id: Ulid) -> Self {
+        Self { id }
+    }
+}
+
+impl Route for UpstreamSaml2Metadata {
+    type Query = ();
+    fn route() -> &'static str {
+        "/upstream/saml2/{provider_id}/metadata"
+    }
+
+    fn path(&self) -> std::borrow::Cow<'static, str> {
+        format!("/upstream/saml2/{}/metadata", self.id).into()
+    }
+}
+
+/// `POST /upstream/saml2/{id}/acs`
+pub struct UpstreamSaml2Acs {
+    id: Ulid,
+}
+
+impl UpstreamSaml2Acs {
+    #[must_use]
+    pub const fn new(id: Ulid) -> Self {
+        Self { id }
+    }
+}
+
+impl Route for UpstreamSaml2Acs {
+    type Query = ();
+    fn route() -> &'static str {
+        "/upstream/saml2/{provider_id}/acs"
+    }
+
+    fn path(&self) -> std::borrow::Cow<'static, str> {
+        format!("/upstream/saml2/{}/acs", self.id).into()
+    }
+}
+
 /// `GET|POST /link`
 #[derive(Default, Serialize, Deserialize, Debug, Clone)]
 pub struct DeviceCodeLink {
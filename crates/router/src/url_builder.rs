@@ -226,6 +226,24 @@ impl UrlBuilder {
         self.absolute_url_for(&crate::endpoints::UpstreamOAuth2Authorize::new(id))
     }
 
+    /// Upstream public keys URI, for providers with their own client keys
+    #[must_use]
+    pub fn upstream_oauth_jwks(&self, id: Ulid) -> Url {
+        self.absolute_url_for(&crate::endpoints::UpstreamOAuth2Jwks::new(id))
+    }
+
+    /// Upstream SAML 2.0 service provider metadata URI
+    #[must_use]
+    pub fn upstream_saml2_metadata(&self, id: Ulid) -> Url {
+        self.absolute_url_for(&crate::endpoints::UpstreamSaml2Metadata::new(id))
+    }
+
+    /// Upstream SAML 2.0 assertion consumer service URI
+    #[must_use]
+    pub fn upstream_saml2_acs(&self, id: Ulid) -> Url {
+        self.absolute_url_for(&crate::endpoints::UpstreamSaml2Acs::new(id))
+    }
+
     /// Account management URI
     #[must_use]
     pub fn account_management_uri(&self) -> Url {
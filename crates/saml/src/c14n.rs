@@ -0,0 +1,315 @@
+// Copyright 2026 Element Creations Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+// Please see LICENSE files in the repository root for full details.
+
+//! Exclusive XML canonicalization, without comments
+//!
+//! See <https://www.w3.org/TR/xml-exc-c14n/>
+
+use crate::xml::{ElementRef, Node, NodeId, XML_NS};
+
+/// Canonicalize an element and its descendants
+///
+/// The `excluded` node and its descendants are omitted from the output, which
+/// is how the enveloped signature transform is applied. The
+/// `inclusive_prefixes` are the namespace prefixes which should be treated as
+/// in the inclusive canonicalization, `#default` being the default namespace.
+pub(crate) fn canonicalize(
+    element: ElementRef<'_>,
+    excluded: Option<NodeId>,
+    inclusive_prefixes: &[&str],
+) -> String {
+    let inclusive_prefixes: Vec<Option<&str>> = inclusive_prefixes
+        .iter()
+        .map(|&prefix| (prefix != "#default").then_some(prefix))
+        .collect();
+
+    let mut output = String::new();
+    let mut rendered = Vec::new();
+    write_element(
+        &mut output,
+        element,
+        excluded,
+        &inclusive_prefixes,
+        &mut rendered,
+    );
+    output
+}
+
+fn qualified_name(prefix: Option<&str>, local_name: &str) -> String {
+    match prefix {
+        Some(prefix) => format!("{prefix}:{local_name}"),
+        None => local_name.to_owned(),
+    }
+}
+
+fn write_element<'a>(
+    output: &mut String,
+    element: ElementRef<'a>,
+    excluded: Option<NodeId>,
+    inclusive_prefixes: &[Option<&'a str>],
+    rendered: &mut Vec<(Option<&'a str>, &'a str)>,
+) {
+    // The namespaces visibly utilized by the element and its attributes, plus
+    // the ones which should be treated inclusively
+    let mut prefixes: Vec<Option<&str>> = vec![element.prefix()];
+    prefixes.extend(
+        element
+            .attributes()
+            .iter()
+            .filter_map(|attribute| attribute.prefix.as_deref())
+            .filter(|&prefix| prefix != "xml")
+            .map(Some),
+    );
+    prefixes.extend(inclusive_prefixes.iter().copied());
+    // This sorts the default namespace first, then by prefix
+    prefixes.sort_unstable();
+    prefixes.dedup();
+
+    // Only render the namespaces which aren't already rendered with the same
+    // value by an output ancestor
+    let rendered_len = rendered.len();
+    let mut namespaces = Vec::new();
+    for prefix in prefixes {
+        let uri = element.lookup_namespace(prefix).unwrap_or_default();
+        if prefix.is_some() && uri.is_empty() {
+            // Inclusive prefixes which are not in scope are ignored
+            continue;
+        }
+
+        let current = rendered
+            .iter()
+            .rev()
+            .find(|(p, _)| *p == prefix)
+            .map_or("", |(_, uri)| *uri);
+        if current != uri {
+            namespaces.push((prefix, uri));
+        }
+    }
+
+    let name = qualified_name(element.prefix(), element.local_name());
+    output.push('<');
+    output.push_str(&name);
+
+    for &(prefix, uri) in &namespaces {
+        match prefix {
+            Some(prefix) => {
+                output.push_str(" xmlns:");
+                output.push_str(prefix);
+            }
+            None => output.push_str(" xmlns"),
+        }
+        output.push_str("=\"");
+        escape_attribute_into(output, uri);
+        output.push('"');
+    }
+    rendered.extend(namespaces);
+
+    // Attributes are sorted by namespace URI, then by local name, attributes
+    // without a namespace coming first
+    let mut attributes: Vec<_> = element
+        .attributes()
+        .iter()
+        .map(|attribute| {
+            let namespace = match attribute.prefix.as_deref() {
+                Some("xml") => XML_NS,
+                prefix @ Some(_) => element.lookup_namespace(prefix).unwrap_or_default(),
+                None => "",
+            };
+            (namespace, attribute)
+        })
+        .collect();
+    attributes.sort_by(|(a_ns, a), (b_ns, b)| (a_ns, &a.local_name).cmp(&(b_ns, &b.local_name)));
+
+    for (_, attribute) in attributes {
+        output.push(' ');
+        output.push_str(&qualified_name(
+            attribute.prefix.as_deref(),
+            &attribute.local_name,
+        ));
+        output.push_str("=\"");
+        escape_attribute_into(output, &attribute.value);
+        output.push('"');
+    }
+    output.push('>');
+
+    for (id, node) in element.child_nodes() {
+        if Some(id) == excluded {
+            continue;
+        }
+
+        match node {
+            Node::Element(_) => {
+                let child = element
+                    .children()
+                    .find(|child| child.id() == id)
+                    .expect("child element should exist");
+                write_element(output, child, excluded, inclusive_prefixes, rendered);
+            }
+            Node::Text(text) => escape_text_into(output, text),
+            Node::ProcessingInstruction { target, data } => {
+                output.push_str("<?");
+                output.push_str(target);
+                if !data.is_empty() {
+                    output.push(' ');
+                    output.push_str(data);
+                }
+                output.push_str("?>");
+            }
+        }
+    }
+
+    rendered.truncate(rendered_len);
+
+    output.push_str("</");
+    output.push_str(&name);
+    output.push('>');
+}
+
+fn escape_text_into(output: &mut String, text: &str) {
+    for c in text.chars() {
+        match c {
+            '&' => output.push_str("&amp;"),
+            '<' => output.push_str("&lt;"),
+            '>' => output.push_str("&gt;"),
+            '\r' => output.push_str("&#xD;"),
+            c => output.push(c),
+        }
+    }
+}
+
+fn escape_attribute_into(output: &mut String, value: &str) {
+    for c in value.chars() {
+        match c {
+            '&' => output.push_str("&amp;"),
+            '<' => output.push_str("&lt;"),
+            '"' => output.push_str("&quot;"),
+            '\t' => output.push_str("&#x9;"),
+            '\n' => output.push_str("&#xA;"),
+            '\r' => output.push_str("&#xD;"),
+            c => output.push(c),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::xml::Document;
+
+    /// Examples from section 3 of the canonical XML spec, without the document
+    /// type declarations, and with the namespace declarations the exclusive
+    /// canonicalization omits. The expected outputs were checked against
+    /// Python's implementation.
+    #[test]
+    fn test_spec_examples() {
+        let examples = [
+            (
+                include_str!("../tests/c14n/example-3.1.xml"),
+                include_str!("../tests/c14n/example-3.1.c14n.xml"),
+            ),
+            (
+                include_str!("../tests/c14n/example-3.2.xml"),
+                include_str!("../tests/c14n/example-3.2.c14n.xml"),
+            ),
+            (
+                include_str!("../tests/c14n/example-3.3.xml"),
+                include_str!("../tests/c14n/example-3.3.c14n.xml"),
+            ),
+            (
+                include_str!("../tests/c14n/example-3.4.xml"),
+                include_str!("../tests/c14n/example-3.4.c14n.xml"),
+            ),
+            (
+                include_str!("../tests/c14n/example-3.6.xml"),
+                include_str!("../tests/c14n/example-3.6.c14n.xml"),
+            ),
+        ];
+
+        for (input, expected) in examples {
+            let document = Document::parse(input).unwrap();
+            assert_eq!(canonicalize(document.root_element(), None, &[]), expected);
+        }
+    }
+
+    #[test]
+    fn test_exclusive_namespaces() {
+        // Example from section 2.2 of the exclusive canonicalization spec
+        let document = Document::parse(
+            r#"<n0:local xmlns:n0="foo:bar" xmlns:n3="ftp://example.org"><n1:elem2 xmlns:n1="http://example.net" xml:lang="en"><n3:stuff xmlns:n3="ftp://example.org"/></n1:elem2></n0:local>"#,
+        )
+        .unwrap();
+        let elem2 = document.root_element().children().next().unwrap();
+
+        assert_eq!(
+            canonicalize(elem2, None, &[]),
+            r#"<n1:elem2 xmlns:n1="http://example.net" xml:lang="en"><n3:stuff xmlns:n3="ftp://example.org"></n3:stuff></n1:elem2>"#
+        );
+
+        // With n3 treated inclusively, it is rendered on the apex element
+        assert_eq!(
+            canonicalize(elem2, None, &["n3"]),
+            r#"<n1:elem2 xmlns:n1="http://example.net" xmlns:n3="ftp://example.org" xml:lang="en"><n3:stuff></n3:stuff></n1:elem2>"#
+        );
+
+        // The second document of the example gives the same result, as the
+        // namespaces and `xml:` attributes of the ancestors aren't inherited
+        let document = Document::parse(
+            r#"<n2:pdu xmlns:n1="http://example.com" xmlns:n2="http://foo.example" xml:lang="fr" xml:space="retain"><n1:elem2 xmlns:n1="http://example.net" xml:lang="en"><n3:stuff xmlns:n3="ftp://example.org"/></n1:elem2></n2:pdu>"#,
+        )
+        .unwrap();
+        let elem2 = document.root_element().children().next().unwrap();
+
+        assert_eq!(
+            canonicalize(elem2, None, &[]),
+            r#"<n1:elem2 xmlns:n1="http://example.net" xml:lang="en"><n3:stuff xmlns:n3="ftp://example.org"></n3:stuff></n1:elem2>"#
+        );
+    }
+
+    #[test]
+    fn test_attributes_and_text() {
+        let document = Document::parse(
+            "<root xmlns=\"urn:default\" xmlns:b=\"urn:b\" xmlns:a=\"urn:a\" z=\"1\" b:attr=\"&quot;&#9;\" a:attr='2' y=\"&lt;\">\r\n  <empty/>A &amp; B &gt; C<!-- comment --><?pi data?></root>",
+        )
+        .unwrap();
+
+        assert_eq!(
+            canonicalize(document.root_element(), None, &[]),
+            "<root xmlns=\"urn:default\" xmlns:a=\"urn:a\" xmlns:b=\"urn:b\" y=\"&lt;\" z=\"1\" a:attr=\"2\" b:attr=\"&quot;&#x9;\">\n  <empty></empty>A &amp; B &gt; C<?pi data?></root>"
+        );
+    }
+
+    #[test]
+    fn test_default_namespace_undeclaration() {
+        let document =
+            Document::parse(r#"<root xmlns="urn:a"><child xmlns=""><leaf/></child></root>"#)
+                .unwrap();
+        let root = document.root_element();
+        assert_eq!(
+            canonicalize(root, None, &[]),
+            r#"<root xmlns="urn:a"><child xmlns=""><leaf></leaf></child></root>"#
+        );
+
+        // The child alone doesn't need to undeclare the default namespace
+        let child = root.children().next().unwrap();
+        assert_eq!(
+            canonicalize(child, None, &[]),
+            r"<child><leaf></leaf></child>"
+        );
+    }
+
+    #[test]
+    fn test_excluded_node() {
+        let document = Document::parse(
+            r#"<root xmlns:ds="urn:ds"><a>1</a><ds:Signature><ds:Value/></ds:Signature><b>2</b></root>"#,
+        )
+        .unwrap();
+        let root = document.root_element();
+        let signature = root.children().nth(1).unwrap();
+        assert_eq!(
+            canonicalize(root, Some(signature.id()), &[]),
+            "<root><a>1</a><b>2</b></root>"
+        );
+    }
+}
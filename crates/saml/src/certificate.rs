@@ -0,0 +1,208 @@
+// Copyright 2026 Element Creations Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+// Please see LICENSE files in the repository root for full details.
+
+//! X.509 certificates and keys
+
+use base64ct::{Base64, Encoding};
+use pkcs1::DecodeRsaPrivateKey;
+use pkcs8::{DecodePrivateKey, DecodePublicKey};
+use rsa::{RsaPrivateKey, RsaPublicKey};
+use thiserror::Error;
+
+/// An error which occurred while loading a certificate
+#[derive(Debug, Error)]
+pub enum CertificateError {
+    #[error("Invalid PEM encoding")]
+    Pem(#[from] pem_rfc7468::Error),
+
+    #[error("Expected a PEM-encoded certificate, got {label:?}")]
+    UnexpectedLabel { label: String },
+
+    #[error("Invalid base64 encoding")]
+    Base64(#[from] base64ct::Error),
+
+    #[error("Invalid certificate structure")]
+    InvalidStructure,
+
+    #[error("Only certificates with an RSA public key are supported")]
+    UnsupportedKey(#[source] pkcs8::spki::Error),
+}
+
+/// An X.509 certificate
+///
+/// Certificates are only used as containers for public keys: their validity
+/// period and issuer are not checked, which is how SAML metadata usually
+/// treats them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Certificate {
+    der: Vec<u8>,
+    public_key: RsaPublicKey,
+}
+
+impl Certificate {
+    /// Load a DER-encoded certificate
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the certificate is malformed or doesn't have an RSA
+    /// public key
+    pub fn from_der(der: Vec<u8>) -> Result<Self, CertificateError> {
+        let spki = subject_public_key_info(&der).ok_or(CertificateError::InvalidStructure)?;
+        let public_key =
+            RsaPublicKey::from_public_key_der(spki).map_err(CertificateError::UnsupportedKey)?;
+        Ok(Self { der, public_key })
+    }
+
+    /// Load a certificate, either PEM-encoded, or as the base64-encoded DER
+    /// found in SAML metadata
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the certificate is malformed or doesn't have an RSA
+    /// public key
+    pub fn parse(input: &str) -> Result<Self, CertificateError> {
+        let input = input.trim();
+        if input.starts_with("-----BEGIN") {
+            let (label, der) = pem_rfc7468::decode_vec(input.as_bytes())?;
+            if label != "CERTIFICATE" {
+                return Err(CertificateError::UnexpectedLabel {
+                    label: label.to_owned(),
+                });
+            }
+            Self::from_der(der)
+        } else {
+            Self::from_der(decode_base64(input)?)
+        }
+    }
+
+    /// The public key of the certificate
+    #[must_use]
+    pub fn public_key(&self) -> &RsaPublicKey {
+        &self.public_key
+    }
+
+    /// The certificate, as base64-encoded DER
+    #[must_use]
+    pub fn to_base64(&self) -> String {
+        Base64::encode_string(&self.der)
+    }
+}
+
+/// An error which occurred while loading a private key
+#[derive(Debug, Error)]
+#[error("Could not load the RSA private key, expected a PKCS#1 or PKCS#8 PEM-encoded key")]
+pub struct PrivateKeyError;
+
+/// Load a PEM-encoded RSA private key, in either the PKCS#1 or the PKCS#8
+/// format
+///
+/// # Errors
+///
+/// Returns an error if the key is not a valid RSA private key
+pub fn private_key_from_pem(pem: &str) -> Result<RsaPrivateKey, PrivateKeyError> {
+    RsaPrivateKey::from_pkcs8_pem(pem)
+        .or_else(|_| RsaPrivateKey::from_pkcs1_pem(pem))
+        .map_err(|_| PrivateKeyError)
+}
+
+/// Decode base64 which may contain whitespace, as commonly found in XML
+pub(crate) fn decode_base64(input: &str) -> Result<Vec<u8>, base64ct::Error> {
+    let compact: String = input.chars().filter(|c| !c.is_ascii_whitespace()).collect();
+    Base64::decode_vec(&compact)
+}
+
+/// A DER tag-length-value: the tag, the value, the whole TLV and what comes
+/// after it
+type Tlv<'a> = (u8, &'a [u8], &'a [u8], &'a [u8]);
+
+/// Read a DER tag-length-value
+fn read_tlv(input: &[u8]) -> Option<Tlv<'_>> {
+    let (&tag, rest) = input.split_first()?;
+    let (&first, rest) = rest.split_first()?;
+
+    let (length, rest) = if first < 0x80 {
+        (usize::from(first), rest)
+    } else {
+        let count = usize::from(first & 0x7f);
+        if count == 0 || count > 4 || rest.len() < count {
+            return None;
+        }
+        let length = rest[..count]
+            .iter()
+            .fold(0usize, |acc, &byte| (acc << 8) | usize::from(byte));
+        (length, &rest[count..])
+    };
+
+    if rest.len() < length {
+        return None;
+    }
+
+    let header_length = input.len() - rest.len();
+    let whole = &input[..header_length + length];
+    Some((tag, &rest[..length], whole, &rest[length..]))
+}
+
+/// Extract the DER-encoded `SubjectPublicKeyInfo` of a certificate
+fn subject_public_key_info(der: &[u8]) -> Option<&[u8]> {
+    const SEQUENCE: u8 = 0x30;
+    const EXPLICIT_VERSION: u8 = 0xa0;
+
+    // Certificate ::= SEQUENCE { tbsCertificate, signatureAlgorithm, signature }
+    let (SEQUENCE, certificate, _, _) = read_tlv(der)? else {
+        return None;
+    };
+    let (SEQUENCE, tbs_certificate, _, _) = read_tlv(certificate)? else {
+        return None;
+    };
+
+    // TBSCertificate ::= SEQUENCE { [0] version OPTIONAL, serialNumber,
+    // signature, issuer, validity, subject, subjectPublicKeyInfo, ... }
+    let (tag, _, _, mut rest) = read_tlv(tbs_certificate)?;
+    if tag != EXPLICIT_VERSION {
+        rest = tbs_certificate;
+    }
+
+    for _ in 0..5 {
+        let (_, _, _, next) = read_tlv(rest)?;
+        rest = next;
+    }
+
+    let (SEQUENCE, _, spki, _) = read_tlv(rest)? else {
+        return None;
+    };
+    Some(spki)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_certificate() {
+        let pem = include_str!("../tests/keys/idp.crt.pem");
+        let certificate = Certificate::parse(pem).unwrap();
+
+        // The base64 form found in metadata loads the same certificate
+        let base64 = certificate.to_base64();
+        let from_base64 = Certificate::parse(&base64).unwrap();
+        assert_eq!(certificate, from_base64);
+
+        let key = private_key_from_pem(include_str!("../tests/keys/idp.key.pem")).unwrap();
+        assert_eq!(certificate.public_key(), &key.to_public_key());
+    }
+
+    #[test]
+    fn test_invalid_certificate() {
+        let key = include_str!("../tests/keys/idp.key.pem");
+        assert!(matches!(
+            Certificate::parse(key),
+            Err(CertificateError::UnexpectedLabel { .. })
+        ));
+        assert!(matches!(
+            Certificate::parse("AAAA"),
+            Err(CertificateError::InvalidStructure)
+        ));
+    }
+}
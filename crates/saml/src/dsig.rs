@@ -0,0 +1,358 @@
+// Copyright 2026 Element Creations Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+// Please see LICENSE files in the repository root for full details.
+
+//! XML signatures, as used by SAML
+//!
+//! Only enveloped signatures referencing their parent element by ID are
+//! supported, which is what the SAML profile of XML signatures mandates.
+//! Verifying the signature of the exact element which is later read, instead
+//! of looking up elements by ID, protects against signature wrapping attacks.
+
+use base64ct::{Base64, Encoding};
+use rsa::{
+    RsaPrivateKey, RsaPublicKey,
+    pkcs1v15::{Signature, SigningKey, VerifyingKey},
+    signature::{SignatureEncoding, Signer, Verifier},
+};
+use sha2::{Digest, Sha256, Sha512};
+use thiserror::Error;
+
+use crate::{
+    Certificate,
+    c14n::canonicalize,
+    certificate::decode_base64,
+    xml::{Document, ElementRef, escape_attribute},
+};
+
+pub(crate) const DSIG_NS: &str = "http://www.w3.org/2000/09/xmldsig#";
+const EXC_C14N: &str = "http://www.w3.org/2001/10/xml-exc-c14n#";
+const ENVELOPED_SIGNATURE: &str = "http://www.w3.org/2000/09/xmldsig#enveloped-signature";
+pub(crate) const RSA_SHA256: &str = "http://www.w3.org/2001/04/xmldsig-more#rsa-sha256";
+const RSA_SHA512: &str = "http://www.w3.org/2001/04/xmldsig-more#rsa-sha512";
+const SHA256: &str = "http://www.w3.org/2001/04/xmlenc#sha256";
+const SHA512: &str = "http://www.w3.org/2001/04/xmlenc#sha512";
+
+/// An error which occurred while verifying an XML signature
+#[derive(Debug, Error)]
+pub enum SignatureError {
+    #[error("The signature is malformed: {0}")]
+    Malformed(&'static str),
+
+    #[error("Unsupported algorithm {0:?}")]
+    UnsupportedAlgorithm(String),
+
+    #[error("The signature doesn't reference the signed element")]
+    InvalidReference,
+
+    #[error("The digest of the signed element doesn't match")]
+    DigestMismatch,
+
+    #[error("The signature could not be verified with any of the trusted certificates")]
+    Invalid,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum HashAlgorithm {
+    Sha256,
+    Sha512,
+}
+
+impl HashAlgorithm {
+    fn from_digest_method(uri: &str) -> Result<Self, SignatureError> {
+        match uri {
+            SHA256 => Ok(Self::Sha256),
+            SHA512 => Ok(Self::Sha512),
+            _ => Err(SignatureError::UnsupportedAlgorithm(uri.to_owned())),
+        }
+    }
+
+    fn from_signature_method(uri: &str) -> Result<Self, SignatureError> {
+        match uri {
+            RSA_SHA256 => Ok(Self::Sha256),
+            RSA_SHA512 => Ok(Self::Sha512),
+            _ => Err(SignatureError::UnsupportedAlgorithm(uri.to_owned())),
+        }
+    }
+
+    fn digest(self, data: &[u8]) -> Vec<u8> {
+        match self {
+            Self::Sha256 => Sha256::digest(data).to_vec(),
+            Self::Sha512 => Sha512::digest(data).to_vec(),
+        }
+    }
+
+    fn verify(self, key: &RsaPublicKey, data: &[u8], signature: &Signature) -> bool {
+        match self {
+            Self::Sha256 => VerifyingKey::<Sha256>::new(key.clone())
+                .verify(data, signature)
+                .is_ok(),
+            Self::Sha512 => VerifyingKey::<Sha512>::new(key.clone())
+                .verify(data, signature)
+                .is_ok(),
+        }
+    }
+}
+
+fn algorithm(element: ElementRef<'_>) -> Result<&str, SignatureError> {
+    element
+        .attribute("Algorithm")
+        .ok_or(SignatureError::Malformed("missing Algorithm attribute"))
+}
+
+/// Get the inclusive namespace prefixes of a canonicalization method or
+/// transform
+fn inclusive_prefixes(element: ElementRef<'_>) -> Vec<&str> {
+    element
+        .first_child(EXC_C14N, "InclusiveNamespaces")
+        .and_then(|inclusive| inclusive.attribute("PrefixList"))
+        .map(|list| list.split_ascii_whitespace().collect())
+        .unwrap_or_default()
+}
+
+/// Verify the enveloped signature of an element
+///
+/// Returns `false` if the element isn't signed, and an error if it is signed
+/// but the signature is invalid.
+pub(crate) fn verify_enveloped(
+    element: ElementRef<'_>,
+    certificates: &[Certificate],
+) -> Result<bool, SignatureError> {
+    let mut signatures = element.children_named(DSIG_NS, "Signature");
+    let Some(signature) = signatures.next() else {
+        return Ok(false);
+    };
+    if signatures.next().is_some() {
+        return Err(SignatureError::Malformed("multiple signatures"));
+    }
+
+    let signed_info = signature
+        .first_child(DSIG_NS, "SignedInfo")
+        .ok_or(SignatureError::Malformed("missing SignedInfo"))?;
+
+    let canonicalization_method = signed_info
+        .first_child(DSIG_NS, "CanonicalizationMethod")
+        .ok_or(SignatureError::Malformed("missing CanonicalizationMethod"))?;
+    let uri = algorithm(canonicalization_method)?;
+    if uri != EXC_C14N {
+        return Err(SignatureError::UnsupportedAlgorithm(uri.to_owned()));
+    }
+
+    let signature_method = signed_info
+        .first_child(DSIG_NS, "SignatureMethod")
+        .ok_or(SignatureError::Malformed("missing SignatureMethod"))?;
+    let signature_algorithm = HashAlgorithm::from_signature_method(algorithm(signature_method)?)?;
+
+    // There must be exactly one reference, to the parent element
+    let mut references = signed_info.children_named(DSIG_NS, "Reference");
+    let reference = references
+        .next()
+        .ok_or(SignatureError::Malformed("missing Reference"))?;
+    if references.next().is_some() {
+        return Err(SignatureError::Malformed("multiple references"));
+    }
+
+    let id = element
+        .attribute("ID")
+        .filter(|id| !id.is_empty())
+        .ok_or(SignatureError::InvalidReference)?;
+    if reference
+        .attribute("URI")
+        .and_then(|uri| uri.strip_prefix('#'))
+        != Some(id)
+    {
+        return Err(SignatureError::InvalidReference);
+    }
+
+    // Only the enveloped signature and exclusive canonicalization transforms
+    // are supported, and the canonicalization is required
+    let mut reference_prefixes = None;
+    if let Some(transforms) = reference.first_child(DSIG_NS, "Transforms") {
+        for transform in transforms.children_named(DSIG_NS, "Transform") {
+            match algorithm(transform)? {
+                ENVELOPED_SIGNATURE => {}
+                EXC_C14N => reference_prefixes = Some(inclusive_prefixes(transform)),
+                uri => return Err(SignatureError::UnsupportedAlgorithm(uri.to_owned())),
+            }
+        }
+    }
+    let Some(reference_prefixes) = reference_prefixes else {
+        return Err(SignatureError::Malformed(
+            "missing exclusive canonicalization transform",
+        ));
+    };
+
+    let digest_method = reference
+        .first_child(DSIG_NS, "DigestMethod")
+        .ok_or(SignatureError::Malformed("missing DigestMethod"))?;
+    let digest_algorithm = HashAlgorithm::from_digest_method(algorithm(digest_method)?)?;
+
+    let expected_digest = reference
+        .first_child(DSIG_NS, "DigestValue")
+        .map(|value| decode_base64(&value.text()))
+        .ok_or(SignatureError::Malformed("missing DigestValue"))?
+        .map_err(|_| SignatureError::Malformed("invalid DigestValue"))?;
+
+    let canonical = canonicalize(element, Some(signature.id()), &reference_prefixes);
+    if digest_algorithm.digest(canonical.as_bytes()) != expected_digest {
+        return Err(SignatureError::DigestMismatch);
+    }
+
+    let signature_value = signature
+        .first_child(DSIG_NS, "SignatureValue")
+        .map(|value| decode_base64(&value.text()))
+        .ok_or(SignatureError::Malformed("missing SignatureValue"))?
+        .map_err(|_| SignatureError::Malformed("invalid SignatureValue"))?;
+    let signature_value = Signature::try_from(signature_value.as_slice())
+        .map_err(|_| SignatureError::Malformed("invalid SignatureValue"))?;
+
+    let signed_info_prefixes = inclusive_prefixes(canonicalization_method);
+    let canonical = canonicalize(signed_info, None, &signed_info_prefixes);
+
+    let valid = certificates.iter().any(|certificate| {
+        signature_algorithm.verify(
+            certificate.public_key(),
+            canonical.as_bytes(),
+            &signature_value,
+        )
+    });
+
+    if valid {
+        Ok(true)
+    } else {
+        Err(SignatureError::Invalid)
+    }
+}
+
+/// Sign an element with RSA-SHA256, returning the `ds:Signature` element to
+/// insert in it
+///
+/// The element must have an `ID` attribute, and the signature must be
+/// inserted without any surrounding whitespace.
+pub(crate) fn sign_enveloped(
+    element: ElementRef<'_>,
+    key: &RsaPrivateKey,
+    certificate: Option<&Certificate>,
+) -> String {
+    let id = element.attribute("ID").unwrap_or_default();
+    let digest = Base64::encode_string(&Sha256::digest(canonicalize(element, None, &[])));
+
+    let signed_info = format!(
+        concat!(
+            r#"<ds:SignedInfo xmlns:ds="{DSIG_NS}">"#,
+            r#"<ds:CanonicalizationMethod Algorithm="{EXC_C14N}"></ds:CanonicalizationMethod>"#,
+            r#"<ds:SignatureMethod Algorithm="{RSA_SHA256}"></ds:SignatureMethod>"#,
+            r##"<ds:Reference URI="#{id}">"##,
+            r#"<ds:Transforms>"#,
+            r#"<ds:Transform Algorithm="{ENVELOPED_SIGNATURE}"></ds:Transform>"#,
+            r#"<ds:Transform Algorithm="{EXC_C14N}"></ds:Transform>"#,
+            r#"</ds:Transforms>"#,
+            r#"<ds:DigestMethod Algorithm="{SHA256}"></ds:DigestMethod>"#,
+            r#"<ds:DigestValue>{digest}</ds:DigestValue>"#,
+            r#"</ds:Reference>"#,
+            r#"</ds:SignedInfo>"#,
+        ),
+        DSIG_NS = DSIG_NS,
+        EXC_C14N = EXC_C14N,
+        RSA_SHA256 = RSA_SHA256,
+        ENVELOPED_SIGNATURE = ENVELOPED_SIGNATURE,
+        SHA256 = SHA256,
+        id = escape_attribute(id),
+        digest = digest,
+    );
+
+    // Sign the canonical form of the SignedInfo, to be sure it matches what the
+    // verifier computes
+    let signed_info_document =
+        Document::parse(&signed_info).expect("the generated SignedInfo should be valid XML");
+    let canonical = canonicalize(signed_info_document.root_element(), None, &[]);
+    let signature = SigningKey::<Sha256>::new(key.clone()).sign(canonical.as_bytes());
+    let signature = Base64::encode_string(&signature.to_vec());
+
+    let key_info = certificate
+        .map(|certificate| {
+            format!(
+                "<ds:KeyInfo><ds:X509Data><ds:X509Certificate>{}</ds:X509Certificate></ds:X509Data></ds:KeyInfo>",
+                certificate.to_base64()
+            )
+        })
+        .unwrap_or_default();
+
+    format!(
+        r#"<ds:Signature xmlns:ds="{DSIG_NS}">{signed_info}<ds:SignatureValue>{signature}</ds:SignatureValue>{key_info}</ds:Signature>"#
+    )
+}
+
+/// Sign some bytes with RSA-SHA256, as used by the HTTP-Redirect binding
+pub(crate) fn sign_bytes(key: &RsaPrivateKey, data: &[u8]) -> Vec<u8> {
+    SigningKey::<Sha256>::new(key.clone()).sign(data).to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::certificate::private_key_from_pem;
+
+    /// Sign the given element, inserting the signature right after its start
+    /// tag
+    fn sign(xml: &str, key: &RsaPrivateKey) -> String {
+        let document = Document::parse(xml).unwrap();
+        let signature = sign_enveloped(document.root_element(), key, None);
+        let index = xml.find('>').unwrap() + 1;
+        format!("{}{signature}{}", &xml[..index], &xml[index..])
+    }
+
+    #[test]
+    fn test_sign_and_verify() {
+        let key = private_key_from_pem(include_str!("../tests/keys/idp.key.pem")).unwrap();
+        let certificate = Certificate::parse(include_str!("../tests/keys/idp.crt.pem")).unwrap();
+        let other = Certificate::parse(include_str!("../tests/keys/sp.crt.pem")).unwrap();
+
+        let xml = r#"<a:Root xmlns:a="urn:a" xmlns:unused="urn:unused" ID="_abc"><a:Child>Hello</a:Child></a:Root>"#;
+        let signed = sign(xml, &key);
+
+        let document = Document::parse(&signed).unwrap();
+        let root = document.root_element();
+        assert!(verify_enveloped(root, std::slice::from_ref(&certificate)).unwrap());
+
+        // Any of the trusted certificates can match
+        assert!(verify_enveloped(root, &[other.clone(), certificate.clone()]).unwrap());
+
+        // An untrusted certificate doesn't
+        assert!(matches!(
+            verify_enveloped(root, std::slice::from_ref(&other)),
+            Err(SignatureError::Invalid)
+        ));
+
+        // Unsigned elements are reported as such
+        let document = Document::parse(xml).unwrap();
+        assert!(
+            !verify_enveloped(document.root_element(), std::slice::from_ref(&certificate)).unwrap()
+        );
+
+        // Tampering with the content breaks the digest
+        let tampered = signed.replace("Hello", "Goodbye");
+        let document = Document::parse(&tampered).unwrap();
+        assert!(matches!(
+            verify_enveloped(document.root_element(), std::slice::from_ref(&certificate)),
+            Err(SignatureError::DigestMismatch)
+        ));
+
+        // Changing the ID breaks the reference
+        let tampered = signed.replace(r#"ID="_abc""#, r#"ID="_def""#);
+        let document = Document::parse(&tampered).unwrap();
+        assert!(matches!(
+            verify_enveloped(document.root_element(), std::slice::from_ref(&certificate)),
+            Err(SignatureError::InvalidReference)
+        ));
+
+        // Reformatting the namespace declarations and quotes doesn't change
+        // the canonical form
+        let reformatted = signed
+            .replace(r#"xmlns:unused="urn:unused" "#, "")
+            .replace(r#"ID="_abc""#, "ID='_abc'");
+        let document = Document::parse(&reformatted).unwrap();
+        assert!(verify_enveloped(document.root_element(), &[certificate]).unwrap());
+    }
+}
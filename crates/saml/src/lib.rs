@@ -0,0 +1,54 @@
+// Copyright 2026 Element Creations Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+// Please see LICENSE files in the repository root for full details.
+
+//! A minimal SAML 2.0 service provider implementation, used to log in with
+//! upstream SAML identity providers.
+//!
+//! It supports the web browser SSO profile: sending authentication requests
+//! with the HTTP-Redirect or HTTP-POST bindings, and validating the signed and
+//! optionally encrypted responses received on the assertion consumer service
+//! through the HTTP-POST binding.
+//!
+//! The XML parser is deliberately restrictive: document type declarations are
+//! rejected, which rules out entity expansion attacks.
+
+mod c14n;
+mod certificate;
+mod dsig;
+pub mod metadata;
+pub mod request;
+pub mod response;
+mod xml;
+mod xmlenc;
+
+pub use rsa::RsaPrivateKey;
+
+pub use self::{
+    certificate::{Certificate, CertificateError, PrivateKeyError, private_key_from_pem},
+    dsig::SignatureError,
+    xml::XmlError,
+    xmlenc::DecryptionError,
+};
+
+/// The SAML 2.0 protocol namespace
+pub const PROTOCOL_NS: &str = "urn:oasis:names:tc:SAML:2.0:protocol";
+
+/// The SAML 2.0 assertion namespace
+pub const ASSERTION_NS: &str = "urn:oasis:names:tc:SAML:2.0:assertion";
+
+/// The SAML 2.0 metadata namespace
+pub const METADATA_NS: &str = "urn:oasis:names:tc:SAML:2.0:metadata";
+
+/// The HTTP-POST binding
+pub const BINDING_HTTP_POST: &str = "urn:oasis:names:tc:SAML:2.0:bindings:HTTP-POST";
+
+/// The HTTP-Redirect binding
+pub const BINDING_HTTP_REDIRECT: &str = "urn:oasis:names:tc:SAML:2.0:bindings:HTTP-Redirect";
+
+/// The bearer subject confirmation method
+pub const BEARER: &str = "urn:oasis:names:tc:SAML:2.0:cm:bearer";
+
+/// The status code of successful responses
+pub const STATUS_SUCCESS: &str = "urn:oasis:names:tc:SAML:2.0:status:Success";
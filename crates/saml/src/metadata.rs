@@ -0,0 +1,156 @@
+// Copyright 2026 Element Creations Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+// Please see LICENSE files in the repository root for full details.
+
+//! Service provider metadata, to be consumed by identity providers
+
+use std::fmt::Write;
+
+use url::Url;
+
+use crate::{
+    BINDING_HTTP_POST, Certificate, METADATA_NS,
+    dsig::DSIG_NS,
+    xml::{escape_attribute, escape_text},
+    xmlenc::{AES128_CBC, AES128_GCM, AES256_CBC, AES256_GCM, RSA_OAEP, RSA_OAEP_MGF1P},
+};
+
+/// The metadata of a SAML service provider
+#[derive(Debug, Clone)]
+pub struct ServiceProviderMetadata<'a> {
+    /// The entity ID of the service provider
+    pub entity_id: &'a str,
+
+    /// Where the identity provider should send responses
+    pub assertion_consumer_service_url: &'a Url,
+
+    /// The certificate of the service provider, used both for signing requests
+    /// and for encrypting assertions
+    pub certificate: Option<&'a Certificate>,
+
+    /// The name identifier format the service provider wants
+    pub name_id_format: Option<&'a str>,
+
+    /// Whether the authentication requests are signed
+    pub authn_requests_signed: bool,
+
+    /// Whether the assertions should be signed
+    pub want_assertions_signed: bool,
+
+    /// Whether to advertise the AES-CBC content encryption algorithms
+    pub allow_cbc_encryption: bool,
+}
+
+impl ServiceProviderMetadata<'_> {
+    /// Serialize the metadata as an `EntityDescriptor` XML document
+    #[must_use]
+    pub fn to_xml(&self) -> String {
+        let mut xml = format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?><md:EntityDescriptor xmlns:md="{METADATA_NS}" entityID="{entity_id}"><md:SPSSODescriptor AuthnRequestsSigned="{authn_requests_signed}" WantAssertionsSigned="{want_assertions_signed}" protocolSupportEnumeration="urn:oasis:names:tc:SAML:2.0:protocol">"#,
+            entity_id = escape_attribute(self.entity_id),
+            authn_requests_signed = self.authn_requests_signed,
+            want_assertions_signed = self.want_assertions_signed,
+        );
+
+        if let Some(certificate) = self.certificate {
+            let key_info = format!(
+                r#"<ds:KeyInfo xmlns:ds="{DSIG_NS}"><ds:X509Data><ds:X509Certificate>{}</ds:X509Certificate></ds:X509Data></ds:KeyInfo>"#,
+                certificate.to_base64()
+            );
+
+            xml.push_str(r#"<md:KeyDescriptor use="signing">"#);
+            xml.push_str(&key_info);
+            xml.push_str("</md:KeyDescriptor>");
+
+            xml.push_str(r#"<md:KeyDescriptor use="encryption">"#);
+            xml.push_str(&key_info);
+            let cbc: &[&str] = if self.allow_cbc_encryption {
+                &[AES256_CBC, AES128_CBC]
+            } else {
+                &[]
+            };
+            for algorithm in [AES256_GCM, AES128_GCM]
+                .iter()
+                .chain(cbc)
+                .chain(&[RSA_OAEP, RSA_OAEP_MGF1P])
+            {
+                write!(xml, r#"<md:EncryptionMethod Algorithm="{algorithm}"/>"#).unwrap();
+            }
+            xml.push_str("</md:KeyDescriptor>");
+        }
+
+        if let Some(name_id_format) = self.name_id_format {
+            write!(
+                xml,
+                "<md:NameIDFormat>{}</md:NameIDFormat>",
+                escape_text(name_id_format)
+            )
+            .unwrap();
+        }
+
+        write!(
+            xml,
+            r#"<md:AssertionConsumerService Binding="{BINDING_HTTP_POST}" Location="{}" index="0" isDefault="true"/>"#,
+            escape_attribute(self.assertion_consumer_service_url.as_str())
+        )
+        .unwrap();
+
+        xml.push_str("</md:SPSSODescriptor></md:EntityDescriptor>");
+        xml
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::xml::Document;
+
+    #[test]
+    fn test_metadata() {
+        let certificate = Certificate::parse(include_str!("../tests/keys/sp.crt.pem")).unwrap();
+        let acs_url: Url = "https://mas.example.com/upstream/saml2/01H8PKNWKKRPCBW4YGH1RWV279/acs"
+            .parse()
+            .unwrap();
+
+        let metadata = ServiceProviderMetadata {
+            entity_id: "https://mas.example.com/upstream/saml2/01H8PKNWKKRPCBW4YGH1RWV279/metadata",
+            assertion_consumer_service_url: &acs_url,
+            certificate: Some(&certificate),
+            name_id_format: Some("urn:oasis:names:tc:SAML:2.0:nameid-format:persistent"),
+            authn_requests_signed: true,
+            want_assertions_signed: true,
+            allow_cbc_encryption: false,
+        };
+
+        let xml = metadata.to_xml();
+        let document = Document::parse(&xml).unwrap();
+        let root = document.root_element();
+        assert!(root.is(METADATA_NS, "EntityDescriptor"));
+        assert_eq!(root.attribute("entityID"), Some(metadata.entity_id));
+
+        let descriptor = root.first_child(METADATA_NS, "SPSSODescriptor").unwrap();
+        assert_eq!(descriptor.attribute("AuthnRequestsSigned"), Some("true"));
+        assert_eq!(
+            descriptor
+                .children_named(METADATA_NS, "KeyDescriptor")
+                .count(),
+            2
+        );
+
+        // AES-CBC is only advertised when allowed
+        assert!(!xml.contains(AES256_CBC));
+        let xml = ServiceProviderMetadata {
+            allow_cbc_encryption: true,
+            ..metadata
+        }
+        .to_xml();
+        assert!(xml.contains(AES256_CBC));
+
+        let acs = descriptor
+            .first_child(METADATA_NS, "AssertionConsumerService")
+            .unwrap();
+        assert_eq!(acs.attribute("Location"), Some(acs_url.as_str()));
+        assert_eq!(acs.attribute("Binding"), Some(BINDING_HTTP_POST));
+    }
+}
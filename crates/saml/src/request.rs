@@ -0,0 +1,264 @@
+// Copyright 2026 Element Creations Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+// Please see LICENSE files in the repository root for full details.
+
+//! Authentication requests, sent to the identity provider
+
+use base64ct::{Base64, Encoding};
+use chrono::{DateTime, SecondsFormat, Utc};
+use rand::{
+    Rng,
+    distributions::{Alphanumeric, DistString},
+};
+use rsa::RsaPrivateKey;
+use url::Url;
+
+use crate::{
+    ASSERTION_NS, BINDING_HTTP_POST, Certificate, PROTOCOL_NS,
+    dsig::{RSA_SHA256, sign_bytes, sign_enveloped},
+    xml::{Document, escape_attribute, escape_text},
+};
+
+/// A SAML `AuthnRequest`
+#[derive(Debug, Clone)]
+pub struct AuthnRequest {
+    /// The unique identifier of the request, which the response must
+    /// reference
+    pub id: String,
+
+    /// When the request was issued
+    pub issue_instant: DateTime<Utc>,
+
+    /// The URL of the single sign-on service of the identity provider
+    pub destination: Url,
+
+    /// The entity ID of the service provider
+    pub issuer: String,
+
+    /// Where the identity provider should send the response
+    pub assertion_consumer_service_url: Url,
+
+    /// The format of the name identifier to request, if any
+    pub name_id_format: Option<String>,
+}
+
+impl AuthnRequest {
+    /// Create a new request with a random ID
+    #[must_use]
+    pub fn new(
+        rng: &mut (impl Rng + ?Sized),
+        now: DateTime<Utc>,
+        issuer: String,
+        destination: Url,
+        assertion_consumer_service_url: Url,
+    ) -> Self {
+        // IDs must not start with a digit, hence the underscore
+        let id = format!("_{}", Alphanumeric.sample_string(rng, 32));
+        Self {
+            id,
+            issue_instant: now,
+            destination,
+            issuer,
+            assertion_consumer_service_url,
+            name_id_format: None,
+        }
+    }
+
+    /// Request a specific name identifier format
+    #[must_use]
+    pub fn with_name_id_format(mut self, name_id_format: Option<String>) -> Self {
+        self.name_id_format = name_id_format;
+        self
+    }
+
+    /// Serialize the request, split where the signature should be inserted
+    fn to_xml_parts(&self) -> (String, String) {
+        let head = format!(
+            r#"<samlp:AuthnRequest xmlns:samlp="{PROTOCOL_NS}" xmlns:saml="{ASSERTION_NS}" ID="{id}" Version="2.0" IssueInstant="{issue_instant}" Destination="{destination}" ProtocolBinding="{BINDING_HTTP_POST}" AssertionConsumerServiceURL="{acs_url}"><saml:Issuer>{issuer}</saml:Issuer>"#,
+            id = escape_attribute(&self.id),
+            issue_instant = self
+                .issue_instant
+                .to_rfc3339_opts(SecondsFormat::Secs, true),
+            destination = escape_attribute(self.destination.as_str()),
+            acs_url = escape_attribute(self.assertion_consumer_service_url.as_str()),
+            issuer = escape_text(&self.issuer),
+        );
+
+        let name_id_policy = match &self.name_id_format {
+            Some(format) => format!(
+                r#"<samlp:NameIDPolicy Format="{}" AllowCreate="true"/>"#,
+                escape_attribute(format)
+            ),
+            None => r#"<samlp:NameIDPolicy AllowCreate="true"/>"#.to_owned(),
+        };
+        let tail = format!("{name_id_policy}</samlp:AuthnRequest>");
+
+        (head, tail)
+    }
+
+    /// Serialize the request, without a signature
+    #[must_use]
+    pub fn to_xml(&self) -> String {
+        let (head, tail) = self.to_xml_parts();
+        head + &tail
+    }
+
+    /// Build the URL to redirect the user agent to, using the HTTP-Redirect
+    /// binding
+    ///
+    /// If a signing key is given, the query string is signed with RSA-SHA256
+    /// as described by the binding.
+    #[must_use]
+    pub fn redirect_url(&self, relay_state: &str, signing_key: Option<&RsaPrivateKey>) -> Url {
+        let deflated = miniz_oxide::deflate::compress_to_vec(self.to_xml().as_bytes(), 8);
+        let encoded = Base64::encode_string(&deflated);
+
+        let encode =
+            |value: &str| -> String { form_urlencoded::byte_serialize(value.as_bytes()).collect() };
+        let mut query = format!(
+            "SAMLRequest={}&RelayState={}",
+            encode(&encoded),
+            encode(relay_state)
+        );
+
+        // The signature covers the query string, in this exact order and
+        // encoding
+        if let Some(key) = signing_key {
+            query.push_str("&SigAlg=");
+            query.push_str(&encode(RSA_SHA256));
+            let signature = sign_bytes(key, query.as_bytes());
+            query.push_str("&Signature=");
+            query.push_str(&encode(&Base64::encode_string(&signature)));
+        }
+
+        let mut url = self.destination.clone();
+        let query = match url.query() {
+            Some(existing) if !existing.is_empty() => format!("{existing}&{query}"),
+            _ => query,
+        };
+        url.set_query(Some(&query));
+        url
+    }
+
+    /// Encode the request for the HTTP-POST binding, as the value of the
+    /// `SAMLRequest` form field
+    ///
+    /// If a signing key is given, the request gets an enveloped signature.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the generated request can't be parsed back, which would be a
+    /// bug.
+    #[must_use]
+    pub fn post_form_value(
+        &self,
+        signing_key: Option<&RsaPrivateKey>,
+        certificate: Option<&Certificate>,
+    ) -> String {
+        let (head, tail) = self.to_xml_parts();
+        let xml = match signing_key {
+            Some(key) => {
+                let unsigned = format!("{head}{tail}");
+                let document =
+                    Document::parse(&unsigned).expect("the generated request should be valid XML");
+                let signature = sign_enveloped(document.root_element(), key, certificate);
+                format!("{head}{signature}{tail}")
+            }
+            None => head + &tail,
+        };
+
+        Base64::encode_string(xml.as_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+    use rand::SeedableRng;
+    use rand_chacha::ChaChaRng;
+
+    use super::*;
+    use crate::{certificate::private_key_from_pem, dsig::verify_enveloped};
+
+    fn request() -> AuthnRequest {
+        let mut rng = ChaChaRng::seed_from_u64(42);
+        AuthnRequest::new(
+            &mut rng,
+            Utc.with_ymd_and_hms(2026, 10, 18, 12, 0, 0).unwrap(),
+            "https://mas.example.com/".to_owned(),
+            "https://idp.example.com/sso?tenant=1".parse().unwrap(),
+            "https://mas.example.com/upstream/saml2/acs"
+                .parse()
+                .unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_redirect_binding() {
+        let request = request();
+        assert!(request.id.starts_with('_'));
+
+        let url = request.redirect_url("some-state", None);
+        let params: Vec<(String, String)> = url.query_pairs().into_owned().collect();
+        assert_eq!(params[0], ("tenant".to_owned(), "1".to_owned()));
+        assert_eq!(params[1].0, "SAMLRequest");
+        assert_eq!(
+            params[2],
+            ("RelayState".to_owned(), "some-state".to_owned())
+        );
+        assert_eq!(params.len(), 3);
+
+        let deflated = Base64::decode_vec(&params[1].1).unwrap();
+        let inflated = miniz_oxide::inflate::decompress_to_vec(&deflated).unwrap();
+        assert_eq!(inflated, request.to_xml().as_bytes());
+    }
+
+    #[test]
+    fn test_signed_redirect_binding() {
+        let key = private_key_from_pem(include_str!("../tests/keys/sp.key.pem")).unwrap();
+        let request = request();
+        let url = request.redirect_url("some-state", Some(&key));
+
+        let query = url.query().unwrap();
+        let (signed, signature) = query.split_once("&Signature=").unwrap();
+        let signed = signed.strip_prefix("tenant=1&").unwrap();
+        assert!(
+            signed.ends_with(
+                "&SigAlg=http%3A%2F%2Fwww.w3.org%2F2001%2F04%2Fxmldsig-more%23rsa-sha256"
+            )
+        );
+
+        let signature: String = form_urlencoded::parse(format!("s={signature}").as_bytes())
+            .next()
+            .unwrap()
+            .1
+            .into_owned();
+        let signature = Base64::decode_vec(&signature).unwrap();
+        let signature = rsa::pkcs1v15::Signature::try_from(signature.as_slice()).unwrap();
+        rsa::signature::Verifier::verify(
+            &rsa::pkcs1v15::VerifyingKey::<sha2::Sha256>::new(key.to_public_key()),
+            signed.as_bytes(),
+            &signature,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_signed_post_binding() {
+        let key = private_key_from_pem(include_str!("../tests/keys/sp.key.pem")).unwrap();
+        let certificate = Certificate::parse(include_str!("../tests/keys/sp.crt.pem")).unwrap();
+        let request = request();
+
+        let value = request.post_form_value(Some(&key), Some(&certificate));
+        let xml = String::from_utf8(Base64::decode_vec(&value).unwrap()).unwrap();
+        let document = Document::parse(&xml).unwrap();
+        let root = document.root_element();
+        assert!(root.is(PROTOCOL_NS, "AuthnRequest"));
+        assert!(verify_enveloped(root, &[certificate]).unwrap());
+
+        // The signature must come right after the issuer
+        let children: Vec<_> = root.children().map(|child| child.local_name()).collect();
+        assert_eq!(children, ["Issuer", "Signature", "NameIDPolicy"]);
+    }
+}
@@ -0,0 +1,821 @@
+// Copyright 2026 Element Creations Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+// Please see LICENSE files in the repository root for full details.
+
+//! Validation of the responses sent by the identity provider
+
+use chrono::{DateTime, Duration, Utc};
+use rsa::RsaPrivateKey;
+use thiserror::Error;
+use url::Url;
+
+use crate::{
+    ASSERTION_NS, BEARER, Certificate, PROTOCOL_NS, STATUS_SUCCESS, SignatureError, XmlError,
+    certificate::decode_base64,
+    dsig::verify_enveloped,
+    xml::{Document, ElementRef},
+    xmlenc::{self, DecryptionError},
+};
+
+/// An error which occurred while validating a SAML response
+#[derive(Debug, Error)]
+pub enum ResponseError {
+    #[error("Invalid base64 encoding")]
+    Base64(#[from] base64ct::Error),
+
+    #[error("The response is not valid UTF-8")]
+    Utf8(#[from] std::string::FromUtf8Error),
+
+    #[error(transparent)]
+    Xml(#[from] XmlError),
+
+    #[error("The response is malformed: {0}")]
+    Malformed(&'static str),
+
+    #[error("The identity provider returned the status {code:?}")]
+    Status {
+        code: String,
+        message: Option<String>,
+    },
+
+    #[error("The response was sent to another destination")]
+    Destination,
+
+    #[error("The response doesn't match the authentication request")]
+    InResponseTo,
+
+    #[error("The response was not issued by the expected identity provider")]
+    Issuer,
+
+    #[error("The assertion is not signed")]
+    Unsigned,
+
+    #[error(transparent)]
+    Signature(#[from] SignatureError),
+
+    #[error("The assertion is not encrypted")]
+    Unencrypted,
+
+    #[error("The assertion is encrypted, but no decryption key is configured")]
+    NoDecryptionKey,
+
+    #[error(transparent)]
+    Decryption(#[from] DecryptionError),
+
+    #[error("The assertion is not valid yet")]
+    NotYetValid,
+
+    #[error("The assertion has expired")]
+    Expired,
+
+    #[error("The assertion is not intended for this service provider")]
+    Audience,
+
+    #[error("The assertion has no valid bearer subject confirmation")]
+    SubjectConfirmation,
+}
+
+/// An attribute of the authenticated subject
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Attribute {
+    /// The name of the attribute, usually a URI or an OID
+    pub name: String,
+
+    /// The human-readable name of the attribute, if any
+    pub friendly_name: Option<String>,
+
+    /// The values of the attribute
+    pub values: Vec<String>,
+}
+
+/// A validated assertion about the authenticated subject
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Assertion {
+    /// The ID of the assertion
+    pub id: String,
+
+    /// The entity ID of the identity provider which issued the assertion
+    pub issuer: String,
+
+    /// The name identifier of the subject
+    pub name_id: String,
+
+    /// The format of the name identifier, if specified
+    pub name_id_format: Option<String>,
+
+    /// The session index of the authentication statement, if any
+    pub session_index: Option<String>,
+
+    /// The attributes of the subject
+    pub attributes: Vec<Attribute>,
+}
+
+/// The parameters used to validate a SAML response
+#[derive(Debug, Clone)]
+pub struct ResponseValidation<'a> {
+    /// The entity ID of the identity provider
+    pub identity_provider_entity_id: &'a str,
+
+    /// The certificates trusted to sign responses and assertions
+    pub identity_provider_certificates: &'a [Certificate],
+
+    /// The entity ID of the service provider, which must be in the audience of
+    /// the assertion
+    pub service_provider_entity_id: &'a str,
+
+    /// The URL of the assertion consumer service which received the response
+    pub assertion_consumer_service_url: &'a Url,
+
+    /// The ID of the authentication request this response answers, or `None`
+    /// for unsolicited responses
+    pub in_response_to: Option<&'a str>,
+
+    /// The current time
+    pub now: DateTime<Utc>,
+
+    /// The key used to decrypt encrypted assertions
+    pub decryption_key: Option<&'a RsaPrivateKey>,
+
+    /// Whether the assertion itself must be signed, a signed response not
+    /// being enough
+    pub require_signed_assertions: bool,
+
+    /// Whether the assertion must be encrypted
+    pub require_encrypted_assertions: bool,
+
+    /// Whether to accept assertions encrypted with AES-CBC, which is
+    /// vulnerable to padding oracle attacks
+    pub allow_cbc_encryption: bool,
+}
+
+fn parse_timestamp(value: &str) -> Result<DateTime<Utc>, ResponseError> {
+    DateTime::parse_from_rfc3339(value.trim())
+        .map(|timestamp| timestamp.with_timezone(&Utc))
+        .map_err(|_| ResponseError::Malformed("invalid timestamp"))
+}
+
+fn parse_attribute(attribute: ElementRef<'_>) -> Result<Attribute, ResponseError> {
+    let name = attribute
+        .attribute("Name")
+        .ok_or(ResponseError::Malformed("missing attribute name"))?;
+    let friendly_name = attribute.attribute("FriendlyName");
+    let values = attribute
+        .children_named(ASSERTION_NS, "AttributeValue")
+        .map(|value| value.text().trim().to_owned())
+        .collect();
+
+    Ok(Attribute {
+        name: name.to_owned(),
+        friendly_name: friendly_name.map(ToOwned::to_owned),
+        values,
+    })
+}
+
+impl ResponseValidation<'_> {
+    /// The tolerated difference between the clocks of the identity provider
+    /// and ours
+    fn clock_skew() -> Duration {
+        Duration::minutes(3)
+    }
+
+    /// Validate the base64-encoded response received through the HTTP-POST
+    /// binding, returning the assertion it contains
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the response is malformed, unsuccessful, or fails
+    /// any of the checks mandated by the SAML web browser SSO profile.
+    pub fn validate(&self, saml_response: &str) -> Result<Assertion, ResponseError> {
+        let xml = String::from_utf8(decode_base64(saml_response)?)?;
+        let document = Document::parse(&xml)?;
+        let response = document.root_element();
+
+        if !response.is(PROTOCOL_NS, "Response") {
+            return Err(ResponseError::Malformed("expected a Response element"));
+        }
+        if response.attribute("Version") != Some("2.0") {
+            return Err(ResponseError::Malformed("unsupported SAML version"));
+        }
+
+        if let Some(destination) = response.attribute("Destination")
+            && destination != self.assertion_consumer_service_url.as_str()
+        {
+            return Err(ResponseError::Destination);
+        }
+
+        // Unsolicited responses must not reference a request
+        if response.attribute("InResponseTo") != self.in_response_to {
+            return Err(ResponseError::InResponseTo);
+        }
+
+        if let Some(issuer) = response.first_child(ASSERTION_NS, "Issuer")
+            && issuer.text().trim() != self.identity_provider_entity_id
+        {
+            return Err(ResponseError::Issuer);
+        }
+
+        let status_code = response
+            .first_child(PROTOCOL_NS, "Status")
+            .and_then(|status| status.first_child(PROTOCOL_NS, "StatusCode"))
+            .and_then(|code| code.attribute("Value"))
+            .ok_or(ResponseError::Malformed("missing status code"))?;
+        if status_code != STATUS_SUCCESS {
+            let message = response
+                .first_child(PROTOCOL_NS, "Status")
+                .and_then(|status| status.first_child(PROTOCOL_NS, "StatusMessage"))
+                .map(|message| message.text().trim().to_owned());
+            return Err(ResponseError::Status {
+                code: status_code.to_owned(),
+                message,
+            });
+        }
+
+        let response_signed = verify_enveloped(response, self.identity_provider_certificates)?;
+
+        // Exactly one assertion is expected, to avoid any confusion on which
+        // one was validated
+        let mut assertions = response.children_named(ASSERTION_NS, "Assertion");
+        let mut encrypted_assertions = response.children_named(ASSERTION_NS, "EncryptedAssertion");
+        match (
+            assertions.next(),
+            encrypted_assertions.next(),
+            assertions.next().is_some() || encrypted_assertions.next().is_some(),
+        ) {
+            (Some(assertion), None, false) => {
+                if self.require_encrypted_assertions {
+                    return Err(ResponseError::Unencrypted);
+                }
+
+                self.validate_assertion(assertion, response_signed)
+            }
+
+            (None, Some(encrypted), false) => {
+                let document = self.decrypt(encrypted, "Assertion")?;
+
+                // Unless the response signature already proved that the
+                // ciphertext comes from the identity provider, any detail on
+                // why the decrypted assertion is invalid would leak something
+                // about the plaintext
+                self.validate_assertion(document.root_element(), response_signed)
+                    .map_err(|error| {
+                        if response_signed {
+                            error
+                        } else {
+                            DecryptionError::Invalid.into()
+                        }
+                    })
+            }
+
+            (None, None, _) => Err(ResponseError::Malformed("missing assertion")),
+            _ => Err(ResponseError::Malformed("multiple assertions")),
+        }
+    }
+
+    /// Decrypt an element like `saml:EncryptedAssertion`, which must contain
+    /// the element with the given local name
+    ///
+    /// Once the content encryption key is decrypted, every failure results in
+    /// the same [`DecryptionError::Invalid`] error.
+    fn decrypt(
+        &self,
+        encrypted: ElementRef<'_>,
+        local_name: &str,
+    ) -> Result<Document, ResponseError> {
+        let key = self.decryption_key.ok_or(ResponseError::NoDecryptionKey)?;
+        let plaintext = xmlenc::decrypt(encrypted, key, self.allow_cbc_encryption)?;
+        let document = String::from_utf8(plaintext)
+            .ok()
+            .and_then(|plaintext| {
+                Document::parse_with_namespaces(&plaintext, encrypted.in_scope_namespaces()).ok()
+            })
+            .filter(|document| document.root_element().is(ASSERTION_NS, local_name))
+            .ok_or(DecryptionError::Invalid)?;
+        Ok(document)
+    }
+
+    fn validate_assertion(
+        &self,
+        assertion: ElementRef<'_>,
+        response_signed: bool,
+    ) -> Result<Assertion, ResponseError> {
+        if assertion.attribute("Version") != Some("2.0") {
+            return Err(ResponseError::Malformed("unsupported SAML version"));
+        }
+
+        let id = assertion
+            .attribute("ID")
+            .ok_or(ResponseError::Malformed("missing assertion ID"))?;
+
+        let issuer = assertion
+            .first_child(ASSERTION_NS, "Issuer")
+            .map(|issuer| issuer.text().trim().to_owned())
+            .ok_or(ResponseError::Malformed("missing assertion issuer"))?;
+        if issuer != self.identity_provider_entity_id {
+            return Err(ResponseError::Issuer);
+        }
+
+        let assertion_signed = verify_enveloped(assertion, self.identity_provider_certificates)?;
+        if !assertion_signed && (self.require_signed_assertions || !response_signed) {
+            return Err(ResponseError::Unsigned);
+        }
+
+        let conditions = assertion
+            .first_child(ASSERTION_NS, "Conditions")
+            .ok_or(ResponseError::Malformed("missing conditions"))?;
+        self.validate_conditions(conditions)?;
+
+        let subject = assertion
+            .first_child(ASSERTION_NS, "Subject")
+            .ok_or(ResponseError::Malformed("missing subject"))?;
+        let name_id = subject
+            .first_child(ASSERTION_NS, "NameID")
+            .ok_or(ResponseError::Malformed("missing name identifier"))?;
+        self.validate_subject_confirmation(subject)?;
+
+        let session_index = assertion
+            .first_child(ASSERTION_NS, "AuthnStatement")
+            .ok_or(ResponseError::Malformed("missing authentication statement"))?
+            .attribute("SessionIndex");
+
+        let mut attributes = Vec::new();
+        for statement in assertion.children_named(ASSERTION_NS, "AttributeStatement") {
+            for attribute in statement.children_named(ASSERTION_NS, "Attribute") {
+                attributes.push(parse_attribute(attribute)?);
+            }
+
+            for encrypted in statement.children_named(ASSERTION_NS, "EncryptedAttribute") {
+                let document = self.decrypt(encrypted, "Attribute")?;
+                attributes.push(
+                    parse_attribute(document.root_element())
+                        .map_err(|_| DecryptionError::Invalid)?,
+                );
+            }
+        }
+
+        Ok(Assertion {
+            id: id.to_owned(),
+            issuer,
+            name_id: name_id.text().trim().to_owned(),
+            name_id_format: name_id.attribute("Format").map(ToOwned::to_owned),
+            session_index: session_index.map(ToOwned::to_owned),
+            attributes,
+        })
+    }
+
+    /// Check that the subject has a bearer confirmation for us, which hasn't
+    /// expired
+    fn validate_subject_confirmation(&self, subject: ElementRef<'_>) -> Result<(), ResponseError> {
+        let skew = Self::clock_skew();
+
+        for confirmation in subject.children_named(ASSERTION_NS, "SubjectConfirmation") {
+            if confirmation.attribute("Method") != Some(BEARER) {
+                continue;
+            }
+
+            let Some(data) = confirmation.first_child(ASSERTION_NS, "SubjectConfirmationData")
+            else {
+                continue;
+            };
+
+            if data.attribute("Recipient") != Some(self.assertion_consumer_service_url.as_str()) {
+                continue;
+            }
+
+            if data.attribute("InResponseTo") != self.in_response_to {
+                continue;
+            }
+
+            let Some(not_on_or_after) = data.attribute("NotOnOrAfter") else {
+                continue;
+            };
+            if parse_timestamp(not_on_or_after)? + skew <= self.now {
+                continue;
+            }
+
+            if let Some(not_before) = data.attribute("NotBefore")
+                && parse_timestamp(not_before)? - skew > self.now
+            {
+                continue;
+            }
+
+            return Ok(());
+        }
+
+        Err(ResponseError::SubjectConfirmation)
+    }
+
+    /// Check the validity period and the audience of the assertion
+    fn validate_conditions(&self, conditions: ElementRef<'_>) -> Result<(), ResponseError> {
+        let skew = Self::clock_skew();
+
+        if let Some(not_before) = conditions.attribute("NotBefore")
+            && parse_timestamp(not_before)? - skew > self.now
+        {
+            return Err(ResponseError::NotYetValid);
+        }
+
+        if let Some(not_on_or_after) = conditions.attribute("NotOnOrAfter")
+            && parse_timestamp(not_on_or_after)? + skew <= self.now
+        {
+            return Err(ResponseError::Expired);
+        }
+
+        // The profile requires at least one audience restriction, and all of
+        // them must include us
+        let mut restrictions = conditions
+            .children_named(ASSERTION_NS, "AudienceRestriction")
+            .peekable();
+        if restrictions.peek().is_none() {
+            return Err(ResponseError::Audience);
+        }
+
+        for restriction in restrictions {
+            let found = restriction
+                .children_named(ASSERTION_NS, "Audience")
+                .any(|audience| audience.text().trim() == self.service_provider_entity_id);
+            if !found {
+                return Err(ResponseError::Audience);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_matches::assert_matches;
+    use base64ct::{Base64, Encoding};
+    use chrono::TimeZone;
+
+    use std::sync::LazyLock;
+
+    use super::*;
+    use crate::{
+        certificate::private_key_from_pem,
+        dsig::sign_enveloped,
+        xmlenc::{AES256_CBC, AES256_GCM, tests::encrypt},
+    };
+
+    const IDP: &str = "https://idp.example.com/metadata";
+    const SP: &str = "https://mas.example.com/upstream/saml2/01H8PKNWKKRPCBW4YGH1RWV279/metadata";
+    const ACS: &str = "https://mas.example.com/upstream/saml2/01H8PKNWKKRPCBW4YGH1RWV279/acs";
+
+    fn now() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 10, 18, 12, 0, 0).unwrap()
+    }
+
+    fn idp_key() -> RsaPrivateKey {
+        private_key_from_pem(include_str!("../tests/keys/idp.key.pem")).unwrap()
+    }
+
+    fn sp_key() -> RsaPrivateKey {
+        private_key_from_pem(include_str!("../tests/keys/sp.key.pem")).unwrap()
+    }
+
+    static IDP_CERTIFICATES: LazyLock<[Certificate; 1]> =
+        LazyLock::new(|| [Certificate::parse(include_str!("../tests/keys/idp.crt.pem")).unwrap()]);
+    static SP_KEY: LazyLock<RsaPrivateKey> = LazyLock::new(sp_key);
+    static ACS_URL: LazyLock<Url> = LazyLock::new(|| Url::parse(ACS).unwrap());
+
+    /// Sign an element, inserting the signature right after its issuer, as
+    /// required by the schema
+    fn sign(xml: &str) -> String {
+        let document = Document::parse(xml).unwrap();
+        let signature = sign_enveloped(document.root_element(), &idp_key(), None);
+        let index = xml.find("</saml:Issuer>").unwrap() + "</saml:Issuer>".len();
+        format!("{}{signature}{}", &xml[..index], &xml[index..])
+    }
+
+    fn assertion() -> String {
+        format!(
+            concat!(
+                r#"<saml:Assertion xmlns:saml="{ASSERTION_NS}" ID="_assertion" Version="2.0" IssueInstant="2026-10-18T11:59:50Z">"#,
+                r#"<saml:Issuer>{IDP}</saml:Issuer>"#,
+                r#"<saml:Subject>"#,
+                r#"<saml:NameID Format="urn:oasis:names:tc:SAML:2.0:nameid-format:persistent">alice</saml:NameID>"#,
+                r#"<saml:SubjectConfirmation Method="{BEARER}">"#,
+                r#"<saml:SubjectConfirmationData InResponseTo="_request" Recipient="{ACS}" NotOnOrAfter="2026-10-18T12:04:50Z"/>"#,
+                r#"</saml:SubjectConfirmation>"#,
+                r#"</saml:Subject>"#,
+                r#"<saml:Conditions NotBefore="2026-10-18T11:59:50Z" NotOnOrAfter="2026-10-18T12:04:50Z">"#,
+                r#"<saml:AudienceRestriction><saml:Audience>{SP}</saml:Audience></saml:AudienceRestriction>"#,
+                r#"</saml:Conditions>"#,
+                r#"<saml:AuthnStatement AuthnInstant="2026-10-18T11:59:50Z" SessionIndex="_session">"#,
+                r#"<saml:AuthnContext><saml:AuthnContextClassRef>urn:oasis:names:tc:SAML:2.0:ac:classes:PasswordProtectedTransport</saml:AuthnContextClassRef></saml:AuthnContext>"#,
+                r#"</saml:AuthnStatement>"#,
+                r#"<saml:AttributeStatement>"#,
+                r#"<saml:Attribute Name="urn:oid:0.9.2342.19200300.100.1.3" FriendlyName="mail"><saml:AttributeValue>alice@example.com</saml:AttributeValue></saml:Attribute>"#,
+                r#"<saml:Attribute Name="groups"><saml:AttributeValue>admins</saml:AttributeValue><saml:AttributeValue>users</saml:AttributeValue></saml:Attribute>"#,
+                r#"</saml:AttributeStatement>"#,
+                r#"</saml:Assertion>"#,
+            ),
+            ASSERTION_NS = ASSERTION_NS,
+            IDP = IDP,
+            BEARER = BEARER,
+            ACS = ACS,
+            SP = SP,
+        )
+    }
+
+    fn response(assertion: &str) -> String {
+        format!(
+            concat!(
+                r#"<samlp:Response xmlns:samlp="{PROTOCOL_NS}" xmlns:saml="{ASSERTION_NS}" ID="_response" Version="2.0" IssueInstant="2026-10-18T11:59:50Z" Destination="{ACS}" InResponseTo="_request">"#,
+                r#"<saml:Issuer>{IDP}</saml:Issuer>"#,
+                r#"<samlp:Status><samlp:StatusCode Value="{STATUS_SUCCESS}"/></samlp:Status>"#,
+                r#"{assertion}"#,
+                r#"</samlp:Response>"#,
+            ),
+            PROTOCOL_NS = PROTOCOL_NS,
+            ASSERTION_NS = ASSERTION_NS,
+            ACS = ACS,
+            IDP = IDP,
+            STATUS_SUCCESS = STATUS_SUCCESS,
+            assertion = assertion,
+        )
+    }
+
+    fn encode(xml: &str) -> String {
+        Base64::encode_string(xml.as_bytes())
+    }
+
+    fn validate<'a>(
+        response: &str,
+        customize: impl FnOnce(&mut ResponseValidation<'a>),
+    ) -> Result<Assertion, ResponseError> {
+        let mut validation = ResponseValidation {
+            identity_provider_entity_id: IDP,
+            identity_provider_certificates: &*IDP_CERTIFICATES,
+            service_provider_entity_id: SP,
+            assertion_consumer_service_url: &ACS_URL,
+            in_response_to: Some("_request"),
+            now: now(),
+            decryption_key: Some(&SP_KEY),
+            require_signed_assertions: true,
+            require_encrypted_assertions: false,
+            allow_cbc_encryption: false,
+        };
+        customize(&mut validation);
+        validation.validate(&encode(response))
+    }
+
+    #[test]
+    fn test_signed_assertion() {
+        let response = response(&sign(&assertion()));
+        let assertion = validate(&response, |_| {}).unwrap();
+
+        assert_eq!(assertion.id, "_assertion");
+        assert_eq!(assertion.issuer, IDP);
+        assert_eq!(assertion.name_id, "alice");
+        assert_eq!(
+            assertion.name_id_format.as_deref(),
+            Some("urn:oasis:names:tc:SAML:2.0:nameid-format:persistent")
+        );
+        assert_eq!(assertion.session_index.as_deref(), Some("_session"));
+        assert_eq!(
+            assertion.attributes,
+            vec![
+                Attribute {
+                    name: "urn:oid:0.9.2342.19200300.100.1.3".to_owned(),
+                    friendly_name: Some("mail".to_owned()),
+                    values: vec!["alice@example.com".to_owned()],
+                },
+                Attribute {
+                    name: "groups".to_owned(),
+                    friendly_name: None,
+                    values: vec!["admins".to_owned(), "users".to_owned()],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_signed_response() {
+        let response = sign(&response(&assertion()));
+
+        // A signed response is not enough if the assertion must be signed
+        assert_matches!(validate(&response, |_| {}), Err(ResponseError::Unsigned));
+
+        let assertion = validate(&response, |v| v.require_signed_assertions = false).unwrap();
+        assert_eq!(assertion.name_id, "alice");
+
+        // Nothing signed at all is always rejected
+        let response = self::response(&self::assertion());
+        assert_matches!(
+            validate(&response, |v| v.require_signed_assertions = false),
+            Err(ResponseError::Unsigned)
+        );
+    }
+
+    /// Responses signed by an independent implementation, see
+    /// `tests/generate.py`
+    #[test]
+    fn test_known_signed_responses() {
+        let response = include_str!("../tests/responses/signed-assertion.xml");
+        let assertion = validate(response, |_| {}).unwrap();
+        assert_eq!(assertion.id, "_assertion");
+        assert_eq!(assertion.name_id, "alice");
+        assert_eq!(assertion.session_index.as_deref(), Some("_session"));
+        assert_eq!(
+            assertion.attributes[1].values,
+            ["admins".to_owned(), "users & friends".to_owned()]
+        );
+
+        // The response itself isn't signed
+        assert_matches!(
+            validate(response, |v| v.require_signed_assertions = false),
+            Ok(_)
+        );
+        let tampered = response.replace("alice@example.com", "mallory@example.com");
+        assert_matches!(
+            validate(&tampered, |_| {}),
+            Err(ResponseError::Signature(SignatureError::DigestMismatch))
+        );
+
+        let response = include_str!("../tests/responses/signed-response.xml");
+        let assertion = validate(response, |_| {}).unwrap();
+        assert_eq!(assertion.name_id, "alice");
+
+        // Changing the response outside of the assertion breaks its signature
+        let tampered = response.replace(
+            r#"IssueInstant="2026-10-18T11:59:50Z" Destination"#,
+            r#"IssueInstant="2026-10-18T11:59:51Z" Destination"#,
+        );
+        assert_matches!(
+            validate(&tampered, |_| {}),
+            Err(ResponseError::Signature(SignatureError::DigestMismatch))
+        );
+    }
+
+    #[test]
+    fn test_untrusted_signature() {
+        let response = response(&sign(&assertion()));
+        let other = Certificate::parse(include_str!("../tests/keys/sp.crt.pem")).unwrap();
+        let certificates = [other];
+        assert_matches!(
+            validate(&response, |v| v.identity_provider_certificates =
+                &certificates),
+            Err(ResponseError::Signature(SignatureError::Invalid))
+        );
+    }
+
+    #[test]
+    fn test_signature_wrapping() {
+        // Adding an unsigned assertion next to the signed one must not work
+        let forged = assertion()
+            .replace("_assertion", "_forged")
+            .replace(">alice<", ">admin<");
+        let response = response(&format!("{forged}{}", sign(&assertion())));
+        assert_matches!(
+            validate(&response, |_| {}),
+            Err(ResponseError::Malformed("multiple assertions"))
+        );
+
+        // Neither must modifying the signed assertion
+        let response = self::response(&sign(&assertion()).replace(">alice<", ">admin<"));
+        assert_matches!(
+            validate(&response, |_| {}),
+            Err(ResponseError::Signature(SignatureError::DigestMismatch))
+        );
+    }
+
+    #[test]
+    fn test_encrypted_assertion() {
+        let encrypted = encrypt(&sign(&assertion()), &sp_key().to_public_key(), AES256_GCM);
+        let response = response(&encrypted);
+
+        let assertion = validate(&response, |v| v.require_encrypted_assertions = true).unwrap();
+        assert_eq!(assertion.name_id, "alice");
+
+        assert_matches!(
+            validate(&response, |v| v.decryption_key = None),
+            Err(ResponseError::NoDecryptionKey)
+        );
+
+        let response = self::response(&sign(&self::assertion()));
+        assert_matches!(
+            validate(&response, |v| v.require_encrypted_assertions = true),
+            Err(ResponseError::Unencrypted)
+        );
+    }
+
+    #[test]
+    fn test_encrypted_assertion_cbc() {
+        let encrypted = encrypt(&sign(&assertion()), &sp_key().to_public_key(), AES256_CBC);
+        let response = response(&encrypted);
+
+        assert_matches!(
+            validate(&response, |_| {}),
+            Err(ResponseError::Decryption(
+                DecryptionError::UnsupportedAlgorithm(_)
+            ))
+        );
+
+        let assertion = validate(&response, |v| v.allow_cbc_encryption = true).unwrap();
+        assert_eq!(assertion.name_id, "alice");
+    }
+
+    #[test]
+    fn test_encrypted_assertion_opaque_errors() {
+        // Something which isn't an assertion
+        let encrypted = encrypt("<Hello/>", &sp_key().to_public_key(), AES256_GCM);
+        assert_matches!(
+            validate(&response(&encrypted), |_| {}),
+            Err(ResponseError::Decryption(DecryptionError::Invalid))
+        );
+
+        // Something which isn't XML
+        let encrypted = encrypt("Hello", &sp_key().to_public_key(), AES256_GCM);
+        assert_matches!(
+            validate(&response(&encrypted), |_| {}),
+            Err(ResponseError::Decryption(DecryptionError::Invalid))
+        );
+
+        // An unsigned response doesn't tell why the decrypted assertion is
+        // invalid
+        let encrypted = encrypt(&sign(&assertion()), &sp_key().to_public_key(), AES256_GCM);
+        let response = response(&encrypted);
+        assert_matches!(
+            validate(&response, |v| v.now = now() + Duration::hours(1)),
+            Err(ResponseError::Decryption(DecryptionError::Invalid))
+        );
+
+        // A signed one does, as the ciphertext can't have been tampered with
+        let response = sign(&response);
+        assert_matches!(
+            validate(&response, |v| v.now = now() + Duration::hours(1)),
+            Err(ResponseError::Expired)
+        );
+    }
+
+    #[test]
+    fn test_request_correlation() {
+        let response = response(&sign(&assertion()));
+        assert_matches!(
+            validate(&response, |v| v.in_response_to = Some("_other")),
+            Err(ResponseError::InResponseTo)
+        );
+
+        // Unsolicited responses must not reference a request
+        assert_matches!(
+            validate(&response, |v| v.in_response_to = None),
+            Err(ResponseError::InResponseTo)
+        );
+
+        let unsolicited = self::response(&sign(
+            &assertion().replace(r#" InResponseTo="_request""#, ""),
+        ))
+        .replace(r#" InResponseTo="_request""#, "");
+        let assertion = validate(&unsolicited, |v| v.in_response_to = None).unwrap();
+        assert_eq!(assertion.name_id, "alice");
+    }
+
+    #[test]
+    fn test_conditions() {
+        let response = response(&sign(&assertion()));
+
+        // Within the clock skew
+        validate(&response, |v| v.now = now() + Duration::minutes(6)).unwrap();
+
+        assert_matches!(
+            validate(&response, |v| v.now = now() + Duration::minutes(10)),
+            Err(ResponseError::Expired)
+        );
+        assert_matches!(
+            validate(&response, |v| v.now = now() - Duration::minutes(10)),
+            Err(ResponseError::NotYetValid)
+        );
+        assert_matches!(
+            validate(&response, |v| v.service_provider_entity_id =
+                "https://other.example.com/"),
+            Err(ResponseError::Audience)
+        );
+
+        let acs_url = Url::parse("https://mas.example.com/other").unwrap();
+        assert_matches!(
+            validate(&response, |v| v.assertion_consumer_service_url = &acs_url),
+            Err(ResponseError::Destination)
+        );
+        assert_matches!(
+            validate(&response, |v| v.identity_provider_entity_id =
+                "https://other.example.com/"),
+            Err(ResponseError::Issuer)
+        );
+    }
+
+    #[test]
+    fn test_error_status() {
+        let response = response("").replace(
+            &format!(r#"<samlp:StatusCode Value="{STATUS_SUCCESS}"/>"#),
+            r#"<samlp:StatusCode Value="urn:oasis:names:tc:SAML:2.0:status:Responder"/><samlp:StatusMessage>Access denied</samlp:StatusMessage>"#,
+        );
+        assert_matches!(
+            validate(&response, |_| {}),
+            Err(ResponseError::Status { code, message })
+                if code == "urn:oasis:names:tc:SAML:2.0:status:Responder"
+                    && message.as_deref() == Some("Access denied")
+        );
+    }
+}
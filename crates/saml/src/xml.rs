@@ -0,0 +1,818 @@
+// Copyright 2026 Element Creations Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+// Please see LICENSE files in the repository root for full details.
+
+//! A minimal, non-validating XML parser producing a read-only tree
+//!
+//! SAML messages only use a small subset of XML: they don't need document
+//! type declarations, external entities or custom entities. Documents using
+//! any of those are rejected, which rules out entity expansion and external
+//! entity attacks.
+//!
+//! The tree keeps the namespace prefixes as they appear in the document, as
+//! they are needed to canonicalize elements when verifying signatures.
+
+use std::borrow::Cow;
+
+use thiserror::Error;
+
+/// The namespace bound to the `xml` prefix
+pub(crate) const XML_NS: &str = "http://www.w3.org/XML/1998/namespace";
+
+/// The maximum depth of nested elements
+const MAX_DEPTH: usize = 64;
+
+/// The maximum size of a document, in bytes
+const MAX_SIZE: usize = 1024 * 1024;
+
+/// An error which occurred while parsing an XML document
+#[derive(Debug, Error)]
+#[error("Invalid XML document at offset {offset}: {message}")]
+pub struct XmlError {
+    offset: usize,
+    message: &'static str,
+}
+
+pub(crate) type NodeId = usize;
+
+/// A namespace declaration, with `None` as prefix for the default namespace
+pub(crate) type NamespaceDeclaration = (Option<String>, String);
+
+#[derive(Debug)]
+pub(crate) enum Node {
+    Element(Element),
+    Text(String),
+    ProcessingInstruction { target: String, data: String },
+}
+
+#[derive(Debug)]
+pub(crate) struct Attribute {
+    pub prefix: Option<String>,
+    pub local_name: String,
+    pub value: String,
+}
+
+#[derive(Debug)]
+pub(crate) struct Element {
+    prefix: Option<String>,
+    local_name: String,
+    namespaces: Vec<NamespaceDeclaration>,
+    attributes: Vec<Attribute>,
+    children: Vec<NodeId>,
+    parent: Option<NodeId>,
+}
+
+/// A parsed XML document
+#[derive(Debug)]
+pub(crate) struct Document {
+    nodes: Vec<Node>,
+    root: NodeId,
+
+    /// Namespaces in scope for the root element, when parsing a fragment
+    /// extracted from another document
+    inherited_namespaces: Vec<NamespaceDeclaration>,
+}
+
+impl Document {
+    /// Parse a standalone XML document
+    pub fn parse(input: &str) -> Result<Self, XmlError> {
+        Self::parse_with_namespaces(input, Vec::new())
+    }
+
+    /// Parse an XML document which was extracted from another document, with
+    /// the given namespace declarations in scope
+    pub fn parse_with_namespaces(
+        input: &str,
+        inherited_namespaces: Vec<NamespaceDeclaration>,
+    ) -> Result<Self, XmlError> {
+        if input.len() > MAX_SIZE {
+            return Err(XmlError {
+                offset: MAX_SIZE,
+                message: "document is too large",
+            });
+        }
+
+        let mut parser = Parser {
+            input,
+            pos: 0,
+            nodes: Vec::new(),
+            inherited_namespaces: &inherited_namespaces,
+        };
+        let root = parser.parse_document()?;
+        let nodes = parser.nodes;
+
+        Ok(Self {
+            nodes,
+            root,
+            inherited_namespaces,
+        })
+    }
+
+    /// The root element of the document
+    pub fn root_element(&self) -> ElementRef<'_> {
+        self.element(self.root)
+            .expect("the root of a document is always an element")
+    }
+
+    pub fn node(&self, id: NodeId) -> &Node {
+        &self.nodes[id]
+    }
+
+    fn element(&self, id: NodeId) -> Option<ElementRef<'_>> {
+        match &self.nodes[id] {
+            Node::Element(element) => Some(ElementRef {
+                document: self,
+                id,
+                element,
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// A reference to an element in a [`Document`]
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ElementRef<'a> {
+    document: &'a Document,
+    id: NodeId,
+    element: &'a Element,
+}
+
+impl<'a> ElementRef<'a> {
+    pub fn id(&self) -> NodeId {
+        self.id
+    }
+
+    pub fn prefix(&self) -> Option<&'a str> {
+        self.element.prefix.as_deref()
+    }
+
+    pub fn local_name(&self) -> &'a str {
+        &self.element.local_name
+    }
+
+    /// The namespace of the element, if any
+    pub fn namespace(&self) -> Option<&'a str> {
+        self.lookup_namespace(self.prefix())
+    }
+
+    /// Check if this element has the given namespace and local name
+    pub fn is(&self, namespace: &str, local_name: &str) -> bool {
+        self.local_name() == local_name && self.namespace() == Some(namespace)
+    }
+
+    /// Get the value of an attribute without a namespace
+    pub fn attribute(&self, name: &str) -> Option<&'a str> {
+        self.element
+            .attributes
+            .iter()
+            .find(|attribute| attribute.prefix.is_none() && attribute.local_name == name)
+            .map(|attribute| attribute.value.as_str())
+    }
+
+    pub fn attributes(&self) -> &'a [Attribute] {
+        &self.element.attributes
+    }
+
+    pub fn parent(&self) -> Option<ElementRef<'a>> {
+        self.element
+            .parent
+            .and_then(|parent| self.document.element(parent))
+    }
+
+    /// All the child nodes of this element
+    pub fn child_nodes(&self) -> impl Iterator<Item = (NodeId, &'a Node)> + use<'a> {
+        let document = self.document;
+        self.element
+            .children
+            .iter()
+            .map(move |&id| (id, document.node(id)))
+    }
+
+    /// The child elements of this element
+    pub fn children(&self) -> impl Iterator<Item = ElementRef<'a>> + use<'a> {
+        let document = self.document;
+        self.element
+            .children
+            .iter()
+            .filter_map(move |&id| document.element(id))
+    }
+
+    /// The child elements with the given namespace and local name
+    pub fn children_named<'n>(
+        &self,
+        namespace: &'n str,
+        local_name: &'n str,
+    ) -> impl Iterator<Item = ElementRef<'a>> + use<'a, 'n> {
+        self.children()
+            .filter(move |child| child.is(namespace, local_name))
+    }
+
+    /// The first child element with the given namespace and local name
+    pub fn first_child(&self, namespace: &str, local_name: &str) -> Option<ElementRef<'a>> {
+        self.children_named(namespace, local_name).next()
+    }
+
+    /// The concatenation of the text nodes directly under this element
+    pub fn text(&self) -> String {
+        self.child_nodes()
+            .filter_map(|(_, node)| match node {
+                Node::Text(text) => Some(text.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Find the namespace bound to a prefix in the scope of this element, the
+    /// `None` prefix being the default namespace
+    pub fn lookup_namespace(&self, prefix: Option<&str>) -> Option<&'a str> {
+        if prefix == Some("xml") {
+            return Some(XML_NS);
+        }
+
+        let mut current = Some(*self);
+        while let Some(element) = current {
+            if let Some((_, uri)) = element
+                .element
+                .namespaces
+                .iter()
+                .find(|(p, _)| p.as_deref() == prefix)
+            {
+                return Some(uri.as_str()).filter(|uri| !uri.is_empty());
+            }
+            current = element.parent();
+        }
+
+        self.document
+            .inherited_namespaces
+            .iter()
+            .find(|(p, _)| p.as_deref() == prefix)
+            .map(|(_, uri)| uri.as_str())
+            .filter(|uri| !uri.is_empty())
+    }
+
+    /// All the namespace declarations in scope for this element
+    pub fn in_scope_namespaces(&self) -> Vec<NamespaceDeclaration> {
+        let mut namespaces: Vec<NamespaceDeclaration> = Vec::new();
+        let mut current = Some(*self);
+        while let Some(element) = current {
+            for (prefix, uri) in &element.element.namespaces {
+                if !namespaces.iter().any(|(p, _)| p == prefix) {
+                    namespaces.push((prefix.clone(), uri.clone()));
+                }
+            }
+            current = element.parent();
+        }
+
+        for (prefix, uri) in &self.document.inherited_namespaces {
+            if !namespaces.iter().any(|(p, _)| p == prefix) {
+                namespaces.push((prefix.clone(), uri.clone()));
+            }
+        }
+
+        namespaces
+    }
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    pos: usize,
+    nodes: Vec<Node>,
+    inherited_namespaces: &'a [NamespaceDeclaration],
+}
+
+impl<'a> Parser<'a> {
+    fn error(&self, message: &'static str) -> XmlError {
+        XmlError {
+            offset: self.pos,
+            message,
+        }
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.input[self.pos..]
+    }
+
+    fn starts_with(&self, prefix: &str) -> bool {
+        self.rest().starts_with(prefix)
+    }
+
+    fn expect(&mut self, token: &str, message: &'static str) -> Result<(), XmlError> {
+        if self.starts_with(token) {
+            self.pos += token.len();
+            Ok(())
+        } else {
+            Err(self.error(message))
+        }
+    }
+
+    /// Skip whitespace, returning whether any whitespace was skipped
+    fn skip_whitespace(&mut self) -> bool {
+        let rest = self.rest();
+        let trimmed = rest.trim_start_matches([' ', '\t', '\r', '\n']);
+        self.pos += rest.len() - trimmed.len();
+        rest.len() != trimmed.len()
+    }
+
+    /// Consume everything up to the given delimiter, and the delimiter itself
+    fn take_until(&mut self, delimiter: &str, message: &'static str) -> Result<&'a str, XmlError> {
+        let rest = self.rest();
+        let Some(index) = rest.find(delimiter) else {
+            return Err(self.error(message));
+        };
+        self.pos += index + delimiter.len();
+        Ok(&rest[..index])
+    }
+
+    fn parse_document(&mut self) -> Result<NodeId, XmlError> {
+        if self.starts_with("\u{feff}") {
+            self.pos += '\u{feff}'.len_utf8();
+        }
+
+        if self.starts_with("<?xml") && self.rest()[5..].starts_with([' ', '\t', '\r', '\n', '?']) {
+            self.take_until("?>", "unterminated XML declaration")?;
+        }
+
+        self.skip_misc()?;
+
+        if self.starts_with("<!DOCTYPE") {
+            return Err(self.error("document type declarations are not supported"));
+        }
+
+        if !self.starts_with("<") {
+            return Err(self.error("expected the root element"));
+        }
+
+        let root = self.parse_element(None, 0)?;
+
+        self.skip_misc()?;
+        if !self.rest().is_empty() {
+            return Err(self.error("unexpected content after the root element"));
+        }
+
+        Ok(root)
+    }
+
+    /// Skip comments, processing instructions and whitespace outside of the
+    /// root element
+    fn skip_misc(&mut self) -> Result<(), XmlError> {
+        loop {
+            self.skip_whitespace();
+            if self.starts_with("<!--") {
+                self.pos += 4;
+                self.take_until("-->", "unterminated comment")?;
+            } else if self.starts_with("<?") {
+                self.pos += 2;
+                self.take_until("?>", "unterminated processing instruction")?;
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    fn parse_name(&mut self) -> Result<&'a str, XmlError> {
+        let rest = self.rest();
+        let end = rest
+            .char_indices()
+            .find(|&(_, c)| !is_name_char(c))
+            .map_or(rest.len(), |(index, _)| index);
+        let name = &rest[..end];
+
+        match name.chars().next() {
+            Some(c) if is_name_start_char(c) => {}
+            _ => return Err(self.error("expected a name")),
+        }
+
+        self.pos += end;
+        Ok(name)
+    }
+
+    fn parse_attribute_value(&mut self) -> Result<String, XmlError> {
+        let Some(quote @ ('"' | '\'')) = self.rest().chars().next() else {
+            return Err(self.error("expected a quoted attribute value"));
+        };
+        self.pos += 1;
+
+        let start = self.pos;
+        let rest = self.rest();
+        let Some(end) = rest.find(quote) else {
+            return Err(self.error("unterminated attribute value"));
+        };
+        self.pos += end + 1;
+        decode(&rest[..end], start, true)
+    }
+
+    fn parse_element(&mut self, parent: Option<NodeId>, depth: usize) -> Result<NodeId, XmlError> {
+        if depth >= MAX_DEPTH {
+            return Err(self.error("elements are nested too deeply"));
+        }
+
+        let start = self.pos;
+        self.expect("<", "expected an element")?;
+        let name = self.parse_name()?;
+
+        let mut raw_attributes: Vec<(&str, String)> = Vec::new();
+        let empty = loop {
+            let had_whitespace = self.skip_whitespace();
+            if self.starts_with("/>") {
+                self.pos += 2;
+                break true;
+            }
+            if self.starts_with(">") {
+                self.pos += 1;
+                break false;
+            }
+            if !had_whitespace {
+                return Err(self.error("expected whitespace before an attribute"));
+            }
+
+            let attribute_name = self.parse_name()?;
+            self.skip_whitespace();
+            self.expect("=", "expected '=' after an attribute name")?;
+            self.skip_whitespace();
+            let value = self.parse_attribute_value()?;
+
+            if raw_attributes.iter().any(|(n, _)| *n == attribute_name) {
+                return Err(self.error("duplicate attribute"));
+            }
+            raw_attributes.push((attribute_name, value));
+        };
+
+        let (prefix, local_name) =
+            split_qname(name).ok_or_else(|| self.error("invalid element name"))?;
+
+        let mut namespaces = Vec::new();
+        let mut attributes = Vec::new();
+        for (attribute_name, value) in raw_attributes {
+            if attribute_name == "xmlns" {
+                namespaces.push((None, value));
+            } else if let Some(declared) = attribute_name.strip_prefix("xmlns:") {
+                if value.is_empty() {
+                    return Err(self.error("namespace prefixes can't be undeclared"));
+                }
+                namespaces.push((Some(declared.to_owned()), value));
+            } else {
+                let (prefix, local_name) = split_qname(attribute_name)
+                    .ok_or_else(|| self.error("invalid attribute name"))?;
+                attributes.push(Attribute {
+                    prefix: prefix.map(ToOwned::to_owned),
+                    local_name: local_name.to_owned(),
+                    value,
+                });
+            }
+        }
+
+        let id = self.nodes.len();
+        self.nodes.push(Node::Element(Element {
+            prefix: prefix.map(ToOwned::to_owned),
+            local_name: local_name.to_owned(),
+            namespaces,
+            attributes,
+            children: Vec::new(),
+            parent,
+        }));
+
+        // Make sure all the prefixes used by the element and its attributes are
+        // declared
+        {
+            let document = PartialDocument {
+                nodes: &self.nodes,
+                inherited_namespaces: self.inherited_namespaces,
+            };
+            let Node::Element(element) = &self.nodes[id] else {
+                unreachable!()
+            };
+            let prefixes = element
+                .prefix
+                .iter()
+                .chain(element.attributes.iter().filter_map(|a| a.prefix.as_ref()));
+            for prefix in prefixes {
+                if !document.is_prefix_bound(id, prefix) {
+                    return Err(XmlError {
+                        offset: start,
+                        message: "undeclared namespace prefix",
+                    });
+                }
+            }
+        }
+
+        if !empty {
+            self.parse_content(id, depth)?;
+            self.expect("</", "expected a closing tag")?;
+            let closing = self.parse_name()?;
+            if closing != name {
+                return Err(self.error("mismatched closing tag"));
+            }
+            self.skip_whitespace();
+            self.expect(">", "expected '>' after a closing tag")?;
+        }
+
+        Ok(id)
+    }
+
+    fn push_child(&mut self, parent: NodeId, node: Node) {
+        // Merge adjacent text nodes, which happens around CDATA sections and
+        // comments
+        if let Node::Text(text) = &node
+            && let Node::Element(element) = &self.nodes[parent]
+            && let Some(&last) = element.children.last()
+            && let Node::Text(previous) = &mut self.nodes[last]
+        {
+            previous.push_str(text);
+            return;
+        }
+
+        let id = self.nodes.len();
+        self.nodes.push(node);
+        self.add_child(parent, id);
+    }
+
+    fn add_child(&mut self, parent: NodeId, child: NodeId) {
+        if let Node::Element(element) = &mut self.nodes[parent] {
+            element.children.push(child);
+        }
+    }
+
+    fn parse_content(&mut self, id: NodeId, depth: usize) -> Result<(), XmlError> {
+        loop {
+            if self.rest().is_empty() {
+                return Err(self.error("unexpected end of document"));
+            }
+
+            if self.starts_with("</") {
+                return Ok(());
+            } else if self.starts_with("<!--") {
+                self.pos += 4;
+                self.take_until("-->", "unterminated comment")?;
+            } else if self.starts_with("<![CDATA[") {
+                self.pos += 9;
+                let text = self.take_until("]]>", "unterminated CDATA section")?;
+                self.push_child(id, Node::Text(normalize_newlines(text).into_owned()));
+            } else if self.starts_with("<?") {
+                self.pos += 2;
+                let target = self.parse_name()?;
+                if target.eq_ignore_ascii_case("xml") {
+                    return Err(self.error("misplaced XML declaration"));
+                }
+                self.skip_whitespace();
+                let data = self.take_until("?>", "unterminated processing instruction")?;
+                self.push_child(
+                    id,
+                    Node::ProcessingInstruction {
+                        target: target.to_owned(),
+                        data: normalize_newlines(data).into_owned(),
+                    },
+                );
+            } else if self.starts_with("<!") {
+                return Err(self.error("unsupported markup declaration"));
+            } else if self.starts_with("<") {
+                let child = self.parse_element(Some(id), depth + 1)?;
+                self.add_child(id, child);
+            } else {
+                let start = self.pos;
+                let rest = self.rest();
+                let end = rest.find('<').unwrap_or(rest.len());
+                self.pos += end;
+                let text = decode(&rest[..end], start, false)?;
+                self.push_child(id, Node::Text(text));
+            }
+        }
+    }
+}
+
+/// A view over the nodes parsed so far, used to resolve namespace prefixes
+/// while parsing
+struct PartialDocument<'a> {
+    nodes: &'a [Node],
+    inherited_namespaces: &'a [NamespaceDeclaration],
+}
+
+impl PartialDocument<'_> {
+    fn is_prefix_bound(&self, mut id: NodeId, prefix: &str) -> bool {
+        if prefix == "xml" {
+            return true;
+        }
+
+        loop {
+            let Node::Element(element) = &self.nodes[id] else {
+                return false;
+            };
+
+            if element
+                .namespaces
+                .iter()
+                .any(|(p, _)| p.as_deref() == Some(prefix))
+            {
+                return true;
+            }
+
+            match element.parent {
+                Some(parent) => id = parent,
+                None => break,
+            }
+        }
+
+        self.inherited_namespaces
+            .iter()
+            .any(|(p, _)| p.as_deref() == Some(prefix))
+    }
+}
+
+fn is_name_start_char(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_' || c == ':' || !c.is_ascii()
+}
+
+fn is_name_char(c: char) -> bool {
+    is_name_start_char(c) || c.is_ascii_digit() || c == '-' || c == '.'
+}
+
+/// Split a qualified name into its prefix and local name
+fn split_qname(name: &str) -> Option<(Option<&str>, &str)> {
+    match name.split_once(':') {
+        None => Some((None, name)),
+        Some((prefix, local_name))
+            if !prefix.is_empty() && !local_name.is_empty() && !local_name.contains(':') =>
+        {
+            Some((Some(prefix), local_name))
+        }
+        Some(_) => None,
+    }
+}
+
+fn normalize_newlines(text: &str) -> Cow<'_, str> {
+    if text.contains('\r') {
+        Cow::Owned(text.replace("\r\n", "\n").replace('\r', "\n"))
+    } else {
+        Cow::Borrowed(text)
+    }
+}
+
+/// Decode character and entity references, and normalize newlines.
+///
+/// In attribute values, literal whitespace characters are also normalized to
+/// spaces.
+fn decode(raw: &str, offset: usize, in_attribute: bool) -> Result<String, XmlError> {
+    let raw = normalize_newlines(raw);
+    let mut output = String::with_capacity(raw.len());
+    let mut rest: &str = &raw;
+
+    while let Some(index) = rest.find(['&', '<', '\t', '\n']) {
+        output.push_str(&rest[..index]);
+        let c = rest[index..].chars().next().unwrap_or_default();
+        rest = &rest[index + 1..];
+
+        match c {
+            '&' => {
+                let Some(end) = rest.find(';') else {
+                    return Err(XmlError {
+                        offset,
+                        message: "unterminated entity reference",
+                    });
+                };
+                let reference = &rest[..end];
+                rest = &rest[end + 1..];
+
+                let decoded = match reference {
+                    "lt" => '<',
+                    "gt" => '>',
+                    "amp" => '&',
+                    "apos" => '\'',
+                    "quot" => '"',
+                    _ => {
+                        let code = if let Some(hex) = reference.strip_prefix("#x") {
+                            u32::from_str_radix(hex, 16).ok()
+                        } else if let Some(decimal) = reference.strip_prefix('#') {
+                            decimal.parse().ok()
+                        } else {
+                            return Err(XmlError {
+                                offset,
+                                message: "unknown entity reference",
+                            });
+                        };
+
+                        code.and_then(char::from_u32)
+                            .filter(|&c| c != '\0')
+                            .ok_or(XmlError {
+                                offset,
+                                message: "invalid character reference",
+                            })?
+                    }
+                };
+                output.push(decoded);
+            }
+            '<' if in_attribute => {
+                return Err(XmlError {
+                    offset,
+                    message: "'<' is not allowed in attribute values",
+                });
+            }
+            '\t' | '\n' if in_attribute => output.push(' '),
+            c => output.push(c),
+        }
+    }
+
+    output.push_str(rest);
+    Ok(output)
+}
+
+/// Escape a string to be used as text content
+pub(crate) fn escape_text(text: &str) -> Cow<'_, str> {
+    if !text.contains(['&', '<', '>']) {
+        return Cow::Borrowed(text);
+    }
+
+    Cow::Owned(
+        text.replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;"),
+    )
+}
+
+/// Escape a string to be used as a double-quoted attribute value
+pub(crate) fn escape_attribute(value: &str) -> Cow<'_, str> {
+    if !value.contains(['&', '<', '"']) {
+        return Cow::Borrowed(value);
+    }
+
+    Cow::Owned(
+        value
+            .replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('"', "&quot;"),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_namespaces() {
+        let document = Document::parse(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+            <!-- A comment -->
+            <a:root xmlns:a="urn:a" xmlns="urn:default" attr="1">
+                <child a:attr="2">Hello &amp; <![CDATA[<world>]]>&#x21;</child>
+                <other xmlns="">text</other>
+            </a:root>"#,
+        )
+        .unwrap();
+
+        let root = document.root_element();
+        assert!(root.is("urn:a", "root"));
+        assert_eq!(root.attribute("attr"), Some("1"));
+
+        let child = root.first_child("urn:default", "child").unwrap();
+        assert_eq!(child.text(), "Hello & <world>!");
+        assert_eq!(child.attribute("attr"), None);
+        assert_eq!(child.attributes()[0].prefix.as_deref(), Some("a"));
+
+        let other = root.children().nth(1).unwrap();
+        assert_eq!(other.local_name(), "other");
+        assert_eq!(other.namespace(), None);
+        assert_eq!(other.parent().unwrap().id(), root.id());
+    }
+
+    #[test]
+    fn test_attribute_normalization() {
+        let document = Document::parse("<root attr='a\r\nb\tc&#10;d'/>").unwrap();
+        assert_eq!(document.root_element().attribute("attr"), Some("a b c\nd"));
+    }
+
+    #[test]
+    fn test_reject_invalid_documents() {
+        // Document type declarations, which could be used for entity expansion
+        Document::parse(r#"<!DOCTYPE root [<!ENTITY a "aaaaaaaa">]><root>&a;</root>"#).unwrap_err();
+        // Unknown entities
+        Document::parse("<root>&a;</root>").unwrap_err();
+        // Undeclared prefixes
+        Document::parse("<a:root/>").unwrap_err();
+        Document::parse("<root a:attr='1'/>").unwrap_err();
+        // Mismatched tags
+        Document::parse("<root></other>").unwrap_err();
+        // Trailing content
+        Document::parse("<root/><root/>").unwrap_err();
+        // Duplicate attributes
+        Document::parse("<root a='1' a='2'/>").unwrap_err();
+        // Unterminated documents
+        Document::parse("<root><child>").unwrap_err();
+    }
+
+    #[test]
+    fn test_inherited_namespaces() {
+        Document::parse("<a:root/>").unwrap_err();
+        let document = Document::parse_with_namespaces(
+            "<a:root/>",
+            vec![(Some("a".to_owned()), "urn:a".to_owned())],
+        )
+        .unwrap();
+        assert!(document.root_element().is("urn:a", "root"));
+    }
+
+    #[test]
+    fn test_depth_limit() {
+        let input = format!("{}{}", "<a>".repeat(100), "</a>".repeat(100));
+        Document::parse(&input).unwrap_err();
+    }
+}
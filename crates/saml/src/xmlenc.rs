@@ -0,0 +1,340 @@
+// Copyright 2026 Element Creations Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+// Please see LICENSE files in the repository root for full details.
+
+//! XML encryption, as used by SAML to encrypt assertions
+//!
+//! Only decryption is supported. The content encryption key must be wrapped
+//! with RSA-OAEP: RSA PKCS#1 v1.5 key transport is vulnerable to padding
+//! oracle attacks and is rejected. For the same reason, AES-CBC content
+//! encryption is only accepted when explicitly allowed.
+//!
+//! Once the content encryption key has been decrypted, every failure is
+//! reported with the same [`DecryptionError::Invalid`] error, so that an
+//! attacker submitting tampered ciphertexts can't learn anything about the
+//! plaintext.
+
+use aes_gcm::{
+    Aes128Gcm, Aes256Gcm, KeyInit,
+    aead::{Aead, Nonce},
+};
+use cbc::cipher::{BlockDecryptMut, KeyIvInit, block_padding::NoPadding};
+use rsa::{Oaep, RsaPrivateKey};
+use sha1::Sha1;
+use sha2::Sha256;
+use thiserror::Error;
+
+use crate::{certificate::decode_base64, dsig::DSIG_NS, xml::ElementRef};
+
+pub(crate) const XENC_NS: &str = "http://www.w3.org/2001/04/xmlenc#";
+const XENC11_NS: &str = "http://www.w3.org/2009/xmlenc11#";
+
+pub(crate) const AES128_CBC: &str = "http://www.w3.org/2001/04/xmlenc#aes128-cbc";
+pub(crate) const AES256_CBC: &str = "http://www.w3.org/2001/04/xmlenc#aes256-cbc";
+pub(crate) const AES128_GCM: &str = "http://www.w3.org/2009/xmlenc11#aes128-gcm";
+pub(crate) const AES256_GCM: &str = "http://www.w3.org/2009/xmlenc11#aes256-gcm";
+pub(crate) const RSA_OAEP_MGF1P: &str = "http://www.w3.org/2001/04/xmlenc#rsa-oaep-mgf1p";
+pub(crate) const RSA_OAEP: &str = "http://www.w3.org/2009/xmlenc11#rsa-oaep";
+
+const SHA1: &str = "http://www.w3.org/2000/09/xmldsig#sha1";
+const SHA256: &str = "http://www.w3.org/2001/04/xmlenc#sha256";
+const MGF1_SHA1: &str = "http://www.w3.org/2009/xmlenc11#mgf1sha1";
+const MGF1_SHA256: &str = "http://www.w3.org/2009/xmlenc11#mgf1sha256";
+
+/// An error which occurred while decrypting an encrypted element
+#[derive(Debug, Error)]
+pub enum DecryptionError {
+    #[error("The encrypted data is malformed: {0}")]
+    Malformed(&'static str),
+
+    #[error("Unsupported algorithm {0:?}")]
+    UnsupportedAlgorithm(String),
+
+    #[error("The encrypted element could not be decrypted, or is invalid")]
+    Invalid,
+}
+
+fn algorithm(element: ElementRef<'_>) -> Result<&str, DecryptionError> {
+    element
+        .first_child(XENC_NS, "EncryptionMethod")
+        .and_then(|method| method.attribute("Algorithm"))
+        .ok_or(DecryptionError::Malformed("missing EncryptionMethod"))
+}
+
+fn cipher_value(element: ElementRef<'_>) -> Result<Vec<u8>, DecryptionError> {
+    let value = element
+        .first_child(XENC_NS, "CipherData")
+        .and_then(|data| data.first_child(XENC_NS, "CipherValue"))
+        .ok_or(DecryptionError::Malformed("missing CipherValue"))?;
+    decode_base64(&value.text()).map_err(|_| DecryptionError::Malformed("invalid CipherValue"))
+}
+
+/// Decrypt the content encryption key of an `EncryptedKey` element
+fn decrypt_key(
+    encrypted_key: ElementRef<'_>,
+    key: &RsaPrivateKey,
+) -> Result<Vec<u8>, DecryptionError> {
+    let method = encrypted_key
+        .first_child(XENC_NS, "EncryptionMethod")
+        .ok_or(DecryptionError::Malformed("missing EncryptionMethod"))?;
+
+    let digest = method
+        .first_child(DSIG_NS, "DigestMethod")
+        .and_then(|digest| digest.attribute("Algorithm"))
+        .unwrap_or(SHA1);
+
+    let padding = match algorithm(encrypted_key)? {
+        RSA_OAEP_MGF1P => match digest {
+            SHA1 => Oaep::new::<Sha1>(),
+            SHA256 => Oaep::new_with_mgf_hash::<Sha256, Sha1>(),
+            digest => return Err(DecryptionError::UnsupportedAlgorithm(digest.to_owned())),
+        },
+        RSA_OAEP => {
+            let mgf = method
+                .first_child(XENC11_NS, "MGF")
+                .and_then(|mgf| mgf.attribute("Algorithm"))
+                .unwrap_or(MGF1_SHA1);
+            match (digest, mgf) {
+                (SHA1, MGF1_SHA1) => Oaep::new::<Sha1>(),
+                (SHA256, MGF1_SHA1) => Oaep::new_with_mgf_hash::<Sha256, Sha1>(),
+                (SHA256, MGF1_SHA256) => Oaep::new::<Sha256>(),
+                (SHA1 | SHA256, mgf) => {
+                    return Err(DecryptionError::UnsupportedAlgorithm(mgf.to_owned()));
+                }
+                (digest, _) => {
+                    return Err(DecryptionError::UnsupportedAlgorithm(digest.to_owned()));
+                }
+            }
+        }
+        algorithm => return Err(DecryptionError::UnsupportedAlgorithm(algorithm.to_owned())),
+    };
+
+    let ciphertext = cipher_value(encrypted_key)?;
+    key.decrypt(padding, &ciphertext)
+        .map_err(|_| DecryptionError::Invalid)
+}
+
+/// A function decrypting some content with the content encryption key
+type ContentDecryptor = fn(&[u8], &[u8]) -> Result<Vec<u8>, DecryptionError>;
+
+fn decrypt_cbc<C>(key: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>, DecryptionError>
+where
+    cbc::Decryptor<C>: KeyIvInit + BlockDecryptMut,
+    C: cbc::cipher::BlockDecryptMut + cbc::cipher::BlockCipher,
+{
+    const BLOCK_SIZE: usize = 16;
+    if ciphertext.len() < 2 * BLOCK_SIZE || !ciphertext.len().is_multiple_of(BLOCK_SIZE) {
+        return Err(DecryptionError::Invalid);
+    }
+
+    let (iv, ciphertext) = ciphertext.split_at(BLOCK_SIZE);
+    let mut plaintext = cbc::Decryptor::<C>::new_from_slices(key, iv)
+        .map_err(|_| DecryptionError::Invalid)?
+        .decrypt_padded_vec_mut::<NoPadding>(ciphertext)
+        .map_err(|_| DecryptionError::Invalid)?;
+
+    // XML encryption uses a padding where only the last byte is meaningful,
+    // and gives the number of padding bytes
+    let padding = usize::from(*plaintext.last().unwrap_or(&0));
+    if padding == 0 || padding > BLOCK_SIZE {
+        return Err(DecryptionError::Invalid);
+    }
+    plaintext.truncate(plaintext.len() - padding);
+    Ok(plaintext)
+}
+
+fn decrypt_gcm<C>(key: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>, DecryptionError>
+where
+    C: KeyInit + Aead,
+{
+    const NONCE_SIZE: usize = 12;
+    if ciphertext.len() < NONCE_SIZE {
+        return Err(DecryptionError::Invalid);
+    }
+
+    let (nonce, ciphertext) = ciphertext.split_at(NONCE_SIZE);
+    C::new_from_slice(key)
+        .map_err(|_| DecryptionError::Invalid)?
+        .decrypt(Nonce::<C>::from_slice(nonce), ciphertext)
+        .map_err(|_| DecryptionError::Invalid)
+}
+
+/// Decrypt the `EncryptedData` found in an element like
+/// `saml:EncryptedAssertion`
+///
+/// The `EncryptedKey` can either be in the `KeyInfo` of the `EncryptedData`,
+/// or next to it. AES-CBC content encryption is rejected unless `allow_cbc`
+/// is set.
+pub(crate) fn decrypt(
+    container: ElementRef<'_>,
+    key: &RsaPrivateKey,
+    allow_cbc: bool,
+) -> Result<Vec<u8>, DecryptionError> {
+    let encrypted_data = container
+        .first_child(XENC_NS, "EncryptedData")
+        .ok_or(DecryptionError::Malformed("missing EncryptedData"))?;
+
+    let encrypted_key = encrypted_data
+        .first_child(DSIG_NS, "KeyInfo")
+        .and_then(|key_info| key_info.first_child(XENC_NS, "EncryptedKey"))
+        .or_else(|| container.first_child(XENC_NS, "EncryptedKey"))
+        .ok_or(DecryptionError::Malformed("missing EncryptedKey"))?;
+
+    // Everything which doesn't depend on the key is checked before decrypting
+    // anything
+    let decrypt_content: ContentDecryptor = match algorithm(encrypted_data)? {
+        AES128_GCM => decrypt_gcm::<Aes128Gcm>,
+        AES256_GCM => decrypt_gcm::<Aes256Gcm>,
+        AES128_CBC if allow_cbc => decrypt_cbc::<aes::Aes128>,
+        AES256_CBC if allow_cbc => decrypt_cbc::<aes::Aes256>,
+        algorithm => return Err(DecryptionError::UnsupportedAlgorithm(algorithm.to_owned())),
+    };
+    let ciphertext = cipher_value(encrypted_data)?;
+
+    let content_key = decrypt_key(encrypted_key, key)?;
+    decrypt_content(&content_key, &ciphertext)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use base64ct::{Base64, Encoding};
+    use cbc::cipher::{BlockEncryptMut, block_padding::Pkcs7};
+    use rand::{RngCore, SeedableRng};
+    use rand_chacha::ChaChaRng;
+    use rsa::RsaPublicKey;
+
+    use super::*;
+    use crate::{certificate::private_key_from_pem, xml::Document};
+
+    /// Encrypt some content in an `EncryptedAssertion` element
+    pub(crate) fn encrypt(content: &str, key: &RsaPublicKey, algorithm: &str) -> String {
+        let mut rng = ChaChaRng::seed_from_u64(42);
+        let key_size = if algorithm == AES128_CBC || algorithm == AES128_GCM {
+            16
+        } else {
+            32
+        };
+        let mut content_key = vec![0; key_size];
+        rng.fill_bytes(&mut content_key);
+
+        let ciphertext = match algorithm {
+            AES128_GCM | AES256_GCM => {
+                let mut nonce = [0; 12];
+                rng.fill_bytes(&mut nonce);
+                let encrypted = if key_size == 16 {
+                    Aes128Gcm::new_from_slice(&content_key)
+                        .unwrap()
+                        .encrypt(Nonce::<Aes128Gcm>::from_slice(&nonce), content.as_bytes())
+                } else {
+                    Aes256Gcm::new_from_slice(&content_key)
+                        .unwrap()
+                        .encrypt(Nonce::<Aes256Gcm>::from_slice(&nonce), content.as_bytes())
+                }
+                .unwrap();
+                [nonce.as_slice(), &encrypted].concat()
+            }
+            _ => {
+                let mut iv = [0; 16];
+                rng.fill_bytes(&mut iv);
+                let encrypted = if key_size == 16 {
+                    cbc::Encryptor::<aes::Aes128>::new_from_slices(&content_key, &iv)
+                        .unwrap()
+                        .encrypt_padded_vec_mut::<Pkcs7>(content.as_bytes())
+                } else {
+                    cbc::Encryptor::<aes::Aes256>::new_from_slices(&content_key, &iv)
+                        .unwrap()
+                        .encrypt_padded_vec_mut::<Pkcs7>(content.as_bytes())
+                };
+                [iv.as_slice(), &encrypted].concat()
+            }
+        };
+
+        let encrypted_key = key
+            .encrypt(&mut rng, Oaep::new::<Sha1>(), &content_key)
+            .unwrap();
+
+        format!(
+            r#"<saml:EncryptedAssertion xmlns:saml="urn:oasis:names:tc:SAML:2.0:assertion"><xenc:EncryptedData xmlns:xenc="{XENC_NS}" Type="http://www.w3.org/2001/04/xmlenc#Element"><xenc:EncryptionMethod Algorithm="{algorithm}"/><ds:KeyInfo xmlns:ds="{DSIG_NS}"><xenc:EncryptedKey><xenc:EncryptionMethod Algorithm="{RSA_OAEP_MGF1P}"/><xenc:CipherData><xenc:CipherValue>{encrypted_key}</xenc:CipherValue></xenc:CipherData></xenc:EncryptedKey></ds:KeyInfo><xenc:CipherData><xenc:CipherValue>{ciphertext}</xenc:CipherValue></xenc:CipherData></xenc:EncryptedData></saml:EncryptedAssertion>"#,
+            encrypted_key = Base64::encode_string(&encrypted_key),
+            ciphertext = Base64::encode_string(&ciphertext),
+        )
+    }
+
+    #[test]
+    fn test_decrypt() {
+        let key = private_key_from_pem(include_str!("../tests/keys/sp.key.pem")).unwrap();
+        let other_key = private_key_from_pem(include_str!("../tests/keys/idp.key.pem")).unwrap();
+
+        for algorithm in [AES128_CBC, AES256_CBC, AES128_GCM, AES256_GCM] {
+            let encrypted = encrypt("<Hello/>", &key.to_public_key(), algorithm);
+            let document = Document::parse(&encrypted).unwrap();
+            let plaintext = decrypt(document.root_element(), &key, true).unwrap();
+            assert_eq!(plaintext, b"<Hello/>");
+
+            assert!(matches!(
+                decrypt(document.root_element(), &other_key, true),
+                Err(DecryptionError::Invalid)
+            ));
+        }
+    }
+
+    #[test]
+    fn test_reject_cbc_by_default() {
+        let key = private_key_from_pem(include_str!("../tests/keys/sp.key.pem")).unwrap();
+
+        for algorithm in [AES128_CBC, AES256_CBC] {
+            let encrypted = encrypt("<Hello/>", &key.to_public_key(), algorithm);
+            let document = Document::parse(&encrypted).unwrap();
+            assert!(matches!(
+                decrypt(document.root_element(), &key, false),
+                Err(DecryptionError::UnsupportedAlgorithm(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_tampered_content() {
+        let key = private_key_from_pem(include_str!("../tests/keys/sp.key.pem")).unwrap();
+
+        for algorithm in [AES128_CBC, AES256_CBC, AES128_GCM, AES256_GCM] {
+            let encrypted = encrypt("<Hello/>", &key.to_public_key(), algorithm);
+            let document = Document::parse(&encrypted).unwrap();
+            let encrypted_data = document
+                .root_element()
+                .first_child(XENC_NS, "EncryptedData")
+                .unwrap();
+            let ciphertext = cipher_value(encrypted_data).unwrap();
+
+            // Breaking the padding or the tag, or truncating the ciphertext,
+            // must give the same error as using the wrong key
+            let mut flipped = ciphertext.clone();
+            *flipped.last_mut().unwrap() ^= 1;
+            let truncated = &ciphertext[..ciphertext.len() - 1];
+
+            for tampered in [flipped.as_slice(), truncated] {
+                let tampered = encrypted.replace(
+                    &Base64::encode_string(&ciphertext),
+                    &Base64::encode_string(tampered),
+                );
+                let document = Document::parse(&tampered).unwrap();
+                assert!(matches!(
+                    decrypt(document.root_element(), &key, true),
+                    Err(DecryptionError::Invalid)
+                ));
+            }
+        }
+    }
+
+    #[test]
+    fn test_reject_pkcs1v15_key_transport() {
+        let key = private_key_from_pem(include_str!("../tests/keys/sp.key.pem")).unwrap();
+        let encrypted = encrypt("<Hello/>", &key.to_public_key(), AES256_GCM)
+            .replace(RSA_OAEP_MGF1P, "http://www.w3.org/2001/04/xmlenc#rsa-1_5");
+        let document = Document::parse(&encrypted).unwrap();
+        assert!(matches!(
+            decrypt(document.root_element(), &key, true),
+            Err(DecryptionError::UnsupportedAlgorithm(_))
+        ));
+    }
+}
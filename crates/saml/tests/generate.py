@@ -0,0 +1,149 @@
+# Copyright 2026 Element Creations Ltd.
+#
+# SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+# Please see LICENSE files in the repository root for full details.
+
+# Generates signed SAML responses, to check the signature verification against
+# an independent implementation of the canonicalization and of the signatures
+# Requires the `cryptography` python library, and the keys from `generate.sh`
+#
+# Python's canonicalization is C14N 2.0, which only renders the namespaces
+# visibly used by each element: as long as the signed elements don't rely on
+# namespaces only used in attribute values, this gives the same result as the
+# exclusive canonicalization.
+
+import hashlib
+from base64 import b64encode
+from pathlib import Path
+from xml.etree.ElementTree import canonicalize
+
+from cryptography.hazmat.primitives import hashes, serialization
+from cryptography.hazmat.primitives.asymmetric import padding
+
+tests_path = Path(__file__).parent
+responses_path = tests_path / "responses"
+responses_path.mkdir(parents=True, exist_ok=True)
+
+idp_key = serialization.load_pem_private_key(
+    (tests_path / "keys" / "idp.key.pem").read_bytes(), password=None
+)
+
+PROTOCOL_NS = "urn:oasis:names:tc:SAML:2.0:protocol"
+ASSERTION_NS = "urn:oasis:names:tc:SAML:2.0:assertion"
+DSIG_NS = "http://www.w3.org/2000/09/xmldsig#"
+
+ALGORITHMS = {
+    "sha256": (
+        "http://www.w3.org/2001/04/xmldsig-more#rsa-sha256",
+        "http://www.w3.org/2001/04/xmlenc#sha256",
+        hashlib.sha256,
+        hashes.SHA256(),
+    ),
+    "sha512": (
+        "http://www.w3.org/2001/04/xmldsig-more#rsa-sha512",
+        "http://www.w3.org/2001/04/xmlenc#sha512",
+        hashlib.sha512,
+        hashes.SHA512(),
+    ),
+}
+
+
+def sign(xml: str, id: str, namespaces: str, algorithm: str) -> str:
+    """Sign the element with the given ID, inserting the signature right after
+    its issuer
+
+    Args:
+        xml: The element to sign
+        id: The ID of the element
+        namespaces: The namespace declarations inherited by the element, which
+            are needed to canonicalize it on its own
+        algorithm: Either `sha256` or `sha512`
+    """
+    signature_method, digest_method, digest, hash = ALGORITHMS[algorithm]
+
+    # Canonicalize the element on its own, with the namespaces it inherits
+    standalone = xml.replace(">", f" {namespaces}>", 1) if namespaces else xml
+    canonical = canonicalize(standalone, strip_text=False)
+    digest_value = b64encode(digest(canonical.encode()).digest()).decode()
+
+    signed_info = (
+        f'<ds:SignedInfo><ds:CanonicalizationMethod Algorithm="http://www.w3.org/2001/10/xml-exc-c14n#"/>'
+        f'<ds:SignatureMethod Algorithm="{signature_method}"/>'
+        f'<ds:Reference URI="#{id}"><ds:Transforms>'
+        f'<ds:Transform Algorithm="http://www.w3.org/2000/09/xmldsig#enveloped-signature"/>'
+        f'<ds:Transform Algorithm="http://www.w3.org/2001/10/xml-exc-c14n#"/>'
+        f"</ds:Transforms>"
+        f'<ds:DigestMethod Algorithm="{digest_method}"/>'
+        f"<ds:DigestValue>{digest_value}</ds:DigestValue>"
+        f"</ds:Reference></ds:SignedInfo>"
+    )
+    canonical = canonicalize(
+        signed_info.replace(">", f' xmlns:ds="{DSIG_NS}">', 1), strip_text=False
+    )
+    signature_value = b64encode(
+        idp_key.sign(canonical.encode(), padding.PKCS1v15(), hash)
+    ).decode()
+
+    signature = (
+        f'<ds:Signature xmlns:ds="{DSIG_NS}">{signed_info}'
+        f"<ds:SignatureValue>{signature_value}</ds:SignatureValue>"
+        f"</ds:Signature>"
+    )
+    index = xml.index("</saml:Issuer>") + len("</saml:Issuer>")
+    return xml[:index] + signature + xml[index:]
+
+
+# Formatted like most identity providers do, with the namespaces declared on
+# the response and whitespace between the elements. The `xs` namespace is only
+# used in an attribute value, so it isn't part of the canonical form
+assertion = """<saml:Assertion ID="_assertion" Version="2.0" IssueInstant="2026-10-18T11:59:50Z">
+    <saml:Issuer>https://idp.example.com/metadata</saml:Issuer>
+    <saml:Subject>
+      <saml:NameID Format="urn:oasis:names:tc:SAML:2.0:nameid-format:persistent">alice</saml:NameID>
+      <saml:SubjectConfirmation Method="urn:oasis:names:tc:SAML:2.0:cm:bearer">
+        <saml:SubjectConfirmationData InResponseTo="_request" Recipient="https://mas.example.com/upstream/saml2/01H8PKNWKKRPCBW4YGH1RWV279/acs" NotOnOrAfter="2026-10-18T12:04:50Z"/>
+      </saml:SubjectConfirmation>
+    </saml:Subject>
+    <saml:Conditions NotBefore="2026-10-18T11:59:50Z" NotOnOrAfter="2026-10-18T12:04:50Z">
+      <saml:AudienceRestriction>
+        <saml:Audience>https://mas.example.com/upstream/saml2/01H8PKNWKKRPCBW4YGH1RWV279/metadata</saml:Audience>
+      </saml:AudienceRestriction>
+    </saml:Conditions>
+    <saml:AuthnStatement AuthnInstant="2026-10-18T11:59:50Z" SessionIndex="_session">
+      <saml:AuthnContext>
+        <saml:AuthnContextClassRef>urn:oasis:names:tc:SAML:2.0:ac:classes:PasswordProtectedTransport</saml:AuthnContextClassRef>
+      </saml:AuthnContext>
+    </saml:AuthnStatement>
+    <saml:AttributeStatement>
+      <saml:Attribute Name="urn:oid:0.9.2342.19200300.100.1.3" FriendlyName="mail" NameFormat="urn:oasis:names:tc:SAML:2.0:attrname-format:uri">
+        <saml:AttributeValue xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance" xsi:type="xs:string">alice@example.com</saml:AttributeValue>
+      </saml:Attribute>
+      <saml:Attribute Name="groups">
+        <saml:AttributeValue>admins</saml:AttributeValue>
+        <saml:AttributeValue>users &amp; friends</saml:AttributeValue>
+      </saml:Attribute>
+    </saml:AttributeStatement>
+  </saml:Assertion>"""
+
+response = """<samlp:Response xmlns:samlp="{PROTOCOL_NS}" xmlns:saml="{ASSERTION_NS}" xmlns:xs="http://www.w3.org/2001/XMLSchema" ID="_response" Version="2.0" IssueInstant="2026-10-18T11:59:50Z" Destination="https://mas.example.com/upstream/saml2/01H8PKNWKKRPCBW4YGH1RWV279/acs" InResponseTo="_request">
+  <saml:Issuer>https://idp.example.com/metadata</saml:Issuer>
+  <samlp:Status>
+    <samlp:StatusCode Value="urn:oasis:names:tc:SAML:2.0:status:Success"/>
+  </samlp:Status>
+  {assertion}
+</samlp:Response>
+"""
+
+# Only the assertion is signed
+signed_assertion = sign(
+    assertion, "_assertion", f'xmlns:saml="{ASSERTION_NS}"', "sha256"
+)
+signed = response.format(
+    PROTOCOL_NS=PROTOCOL_NS, ASSERTION_NS=ASSERTION_NS, assertion=signed_assertion
+)
+(responses_path / "signed-assertion.xml").write_text(signed)
+
+# Both the assertion and the response are signed, the latter with SHA-512
+(responses_path / "signed-response.xml").write_text(
+    sign(signed.strip(), "_response", "", "sha512") + "\n"
+)
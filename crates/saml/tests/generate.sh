@@ -0,0 +1,17 @@
+#!/bin/sh
+# Copyright 2026 Element Creations Ltd.
+#
+# SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+# Please see LICENSE files in the repository root for full details.
+
+set -eux
+
+KEYS="$(dirname "$0")/keys"
+mkdir -p "${KEYS}"
+
+for NAME in idp sp; do
+  openssl req -x509 -newkey rsa:2048 -nodes -days 36500 \
+    -subj "/CN=${NAME}.example.com" \
+    -keyout "${KEYS}/${NAME}.key.pem" \
+    -out "${KEYS}/${NAME}.crt.pem"
+done
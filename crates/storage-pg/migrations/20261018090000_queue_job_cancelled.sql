@@ -0,0 +1,11 @@
+-- Copyright 2026 Element Creations Ltd.
+--
+-- SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+-- Please see LICENSE files in the repository root for full details.
+
+-- Add a new status for jobs cancelled by an administrator
+ALTER TYPE "queue_job_status" ADD VALUE 'cancelled';
+
+ALTER TABLE "queue_jobs"
+  -- When the job was cancelled
+  ADD COLUMN "cancelled_at" TIMESTAMP WITH TIME ZONE;
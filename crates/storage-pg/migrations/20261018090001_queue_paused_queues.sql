@@ -0,0 +1,15 @@
+-- Copyright 2026 Element Creations Ltd.
+--
+-- SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+-- Please see LICENSE files in the repository root for full details.
+
+-- This table tracks the queues which were paused by an administrator. Workers
+-- don't pick up jobs from paused queues, but jobs can still be scheduled on
+-- them.
+CREATE TABLE queue_paused_queues (
+  -- The name of the paused queue
+  queue_name TEXT NOT NULL PRIMARY KEY,
+
+  -- When the queue was paused
+  paused_at TIMESTAMP WITH TIME ZONE NOT NULL
+);
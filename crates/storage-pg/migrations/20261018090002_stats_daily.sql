@@ -0,0 +1,30 @@
+-- Copyright 2026 Element Creations Ltd.
+--
+-- SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+-- Please see LICENSE files in the repository root for full details.
+
+-- This table holds the usage statistics, rolled up once a day by a scheduled
+-- job so that reporting doesn't have to scan the live tables.
+CREATE TABLE stats_daily (
+  -- The day the value is for, in UTC
+  day DATE NOT NULL,
+
+  -- The name of the metric, e.g. `daily_active_users`
+  metric TEXT NOT NULL,
+
+  -- An optional dimension, which meaning depends on the metric, e.g. the ID of
+  -- the OAuth 2.0 client for `active_oauth2_sessions`
+  dimension UUID,
+
+  -- The value of the metric
+  value BIGINT NOT NULL,
+
+  -- When the value was computed
+  computed_at TIMESTAMP WITH TIME ZONE NOT NULL
+);
+
+CREATE UNIQUE INDEX stats_daily_day_metric_dimension_idx
+  ON stats_daily (day, metric, COALESCE(dimension, '00000000-0000-0000-0000-000000000000'));
+
+CREATE INDEX stats_daily_metric_day_idx
+  ON stats_daily (metric, day);
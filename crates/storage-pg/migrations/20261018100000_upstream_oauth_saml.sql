@@ -0,0 +1,10 @@
+-- Copyright 2026 Element Creations Ltd.
+--
+-- SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+-- Please see LICENSE files in the repository root for full details.
+
+-- Holds the SAML 2.0 settings of upstream providers which are SAML identity
+-- providers instead of OAuth 2.0 ones. The private key of the service provider
+-- is stored encrypted in the `encrypted_client_secret` column.
+ALTER TABLE "upstream_oauth_providers"
+  ADD COLUMN "saml_config" JSONB;
@@ -0,0 +1,9 @@
+-- Copyright 2026 Element Creations Ltd.
+--
+-- SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+-- Please see LICENSE files in the repository root for full details.
+
+-- Records the DN of the directory entry used for authentications done with
+-- the LDAP password backend
+ALTER TABLE "user_session_authentications"
+  ADD COLUMN "ldap_dn" TEXT;
@@ -0,0 +1,14 @@
+-- Copyright 2026 Element Creations Ltd.
+--
+-- SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+-- Please see LICENSE files in the repository root for full details.
+
+-- Stores the encrypted refresh token obtained during the last login through
+-- the link, used to refresh the claims of the user in the background
+ALTER TABLE "upstream_oauth_links"
+  ADD COLUMN "encrypted_refresh_token" TEXT;
+
+-- Used to find the links to refresh in the background
+CREATE INDEX "upstream_oauth_links_refreshable_idx"
+  ON "upstream_oauth_links" ("upstream_oauth_provider_id", "upstream_oauth_link_id")
+  WHERE "encrypted_refresh_token" IS NOT NULL;
@@ -0,0 +1,9 @@
+-- Copyright 2026 Element Creations Ltd.
+--
+-- SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+-- Please see LICENSE files in the repository root for full details.
+
+-- Stores the user attributes imported from the upstream provider on the last
+-- login through the link, which are passed to the policy engine
+ALTER TABLE "upstream_oauth_links"
+  ADD COLUMN "attributes" JSONB;
@@ -0,0 +1,9 @@
+-- Copyright 2026 Element Creations Ltd.
+--
+-- SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+-- Please see LICENSE files in the repository root for full details.
+
+-- The email domains handled by each upstream provider, used to route logins
+-- to the right provider
+ALTER TABLE "upstream_oauth_providers"
+  ADD COLUMN "email_domains" TEXT[] NOT NULL DEFAULT '{}';
@@ -0,0 +1,14 @@
+-- Copyright 2026 Element Creations Ltd.
+--
+-- SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+-- Please see LICENSE files in the repository root for full details.
+
+-- Whether the tokens obtained from the provider should be kept, so that they
+-- can be used to call the provider's APIs on behalf of users
+ALTER TABLE "upstream_oauth_providers"
+  ADD COLUMN "store_tokens" BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- The encrypted access token obtained during the last login or refresh
+ALTER TABLE "upstream_oauth_links"
+  ADD COLUMN "encrypted_access_token" TEXT,
+  ADD COLUMN "access_token_expires_at" TIMESTAMP WITH TIME ZONE;
@@ -0,0 +1,9 @@
+-- Copyright 2026 Element Creations Ltd.
+--
+-- SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+-- Please see LICENSE files in the repository root for full details.
+
+-- Whether logins with this provider can be started by the provider itself or
+-- by a third party, through the initiate login endpoint
+ALTER TABLE "upstream_oauth_providers"
+  ADD COLUMN "allow_initiated_login" BOOLEAN NOT NULL DEFAULT FALSE;
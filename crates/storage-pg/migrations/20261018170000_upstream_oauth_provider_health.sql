@@ -0,0 +1,19 @@
+-- Copyright 2026 Element Creations Ltd.
+--
+-- SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+-- Please see LICENSE files in the repository root for full details.
+
+-- The outcome of the last health check of each upstream provider
+CREATE TABLE upstream_oauth_provider_health (
+    upstream_oauth_provider_id UUID NOT NULL PRIMARY KEY
+        REFERENCES upstream_oauth_providers (upstream_oauth_provider_id),
+
+    -- When the checks were made
+    checked_at TIMESTAMP WITH TIME ZONE NOT NULL,
+
+    -- The outcome of each check, as a JSON object with a `status` field, and
+    -- an `error` field if the check failed
+    discovery JSONB NOT NULL,
+    jwks JSONB NOT NULL,
+    token_endpoint JSONB NOT NULL
+);
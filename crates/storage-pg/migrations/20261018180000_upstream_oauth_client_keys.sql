@@ -0,0 +1,10 @@
+-- Copyright 2026 Element Creations Ltd.
+--
+-- SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+-- Please see LICENSE files in the repository root for full details.
+
+-- Key material used to authenticate against the provider: private keys for
+-- `private_key_jwt` and a TLS client certificate. Stored as an encrypted JSON
+-- document.
+ALTER TABLE "upstream_oauth_providers"
+  ADD COLUMN "encrypted_client_keys" TEXT;
@@ -0,0 +1,26 @@
+-- Copyright 2026 Element Creations Ltd.
+--
+-- SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+-- Please see LICENSE files in the repository root for full details.
+
+-- Signing keys generated and rotated by the service itself, on top of the
+-- ones coming from the configuration
+--
+-- A key is published as soon as it is created, used for signing once
+-- activated, and stays published after being retired until it is removed.
+CREATE TABLE signing_keys (
+    signing_key_id UUID NOT NULL PRIMARY KEY,
+
+    -- The JWK key ID
+    kid TEXT NOT NULL UNIQUE,
+
+    -- The type of key, e.g. `rsa` or `ec_p256`
+    key_type TEXT NOT NULL,
+
+    -- The PKCS#8 PEM-encoded private key, encrypted with the encryption secret
+    encrypted_key TEXT NOT NULL,
+
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    activated_at TIMESTAMP WITH TIME ZONE,
+    retired_at TIMESTAMP WITH TIME ZONE
+);
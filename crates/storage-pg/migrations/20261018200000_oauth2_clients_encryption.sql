@@ -0,0 +1,12 @@
+-- Copyright 2026 Element Creations Ltd.
+--
+-- SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+-- Please see LICENSE files in the repository root for full details.
+
+-- The JWE algorithms the client requested for encrypting ID tokens and
+-- userinfo responses. The `enc` is always set when the `alg` is set.
+ALTER TABLE oauth2_clients
+  ADD COLUMN id_token_encrypted_response_alg TEXT,
+  ADD COLUMN id_token_encrypted_response_enc TEXT,
+  ADD COLUMN userinfo_encrypted_response_alg TEXT,
+  ADD COLUMN userinfo_encrypted_response_enc TEXT;
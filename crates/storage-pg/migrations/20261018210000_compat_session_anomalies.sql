@@ -0,0 +1,34 @@
+-- Copyright 2026 Element Creations Ltd.
+--
+-- SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+-- Please see LICENSE files in the repository root for full details.
+
+-- Records of compatibility access tokens being used from an unusual network or
+-- client, compared to what the session was last active from
+CREATE TABLE compat_session_anomalies (
+    compat_session_anomaly_id UUID NOT NULL PRIMARY KEY,
+
+    compat_session_id UUID NOT NULL
+        REFERENCES compat_sessions (compat_session_id) ON DELETE CASCADE,
+
+    -- Denormalized from the session, to list the anomalies of a user
+    user_id UUID NOT NULL
+        REFERENCES users (user_id) ON DELETE CASCADE,
+
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL,
+
+    -- What was unusual, e.g. `ip_range` or `user_agent`
+    kinds TEXT[] NOT NULL,
+
+    ip_address INET,
+    user_agent TEXT,
+
+    -- Whether the use of the token was rejected, or only flagged
+    rejected BOOLEAN NOT NULL
+);
+
+CREATE INDEX compat_session_anomalies_compat_session_id_idx
+    ON compat_session_anomalies (compat_session_id);
+
+CREATE INDEX compat_session_anomalies_user_id_idx
+    ON compat_session_anomalies (user_id);
@@ -0,0 +1,32 @@
+-- Copyright 2026 Element Creations Ltd.
+--
+-- SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+-- Please see LICENSE files in the repository root for full details.
+
+-- Client secrets can be hashed with a versioned HMAC-SHA256 scheme instead
+-- of being encrypted, and can expire.
+--
+-- When a secret is rotated, the previous one is kept in the `previous_*`
+-- columns, and is still accepted until it expires, so that every deployed
+-- instance of the client can be updated.
+ALTER TABLE oauth2_clients
+  ADD COLUMN hashed_client_secret TEXT,
+  ADD COLUMN hashed_client_secret_version INTEGER,
+  ADD COLUMN client_secret_expires_at TIMESTAMP WITH TIME ZONE,
+  ADD COLUMN previous_encrypted_client_secret TEXT,
+  ADD COLUMN previous_hashed_client_secret TEXT,
+  ADD COLUMN previous_hashed_client_secret_version INTEGER,
+  ADD COLUMN previous_client_secret_expires_at TIMESTAMP WITH TIME ZONE;
+
+-- A secret is either encrypted or hashed, and hashes always have a version
+ALTER TABLE oauth2_clients
+  ADD CONSTRAINT oauth2_clients_client_secret_check
+    CHECK (
+      (encrypted_client_secret IS NULL OR hashed_client_secret IS NULL)
+      AND (hashed_client_secret IS NULL) = (hashed_client_secret_version IS NULL)
+    ),
+  ADD CONSTRAINT oauth2_clients_previous_client_secret_check
+    CHECK (
+      (previous_encrypted_client_secret IS NULL OR previous_hashed_client_secret IS NULL)
+      AND (previous_hashed_client_secret IS NULL) = (previous_hashed_client_secret_version IS NULL)
+    );
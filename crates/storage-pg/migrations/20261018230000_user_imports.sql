@@ -0,0 +1,13 @@
+-- Copyright 2026 Element Creations Ltd.
+--
+-- SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+-- Please see LICENSE files in the repository root for full details.
+
+-- Batches of users submitted through the admin API, waiting to be imported by
+-- the `import-users` job. They contain password hashes, so they are kept out of
+-- the job payload and deleted once the import is done
+CREATE TABLE user_imports (
+    user_import_id UUID NOT NULL PRIMARY KEY,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    records JSONB NOT NULL
+);
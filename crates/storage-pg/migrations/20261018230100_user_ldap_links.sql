@@ -0,0 +1,17 @@
+-- Copyright 2026 Element Creations Ltd.
+--
+-- SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+-- Please see LICENSE files in the repository root for full details.
+
+-- Links between users and their entry in the LDAP directory, so that a
+-- directory login only ever signs into the user it provisioned, and never into
+-- a local user which happens to have the same username
+CREATE TABLE user_ldap_links (
+    user_id UUID NOT NULL PRIMARY KEY
+        REFERENCES users (user_id) ON DELETE CASCADE,
+
+    -- The distinguished name of the directory entry
+    dn TEXT NOT NULL UNIQUE,
+
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL
+);
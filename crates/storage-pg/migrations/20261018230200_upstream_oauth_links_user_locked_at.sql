@@ -0,0 +1,9 @@
+-- Copyright 2026 Element Creations Ltd.
+--
+-- SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+-- Please see LICENSE files in the repository root for full details.
+
+-- Records when the account status import of the provider locked the user, so
+-- that it only ever lifts the locks it created itself
+ALTER TABLE "upstream_oauth_links"
+  ADD COLUMN "user_locked_at" TIMESTAMP WITH TIME ZONE;
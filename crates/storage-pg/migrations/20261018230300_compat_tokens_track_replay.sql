@@ -0,0 +1,15 @@
+-- Copyright 2026 Element Creations Ltd.
+--
+-- SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+-- Please see LICENSE files in the repository root for full details.
+
+-- Track when compatibility access tokens were first used, and which refresh
+-- token replaced a consumed one, so that a lost refresh response can be told
+-- apart from a replayed refresh token
+ALTER TABLE compat_access_tokens
+  ADD COLUMN "first_used_at" TIMESTAMP WITH TIME ZONE;
+
+ALTER TABLE compat_refresh_tokens
+  ADD COLUMN "next_compat_refresh_token_id" UUID
+    REFERENCES compat_refresh_tokens (compat_refresh_token_id)
+    ON DELETE SET NULL;
@@ -34,6 +34,7 @@ struct CompatAccessTokenLookup {
     access_token: String,
     created_at: DateTime<Utc>,
     expires_at: Option<DateTime<Utc>>,
+    first_used_at: Option<DateTime<Utc>>,
     compat_session_id: Uuid,
 }
 
@@ -45,6 +46,7 @@ impl From<CompatAccessTokenLookup> for CompatAccessToken {
             token: value.access_token,
             created_at: value.created_at,
             expires_at: value.expires_at,
+            first_used_at: value.first_used_at,
         }
     }
 }
@@ -70,6 +72,7 @@ impl CompatAccessTokenRepository for PgCompatAccessTokenRepository<'_> {
                      , access_token
                      , created_at
                      , expires_at
+                     , first_used_at
                      , compat_session_id
 
                 FROM compat_access_tokens
@@ -106,6 +109,7 @@ impl CompatAccessTokenRepository for PgCompatAccessTokenRepository<'_> {
                      , access_token
                      , created_at
                      , expires_at
+                     , first_used_at
                      , compat_session_id
 
                 FROM compat_access_tokens
@@ -170,6 +174,7 @@ impl CompatAccessTokenRepository for PgCompatAccessTokenRepository<'_> {
             token,
             created_at,
             expires_at,
+            first_used_at: None,
         })
     }
 
@@ -207,4 +212,39 @@ impl CompatAccessTokenRepository for PgCompatAccessTokenRepository<'_> {
         compat_access_token.expires_at = Some(expires_at);
         Ok(compat_access_token)
     }
+
+    #[tracing::instrument(
+        name = "db.compat_access_token.mark_used",
+        skip_all,
+        fields(
+            db.query.text,
+            %compat_access_token.id,
+            compat_session.id = %compat_access_token.session_id,
+        ),
+        err,
+    )]
+    async fn mark_used(
+        &mut self,
+        clock: &dyn Clock,
+        mut compat_access_token: CompatAccessToken,
+    ) -> Result<CompatAccessToken, Self::Error> {
+        let now = clock.now();
+        let res = sqlx::query!(
+            r#"
+                UPDATE compat_access_tokens
+                SET first_used_at = $2
+                WHERE compat_access_token_id = $1
+            "#,
+            Uuid::from(compat_access_token.id),
+            now,
+        )
+        .traced()
+        .execute(&mut *self.conn)
+        .await?;
+
+        DatabaseError::ensure_affected_rows(&res, 1)?;
+
+        compat_access_token.first_used_at = Some(now);
+        Ok(compat_access_token)
+    }
 }
@@ -0,0 +1,315 @@
+// Copyright 2026 Element Creations Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+// Please see LICENSE files in the repository root for full details.
+
+use std::net::IpAddr;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use mas_data_model::{Clock, CompatSession, CompatSessionAnomaly, CompatSessionAnomalyKind};
+use mas_storage::{
+    Page, Pagination,
+    compat::{CompatSessionAnomalyFilter, CompatSessionAnomalyRepository},
+    pagination::Node,
+};
+use rand::RngCore;
+use sea_query::{Expr, PostgresQueryBuilder, Query, enum_def};
+use sea_query_binder::SqlxBinder;
+use sqlx::PgConnection;
+use ulid::Ulid;
+use uuid::Uuid;
+
+use crate::{
+    DatabaseError, DatabaseInconsistencyError,
+    filter::{Filter, StatementExt},
+    iden::CompatSessionAnomalies,
+    pagination::QueryBuilderExt,
+    tracing::ExecuteExt,
+};
+
+/// An implementation of [`CompatSessionAnomalyRepository`] for a PostgreSQL
+/// connection
+pub struct PgCompatSessionAnomalyRepository<'c> {
+    conn: &'c mut PgConnection,
+}
+
+impl<'c> PgCompatSessionAnomalyRepository<'c> {
+    /// Create a new [`PgCompatSessionAnomalyRepository`] from an active
+    /// PostgreSQL connection
+    pub fn new(conn: &'c mut PgConnection) -> Self {
+        Self { conn }
+    }
+}
+
+#[derive(sqlx::FromRow)]
+#[enum_def]
+struct CompatSessionAnomalyLookup {
+    compat_session_anomaly_id: Uuid,
+    compat_session_id: Uuid,
+    user_id: Uuid,
+    created_at: DateTime<Utc>,
+    kinds: Vec<String>,
+    ip_address: Option<IpAddr>,
+    user_agent: Option<String>,
+    rejected: bool,
+}
+
+impl Node<Ulid> for CompatSessionAnomalyLookup {
+    fn cursor(&self) -> Ulid {
+        self.compat_session_anomaly_id.into()
+    }
+}
+
+impl TryFrom<CompatSessionAnomalyLookup> for CompatSessionAnomaly {
+    type Error = DatabaseInconsistencyError;
+
+    fn try_from(value: CompatSessionAnomalyLookup) -> Result<Self, Self::Error> {
+        let id = value.compat_session_anomaly_id.into();
+        let kinds = value
+            .kinds
+            .iter()
+            .map(|kind| kind.parse())
+            .collect::<Result<Vec<CompatSessionAnomalyKind>, _>>()
+            .map_err(|e| {
+                DatabaseInconsistencyError::on("compat_session_anomalies")
+                    .column("kinds")
+                    .row(id)
+                    .source(e)
+            })?;
+
+        Ok(CompatSessionAnomaly {
+            id,
+            compat_session_id: value.compat_session_id.into(),
+            user_id: value.user_id.into(),
+            created_at: value.created_at,
+            kinds,
+            ip_address: value.ip_address,
+            user_agent: value.user_agent,
+            rejected: value.rejected,
+        })
+    }
+}
+
+impl Filter for CompatSessionAnomalyFilter<'_> {
+    fn generate_condition(&self, _has_joins: bool) -> impl sea_query::IntoCondition {
+        sea_query::Condition::all()
+            .add_option(self.user().map(|user| {
+                Expr::col((
+                    CompatSessionAnomalies::Table,
+                    CompatSessionAnomalies::UserId,
+                ))
+                .eq(Uuid::from(user.id))
+            }))
+            .add_option(self.compat_session().map(|compat_session| {
+                Expr::col((
+                    CompatSessionAnomalies::Table,
+                    CompatSessionAnomalies::CompatSessionId,
+                ))
+                .eq(Uuid::from(compat_session.id))
+            }))
+            .add_option(self.rejected().map(|rejected| {
+                Expr::col((
+                    CompatSessionAnomalies::Table,
+                    CompatSessionAnomalies::Rejected,
+                ))
+                .eq(rejected)
+            }))
+    }
+}
+
+#[async_trait]
+impl CompatSessionAnomalyRepository for PgCompatSessionAnomalyRepository<'_> {
+    type Error = DatabaseError;
+
+    #[tracing::instrument(
+        name = "db.compat_session_anomaly.add",
+        skip_all,
+        fields(
+            db.query.text,
+            compat_session_anomaly.id,
+            %compat_session.id,
+            user.id = %compat_session.user_id,
+            compat_session_anomaly.rejected = rejected,
+        ),
+        err,
+    )]
+    async fn add(
+        &mut self,
+        rng: &mut (dyn RngCore + Send),
+        clock: &dyn Clock,
+        compat_session: &CompatSession,
+        kinds: Vec<CompatSessionAnomalyKind>,
+        ip_address: Option<IpAddr>,
+        user_agent: Option<String>,
+        rejected: bool,
+    ) -> Result<CompatSessionAnomaly, Self::Error> {
+        let created_at = clock.now();
+        let id = Ulid::from_datetime_with_source(created_at.into(), rng);
+        tracing::Span::current().record("compat_session_anomaly.id", tracing::field::display(id));
+
+        let kinds_str: Vec<String> = kinds.iter().map(|kind| kind.as_str().to_owned()).collect();
+
+        sqlx::query!(
+            r#"
+                INSERT INTO compat_session_anomalies
+                    ( compat_session_anomaly_id
+                    , compat_session_id
+                    , user_id
+                    , created_at
+                    , kinds
+                    , ip_address
+                    , user_agent
+                    , rejected
+                    )
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            "#,
+            Uuid::from(id),
+            Uuid::from(compat_session.id),
+            Uuid::from(compat_session.user_id),
+            created_at,
+            &kinds_str,
+            ip_address as Option<IpAddr>,
+            user_agent.as_deref(),
+            rejected,
+        )
+        .traced()
+        .execute(&mut *self.conn)
+        .await?;
+
+        Ok(CompatSessionAnomaly {
+            id,
+            compat_session_id: compat_session.id,
+            user_id: compat_session.user_id,
+            created_at,
+            kinds,
+            ip_address,
+            user_agent,
+            rejected,
+        })
+    }
+
+    #[tracing::instrument(
+        name = "db.compat_session_anomaly.list",
+        skip_all,
+        fields(
+            db.query.text,
+        ),
+        err,
+    )]
+    async fn list(
+        &mut self,
+        filter: CompatSessionAnomalyFilter<'_>,
+        pagination: Pagination,
+    ) -> Result<Page<CompatSessionAnomaly>, Self::Error> {
+        let (sql, arguments) = Query::select()
+            .expr_as(
+                Expr::col((
+                    CompatSessionAnomalies::Table,
+                    CompatSessionAnomalies::CompatSessionAnomalyId,
+                )),
+                CompatSessionAnomalyLookupIden::CompatSessionAnomalyId,
+            )
+            .expr_as(
+                Expr::col((
+                    CompatSessionAnomalies::Table,
+                    CompatSessionAnomalies::CompatSessionId,
+                )),
+                CompatSessionAnomalyLookupIden::CompatSessionId,
+            )
+            .expr_as(
+                Expr::col((
+                    CompatSessionAnomalies::Table,
+                    CompatSessionAnomalies::UserId,
+                )),
+                CompatSessionAnomalyLookupIden::UserId,
+            )
+            .expr_as(
+                Expr::col((
+                    CompatSessionAnomalies::Table,
+                    CompatSessionAnomalies::CreatedAt,
+                )),
+                CompatSessionAnomalyLookupIden::CreatedAt,
+            )
+            .expr_as(
+                Expr::col((CompatSessionAnomalies::Table, CompatSessionAnomalies::Kinds)),
+                CompatSessionAnomalyLookupIden::Kinds,
+            )
+            .expr_as(
+                Expr::col((
+                    CompatSessionAnomalies::Table,
+                    CompatSessionAnomalies::IpAddress,
+                )),
+                CompatSessionAnomalyLookupIden::IpAddress,
+            )
+            .expr_as(
+                Expr::col((
+                    CompatSessionAnomalies::Table,
+                    CompatSessionAnomalies::UserAgent,
+                )),
+                CompatSessionAnomalyLookupIden::UserAgent,
+            )
+            .expr_as(
+                Expr::col((
+                    CompatSessionAnomalies::Table,
+                    CompatSessionAnomalies::Rejected,
+                )),
+                CompatSessionAnomalyLookupIden::Rejected,
+            )
+            .from(CompatSessionAnomalies::Table)
+            .apply_filter(filter)
+            .generate_pagination(
+                (
+                    CompatSessionAnomalies::Table,
+                    CompatSessionAnomalies::CompatSessionAnomalyId,
+                ),
+                pagination,
+            )
+            .build_sqlx(PostgresQueryBuilder);
+
+        let edges: Vec<CompatSessionAnomalyLookup> = sqlx::query_as_with(&sql, arguments)
+            .traced()
+            .fetch_all(&mut *self.conn)
+            .await?;
+
+        let page = pagination
+            .process(edges)
+            .try_map(CompatSessionAnomaly::try_from)?;
+
+        Ok(page)
+    }
+
+    #[tracing::instrument(
+        name = "db.compat_session_anomaly.count",
+        skip_all,
+        fields(
+            db.query.text,
+        ),
+        err,
+    )]
+    async fn count(
+        &mut self,
+        filter: CompatSessionAnomalyFilter<'_>,
+    ) -> Result<usize, Self::Error> {
+        let (sql, arguments) = Query::select()
+            .expr(
+                Expr::col((
+                    CompatSessionAnomalies::Table,
+                    CompatSessionAnomalies::CompatSessionAnomalyId,
+                ))
+                .count(),
+            )
+            .from(CompatSessionAnomalies::Table)
+            .apply_filter(filter)
+            .build_sqlx(PostgresQueryBuilder);
+
+        let count: i64 = sqlx::query_scalar_with(&sql, arguments)
+            .traced()
+            .fetch_one(&mut *self.conn)
+            .await?;
+
+        count
+            .try_into()
+            .map_err(DatabaseError::to_invalid_operation)
+    }
+}
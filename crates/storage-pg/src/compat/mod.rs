@@ -8,24 +8,28 @@
 //! compatibility layer
 
 mod access_token;
+mod anomaly;
 mod refresh_token;
 mod session;
 mod sso_login;
 
 pub use self::{
-    access_token::PgCompatAccessTokenRepository, refresh_token::PgCompatRefreshTokenRepository,
-    session::PgCompatSessionRepository, sso_login::PgCompatSsoLoginRepository,
+    access_token::PgCompatAccessTokenRepository, anomaly::PgCompatSessionAnomalyRepository,
+    refresh_token::PgCompatRefreshTokenRepository, session::PgCompatSessionRepository,
+    sso_login::PgCompatSsoLoginRepository,
 };
 
 #[cfg(test)]
 mod tests {
+    use std::net::{IpAddr, Ipv4Addr};
+
     use chrono::Duration;
-    use mas_data_model::{Clock, Device, clock::MockClock};
+    use mas_data_model::{Clock, CompatSessionAnomalyKind, Device, clock::MockClock};
     use mas_storage::{
         Pagination, RepositoryAccess,
         compat::{
-            CompatAccessTokenRepository, CompatRefreshTokenRepository, CompatSessionFilter,
-            CompatSessionRepository, CompatSsoLoginFilter,
+            CompatAccessTokenRepository, CompatRefreshTokenRepository, CompatSessionAnomalyFilter,
+            CompatSessionFilter, CompatSessionRepository, CompatSsoLoginFilter,
         },
         user::UserRepository,
     };
@@ -750,4 +754,107 @@ mod tests {
         assert_eq!(logins.edges.len(), 1);
         assert_eq!(logins.edges[0].node, login);
     }
+
+    #[sqlx::test(migrator = "crate::MIGRATOR")]
+    async fn test_session_anomaly_repository(pool: PgPool) {
+        let mut rng = ChaChaRng::seed_from_u64(42);
+        let clock = MockClock::default();
+        let mut repo = PgRepository::from_pool(&pool).await.unwrap();
+
+        let user = repo
+            .user()
+            .add(&mut rng, &clock, "john".to_owned())
+            .await
+            .unwrap();
+        let device = Device::generate(&mut rng);
+        let session = repo
+            .compat_session()
+            .add(&mut rng, &clock, &user, device, None, false, None)
+            .await
+            .unwrap();
+
+        let all = CompatSessionAnomalyFilter::new().for_user(&user);
+        let for_session = CompatSessionAnomalyFilter::new().for_compat_session(&session);
+        let rejected = all.rejected_only();
+        let flagged = all.flagged_only();
+        let pagination = Pagination::first(10);
+
+        assert_eq!(repo.compat_session_anomaly().count(all).await.unwrap(), 0);
+
+        let ip = IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1));
+        let flagged_anomaly = repo
+            .compat_session_anomaly()
+            .add(
+                &mut rng,
+                &clock,
+                &session,
+                vec![CompatSessionAnomalyKind::IpRange],
+                Some(ip),
+                None,
+                false,
+            )
+            .await
+            .unwrap();
+
+        clock.advance(Duration::try_minutes(1).unwrap());
+        let rejected_anomaly = repo
+            .compat_session_anomaly()
+            .add(
+                &mut rng,
+                &clock,
+                &session,
+                vec![
+                    CompatSessionAnomalyKind::IpRange,
+                    CompatSessionAnomalyKind::UserAgent,
+                ],
+                Some(ip),
+                Some("curl/8.0".to_owned()),
+                true,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(repo.compat_session_anomaly().count(all).await.unwrap(), 2);
+        assert_eq!(
+            repo.compat_session_anomaly()
+                .count(for_session)
+                .await
+                .unwrap(),
+            2
+        );
+        assert_eq!(
+            repo.compat_session_anomaly().count(rejected).await.unwrap(),
+            1
+        );
+        assert_eq!(
+            repo.compat_session_anomaly().count(flagged).await.unwrap(),
+            1
+        );
+
+        let list = repo
+            .compat_session_anomaly()
+            .list(all, pagination)
+            .await
+            .unwrap();
+        assert_eq!(list.edges.len(), 2);
+        assert_eq!(list.edges[0].node, flagged_anomaly);
+        assert_eq!(list.edges[1].node, rejected_anomaly);
+
+        // The latest anomaly of the session
+        let list = repo
+            .compat_session_anomaly()
+            .list(for_session, Pagination::last(1))
+            .await
+            .unwrap();
+        assert_eq!(list.edges.len(), 1);
+        assert_eq!(list.edges[0].node, rejected_anomaly);
+
+        let list = repo
+            .compat_session_anomaly()
+            .list(flagged, pagination)
+            .await
+            .unwrap();
+        assert_eq!(list.edges.len(), 1);
+        assert_eq!(list.edges[0].node, flagged_anomaly);
+    }
 }
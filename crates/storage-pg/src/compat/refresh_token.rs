@@ -36,6 +36,7 @@ struct CompatRefreshTokenLookup {
     refresh_token: String,
     created_at: DateTime<Utc>,
     consumed_at: Option<DateTime<Utc>>,
+    next_compat_refresh_token_id: Option<Uuid>,
     compat_access_token_id: Uuid,
     compat_session_id: Uuid,
 }
@@ -43,7 +44,10 @@ struct CompatRefreshTokenLookup {
 impl From<CompatRefreshTokenLookup> for CompatRefreshToken {
     fn from(value: CompatRefreshTokenLookup) -> Self {
         let state = match value.consumed_at {
-            Some(consumed_at) => CompatRefreshTokenState::Consumed { consumed_at },
+            Some(consumed_at) => CompatRefreshTokenState::Consumed {
+                consumed_at,
+                next_refresh_token_id: value.next_compat_refresh_token_id.map(Ulid::from),
+            },
             None => CompatRefreshTokenState::Valid,
         };
 
@@ -79,6 +83,7 @@ impl CompatRefreshTokenRepository for PgCompatRefreshTokenRepository<'_> {
                      , refresh_token
                      , created_at
                      , consumed_at
+                     , next_compat_refresh_token_id
                      , compat_session_id
                      , compat_access_token_id
 
@@ -116,6 +121,7 @@ impl CompatRefreshTokenRepository for PgCompatRefreshTokenRepository<'_> {
                      , refresh_token
                      , created_at
                      , consumed_at
+                     , next_compat_refresh_token_id
                      , compat_session_id
                      , compat_access_token_id
 
@@ -210,6 +216,7 @@ impl CompatRefreshTokenRepository for PgCompatRefreshTokenRepository<'_> {
             r#"
                 UPDATE compat_refresh_tokens
                 SET consumed_at = $2
+                  , next_compat_refresh_token_id = $3
                 WHERE compat_session_id = $1
                   AND consumed_at IS NULL
                   AND compat_refresh_token_id <> $3
@@ -233,7 +240,7 @@ impl CompatRefreshTokenRepository for PgCompatRefreshTokenRepository<'_> {
         }
 
         let compat_refresh_token = compat_refresh_token
-            .consume(consumed_at)
+            .consume(consumed_at, successor_compat_refresh_token)
             .map_err(DatabaseError::to_invalid_operation)?;
 
         Ok(compat_refresh_token)
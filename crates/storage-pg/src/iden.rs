@@ -56,6 +56,19 @@ pub enum CompatSessions {
     LastActiveIp,
 }
 
+#[derive(sea_query::Iden)]
+pub enum CompatSessionAnomalies {
+    Table,
+    CompatSessionAnomalyId,
+    CompatSessionId,
+    UserId,
+    CreatedAt,
+    Kinds,
+    IpAddress,
+    UserAgent,
+    Rejected,
+}
+
 #[derive(sea_query::Iden)]
 pub enum CompatSsoLogins {
     Table,
@@ -156,6 +169,11 @@ pub enum UpstreamOAuthProviders {
     AuthorizationEndpointOverride,
     UserinfoEndpointOverride,
     OnBackchannelLogout,
+    SamlConfig,
+    EmailDomains,
+    StoreTokens,
+    AllowInitiatedLogin,
+    EncryptedClientKeys,
 }
 
 #[derive(sea_query::Iden)]
@@ -170,6 +188,8 @@ pub enum UpstreamOAuthLinks {
     Subject,
     HumanAccountName,
     CreatedAt,
+    EncryptedRefreshToken,
+    AccessTokenExpiresAt,
 }
 
 #[derive(sea_query::Iden)]
@@ -208,3 +228,23 @@ pub enum UserRegistrationTokens {
     ExpiresAt,
     RevokedAt,
 }
+
+#[derive(sea_query::Iden)]
+pub enum QueueJobs {
+    Table,
+    QueueJobId,
+    QueueName,
+    Status,
+    Payload,
+    Attempt,
+    CreatedAt,
+    ScheduledAt,
+    StartedAt,
+    StartedBy,
+    CompletedAt,
+    FailedAt,
+    FailedReason,
+    CancelledAt,
+    NextAttemptId,
+    ScheduleName,
+}
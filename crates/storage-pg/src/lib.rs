@@ -184,6 +184,8 @@ pub(crate) mod iden;
 pub(crate) mod pagination;
 pub(crate) mod policy_data;
 pub(crate) mod repository;
+pub(crate) mod signing_key;
+pub(crate) mod stats;
 pub(crate) mod telemetry;
 pub(crate) mod tracing;
 
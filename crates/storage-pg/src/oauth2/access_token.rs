@@ -130,7 +130,6 @@ impl OAuth2AccessTokenRepository for PgOAuth2AccessTokenRepository<'_> {
         name = "db.oauth2_access_token.add",
         skip_all,
         fields(
-            db.query.text,
             %session.id,
             client.id = %session.client_id,
             access_token.id,
@@ -144,12 +143,35 @@ impl OAuth2AccessTokenRepository for PgOAuth2AccessTokenRepository<'_> {
         session: &Session,
         access_token: String,
         expires_after: Option<Duration>,
+    ) -> Result<AccessToken, Self::Error> {
+        let id = Ulid::from_datetime_with_source(clock.now().into(), rng);
+        tracing::Span::current().record("access_token.id", tracing::field::display(id));
+
+        self.add_with_id(id, clock, session, access_token, expires_after)
+            .await
+    }
+
+    #[tracing::instrument(
+        name = "db.oauth2_access_token.add_with_id",
+        skip_all,
+        fields(
+            db.query.text,
+            %session.id,
+            client.id = %session.client_id,
+            access_token.id = %id,
+        ),
+        err,
+    )]
+    async fn add_with_id(
+        &mut self,
+        id: Ulid,
+        clock: &dyn Clock,
+        session: &Session,
+        access_token: String,
+        expires_after: Option<Duration>,
     ) -> Result<AccessToken, Self::Error> {
         let created_at = clock.now();
         let expires_at = expires_after.map(|d| created_at + d);
-        let id = Ulid::from_datetime_with_source(created_at.into(), rng);
-
-        tracing::Span::current().record("access_token.id", tracing::field::display(id));
 
         sqlx::query!(
             r#"
@@ -215,6 +237,38 @@ impl OAuth2AccessTokenRepository for PgOAuth2AccessTokenRepository<'_> {
             .map_err(DatabaseError::to_invalid_operation)
     }
 
+    #[tracing::instrument(
+        name = "db.oauth2_access_token.revoke_all_for_session",
+        skip_all,
+        fields(
+            db.query.text,
+            %session.id,
+        ),
+        err,
+    )]
+    async fn revoke_all_for_session(
+        &mut self,
+        clock: &dyn Clock,
+        session: &Session,
+    ) -> Result<usize, Self::Error> {
+        let revoked_at = clock.now();
+        let res = sqlx::query!(
+            r#"
+                UPDATE oauth2_access_tokens
+                SET revoked_at = $2
+                WHERE oauth2_session_id = $1
+                  AND revoked_at IS NULL
+            "#,
+            Uuid::from(session.id),
+            revoked_at,
+        )
+        .traced()
+        .execute(&mut *self.conn)
+        .await?;
+
+        Ok(res.rows_affected().try_into().unwrap_or(usize::MAX))
+    }
+
     #[tracing::instrument(
         name = "db.oauth2_access_token.mark_used",
         skip_all,
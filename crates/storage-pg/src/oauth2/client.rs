@@ -10,8 +10,12 @@ use std::{
 };
 
 use async_trait::async_trait;
-use mas_data_model::{Client, Clock, JwksOrJwksUri};
-use mas_iana::{jose::JsonWebSignatureAlg, oauth::OAuthClientAuthenticationMethod};
+use chrono::{DateTime, Utc};
+use mas_data_model::{Client, ClientSecret, ClientSecretValue, Clock, JwksOrJwksUri};
+use mas_iana::{
+    jose::{JsonWebEncryptionAlg, JsonWebEncryptionEnc, JsonWebSignatureAlg},
+    oauth::OAuthClientAuthenticationMethod,
+};
 use mas_jose::jwk::PublicJsonWebKeySet;
 use mas_storage::oauth2::OAuth2ClientRepository;
 use oauth2_types::{oidc::ApplicationType, requests::GrantType};
@@ -44,6 +48,13 @@ struct OAuth2ClientLookup {
     oauth2_client_id: Uuid,
     metadata_digest: Option<String>,
     encrypted_client_secret: Option<String>,
+    hashed_client_secret: Option<String>,
+    hashed_client_secret_version: Option<i32>,
+    client_secret_expires_at: Option<DateTime<Utc>>,
+    previous_encrypted_client_secret: Option<String>,
+    previous_hashed_client_secret: Option<String>,
+    previous_hashed_client_secret_version: Option<i32>,
+    previous_client_secret_expires_at: Option<DateTime<Utc>>,
     application_type: Option<String>,
     redirect_uris: Vec<String>,
     grant_type_authorization_code: bool,
@@ -59,11 +70,73 @@ struct OAuth2ClientLookup {
     jwks: Option<serde_json::Value>,
     id_token_signed_response_alg: Option<String>,
     userinfo_signed_response_alg: Option<String>,
+    id_token_encrypted_response_alg: Option<String>,
+    id_token_encrypted_response_enc: Option<String>,
+    userinfo_encrypted_response_alg: Option<String>,
+    userinfo_encrypted_response_enc: Option<String>,
     token_endpoint_auth_method: Option<String>,
     token_endpoint_auth_signing_alg: Option<String>,
     initiate_login_uri: Option<String>,
 }
 
+/// Build a [`ClientSecret`] from the columns it is stored in
+fn client_secret_from_columns(
+    id: Ulid,
+    column: &'static str,
+    encrypted: Option<String>,
+    hashed: Option<String>,
+    version: Option<i32>,
+    expires_at: Option<DateTime<Utc>>,
+) -> Result<Option<ClientSecret>, DatabaseInconsistencyError> {
+    let value = match (encrypted, hashed, version) {
+        (None, None, None) => return Ok(None),
+        (Some(encrypted), None, None) => ClientSecretValue::Encrypted { encrypted },
+        (None, Some(hash), Some(version)) => {
+            let version = version.try_into().map_err(|e| {
+                DatabaseInconsistencyError::on("oauth2_clients")
+                    .column(column)
+                    .row(id)
+                    .source(e)
+            })?;
+            ClientSecretValue::Hashed { version, hash }
+        }
+        _ => {
+            return Err(DatabaseInconsistencyError::on("oauth2_clients")
+                .column(column)
+                .row(id));
+        }
+    };
+
+    Ok(Some(ClientSecret { value, expires_at }))
+}
+
+/// The encrypted secret, hashed secret, hash version and expiration of a
+/// [`ClientSecret`], as stored in the database
+type ClientSecretColumns<'a> = (
+    Option<&'a str>,
+    Option<&'a str>,
+    Option<i32>,
+    Option<DateTime<Utc>>,
+);
+
+fn client_secret_columns(secret: Option<&ClientSecret>) -> ClientSecretColumns<'_> {
+    let Some(secret) = secret else {
+        return (None, None, None, None);
+    };
+
+    match &secret.value {
+        ClientSecretValue::Encrypted { encrypted } => {
+            (Some(encrypted), None, None, secret.expires_at)
+        }
+        ClientSecretValue::Hashed { version, hash } => (
+            None,
+            Some(hash),
+            Some(i32::from(*version)),
+            secret.expires_at,
+        ),
+    }
+}
+
 impl TryInto<Client> for OAuth2ClientLookup {
     type Error = DatabaseInconsistencyError;
 
@@ -162,6 +235,50 @@ impl TryInto<Client> for OAuth2ClientLookup {
                     .source(e)
             })?;
 
+        let id_token_encrypted_response_alg = self
+            .id_token_encrypted_response_alg
+            .map(|s| s.parse())
+            .transpose()
+            .map_err(|e| {
+                DatabaseInconsistencyError::on("oauth2_clients")
+                    .column("id_token_encrypted_response_alg")
+                    .row(id)
+                    .source(e)
+            })?;
+
+        let id_token_encrypted_response_enc = self
+            .id_token_encrypted_response_enc
+            .map(|s| s.parse())
+            .transpose()
+            .map_err(|e| {
+                DatabaseInconsistencyError::on("oauth2_clients")
+                    .column("id_token_encrypted_response_enc")
+                    .row(id)
+                    .source(e)
+            })?;
+
+        let userinfo_encrypted_response_alg = self
+            .userinfo_encrypted_response_alg
+            .map(|s| s.parse())
+            .transpose()
+            .map_err(|e| {
+                DatabaseInconsistencyError::on("oauth2_clients")
+                    .column("userinfo_encrypted_response_alg")
+                    .row(id)
+                    .source(e)
+            })?;
+
+        let userinfo_encrypted_response_enc = self
+            .userinfo_encrypted_response_enc
+            .map(|s| s.parse())
+            .transpose()
+            .map_err(|e| {
+                DatabaseInconsistencyError::on("oauth2_clients")
+                    .column("userinfo_encrypted_response_enc")
+                    .row(id)
+                    .source(e)
+            })?;
+
         let token_endpoint_auth_method = self
             .token_endpoint_auth_method
             .map(|s| s.parse())
@@ -195,6 +312,24 @@ impl TryInto<Client> for OAuth2ClientLookup {
                     .source(e)
             })?;
 
+        let client_secret = client_secret_from_columns(
+            id,
+            "client_secret",
+            self.encrypted_client_secret,
+            self.hashed_client_secret,
+            self.hashed_client_secret_version,
+            self.client_secret_expires_at,
+        )?;
+
+        let previous_client_secret = client_secret_from_columns(
+            id,
+            "previous_client_secret",
+            self.previous_encrypted_client_secret,
+            self.previous_hashed_client_secret,
+            self.previous_hashed_client_secret_version,
+            self.previous_client_secret_expires_at,
+        )?;
+
         let jwks = match (self.jwks, self.jwks_uri) {
             (None, None) => None,
             (Some(jwks), None) => {
@@ -227,7 +362,8 @@ impl TryInto<Client> for OAuth2ClientLookup {
             id,
             client_id: id.to_string(),
             metadata_digest: self.metadata_digest,
-            encrypted_client_secret: self.encrypted_client_secret,
+            client_secret,
+            previous_client_secret,
             application_type,
             redirect_uris,
             grant_types,
@@ -239,6 +375,10 @@ impl TryInto<Client> for OAuth2ClientLookup {
             jwks,
             id_token_signed_response_alg,
             userinfo_signed_response_alg,
+            id_token_encrypted_response_alg,
+            id_token_encrypted_response_enc,
+            userinfo_encrypted_response_alg,
+            userinfo_encrypted_response_enc,
             token_endpoint_auth_method,
             token_endpoint_auth_signing_alg,
             initiate_login_uri,
@@ -266,6 +406,13 @@ impl OAuth2ClientRepository for PgOAuth2ClientRepository<'_> {
                 SELECT oauth2_client_id
                      , metadata_digest
                      , encrypted_client_secret
+                     , hashed_client_secret
+                     , hashed_client_secret_version
+                     , client_secret_expires_at
+                     , previous_encrypted_client_secret
+                     , previous_hashed_client_secret
+                     , previous_hashed_client_secret_version
+                     , previous_client_secret_expires_at
                      , application_type
                      , redirect_uris
                      , grant_type_authorization_code
@@ -281,6 +428,10 @@ impl OAuth2ClientRepository for PgOAuth2ClientRepository<'_> {
                      , jwks
                      , id_token_signed_response_alg
                      , userinfo_signed_response_alg
+                     , id_token_encrypted_response_alg
+                     , id_token_encrypted_response_enc
+                     , userinfo_encrypted_response_alg
+                     , userinfo_encrypted_response_enc
                      , token_endpoint_auth_method
                      , token_endpoint_auth_signing_alg
                      , initiate_login_uri
@@ -317,6 +468,13 @@ impl OAuth2ClientRepository for PgOAuth2ClientRepository<'_> {
                 SELECT oauth2_client_id
                     , metadata_digest
                     , encrypted_client_secret
+                    , hashed_client_secret
+                    , hashed_client_secret_version
+                    , client_secret_expires_at
+                    , previous_encrypted_client_secret
+                    , previous_hashed_client_secret
+                    , previous_hashed_client_secret_version
+                    , previous_client_secret_expires_at
                     , application_type
                     , redirect_uris
                     , grant_type_authorization_code
@@ -332,6 +490,10 @@ impl OAuth2ClientRepository for PgOAuth2ClientRepository<'_> {
                     , jwks
                     , id_token_signed_response_alg
                     , userinfo_signed_response_alg
+                    , id_token_encrypted_response_alg
+                    , id_token_encrypted_response_enc
+                    , userinfo_encrypted_response_alg
+                    , userinfo_encrypted_response_enc
                     , token_endpoint_auth_method
                     , token_endpoint_auth_signing_alg
                     , initiate_login_uri
@@ -368,6 +530,13 @@ impl OAuth2ClientRepository for PgOAuth2ClientRepository<'_> {
                 SELECT oauth2_client_id
                      , metadata_digest
                      , encrypted_client_secret
+                     , hashed_client_secret
+                     , hashed_client_secret_version
+                     , client_secret_expires_at
+                     , previous_encrypted_client_secret
+                     , previous_hashed_client_secret
+                     , previous_hashed_client_secret_version
+                     , previous_client_secret_expires_at
                      , application_type
                      , redirect_uris
                      , grant_type_authorization_code
@@ -383,6 +552,10 @@ impl OAuth2ClientRepository for PgOAuth2ClientRepository<'_> {
                      , jwks
                      , id_token_signed_response_alg
                      , userinfo_signed_response_alg
+                     , id_token_encrypted_response_alg
+                     , id_token_encrypted_response_enc
+                     , userinfo_encrypted_response_alg
+                     , userinfo_encrypted_response_enc
                      , token_endpoint_auth_method
                      , token_endpoint_auth_signing_alg
                      , initiate_login_uri
@@ -421,7 +594,7 @@ impl OAuth2ClientRepository for PgOAuth2ClientRepository<'_> {
         clock: &dyn Clock,
         redirect_uris: Vec<Url>,
         metadata_digest: Option<String>,
-        encrypted_client_secret: Option<String>,
+        client_secret: Option<ClientSecret>,
         application_type: Option<ApplicationType>,
         grant_types: Vec<GrantType>,
         client_name: Option<String>,
@@ -449,12 +622,22 @@ impl OAuth2ClientRepository for PgOAuth2ClientRepository<'_> {
 
         let redirect_uris_array = redirect_uris.iter().map(Url::to_string).collect::<Vec<_>>();
 
+        let (
+            encrypted_client_secret,
+            hashed_client_secret,
+            hashed_client_secret_version,
+            client_secret_expires_at,
+        ) = client_secret_columns(client_secret.as_ref());
+
         sqlx::query!(
             r#"
                 INSERT INTO oauth2_clients
                     ( oauth2_client_id
                     , metadata_digest
                     , encrypted_client_secret
+                    , hashed_client_secret
+                    , hashed_client_secret_version
+                    , client_secret_expires_at
                     , application_type
                     , redirect_uris
                     , grant_type_authorization_code
@@ -477,11 +660,14 @@ impl OAuth2ClientRepository for PgOAuth2ClientRepository<'_> {
                     )
                 VALUES
                     ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13,
-                    $14, $15, $16, $17, $18, $19, $20, $21, FALSE)
+                    $14, $15, $16, $17, $18, $19, $20, $21, $22, $23, $24, FALSE)
             "#,
             Uuid::from(id),
             metadata_digest,
             encrypted_client_secret,
+            hashed_client_secret,
+            hashed_client_secret_version,
+            client_secret_expires_at,
             application_type.as_ref().map(ToString::to_string),
             &redirect_uris_array,
             grant_types.contains(&GrantType::AuthorizationCode),
@@ -522,7 +708,8 @@ impl OAuth2ClientRepository for PgOAuth2ClientRepository<'_> {
             id,
             client_id: id.to_string(),
             metadata_digest: None,
-            encrypted_client_secret,
+            client_secret,
+            previous_client_secret: None,
             application_type,
             redirect_uris,
             grant_types,
@@ -534,12 +721,137 @@ impl OAuth2ClientRepository for PgOAuth2ClientRepository<'_> {
             jwks,
             id_token_signed_response_alg,
             userinfo_signed_response_alg,
+            id_token_encrypted_response_alg: None,
+            id_token_encrypted_response_enc: None,
+            userinfo_encrypted_response_alg: None,
+            userinfo_encrypted_response_enc: None,
             token_endpoint_auth_method,
             token_endpoint_auth_signing_alg,
             initiate_login_uri,
         })
     }
 
+    #[tracing::instrument(
+        name = "db.oauth2_client.set_encryption_preferences",
+        skip_all,
+        fields(
+            db.query.text,
+            %client.id,
+        ),
+        err,
+    )]
+    async fn set_encryption_preferences(
+        &mut self,
+        mut client: Client,
+        id_token_encrypted_response: Option<(JsonWebEncryptionAlg, JsonWebEncryptionEnc)>,
+        userinfo_encrypted_response: Option<(JsonWebEncryptionAlg, JsonWebEncryptionEnc)>,
+    ) -> Result<Client, Self::Error> {
+        let (id_token_encrypted_response_alg, id_token_encrypted_response_enc) =
+            id_token_encrypted_response.unzip();
+        let (userinfo_encrypted_response_alg, userinfo_encrypted_response_enc) =
+            userinfo_encrypted_response.unzip();
+
+        let res = sqlx::query!(
+            r#"
+                UPDATE oauth2_clients
+                SET id_token_encrypted_response_alg = $2
+                  , id_token_encrypted_response_enc = $3
+                  , userinfo_encrypted_response_alg = $4
+                  , userinfo_encrypted_response_enc = $5
+                WHERE oauth2_client_id = $1
+            "#,
+            Uuid::from(client.id),
+            id_token_encrypted_response_alg
+                .as_ref()
+                .map(ToString::to_string),
+            id_token_encrypted_response_enc
+                .as_ref()
+                .map(ToString::to_string),
+            userinfo_encrypted_response_alg
+                .as_ref()
+                .map(ToString::to_string),
+            userinfo_encrypted_response_enc
+                .as_ref()
+                .map(ToString::to_string),
+        )
+        .traced()
+        .execute(&mut *self.conn)
+        .await?;
+
+        DatabaseError::ensure_affected_rows(&res, 1)?;
+
+        client.id_token_encrypted_response_alg = id_token_encrypted_response_alg;
+        client.id_token_encrypted_response_enc = id_token_encrypted_response_enc;
+        client.userinfo_encrypted_response_alg = userinfo_encrypted_response_alg;
+        client.userinfo_encrypted_response_enc = userinfo_encrypted_response_enc;
+
+        Ok(client)
+    }
+
+    #[tracing::instrument(
+        name = "db.oauth2_client.set_secrets",
+        skip_all,
+        fields(
+            db.query.text,
+            %client.id,
+        ),
+        err,
+    )]
+    async fn set_secrets(
+        &mut self,
+        mut client: Client,
+        client_secret: Option<ClientSecret>,
+        previous_client_secret: Option<ClientSecret>,
+    ) -> Result<Client, Self::Error> {
+        let (
+            encrypted_client_secret,
+            hashed_client_secret,
+            hashed_client_secret_version,
+            client_secret_expires_at,
+        ) = client_secret_columns(client_secret.as_ref());
+
+        let (
+            previous_encrypted_client_secret,
+            previous_hashed_client_secret,
+            previous_hashed_client_secret_version,
+            previous_client_secret_expires_at,
+        ) = client_secret_columns(previous_client_secret.as_ref());
+
+        let res = sqlx::query!(
+            r#"
+                UPDATE oauth2_clients
+                SET encrypted_client_secret = $2
+                  , hashed_client_secret = $3
+                  , hashed_client_secret_version = $4
+                  , client_secret_expires_at = $5
+                  , previous_encrypted_client_secret = $6
+                  , previous_hashed_client_secret = $7
+                  , previous_hashed_client_secret_version = $8
+                  , previous_client_secret_expires_at = $9
+                WHERE oauth2_client_id = $1
+            "#,
+            Uuid::from(client.id),
+            encrypted_client_secret,
+            hashed_client_secret,
+            hashed_client_secret_version,
+            client_secret_expires_at,
+            previous_encrypted_client_secret,
+            previous_hashed_client_secret,
+            previous_hashed_client_secret_version,
+            previous_client_secret_expires_at,
+        )
+        .traced()
+        .execute(&mut *self.conn)
+        .await?;
+
+        DatabaseError::ensure_affected_rows(&res, 1)?;
+
+        client.client_secret = client_secret;
+        client.previous_client_secret = previous_client_secret;
+
+        Ok(client)
+    }
+
     #[tracing::instrument(
         name = "db.oauth2_client.upsert_static",
         skip_all,
@@ -589,6 +901,13 @@ impl OAuth2ClientRepository for PgOAuth2ClientRepository<'_> {
                 ON CONFLICT (oauth2_client_id)
                 DO
                     UPDATE SET encrypted_client_secret = EXCLUDED.encrypted_client_secret
+                             , hashed_client_secret = NULL
+                             , hashed_client_secret_version = NULL
+                             , client_secret_expires_at = NULL
+                             , previous_encrypted_client_secret = NULL
+                             , previous_hashed_client_secret = NULL
+                             , previous_hashed_client_secret_version = NULL
+                             , previous_client_secret_expires_at = NULL
                              , redirect_uris = EXCLUDED.redirect_uris
                              , grant_type_authorization_code = EXCLUDED.grant_type_authorization_code
                              , grant_type_refresh_token = EXCLUDED.grant_type_refresh_token
@@ -627,7 +946,9 @@ impl OAuth2ClientRepository for PgOAuth2ClientRepository<'_> {
             id: client_id,
             client_id: client_id.to_string(),
             metadata_digest: None,
-            encrypted_client_secret,
+            client_secret: encrypted_client_secret
+                .map(|encrypted| ClientSecret::encrypted(encrypted, None)),
+            previous_client_secret: None,
             application_type: None,
             redirect_uris,
             grant_types: vec![
@@ -643,6 +964,10 @@ impl OAuth2ClientRepository for PgOAuth2ClientRepository<'_> {
             jwks,
             id_token_signed_response_alg: None,
             userinfo_signed_response_alg: None,
+            id_token_encrypted_response_alg: None,
+            id_token_encrypted_response_enc: None,
+            userinfo_encrypted_response_alg: None,
+            userinfo_encrypted_response_enc: None,
             token_endpoint_auth_method: None,
             token_endpoint_auth_signing_alg: None,
             initiate_login_uri: None,
@@ -664,6 +989,13 @@ impl OAuth2ClientRepository for PgOAuth2ClientRepository<'_> {
                 SELECT oauth2_client_id
                      , metadata_digest
                      , encrypted_client_secret
+                     , hashed_client_secret
+                     , hashed_client_secret_version
+                     , client_secret_expires_at
+                     , previous_encrypted_client_secret
+                     , previous_hashed_client_secret
+                     , previous_hashed_client_secret_version
+                     , previous_client_secret_expires_at
                      , application_type
                      , redirect_uris
                      , grant_type_authorization_code
@@ -679,6 +1011,10 @@ impl OAuth2ClientRepository for PgOAuth2ClientRepository<'_> {
                      , jwks
                      , id_token_signed_response_alg
                      , userinfo_signed_response_alg
+                     , id_token_encrypted_response_alg
+                     , id_token_encrypted_response_enc
+                     , userinfo_encrypted_response_alg
+                     , userinfo_encrypted_response_enc
                      , token_endpoint_auth_method
                      , token_endpoint_auth_signing_alg
                      , initiate_login_uri
@@ -24,7 +24,8 @@ pub use self::{
 #[cfg(test)]
 mod tests {
     use chrono::Duration;
-    use mas_data_model::{AuthorizationCode, Clock, clock::MockClock};
+    use mas_data_model::{AuthorizationCode, ClientSecret, Clock, clock::MockClock};
+    use mas_iana::jose::{JsonWebEncryptionAlg, JsonWebEncryptionEnc};
     use mas_storage::{
         Pagination,
         oauth2::{OAuth2DeviceCodeGrantParams, OAuth2SessionFilter, OAuth2SessionRepository},
@@ -103,6 +104,66 @@ mod tests {
             .expect("client not found");
         assert_eq!(client, client_lookup);
 
+        // Set the encryption preferences of the client
+        assert_eq!(client.id_token_encrypted_response_alg, None);
+        let client = repo
+            .oauth2_client()
+            .set_encryption_preferences(
+                client,
+                Some((JsonWebEncryptionAlg::EcdhEs, JsonWebEncryptionEnc::A256Gcm)),
+                None,
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            client.id_token_encrypted_response_alg,
+            Some(JsonWebEncryptionAlg::EcdhEs)
+        );
+        assert_eq!(
+            client.id_token_encrypted_response_enc,
+            Some(JsonWebEncryptionEnc::A256Gcm)
+        );
+        assert_eq!(client.userinfo_encrypted_response_alg, None);
+
+        let client_lookup = repo
+            .oauth2_client()
+            .lookup(client.id)
+            .await
+            .unwrap()
+            .expect("client not found");
+        assert_eq!(client, client_lookup);
+
+        // Rotate the client secret, keeping the previous one for a day
+        assert_eq!(client.client_secret, None);
+        let client_secret = ClientSecret::hash(&client.client_id, "secret", None);
+        let client = repo
+            .oauth2_client()
+            .set_secrets(
+                client,
+                Some(client_secret),
+                Some(ClientSecret::encrypted(
+                    "encrypted".to_owned(),
+                    Some(clock.now() + Duration::days(1)),
+                )),
+            )
+            .await
+            .unwrap();
+        assert_eq!(client.active_secrets(clock.now()).count(), 2);
+
+        let client_lookup = repo
+            .oauth2_client()
+            .lookup(client.id)
+            .await
+            .unwrap()
+            .expect("client not found");
+        assert_eq!(client, client_lookup);
+        assert_eq!(
+            client_lookup
+                .active_secrets(clock.now() + Duration::days(2))
+                .count(),
+            1
+        );
+
         // Lookup a non-existing grant
         let grant = repo
             .oauth2_authorization_grant()
@@ -283,6 +283,39 @@ impl OAuth2RefreshTokenRepository for PgOAuth2RefreshTokenRepository<'_> {
             .map_err(DatabaseError::to_invalid_operation)
     }
 
+    #[tracing::instrument(
+        name = "db.oauth2_refresh_token.revoke_all_for_session",
+        skip_all,
+        fields(
+            db.query.text,
+            %session.id,
+        ),
+        err,
+    )]
+    async fn revoke_all_for_session(
+        &mut self,
+        clock: &dyn Clock,
+        session: &Session,
+    ) -> Result<usize, Self::Error> {
+        let revoked_at = clock.now();
+        let res = sqlx::query!(
+            r#"
+                UPDATE oauth2_refresh_tokens
+                SET revoked_at = $2
+                WHERE oauth2_session_id = $1
+                  AND revoked_at IS NULL
+                  AND consumed_at IS NULL
+            "#,
+            Uuid::from(session.id),
+            revoked_at,
+        )
+        .traced()
+        .execute(&mut *self.conn)
+        .await?;
+
+        Ok(res.rows_affected().try_into().unwrap_or(usize::MAX))
+    }
+
     #[tracing::instrument(
         name = "db.oauth2_refresh_token.cleanup_revoked",
         skip_all,
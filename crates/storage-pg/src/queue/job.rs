@@ -10,15 +10,26 @@
 use async_trait::async_trait;
 use chrono::{DateTime, Duration, Utc};
 use mas_data_model::Clock;
-use mas_storage::queue::{Job, QueueJobRepository, Worker};
+use mas_storage::{
+    Page, Pagination,
+    pagination::Node,
+    queue::{Job, JobDetails, JobFilter, QueueJobRepository, Worker},
+};
 use opentelemetry_semantic_conventions::trace::DB_QUERY_TEXT;
 use rand::RngCore;
+use sea_query::{Alias, Expr, Func, PostgresQueryBuilder, Query};
+use sea_query_binder::SqlxBinder;
 use sqlx::PgConnection;
 use tracing::Instrument;
 use ulid::Ulid;
 use uuid::Uuid;
 
-use crate::{DatabaseError, DatabaseInconsistencyError, ExecuteExt};
+use crate::{
+    DatabaseError, DatabaseInconsistencyError, ExecuteExt,
+    filter::{Filter, StatementExt},
+    iden::QueueJobs,
+    pagination::QueryBuilderExt,
+};
 
 /// An implementation of [`QueueJobRepository`] for a PostgreSQL connection.
 pub struct PgQueueJobRepository<'c> {
@@ -74,6 +85,106 @@ impl TryFrom<JobReservationResult> for Job {
     }
 }
 
+#[derive(sqlx::FromRow)]
+struct JobLookup {
+    queue_job_id: Uuid,
+    queue_name: String,
+    status: String,
+    payload: serde_json::Value,
+    attempt: i32,
+    created_at: DateTime<Utc>,
+    scheduled_at: Option<DateTime<Utc>>,
+    started_at: Option<DateTime<Utc>>,
+    started_by: Option<Uuid>,
+    completed_at: Option<DateTime<Utc>>,
+    failed_at: Option<DateTime<Utc>>,
+    failed_reason: Option<String>,
+    cancelled_at: Option<DateTime<Utc>>,
+    next_attempt_id: Option<Uuid>,
+    schedule_name: Option<String>,
+}
+
+/// The columns of [`JobLookup`], for building queries with `sea_query`.
+///
+/// This isn't generated with `#[enum_def]`, as that would make it public
+#[derive(sea_query::Iden)]
+enum JobLookupIden {
+    QueueJobId,
+    QueueName,
+    Status,
+    Payload,
+    Attempt,
+    CreatedAt,
+    ScheduledAt,
+    StartedAt,
+    StartedBy,
+    CompletedAt,
+    FailedAt,
+    FailedReason,
+    CancelledAt,
+    NextAttemptId,
+    ScheduleName,
+}
+
+impl Node<Ulid> for JobLookup {
+    fn cursor(&self) -> Ulid {
+        self.queue_job_id.into()
+    }
+}
+
+impl TryFrom<JobLookup> for JobDetails {
+    type Error = DatabaseInconsistencyError;
+
+    fn try_from(value: JobLookup) -> Result<Self, Self::Error> {
+        let id = value.queue_job_id.into();
+
+        let status = value.status.parse().map_err(|e| {
+            DatabaseInconsistencyError::on("queue_jobs")
+                .column("status")
+                .row(id)
+                .source(e)
+        })?;
+
+        let attempt = value.attempt.try_into().map_err(|e| {
+            DatabaseInconsistencyError::on("queue_jobs")
+                .column("attempt")
+                .row(id)
+                .source(e)
+        })?;
+
+        Ok(Self {
+            id,
+            queue_name: value.queue_name,
+            status,
+            payload: value.payload,
+            attempt,
+            created_at: value.created_at,
+            scheduled_at: value.scheduled_at,
+            started_at: value.started_at,
+            started_by: value.started_by.map(Ulid::from),
+            completed_at: value.completed_at,
+            failed_at: value.failed_at,
+            failed_reason: value.failed_reason,
+            cancelled_at: value.cancelled_at,
+            next_attempt_id: value.next_attempt_id.map(Ulid::from),
+            schedule_name: value.schedule_name,
+        })
+    }
+}
+
+impl Filter for JobFilter<'_> {
+    fn generate_condition(&self, _has_joins: bool) -> impl sea_query::IntoCondition {
+        sea_query::Condition::all()
+            .add_option(self.queue_name().map(|queue_name| {
+                Expr::col((QueueJobs::Table, QueueJobs::QueueName)).eq(queue_name)
+            }))
+            .add_option(self.status().map(|status| {
+                Expr::col((QueueJobs::Table, QueueJobs::Status))
+                    .eq(Expr::val(status.as_str()).as_enum(Alias::new("queue_job_status")))
+            }))
+    }
+}
+
 #[async_trait]
 impl QueueJobRepository for PgQueueJobRepository<'_> {
     type Error = DatabaseError;
@@ -221,6 +332,8 @@ impl QueueJobRepository for PgQueueJobRepository<'_> {
                     WHERE
                         status = 'available'
                         AND queue_name = ANY($1)
+                        -- Skip queues paused by an administrator
+                        AND queue_name NOT IN (SELECT queue_name FROM queue_paused_queues)
                     ORDER BY queue_job_id ASC
                     LIMIT $2
                     FOR UPDATE
@@ -446,6 +559,197 @@ impl QueueJobRepository for PgQueueJobRepository<'_> {
         Ok(usize::try_from(count).unwrap_or(usize::MAX))
     }
 
+    #[tracing::instrument(
+        name = "db.queue_job.cancel",
+        skip_all,
+        fields(
+            db.query.text,
+            job.id = %id,
+        ),
+        err,
+    )]
+    async fn cancel(&mut self, clock: &dyn Clock, id: Ulid) -> Result<(), Self::Error> {
+        let now = clock.now();
+        let res = sqlx::query!(
+            r#"
+                UPDATE queue_jobs
+                SET status = 'cancelled', cancelled_at = $1
+                WHERE queue_job_id = $2
+                  AND (status = 'available' OR status = 'scheduled')
+            "#,
+            now,
+            Uuid::from(id),
+        )
+        .traced()
+        .execute(&mut *self.conn)
+        .await?;
+
+        DatabaseError::ensure_affected_rows(&res, 1)?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(
+        name = "db.queue_job.lookup",
+        skip_all,
+        fields(
+            db.query.text,
+            job.id = %id,
+        ),
+        err,
+    )]
+    async fn lookup(&mut self, id: Ulid) -> Result<Option<JobDetails>, Self::Error> {
+        let res = sqlx::query_as!(
+            JobLookup,
+            r#"
+                SELECT queue_job_id,
+                       queue_name,
+                       status::text AS "status!",
+                       payload,
+                       attempt,
+                       created_at,
+                       scheduled_at,
+                       started_at,
+                       started_by,
+                       completed_at,
+                       failed_at,
+                       failed_reason,
+                       cancelled_at,
+                       next_attempt_id,
+                       schedule_name
+                FROM queue_jobs
+                WHERE queue_job_id = $1
+            "#,
+            Uuid::from(id),
+        )
+        .traced()
+        .fetch_optional(&mut *self.conn)
+        .await?;
+
+        let Some(res) = res else { return Ok(None) };
+
+        Ok(Some(res.try_into()?))
+    }
+
+    #[tracing::instrument(
+        name = "db.queue_job.list",
+        skip_all,
+        fields(
+            db.query.text,
+            queue_job.filter = ?filter,
+        ),
+        err,
+    )]
+    async fn list(
+        &mut self,
+        filter: JobFilter<'_>,
+        pagination: Pagination,
+    ) -> Result<Page<JobDetails>, Self::Error> {
+        let (sql, arguments) = Query::select()
+            .expr_as(
+                Expr::col((QueueJobs::Table, QueueJobs::QueueJobId)),
+                JobLookupIden::QueueJobId,
+            )
+            .expr_as(
+                Expr::col((QueueJobs::Table, QueueJobs::QueueName)),
+                JobLookupIden::QueueName,
+            )
+            .expr_as(
+                Func::cast_as(
+                    Expr::col((QueueJobs::Table, QueueJobs::Status)),
+                    Alias::new("text"),
+                ),
+                JobLookupIden::Status,
+            )
+            .expr_as(
+                Expr::col((QueueJobs::Table, QueueJobs::Payload)),
+                JobLookupIden::Payload,
+            )
+            .expr_as(
+                Expr::col((QueueJobs::Table, QueueJobs::Attempt)),
+                JobLookupIden::Attempt,
+            )
+            .expr_as(
+                Expr::col((QueueJobs::Table, QueueJobs::CreatedAt)),
+                JobLookupIden::CreatedAt,
+            )
+            .expr_as(
+                Expr::col((QueueJobs::Table, QueueJobs::ScheduledAt)),
+                JobLookupIden::ScheduledAt,
+            )
+            .expr_as(
+                Expr::col((QueueJobs::Table, QueueJobs::StartedAt)),
+                JobLookupIden::StartedAt,
+            )
+            .expr_as(
+                Expr::col((QueueJobs::Table, QueueJobs::StartedBy)),
+                JobLookupIden::StartedBy,
+            )
+            .expr_as(
+                Expr::col((QueueJobs::Table, QueueJobs::CompletedAt)),
+                JobLookupIden::CompletedAt,
+            )
+            .expr_as(
+                Expr::col((QueueJobs::Table, QueueJobs::FailedAt)),
+                JobLookupIden::FailedAt,
+            )
+            .expr_as(
+                Expr::col((QueueJobs::Table, QueueJobs::FailedReason)),
+                JobLookupIden::FailedReason,
+            )
+            .expr_as(
+                Expr::col((QueueJobs::Table, QueueJobs::CancelledAt)),
+                JobLookupIden::CancelledAt,
+            )
+            .expr_as(
+                Expr::col((QueueJobs::Table, QueueJobs::NextAttemptId)),
+                JobLookupIden::NextAttemptId,
+            )
+            .expr_as(
+                Expr::col((QueueJobs::Table, QueueJobs::ScheduleName)),
+                JobLookupIden::ScheduleName,
+            )
+            .from(QueueJobs::Table)
+            .apply_filter(filter)
+            .generate_pagination((QueueJobs::Table, QueueJobs::QueueJobId), pagination)
+            .build_sqlx(PostgresQueryBuilder);
+
+        let edges: Vec<JobLookup> = sqlx::query_as_with(&sql, arguments)
+            .traced()
+            .fetch_all(&mut *self.conn)
+            .await?;
+
+        let page = pagination.process(edges).try_map(JobDetails::try_from)?;
+
+        Ok(page)
+    }
+
+    #[tracing::instrument(
+        name = "db.queue_job.count",
+        skip_all,
+        fields(
+            db.query.text,
+            queue_job.filter = ?filter,
+        ),
+        err,
+    )]
+    async fn count(&mut self, filter: JobFilter<'_>) -> Result<usize, Self::Error> {
+        let (sql, arguments) = Query::select()
+            .expr(Expr::col((QueueJobs::Table, QueueJobs::QueueJobId)).count())
+            .from(QueueJobs::Table)
+            .apply_filter(filter)
+            .build_sqlx(PostgresQueryBuilder);
+
+        let count: i64 = sqlx::query_scalar_with(&sql, arguments)
+            .traced()
+            .fetch_one(&mut *self.conn)
+            .await?;
+
+        count
+            .try_into()
+            .map_err(DatabaseError::to_invalid_operation)
+    }
+
     #[tracing::instrument(
         name = "db.queue_job.cleanup",
         skip_all,
@@ -463,15 +767,15 @@ impl QueueJobRepository for PgQueueJobRepository<'_> {
         until: Ulid,
         limit: usize,
     ) -> Result<(usize, Option<Ulid>), Self::Error> {
-        // Use ULID cursor-based pagination for completed and failed jobs.
-        // We delete both completed and failed jobs in the same batch.
+        // Use ULID cursor-based pagination for completed, failed and cancelled jobs.
+        // We delete all of them in the same batch.
         // `MAX(uuid)` isn't a thing in Postgres, so we aggregate on the client side.
         let res = sqlx::query_scalar!(
             r#"
                 WITH to_delete AS (
                     SELECT queue_job_id
                     FROM queue_jobs
-                    WHERE (status = 'completed' OR status = 'failed')
+                    WHERE (status = 'completed' OR status = 'failed' OR status = 'cancelled')
                       AND ($1::uuid IS NULL OR queue_job_id > $1)
                       AND queue_job_id <= $2
                     ORDER BY queue_job_id
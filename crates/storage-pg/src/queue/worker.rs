@@ -1,3 +1,4 @@
+// Copyright 2026 Element Creations Ltd.
 // Copyright 2024, 2025 New Vector Ltd.
 //
 // SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
@@ -7,11 +8,13 @@
 //! [`QueueWorkerRepository`].
 
 use async_trait::async_trait;
-use chrono::Duration;
+use chrono::{DateTime, Duration, Utc};
 use mas_data_model::Clock;
-use mas_storage::queue::{QueueWorkerRepository, Worker};
+use mas_storage::queue::{PausedQueue, QueueWorkerRepository, Worker};
+use opentelemetry_semantic_conventions::trace::DB_QUERY_TEXT;
 use rand::RngCore;
 use sqlx::PgConnection;
+use tracing::Instrument;
 use ulid::Ulid;
 use uuid::Uuid;
 
@@ -22,6 +25,20 @@ pub struct PgQueueWorkerRepository<'c> {
     conn: &'c mut PgConnection,
 }
 
+struct PausedQueueLookup {
+    queue_name: String,
+    paused_at: DateTime<Utc>,
+}
+
+impl From<PausedQueueLookup> for PausedQueue {
+    fn from(value: PausedQueueLookup) -> Self {
+        Self {
+            queue_name: value.queue_name,
+            paused_at: value.paused_at,
+        }
+    }
+}
+
 impl<'c> PgQueueWorkerRepository<'c> {
     /// Create a new [`PgQueueWorkerRepository`] from an active PostgreSQL
     /// connection.
@@ -256,4 +273,104 @@ impl QueueWorkerRepository for PgQueueWorkerRepository<'_> {
 
         Ok(am_i_the_leader)
     }
+
+    #[tracing::instrument(
+        name = "db.queue_worker.pause_queue",
+        skip_all,
+        fields(
+            queue.name = queue_name,
+            db.query.text,
+        ),
+        err,
+    )]
+    async fn pause_queue(
+        &mut self,
+        clock: &dyn Clock,
+        queue_name: &str,
+    ) -> Result<bool, Self::Error> {
+        let now = clock.now();
+        let res = sqlx::query!(
+            r#"
+                INSERT INTO queue_paused_queues (queue_name, paused_at)
+                VALUES ($1, $2)
+                ON CONFLICT (queue_name) DO NOTHING
+            "#,
+            queue_name,
+            now,
+        )
+        .traced()
+        .execute(&mut *self.conn)
+        .await?;
+
+        Ok(res.rows_affected() == 1)
+    }
+
+    #[tracing::instrument(
+        name = "db.queue_worker.resume_queue",
+        skip_all,
+        fields(
+            queue.name = queue_name,
+            db.query.text,
+        ),
+        err,
+    )]
+    async fn resume_queue(&mut self, queue_name: &str) -> Result<bool, Self::Error> {
+        let res = sqlx::query!(
+            r#"
+                DELETE FROM queue_paused_queues
+                WHERE queue_name = $1
+            "#,
+            queue_name,
+        )
+        .traced()
+        .execute(&mut *self.conn)
+        .await?;
+
+        if res.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        // Jobs may have piled up while the queue was paused, so wake up the
+        // workers the same way the `queue_job_notify` trigger does
+        let span = tracing::info_span!(
+            "db.queue_worker.resume_queue.notify",
+            { DB_QUERY_TEXT } = tracing::field::Empty,
+        );
+        sqlx::query!(
+            r#"
+                SELECT pg_notify('queue_available', json_build_object('queue', $1::text)::text)
+            "#,
+            queue_name,
+        )
+        .record(&span)
+        .execute(&mut *self.conn)
+        .instrument(span)
+        .await?;
+
+        Ok(true)
+    }
+
+    #[tracing::instrument(
+        name = "db.queue_worker.list_paused_queues",
+        skip_all,
+        fields(
+            db.query.text,
+        ),
+        err,
+    )]
+    async fn list_paused_queues(&mut self) -> Result<Vec<PausedQueue>, Self::Error> {
+        let res = sqlx::query_as!(
+            PausedQueueLookup,
+            r#"
+                SELECT queue_name, paused_at
+                FROM queue_paused_queues
+                ORDER BY queue_name ASC
+            "#,
+        )
+        .traced()
+        .fetch_all(&mut *self.conn)
+        .await?;
+
+        Ok(res.into_iter().map(Into::into).collect())
+    }
 }
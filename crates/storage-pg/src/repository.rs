@@ -13,8 +13,8 @@ use mas_storage::{
     RepositoryFactory, RepositoryTransaction,
     app_session::AppSessionRepository,
     compat::{
-        CompatAccessTokenRepository, CompatRefreshTokenRepository, CompatSessionRepository,
-        CompatSsoLoginRepository,
+        CompatAccessTokenRepository, CompatRefreshTokenRepository, CompatSessionAnomalyRepository,
+        CompatSessionRepository, CompatSsoLoginRepository,
     },
     oauth2::{
         OAuth2AccessTokenRepository, OAuth2AuthorizationGrantRepository, OAuth2ClientRepository,
@@ -23,14 +23,16 @@ use mas_storage::{
     personal::PersonalSessionRepository,
     policy_data::PolicyDataRepository,
     queue::{QueueJobRepository, QueueScheduleRepository, QueueWorkerRepository},
+    signing_key::SigningKeyRepository,
+    stats::StatsRepository,
     upstream_oauth2::{
         UpstreamOAuthLinkRepository, UpstreamOAuthProviderRepository,
         UpstreamOAuthSessionRepository,
     },
     user::{
-        BrowserSessionRepository, UserEmailRepository, UserPasswordRepository,
-        UserRecoveryRepository, UserRegistrationRepository, UserRegistrationTokenRepository,
-        UserRepository, UserTermsRepository,
+        BrowserSessionRepository, UserEmailRepository, UserImportRepository,
+        UserPasswordRepository, UserRecoveryRepository, UserRegistrationRepository,
+        UserRegistrationTokenRepository, UserRepository, UserTermsRepository,
     },
 };
 use sqlx::{PgConnection, PgPool, Postgres, Transaction};
@@ -40,8 +42,8 @@ use crate::{
     DatabaseError,
     app_session::PgAppSessionRepository,
     compat::{
-        PgCompatAccessTokenRepository, PgCompatRefreshTokenRepository, PgCompatSessionRepository,
-        PgCompatSsoLoginRepository,
+        PgCompatAccessTokenRepository, PgCompatRefreshTokenRepository,
+        PgCompatSessionAnomalyRepository, PgCompatSessionRepository, PgCompatSsoLoginRepository,
     },
     oauth2::{
         PgOAuth2AccessTokenRepository, PgOAuth2AuthorizationGrantRepository,
@@ -54,15 +56,17 @@ use crate::{
         job::PgQueueJobRepository, schedule::PgQueueScheduleRepository,
         worker::PgQueueWorkerRepository,
     },
+    signing_key::PgSigningKeyRepository,
+    stats::PgStatsRepository,
     telemetry::DB_CLIENT_CONNECTIONS_CREATE_TIME_HISTOGRAM,
     upstream_oauth2::{
         PgUpstreamOAuthLinkRepository, PgUpstreamOAuthProviderRepository,
         PgUpstreamOAuthSessionRepository,
     },
     user::{
-        PgBrowserSessionRepository, PgUserEmailRepository, PgUserPasswordRepository,
-        PgUserRecoveryRepository, PgUserRegistrationRepository, PgUserRegistrationTokenRepository,
-        PgUserRepository, PgUserTermsRepository,
+        PgBrowserSessionRepository, PgUserEmailRepository, PgUserImportRepository,
+        PgUserPasswordRepository, PgUserRecoveryRepository, PgUserRegistrationRepository,
+        PgUserRegistrationTokenRepository, PgUserRepository, PgUserTermsRepository,
     },
 };
 
@@ -242,6 +246,10 @@ where
         Box::new(PgUserRecoveryRepository::new(self.conn.as_mut()))
     }
 
+    fn user_import<'c>(&'c mut self) -> Box<dyn UserImportRepository<Error = Self::Error> + 'c> {
+        Box::new(PgUserImportRepository::new(self.conn.as_mut()))
+    }
+
     fn user_terms<'c>(&'c mut self) -> Box<dyn UserTermsRepository<Error = Self::Error> + 'c> {
         Box::new(PgUserTermsRepository::new(self.conn.as_mut()))
     }
@@ -312,6 +320,12 @@ where
         Box::new(PgCompatSessionRepository::new(self.conn.as_mut()))
     }
 
+    fn compat_session_anomaly<'c>(
+        &'c mut self,
+    ) -> Box<dyn CompatSessionAnomalyRepository<Error = Self::Error> + 'c> {
+        Box::new(PgCompatSessionAnomalyRepository::new(self.conn.as_mut()))
+    }
+
     fn compat_sso_login<'c>(
         &'c mut self,
     ) -> Box<dyn CompatSsoLoginRepository<Error = Self::Error> + 'c> {
@@ -360,4 +374,12 @@ where
     fn policy_data<'c>(&'c mut self) -> Box<dyn PolicyDataRepository<Error = Self::Error> + 'c> {
         Box::new(PgPolicyDataRepository::new(self.conn.as_mut()))
     }
+
+    fn stats<'c>(&'c mut self) -> Box<dyn StatsRepository<Error = Self::Error> + 'c> {
+        Box::new(PgStatsRepository::new(self.conn.as_mut()))
+    }
+
+    fn signing_key<'c>(&'c mut self) -> Box<dyn SigningKeyRepository<Error = Self::Error> + 'c> {
+        Box::new(PgSigningKeyRepository::new(self.conn.as_mut()))
+    }
 }
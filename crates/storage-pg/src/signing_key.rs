@@ -0,0 +1,380 @@
+// Copyright 2026 Element Creations Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+// Please see LICENSE files in the repository root for full details.
+
+//! A module containing the PostgreSQL implementation of the signing keys
+//! storage.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use mas_data_model::{Clock, SigningKey, SigningKeyState, SigningKeyType};
+use mas_storage::signing_key::SigningKeyRepository;
+use rand::RngCore;
+use sqlx::PgConnection;
+use ulid::Ulid;
+use uuid::Uuid;
+
+use crate::{DatabaseError, DatabaseInconsistencyError, ExecuteExt};
+
+/// An implementation of [`SigningKeyRepository`] for a PostgreSQL connection.
+pub struct PgSigningKeyRepository<'c> {
+    conn: &'c mut PgConnection,
+}
+
+impl<'c> PgSigningKeyRepository<'c> {
+    /// Create a new [`PgSigningKeyRepository`] from an active PostgreSQL
+    /// connection.
+    #[must_use]
+    pub fn new(conn: &'c mut PgConnection) -> Self {
+        Self { conn }
+    }
+}
+
+struct SigningKeyLookup {
+    signing_key_id: Uuid,
+    kid: String,
+    key_type: String,
+    encrypted_key: String,
+    created_at: DateTime<Utc>,
+    activated_at: Option<DateTime<Utc>>,
+    retired_at: Option<DateTime<Utc>>,
+}
+
+impl TryFrom<SigningKeyLookup> for SigningKey {
+    type Error = DatabaseInconsistencyError;
+
+    fn try_from(value: SigningKeyLookup) -> Result<Self, Self::Error> {
+        let id = value.signing_key_id.into();
+        let key_type: SigningKeyType = value.key_type.parse().map_err(|e| {
+            DatabaseInconsistencyError::on("signing_keys")
+                .column("key_type")
+                .row(id)
+                .source(e)
+        })?;
+
+        let state = match (value.activated_at, value.retired_at) {
+            (None, None) => SigningKeyState::Pending,
+            (Some(activated_at), None) => SigningKeyState::Active { activated_at },
+            (activated_at, Some(retired_at)) => SigningKeyState::Retired {
+                activated_at,
+                retired_at,
+            },
+        };
+
+        Ok(SigningKey {
+            id,
+            kid: value.kid,
+            key_type,
+            state,
+            encrypted_key: value.encrypted_key,
+            created_at: value.created_at,
+        })
+    }
+}
+
+#[async_trait]
+impl SigningKeyRepository for PgSigningKeyRepository<'_> {
+    type Error = DatabaseError;
+
+    #[tracing::instrument(
+        name = "db.signing_key.lookup",
+        skip_all,
+        fields(
+            db.query.text,
+            signing_key.id = %id,
+        ),
+        err,
+    )]
+    async fn lookup(&mut self, id: Ulid) -> Result<Option<SigningKey>, Self::Error> {
+        let res = sqlx::query_as!(
+            SigningKeyLookup,
+            r#"
+                SELECT signing_key_id
+                     , kid
+                     , key_type
+                     , encrypted_key
+                     , created_at
+                     , activated_at
+                     , retired_at
+                FROM signing_keys
+                WHERE signing_key_id = $1
+            "#,
+            Uuid::from(id),
+        )
+        .traced()
+        .fetch_optional(&mut *self.conn)
+        .await?;
+
+        let Some(res) = res else { return Ok(None) };
+
+        Ok(Some(res.try_into()?))
+    }
+
+    #[tracing::instrument(
+        name = "db.signing_key.all",
+        skip_all,
+        fields(
+            db.query.text,
+        ),
+        err,
+    )]
+    async fn all(&mut self) -> Result<Vec<SigningKey>, Self::Error> {
+        let res = sqlx::query_as!(
+            SigningKeyLookup,
+            r#"
+                SELECT signing_key_id
+                     , kid
+                     , key_type
+                     , encrypted_key
+                     , created_at
+                     , activated_at
+                     , retired_at
+                FROM signing_keys
+                ORDER BY signing_key_id ASC
+            "#,
+        )
+        .traced()
+        .fetch_all(&mut *self.conn)
+        .await?;
+
+        let res: Result<Vec<_>, _> = res.into_iter().map(TryInto::try_into).collect();
+        Ok(res?)
+    }
+
+    #[tracing::instrument(
+        name = "db.signing_key.add",
+        skip_all,
+        fields(
+            db.query.text,
+            signing_key.id,
+            signing_key.kid = %kid,
+            signing_key.key_type = %key_type,
+        ),
+        err,
+    )]
+    async fn add(
+        &mut self,
+        rng: &mut (dyn RngCore + Send),
+        clock: &dyn Clock,
+        kid: String,
+        key_type: SigningKeyType,
+        encrypted_key: String,
+    ) -> Result<SigningKey, Self::Error> {
+        let created_at = clock.now();
+        let id = Ulid::from_datetime_with_source(created_at.into(), rng);
+        tracing::Span::current().record("signing_key.id", tracing::field::display(id));
+
+        sqlx::query!(
+            r#"
+                INSERT INTO signing_keys
+                    ( signing_key_id
+                    , kid
+                    , key_type
+                    , encrypted_key
+                    , created_at
+                    )
+                VALUES ($1, $2, $3, $4, $5)
+            "#,
+            Uuid::from(id),
+            &kid,
+            key_type.as_str(),
+            &encrypted_key,
+            created_at,
+        )
+        .traced()
+        .execute(&mut *self.conn)
+        .await?;
+
+        Ok(SigningKey {
+            id,
+            kid,
+            key_type,
+            state: SigningKeyState::Pending,
+            encrypted_key,
+            created_at,
+        })
+    }
+
+    #[tracing::instrument(
+        name = "db.signing_key.activate",
+        skip_all,
+        fields(
+            db.query.text,
+            %signing_key.id,
+            %signing_key.kid,
+        ),
+        err,
+    )]
+    async fn activate(
+        &mut self,
+        clock: &dyn Clock,
+        signing_key: SigningKey,
+    ) -> Result<SigningKey, Self::Error> {
+        let activated_at = clock.now();
+        let res = sqlx::query!(
+            r#"
+                UPDATE signing_keys
+                SET activated_at = $2
+                WHERE signing_key_id = $1
+                  AND activated_at IS NULL
+                  AND retired_at IS NULL
+            "#,
+            Uuid::from(signing_key.id),
+            activated_at,
+        )
+        .traced()
+        .execute(&mut *self.conn)
+        .await?;
+
+        DatabaseError::ensure_affected_rows(&res, 1)?;
+
+        signing_key
+            .activate(activated_at)
+            .map_err(DatabaseError::to_invalid_operation)
+    }
+
+    #[tracing::instrument(
+        name = "db.signing_key.retire",
+        skip_all,
+        fields(
+            db.query.text,
+            %signing_key.id,
+            %signing_key.kid,
+        ),
+        err,
+    )]
+    async fn retire(
+        &mut self,
+        clock: &dyn Clock,
+        signing_key: SigningKey,
+    ) -> Result<SigningKey, Self::Error> {
+        let retired_at = clock.now();
+        let res = sqlx::query!(
+            r#"
+                UPDATE signing_keys
+                SET retired_at = $2
+                WHERE signing_key_id = $1
+                  AND retired_at IS NULL
+            "#,
+            Uuid::from(signing_key.id),
+            retired_at,
+        )
+        .traced()
+        .execute(&mut *self.conn)
+        .await?;
+
+        DatabaseError::ensure_affected_rows(&res, 1)?;
+
+        signing_key
+            .retire(retired_at)
+            .map_err(DatabaseError::to_invalid_operation)
+    }
+
+    #[tracing::instrument(
+        name = "db.signing_key.remove_retired",
+        skip_all,
+        fields(
+            db.query.text,
+        ),
+        err,
+    )]
+    async fn remove_retired(
+        &mut self,
+        retired_before: DateTime<Utc>,
+    ) -> Result<usize, Self::Error> {
+        let res = sqlx::query!(
+            r#"
+                DELETE FROM signing_keys
+                WHERE retired_at < $1
+            "#,
+            retired_before,
+        )
+        .traced()
+        .execute(&mut *self.conn)
+        .await?;
+
+        Ok(res
+            .rows_affected()
+            .try_into()
+            .map_err(DatabaseError::to_invalid_operation)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+    use mas_data_model::{Clock, SigningKeyType, clock::MockClock};
+    use mas_storage::signing_key::SigningKeyRepository;
+    use rand::SeedableRng;
+    use rand_chacha::ChaChaRng;
+    use sqlx::PgPool;
+
+    use crate::signing_key::PgSigningKeyRepository;
+
+    #[sqlx::test(migrator = "crate::MIGRATOR")]
+    async fn test_signing_key_lifecycle(pool: PgPool) {
+        let mut rng = ChaChaRng::seed_from_u64(42);
+        let clock = MockClock::default();
+        let mut conn = pool.acquire().await.unwrap();
+        let mut repo = PgSigningKeyRepository::new(&mut conn);
+
+        // No keys at first
+        assert!(repo.all().await.unwrap().is_empty());
+
+        // Add a key
+        let key = repo
+            .add(
+                &mut rng,
+                &clock,
+                "kid1".to_owned(),
+                SigningKeyType::Rsa,
+                "encrypted".to_owned(),
+            )
+            .await
+            .unwrap();
+        assert!(key.is_pending());
+        assert_eq!(repo.lookup(key.id).await.unwrap().as_ref(), Some(&key));
+
+        // Activate it
+        clock.advance(Duration::hours(1));
+        let key = repo.activate(&clock, key).await.unwrap();
+        assert!(key.is_active());
+        assert_eq!(key.activated_at(), Some(clock.now()));
+        assert_eq!(repo.lookup(key.id).await.unwrap().as_ref(), Some(&key));
+
+        // Activating it twice should fail
+        assert!(repo.activate(&clock, key.clone()).await.is_err());
+
+        // Add a second key
+        let key2 = repo
+            .add(
+                &mut rng,
+                &clock,
+                "kid2".to_owned(),
+                SigningKeyType::EcP256,
+                "encrypted".to_owned(),
+            )
+            .await
+            .unwrap();
+
+        let all = repo.all().await.unwrap();
+        assert_eq!(all, vec![key.clone(), key2.clone()]);
+
+        // Retire the first key
+        clock.advance(Duration::hours(1));
+        let key = repo.retire(&clock, key).await.unwrap();
+        assert!(key.is_retired());
+        assert_eq!(key.retired_at(), Some(clock.now()));
+        assert!(key.activated_at().is_some());
+
+        // Removing keys retired before now does nothing
+        assert_eq!(repo.remove_retired(clock.now()).await.unwrap(), 0);
+
+        // But removing the ones retired before a bit later removes it
+        clock.advance(Duration::hours(1));
+        assert_eq!(repo.remove_retired(clock.now()).await.unwrap(), 1);
+        assert_eq!(repo.lookup(key.id).await.unwrap(), None);
+        assert_eq!(repo.all().await.unwrap(), vec![key2]);
+    }
+}
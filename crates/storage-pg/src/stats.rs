@@ -0,0 +1,447 @@
+// Copyright 2026 Element Creations Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+// Please see LICENSE files in the repository root for full details.
+
+//! A module containing the PostgreSQL implementation of the usage statistics
+//! storage.
+
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDate, Utc};
+use mas_data_model::Clock;
+use mas_storage::stats::{DailyStat, StatsMetric, StatsRepository};
+use sqlx::PgConnection;
+use ulid::Ulid;
+use uuid::Uuid;
+
+use crate::{DatabaseError, DatabaseInconsistencyError, ExecuteExt};
+
+/// An implementation of [`StatsRepository`] for a PostgreSQL connection.
+pub struct PgStatsRepository<'c> {
+    conn: &'c mut PgConnection,
+}
+
+impl<'c> PgStatsRepository<'c> {
+    /// Create a new [`PgStatsRepository`] from an active PostgreSQL
+    /// connection.
+    #[must_use]
+    pub fn new(conn: &'c mut PgConnection) -> Self {
+        Self { conn }
+    }
+}
+
+struct DailyStatLookup {
+    day: NaiveDate,
+    metric: String,
+    dimension: Option<Uuid>,
+    value: i64,
+}
+
+impl TryFrom<DailyStatLookup> for DailyStat {
+    type Error = DatabaseInconsistencyError;
+
+    fn try_from(value: DailyStatLookup) -> Result<Self, Self::Error> {
+        let metric = value.metric.parse().map_err(|e| {
+            DatabaseInconsistencyError::on("stats_daily")
+                .column("metric")
+                .source(e)
+        })?;
+
+        let count = value.value.try_into().map_err(|e| {
+            DatabaseInconsistencyError::on("stats_daily")
+                .column("value")
+                .source(e)
+        })?;
+
+        Ok(DailyStat {
+            day: value.day,
+            metric,
+            dimension: value.dimension.map(Ulid::from),
+            value: count,
+        })
+    }
+}
+
+#[async_trait]
+impl StatsRepository for PgStatsRepository<'_> {
+    type Error = DatabaseError;
+
+    #[tracing::instrument(
+        name = "db.stats.count_active_users",
+        skip_all,
+        fields(
+            db.query.text,
+            since = %since,
+            until = %until,
+        ),
+        err,
+    )]
+    async fn count_active_users(
+        &mut self,
+        since: DateTime<Utc>,
+        until: DateTime<Utc>,
+    ) -> Result<usize, Self::Error> {
+        let count = sqlx::query_scalar!(
+            r#"
+                SELECT COUNT(*) AS "count!"
+                FROM (
+                    SELECT user_id
+                    FROM user_sessions
+                    WHERE last_active_at >= $1 AND last_active_at < $2
+
+                    UNION
+
+                    SELECT user_id
+                    FROM compat_sessions
+                    WHERE last_active_at >= $1 AND last_active_at < $2
+
+                    UNION
+
+                    SELECT user_id
+                    FROM oauth2_sessions
+                    WHERE user_id IS NOT NULL
+                      AND last_active_at >= $1 AND last_active_at < $2
+                ) AS active_users
+            "#,
+            since,
+            until,
+        )
+        .traced()
+        .fetch_one(&mut *self.conn)
+        .await?;
+
+        count
+            .try_into()
+            .map_err(DatabaseError::to_invalid_operation)
+    }
+
+    #[tracing::instrument(
+        name = "db.stats.count_active_oauth2_sessions_by_client",
+        skip_all,
+        fields(
+            db.query.text,
+        ),
+        err,
+    )]
+    async fn count_active_oauth2_sessions_by_client(
+        &mut self,
+    ) -> Result<Vec<(Ulid, usize)>, Self::Error> {
+        let rows = sqlx::query!(
+            r#"
+                SELECT oauth2_client_id, COUNT(*) AS "count!"
+                FROM oauth2_sessions
+                WHERE finished_at IS NULL
+                GROUP BY oauth2_client_id
+                ORDER BY oauth2_client_id
+            "#,
+        )
+        .traced()
+        .fetch_all(&mut *self.conn)
+        .await?;
+
+        rows.into_iter()
+            .map(|row| {
+                let count = row
+                    .count
+                    .try_into()
+                    .map_err(DatabaseError::to_invalid_operation)?;
+                Ok((Ulid::from(row.oauth2_client_id), count))
+            })
+            .collect()
+    }
+
+    #[tracing::instrument(
+        name = "db.stats.replace_day",
+        skip_all,
+        fields(
+            db.query.text,
+            stats.day = %day,
+            stats.count = stats.len(),
+        ),
+        err,
+    )]
+    async fn replace_day(
+        &mut self,
+        clock: &dyn Clock,
+        day: NaiveDate,
+        stats: &[DailyStat],
+    ) -> Result<(), Self::Error> {
+        let computed_at = clock.now();
+
+        let mut metrics = Vec::with_capacity(stats.len());
+        let mut dimensions = Vec::with_capacity(stats.len());
+        let mut values = Vec::with_capacity(stats.len());
+        for stat in stats {
+            metrics.push(stat.metric.as_str().to_owned());
+            dimensions.push(stat.dimension.map(Uuid::from));
+            values.push(i64::try_from(stat.value).map_err(DatabaseError::to_invalid_operation)?);
+        }
+
+        sqlx::query!(
+            r#"
+                DELETE FROM stats_daily
+                WHERE day = $1
+            "#,
+            day,
+        )
+        .traced()
+        .execute(&mut *self.conn)
+        .await?;
+
+        sqlx::query!(
+            r#"
+                INSERT INTO stats_daily (day, metric, dimension, value, computed_at)
+                SELECT $1, metric, dimension, value, $5
+                FROM UNNEST($2::text[], $3::uuid[], $4::int8[])
+                    AS t(metric, dimension, value)
+            "#,
+            day,
+            &metrics,
+            &dimensions as &[Option<Uuid>],
+            &values,
+            computed_at,
+        )
+        .traced()
+        .execute(&mut *self.conn)
+        .await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(
+        name = "db.stats.last_day",
+        skip_all,
+        fields(
+            db.query.text,
+        ),
+        err,
+    )]
+    async fn last_day(&mut self) -> Result<Option<NaiveDate>, Self::Error> {
+        let day = sqlx::query_scalar!(
+            r#"
+                SELECT MAX(day) AS "day"
+                FROM stats_daily
+            "#,
+        )
+        .traced()
+        .fetch_one(&mut *self.conn)
+        .await?;
+
+        Ok(day)
+    }
+
+    #[tracing::instrument(
+        name = "db.stats.list",
+        skip_all,
+        fields(
+            db.query.text,
+            stats.metric = %metric,
+            since = %since,
+            until = %until,
+        ),
+        err,
+    )]
+    async fn list(
+        &mut self,
+        metric: StatsMetric,
+        since: NaiveDate,
+        until: NaiveDate,
+    ) -> Result<Vec<DailyStat>, Self::Error> {
+        let rows = sqlx::query_as!(
+            DailyStatLookup,
+            r#"
+                SELECT day, metric, dimension, value
+                FROM stats_daily
+                WHERE metric = $1
+                  AND day >= $2
+                  AND day <= $3
+                ORDER BY day, dimension NULLS FIRST
+            "#,
+            metric.as_str(),
+            since,
+            until,
+        )
+        .traced()
+        .fetch_all(&mut *self.conn)
+        .await?;
+
+        rows.into_iter()
+            .map(|row| row.try_into().map_err(DatabaseError::from))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, NaiveDate};
+    use mas_data_model::{Clock, clock::MockClock};
+    use mas_storage::{
+        RepositoryAccess,
+        stats::{DailyStat, StatsMetric},
+    };
+    use rand::SeedableRng;
+    use rand_chacha::ChaChaRng;
+    use sqlx::PgPool;
+
+    use crate::PgRepository;
+
+    #[sqlx::test(migrator = "crate::MIGRATOR")]
+    async fn test_count_active_users(pool: PgPool) {
+        let mut rng = ChaChaRng::seed_from_u64(42);
+        let clock = MockClock::default();
+        let mut repo = PgRepository::from_pool(&pool).await.unwrap().boxed();
+
+        let since = clock.now() - Duration::days(1);
+        let until = clock.now() + Duration::days(1);
+        assert_eq!(
+            repo.stats().count_active_users(since, until).await.unwrap(),
+            0
+        );
+
+        let alice = repo
+            .user()
+            .add(&mut rng, &clock, "alice".to_owned())
+            .await
+            .unwrap();
+        let bob = repo
+            .user()
+            .add(&mut rng, &clock, "bob".to_owned())
+            .await
+            .unwrap();
+
+        // Alice has two active sessions, Bob has one which never recorded any
+        // activity
+        let session1 = repo
+            .browser_session()
+            .add(&mut rng, &clock, &alice, None)
+            .await
+            .unwrap();
+        let session2 = repo
+            .browser_session()
+            .add(&mut rng, &clock, &alice, None)
+            .await
+            .unwrap();
+        repo.browser_session()
+            .add(&mut rng, &clock, &bob, None)
+            .await
+            .unwrap();
+
+        repo.browser_session()
+            .record_batch_activity(vec![
+                (session1.id, clock.now(), None),
+                (session2.id, clock.now(), None),
+            ])
+            .await
+            .unwrap();
+
+        assert_eq!(
+            repo.stats().count_active_users(since, until).await.unwrap(),
+            1
+        );
+
+        // The range end is exclusive
+        assert_eq!(
+            repo.stats()
+                .count_active_users(since, clock.now())
+                .await
+                .unwrap(),
+            0
+        );
+    }
+
+    #[sqlx::test(migrator = "crate::MIGRATOR")]
+    async fn test_replace_and_list(pool: PgPool) {
+        let clock = MockClock::default();
+        let mut repo = PgRepository::from_pool(&pool).await.unwrap().boxed();
+
+        let day1 = NaiveDate::from_ymd_opt(2026, 10, 1).unwrap();
+        let day2 = NaiveDate::from_ymd_opt(2026, 10, 2).unwrap();
+        let client_id = ulid::Ulid::from_bytes([0x01; 16]);
+
+        assert_eq!(repo.stats().last_day().await.unwrap(), None);
+
+        let stat = |day, metric, dimension, value| DailyStat {
+            day,
+            metric,
+            dimension,
+            value,
+        };
+
+        repo.stats()
+            .replace_day(
+                &clock,
+                day1,
+                &[
+                    stat(day1, StatsMetric::DailyActiveUsers, None, 10),
+                    stat(day1, StatsMetric::ActiveOAuth2Sessions, None, 5),
+                    stat(day1, StatsMetric::ActiveOAuth2Sessions, Some(client_id), 5),
+                ],
+            )
+            .await
+            .unwrap();
+
+        repo.stats()
+            .replace_day(
+                &clock,
+                day2,
+                &[stat(day2, StatsMetric::DailyActiveUsers, None, 12)],
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(repo.stats().last_day().await.unwrap(), Some(day2));
+
+        let stats = repo
+            .stats()
+            .list(StatsMetric::DailyActiveUsers, day1, day2)
+            .await
+            .unwrap();
+        assert_eq!(
+            stats,
+            vec![
+                stat(day1, StatsMetric::DailyActiveUsers, None, 10),
+                stat(day2, StatsMetric::DailyActiveUsers, None, 12),
+            ]
+        );
+
+        let stats = repo
+            .stats()
+            .list(StatsMetric::ActiveOAuth2Sessions, day1, day1)
+            .await
+            .unwrap();
+        assert_eq!(
+            stats,
+            vec![
+                stat(day1, StatsMetric::ActiveOAuth2Sessions, None, 5),
+                stat(day1, StatsMetric::ActiveOAuth2Sessions, Some(client_id), 5),
+            ]
+        );
+
+        // Replacing a day removes all the previous values for that day
+        repo.stats()
+            .replace_day(
+                &clock,
+                day1,
+                &[stat(day1, StatsMetric::DailyActiveUsers, None, 11)],
+            )
+            .await
+            .unwrap();
+
+        let stats = repo
+            .stats()
+            .list(StatsMetric::ActiveOAuth2Sessions, day1, day2)
+            .await
+            .unwrap();
+        assert!(stats.is_empty());
+
+        let stats = repo
+            .stats()
+            .list(StatsMetric::DailyActiveUsers, day1, day1)
+            .await
+            .unwrap();
+        assert_eq!(
+            stats,
+            vec![stat(day1, StatsMetric::DailyActiveUsers, None, 11)]
+        );
+    }
+}
@@ -114,6 +114,21 @@ impl Filter for UpstreamOAuthLinkFilter<'_> {
             .add_option(self.subject().map(|subject| {
                 Expr::col((UpstreamOAuthLinks::Table, UpstreamOAuthLinks::Subject)).eq(subject)
             }))
+            .add_option(self.has_refresh_token().map(|has_refresh_token| {
+                Expr::col((
+                    UpstreamOAuthLinks::Table,
+                    UpstreamOAuthLinks::EncryptedRefreshToken,
+                ))
+                .is_not_null()
+                .eq(has_refresh_token)
+            }))
+            .add_option(self.access_token_expires_before().map(|before| {
+                Expr::col((
+                    UpstreamOAuthLinks::Table,
+                    UpstreamOAuthLinks::AccessTokenExpiresAt,
+                ))
+                .lt(before)
+            }))
     }
 }
 
@@ -286,6 +301,262 @@ impl UpstreamOAuthLinkRepository for PgUpstreamOAuthLinkRepository<'_> {
         Ok(())
     }
 
+    #[tracing::instrument(
+        name = "db.upstream_oauth_link.set_encrypted_refresh_token",
+        skip_all,
+        fields(
+            db.query.text,
+            %upstream_oauth_link.id,
+        ),
+        err,
+    )]
+    async fn set_encrypted_refresh_token(
+        &mut self,
+        upstream_oauth_link: &UpstreamOAuthLink,
+        encrypted_refresh_token: Option<String>,
+    ) -> Result<(), Self::Error> {
+        let res = sqlx::query!(
+            r#"
+                UPDATE upstream_oauth_links
+                SET encrypted_refresh_token = $1
+                WHERE upstream_oauth_link_id = $2
+            "#,
+            encrypted_refresh_token,
+            Uuid::from(upstream_oauth_link.id),
+        )
+        .traced()
+        .execute(&mut *self.conn)
+        .await?;
+
+        DatabaseError::ensure_affected_rows(&res, 1)?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(
+        name = "db.upstream_oauth_link.get_encrypted_refresh_token",
+        skip_all,
+        fields(
+            db.query.text,
+            %upstream_oauth_link.id,
+        ),
+        err,
+    )]
+    async fn get_encrypted_refresh_token(
+        &mut self,
+        upstream_oauth_link: &UpstreamOAuthLink,
+    ) -> Result<Option<String>, Self::Error> {
+        let res = sqlx::query_scalar!(
+            r#"
+                SELECT encrypted_refresh_token
+                FROM upstream_oauth_links
+                WHERE upstream_oauth_link_id = $1
+            "#,
+            Uuid::from(upstream_oauth_link.id),
+        )
+        .traced()
+        .fetch_optional(&mut *self.conn)
+        .await?;
+
+        Ok(res.flatten())
+    }
+
+    #[tracing::instrument(
+        name = "db.upstream_oauth_link.set_encrypted_access_token",
+        skip_all,
+        fields(
+            db.query.text,
+            %upstream_oauth_link.id,
+        ),
+        err,
+    )]
+    async fn set_encrypted_access_token(
+        &mut self,
+        upstream_oauth_link: &UpstreamOAuthLink,
+        encrypted_access_token: Option<String>,
+        expires_at: Option<DateTime<Utc>>,
+    ) -> Result<(), Self::Error> {
+        let res = sqlx::query!(
+            r#"
+                UPDATE upstream_oauth_links
+                SET encrypted_access_token = $1,
+                    access_token_expires_at = $2
+                WHERE upstream_oauth_link_id = $3
+            "#,
+            encrypted_access_token,
+            expires_at,
+            Uuid::from(upstream_oauth_link.id),
+        )
+        .traced()
+        .execute(&mut *self.conn)
+        .await?;
+
+        DatabaseError::ensure_affected_rows(&res, 1)?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(
+        name = "db.upstream_oauth_link.get_encrypted_access_token",
+        skip_all,
+        fields(
+            db.query.text,
+            %upstream_oauth_link.id,
+        ),
+        err,
+    )]
+    async fn get_encrypted_access_token(
+        &mut self,
+        upstream_oauth_link: &UpstreamOAuthLink,
+    ) -> Result<Option<(String, Option<DateTime<Utc>>)>, Self::Error> {
+        let res = sqlx::query!(
+            r#"
+                SELECT encrypted_access_token, access_token_expires_at
+                FROM upstream_oauth_links
+                WHERE upstream_oauth_link_id = $1
+            "#,
+            Uuid::from(upstream_oauth_link.id),
+        )
+        .traced()
+        .fetch_optional(&mut *self.conn)
+        .await?;
+
+        Ok(res.and_then(|row| {
+            row.encrypted_access_token
+                .map(|token| (token, row.access_token_expires_at))
+        }))
+    }
+
+    #[tracing::instrument(
+        name = "db.upstream_oauth_link.set_user_locked_at",
+        skip_all,
+        fields(
+            db.query.text,
+            %upstream_oauth_link.id,
+        ),
+        err,
+    )]
+    async fn set_user_locked_at(
+        &mut self,
+        upstream_oauth_link: &UpstreamOAuthLink,
+        user_locked_at: Option<DateTime<Utc>>,
+    ) -> Result<(), Self::Error> {
+        let res = sqlx::query!(
+            r#"
+                UPDATE upstream_oauth_links
+                SET user_locked_at = $1
+                WHERE upstream_oauth_link_id = $2
+            "#,
+            user_locked_at,
+            Uuid::from(upstream_oauth_link.id),
+        )
+        .traced()
+        .execute(&mut *self.conn)
+        .await?;
+
+        DatabaseError::ensure_affected_rows(&res, 1)?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(
+        name = "db.upstream_oauth_link.get_user_locked_at",
+        skip_all,
+        fields(
+            db.query.text,
+            %upstream_oauth_link.id,
+        ),
+        err,
+    )]
+    async fn get_user_locked_at(
+        &mut self,
+        upstream_oauth_link: &UpstreamOAuthLink,
+    ) -> Result<Option<DateTime<Utc>>, Self::Error> {
+        let res = sqlx::query_scalar!(
+            r#"
+                SELECT user_locked_at
+                FROM upstream_oauth_links
+                WHERE upstream_oauth_link_id = $1
+            "#,
+            Uuid::from(upstream_oauth_link.id),
+        )
+        .traced()
+        .fetch_optional(&mut *self.conn)
+        .await?;
+
+        Ok(res.flatten())
+    }
+
+    #[tracing::instrument(
+        name = "db.upstream_oauth_link.set_attributes",
+        skip_all,
+        fields(
+            db.query.text,
+            %upstream_oauth_link.id,
+        ),
+        err,
+    )]
+    async fn set_attributes(
+        &mut self,
+        upstream_oauth_link: &UpstreamOAuthLink,
+        attributes: Option<serde_json::Map<String, serde_json::Value>>,
+    ) -> Result<(), Self::Error> {
+        let res = sqlx::query!(
+            r#"
+                UPDATE upstream_oauth_links
+                SET attributes = $1
+                WHERE upstream_oauth_link_id = $2
+            "#,
+            attributes.map(serde_json::Value::Object),
+            Uuid::from(upstream_oauth_link.id),
+        )
+        .traced()
+        .execute(&mut *self.conn)
+        .await?;
+
+        DatabaseError::ensure_affected_rows(&res, 1)?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(
+        name = "db.upstream_oauth_link.attributes_for_user",
+        skip_all,
+        fields(
+            db.query.text,
+            %user.id,
+        ),
+        err,
+    )]
+    async fn attributes_for_user(
+        &mut self,
+        user: &User,
+    ) -> Result<serde_json::Map<String, serde_json::Value>, Self::Error> {
+        let rows = sqlx::query_scalar!(
+            r#"
+                SELECT attributes AS "attributes!"
+                FROM upstream_oauth_links
+                WHERE user_id = $1
+                  AND attributes IS NOT NULL
+                ORDER BY upstream_oauth_link_id
+            "#,
+            Uuid::from(user.id),
+        )
+        .traced()
+        .fetch_all(&mut *self.conn)
+        .await?;
+
+        // Links are ordered by creation, so newer links override older ones
+        let mut attributes = serde_json::Map::new();
+        for row in rows {
+            if let serde_json::Value::Object(object) = row {
+                attributes.extend(object);
+            }
+        }
+
+        Ok(attributes)
+    }
+
     #[tracing::instrument(
         name = "db.upstream_oauth_link.list",
         skip_all,
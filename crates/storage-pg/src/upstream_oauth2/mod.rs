@@ -20,7 +20,8 @@ pub use self::{
 mod tests {
     use chrono::Duration;
     use mas_data_model::{
-        UpstreamOAuthProviderClaimsImports, UpstreamOAuthProviderOnBackchannelLogout,
+        Clock, UpstreamOAuthProviderClaimsImports, UpstreamOAuthProviderHealthCheck,
+        UpstreamOAuthProviderHealthChecks, UpstreamOAuthProviderOnBackchannelLogout,
         UpstreamOAuthProviderTokenAuthMethod, clock::MockClock,
     };
     use mas_iana::jose::JsonWebSignatureAlg;
@@ -79,6 +80,11 @@ mod tests {
                     forward_login_hint: false,
                     ui_order: 0,
                     on_backchannel_logout: UpstreamOAuthProviderOnBackchannelLogout::DoNothing,
+                    saml: None,
+                    email_domains: Vec::new(),
+                    store_tokens: false,
+                    allow_initiated_login: false,
+                    encrypted_client_keys: None,
                 },
             )
             .await
@@ -219,6 +225,136 @@ mod tests {
 
         assert_eq!(repo.upstream_oauth_link().count(filter).await.unwrap(), 1);
 
+        // Store a refresh token on the link
+        let filter = UpstreamOAuthLinkFilter::new().with_refresh_token_only();
+        assert_eq!(repo.upstream_oauth_link().count(filter).await.unwrap(), 0);
+        assert_eq!(
+            repo.upstream_oauth_link()
+                .get_encrypted_refresh_token(&link)
+                .await
+                .unwrap(),
+            None
+        );
+
+        repo.upstream_oauth_link()
+            .set_encrypted_refresh_token(&link, Some("encrypted".to_owned()))
+            .await
+            .unwrap();
+        assert_eq!(repo.upstream_oauth_link().count(filter).await.unwrap(), 1);
+        assert_eq!(
+            repo.upstream_oauth_link()
+                .get_encrypted_refresh_token(&link)
+                .await
+                .unwrap()
+                .as_deref(),
+            Some("encrypted")
+        );
+
+        repo.upstream_oauth_link()
+            .set_encrypted_refresh_token(&link, None)
+            .await
+            .unwrap();
+        assert_eq!(repo.upstream_oauth_link().count(filter).await.unwrap(), 0);
+
+        // Store an access token on the link
+        let expires_at = clock.now() + Duration::minutes(5);
+        let filter = UpstreamOAuthLinkFilter::new()
+            .with_access_token_expiring_before(clock.now() + Duration::minutes(10));
+        assert_eq!(repo.upstream_oauth_link().count(filter).await.unwrap(), 0);
+        repo.upstream_oauth_link()
+            .set_encrypted_access_token(&link, Some("encrypted".to_owned()), Some(expires_at))
+            .await
+            .unwrap();
+        assert_eq!(repo.upstream_oauth_link().count(filter).await.unwrap(), 1);
+        assert_eq!(
+            repo.upstream_oauth_link()
+                .get_encrypted_access_token(&link)
+                .await
+                .unwrap(),
+            Some(("encrypted".to_owned(), Some(expires_at)))
+        );
+
+        let filter = UpstreamOAuthLinkFilter::new().with_access_token_expiring_before(clock.now());
+        assert_eq!(repo.upstream_oauth_link().count(filter).await.unwrap(), 0);
+
+        repo.upstream_oauth_link()
+            .set_encrypted_access_token(&link, None, None)
+            .await
+            .unwrap();
+        assert_eq!(
+            repo.upstream_oauth_link()
+                .get_encrypted_access_token(&link)
+                .await
+                .unwrap(),
+            None
+        );
+
+        // Attributes are empty until some are imported
+        assert!(
+            repo.upstream_oauth_link()
+                .attributes_for_user(&user)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+
+        let mut attributes = serde_json::Map::new();
+        attributes.insert("groups".to_owned(), serde_json::json!(["admins"]));
+        repo.upstream_oauth_link()
+            .set_attributes(&link, Some(attributes.clone()))
+            .await
+            .unwrap();
+        assert_eq!(
+            repo.upstream_oauth_link()
+                .attributes_for_user(&user)
+                .await
+                .unwrap(),
+            attributes
+        );
+
+        repo.upstream_oauth_link()
+            .set_attributes(&link, None)
+            .await
+            .unwrap();
+        assert!(
+            repo.upstream_oauth_link()
+                .attributes_for_user(&user)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+
+        // Record that the provider locked the user
+        assert_eq!(
+            repo.upstream_oauth_link()
+                .get_user_locked_at(&link)
+                .await
+                .unwrap(),
+            None
+        );
+        repo.upstream_oauth_link()
+            .set_user_locked_at(&link, Some(clock.now()))
+            .await
+            .unwrap();
+        assert_eq!(
+            repo.upstream_oauth_link()
+                .get_user_locked_at(&link)
+                .await
+                .unwrap(),
+            Some(clock.now())
+        );
+        repo.upstream_oauth_link()
+            .set_user_locked_at(&link, None)
+            .await
+            .unwrap();
+        assert_eq!(
+            repo.upstream_oauth_link()
+                .get_user_locked_at(&link)
+                .await
+                .unwrap(),
+            None
+        );
+
         // There should be exactly one enabled provider
         assert_eq!(
             repo.upstream_oauth_provider()
@@ -294,6 +430,59 @@ mod tests {
         assert!(!session_page.has_next_page);
         assert!(!session_page.has_previous_page);
 
+        // The provider was never checked
+        assert!(
+            repo.upstream_oauth_provider()
+                .health(&provider)
+                .await
+                .unwrap()
+                .is_none()
+        );
+
+        // Record a failed health check
+        let checks = UpstreamOAuthProviderHealthChecks {
+            discovery: UpstreamOAuthProviderHealthCheck::Ok,
+            jwks: UpstreamOAuthProviderHealthCheck::Failed {
+                error: "connection refused".to_owned(),
+            },
+            token_endpoint: UpstreamOAuthProviderHealthCheck::Skipped,
+        };
+        repo.upstream_oauth_provider()
+            .record_health(&clock, &provider, checks.clone())
+            .await
+            .unwrap();
+
+        let health = repo
+            .upstream_oauth_provider()
+            .health(&provider)
+            .await
+            .unwrap()
+            .expect("health to be recorded");
+        assert_eq!(health.checked_at, clock.now());
+        assert_eq!(health.checks, checks);
+        assert!(!health.checks.is_healthy());
+
+        // Recording a new one replaces it
+        clock.advance(Duration::microseconds(10 * 1000 * 1000));
+        let checks = UpstreamOAuthProviderHealthChecks {
+            discovery: UpstreamOAuthProviderHealthCheck::Ok,
+            jwks: UpstreamOAuthProviderHealthCheck::Ok,
+            token_endpoint: UpstreamOAuthProviderHealthCheck::Ok,
+        };
+        repo.upstream_oauth_provider()
+            .record_health(&clock, &provider, checks.clone())
+            .await
+            .unwrap();
+
+        let health = repo
+            .upstream_oauth_provider()
+            .health(&provider)
+            .await
+            .unwrap()
+            .expect("health to be recorded");
+        assert_eq!(health.checked_at, clock.now());
+        assert!(health.checks.is_healthy());
+
         // Try deleting the provider
         repo.upstream_oauth_provider()
             .delete(provider)
@@ -359,6 +548,11 @@ mod tests {
                         forward_login_hint: false,
                         ui_order: 0,
                         on_backchannel_logout: UpstreamOAuthProviderOnBackchannelLogout::DoNothing,
+                        saml: None,
+                        email_domains: Vec::new(),
+                        store_tokens: false,
+                        allow_initiated_login: false,
+                        encrypted_client_keys: None,
                     },
                 )
                 .await
@@ -497,6 +691,11 @@ mod tests {
                     forward_login_hint: false,
                     ui_order: 0,
                     on_backchannel_logout: UpstreamOAuthProviderOnBackchannelLogout::DoNothing,
+                    saml: None,
+                    email_domains: Vec::new(),
+                    store_tokens: false,
+                    allow_initiated_login: false,
+                    encrypted_client_keys: None,
                 },
             )
             .await
@@ -6,7 +6,11 @@
 
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
-use mas_data_model::{Clock, UpstreamOAuthProvider, UpstreamOAuthProviderClaimsImports};
+use mas_data_model::{
+    Clock, UpstreamOAuthProvider, UpstreamOAuthProviderClaimsImports, UpstreamOAuthProviderHealth,
+    UpstreamOAuthProviderHealthCheck, UpstreamOAuthProviderHealthChecks,
+    UpstreamOAuthProviderSamlConfig,
+};
 use mas_storage::{
     Page, Pagination,
     pagination::Node,
@@ -45,6 +49,7 @@ impl<'c> PgUpstreamOAuthProviderRepository<'c> {
     }
 }
 
+#[allow(clippy::struct_excessive_bools)]
 #[derive(sqlx::FromRow)]
 #[enum_def]
 struct ProviderLookup {
@@ -73,6 +78,11 @@ struct ProviderLookup {
     additional_parameters: Option<Json<Vec<(String, String)>>>,
     forward_login_hint: bool,
     on_backchannel_logout: String,
+    saml_config: Option<Json<UpstreamOAuthProviderSamlConfig>>,
+    email_domains: Vec<String>,
+    store_tokens: bool,
+    allow_initiated_login: bool,
+    encrypted_client_keys: Option<String>,
 }
 
 impl Node<Ulid> for ProviderLookup {
@@ -234,6 +244,11 @@ impl TryFrom<ProviderLookup> for UpstreamOAuthProvider {
             additional_authorization_parameters,
             forward_login_hint: value.forward_login_hint,
             on_backchannel_logout,
+            saml: value.saml_config.map(|Json(x)| x),
+            email_domains: value.email_domains,
+            store_tokens: value.store_tokens,
+            allow_initiated_login: value.allow_initiated_login,
+            encrypted_client_keys: value.encrypted_client_keys,
         })
     }
 }
@@ -293,7 +308,12 @@ impl UpstreamOAuthProviderRepository for PgUpstreamOAuthProviderRepository<'_> {
                     response_mode,
                     additional_parameters as "additional_parameters: Json<Vec<(String, String)>>",
                     forward_login_hint,
-                    on_backchannel_logout
+                    on_backchannel_logout,
+                    saml_config as "saml_config: Json<UpstreamOAuthProviderSamlConfig>",
+                    email_domains,
+                    store_tokens,
+                    allow_initiated_login,
+                    encrypted_client_keys
                 FROM upstream_oauth_providers
                 WHERE upstream_oauth_provider_id = $1
             "#,
@@ -357,10 +377,15 @@ impl UpstreamOAuthProviderRepository for PgUpstreamOAuthProviderRepository<'_> {
                 response_mode,
                 forward_login_hint,
                 on_backchannel_logout,
+                saml_config,
+                email_domains,
+                store_tokens,
+                allow_initiated_login,
+                encrypted_client_keys,
                 created_at
             ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11,
                       $12, $13, $14, $15, $16, $17, $18, $19, $20,
-                      $21, $22, $23)
+                      $21, $22, $23, $24, $25, $26, $27, $28)
         "#,
             Uuid::from(id),
             params.issuer.as_deref(),
@@ -399,6 +424,11 @@ impl UpstreamOAuthProviderRepository for PgUpstreamOAuthProviderRepository<'_> {
             params.response_mode.as_ref().map(ToString::to_string),
             params.forward_login_hint,
             params.on_backchannel_logout.as_str(),
+            params.saml.as_ref().map(Json) as _,
+            &params.email_domains,
+            params.store_tokens,
+            params.allow_initiated_login,
+            params.encrypted_client_keys.as_deref(),
             created_at,
         )
         .traced()
@@ -431,6 +461,11 @@ impl UpstreamOAuthProviderRepository for PgUpstreamOAuthProviderRepository<'_> {
             additional_authorization_parameters: params.additional_authorization_parameters,
             on_backchannel_logout: params.on_backchannel_logout,
             forward_login_hint: params.forward_login_hint,
+            saml: params.saml,
+            email_domains: params.email_domains,
+            store_tokens: params.store_tokens,
+            allow_initiated_login: params.allow_initiated_login,
+            encrypted_client_keys: params.encrypted_client_keys,
         })
     }
 
@@ -465,6 +500,27 @@ impl UpstreamOAuthProviderRepository for PgUpstreamOAuthProviderRepository<'_> {
             .await?;
         }
 
+        // Delete the health check results, as they have a foreign key constraint
+        // on the providers.
+        {
+            let span = info_span!(
+                "db.oauth2_client.delete_by_id.health",
+                upstream_oauth_provider.id = %id,
+                { DB_QUERY_TEXT } = tracing::field::Empty,
+            );
+            sqlx::query!(
+                r#"
+                    DELETE FROM upstream_oauth_provider_health
+                    WHERE upstream_oauth_provider_id = $1
+                "#,
+                Uuid::from(id),
+            )
+            .record(&span)
+            .execute(&mut *self.conn)
+            .instrument(span)
+            .await?;
+        }
+
         // Delete the links next, as they have a foreign key constraint on the
         // providers.
         {
@@ -546,10 +602,15 @@ impl UpstreamOAuthProviderRepository for PgUpstreamOAuthProviderRepository<'_> {
                     forward_login_hint,
                     ui_order,
                     on_backchannel_logout,
+                    saml_config,
+                    email_domains,
+                    store_tokens,
+                    allow_initiated_login,
+                    encrypted_client_keys,
                     created_at
                 ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10,
                           $11, $12, $13, $14, $15, $16, $17, $18, $19, $20,
-                          $21, $22, $23, $24, $25)
+                          $21, $22, $23, $24, $25, $26, $27, $28, $29, $30)
                 ON CONFLICT (upstream_oauth_provider_id)
                     DO UPDATE
                     SET
@@ -576,7 +637,12 @@ impl UpstreamOAuthProviderRepository for PgUpstreamOAuthProviderRepository<'_> {
                         additional_parameters = EXCLUDED.additional_parameters,
                         forward_login_hint = EXCLUDED.forward_login_hint,
                         ui_order = EXCLUDED.ui_order,
-                        on_backchannel_logout = EXCLUDED.on_backchannel_logout
+                        on_backchannel_logout = EXCLUDED.on_backchannel_logout,
+                        saml_config = EXCLUDED.saml_config,
+                        email_domains = EXCLUDED.email_domains,
+                        store_tokens = EXCLUDED.store_tokens,
+                        allow_initiated_login = EXCLUDED.allow_initiated_login,
+                        encrypted_client_keys = EXCLUDED.encrypted_client_keys
                 RETURNING created_at
             "#,
             Uuid::from(id),
@@ -618,6 +684,11 @@ impl UpstreamOAuthProviderRepository for PgUpstreamOAuthProviderRepository<'_> {
             params.forward_login_hint,
             params.ui_order,
             params.on_backchannel_logout.as_str(),
+            params.saml.as_ref().map(Json) as _,
+            &params.email_domains,
+            params.store_tokens,
+            params.allow_initiated_login,
+            params.encrypted_client_keys.as_deref(),
             created_at,
         )
         .traced()
@@ -650,6 +721,11 @@ impl UpstreamOAuthProviderRepository for PgUpstreamOAuthProviderRepository<'_> {
             additional_authorization_parameters: params.additional_authorization_parameters,
             forward_login_hint: params.forward_login_hint,
             on_backchannel_logout: params.on_backchannel_logout,
+            saml: params.saml,
+            email_domains: params.email_domains,
+            store_tokens: params.store_tokens,
+            allow_initiated_login: params.allow_initiated_login,
+            encrypted_client_keys: params.encrypted_client_keys,
         })
     }
 
@@ -874,6 +950,41 @@ impl UpstreamOAuthProviderRepository for PgUpstreamOAuthProviderRepository<'_> {
                 )),
                 ProviderLookupIden::OnBackchannelLogout,
             )
+            .expr_as(
+                Expr::col((
+                    UpstreamOAuthProviders::Table,
+                    UpstreamOAuthProviders::SamlConfig,
+                )),
+                ProviderLookupIden::SamlConfig,
+            )
+            .expr_as(
+                Expr::col((
+                    UpstreamOAuthProviders::Table,
+                    UpstreamOAuthProviders::EmailDomains,
+                )),
+                ProviderLookupIden::EmailDomains,
+            )
+            .expr_as(
+                Expr::col((
+                    UpstreamOAuthProviders::Table,
+                    UpstreamOAuthProviders::StoreTokens,
+                )),
+                ProviderLookupIden::StoreTokens,
+            )
+            .expr_as(
+                Expr::col((
+                    UpstreamOAuthProviders::Table,
+                    UpstreamOAuthProviders::AllowInitiatedLogin,
+                )),
+                ProviderLookupIden::AllowInitiatedLogin,
+            )
+            .expr_as(
+                Expr::col((
+                    UpstreamOAuthProviders::Table,
+                    UpstreamOAuthProviders::EncryptedClientKeys,
+                )),
+                ProviderLookupIden::EncryptedClientKeys,
+            )
             .from(UpstreamOAuthProviders::Table)
             .apply_filter(filter)
             .generate_pagination(
@@ -968,7 +1079,12 @@ impl UpstreamOAuthProviderRepository for PgUpstreamOAuthProviderRepository<'_> {
                     response_mode,
                     additional_parameters as "additional_parameters: Json<Vec<(String, String)>>",
                     forward_login_hint,
-                    on_backchannel_logout
+                    on_backchannel_logout,
+                    saml_config as "saml_config: Json<UpstreamOAuthProviderSamlConfig>",
+                    email_domains,
+                    store_tokens,
+                    allow_initiated_login,
+                    encrypted_client_keys
                 FROM upstream_oauth_providers
                 WHERE disabled_at IS NULL
                 ORDER BY ui_order ASC, upstream_oauth_provider_id ASC
@@ -981,4 +1097,97 @@ impl UpstreamOAuthProviderRepository for PgUpstreamOAuthProviderRepository<'_> {
         let res: Result<Vec<_>, _> = res.into_iter().map(TryInto::try_into).collect();
         Ok(res?)
     }
+
+    #[tracing::instrument(
+        name = "db.upstream_oauth_provider.record_health",
+        skip_all,
+        fields(
+            db.query.text,
+            %upstream_oauth_provider.id,
+        ),
+        err,
+    )]
+    async fn record_health(
+        &mut self,
+        clock: &dyn Clock,
+        upstream_oauth_provider: &UpstreamOAuthProvider,
+        checks: UpstreamOAuthProviderHealthChecks,
+    ) -> Result<UpstreamOAuthProviderHealth, Self::Error> {
+        let checked_at = clock.now();
+
+        sqlx::query!(
+            r#"
+                INSERT INTO upstream_oauth_provider_health (
+                    upstream_oauth_provider_id,
+                    checked_at,
+                    discovery,
+                    jwks,
+                    token_endpoint
+                ) VALUES ($1, $2, $3, $4, $5)
+                ON CONFLICT (upstream_oauth_provider_id)
+                    DO UPDATE
+                    SET
+                        checked_at = EXCLUDED.checked_at,
+                        discovery = EXCLUDED.discovery,
+                        jwks = EXCLUDED.jwks,
+                        token_endpoint = EXCLUDED.token_endpoint
+            "#,
+            Uuid::from(upstream_oauth_provider.id),
+            checked_at,
+            Json(&checks.discovery) as _,
+            Json(&checks.jwks) as _,
+            Json(&checks.token_endpoint) as _,
+        )
+        .traced()
+        .execute(&mut *self.conn)
+        .await?;
+
+        Ok(UpstreamOAuthProviderHealth {
+            provider_id: upstream_oauth_provider.id,
+            checked_at,
+            checks,
+        })
+    }
+
+    #[tracing::instrument(
+        name = "db.upstream_oauth_provider.health",
+        skip_all,
+        fields(
+            db.query.text,
+            %upstream_oauth_provider.id,
+        ),
+        err,
+    )]
+    async fn health(
+        &mut self,
+        upstream_oauth_provider: &UpstreamOAuthProvider,
+    ) -> Result<Option<UpstreamOAuthProviderHealth>, Self::Error> {
+        let res = sqlx::query!(
+            r#"
+                SELECT
+                    checked_at,
+                    discovery as "discovery: Json<UpstreamOAuthProviderHealthCheck>",
+                    jwks as "jwks: Json<UpstreamOAuthProviderHealthCheck>",
+                    token_endpoint as "token_endpoint: Json<UpstreamOAuthProviderHealthCheck>"
+                FROM upstream_oauth_provider_health
+                WHERE upstream_oauth_provider_id = $1
+            "#,
+            Uuid::from(upstream_oauth_provider.id),
+        )
+        .traced()
+        .fetch_optional(&mut *self.conn)
+        .await?;
+
+        let Some(res) = res else { return Ok(None) };
+
+        Ok(Some(UpstreamOAuthProviderHealth {
+            provider_id: upstream_oauth_provider.id,
+            checked_at: res.checked_at,
+            checks: UpstreamOAuthProviderHealthChecks {
+                discovery: res.discovery.0,
+                jwks: res.jwks.0,
+                token_endpoint: res.token_endpoint.0,
+            },
+        }))
+    }
 }
@@ -57,6 +57,20 @@ impl Filter for UpstreamOAuthSessionFilter<'_> {
                 .cast_json_field("sid")
                 .eq(sid)
             }))
+            .add_option(self.consumed_after().map(|consumed_after| {
+                Expr::col((
+                    UpstreamOAuthAuthorizationSessions::Table,
+                    UpstreamOAuthAuthorizationSessions::ConsumedAt,
+                ))
+                .gte(consumed_after)
+            }))
+            .add_option(self.consumed_before().map(|consumed_before| {
+                Expr::col((
+                    UpstreamOAuthAuthorizationSessions::Table,
+                    UpstreamOAuthAuthorizationSessions::ConsumedAt,
+                ))
+                .lt(consumed_before)
+            }))
     }
 }
 
@@ -0,0 +1,129 @@
+// Copyright 2026 Element Creations Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+// Please see LICENSE files in the repository root for full details.
+
+use async_trait::async_trait;
+use mas_data_model::Clock;
+use mas_storage::user::{UserImportRecord, UserImportRepository};
+use rand::RngCore;
+use sqlx::PgConnection;
+use ulid::Ulid;
+use uuid::Uuid;
+
+use crate::{DatabaseError, DatabaseInconsistencyError, tracing::ExecuteExt};
+
+/// An implementation of [`UserImportRepository`] for a PostgreSQL connection
+pub struct PgUserImportRepository<'c> {
+    conn: &'c mut PgConnection,
+}
+
+impl<'c> PgUserImportRepository<'c> {
+    /// Create a new [`PgUserImportRepository`] from an active PostgreSQL
+    /// connection
+    pub fn new(conn: &'c mut PgConnection) -> Self {
+        Self { conn }
+    }
+}
+
+#[async_trait]
+impl UserImportRepository for PgUserImportRepository<'_> {
+    type Error = DatabaseError;
+
+    #[tracing::instrument(
+        name = "db.user_import.add",
+        skip_all,
+        fields(
+            db.query.text,
+            user_import.id,
+            user_import.count = records.len(),
+        ),
+        err,
+    )]
+    async fn add(
+        &mut self,
+        rng: &mut (dyn RngCore + Send),
+        clock: &dyn Clock,
+        records: Vec<UserImportRecord>,
+    ) -> Result<Ulid, Self::Error> {
+        let created_at = clock.now();
+        let id = Ulid::from_datetime_with_source(created_at.into(), rng);
+        tracing::Span::current().record("user_import.id", tracing::field::display(id));
+
+        let records = serde_json::to_value(records).expect("Could not serialize user records");
+
+        sqlx::query!(
+            r#"
+                INSERT INTO user_imports
+                    (user_import_id, created_at, records)
+                VALUES ($1, $2, $3)
+            "#,
+            Uuid::from(id),
+            created_at,
+            records,
+        )
+        .traced()
+        .execute(&mut *self.conn)
+        .await?;
+
+        Ok(id)
+    }
+
+    #[tracing::instrument(
+        name = "db.user_import.lookup",
+        skip_all,
+        fields(
+            db.query.text,
+            user_import.id = %id,
+        ),
+        err,
+    )]
+    async fn lookup(&mut self, id: Ulid) -> Result<Option<Vec<UserImportRecord>>, Self::Error> {
+        let res = sqlx::query_scalar!(
+            r#"
+                SELECT records
+                FROM user_imports
+                WHERE user_import_id = $1
+            "#,
+            Uuid::from(id),
+        )
+        .traced()
+        .fetch_optional(&mut *self.conn)
+        .await?;
+
+        let Some(records) = res else { return Ok(None) };
+
+        let records = serde_json::from_value(records).map_err(|e| {
+            DatabaseInconsistencyError::on("user_imports")
+                .column("records")
+                .row(id)
+                .source(e)
+        })?;
+
+        Ok(Some(records))
+    }
+
+    #[tracing::instrument(
+        name = "db.user_import.remove",
+        skip_all,
+        fields(
+            db.query.text,
+            user_import.id = %id,
+        ),
+        err,
+    )]
+    async fn remove(&mut self, id: Ulid) -> Result<(), Self::Error> {
+        sqlx::query!(
+            r#"
+                DELETE FROM user_imports
+                WHERE user_import_id = $1
+            "#,
+            Uuid::from(id),
+        )
+        .traced()
+        .execute(&mut *self.conn)
+        .await?;
+
+        Ok(())
+    }
+}
@@ -27,6 +27,7 @@ use crate::{
 };
 
 mod email;
+mod import;
 mod password;
 mod recovery;
 mod registration;
@@ -38,8 +39,9 @@ mod terms;
 mod tests;
 
 pub use self::{
-    email::PgUserEmailRepository, password::PgUserPasswordRepository,
-    recovery::PgUserRecoveryRepository, registration::PgUserRegistrationRepository,
+    email::PgUserEmailRepository, import::PgUserImportRepository,
+    password::PgUserPasswordRepository, recovery::PgUserRecoveryRepository,
+    registration::PgUserRegistrationRepository,
     registration_token::PgUserRegistrationTokenRepository, session::PgBrowserSessionRepository,
     terms::PgUserTermsRepository,
 };
@@ -132,6 +134,12 @@ impl Filter for UserFilter<'_> {
             .add_option(self.search().map(|search| {
                 Expr::col((Users::Table, Users::Username)).ilike(format!("%{search}%"))
             }))
+            .add_option(self.created_after().map(|created_after| {
+                Expr::col((Users::Table, Users::CreatedAt)).gte(created_after)
+            }))
+            .add_option(self.created_before().map(|created_before| {
+                Expr::col((Users::Table, Users::CreatedAt)).lt(created_before)
+            }))
     }
 }
 
@@ -223,6 +231,97 @@ impl UserRepository for PgUserRepository<'_> {
         }
     }
 
+    #[tracing::instrument(
+        name = "db.user.find_by_ldap_dn",
+        skip_all,
+        fields(
+            db.query.text,
+            user_ldap_link.dn = dn,
+        ),
+        err,
+    )]
+    async fn find_by_ldap_dn(&mut self, dn: &str) -> Result<Option<User>, Self::Error> {
+        let res = sqlx::query_as!(
+            UserLookup,
+            r#"
+                SELECT u.user_id
+                     , u.username
+                     , u.created_at
+                     , u.locked_at
+                     , u.deactivated_at
+                     , u.can_request_admin
+                     , u.is_guest
+                FROM users u
+                INNER JOIN user_ldap_links l
+                    USING (user_id)
+                WHERE l.dn = $1
+            "#,
+            dn,
+        )
+        .traced()
+        .fetch_optional(&mut *self.conn)
+        .await?;
+
+        Ok(res.map(Into::into))
+    }
+
+    #[tracing::instrument(
+        name = "db.user.ldap_dn",
+        skip_all,
+        fields(
+            db.query.text,
+            %user.id,
+        ),
+        err,
+    )]
+    async fn ldap_dn(&mut self, user: &User) -> Result<Option<String>, Self::Error> {
+        let dn = sqlx::query_scalar!(
+            r#"
+                SELECT dn
+                FROM user_ldap_links
+                WHERE user_id = $1
+            "#,
+            Uuid::from(user.id),
+        )
+        .traced()
+        .fetch_optional(&mut *self.conn)
+        .await?;
+
+        Ok(dn)
+    }
+
+    #[tracing::instrument(
+        name = "db.user.add_ldap_link",
+        skip_all,
+        fields(
+            db.query.text,
+            %user.id,
+            user_ldap_link.dn = dn,
+        ),
+        err,
+    )]
+    async fn add_ldap_link(
+        &mut self,
+        clock: &dyn Clock,
+        user: &User,
+        dn: String,
+    ) -> Result<(), Self::Error> {
+        sqlx::query!(
+            r#"
+                INSERT INTO user_ldap_links (user_id, dn, created_at)
+                VALUES ($1, $2, $3)
+            "#,
+            Uuid::from(user.id),
+            dn,
+            clock.now(),
+        )
+        .traced()
+        .execute(&mut *self.conn)
+        .await?;
+
+        Ok(())
+    }
+
     #[tracing::instrument(
         name = "db.user.add",
         skip_all,
@@ -494,6 +593,42 @@ impl UserRepository for PgUserRepository<'_> {
         Ok(user)
     }
 
+    #[tracing::instrument(
+        name = "db.user.set_username",
+        skip_all,
+        fields(
+            db.query.text,
+            %user.id,
+            %user.username,
+            user.new_username = %username,
+        ),
+        err,
+    )]
+    async fn set_username(
+        &mut self,
+        mut user: User,
+        username: String,
+    ) -> Result<User, Self::Error> {
+        let res = sqlx::query!(
+            r#"
+                UPDATE users
+                SET username = $2
+                WHERE user_id = $1
+            "#,
+            Uuid::from(user.id),
+            &username,
+        )
+        .traced()
+        .execute(&mut *self.conn)
+        .await?;
+
+        DatabaseError::ensure_affected_rows(&res, 1)?;
+
+        user.username = username;
+
+        Ok(user)
+    }
+
     #[tracing::instrument(
         name = "db.user.list",
         skip_all,
@@ -105,6 +105,7 @@ struct AuthenticationLookup {
     created_at: DateTime<Utc>,
     user_password_id: Option<Uuid>,
     upstream_oauth_authorization_session_id: Option<Uuid>,
+    ldap_dn: Option<String>,
 }
 
 impl TryFrom<AuthenticationLookup> for Authentication {
@@ -117,12 +118,18 @@ impl TryFrom<AuthenticationLookup> for Authentication {
             value
                 .upstream_oauth_authorization_session_id
                 .map(Into::into),
+            value.ldap_dn,
         ) {
-            (Some(user_password_id), None) => AuthenticationMethod::Password { user_password_id },
-            (None, Some(upstream_oauth2_session_id)) => AuthenticationMethod::UpstreamOAuth2 {
-                upstream_oauth2_session_id,
-            },
-            (None, None) => AuthenticationMethod::Unknown,
+            (Some(user_password_id), None, None) => {
+                AuthenticationMethod::Password { user_password_id }
+            }
+            (None, Some(upstream_oauth2_session_id), None) => {
+                AuthenticationMethod::UpstreamOAuth2 {
+                    upstream_oauth2_session_id,
+                }
+            }
+            (None, None, Some(dn)) => AuthenticationMethod::Ldap { dn },
+            (None, None, None) => AuthenticationMethod::Unknown,
             _ => {
                 return Err(DatabaseInconsistencyError::on("user_session_authentications").row(id));
             }
@@ -542,6 +549,52 @@ impl BrowserSessionRepository for PgBrowserSessionRepository<'_> {
         })
     }
 
+    #[tracing::instrument(
+        name = "db.browser_session.authenticate_with_ldap",
+        skip_all,
+        fields(
+            db.query.text,
+            %user_session.id,
+            user_session_authentication.id,
+        ),
+        err,
+    )]
+    async fn authenticate_with_ldap(
+        &mut self,
+        rng: &mut (dyn RngCore + Send),
+        clock: &dyn Clock,
+        user_session: &BrowserSession,
+        dn: &str,
+    ) -> Result<Authentication, Self::Error> {
+        let created_at = clock.now();
+        let id = Ulid::from_datetime_with_source(created_at.into(), rng);
+        tracing::Span::current().record(
+            "user_session_authentication.id",
+            tracing::field::display(id),
+        );
+
+        sqlx::query!(
+            r#"
+                INSERT INTO user_session_authentications
+                    (user_session_authentication_id, user_session_id, created_at, ldap_dn)
+                VALUES ($1, $2, $3, $4)
+            "#,
+            Uuid::from(id),
+            Uuid::from(user_session.id),
+            created_at,
+            dn,
+        )
+        .traced()
+        .execute(&mut *self.conn)
+        .await?;
+
+        Ok(Authentication {
+            id,
+            created_at,
+            authentication_method: AuthenticationMethod::Ldap { dn: dn.to_owned() },
+        })
+    }
+
     #[tracing::instrument(
         name = "db.browser_session.get_last_authentication",
         skip_all,
@@ -562,6 +615,7 @@ impl BrowserSessionRepository for PgBrowserSessionRepository<'_> {
                      , created_at
                      , user_password_id
                      , upstream_oauth_authorization_session_id
+                     , ldap_dn
                 FROM user_session_authentications
                 WHERE user_session_id = $1
                 ORDER BY created_at DESC
@@ -6,7 +6,7 @@
 // Please see LICENSE files in the repository root for full details.
 
 use chrono::Duration;
-use mas_data_model::{Clock, clock::MockClock};
+use mas_data_model::{AuthenticationMethod, Clock, clock::MockClock};
 use mas_iana::jose::JsonWebSignatureAlg;
 use mas_storage::{
     Pagination, RepositoryAccess,
@@ -149,6 +149,23 @@ async fn test_user_repo(pool: PgPool) {
     let user = repo.user().lookup(user.id).await.unwrap().unwrap();
     assert!(!user.can_request_admin);
 
+    // Rename the user, and rename it back
+    let user = repo
+        .user()
+        .set_username(user, "jane".to_owned())
+        .await
+        .unwrap();
+    assert_eq!(user.username, "jane");
+    assert!(repo.user().exists("jane").await.unwrap());
+    assert!(!repo.user().exists(USERNAME).await.unwrap());
+
+    let user = repo
+        .user()
+        .set_username(user, USERNAME.to_owned())
+        .await
+        .unwrap();
+    assert_eq!(user.username, USERNAME);
+
     assert_eq!(repo.user().count(all).await.unwrap(), 1);
     assert_eq!(repo.user().count(admin).await.unwrap(), 0);
     assert_eq!(repo.user().count(non_admin).await.unwrap(), 1);
@@ -772,6 +789,11 @@ async fn test_user_session(pool: PgPool) {
                 ui_order: 0,
                 on_backchannel_logout:
                     mas_data_model::UpstreamOAuthProviderOnBackchannelLogout::DoNothing,
+                saml: None,
+                email_domains: Vec::new(),
+                store_tokens: false,
+                allow_initiated_login: false,
+                encrypted_client_keys: None,
             },
         )
         .await
@@ -834,6 +856,59 @@ async fn test_user_session(pool: PgPool) {
     assert!(lookup.finished_at.is_some());
 }
 
+#[sqlx::test(migrator = "crate::MIGRATOR")]
+async fn test_user_session_ldap_authentication(pool: PgPool) {
+    let mut repo = PgRepository::from_pool(&pool).await.unwrap();
+    let mut rng = ChaChaRng::seed_from_u64(42);
+    let clock = MockClock::default();
+
+    let user = repo
+        .user()
+        .add(&mut rng, &clock, "alice".to_owned())
+        .await
+        .unwrap();
+
+    let session = repo
+        .browser_session()
+        .add(&mut rng, &clock, &user, None)
+        .await
+        .unwrap();
+
+    // There is no authentication yet
+    assert!(
+        repo.browser_session()
+            .get_last_authentication(&session)
+            .await
+            .unwrap()
+            .is_none()
+    );
+
+    let authentication = repo
+        .browser_session()
+        .authenticate_with_ldap(
+            &mut rng,
+            &clock,
+            &session,
+            "uid=alice,ou=people,dc=example,dc=com",
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        authentication.authentication_method,
+        AuthenticationMethod::Ldap {
+            dn: "uid=alice,ou=people,dc=example,dc=com".to_owned()
+        }
+    );
+
+    let last = repo
+        .browser_session()
+        .get_last_authentication(&session)
+        .await
+        .unwrap()
+        .expect("the authentication to be found");
+    assert_eq!(last, authentication);
+}
+
 #[sqlx::test(migrator = "crate::MIGRATOR")]
 async fn test_user_terms(pool: PgPool) {
     let mut repo = PgRepository::from_pool(&pool).await.unwrap();
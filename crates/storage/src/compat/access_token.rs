@@ -86,6 +86,20 @@ pub trait CompatAccessTokenRepository: Send + Sync {
         clock: &dyn Clock,
         compat_access_token: CompatAccessToken,
     ) -> Result<CompatAccessToken, Self::Error>;
+
+    /// Mark the compat access token as used
+    ///
+    /// Returns the updated compat access token
+    ///
+    /// # Parameters
+    ///
+    /// * `clock`: The clock used to generate timestamps
+    /// * `compat_access_token`: The compat access token to mark as used
+    async fn mark_used(
+        &mut self,
+        clock: &dyn Clock,
+        compat_access_token: CompatAccessToken,
+    ) -> Result<CompatAccessToken, Self::Error>;
 }
 
 repository_impl!(CompatAccessTokenRepository:
@@ -110,4 +124,10 @@ repository_impl!(CompatAccessTokenRepository:
         clock: &dyn Clock,
         compat_access_token: CompatAccessToken,
     ) -> Result<CompatAccessToken, Self::Error>;
+
+    async fn mark_used(
+        &mut self,
+        clock: &dyn Clock,
+        compat_access_token: CompatAccessToken,
+    ) -> Result<CompatAccessToken, Self::Error>;
 );
@@ -0,0 +1,166 @@
+// Copyright 2026 Element Creations Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+// Please see LICENSE files in the repository root for full details.
+
+use std::net::IpAddr;
+
+use async_trait::async_trait;
+use mas_data_model::{Clock, CompatSession, CompatSessionAnomaly, CompatSessionAnomalyKind, User};
+use rand_core::RngCore;
+
+use crate::{Page, Pagination, repository_impl};
+
+/// Filter parameters for listing compat session anomalies
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub struct CompatSessionAnomalyFilter<'a> {
+    user: Option<&'a User>,
+    compat_session: Option<&'a CompatSession>,
+    rejected: Option<bool>,
+}
+
+impl<'a> CompatSessionAnomalyFilter<'a> {
+    /// Create a new [`CompatSessionAnomalyFilter`] with default values
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Filter for anomalies of a specific user
+    #[must_use]
+    pub fn for_user(mut self, user: &'a User) -> Self {
+        self.user = Some(user);
+        self
+    }
+
+    /// Get the user filter
+    ///
+    /// Returns [`None`] if no user filter was set
+    #[must_use]
+    pub fn user(&self) -> Option<&'a User> {
+        self.user
+    }
+
+    /// Filter for anomalies of a specific compat session
+    #[must_use]
+    pub fn for_compat_session(mut self, compat_session: &'a CompatSession) -> Self {
+        self.compat_session = Some(compat_session);
+        self
+    }
+
+    /// Get the compat session filter
+    ///
+    /// Returns [`None`] if no compat session filter was set
+    #[must_use]
+    pub fn compat_session(&self) -> Option<&'a CompatSession> {
+        self.compat_session
+    }
+
+    /// Only return anomalies for which the use of the token was rejected
+    #[must_use]
+    pub fn rejected_only(mut self) -> Self {
+        self.rejected = Some(true);
+        self
+    }
+
+    /// Only return anomalies for which the use of the token was only flagged
+    #[must_use]
+    pub fn flagged_only(mut self) -> Self {
+        self.rejected = Some(false);
+        self
+    }
+
+    /// Get the rejected filter
+    ///
+    /// Returns [`None`] if no rejected filter was set
+    #[must_use]
+    pub fn rejected(&self) -> Option<bool> {
+        self.rejected
+    }
+}
+
+/// A [`CompatSessionAnomalyRepository`] helps interacting with
+/// [`CompatSessionAnomaly`] saved in the storage backend
+#[async_trait]
+pub trait CompatSessionAnomalyRepository: Send + Sync {
+    /// The error type returned by the repository
+    type Error;
+
+    /// Record an anomaly detected when using a token of a [`CompatSession`]
+    ///
+    /// Returns the newly created [`CompatSessionAnomaly`]
+    ///
+    /// # Parameters
+    ///
+    /// * `rng`: The random number generator to use
+    /// * `clock`: The clock used to generate timestamps
+    /// * `compat_session`: The [`CompatSession`] the token belongs to
+    /// * `kinds`: What was unusual about this use of the token
+    /// * `ip_address`: The IP address the token was used from
+    /// * `user_agent`: The user agent the token was used with
+    /// * `rejected`: Whether the use of the token was rejected
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Self::Error`] if the underlying repository fails
+    #[expect(clippy::too_many_arguments)]
+    async fn add(
+        &mut self,
+        rng: &mut (dyn RngCore + Send),
+        clock: &dyn Clock,
+        compat_session: &CompatSession,
+        kinds: Vec<CompatSessionAnomalyKind>,
+        ip_address: Option<IpAddr>,
+        user_agent: Option<String>,
+        rejected: bool,
+    ) -> Result<CompatSessionAnomaly, Self::Error>;
+
+    /// List [`CompatSessionAnomaly`] with the given filter and pagination
+    ///
+    /// # Parameters
+    ///
+    /// * `filter`: The filter parameters
+    /// * `pagination`: The pagination parameters
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Self::Error`] if the underlying repository fails
+    async fn list(
+        &mut self,
+        filter: CompatSessionAnomalyFilter<'_>,
+        pagination: Pagination,
+    ) -> Result<Page<CompatSessionAnomaly>, Self::Error>;
+
+    /// Count the [`CompatSessionAnomaly`] with the given filter
+    ///
+    /// # Parameters
+    ///
+    /// * `filter`: The filter parameters
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Self::Error`] if the underlying repository fails
+    async fn count(&mut self, filter: CompatSessionAnomalyFilter<'_>)
+    -> Result<usize, Self::Error>;
+}
+
+repository_impl!(CompatSessionAnomalyRepository:
+    async fn add(
+        &mut self,
+        rng: &mut (dyn RngCore + Send),
+        clock: &dyn Clock,
+        compat_session: &CompatSession,
+        kinds: Vec<CompatSessionAnomalyKind>,
+        ip_address: Option<IpAddr>,
+        user_agent: Option<String>,
+        rejected: bool,
+    ) -> Result<CompatSessionAnomaly, Self::Error>;
+
+    async fn list(
+        &mut self,
+        filter: CompatSessionAnomalyFilter<'_>,
+        pagination: Pagination,
+    ) -> Result<Page<CompatSessionAnomaly>, Self::Error>;
+
+    async fn count(&mut self, filter: CompatSessionAnomalyFilter<'_>) -> Result<usize, Self::Error>;
+);
@@ -7,12 +7,14 @@
 //! Repositories to interact with entities of the compatibility layer
 
 mod access_token;
+mod anomaly;
 mod refresh_token;
 mod session;
 mod sso_login;
 
 pub use self::{
     access_token::CompatAccessTokenRepository,
+    anomaly::{CompatSessionAnomalyFilter, CompatSessionAnomalyRepository},
     refresh_token::CompatRefreshTokenRepository,
     session::{CompatSessionFilter, CompatSessionRepository},
     sso_login::{CompatSsoLoginFilter, CompatSsoLoginRepository},
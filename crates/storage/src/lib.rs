@@ -114,6 +114,8 @@ pub mod oauth2;
 pub mod personal;
 pub mod policy_data;
 pub mod queue;
+pub mod signing_key;
+pub mod stats;
 pub mod upstream_oauth2;
 pub mod user;
 
@@ -74,6 +74,34 @@ pub trait OAuth2AccessTokenRepository: Send + Sync {
         expires_after: Option<Duration>,
     ) -> Result<AccessToken, Self::Error>;
 
+    /// Add a new access token to the database, with an ID chosen by the caller
+    ///
+    /// This is used for JWT access tokens, which embed the ID of the access
+    /// token as their `jti` claim, and therefore need it before being stored.
+    ///
+    /// Returns the newly created access token
+    ///
+    /// # Parameters
+    ///
+    /// * `id`: The ID of the access token
+    /// * `clock`: The clock used to generate timestamps
+    /// * `session`: The session the access token is associated with
+    /// * `access_token`: The access token to add
+    /// * `expires_after`: The duration after which the access token expires. If
+    ///   [`None`] the access token never expires
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Self::Error`] if the underlying repository fails
+    async fn add_with_id(
+        &mut self,
+        id: Ulid,
+        clock: &dyn Clock,
+        session: &Session,
+        access_token: String,
+        expires_after: Option<Duration>,
+    ) -> Result<AccessToken, Self::Error>;
+
     /// Revoke an access token
     ///
     /// Returns the revoked access token
@@ -92,6 +120,24 @@ pub trait OAuth2AccessTokenRepository: Send + Sync {
         access_token: AccessToken,
     ) -> Result<AccessToken, Self::Error>;
 
+    /// Revoke all the valid access tokens of a session
+    ///
+    /// Returns the number of revoked tokens
+    ///
+    /// # Parameters
+    ///
+    /// * `clock`: The clock used to generate timestamps
+    /// * `session`: The session whose access tokens should be revoked
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Self::Error`] if the underlying repository fails
+    async fn revoke_all_for_session(
+        &mut self,
+        clock: &dyn Clock,
+        session: &Session,
+    ) -> Result<usize, Self::Error>;
+
     /// Mark the access token as used, to track when it was first used
     ///
     /// # Parameters
@@ -170,12 +216,27 @@ repository_impl!(OAuth2AccessTokenRepository:
         expires_after: Option<Duration>,
     ) -> Result<AccessToken, Self::Error>;
 
+    async fn add_with_id(
+        &mut self,
+        id: Ulid,
+        clock: &dyn Clock,
+        session: &Session,
+        access_token: String,
+        expires_after: Option<Duration>,
+    ) -> Result<AccessToken, Self::Error>;
+
     async fn revoke(
         &mut self,
         clock: &dyn Clock,
         access_token: AccessToken,
     ) -> Result<AccessToken, Self::Error>;
 
+    async fn revoke_all_for_session(
+        &mut self,
+        clock: &dyn Clock,
+        session: &Session,
+    ) -> Result<usize, Self::Error>;
+
     async fn mark_used(
         &mut self,
         clock: &dyn Clock,
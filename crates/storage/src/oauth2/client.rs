@@ -7,8 +7,11 @@
 use std::collections::{BTreeMap, BTreeSet};
 
 use async_trait::async_trait;
-use mas_data_model::{Client, Clock};
-use mas_iana::{jose::JsonWebSignatureAlg, oauth::OAuthClientAuthenticationMethod};
+use mas_data_model::{Client, ClientSecret, Clock};
+use mas_iana::{
+    jose::{JsonWebEncryptionAlg, JsonWebEncryptionEnc, JsonWebSignatureAlg},
+    oauth::OAuthClientAuthenticationMethod,
+};
 use mas_jose::jwk::PublicJsonWebKeySet;
 use oauth2_types::{oidc::ApplicationType, requests::GrantType};
 use rand_core::RngCore;
@@ -89,7 +92,7 @@ pub trait OAuth2ClientRepository: Send + Sync {
     /// * `clock`: The clock used to generate timestamps
     /// * `redirect_uris`: The list of redirect URIs used by this client
     /// * `metadata_digest`: The hash of the client metadata, if computed
-    /// * `encrypted_client_secret`: The encrypted client secret, if any
+    /// * `client_secret`: The client secret, if any
     /// * `application_type`: The application type of this client
     /// * `grant_types`: The list of grant types this client can use
     /// * `client_name`: The human-readable name of this client, if given
@@ -120,7 +123,7 @@ pub trait OAuth2ClientRepository: Send + Sync {
         clock: &dyn Clock,
         redirect_uris: Vec<Url>,
         metadata_digest: Option<String>,
-        encrypted_client_secret: Option<String>,
+        client_secret: Option<ClientSecret>,
         application_type: Option<ApplicationType>,
         grant_types: Vec<GrantType>,
         client_name: Option<String>,
@@ -137,8 +140,54 @@ pub trait OAuth2ClientRepository: Send + Sync {
         initiate_login_uri: Option<Url>,
     ) -> Result<Client, Self::Error>;
 
+    /// Set the JWE algorithms used to encrypt the ID tokens and the user info
+    /// responses issued to a client
+    ///
+    /// Returns the updated client
+    ///
+    /// # Parameters
+    ///
+    /// * `client`: The client to update
+    /// * `id_token_encrypted_response`: The `alg` and `enc` algorithms used to
+    ///   encrypt ID tokens, or `None` to not encrypt them
+    /// * `userinfo_encrypted_response`: The `alg` and `enc` algorithms used to
+    ///   encrypt user info responses, or `None` to not encrypt them
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Self::Error`] if the underlying repository fails
+    async fn set_encryption_preferences(
+        &mut self,
+        client: Client,
+        id_token_encrypted_response: Option<(JsonWebEncryptionAlg, JsonWebEncryptionEnc)>,
+        userinfo_encrypted_response: Option<(JsonWebEncryptionAlg, JsonWebEncryptionEnc)>,
+    ) -> Result<Client, Self::Error>;
+
+    /// Replace the secrets of a client
+    ///
+    /// Returns the updated client
+    ///
+    /// # Parameters
+    ///
+    /// * `client`: The client to update
+    /// * `client_secret`: The new current secret, if any
+    /// * `previous_client_secret`: The secret which is still accepted while
+    ///   deployed instances of the client are updated, if any
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Self::Error`] if the underlying repository fails
+    async fn set_secrets(
+        &mut self,
+        client: Client,
+        client_secret: Option<ClientSecret>,
+        previous_client_secret: Option<ClientSecret>,
+    ) -> Result<Client, Self::Error>;
+
     /// Add or replace a static client
     ///
+    /// Any hashed or previous secret of the client is removed.
+    ///
     /// Returns the client that was added or replaced
     ///
     /// # Parameters
@@ -218,7 +267,7 @@ repository_impl!(OAuth2ClientRepository:
         clock: &dyn Clock,
         redirect_uris: Vec<Url>,
         metadata_digest: Option<String>,
-        encrypted_client_secret: Option<String>,
+        client_secret: Option<ClientSecret>,
         application_type: Option<ApplicationType>,
         grant_types: Vec<GrantType>,
         client_name: Option<String>,
@@ -235,6 +284,20 @@ repository_impl!(OAuth2ClientRepository:
         initiate_login_uri: Option<Url>,
     ) -> Result<Client, Self::Error>;
 
+    async fn set_encryption_preferences(
+        &mut self,
+        client: Client,
+        id_token_encrypted_response: Option<(JsonWebEncryptionAlg, JsonWebEncryptionEnc)>,
+        userinfo_encrypted_response: Option<(JsonWebEncryptionAlg, JsonWebEncryptionEnc)>,
+    ) -> Result<Client, Self::Error>;
+
+    async fn set_secrets(
+        &mut self,
+        client: Client,
+        client_secret: Option<ClientSecret>,
+        previous_client_secret: Option<ClientSecret>,
+    ) -> Result<Client, Self::Error>;
+
     async fn upsert_static(
         &mut self,
         client_id: Ulid,
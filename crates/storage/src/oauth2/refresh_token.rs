@@ -113,6 +113,24 @@ pub trait OAuth2RefreshTokenRepository: Send + Sync {
         refresh_token: RefreshToken,
     ) -> Result<RefreshToken, Self::Error>;
 
+    /// Revoke all the valid refresh tokens of a session
+    ///
+    /// Returns the number of revoked tokens
+    ///
+    /// # Parameters
+    ///
+    /// * `clock`: The clock used to generate timestamps
+    /// * `session`: The session whose refresh tokens should be revoked
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Self::Error`] if the underlying repository fails
+    async fn revoke_all_for_session(
+        &mut self,
+        clock: &dyn Clock,
+        session: &Session,
+    ) -> Result<usize, Self::Error>;
+
     /// Cleanup revoked refresh tokens that were revoked before a certain time
     ///
     /// Returns the number of deleted tokens and the last `revoked_at` timestamp
@@ -190,6 +208,12 @@ repository_impl!(OAuth2RefreshTokenRepository:
         refresh_token: RefreshToken,
     ) -> Result<RefreshToken, Self::Error>;
 
+    async fn revoke_all_for_session(
+        &mut self,
+        clock: &dyn Clock,
+        session: &Session,
+    ) -> Result<usize, Self::Error>;
+
     async fn cleanup_revoked(
         &mut self,
         since: Option<chrono::DateTime<chrono::Utc>>,
@@ -16,7 +16,7 @@ use tracing_opentelemetry::OpenTelemetrySpanExt;
 use ulid::Ulid;
 
 use super::Worker;
-use crate::repository_impl;
+use crate::{Page, Pagination, pagination::Node, repository_impl};
 
 /// Represents a job in the job queue
 pub struct Job {
@@ -36,6 +36,182 @@ pub struct Job {
     pub attempt: usize,
 }
 
+/// The status of a job in the job queue
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum JobStatus {
+    /// The job is available to be picked up by a worker
+    Available,
+
+    /// The job is currently being processed by a worker
+    Running,
+
+    /// The job has been completed
+    Completed,
+
+    /// The job failed, and may have been retried as a new job
+    Failed,
+
+    /// The job is scheduled to run at a later date
+    Scheduled,
+
+    /// The worker running the job was lost
+    Lost,
+
+    /// The job was cancelled before it ran
+    Cancelled,
+}
+
+impl JobStatus {
+    /// Get the string representation of the status, as stored in the database
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Available => "available",
+            Self::Running => "running",
+            Self::Completed => "completed",
+            Self::Failed => "failed",
+            Self::Scheduled => "scheduled",
+            Self::Lost => "lost",
+            Self::Cancelled => "cancelled",
+        }
+    }
+
+    /// Whether a job with this status can be cancelled, meaning it was not
+    /// picked up by a worker yet
+    #[must_use]
+    pub const fn is_cancellable(self) -> bool {
+        matches!(self, Self::Available | Self::Scheduled)
+    }
+}
+
+impl std::fmt::Display for JobStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// An error which can happen when parsing a [`JobStatus`]
+#[derive(Debug, thiserror::Error)]
+#[error("Invalid job status {0:?}")]
+pub struct InvalidJobStatusError(String);
+
+impl std::str::FromStr for JobStatus {
+    type Err = InvalidJobStatusError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "available" => Ok(Self::Available),
+            "running" => Ok(Self::Running),
+            "completed" => Ok(Self::Completed),
+            "failed" => Ok(Self::Failed),
+            "scheduled" => Ok(Self::Scheduled),
+            "lost" => Ok(Self::Lost),
+            "cancelled" => Ok(Self::Cancelled),
+            _ => Err(InvalidJobStatusError(s.to_owned())),
+        }
+    }
+}
+
+/// The full state of a job in the job queue, as shown to administrators
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JobDetails {
+    /// The ID of the job
+    pub id: Ulid,
+
+    /// The queue on which the job was placed
+    pub queue_name: String,
+
+    /// The current status of the job
+    pub status: JobStatus,
+
+    /// The payload of the job
+    pub payload: serde_json::Value,
+
+    /// Which attempt it is, starting from 0
+    pub attempt: usize,
+
+    /// When the job was created
+    pub created_at: DateTime<Utc>,
+
+    /// When the job is scheduled to run, if it was scheduled for later
+    pub scheduled_at: Option<DateTime<Utc>>,
+
+    /// When the job was picked up by a worker
+    pub started_at: Option<DateTime<Utc>>,
+
+    /// The worker which picked up the job
+    pub started_by: Option<Ulid>,
+
+    /// When the job was completed
+    pub completed_at: Option<DateTime<Utc>>,
+
+    /// When the job failed
+    pub failed_at: Option<DateTime<Utc>>,
+
+    /// Why the job failed
+    pub failed_reason: Option<String>,
+
+    /// When the job was cancelled
+    pub cancelled_at: Option<DateTime<Utc>>,
+
+    /// The ID of the job which retried this one, if any
+    pub next_attempt_id: Option<Ulid>,
+
+    /// The name of the recurring schedule which scheduled this job, if any
+    pub schedule_name: Option<String>,
+}
+
+impl Node for JobDetails {
+    fn cursor(&self) -> Ulid {
+        self.id
+    }
+}
+
+/// Filter parameters for listing jobs in the job queue
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct JobFilter<'a> {
+    queue_name: Option<&'a str>,
+    status: Option<JobStatus>,
+}
+
+impl<'a> JobFilter<'a> {
+    /// Create a new [`JobFilter`] with default values
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Filter for jobs on a specific queue
+    #[must_use]
+    pub fn for_queue(mut self, queue_name: &'a str) -> Self {
+        self.queue_name = Some(queue_name);
+        self
+    }
+
+    /// Get the queue filter
+    ///
+    /// Returns [`None`] if no queue filter was set
+    #[must_use]
+    pub fn queue_name(&self) -> Option<&'a str> {
+        self.queue_name
+    }
+
+    /// Filter for jobs with a specific status
+    #[must_use]
+    pub fn with_status(mut self, status: JobStatus) -> Self {
+        self.status = Some(status);
+        self
+    }
+
+    /// Get the status filter
+    ///
+    /// Returns [`None`] if no status filter was set
+    #[must_use]
+    pub fn status(&self) -> Option<JobStatus> {
+        self.status
+    }
+}
+
 /// Metadata stored alongside the job
 #[derive(Serialize, Deserialize, Default, Clone, Debug)]
 pub struct JobMetadata {
@@ -215,9 +391,63 @@ pub trait QueueJobRepository: Send + Sync {
     /// Returns an error if the underlying repository fails.
     async fn schedule_available_jobs(&mut self, clock: &dyn Clock) -> Result<usize, Self::Error>;
 
-    /// Cleanup old completed and failed jobs
+    /// Cancel a job which was not picked up by a worker yet
+    ///
+    /// # Parameters
+    ///
+    /// * `clock` - The clock used to generate timestamps
+    /// * `id` - The ID of the job to cancel
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the underlying repository fails, or if the job is
+    /// not in a cancellable state.
+    async fn cancel(&mut self, clock: &dyn Clock, id: Ulid) -> Result<(), Self::Error>;
+
+    /// Lookup a job by its ID
+    ///
+    /// Returns `None` if no job was found
+    ///
+    /// # Parameters
+    ///
+    /// * `id` - The ID of the job to lookup
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the underlying repository fails.
+    async fn lookup(&mut self, id: Ulid) -> Result<Option<JobDetails>, Self::Error>;
+
+    /// List jobs matching the given filter, with the given pagination
+    ///
+    /// # Parameters
+    ///
+    /// * `filter` - The filter to apply
+    /// * `pagination` - The pagination parameters
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the underlying repository fails.
+    async fn list(
+        &mut self,
+        filter: JobFilter<'_>,
+        pagination: Pagination,
+    ) -> Result<Page<JobDetails>, Self::Error>;
+
+    /// Count the jobs matching the given filter
     ///
-    /// This will delete jobs with status 'completed' or 'failed' and IDs up to
+    /// # Parameters
+    ///
+    /// * `filter` - The filter to apply
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the underlying repository fails.
+    async fn count(&mut self, filter: JobFilter<'_>) -> Result<usize, Self::Error>;
+
+    /// Cleanup old completed, failed and cancelled jobs
+    ///
+    /// This will delete jobs with status 'completed', 'failed' or 'cancelled'
+    /// and IDs up to
     /// and including `until`. Uses ULID cursor-based pagination for efficiency.
     ///
     /// Returns the number of jobs deleted and the cursor for the next batch
@@ -287,6 +517,18 @@ repository_impl!(QueueJobRepository:
 
     async fn schedule_available_jobs(&mut self, clock: &dyn Clock) -> Result<usize, Self::Error>;
 
+    async fn cancel(&mut self, clock: &dyn Clock, id: Ulid) -> Result<(), Self::Error>;
+
+    async fn lookup(&mut self, id: Ulid) -> Result<Option<JobDetails>, Self::Error>;
+
+    async fn list(
+        &mut self,
+        filter: JobFilter<'_>,
+        pagination: Pagination,
+    ) -> Result<Page<JobDetails>, Self::Error>;
+
+    async fn count(&mut self, filter: JobFilter<'_>) -> Result<usize, Self::Error>;
+
     async fn cleanup(
         &mut self,
         since: Option<Ulid>,
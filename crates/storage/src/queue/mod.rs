@@ -11,8 +11,11 @@ mod tasks;
 mod worker;
 
 pub use self::{
-    job::{InsertableJob, Job, JobMetadata, QueueJobRepository, QueueJobRepositoryExt},
+    job::{
+        InsertableJob, InvalidJobStatusError, Job, JobDetails, JobFilter, JobMetadata, JobStatus,
+        QueueJobRepository, QueueJobRepositoryExt,
+    },
     schedule::{QueueScheduleRepository, ScheduleStatus},
     tasks::*,
-    worker::{QueueWorkerRepository, Worker},
+    worker::{PausedQueue, QueueWorkerRepository, Worker},
 };
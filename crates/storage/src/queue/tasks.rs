@@ -286,6 +286,39 @@ impl InsertableJob for ReactivateUserJob {
     const QUEUE_NAME: &'static str = "reactivate-user";
 }
 
+/// A job to import users in bulk
+///
+/// The users themselves are stored through the [`UserImportRepository`], so
+/// that password hashes never end up in the job payload
+///
+/// [`UserImportRepository`]: crate::user::UserImportRepository
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ImportUsersJob {
+    user_import_id: Ulid,
+}
+
+impl ImportUsersJob {
+    /// Create a new job to import a batch of users
+    ///
+    /// # Parameters
+    ///
+    /// * `user_import_id` - The ID of the batch of users to import
+    #[must_use]
+    pub fn new(user_import_id: Ulid) -> Self {
+        Self { user_import_id }
+    }
+
+    /// The ID of the batch of users to import
+    #[must_use]
+    pub fn user_import_id(&self) -> Ulid {
+        self.user_import_id
+    }
+}
+
+impl InsertableJob for ImportUsersJob {
+    const QUEUE_NAME: &'static str = "import-users";
+}
+
 /// Send account recovery emails
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct SendAccountRecoveryEmailsJob {
@@ -651,3 +684,67 @@ pub struct CleanupInactiveUserSessionIpsJob;
 impl InsertableJob for CleanupInactiveUserSessionIpsJob {
     const QUEUE_NAME: &'static str = "cleanup-inactive-user-session-ips";
 }
+
+/// Compute the usage statistics of the previous day
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ComputeDailyStatsJob;
+
+impl InsertableJob for ComputeDailyStatsJob {
+    const QUEUE_NAME: &'static str = "compute-daily-stats";
+}
+
+/// Refresh the claims of users linked to upstream OAuth providers which have
+/// background re-synchronisation enabled, and re-apply the claims imports
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct SyncUpstreamOAuthClaimsJob;
+
+impl InsertableJob for SyncUpstreamOAuthClaimsJob {
+    const QUEUE_NAME: &'static str = "sync-upstream-oauth-claims";
+}
+
+/// Refresh the upstream access tokens which are about to expire, for the
+/// upstream OAuth providers which have token storage enabled
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct RefreshUpstreamOAuthTokensJob;
+
+impl InsertableJob for RefreshUpstreamOAuthTokensJob {
+    const QUEUE_NAME: &'static str = "refresh-upstream-oauth-tokens";
+}
+
+/// Check that the enabled upstream OAuth providers are reachable and correctly
+/// configured, and record the outcome
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct CheckUpstreamOAuthProvidersHealthJob;
+
+impl InsertableJob for CheckUpstreamOAuthProvidersHealthJob {
+    const QUEUE_NAME: &'static str = "check-upstream-oauth-providers-health";
+}
+
+/// Rotate the signing keys managed by the service: publish new keys ahead of
+/// time, start using them once they have been published long enough, and
+/// remove the retired keys
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct RotateSigningKeysJob {
+    #[serde(default)]
+    force: bool,
+}
+
+impl RotateSigningKeysJob {
+    /// Create a job which rotates the keys even if the current ones are not
+    /// due for rotation yet
+    #[must_use]
+    pub fn forced() -> Self {
+        Self { force: true }
+    }
+
+    /// Whether the keys should be rotated even if they are not due for
+    /// rotation yet
+    #[must_use]
+    pub fn force(&self) -> bool {
+        self.force
+    }
+}
+
+impl InsertableJob for RotateSigningKeysJob {
+    const QUEUE_NAME: &'static str = "rotate-signing-keys";
+}
@@ -6,7 +6,7 @@
 //! Repository to interact with workers in the job queue
 
 use async_trait::async_trait;
-use chrono::Duration;
+use chrono::{DateTime, Duration, Utc};
 use mas_data_model::Clock;
 use rand_core::RngCore;
 use ulid::Ulid;
@@ -19,6 +19,16 @@ pub struct Worker {
     pub id: Ulid,
 }
 
+/// A queue which was paused by an administrator
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PausedQueue {
+    /// The name of the queue
+    pub queue_name: String,
+
+    /// When the queue was paused
+    pub paused_at: DateTime<Utc>,
+}
+
 /// A [`QueueWorkerRepository`] is used to schedule jobs to be executed by a
 /// worker.
 #[async_trait]
@@ -88,6 +98,45 @@ pub trait QueueWorkerRepository: Send + Sync {
         clock: &dyn Clock,
         worker: &Worker,
     ) -> Result<bool, Self::Error>;
+
+    /// Pause a queue, so that workers stop picking up jobs from it
+    ///
+    /// Returns `true` if the queue was paused, `false` if it was already
+    /// paused
+    ///
+    /// # Parameters
+    ///
+    /// * `clock` - The clock used to generate timestamps
+    /// * `queue_name` - The name of the queue to pause
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the underlying repository fails.
+    async fn pause_queue(
+        &mut self,
+        clock: &dyn Clock,
+        queue_name: &str,
+    ) -> Result<bool, Self::Error>;
+
+    /// Resume a paused queue, waking up the workers
+    ///
+    /// Returns `true` if the queue was resumed, `false` if it was not paused
+    ///
+    /// # Parameters
+    ///
+    /// * `queue_name` - The name of the queue to resume
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the underlying repository fails.
+    async fn resume_queue(&mut self, queue_name: &str) -> Result<bool, Self::Error>;
+
+    /// List the paused queues
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the underlying repository fails.
+    async fn list_paused_queues(&mut self) -> Result<Vec<PausedQueue>, Self::Error>;
 }
 
 repository_impl!(QueueWorkerRepository:
@@ -125,4 +174,14 @@ repository_impl!(QueueWorkerRepository:
         clock: &dyn Clock,
         worker: &Worker,
     ) -> Result<bool, Self::Error>;
+
+    async fn pause_queue(
+        &mut self,
+        clock: &dyn Clock,
+        queue_name: &str,
+    ) -> Result<bool, Self::Error>;
+
+    async fn resume_queue(&mut self, queue_name: &str) -> Result<bool, Self::Error>;
+
+    async fn list_paused_queues(&mut self) -> Result<Vec<PausedQueue>, Self::Error>;
 );
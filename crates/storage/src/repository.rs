@@ -11,8 +11,8 @@ use thiserror::Error;
 use crate::{
     app_session::AppSessionRepository,
     compat::{
-        CompatAccessTokenRepository, CompatRefreshTokenRepository, CompatSessionRepository,
-        CompatSsoLoginRepository,
+        CompatAccessTokenRepository, CompatRefreshTokenRepository, CompatSessionAnomalyRepository,
+        CompatSessionRepository, CompatSsoLoginRepository,
     },
     oauth2::{
         OAuth2AccessTokenRepository, OAuth2AuthorizationGrantRepository, OAuth2ClientRepository,
@@ -21,14 +21,16 @@ use crate::{
     personal::{PersonalAccessTokenRepository, PersonalSessionRepository},
     policy_data::PolicyDataRepository,
     queue::{QueueJobRepository, QueueScheduleRepository, QueueWorkerRepository},
+    signing_key::SigningKeyRepository,
+    stats::StatsRepository,
     upstream_oauth2::{
         UpstreamOAuthLinkRepository, UpstreamOAuthProviderRepository,
         UpstreamOAuthSessionRepository,
     },
     user::{
-        BrowserSessionRepository, UserEmailRepository, UserPasswordRepository,
-        UserRecoveryRepository, UserRegistrationRepository, UserRegistrationTokenRepository,
-        UserRepository, UserTermsRepository,
+        BrowserSessionRepository, UserEmailRepository, UserImportRepository,
+        UserPasswordRepository, UserRecoveryRepository, UserRegistrationRepository,
+        UserRegistrationTokenRepository, UserRepository, UserTermsRepository,
     },
 };
 
@@ -145,6 +147,9 @@ pub trait RepositoryAccess: Send {
     fn user_recovery<'c>(&'c mut self)
     -> Box<dyn UserRecoveryRepository<Error = Self::Error> + 'c>;
 
+    /// Get an [`UserImportRepository`]
+    fn user_import<'c>(&'c mut self) -> Box<dyn UserImportRepository<Error = Self::Error> + 'c>;
+
     /// Get an [`UserRegistrationRepository`]
     fn user_registration<'c>(
         &'c mut self,
@@ -200,6 +205,11 @@ pub trait RepositoryAccess: Send {
         &'c mut self,
     ) -> Box<dyn CompatSessionRepository<Error = Self::Error> + 'c>;
 
+    /// Get a [`CompatSessionAnomalyRepository`]
+    fn compat_session_anomaly<'c>(
+        &'c mut self,
+    ) -> Box<dyn CompatSessionAnomalyRepository<Error = Self::Error> + 'c>;
+
     /// Get a [`CompatSsoLoginRepository`]
     fn compat_sso_login<'c>(
         &'c mut self,
@@ -238,6 +248,12 @@ pub trait RepositoryAccess: Send {
 
     /// Get a [`PolicyDataRepository`]
     fn policy_data<'c>(&'c mut self) -> Box<dyn PolicyDataRepository<Error = Self::Error> + 'c>;
+
+    /// Get a [`StatsRepository`]
+    fn stats<'c>(&'c mut self) -> Box<dyn StatsRepository<Error = Self::Error> + 'c>;
+
+    /// Get a [`SigningKeyRepository`]
+    fn signing_key<'c>(&'c mut self) -> Box<dyn SigningKeyRepository<Error = Self::Error> + 'c>;
 }
 
 /// Implementations of the [`RepositoryAccess`], [`RepositoryTransaction`] and
@@ -250,8 +266,8 @@ mod impls {
         MapErr, Repository, RepositoryTransaction,
         app_session::AppSessionRepository,
         compat::{
-            CompatAccessTokenRepository, CompatRefreshTokenRepository, CompatSessionRepository,
-            CompatSsoLoginRepository,
+            CompatAccessTokenRepository, CompatRefreshTokenRepository,
+            CompatSessionAnomalyRepository, CompatSessionRepository, CompatSsoLoginRepository,
         },
         oauth2::{
             OAuth2AccessTokenRepository, OAuth2AuthorizationGrantRepository,
@@ -261,6 +277,8 @@ mod impls {
         personal::{PersonalAccessTokenRepository, PersonalSessionRepository},
         policy_data::PolicyDataRepository,
         queue::{QueueJobRepository, QueueScheduleRepository, QueueWorkerRepository},
+        signing_key::SigningKeyRepository,
+        stats::StatsRepository,
         upstream_oauth2::{
             UpstreamOAuthLinkRepository, UpstreamOAuthProviderRepository,
             UpstreamOAuthSessionRepository,
@@ -358,6 +376,12 @@ mod impls {
             Box::new(MapErr::new(self.inner.user_recovery(), &mut self.mapper))
         }
 
+        fn user_import<'c>(
+            &'c mut self,
+        ) -> Box<dyn crate::user::UserImportRepository<Error = Self::Error> + 'c> {
+            Box::new(MapErr::new(self.inner.user_import(), &mut self.mapper))
+        }
+
         fn user_registration<'c>(
             &'c mut self,
         ) -> Box<dyn UserRegistrationRepository<Error = Self::Error> + 'c> {
@@ -446,6 +470,15 @@ mod impls {
             Box::new(MapErr::new(self.inner.compat_session(), &mut self.mapper))
         }
 
+        fn compat_session_anomaly<'c>(
+            &'c mut self,
+        ) -> Box<dyn CompatSessionAnomalyRepository<Error = Self::Error> + 'c> {
+            Box::new(MapErr::new(
+                self.inner.compat_session_anomaly(),
+                &mut self.mapper,
+            ))
+        }
+
         fn compat_sso_login<'c>(
             &'c mut self,
         ) -> Box<dyn CompatSsoLoginRepository<Error = Self::Error> + 'c> {
@@ -506,6 +539,16 @@ mod impls {
         ) -> Box<dyn PolicyDataRepository<Error = Self::Error> + 'c> {
             Box::new(MapErr::new(self.inner.policy_data(), &mut self.mapper))
         }
+
+        fn stats<'c>(&'c mut self) -> Box<dyn StatsRepository<Error = Self::Error> + 'c> {
+            Box::new(MapErr::new(self.inner.stats(), &mut self.mapper))
+        }
+
+        fn signing_key<'c>(
+            &'c mut self,
+        ) -> Box<dyn SigningKeyRepository<Error = Self::Error> + 'c> {
+            Box::new(MapErr::new(self.inner.signing_key(), &mut self.mapper))
+        }
     }
 
     impl<R: RepositoryAccess + ?Sized> RepositoryAccess for Box<R> {
@@ -549,6 +592,12 @@ mod impls {
             (**self).user_recovery()
         }
 
+        fn user_import<'c>(
+            &'c mut self,
+        ) -> Box<dyn crate::user::UserImportRepository<Error = Self::Error> + 'c> {
+            (**self).user_import()
+        }
+
         fn user_registration<'c>(
             &'c mut self,
         ) -> Box<dyn UserRegistrationRepository<Error = Self::Error> + 'c> {
@@ -619,6 +668,12 @@ mod impls {
             (**self).compat_session()
         }
 
+        fn compat_session_anomaly<'c>(
+            &'c mut self,
+        ) -> Box<dyn CompatSessionAnomalyRepository<Error = Self::Error> + 'c> {
+            (**self).compat_session_anomaly()
+        }
+
         fn compat_sso_login<'c>(
             &'c mut self,
         ) -> Box<dyn CompatSsoLoginRepository<Error = Self::Error> + 'c> {
@@ -670,5 +725,15 @@ mod impls {
         ) -> Box<dyn PolicyDataRepository<Error = Self::Error> + 'c> {
             (**self).policy_data()
         }
+
+        fn stats<'c>(&'c mut self) -> Box<dyn StatsRepository<Error = Self::Error> + 'c> {
+            (**self).stats()
+        }
+
+        fn signing_key<'c>(
+            &'c mut self,
+        ) -> Box<dyn SigningKeyRepository<Error = Self::Error> + 'c> {
+            (**self).signing_key()
+        }
     }
 }
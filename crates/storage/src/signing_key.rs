@@ -0,0 +1,147 @@
+// Copyright 2026 Element Creations Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+// Please see LICENSE files in the repository root for full details.
+
+//! Repositories to interact with the signing keys managed by the service.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use mas_data_model::{Clock, SigningKey, SigningKeyType};
+use rand_core::RngCore;
+use ulid::Ulid;
+
+use crate::repository_impl;
+
+/// A [`SigningKeyRepository`] helps interacting with the signing keys which
+/// are generated and rotated by the service, and saved in the storage backend.
+#[async_trait]
+pub trait SigningKeyRepository: Send + Sync {
+    /// The error type returned by the repository
+    type Error;
+
+    /// Lookup a signing key by its ID
+    ///
+    /// Returns `None` if no signing key was found
+    ///
+    /// # Parameters
+    ///
+    /// * `id`: The ID of the signing key to lookup
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Self::Error`] if the underlying repository fails
+    async fn lookup(&mut self, id: Ulid) -> Result<Option<SigningKey>, Self::Error>;
+
+    /// Get all the signing keys, ordered by creation date
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Self::Error`] if the underlying repository fails
+    async fn all(&mut self) -> Result<Vec<SigningKey>, Self::Error>;
+
+    /// Add a new pending signing key
+    ///
+    /// Returns the newly created signing key
+    ///
+    /// # Parameters
+    ///
+    /// * `rng`: The random number generator to use
+    /// * `clock`: The clock used to generate the timestamps
+    /// * `kid`: The JWK key ID of the key
+    /// * `key_type`: The type of the key
+    /// * `encrypted_key`: The encrypted PEM-encoded private key
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Self::Error`] if the underlying repository fails
+    async fn add(
+        &mut self,
+        rng: &mut (dyn RngCore + Send),
+        clock: &dyn Clock,
+        kid: String,
+        key_type: SigningKeyType,
+        encrypted_key: String,
+    ) -> Result<SigningKey, Self::Error>;
+
+    /// Mark a pending signing key as active, so that it is used for signing
+    ///
+    /// Returns the updated signing key
+    ///
+    /// # Parameters
+    ///
+    /// * `clock`: The clock used to generate the timestamps
+    /// * `signing_key`: The signing key to activate
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Self::Error`] if the underlying repository fails
+    async fn activate(
+        &mut self,
+        clock: &dyn Clock,
+        signing_key: SigningKey,
+    ) -> Result<SigningKey, Self::Error>;
+
+    /// Mark a signing key as retired, so that it is not used for signing
+    /// anymore
+    ///
+    /// Returns the updated signing key
+    ///
+    /// # Parameters
+    ///
+    /// * `clock`: The clock used to generate the timestamps
+    /// * `signing_key`: The signing key to retire
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Self::Error`] if the underlying repository fails
+    async fn retire(
+        &mut self,
+        clock: &dyn Clock,
+        signing_key: SigningKey,
+    ) -> Result<SigningKey, Self::Error>;
+
+    /// Remove the signing keys which were retired before the given date
+    ///
+    /// Returns the number of keys removed
+    ///
+    /// # Parameters
+    ///
+    /// * `retired_before`: The date before which the keys were retired
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Self::Error`] if the underlying repository fails
+    async fn remove_retired(&mut self, retired_before: DateTime<Utc>)
+    -> Result<usize, Self::Error>;
+}
+
+repository_impl!(SigningKeyRepository:
+    async fn lookup(&mut self, id: Ulid) -> Result<Option<SigningKey>, Self::Error>;
+
+    async fn all(&mut self) -> Result<Vec<SigningKey>, Self::Error>;
+
+    async fn add(
+        &mut self,
+        rng: &mut (dyn RngCore + Send),
+        clock: &dyn Clock,
+        kid: String,
+        key_type: SigningKeyType,
+        encrypted_key: String,
+    ) -> Result<SigningKey, Self::Error>;
+
+    async fn activate(
+        &mut self,
+        clock: &dyn Clock,
+        signing_key: SigningKey,
+    ) -> Result<SigningKey, Self::Error>;
+
+    async fn retire(
+        &mut self,
+        clock: &dyn Clock,
+        signing_key: SigningKey,
+    ) -> Result<SigningKey, Self::Error>;
+
+    async fn remove_retired(&mut self, retired_before: DateTime<Utc>)
+    -> Result<usize, Self::Error>;
+);
@@ -0,0 +1,224 @@
+// Copyright 2026 Element Creations Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+// Please see LICENSE files in the repository root for full details.
+
+//! Repositories to compute and store usage statistics.
+//!
+//! Statistics are rolled up once a day by a scheduled job, so that reporting
+//! doesn't have to scan the live tables.
+
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDate, Utc};
+use mas_data_model::Clock;
+use thiserror::Error;
+use ulid::Ulid;
+
+use crate::repository_impl;
+
+/// A metric tracked in the daily statistics
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StatsMetric {
+    /// The number of users which were active on that day
+    DailyActiveUsers,
+
+    /// The number of users which were active in the 30 days up to the end of
+    /// that day
+    MonthlyActiveUsers,
+
+    /// The number of users registered on that day
+    NewRegistrations,
+
+    /// The number of active browser sessions at the end of that day
+    ActiveBrowserSessions,
+
+    /// The number of active compatibility sessions at the end of that day
+    ActiveCompatSessions,
+
+    /// The number of active OAuth 2.0 sessions at the end of that day.
+    ///
+    /// The dimension, if set, is the ID of the OAuth 2.0 client
+    ActiveOAuth2Sessions,
+
+    /// The number of logins through an upstream provider on that day.
+    ///
+    /// The dimension is the ID of the upstream OAuth 2.0 provider
+    UpstreamLogins,
+}
+
+impl StatsMetric {
+    /// Get the string representation of the metric, as stored in the database
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::DailyActiveUsers => "daily_active_users",
+            Self::MonthlyActiveUsers => "monthly_active_users",
+            Self::NewRegistrations => "new_registrations",
+            Self::ActiveBrowserSessions => "active_browser_sessions",
+            Self::ActiveCompatSessions => "active_compat_sessions",
+            Self::ActiveOAuth2Sessions => "active_oauth2_sessions",
+            Self::UpstreamLogins => "upstream_logins",
+        }
+    }
+}
+
+impl std::fmt::Display for StatsMetric {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Error returned when parsing an invalid [`StatsMetric`]
+#[derive(Debug, Error)]
+#[error("invalid statistics metric {0:?}")]
+pub struct InvalidStatsMetricError(String);
+
+impl std::str::FromStr for StatsMetric {
+    type Err = InvalidStatsMetricError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "daily_active_users" => Ok(Self::DailyActiveUsers),
+            "monthly_active_users" => Ok(Self::MonthlyActiveUsers),
+            "new_registrations" => Ok(Self::NewRegistrations),
+            "active_browser_sessions" => Ok(Self::ActiveBrowserSessions),
+            "active_compat_sessions" => Ok(Self::ActiveCompatSessions),
+            "active_oauth2_sessions" => Ok(Self::ActiveOAuth2Sessions),
+            "upstream_logins" => Ok(Self::UpstreamLogins),
+            _ => Err(InvalidStatsMetricError(s.to_owned())),
+        }
+    }
+}
+
+/// A single value of the daily statistics
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DailyStat {
+    /// The day this value is for, in UTC
+    pub day: NaiveDate,
+
+    /// The metric this value is for
+    pub metric: StatsMetric,
+
+    /// An optional dimension, which meaning depends on the metric
+    pub dimension: Option<Ulid>,
+
+    /// The value of the metric
+    pub value: usize,
+}
+
+/// A [`StatsRepository`] helps computing and storing usage statistics
+#[async_trait]
+pub trait StatsRepository: Send + Sync {
+    /// The error type returned by the repository
+    type Error;
+
+    /// Count the number of distinct users which had a session active in the
+    /// given time range
+    ///
+    /// This considers the last activity of browser, compatibility and OAuth
+    /// 2.0 sessions, so a user active again after `until` is not counted.
+    ///
+    /// # Parameters
+    ///
+    /// * `since`: The start of the time range, inclusive
+    /// * `until`: The end of the time range, exclusive
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Self::Error`] if the underlying repository fails
+    async fn count_active_users(
+        &mut self,
+        since: DateTime<Utc>,
+        until: DateTime<Utc>,
+    ) -> Result<usize, Self::Error>;
+
+    /// Count the number of active OAuth 2.0 sessions, grouped by client
+    ///
+    /// Returns a list of client IDs and their number of active sessions.
+    /// Clients without active sessions are not returned.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Self::Error`] if the underlying repository fails
+    async fn count_active_oauth2_sessions_by_client(
+        &mut self,
+    ) -> Result<Vec<(Ulid, usize)>, Self::Error>;
+
+    /// Replace the statistics saved for a day
+    ///
+    /// All the values previously saved for that day are removed, and replaced
+    /// by the given values.
+    ///
+    /// # Parameters
+    ///
+    /// * `clock`: The clock used to record when the statistics were computed
+    /// * `day`: The day to replace the statistics for
+    /// * `stats`: The new values, which must all be for that day
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Self::Error`] if the underlying repository fails
+    async fn replace_day(
+        &mut self,
+        clock: &dyn Clock,
+        day: NaiveDate,
+        stats: &[DailyStat],
+    ) -> Result<(), Self::Error>;
+
+    /// Get the last day for which statistics were saved
+    ///
+    /// Returns `None` if no statistics were ever saved.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Self::Error`] if the underlying repository fails
+    async fn last_day(&mut self) -> Result<Option<NaiveDate>, Self::Error>;
+
+    /// List the saved values of a metric over a range of days
+    ///
+    /// Values are ordered by day, then by dimension.
+    ///
+    /// # Parameters
+    ///
+    /// * `metric`: The metric to list the values of
+    /// * `since`: The first day of the range, inclusive
+    /// * `until`: The last day of the range, inclusive
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Self::Error`] if the underlying repository fails
+    async fn list(
+        &mut self,
+        metric: StatsMetric,
+        since: NaiveDate,
+        until: NaiveDate,
+    ) -> Result<Vec<DailyStat>, Self::Error>;
+}
+
+repository_impl!(StatsRepository:
+    async fn count_active_users(
+        &mut self,
+        since: DateTime<Utc>,
+        until: DateTime<Utc>,
+    ) -> Result<usize, Self::Error>;
+
+    async fn count_active_oauth2_sessions_by_client(
+        &mut self,
+    ) -> Result<Vec<(Ulid, usize)>, Self::Error>;
+
+    async fn replace_day(
+        &mut self,
+        clock: &dyn Clock,
+        day: NaiveDate,
+        stats: &[DailyStat],
+    ) -> Result<(), Self::Error>;
+
+    async fn last_day(&mut self) -> Result<Option<NaiveDate>, Self::Error>;
+
+    async fn list(
+        &mut self,
+        metric: StatsMetric,
+        since: NaiveDate,
+        until: NaiveDate,
+    ) -> Result<Vec<DailyStat>, Self::Error>;
+);
@@ -6,6 +6,7 @@
 // Please see LICENSE files in the repository root for full details.
 
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use mas_data_model::{Clock, UpstreamOAuthLink, UpstreamOAuthProvider, User};
 use rand_core::RngCore;
 use ulid::Ulid;
@@ -20,6 +21,8 @@ pub struct UpstreamOAuthLinkFilter<'a> {
     provider: Option<&'a UpstreamOAuthProvider>,
     provider_enabled: Option<bool>,
     subject: Option<&'a str>,
+    has_refresh_token: Option<bool>,
+    access_token_expires_before: Option<DateTime<Utc>>,
 }
 
 impl<'a> UpstreamOAuthLinkFilter<'a> {
@@ -91,6 +94,33 @@ impl<'a> UpstreamOAuthLinkFilter<'a> {
     pub const fn subject(&self) -> Option<&str> {
         self.subject
     }
+
+    /// Only list links which have a refresh token stored
+    #[must_use]
+    pub const fn with_refresh_token_only(mut self) -> Self {
+        self.has_refresh_token = Some(true);
+        self
+    }
+
+    /// Get the refresh token filter
+    #[must_use]
+    pub const fn has_refresh_token(&self) -> Option<bool> {
+        self.has_refresh_token
+    }
+
+    /// Only list links which have an access token stored which expires
+    /// before the given date
+    #[must_use]
+    pub const fn with_access_token_expiring_before(mut self, before: DateTime<Utc>) -> Self {
+        self.access_token_expires_before = Some(before);
+        self
+    }
+
+    /// Get the access token expiration filter
+    #[must_use]
+    pub const fn access_token_expires_before(&self) -> Option<DateTime<Utc>> {
+        self.access_token_expires_before
+    }
 }
 
 /// An [`UpstreamOAuthLinkRepository`] helps interacting with
@@ -175,6 +205,147 @@ pub trait UpstreamOAuthLinkRepository: Send + Sync {
         user: &User,
     ) -> Result<(), Self::Error>;
 
+    /// Store the encrypted refresh token obtained during the last login
+    /// through an upstream OAuth link, or clear it
+    ///
+    /// # Parameters
+    ///
+    /// * `upstream_oauth_link`: The upstream OAuth link to update
+    /// * `encrypted_refresh_token`: The encrypted refresh token, or `None` to
+    ///   clear it
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Self::Error`] if the underlying repository fails
+    async fn set_encrypted_refresh_token(
+        &mut self,
+        upstream_oauth_link: &UpstreamOAuthLink,
+        encrypted_refresh_token: Option<String>,
+    ) -> Result<(), Self::Error>;
+
+    /// Get the encrypted refresh token stored on an upstream OAuth link
+    ///
+    /// Returns `None` if no refresh token is stored
+    ///
+    /// # Parameters
+    ///
+    /// * `upstream_oauth_link`: The upstream OAuth link
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Self::Error`] if the underlying repository fails
+    async fn get_encrypted_refresh_token(
+        &mut self,
+        upstream_oauth_link: &UpstreamOAuthLink,
+    ) -> Result<Option<String>, Self::Error>;
+
+    /// Store the encrypted access token obtained from the upstream provider on
+    /// an upstream OAuth link, or clear it
+    ///
+    /// # Parameters
+    ///
+    /// * `upstream_oauth_link`: The upstream OAuth link to update
+    /// * `encrypted_access_token`: The encrypted access token, or `None` to
+    ///   clear it
+    /// * `expires_at`: When the access token expires, if known
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Self::Error`] if the underlying repository fails
+    async fn set_encrypted_access_token(
+        &mut self,
+        upstream_oauth_link: &UpstreamOAuthLink,
+        encrypted_access_token: Option<String>,
+        expires_at: Option<DateTime<Utc>>,
+    ) -> Result<(), Self::Error>;
+
+    /// Get the encrypted access token stored on an upstream OAuth link, along
+    /// with when it expires
+    ///
+    /// Returns `None` if no access token is stored
+    ///
+    /// # Parameters
+    ///
+    /// * `upstream_oauth_link`: The upstream OAuth link
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Self::Error`] if the underlying repository fails
+    async fn get_encrypted_access_token(
+        &mut self,
+        upstream_oauth_link: &UpstreamOAuthLink,
+    ) -> Result<Option<(String, Option<DateTime<Utc>>)>, Self::Error>;
+
+    /// Record when the account status import of the provider locked the user
+    /// behind an upstream OAuth link, or clear it
+    ///
+    /// # Parameters
+    ///
+    /// * `upstream_oauth_link`: The upstream OAuth link to update
+    /// * `user_locked_at`: The lock timestamp set on the user, or `None` to
+    ///   clear it
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Self::Error`] if the underlying repository fails
+    async fn set_user_locked_at(
+        &mut self,
+        upstream_oauth_link: &UpstreamOAuthLink,
+        user_locked_at: Option<DateTime<Utc>>,
+    ) -> Result<(), Self::Error>;
+
+    /// Get when the account status import of the provider locked the user
+    /// behind an upstream OAuth link
+    ///
+    /// Returns `None` if the provider didn't lock the user
+    ///
+    /// # Parameters
+    ///
+    /// * `upstream_oauth_link`: The upstream OAuth link
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Self::Error`] if the underlying repository fails
+    async fn get_user_locked_at(
+        &mut self,
+        upstream_oauth_link: &UpstreamOAuthLink,
+    ) -> Result<Option<DateTime<Utc>>, Self::Error>;
+
+    /// Store the user attributes imported from the upstream provider on an
+    /// upstream OAuth link, or clear them
+    ///
+    /// # Parameters
+    ///
+    /// * `upstream_oauth_link`: The upstream OAuth link to update
+    /// * `attributes`: The attributes, or `None` to clear them
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Self::Error`] if the underlying repository fails
+    async fn set_attributes(
+        &mut self,
+        upstream_oauth_link: &UpstreamOAuthLink,
+        attributes: Option<serde_json::Map<String, serde_json::Value>>,
+    ) -> Result<(), Self::Error>;
+
+    /// Get the user attributes imported through all the upstream OAuth links
+    /// of a user
+    ///
+    /// If multiple links define the same attribute, the one from the most
+    /// recently created link wins.
+    ///
+    /// # Parameters
+    ///
+    /// * `user`: The user to get the attributes for
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Self::Error`] if the underlying repository fails
+    async fn attributes_for_user(
+        &mut self,
+        user: &User,
+    ) -> Result<serde_json::Map<String, serde_json::Value>, Self::Error>;
+
     /// List [`UpstreamOAuthLink`] with the given filter and pagination
     ///
     /// # Parameters
@@ -267,6 +438,51 @@ repository_impl!(UpstreamOAuthLinkRepository:
         user: &User,
     ) -> Result<(), Self::Error>;
 
+    async fn set_encrypted_refresh_token(
+        &mut self,
+        upstream_oauth_link: &UpstreamOAuthLink,
+        encrypted_refresh_token: Option<String>,
+    ) -> Result<(), Self::Error>;
+
+    async fn get_encrypted_refresh_token(
+        &mut self,
+        upstream_oauth_link: &UpstreamOAuthLink,
+    ) -> Result<Option<String>, Self::Error>;
+
+    async fn set_encrypted_access_token(
+        &mut self,
+        upstream_oauth_link: &UpstreamOAuthLink,
+        encrypted_access_token: Option<String>,
+        expires_at: Option<DateTime<Utc>>,
+    ) -> Result<(), Self::Error>;
+
+    async fn get_encrypted_access_token(
+        &mut self,
+        upstream_oauth_link: &UpstreamOAuthLink,
+    ) -> Result<Option<(String, Option<DateTime<Utc>>)>, Self::Error>;
+
+    async fn set_user_locked_at(
+        &mut self,
+        upstream_oauth_link: &UpstreamOAuthLink,
+        user_locked_at: Option<DateTime<Utc>>,
+    ) -> Result<(), Self::Error>;
+
+    async fn get_user_locked_at(
+        &mut self,
+        upstream_oauth_link: &UpstreamOAuthLink,
+    ) -> Result<Option<DateTime<Utc>>, Self::Error>;
+
+    async fn set_attributes(
+        &mut self,
+        upstream_oauth_link: &UpstreamOAuthLink,
+        attributes: Option<serde_json::Map<String, serde_json::Value>>,
+    ) -> Result<(), Self::Error>;
+
+    async fn attributes_for_user(
+        &mut self,
+        user: &User,
+    ) -> Result<serde_json::Map<String, serde_json::Value>, Self::Error>;
+
     async fn list(
         &mut self,
         filter: UpstreamOAuthLinkFilter<'_>,
@@ -9,9 +9,10 @@ use std::marker::PhantomData;
 use async_trait::async_trait;
 use mas_data_model::{
     Clock, UpstreamOAuthProvider, UpstreamOAuthProviderClaimsImports,
-    UpstreamOAuthProviderDiscoveryMode, UpstreamOAuthProviderOnBackchannelLogout,
+    UpstreamOAuthProviderDiscoveryMode, UpstreamOAuthProviderHealth,
+    UpstreamOAuthProviderHealthChecks, UpstreamOAuthProviderOnBackchannelLogout,
     UpstreamOAuthProviderPkceMode, UpstreamOAuthProviderResponseMode,
-    UpstreamOAuthProviderTokenAuthMethod,
+    UpstreamOAuthProviderSamlConfig, UpstreamOAuthProviderTokenAuthMethod,
 };
 use mas_iana::jose::JsonWebSignatureAlg;
 use oauth2_types::scope::Scope;
@@ -105,6 +106,23 @@ pub struct UpstreamOAuthProviderParams {
 
     /// The behavior when receiving a backchannel logout notification
     pub on_backchannel_logout: UpstreamOAuthProviderOnBackchannelLogout,
+
+    /// The SAML 2.0 settings, if this is a SAML identity provider instead of
+    /// an OAuth 2.0 one
+    pub saml: Option<UpstreamOAuthProviderSamlConfig>,
+
+    /// The email domains handled by this provider, used to route logins to it
+    pub email_domains: Vec<String>,
+
+    /// Whether the tokens obtained from the provider should be stored
+    pub store_tokens: bool,
+
+    /// Whether logins can be initiated by the provider or by a third party
+    pub allow_initiated_login: bool,
+
+    /// The encrypted key material used to authenticate against the provider:
+    /// private keys for `private_key_jwt` and a TLS client certificate
+    pub encrypted_client_keys: Option<String>,
 }
 
 /// Filter parameters for listing upstream OAuth 2.0 providers
@@ -284,6 +302,43 @@ pub trait UpstreamOAuthProviderRepository: Send + Sync {
     ///
     /// Returns [`Self::Error`] if the underlying repository fails
     async fn all_enabled(&mut self) -> Result<Vec<UpstreamOAuthProvider>, Self::Error>;
+
+    /// Record the outcome of a health check of an upstream OAuth provider,
+    /// replacing the previous one
+    ///
+    /// Returns the recorded health
+    ///
+    /// # Parameters
+    ///
+    /// * `clock`: The clock used to generate timestamps
+    /// * `provider`: The provider which was checked
+    /// * `checks`: The outcome of the checks
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Self::Error`] if the underlying repository fails
+    async fn record_health(
+        &mut self,
+        clock: &dyn Clock,
+        provider: &UpstreamOAuthProvider,
+        checks: UpstreamOAuthProviderHealthChecks,
+    ) -> Result<UpstreamOAuthProviderHealth, Self::Error>;
+
+    /// Get the outcome of the last health check of an upstream OAuth provider
+    ///
+    /// Returns `None` if the provider was never checked
+    ///
+    /// # Parameters
+    ///
+    /// * `provider`: The provider to get the health of
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Self::Error`] if the underlying repository fails
+    async fn health(
+        &mut self,
+        provider: &UpstreamOAuthProvider,
+    ) -> Result<Option<UpstreamOAuthProviderHealth>, Self::Error>;
 }
 
 repository_impl!(UpstreamOAuthProviderRepository:
@@ -325,4 +380,16 @@ repository_impl!(UpstreamOAuthProviderRepository:
     ) -> Result<usize, Self::Error>;
 
     async fn all_enabled(&mut self) -> Result<Vec<UpstreamOAuthProvider>, Self::Error>;
+
+    async fn record_health(
+        &mut self,
+        clock: &dyn Clock,
+        provider: &UpstreamOAuthProvider,
+        checks: UpstreamOAuthProviderHealthChecks
+    ) -> Result<UpstreamOAuthProviderHealth, Self::Error>;
+
+    async fn health(
+        &mut self,
+        provider: &UpstreamOAuthProvider
+    ) -> Result<Option<UpstreamOAuthProviderHealth>, Self::Error>;
 );
@@ -6,6 +6,7 @@
 // Please see LICENSE files in the repository root for full details.
 
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use mas_data_model::{
     BrowserSession, Clock, UpstreamOAuthAuthorizationSession, UpstreamOAuthLink,
     UpstreamOAuthProvider,
@@ -21,6 +22,8 @@ pub struct UpstreamOAuthSessionFilter<'a> {
     provider: Option<&'a UpstreamOAuthProvider>,
     sub_claim: Option<&'a str>,
     sid_claim: Option<&'a str>,
+    consumed_after: Option<DateTime<Utc>>,
+    consumed_before: Option<DateTime<Utc>>,
 }
 
 impl<'a> UpstreamOAuthSessionFilter<'a> {
@@ -74,6 +77,36 @@ impl<'a> UpstreamOAuthSessionFilter<'a> {
     pub fn sid_claim(&self) -> Option<&str> {
         self.sid_claim
     }
+
+    /// Only return sessions consumed at or after the given time
+    #[must_use]
+    pub fn with_consumed_after(mut self, consumed_after: DateTime<Utc>) -> Self {
+        self.consumed_after = Some(consumed_after);
+        self
+    }
+
+    /// Get the consumed after filter
+    ///
+    /// Returns [`None`] if no filter was set
+    #[must_use]
+    pub fn consumed_after(&self) -> Option<DateTime<Utc>> {
+        self.consumed_after
+    }
+
+    /// Only return sessions consumed before the given time
+    #[must_use]
+    pub fn with_consumed_before(mut self, consumed_before: DateTime<Utc>) -> Self {
+        self.consumed_before = Some(consumed_before);
+        self
+    }
+
+    /// Get the consumed before filter
+    ///
+    /// Returns [`None`] if no filter was set
+    #[must_use]
+    pub fn consumed_before(&self) -> Option<DateTime<Utc>> {
+        self.consumed_before
+    }
 }
 
 /// An [`UpstreamOAuthSessionRepository`] helps interacting with
@@ -0,0 +1,139 @@
+// Copyright 2026 Element Creations Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+// Please see LICENSE files in the repository root for full details.
+
+//! Types and repository to import users in bulk, used by both the
+//! `manage import-users` command and the admin API import job
+
+use async_trait::async_trait;
+use mas_data_model::Clock;
+use rand_core::RngCore;
+use serde::{Deserialize, Serialize};
+use ulid::Ulid;
+
+use crate::repository_impl;
+
+/// A password hash to import, along with the version of the hashing scheme it
+/// was produced with
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ImportedPassword {
+    /// The version of the password hashing scheme, as configured in the
+    /// `passwords.schemes` section
+    pub version: u16,
+
+    /// The password hash
+    pub hash: String,
+}
+
+/// A link to an upstream OAuth 2.0 provider to import
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ImportedUpstreamLink {
+    /// The ID of the upstream provider
+    pub provider_id: Ulid,
+
+    /// The subject of the user on the upstream provider
+    pub subject: String,
+
+    /// A human-readable name for the upstream account
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub human_account_name: Option<String>,
+}
+
+/// A single user, as found in an import or export file
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct UserImportRecord {
+    /// The username (localpart) of the user
+    pub username: String,
+
+    /// The email addresses of the user
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub emails: Vec<String>,
+
+    /// The password hash of the user
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password: Option<ImportedPassword>,
+
+    /// Whether the user can request admin privileges
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub admin: bool,
+
+    /// Whether the user is locked
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub locked: bool,
+
+    /// The links to upstream OAuth 2.0 providers
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub upstream_links: Vec<ImportedUpstreamLink>,
+
+    /// The display name to set on the homeserver when provisioning the user
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+}
+
+/// A [`UserImportRepository`] stores batches of users waiting to be imported
+/// in the background.
+///
+/// Batches contain password hashes, so they are stored separately from the job
+/// which imports them, and should be removed once imported.
+#[async_trait]
+pub trait UserImportRepository: Send + Sync {
+    /// The error type returned by the repository
+    type Error;
+
+    /// Store a batch of users to import
+    ///
+    /// Returns the ID of the batch
+    ///
+    /// # Parameters
+    ///
+    /// * `rng`: The random number generator to use
+    /// * `clock`: The clock used to generate timestamps
+    /// * `records`: The users to import
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Self::Error`] if the underlying repository fails
+    async fn add(
+        &mut self,
+        rng: &mut (dyn RngCore + Send),
+        clock: &dyn Clock,
+        records: Vec<UserImportRecord>,
+    ) -> Result<Ulid, Self::Error>;
+
+    /// Get the users of a batch
+    ///
+    /// Returns `None` if the batch does not exist, e.g. because it was already
+    /// imported
+    ///
+    /// # Parameters
+    ///
+    /// * `id`: The ID of the batch
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Self::Error`] if the underlying repository fails
+    async fn lookup(&mut self, id: Ulid) -> Result<Option<Vec<UserImportRecord>>, Self::Error>;
+
+    /// Remove a batch
+    ///
+    /// # Parameters
+    ///
+    /// * `id`: The ID of the batch
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Self::Error`] if the underlying repository fails
+    async fn remove(&mut self, id: Ulid) -> Result<(), Self::Error>;
+}
+
+repository_impl!(UserImportRepository:
+    async fn add(
+        &mut self,
+        rng: &mut (dyn RngCore + Send),
+        clock: &dyn Clock,
+        records: Vec<UserImportRecord>,
+    ) -> Result<Ulid, Self::Error>;
+    async fn lookup(&mut self, id: Ulid) -> Result<Option<Vec<UserImportRecord>>, Self::Error>;
+    async fn remove(&mut self, id: Ulid) -> Result<(), Self::Error>;
+);
@@ -8,6 +8,7 @@
 //! Repositories to interact with entities related to user accounts
 
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use mas_data_model::{Clock, User};
 use rand_core::RngCore;
 use ulid::Ulid;
@@ -15,6 +16,7 @@ use ulid::Ulid;
 use crate::{Page, Pagination, repository_impl};
 
 mod email;
+mod import;
 mod password;
 mod recovery;
 mod registration;
@@ -24,6 +26,7 @@ mod terms;
 
 pub use self::{
     email::{UserEmailFilter, UserEmailRepository},
+    import::{ImportedPassword, ImportedUpstreamLink, UserImportRecord, UserImportRepository},
     password::UserPasswordRepository,
     recovery::UserRecoveryRepository,
     registration::UserRegistrationRepository,
@@ -78,6 +81,8 @@ pub struct UserFilter<'a> {
     can_request_admin: Option<bool>,
     is_guest: Option<bool>,
     search: Option<&'a str>,
+    created_after: Option<DateTime<Utc>>,
+    created_before: Option<DateTime<Utc>>,
 }
 
 impl<'a> UserFilter<'a> {
@@ -143,6 +148,20 @@ impl<'a> UserFilter<'a> {
         self
     }
 
+    /// Only return users created at or after the given time
+    #[must_use]
+    pub fn with_created_after(mut self, created_after: DateTime<Utc>) -> Self {
+        self.created_after = Some(created_after);
+        self
+    }
+
+    /// Only return users created before the given time
+    #[must_use]
+    pub fn with_created_before(mut self, created_before: DateTime<Utc>) -> Self {
+        self.created_before = Some(created_before);
+        self
+    }
+
     /// Get the state filter
     ///
     /// Returns [`None`] if no state filter was set
@@ -174,6 +193,22 @@ impl<'a> UserFilter<'a> {
     pub fn search(&self) -> Option<&'a str> {
         self.search
     }
+
+    /// Get the created after filter
+    ///
+    /// Returns [`None`] if no created after filter was set
+    #[must_use]
+    pub fn created_after(&self) -> Option<DateTime<Utc>> {
+        self.created_after
+    }
+
+    /// Get the created before filter
+    ///
+    /// Returns [`None`] if no created before filter was set
+    #[must_use]
+    pub fn created_before(&self) -> Option<DateTime<Utc>> {
+        self.created_before
+    }
 }
 
 /// A [`UserRepository`] helps interacting with [`User`] saved in the storage
@@ -209,6 +244,52 @@ pub trait UserRepository: Send + Sync {
     /// Returns [`Self::Error`] if the underlying repository fails
     async fn find_by_username(&mut self, username: &str) -> Result<Option<User>, Self::Error>;
 
+    /// Find the [`User`] linked to an LDAP directory entry
+    ///
+    /// Returns `None` if no [`User`] is linked to this entry
+    ///
+    /// # Parameters
+    ///
+    /// * `dn`: The distinguished name of the directory entry
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Self::Error`] if the underlying repository fails
+    async fn find_by_ldap_dn(&mut self, dn: &str) -> Result<Option<User>, Self::Error>;
+
+    /// Get the distinguished name of the LDAP directory entry linked to a
+    /// [`User`]
+    ///
+    /// Returns `None` if the [`User`] is not linked to a directory entry
+    ///
+    /// # Parameters
+    ///
+    /// * `user`: The [`User`] to get the linked entry of
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Self::Error`] if the underlying repository fails
+    async fn ldap_dn(&mut self, user: &User) -> Result<Option<String>, Self::Error>;
+
+    /// Link a [`User`] to an LDAP directory entry
+    ///
+    /// # Parameters
+    ///
+    /// * `clock`: The clock used to generate timestamps
+    /// * `user`: The [`User`] to link
+    /// * `dn`: The distinguished name of the directory entry
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Self::Error`] if the underlying repository fails, or if either
+    /// the [`User`] or the directory entry is already linked
+    async fn add_ldap_link(
+        &mut self,
+        clock: &dyn Clock,
+        user: &User,
+        dn: String,
+    ) -> Result<(), Self::Error>;
+
     /// Create a new [`User`]
     ///
     /// Returns the newly created [`User`]
@@ -331,6 +412,20 @@ pub trait UserRepository: Send + Sync {
         can_request_admin: bool,
     ) -> Result<User, Self::Error>;
 
+    /// Change the username of a [`User`]
+    ///
+    /// Returns the [`User`] with the new username
+    ///
+    /// # Parameters
+    ///
+    /// * `user`: The [`User`] to rename
+    /// * `username`: The new username
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Self::Error`] if the underlying repository fails
+    async fn set_username(&mut self, user: User, username: String) -> Result<User, Self::Error>;
+
     /// List [`User`] with the given filter and pagination
     ///
     /// # Parameters
@@ -375,6 +470,14 @@ pub trait UserRepository: Send + Sync {
 repository_impl!(UserRepository:
     async fn lookup(&mut self, id: Ulid) -> Result<Option<User>, Self::Error>;
     async fn find_by_username(&mut self, username: &str) -> Result<Option<User>, Self::Error>;
+    async fn find_by_ldap_dn(&mut self, dn: &str) -> Result<Option<User>, Self::Error>;
+    async fn ldap_dn(&mut self, user: &User) -> Result<Option<String>, Self::Error>;
+    async fn add_ldap_link(
+        &mut self,
+        clock: &dyn Clock,
+        user: &User,
+        dn: String,
+    ) -> Result<(), Self::Error>;
     async fn add(
         &mut self,
         rng: &mut (dyn RngCore + Send),
@@ -392,6 +495,7 @@ repository_impl!(UserRepository:
         user: User,
         can_request_admin: bool,
     ) -> Result<User, Self::Error>;
+    async fn set_username(&mut self, user: User, username: String) -> Result<User, Self::Error>;
     async fn list(
         &mut self,
         filter: UserFilter<'_>,
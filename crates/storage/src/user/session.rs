@@ -279,6 +279,27 @@ pub trait BrowserSessionRepository: Send + Sync {
         upstream_oauth_session: &UpstreamOAuthAuthorizationSession,
     ) -> Result<Authentication, Self::Error>;
 
+    /// Authenticate a [`BrowserSession`] with a password checked against an
+    /// LDAP directory
+    ///
+    /// # Parameters
+    ///
+    /// * `rng`: The random number generator to use
+    /// * `clock`: The clock used to generate timestamps
+    /// * `user_session`: The session to authenticate
+    /// * `dn`: The distinguished name of the directory entry of the user
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Self::Error`] if the underlying repository fails
+    async fn authenticate_with_ldap(
+        &mut self,
+        rng: &mut (dyn RngCore + Send),
+        clock: &dyn Clock,
+        user_session: &BrowserSession,
+        dn: &str,
+    ) -> Result<Authentication, Self::Error>;
+
     /// Get the last successful authentication for a [`BrowserSession`]
     ///
     /// # Params
@@ -402,6 +423,14 @@ repository_impl!(BrowserSessionRepository:
         upstream_oauth_session: &UpstreamOAuthAuthorizationSession,
     ) -> Result<Authentication, Self::Error>;
 
+    async fn authenticate_with_ldap(
+        &mut self,
+        rng: &mut (dyn RngCore + Send),
+        clock: &dyn Clock,
+        user_session: &BrowserSession,
+        dn: &str,
+    ) -> Result<Authentication, Self::Error>;
+
     async fn get_last_authentication(
         &mut self,
         user_session: &BrowserSession,
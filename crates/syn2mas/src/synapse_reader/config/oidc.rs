@@ -330,6 +330,8 @@ impl OidcProvider {
             client_id,
             client_secret: self.client_secret.map(ClientSecret::Value),
             token_endpoint_auth_method,
+            private_keys: Vec::new(),
+            client_certificate: None,
             sign_in_with_apple: None,
             token_endpoint_auth_signing_alg: None,
             id_token_signed_response_alg: JsonWebSignatureAlg::Rs256,
@@ -346,7 +348,11 @@ impl OidcProvider {
             claims_imports,
             additional_authorization_parameters,
             forward_login_hint: self.forward_login_hint,
+            email_domains: Vec::new(),
+            store_tokens: false,
+            allow_initiated_login: false,
             on_backchannel_logout,
+            saml: None,
         })
     }
 }
@@ -9,6 +9,7 @@ use std::sync::{Arc, LazyLock};
 
 use mas_data_model::{Clock, SiteConfig};
 use mas_email::Mailer;
+use mas_keystore::Encrypter;
 use mas_matrix::HomeserverConnection;
 use mas_router::UrlBuilder;
 use mas_storage::{BoxRepository, RepositoryError, RepositoryFactory};
@@ -19,7 +20,11 @@ use rand::SeedableRng;
 use sqlx::{Pool, Postgres};
 use tokio_util::{sync::CancellationToken, task::TaskTracker};
 
-pub use crate::new_queue::QueueWorker;
+pub use crate::{
+    new_queue::QueueWorker,
+    upstream_oauth2::UpstreamOAuthClaimsSync,
+    user_import::{UserImportError, import_user},
+};
 
 mod cleanup;
 mod email;
@@ -27,7 +32,11 @@ mod matrix;
 mod new_queue;
 mod recovery;
 mod sessions;
+mod signing_keys;
+mod stats;
+mod upstream_oauth2;
 mod user;
+mod user_import;
 
 static METER: LazyLock<Meter> = LazyLock::new(|| {
     let scope = opentelemetry::InstrumentationScope::builder(env!("CARGO_PKG_NAME"))
@@ -46,9 +55,12 @@ struct State {
     homeserver: Arc<dyn HomeserverConnection>,
     url_builder: UrlBuilder,
     site_config: SiteConfig,
+    encrypter: Encrypter,
+    upstream_oauth_claims_sync: Arc<dyn UpstreamOAuthClaimsSync>,
 }
 
 impl State {
+    #[expect(clippy::too_many_arguments, reason = "this is fine")]
     pub fn new(
         repository_factory: PgRepositoryFactory,
         clock: impl Clock + 'static,
@@ -56,6 +68,8 @@ impl State {
         homeserver: impl HomeserverConnection + 'static,
         url_builder: UrlBuilder,
         site_config: SiteConfig,
+        encrypter: Encrypter,
+        upstream_oauth_claims_sync: Arc<dyn UpstreamOAuthClaimsSync>,
     ) -> Self {
         Self {
             repository_factory,
@@ -64,6 +78,8 @@ impl State {
             homeserver: Arc::new(homeserver),
             url_builder,
             site_config,
+            encrypter,
+            upstream_oauth_claims_sync,
         }
     }
 
@@ -89,6 +105,10 @@ impl State {
         self.repository_factory.create().await
     }
 
+    pub fn repository_factory(&self) -> &PgRepositoryFactory {
+        &self.repository_factory
+    }
+
     pub fn matrix_connection(&self) -> &dyn HomeserverConnection {
         self.homeserver.as_ref()
     }
@@ -100,6 +120,14 @@ impl State {
     pub fn site_config(&self) -> &SiteConfig {
         &self.site_config
     }
+
+    pub fn encrypter(&self) -> &Encrypter {
+        &self.encrypter
+    }
+
+    pub fn upstream_oauth_claims_sync(&self) -> &dyn UpstreamOAuthClaimsSync {
+        self.upstream_oauth_claims_sync.as_ref()
+    }
 }
 
 /// Initialise the worker, without running it.
@@ -109,6 +137,7 @@ impl State {
 /// # Errors
 ///
 /// This function can fail if the database connection fails.
+#[expect(clippy::too_many_arguments, reason = "this is fine")]
 pub async fn init(
     repository_factory: PgRepositoryFactory,
     clock: impl Clock + 'static,
@@ -116,6 +145,8 @@ pub async fn init(
     homeserver: impl HomeserverConnection + 'static,
     url_builder: UrlBuilder,
     site_config: &SiteConfig,
+    encrypter: &Encrypter,
+    upstream_oauth_claims_sync: Arc<dyn UpstreamOAuthClaimsSync>,
     cancellation_token: CancellationToken,
 ) -> Result<QueueWorker, QueueRunnerError> {
     let state = State::new(
@@ -125,6 +156,8 @@ pub async fn init(
         homeserver,
         url_builder,
         site_config.clone(),
+        encrypter.clone(),
+        upstream_oauth_claims_sync,
     );
     let mut worker = QueueWorker::new(state, cancellation_token).await?;
 
@@ -146,6 +179,7 @@ pub async fn init(
         .register_handler::<mas_storage::queue::CleanupQueueJobsJob>()
         .register_handler::<mas_storage::queue::DeactivateUserJob>()
         .register_handler::<mas_storage::queue::DeleteDeviceJob>()
+        .register_handler::<mas_storage::queue::ImportUsersJob>()
         .register_handler::<mas_storage::queue::ProvisionDeviceJob>()
         .register_handler::<mas_storage::queue::ProvisionUserJob>()
         .register_handler::<mas_storage::queue::ReactivateUserJob>()
@@ -161,6 +195,11 @@ pub async fn init(
         .register_handler::<mas_storage::queue::CleanupInactiveOAuth2SessionIpsJob>()
         .register_handler::<mas_storage::queue::CleanupInactiveCompatSessionIpsJob>()
         .register_handler::<mas_storage::queue::CleanupInactiveUserSessionIpsJob>()
+        .register_handler::<mas_storage::queue::ComputeDailyStatsJob>()
+        .register_handler::<mas_storage::queue::SyncUpstreamOAuthClaimsJob>()
+        .register_handler::<mas_storage::queue::RefreshUpstreamOAuthTokensJob>()
+        .register_handler::<mas_storage::queue::CheckUpstreamOAuthProvidersHealthJob>()
+        .register_handler::<mas_storage::queue::RotateSigningKeysJob>()
         .register_deprecated_queue("cleanup-expired-tokens")
         // Recurring jobs are spread across the hour at ~5 minute intervals
         // to avoid clustering and distribute database load evenly.
@@ -284,6 +323,36 @@ pub async fn init(
             // Run once a day at 2:00 AM
             "0 0 2 * * *".parse()?,
             mas_storage::queue::PruneStalePolicyDataJob,
+        )
+        .add_schedule(
+            "compute-daily-stats",
+            // Run once a day at 00:10, to compute the stats of the previous day
+            "0 10 0 * * *".parse()?,
+            mas_storage::queue::ComputeDailyStatsJob,
+        )
+        .add_schedule(
+            "sync-upstream-oauth-claims",
+            // Run once a day at 3:30 AM
+            "0 30 3 * * *".parse()?,
+            mas_storage::queue::SyncUpstreamOAuthClaimsJob,
+        )
+        .add_schedule(
+            "refresh-upstream-oauth-tokens",
+            // Run this job every 5 minutes at second 45
+            "45 */5 * * * *".parse()?,
+            mas_storage::queue::RefreshUpstreamOAuthTokensJob,
+        )
+        .add_schedule(
+            "check-upstream-oauth-providers-health",
+            // Run this job every 5 minutes at second 15
+            "15 */5 * * * *".parse()?,
+            mas_storage::queue::CheckUpstreamOAuthProvidersHealthJob,
+        )
+        .add_schedule(
+            "rotate-signing-keys",
+            // Run this job every hour at minute 2
+            "0 2 * * * *".parse()?,
+            mas_storage::queue::RotateSigningKeysJob::default(),
         );
 
     Ok(worker)
@@ -302,6 +371,8 @@ pub async fn init_and_run(
     homeserver: impl HomeserverConnection + 'static,
     url_builder: UrlBuilder,
     site_config: &SiteConfig,
+    encrypter: &Encrypter,
+    upstream_oauth_claims_sync: Arc<dyn UpstreamOAuthClaimsSync>,
     cancellation_token: CancellationToken,
     task_tracker: &TaskTracker,
 ) -> Result<(), QueueRunnerError> {
@@ -312,6 +383,8 @@ pub async fn init_and_run(
         homeserver,
         url_builder,
         site_config,
+        encrypter,
+        upstream_oauth_claims_sync,
         cancellation_token,
     )
     .await?;
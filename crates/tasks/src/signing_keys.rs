@@ -0,0 +1,166 @@
+// Copyright 2026 Element Creations Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+// Please see LICENSE files in the repository root for full details.
+
+//! Rotation of the signing keys managed by the service
+
+use anyhow::Context;
+use async_trait::async_trait;
+use mas_data_model::{SigningKey, SigningKeyType};
+use mas_jose::jwk::Thumbprint as _;
+use mas_keystore::PrivateKey;
+use mas_storage::queue::RotateSigningKeysJob;
+use rand::SeedableRng;
+use tracing::{info, info_span, warn};
+
+use crate::{
+    State,
+    new_queue::{JobContext, JobError, RunnableJob},
+};
+
+/// Generate a new private key of the given type.
+///
+/// This runs on a blocking thread, as generating RSA keys can take a while.
+async fn generate_key(
+    key_type: SigningKeyType,
+    rng: &mut rand_chacha::ChaChaRng,
+) -> Result<PrivateKey, anyhow::Error> {
+    let span = info_span!("signing_key.generate", signing_key.key_type = %key_type);
+    let key_rng = rand_chacha::ChaChaRng::from_rng(rng)?;
+    tokio::task::spawn_blocking(move || -> Result<PrivateKey, anyhow::Error> {
+        let _entered = span.enter();
+        let key = match key_type {
+            SigningKeyType::Rsa => PrivateKey::generate_rsa(key_rng)?,
+            SigningKeyType::EcP256 => PrivateKey::generate_ec_p256(key_rng),
+            SigningKeyType::EcP384 => PrivateKey::generate_ec_p384(key_rng),
+            SigningKeyType::EcK256 => PrivateKey::generate_ec_k256(key_rng),
+            SigningKeyType::Ed25519 => PrivateKey::generate_ed25519(key_rng),
+        };
+        Ok(key)
+    })
+    .await
+    .context("could not join blocking task")?
+}
+
+#[async_trait]
+impl RunnableJob for RotateSigningKeysJob {
+    #[tracing::instrument(name = "job.rotate_signing_keys", skip_all)]
+    async fn run(&self, state: &State, _context: JobContext) -> Result<(), JobError> {
+        let Some(config) = &state.site_config().signing_key_rotation else {
+            if self.force() {
+                warn!("Signing key rotation was requested, but it is not enabled");
+            }
+            return Ok(());
+        };
+
+        let clock = state.clock();
+        let mut rng = state.rng();
+        let now = clock.now();
+        let mut repo = state.repository().await.map_err(JobError::retry)?;
+
+        let keys = repo.signing_key().all().await.map_err(JobError::retry)?;
+
+        // Retire the keys of types which are not rotated anymore
+        for key in &keys {
+            if !key.is_retired() && !config.key_types.contains(&key.key_type) {
+                info!(
+                    signing_key.id = %key.id,
+                    signing_key.kid = %key.kid,
+                    "Retiring signing key of a type which is not rotated anymore"
+                );
+                repo.signing_key()
+                    .retire(clock, key.clone())
+                    .await
+                    .map_err(JobError::retry)?;
+            }
+        }
+
+        for &key_type in &config.key_types {
+            let of_type = |key: &&SigningKey| key.key_type == key_type;
+            // Keys are ordered by creation, so the last one is the most recent
+            let mut pending = keys
+                .iter()
+                .filter(of_type)
+                .rfind(|key| key.is_pending())
+                .cloned();
+            let mut active = keys
+                .iter()
+                .filter(of_type)
+                .rfind(|key| key.is_active())
+                .cloned();
+
+            // Start using the pending key once it has been published long enough
+            if let Some(key) =
+                pending.take_if(|key| now >= key.created_at + config.publication_period)
+            {
+                for previous in keys.iter().filter(of_type).filter(|key| key.is_active()) {
+                    repo.signing_key()
+                        .retire(clock, previous.clone())
+                        .await
+                        .map_err(JobError::retry)?;
+                }
+
+                let key = repo
+                    .signing_key()
+                    .activate(clock, key)
+                    .await
+                    .map_err(JobError::retry)?;
+                info!(
+                    signing_key.id = %key.id,
+                    signing_key.kid = %key.kid,
+                    %key_type,
+                    "Activated signing key"
+                );
+                active = Some(key);
+            }
+
+            // Publish a new key ahead of the rotation of the active one, so that it
+            // has been published long enough by the time it gets used
+            let due = match &active {
+                None => true,
+                Some(key) => key.activated_at().is_none_or(|activated_at| {
+                    now >= activated_at + config.rotation_interval - config.publication_period
+                }),
+            };
+
+            if pending.is_none() && (due || self.force()) {
+                let private_key = generate_key(key_type, &mut rng)
+                    .await
+                    .map_err(JobError::fail)?;
+                let kid = private_key.thumbprint_sha256_base64();
+                let der = private_key.to_pkcs8_der().map_err(JobError::fail)?;
+                let encrypted_key = state.encrypter().encrypt_to_string(&der).map_err(|_| {
+                    JobError::fail(anyhow::anyhow!("Failed to encrypt signing key"))
+                })?;
+
+                let key = repo
+                    .signing_key()
+                    .add(&mut rng, clock, kid, key_type, encrypted_key)
+                    .await
+                    .map_err(JobError::retry)?;
+                info!(
+                    signing_key.id = %key.id,
+                    signing_key.kid = %key.kid,
+                    %key_type,
+                    "Published new signing key"
+                );
+            }
+        }
+
+        // Remove the keys which were retired long enough ago that the tokens they
+        // signed have expired
+        let removed = repo
+            .signing_key()
+            .remove_retired(now - config.retention_period)
+            .await
+            .map_err(JobError::retry)?;
+        if removed > 0 {
+            info!(removed, "Removed retired signing keys");
+        }
+
+        repo.save().await.map_err(JobError::retry)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,168 @@
+// Copyright 2026 Element Creations Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+// Please see LICENSE files in the repository root for full details.
+
+//! Usage statistics rollup
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::{Days, NaiveDate};
+use mas_storage::{
+    BoxRepository,
+    compat::CompatSessionFilter,
+    oauth2::OAuth2SessionFilter,
+    queue::ComputeDailyStatsJob,
+    stats::{DailyStat, StatsMetric},
+    upstream_oauth2::UpstreamOAuthSessionFilter,
+    user::{BrowserSessionFilter, UserFilter},
+};
+use tracing::info;
+
+use crate::{
+    State,
+    new_queue::{JobContext, JobError, RunnableJob},
+};
+
+/// Compute the statistics of a day, in UTC
+///
+/// Session counts are a snapshot of the current state, so they are only
+/// computed when `snapshot` is set, which is the case for the previous day.
+async fn compute_day(
+    repo: &mut BoxRepository,
+    day: NaiveDate,
+    snapshot: bool,
+) -> Result<Vec<DailyStat>, JobError> {
+    let start = day.and_time(chrono::NaiveTime::MIN).and_utc();
+    let end = start + chrono::Duration::days(1);
+
+    let mut stats = Vec::new();
+    let mut push = |metric, dimension, value| {
+        stats.push(DailyStat {
+            day,
+            metric,
+            dimension,
+            value,
+        });
+    };
+
+    let daily_active_users = repo
+        .stats()
+        .count_active_users(start, end)
+        .await
+        .map_err(JobError::retry)?;
+    push(StatsMetric::DailyActiveUsers, None, daily_active_users);
+
+    let monthly_active_users = repo
+        .stats()
+        .count_active_users(end - chrono::Duration::days(30), end)
+        .await
+        .map_err(JobError::retry)?;
+    push(StatsMetric::MonthlyActiveUsers, None, monthly_active_users);
+
+    let new_registrations = repo
+        .user()
+        .count(
+            UserFilter::new()
+                .with_created_after(start)
+                .with_created_before(end),
+        )
+        .await
+        .map_err(JobError::retry)?;
+    push(StatsMetric::NewRegistrations, None, new_registrations);
+
+    if snapshot {
+        let browser_sessions = repo
+            .browser_session()
+            .count(BrowserSessionFilter::new().active_only())
+            .await
+            .map_err(JobError::retry)?;
+        push(StatsMetric::ActiveBrowserSessions, None, browser_sessions);
+
+        let compat_sessions = repo
+            .compat_session()
+            .count(CompatSessionFilter::new().active_only())
+            .await
+            .map_err(JobError::retry)?;
+        push(StatsMetric::ActiveCompatSessions, None, compat_sessions);
+
+        let oauth2_sessions = repo
+            .oauth2_session()
+            .count(OAuth2SessionFilter::new().active_only())
+            .await
+            .map_err(JobError::retry)?;
+        push(StatsMetric::ActiveOAuth2Sessions, None, oauth2_sessions);
+
+        let by_client = repo
+            .stats()
+            .count_active_oauth2_sessions_by_client()
+            .await
+            .map_err(JobError::retry)?;
+        for (client_id, count) in by_client {
+            push(StatsMetric::ActiveOAuth2Sessions, Some(client_id), count);
+        }
+    }
+
+    let providers = repo
+        .upstream_oauth_provider()
+        .all_enabled()
+        .await
+        .map_err(JobError::retry)?;
+    for provider in &providers {
+        let logins = repo
+            .upstream_oauth_session()
+            .count(
+                UpstreamOAuthSessionFilter::new()
+                    .for_provider(provider)
+                    .with_consumed_after(start)
+                    .with_consumed_before(end),
+            )
+            .await
+            .map_err(JobError::retry)?;
+        push(StatsMetric::UpstreamLogins, Some(provider.id), logins);
+    }
+
+    info!(
+        %day,
+        daily_active_users,
+        monthly_active_users,
+        new_registrations,
+        "computed daily statistics"
+    );
+
+    Ok(stats)
+}
+
+#[async_trait]
+impl RunnableJob for ComputeDailyStatsJob {
+    #[tracing::instrument(name = "job.compute_daily_stats", skip_all)]
+    async fn run(&self, state: &State, _context: JobContext) -> Result<(), JobError> {
+        // Compute the statistics of the previous day, in UTC, as well as of
+        // every day missed since the last rollup, for example if the job didn't
+        // run while the service was down. Session counts are a snapshot taken
+        // when the job runs, so this should run shortly after midnight.
+        let yesterday = state.clock.now().date_naive() - Days::new(1);
+
+        let mut repo = state.repository().await.map_err(JobError::retry)?;
+
+        let last_day = repo.stats().last_day().await.map_err(JobError::retry)?;
+        let first_day = last_day.map_or(yesterday, |day| (day + Days::new(1)).min(yesterday));
+
+        for day in first_day.iter_days().take_while(|day| *day <= yesterday) {
+            let stats = compute_day(&mut repo, day, day == yesterday).await?;
+            repo.stats()
+                .replace_day(state.clock(), day, &stats)
+                .await
+                .map_err(JobError::retry)?;
+        }
+
+        repo.save().await.map_err(JobError::retry)?;
+
+        Ok(())
+    }
+
+    fn timeout(&self) -> Option<Duration> {
+        Some(Duration::from_mins(10))
+    }
+}
@@ -0,0 +1,344 @@
+// Copyright 2026 Element Creations Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+// Please see LICENSE files in the repository root for full details.
+
+use std::sync::LazyLock;
+
+use async_trait::async_trait;
+use chrono::Duration;
+use mas_data_model::{
+    Clock, UpstreamOAuthLink, UpstreamOAuthProvider, UpstreamOAuthProviderHealthChecks, User,
+};
+use mas_storage::{
+    Pagination, RepositoryAccess, RepositoryFactory,
+    queue::{
+        CheckUpstreamOAuthProvidersHealthJob, RefreshUpstreamOAuthTokensJob,
+        SyncUpstreamOAuthClaimsJob,
+    },
+    upstream_oauth2::{
+        UpstreamOAuthLinkFilter, UpstreamOAuthLinkRepository, UpstreamOAuthProviderRepository,
+    },
+};
+use opentelemetry::{Key, KeyValue, metrics::Gauge};
+use rand::RngCore;
+use tracing::{debug, info, warn};
+
+use crate::{
+    METER, State,
+    new_queue::{JobContext, JobError, RunnableJob},
+};
+
+static PROVIDER_HEALTH_GAUGE: LazyLock<Gauge<u64>> = LazyLock::new(|| {
+    METER
+        .u64_gauge("mas.upstream_oauth2.provider.healthy")
+        .with_description("Whether the last health check of an upstream provider passed")
+        .build()
+});
+const PROVIDER: Key = Key::from_static_str("provider");
+
+/// Refreshes the claims of users linked to upstream OAuth providers, and
+/// checks the health of those providers
+///
+/// This lives outside of this crate, as it needs to talk to the upstream
+/// providers.
+#[async_trait]
+pub trait UpstreamOAuthClaimsSync: Send + Sync {
+    /// Refresh the claims behind an upstream OAuth link using the refresh token
+    /// stored on it, and re-apply the claims imports of the provider to the
+    /// linked user
+    ///
+    /// Implementations must not hold a repository while talking to the
+    /// provider, which is why they get a factory to create short-lived ones.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the claims could not be refreshed
+    async fn sync_link(
+        &self,
+        rng: &mut (dyn RngCore + Send),
+        clock: &dyn Clock,
+        repositories: &(dyn RepositoryFactory + Send + Sync),
+        provider: &UpstreamOAuthProvider,
+        link: &UpstreamOAuthLink,
+        user: &User,
+    ) -> Result<(), anyhow::Error>;
+
+    /// Refresh the access token stored on an upstream OAuth link using the
+    /// refresh token stored on it, and store the new tokens
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the tokens could not be refreshed
+    async fn refresh_tokens(
+        &self,
+        rng: &mut (dyn RngCore + Send),
+        clock: &dyn Clock,
+        repositories: &(dyn RepositoryFactory + Send + Sync),
+        provider: &UpstreamOAuthProvider,
+        link: &UpstreamOAuthLink,
+    ) -> Result<(), anyhow::Error>;
+
+    /// Check that an upstream OAuth provider is reachable and correctly
+    /// configured
+    async fn check_health(
+        &self,
+        provider: &UpstreamOAuthProvider,
+    ) -> UpstreamOAuthProviderHealthChecks;
+}
+
+/// How many links are loaded at once
+const BATCH_SIZE: usize = 100;
+
+/// Access tokens expiring in less than this are refreshed. This is larger than
+/// the interval at which the job runs, so that tokens get refreshed before
+/// they expire.
+const REFRESH_WINDOW: Duration = Duration::microseconds(15 * 60 * 1000 * 1000);
+
+#[async_trait]
+impl RunnableJob for SyncUpstreamOAuthClaimsJob {
+    #[tracing::instrument(name = "job.sync_upstream_oauth_claims", skip_all)]
+    async fn run(&self, state: &State, context: JobContext) -> Result<(), JobError> {
+        let clock = state.clock();
+        let mut rng = state.rng();
+        let syncer = state.upstream_oauth_claims_sync();
+
+        let mut repo = state.repository().await.map_err(JobError::retry)?;
+        let providers: Vec<_> = repo
+            .upstream_oauth_provider()
+            .all_enabled()
+            .await
+            .map_err(JobError::retry)?
+            .into_iter()
+            .filter(|provider| provider.claims_imports.resync.in_background)
+            .collect();
+        repo.cancel().await.map_err(JobError::retry)?;
+
+        if providers.is_empty() {
+            debug!("No upstream OAuth provider has background claims sync enabled");
+            return Ok(());
+        }
+
+        for provider in &providers {
+            let mut synced = 0;
+            let mut failed = 0;
+            let mut cursor = Pagination::first(BATCH_SIZE);
+
+            // We don't schedule a retry if we get cancelled, as this is a scheduled
+            // job and it will end up being rescheduled later anyway.
+            while !context.cancellation_token.is_cancelled() {
+                let mut repo = state.repository().await.map_err(JobError::retry)?;
+                let page = repo
+                    .upstream_oauth_link()
+                    .list(
+                        UpstreamOAuthLinkFilter::new()
+                            .for_provider(provider)
+                            .with_refresh_token_only(),
+                        cursor,
+                    )
+                    .await
+                    .map_err(JobError::retry)?;
+
+                let mut links = Vec::with_capacity(page.edges.len());
+                for edge in page.edges {
+                    cursor = cursor.after(edge.cursor);
+                    let link = edge.node;
+
+                    let Some(user_id) = link.user_id else {
+                        continue;
+                    };
+
+                    let user = repo.user().lookup(user_id).await.map_err(JobError::retry)?;
+                    if let Some(user) = user.filter(User::is_valid) {
+                        links.push((link, user));
+                    }
+                }
+
+                // Don't hold a database connection while talking to the provider, the
+                // syncer writes back each link in its own transaction
+                repo.cancel().await.map_err(JobError::retry)?;
+
+                for (link, user) in links {
+                    match syncer
+                        .sync_link(
+                            &mut rng,
+                            clock,
+                            state.repository_factory(),
+                            provider,
+                            &link,
+                            &user,
+                        )
+                        .await
+                    {
+                        Ok(()) => synced += 1,
+                        Err(e) => {
+                            warn!(
+                                upstream_oauth_link.id = %link.id,
+                                user.id = %user.id,
+                                error = &*e as &dyn std::error::Error,
+                                "Failed to refresh the upstream claims of a user"
+                            );
+                            failed += 1;
+                        }
+                    }
+                }
+
+                if !page.has_next_page {
+                    break;
+                }
+            }
+
+            info!(
+                upstream_oauth_provider.id = %provider.id,
+                synced, failed, "Refreshed upstream claims"
+            );
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl RunnableJob for RefreshUpstreamOAuthTokensJob {
+    #[tracing::instrument(name = "job.refresh_upstream_oauth_tokens", skip_all)]
+    async fn run(&self, state: &State, context: JobContext) -> Result<(), JobError> {
+        let clock = state.clock();
+        let mut rng = state.rng();
+        let syncer = state.upstream_oauth_claims_sync();
+
+        let mut repo = state.repository().await.map_err(JobError::retry)?;
+        let providers: Vec<_> = repo
+            .upstream_oauth_provider()
+            .all_enabled()
+            .await
+            .map_err(JobError::retry)?
+            .into_iter()
+            .filter(|provider| provider.store_tokens)
+            .collect();
+        repo.cancel().await.map_err(JobError::retry)?;
+
+        if providers.is_empty() {
+            debug!("No upstream OAuth provider has token storage enabled");
+            return Ok(());
+        }
+
+        let expiring_before = clock.now() + REFRESH_WINDOW;
+
+        for provider in &providers {
+            let mut refreshed = 0;
+            let mut failed = 0;
+            let mut cursor = Pagination::first(BATCH_SIZE);
+
+            // We don't schedule a retry if we get cancelled, as this is a scheduled
+            // job and it will end up being rescheduled later anyway.
+            while !context.cancellation_token.is_cancelled() {
+                let mut repo = state.repository().await.map_err(JobError::retry)?;
+                let page = repo
+                    .upstream_oauth_link()
+                    .list(
+                        UpstreamOAuthLinkFilter::new()
+                            .for_provider(provider)
+                            .with_refresh_token_only()
+                            .with_access_token_expiring_before(expiring_before),
+                        cursor,
+                    )
+                    .await
+                    .map_err(JobError::retry)?;
+
+                // Don't hold a database connection while talking to the provider, the
+                // syncer writes back each link in its own transaction
+                repo.cancel().await.map_err(JobError::retry)?;
+
+                for edge in page.edges {
+                    cursor = cursor.after(edge.cursor);
+                    let link = edge.node;
+
+                    match syncer
+                        .refresh_tokens(
+                            &mut rng,
+                            clock,
+                            state.repository_factory(),
+                            provider,
+                            &link,
+                        )
+                        .await
+                    {
+                        Ok(()) => refreshed += 1,
+                        Err(e) => {
+                            warn!(
+                                upstream_oauth_link.id = %link.id,
+                                error = &*e as &dyn std::error::Error,
+                                "Failed to refresh the upstream tokens of a link"
+                            );
+                            failed += 1;
+                        }
+                    }
+                }
+
+                if !page.has_next_page {
+                    break;
+                }
+            }
+
+            info!(
+                upstream_oauth_provider.id = %provider.id,
+                refreshed, failed, "Refreshed upstream tokens"
+            );
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl RunnableJob for CheckUpstreamOAuthProvidersHealthJob {
+    #[tracing::instrument(name = "job.check_upstream_oauth_providers_health", skip_all)]
+    async fn run(&self, state: &State, context: JobContext) -> Result<(), JobError> {
+        let clock = state.clock();
+        let syncer = state.upstream_oauth_claims_sync();
+
+        let mut repo = state.repository().await.map_err(JobError::retry)?;
+        let providers = repo
+            .upstream_oauth_provider()
+            .all_enabled()
+            .await
+            .map_err(JobError::retry)?;
+        repo.cancel().await.map_err(JobError::retry)?;
+
+        for provider in &providers {
+            // We don't schedule a retry if we get cancelled, as this is a scheduled
+            // job and it will end up being rescheduled later anyway.
+            if context.cancellation_token.is_cancelled() {
+                break;
+            }
+
+            // Don't hold a database connection while talking to the provider
+            let checks = syncer.check_health(provider).await;
+            let healthy = checks.is_healthy();
+
+            if healthy {
+                debug!(upstream_oauth_provider.id = %provider.id, "Upstream provider is healthy");
+            } else {
+                warn!(
+                    upstream_oauth_provider.id = %provider.id,
+                    ?checks,
+                    "Upstream provider failed its health check"
+                );
+            }
+
+            PROVIDER_HEALTH_GAUGE.record(
+                u64::from(healthy),
+                &[KeyValue::new(PROVIDER, provider.id.to_string())],
+            );
+
+            let mut repo = state.repository().await.map_err(JobError::retry)?;
+            repo.upstream_oauth_provider()
+                .record_health(clock, provider, checks)
+                .await
+                .map_err(JobError::retry)?;
+            repo.save().await.map_err(JobError::retry)?;
+        }
+
+        Ok(())
+    }
+}
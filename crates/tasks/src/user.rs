@@ -11,14 +11,15 @@ use mas_storage::{
     compat::CompatSessionFilter,
     oauth2::OAuth2SessionFilter,
     personal::PersonalSessionFilter,
-    queue::{DeactivateUserJob, ReactivateUserJob},
+    queue::{DeactivateUserJob, ImportUsersJob, ReactivateUserJob},
     user::{BrowserSessionFilter, UserEmailFilter, UserRepository},
 };
-use tracing::info;
+use tracing::{info, warn};
 
 use crate::{
     State,
     new_queue::{JobContext, JobError, RunnableJob},
+    user_import::import_user,
 };
 
 /// Job to deactivate a user, both locally and on the Matrix homeserver.
@@ -182,3 +183,68 @@ impl RunnableJob for ReactivateUserJob {
         Ok(())
     }
 }
+
+/// Job to import users in bulk, as submitted through the admin API.
+///
+/// Records which conflict with existing data are skipped and logged, so that
+/// re-submitting the same file after a partial failure is safe.
+#[async_trait]
+impl RunnableJob for ImportUsersJob {
+    #[tracing::instrument(
+        name = "job.import_users",
+        fields(user_import.id = %self.user_import_id()),
+        skip_all,
+    )]
+    async fn run(&self, state: &State, _context: JobContext) -> Result<(), JobError> {
+        let clock = state.clock();
+        let mut rng = state.rng();
+        let mut repo = state.repository().await.map_err(JobError::retry)?;
+
+        let Some(records) = repo
+            .user_import()
+            .lookup(self.user_import_id())
+            .await
+            .map_err(JobError::retry)?
+        else {
+            // The batch is removed in the same transaction as the users are
+            // imported, so this means a previous attempt already succeeded
+            info!("User import batch not found, it was probably already imported");
+            return Ok(());
+        };
+
+        let mut imported = 0;
+        let mut skipped = 0;
+        for record in records {
+            let username = record.username.clone();
+            match import_user(
+                &mut repo,
+                &mut rng,
+                clock,
+                state.matrix_connection(),
+                record,
+            )
+            .await
+            {
+                Ok(user) => {
+                    info!(%user.id, %user.username, "Imported user");
+                    imported += 1;
+                }
+                Err(e) if e.is_invalid_record() => {
+                    warn!(user.username = username, "Skipping user: {e}");
+                    skipped += 1;
+                }
+                Err(e) => return Err(JobError::retry(e)),
+            }
+        }
+
+        repo.user_import()
+            .remove(self.user_import_id())
+            .await
+            .map_err(JobError::retry)?;
+
+        repo.save().await.map_err(JobError::retry)?;
+        info!(imported, skipped, "Finished importing users");
+
+        Ok(())
+    }
+}
@@ -0,0 +1,251 @@
+// Copyright 2026 Element Creations Ltd.
+//
+// SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+// Please see LICENSE files in the repository root for full details.
+
+//! Import users in bulk, used by both the `manage import-users` command and
+//! the admin API import job
+
+use mas_data_model::{Clock, User};
+use mas_matrix::HomeserverConnection;
+use mas_storage::{
+    RepositoryAccess,
+    queue::{ProvisionUserJob, QueueJobRepositoryExt as _},
+    user::{ImportedPassword, UserEmailFilter, UserImportRecord},
+};
+use rand::RngCore;
+use thiserror::Error;
+use ulid::Ulid;
+
+/// An error which can happen when importing a user
+#[derive(Debug, Error)]
+pub enum UserImportError<E> {
+    /// The username is not a valid localpart
+    #[error("Username {0:?} is not valid")]
+    InvalidUsername(String),
+
+    /// A user with the same username already exists
+    #[error("User {0:?} already exists")]
+    UserAlreadyExists(String),
+
+    /// The homeserver does not allow this localpart to be used
+    #[error("Username {0:?} is not available on the homeserver")]
+    LocalpartNotAvailable(String),
+
+    /// The email address is already used by another user
+    #[error("Email {0:?} is already in use")]
+    EmailAlreadyInUse(String),
+
+    /// The upstream provider referenced by a link does not exist
+    #[error("Upstream provider {0} not found")]
+    UnknownProvider(Ulid),
+
+    /// The upstream account is already linked to another user
+    #[error("Upstream account {subject:?} on provider {provider_id} is already linked to a user")]
+    LinkAlreadyExists {
+        /// The ID of the upstream provider
+        provider_id: Ulid,
+        /// The subject of the upstream account
+        subject: String,
+    },
+
+    /// The homeserver could not be reached
+    #[error("Failed to check the username with the homeserver")]
+    Homeserver(#[source] anyhow::Error),
+
+    /// The underlying repository failed
+    #[error(transparent)]
+    Repository(E),
+}
+
+impl<E> UserImportError<E> {
+    /// Returns `true` if the error was caused by the record itself, and not by
+    /// the homeserver or the underlying repository
+    pub fn is_invalid_record(&self) -> bool {
+        !matches!(self, Self::Repository(_) | Self::Homeserver(_))
+    }
+}
+
+fn valid_username_character(c: char) -> bool {
+    c.is_ascii_lowercase()
+        || c.is_ascii_digit()
+        || c == '='
+        || c == '_'
+        || c == '-'
+        || c == '.'
+        || c == '/'
+        || c == '+'
+}
+
+// XXX: this is the same check as in the admin API and GraphQL handlers
+fn username_valid(username: &str) -> bool {
+    if username.is_empty() || username.len() > 255 {
+        return false;
+    }
+
+    // Should not start with an underscore
+    if username.starts_with('_') {
+        return false;
+    }
+
+    // Should only contain valid characters
+    if !username.chars().all(valid_username_character) {
+        return false;
+    }
+
+    true
+}
+
+/// Import a single user, with its password, email addresses and upstream
+/// links, and schedule a job to provision it on the homeserver.
+///
+/// The username goes through the same checks as when registering a user
+/// through the admin API. Nothing is written if the record is invalid or
+/// conflicts with existing data, but the caller is responsible for checking
+/// that the password hash version is known to the password manager.
+///
+/// # Errors
+///
+/// Returns an error if the record is invalid or conflicts with existing data,
+/// or if the homeserver or the underlying repository fails
+pub async fn import_user<E: std::error::Error + Send + Sync + 'static>(
+    repo: &mut dyn RepositoryAccess<Error = E>,
+    rng: &mut (dyn RngCore + Send),
+    clock: &dyn Clock,
+    homeserver: &dyn HomeserverConnection,
+    record: UserImportRecord,
+) -> Result<User, UserImportError<E>> {
+    let UserImportRecord {
+        username,
+        emails,
+        password,
+        admin,
+        locked,
+        upstream_links,
+        display_name,
+    } = record;
+
+    if !username_valid(&username) {
+        return Err(UserImportError::InvalidUsername(username));
+    }
+
+    if repo
+        .user()
+        .exists(&username)
+        .await
+        .map_err(UserImportError::Repository)?
+    {
+        return Err(UserImportError::UserAlreadyExists(username));
+    }
+
+    if !homeserver
+        .is_localpart_available(&username)
+        .await
+        .map_err(UserImportError::Homeserver)?
+    {
+        return Err(UserImportError::LocalpartNotAvailable(username));
+    }
+
+    for email in &emails {
+        let count = repo
+            .user_email()
+            .count(UserEmailFilter::new().for_email(email))
+            .await
+            .map_err(UserImportError::Repository)?;
+
+        if count > 0 {
+            return Err(UserImportError::EmailAlreadyInUse(email.clone()));
+        }
+    }
+
+    // Resolve the upstream links before creating anything
+    let mut links = Vec::with_capacity(upstream_links.len());
+    for link in upstream_links {
+        let provider = repo
+            .upstream_oauth_provider()
+            .lookup(link.provider_id)
+            .await
+            .map_err(UserImportError::Repository)?
+            .ok_or(UserImportError::UnknownProvider(link.provider_id))?;
+
+        let existing = repo
+            .upstream_oauth_link()
+            .find_by_subject(&provider, &link.subject)
+            .await
+            .map_err(UserImportError::Repository)?;
+
+        // A link which exists but was never associated to a user can be reused
+        if existing.as_ref().is_some_and(|l| l.user_id.is_some()) {
+            return Err(UserImportError::LinkAlreadyExists {
+                provider_id: link.provider_id,
+                subject: link.subject,
+            });
+        }
+
+        links.push((provider, existing, link));
+    }
+
+    let mut user = repo
+        .user()
+        .add(rng, clock, username)
+        .await
+        .map_err(UserImportError::Repository)?;
+
+    if let Some(ImportedPassword { version, hash }) = password {
+        repo.user_password()
+            .add(rng, clock, &user, version, hash, None)
+            .await
+            .map_err(UserImportError::Repository)?;
+    }
+
+    for email in emails {
+        repo.user_email()
+            .add(rng, clock, &user, email)
+            .await
+            .map_err(UserImportError::Repository)?;
+    }
+
+    for (provider, existing, link) in links {
+        let link = if let Some(existing) = existing {
+            existing
+        } else {
+            repo.upstream_oauth_link()
+                .add(rng, clock, &provider, link.subject, link.human_account_name)
+                .await
+                .map_err(UserImportError::Repository)?
+        };
+
+        repo.upstream_oauth_link()
+            .associate_to_user(&link, &user)
+            .await
+            .map_err(UserImportError::Repository)?;
+    }
+
+    if admin {
+        user = repo
+            .user()
+            .set_can_request_admin(user, true)
+            .await
+            .map_err(UserImportError::Repository)?;
+    }
+
+    if locked {
+        user = repo
+            .user()
+            .lock(clock, user)
+            .await
+            .map_err(UserImportError::Repository)?;
+    }
+
+    let mut provision_job = ProvisionUserJob::new(&user);
+    if let Some(display_name) = display_name {
+        provision_job = provision_job.set_display_name(display_name);
+    }
+
+    repo.queue_job()
+        .schedule_job(rng, clock, provision_job)
+        .await
+        .map_err(UserImportError::Repository)?;
+
+    Ok(user)
+}
@@ -522,6 +522,12 @@ pub enum PostAuthContextInner {
 
     /// Go to the account management page
     ManageAccount,
+
+    /// Launch a client through its `initiate_login_uri`
+    LaunchClient {
+        /// The client to launch
+        client: Box<Client>,
+    },
 }
 
 /// Context used in login screen, for the post-auth action to do
@@ -541,6 +547,7 @@ pub struct LoginContext {
     form: FormState<LoginFormField>,
     next: Option<PostAuthContext>,
     providers: Vec<UpstreamOAuthProvider>,
+    home_realm_discovery: bool,
 }
 
 impl TemplateContext for LoginContext {
@@ -558,11 +565,13 @@ impl TemplateContext for LoginContext {
                 form: FormState::default(),
                 next: None,
                 providers: Vec::new(),
+                home_realm_discovery: false,
             },
             LoginContext {
                 form: FormState::default(),
                 next: None,
                 providers: Vec::new(),
+                home_realm_discovery: true,
             },
             LoginContext {
                 form: FormState::default()
@@ -576,12 +585,14 @@ impl TemplateContext for LoginContext {
                     ),
                 next: None,
                 providers: Vec::new(),
+                home_realm_discovery: false,
             },
             LoginContext {
                 form: FormState::default()
                     .with_error_on_field(LoginFormField::Username, FieldError::Exists),
                 next: None,
                 providers: Vec::new(),
+                home_realm_discovery: false,
             },
         ])
     }
@@ -605,6 +616,16 @@ impl LoginContext {
         Self { providers, ..self }
     }
 
+    /// Set whether some upstream providers are reached by entering an email
+    /// address in one of their domains
+    #[must_use]
+    pub fn with_home_realm_discovery(self, home_realm_discovery: bool) -> Self {
+        Self {
+            home_realm_discovery,
+            ..self
+        }
+    }
+
     /// Add a post authentication action to the context
     #[must_use]
     pub fn with_post_action(self, context: PostAuthContext) -> Self {
@@ -1739,6 +1760,11 @@ impl TemplateContext for UpstreamRegister {
                 created_at: now,
                 disabled_at: None,
                 on_backchannel_logout: UpstreamOAuthProviderOnBackchannelLogout::DoNothing,
+                saml: None,
+                email_domains: Vec::new(),
+                store_tokens: false,
+                allow_initiated_login: false,
+                encrypted_client_keys: None,
             },
         )])
     }
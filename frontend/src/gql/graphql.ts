@@ -567,6 +567,12 @@ export type Mutation = {
   lockUser: LockUserPayload;
   /** Remove an email address */
   removeEmail: RemoveEmailPayload;
+  /**
+   * Remove a link to an upstream provider.
+   *
+   * This refuses to remove the last way the user has to log in.
+   */
+  removeUpstreamOauth2Link: RemoveUpstreamOAuth2LinkPayload;
   /** Resend the email authentication code */
   resendEmailAuthenticationCode: ResendEmailAuthenticationCodePayload;
   /**
@@ -604,6 +610,13 @@ export type Mutation = {
   setPrimaryEmail: SetPrimaryEmailPayload;
   /** Start a new email authentication flow */
   startEmailAuthentication: StartEmailAuthenticationPayload;
+  /**
+   * Start linking an upstream provider to the current user.
+   *
+   * This returns a URL to send the user to, which goes through the
+   * provider and then asks the user to confirm the link.
+   */
+  startUpstreamOauth2Link: StartUpstreamOAuth2LinkPayload;
   /** Unlock and reactivate a user. This is only available to administrators. */
   unlockUser: UnlockUserPayload;
 };
@@ -675,6 +688,12 @@ export type MutationRemoveEmailArgs = {
 };
 
 
+/** The mutations root of the GraphQL interface. */
+export type MutationRemoveUpstreamOauth2LinkArgs = {
+  input: RemoveUpstreamOAuth2LinkInput;
+};
+
+
 /** The mutations root of the GraphQL interface. */
 export type MutationResendEmailAuthenticationCodeArgs = {
   input: ResendEmailAuthenticationCodeInput;
@@ -735,6 +754,12 @@ export type MutationStartEmailAuthenticationArgs = {
 };
 
 
+/** The mutations root of the GraphQL interface. */
+export type MutationStartUpstreamOauth2LinkArgs = {
+  input: StartUpstreamOAuth2LinkInput;
+};
+
+
 /** The mutations root of the GraphQL interface. */
 export type MutationUnlockUserArgs = {
   input: UnlockUserInput;
@@ -1027,6 +1052,43 @@ export type RemoveEmailStatus =
   /** The email address was removed */
   | 'REMOVED';
 
+/** The input for the `removeUpstreamOAuth2Link` mutation */
+export type RemoveUpstreamOAuth2LinkInput = {
+  /**
+   * The user's current password. This is required if the user is not an
+   * admin and it has a password on its account.
+   */
+  password?: InputMaybe<Scalars['String']['input']>;
+  /** The ID of the upstream link to remove */
+  upstreamOauth2LinkId: Scalars['ID']['input'];
+};
+
+/** The payload of the `removeUpstreamOAuth2Link` mutation */
+export type RemoveUpstreamOAuth2LinkPayload = {
+  __typename?: 'RemoveUpstreamOAuth2LinkPayload';
+  /** The link that was removed */
+  link?: Maybe<UpstreamOAuth2Link>;
+  /** Status of the operation */
+  status: RemoveUpstreamOAuth2LinkStatus;
+  /** The user to whom the link belonged */
+  user?: Maybe<User>;
+  /** The list of policy violations if removing the link was denied */
+  violations?: Maybe<Array<Scalars['String']['output']>>;
+};
+
+/** The status of the `removeUpstreamOAuth2Link` mutation */
+export type RemoveUpstreamOAuth2LinkStatus =
+  /** Removing this link is not allowed by the policy */
+  | 'DENIED'
+  /** The password provided is incorrect */
+  | 'INCORRECT_PASSWORD'
+  /** The user would have no way left to log in without this link */
+  | 'LAST_AUTHENTICATION_METHOD'
+  /** The link was not found */
+  | 'NOT_FOUND'
+  /** The link was removed */
+  | 'REMOVED';
+
 /** The input for the `resendEmailAuthenticationCode` mutation */
 export type ResendEmailAuthenticationCodeInput = {
   /** The ID of the authentication session to resend the code for */
@@ -1345,6 +1407,38 @@ export type StartEmailAuthenticationStatus =
   /** The email address was started */
   | 'STARTED';
 
+/** The input for the `startUpstreamOAuth2Link` mutation */
+export type StartUpstreamOAuth2LinkInput = {
+  /** The ID of the upstream provider to link */
+  upstreamOauth2ProviderId: Scalars['ID']['input'];
+};
+
+/** The payload of the `startUpstreamOAuth2Link` mutation */
+export type StartUpstreamOAuth2LinkPayload = {
+  __typename?: 'StartUpstreamOAuth2LinkPayload';
+  /** The existing link to this provider */
+  link?: Maybe<UpstreamOAuth2Link>;
+  /** The provider being linked */
+  provider?: Maybe<UpstreamOAuth2Provider>;
+  /** Status of the operation */
+  status: StartUpstreamOAuth2LinkStatus;
+  /** The URL to send the user to, to link the provider */
+  url?: Maybe<Scalars['Url']['output']>;
+  /** The list of policy violations if linking was denied */
+  violations?: Maybe<Array<Scalars['String']['output']>>;
+};
+
+/** The status of the `startUpstreamOAuth2Link` mutation */
+export type StartUpstreamOAuth2LinkStatus =
+  /** The user already has a link to this provider */
+  | 'ALREADY_LINKED'
+  /** Linking this provider is not allowed by the policy */
+  | 'DENIED'
+  /** The provider was not found */
+  | 'NOT_FOUND'
+  /** The link can be started by sending the user to the returned URL */
+  | 'STARTED';
+
 /** The input for the `unlockUser` mutation. */
 export type UnlockUserInput = {
   /** The ID of the user to unlock */
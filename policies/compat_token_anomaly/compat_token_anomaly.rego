@@ -0,0 +1,29 @@
+# Copyright 2026 Element Creations Ltd.
+#
+# SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+# Please see LICENSE files in the repository root for full details.
+
+# METADATA
+# schemas:
+#   - input: schema["compat_token_anomaly_input"]
+package compat_token_anomaly
+
+import rego.v1
+
+default allow := false
+
+allow if {
+	count(violation) == 0
+}
+
+# Anomalies are only flagged by default. Listing a kind of anomaly in
+# data.compat_token_anomalies.reject makes the token be rejected when it happens
+# METADATA
+# entrypoint: true
+violation contains {
+	"code": "compat-token-anomaly",
+	"msg": sprintf("the token was used in an unusual way (%s)", [kind]),
+} if {
+	some kind in input.anomalies
+	kind in data.compat_token_anomalies.reject
+}
@@ -0,0 +1,48 @@
+# Copyright 2026 Element Creations Ltd.
+#
+# SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+# Please see LICENSE files in the repository root for full details.
+
+package compat_token_anomaly_test
+
+import data.compat_token_anomaly
+import rego.v1
+
+user := {"username": "john"}
+
+session := {
+	"id": "01H8PKNWKKRPCBW4YGH1RWV279",
+	"device_id": "ABCDEFGHIJ",
+	"last_active_ip": "192.0.2.1",
+	"user_agent": "Element/1.0",
+}
+
+requester := {"ip_address": "198.51.100.1", "user_agent": "Element/1.0"}
+
+test_flag_only_by_default if {
+	compat_token_anomaly.allow with input.user as user
+		with input.session as session
+		with input.anomalies as ["ip_range", "user_agent"]
+		with input.requester as requester
+}
+
+test_reject_listed_anomalies if {
+	not compat_token_anomaly.allow with input.user as user
+		with input.session as session
+		with input.anomalies as ["ip_range"]
+		with input.requester as requester
+		with data.compat_token_anomalies.reject as ["ip_range"]
+
+	not compat_token_anomaly.allow with input.user as user
+		with input.session as session
+		with input.anomalies as ["ip_range", "user_agent"]
+		with input.requester as requester
+		with data.compat_token_anomalies.reject as ["user_agent"]
+
+	# Anomalies which are not listed are only flagged
+	compat_token_anomaly.allow with input.user as user
+		with input.session as session
+		with input.anomalies as ["user_agent"]
+		with input.requester as requester
+		with data.compat_token_anomalies.reject as ["ip_range"]
+}
@@ -71,7 +71,7 @@ violation contains {"msg": "unspecified registration method"} if {
 }
 
 violation contains {"msg": "unknown registration method"} if {
-	not input.registration_method in ["password", "upstream-oauth2"]
+	not input.registration_method in ["password", "upstream-oauth2", "ldap"]
 }
 
 violation contains {"msg": sprintf(
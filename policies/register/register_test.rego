@@ -42,6 +42,7 @@ test_banned_subdomain if {
 test_no_email if {
 	register.allow with input as {"username": "hello", "registration_method": "password"}
 	register.allow with input as {"username": "hello", "registration_method": "upstream-oauth2"}
+	register.allow with input as {"username": "hello", "registration_method": "ldap"}
 }
 
 test_empty_username if {
@@ -0,0 +1,68 @@
+# Copyright 2026 Element Creations Ltd.
+#
+# SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+# Please see LICENSE files in the repository root for full details.
+
+# METADATA
+# schemas:
+#   - input: schema["upstream_oauth_link_input"]
+package upstream_oauth_link
+
+import rego.v1
+
+import data.common
+
+default allow := false
+
+allow if {
+	count(violation) == 0
+}
+
+# Allow linking any provider if the data.upstream_oauth_links.allowed_providers array is not set
+provider_allowed if {
+	not data.upstream_oauth_links.allowed_providers
+}
+
+# Allow linking a provider only if its ID is in the list of allowed providers
+provider_allowed if {
+	some provider_id in data.upstream_oauth_links.allowed_providers
+	input.provider.id == provider_id
+}
+
+# METADATA
+# entrypoint: true
+violation contains {"msg": sprintf(
+	"Requester [%s] isn't allowed to do this action",
+	[common.format_requester(input.requester)],
+)} if {
+	common.requester_banned(input.requester, data.requester)
+}
+
+violation contains {
+	"code": "upstream-provider-not-allowed",
+	"msg": "linking this provider is not allowed",
+} if {
+	input.action == "link"
+	not provider_allowed
+}
+
+# Deny linking providers in the banlist
+violation contains {
+	"code": "upstream-provider-not-allowed",
+	"msg": "linking this provider is not allowed",
+} if {
+	input.action == "link"
+	some provider_id in data.upstream_oauth_links.banned_providers
+	input.provider.id == provider_id
+}
+
+# Deny removing links to providers which are locked, for example because the
+# account is managed by that provider
+violation contains {
+	"code": "upstream-link-locked",
+	"msg": "removing the link to this provider is not allowed",
+} if {
+	input.action == "unlink"
+	some provider_id in data.upstream_oauth_links.locked_providers
+	input.provider.id == provider_id
+}
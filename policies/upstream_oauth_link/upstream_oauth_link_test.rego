@@ -0,0 +1,73 @@
+# Copyright 2026 Element Creations Ltd.
+#
+# SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-Element-Commercial
+# Please see LICENSE files in the repository root for full details.
+
+package upstream_oauth_link_test
+
+import data.upstream_oauth_link
+import rego.v1
+
+user := {"username": "john"}
+
+provider := {"id": "01H8PKNWKKRPCBW4YGH1RWV279", "issuer": "https://example.com/"}
+
+test_allow_by_default if {
+	upstream_oauth_link.allow with input.user as user
+		with input.provider as provider
+		with input.action as "link"
+
+	upstream_oauth_link.allow with input.user as user
+		with input.provider as provider
+		with input.action as "unlink"
+}
+
+test_allowed_providers if {
+	upstream_oauth_link.allow with input.user as user
+		with input.provider as provider
+		with input.action as "link"
+		with data.upstream_oauth_links.allowed_providers as ["01H8PKNWKKRPCBW4YGH1RWV279"]
+
+	not upstream_oauth_link.allow with input.user as user
+		with input.provider as provider
+		with input.action as "link"
+		with data.upstream_oauth_links.allowed_providers as ["01JSHPZHAXC50QBKH67MH33TNF"]
+
+	# Unlinking isn't restricted by the allow list
+	upstream_oauth_link.allow with input.user as user
+		with input.provider as provider
+		with input.action as "unlink"
+		with data.upstream_oauth_links.allowed_providers as ["01JSHPZHAXC50QBKH67MH33TNF"]
+}
+
+test_banned_providers if {
+	not upstream_oauth_link.allow with input.user as user
+		with input.provider as provider
+		with input.action as "link"
+		with data.upstream_oauth_links.banned_providers as ["01H8PKNWKKRPCBW4YGH1RWV279"]
+
+	upstream_oauth_link.allow with input.user as user
+		with input.provider as provider
+		with input.action as "unlink"
+		with data.upstream_oauth_links.banned_providers as ["01H8PKNWKKRPCBW4YGH1RWV279"]
+}
+
+test_locked_providers if {
+	not upstream_oauth_link.allow with input.user as user
+		with input.provider as provider
+		with input.action as "unlink"
+		with data.upstream_oauth_links.locked_providers as ["01H8PKNWKKRPCBW4YGH1RWV279"]
+
+	upstream_oauth_link.allow with input.user as user
+		with input.provider as provider
+		with input.action as "link"
+		with data.upstream_oauth_links.locked_providers as ["01H8PKNWKKRPCBW4YGH1RWV279"]
+}
+
+test_banned_requester if {
+	not upstream_oauth_link.allow with input.user as user
+		with input.provider as provider
+		with input.action as "link"
+		with input.requester as {"ip_address": "1.2.3.4"}
+		with data.requester.banned_ips as ["1.2.3.4"]
+}